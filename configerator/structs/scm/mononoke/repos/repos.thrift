@@ -441,6 +441,9 @@ struct RawRemoteMetadataConfig {
   4: RawDbRemote sparse_profiles;
   5: optional RawDbShardableRemote bonsai_blob_mapping;
   6: optional RawDbRemote deletion_log;
+  // Shards for the segmented changelog idmap, used by the ranges set in
+  // the repo's segmented changelog config.
+  7: optional RawDbShardableRemote segmented_changelog_idmap;
 } (rust.exhaustive)
 
 union RawMetadataConfig {
@@ -579,6 +582,12 @@ struct RawBookmarkConfig {
   // because commit is already public, meaning that hooks already
   // should have been run when the commit was first made public.
   11: optional bool allow_move_to_public_commits_without_hooks;
+
+  // Also check the protections registered at runtime for bookmarks
+  // matching this, e.g. the allowed pushers of release branches created by
+  // repo_create_branch_from_commit.  Only bookmarks with this set can have
+  // protections registered.
+  12: optional bool runtime_protections;
 } (rust.exhaustive)
 
 struct RawAllowlistIdentity {
@@ -743,6 +752,16 @@ struct RawSourceControlServiceParams {
 
   // Whether users can create commits without parents.
   5: bool permit_commits_without_parents;
+
+  // Methods that must be enabled per repo which are enabled for this repo.
+  6: optional set<string> enabled_gated_methods;
+
+  // Scuba dataset to log this repo's requests to, instead of the server's
+  // dataset.
+  7: optional string scuba_table;
+
+  // Value of the repo_category column in this repo's request samples.
+  8: optional string scuba_category;
 } (rust.exhaustive)
 
 struct RawServiceWriteRestrictions {
@@ -772,6 +791,9 @@ union RawSegmentedChangelogHeadConfig {
   1: list<string> all_public_bookmarks_except;
   2: string bookmark;
   3: string bonsai_changeset;
+  // All public bookmarks whose names start with the prefix, e.g. release
+  // branches.
+  4: string public_bookmarks_with_prefix;
 }
 
 struct RawSegmentedChangelogConfig {
@@ -844,6 +866,21 @@ struct RawSegmentedChangelogConfig {
   10: list<
     RawSegmentedChangelogHeadConfig
   > extra_heads_to_include_in_background_jobs;
+  // Whether location-to-hash lookups that the dag can't answer, e.g. because
+  // the repo isn't seeded yet or the location is past the loaded dag, should
+  // be answered by walking the changesets table instead of failing.
+  // (default: false)
+  11: optional bool read_fallback_to_changesets;
+  // The first DagId of each idmap range shard, in increasing order.  Ids
+  // below the first start stay in the unsharded idmap table, and each
+  // shard uses the corresponding shard of the `segmented_changelog_idmap`
+  // database.  (default: [], i.e. unsharded)
+  12: optional list<i64> idmap_range_shard_starts;
+  // Whether a sample of the ancestry queries served by the dag should also
+  // be answered by the commit graph, logging any mismatches.  The sampling
+  // rate is the `segmented_changelog_shadow_read_sampling_rate` tunable.
+  // (default: false)
+  13: optional bool shadow_reads;
 } (rust.exhaustive)
 
 // Describe ACL Regions for a repository.
@@ -6,7 +6,10 @@
  */
 
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
+use anyhow::Error;
 use anyhow::Result;
 use async_trait::async_trait;
 use blobstore::Blobstore;
@@ -138,6 +141,18 @@ pub trait ComponentSamplingHandler: std::fmt::Debug + Send + Sync {
     ) -> Result<()> {
         Ok(())
     }
+
+    /// Called after every get, including failed ones, with its result and
+    /// how long it took.
+    fn record_get(
+        &self,
+        _ctx: &CoreContext,
+        _key: &str,
+        _result: Result<Option<&BlobstoreGetData>, &Error>,
+        _elapsed: Duration,
+        _inner_id: Option<BlobstoreId>,
+    ) {
+    }
 }
 
 /// A lower level sampler that can provide BlobstoreId
@@ -180,7 +195,16 @@ impl<T: Blobstore + BlobstorePutOps> Blobstore for SamplingBlobstoreUnlinkOps<T>
         ctx: &'a CoreContext,
         key: &'a str,
     ) -> Result<Option<BlobstoreGetData>> {
-        let opt_blob = self.inner.get(ctx, key).await?;
+        let start = Instant::now();
+        let result = self.inner.get(ctx, key).await;
+        self.handler.record_get(
+            ctx,
+            key,
+            result.as_ref().map(Option::as_ref),
+            start.elapsed(),
+            self.inner_id,
+        );
+        let opt_blob = result?;
         self.handler
             .sample_get(ctx, key, opt_blob.as_ref(), self.inner_id)?;
         Ok(opt_blob)
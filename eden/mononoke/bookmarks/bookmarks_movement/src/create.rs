@@ -6,18 +6,24 @@
  */
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use anyhow::anyhow;
+use bookmarks::BookmarkTransactionHook;
 use bookmarks::BookmarkUpdateReason;
 use bookmarks_types::BookmarkKey;
 use bookmarks_types::BookmarkKind;
 use bytes::Bytes;
 use context::CoreContext;
+use futures::future::FutureExt;
 use hooks::CrossRepoPushSource;
 use hooks::HookManager;
 use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
 use repo_authorization::AuthorizationContext;
 use repo_authorization::RepoWriteOperation;
+use repo_bookmark_attrs::BookmarkProtections;
+use repo_bookmark_attrs::RepoBookmarkAttrsRef;
 use repo_update_logger::find_draft_ancestors;
 use repo_update_logger::log_bookmark_operation;
 use repo_update_logger::log_new_bonsai_changesets;
@@ -43,6 +49,35 @@ pub struct CreateBookmarkOp<'op> {
     pushvars: Option<&'op HashMap<String, Bytes>>,
     log_new_public_commits_to_scribe: bool,
     only_log_acl_checks: bool,
+    allowed_pushers: Option<&'op [String]>,
+}
+
+/// Wrap a transaction hook so that it also registers the users allowed to
+/// move the bookmark, in the same transaction as the bookmark is created.
+fn register_protections_txn_hook(
+    protections: BookmarkProtections,
+    bookmark: BookmarkKey,
+    allowed_pushers: Vec<String>,
+    txn_hook: Option<BookmarkTransactionHook>,
+) -> BookmarkTransactionHook {
+    let allowed_pushers = Arc::new(allowed_pushers);
+    Arc::new(move |ctx, sql_txn| {
+        let protections = protections.clone();
+        let bookmark = bookmark.clone();
+        let allowed_pushers = allowed_pushers.clone();
+        let txn_hook = txn_hook.clone();
+        async move {
+            let sql_txn = match txn_hook {
+                Some(txn_hook) => txn_hook(ctx, sql_txn).await?,
+                None => sql_txn,
+            };
+            let sql_txn = protections
+                .add_allowed_pushers_in_transaction(sql_txn, &bookmark, &allowed_pushers)
+                .await?;
+            Ok(sql_txn)
+        }
+        .boxed()
+    })
 }
 
 impl<'op> CreateBookmarkOp<'op> {
@@ -61,6 +96,7 @@ impl<'op> CreateBookmarkOp<'op> {
             pushvars: None,
             log_new_public_commits_to_scribe: false,
             only_log_acl_checks: false,
+            allowed_pushers: None,
         }
     }
 
@@ -89,6 +125,14 @@ impl<'op> CreateBookmarkOp<'op> {
         self
     }
 
+    /// Register protections for the new bookmark so that only these users
+    /// may move it.  Only publishing bookmarks configured with
+    /// `runtime_protections` can be protected.
+    pub fn with_allowed_pushers(mut self, allowed_pushers: &'op [String]) -> Self {
+        self.allowed_pushers = Some(allowed_pushers);
+        self
+    }
+
     /// Include bonsai changesets for changesets that have just been added to
     /// the repository.
     pub fn with_new_changesets(
@@ -156,6 +200,11 @@ impl<'op> CreateBookmarkOp<'op> {
 
         let commits_to_log = match kind {
             BookmarkKind::Scratch => {
+                if self.allowed_pushers.is_some() {
+                    return Err(BookmarkMovementError::ProtectionsForScratchBookmark {
+                        bookmark: self.bookmark.clone(),
+                    });
+                }
                 txn_hook = None;
 
                 ctx.scuba()
@@ -201,7 +250,26 @@ impl<'op> CreateBookmarkOp<'op> {
                 };
 
                 let (txn_hook_res, to_log) = futures::join!(txn_hook_fut, to_log);
-                txn_hook = txn_hook_res?;
+                txn_hook = match self.allowed_pushers {
+                    Some(allowed_pushers) => {
+                        let bookmark_attrs = repo.repo_bookmark_attrs();
+                        if !bookmark_attrs.has_runtime_protections(self.bookmark) {
+                            return Err(BookmarkMovementError::RuntimeProtectionsNotConfigured {
+                                bookmark: self.bookmark.clone(),
+                            });
+                        }
+                        let protections = bookmark_attrs.protections().ok_or_else(|| {
+                            anyhow!("Bookmark protections are not available for this repo")
+                        })?;
+                        Some(register_protections_txn_hook(
+                            protections.clone(),
+                            self.bookmark.clone(),
+                            allowed_pushers.to_vec(),
+                            txn_hook_res?,
+                        ))
+                    }
+                    None => txn_hook_res?,
+                };
 
                 ctx.scuba()
                     .clone()
@@ -96,6 +96,15 @@ pub enum BookmarkMovementError {
         to: ChangesetId,
     },
 
+    #[error(
+        "Bookmark '{bookmark}' cannot be moved to {target} because it does not include {ancestor}"
+    )]
+    MissingRequiredAncestor {
+        bookmark: BookmarkKey,
+        target: ChangesetId,
+        ancestor: ChangesetId,
+    },
+
     #[error("Deletion of '{bookmark}' is prohibited")]
     DeletionProhibited { bookmark: BookmarkKey },
 
@@ -178,6 +187,12 @@ pub enum BookmarkMovementError {
     )]
     PushRedirectorEnabledForScratch { bookmark: BookmarkKey },
 
+    #[error("Protections cannot be registered for scratch bookmark '{bookmark}'")]
+    ProtectionsForScratchBookmark { bookmark: BookmarkKey },
+
+    #[error("Bookmark '{bookmark}' is not configured for runtime protections")]
+    RuntimeProtectionsNotConfigured { bookmark: BookmarkKey },
+
     #[error(transparent)]
     Error(#[from] anyhow::Error),
 }
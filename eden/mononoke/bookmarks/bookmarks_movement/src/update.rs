@@ -92,6 +92,7 @@ pub struct UpdateBookmarkOp<'op> {
     cross_repo_push_source: CrossRepoPushSource,
     affected_changesets: AffectedChangesets,
     pushvars: Option<&'op HashMap<String, Bytes>>,
+    required_ancestors: Vec<ChangesetId>,
     log_new_public_commits_to_scribe: bool,
     only_log_acl_checks: bool,
 }
@@ -112,6 +113,7 @@ impl<'op> UpdateBookmarkOp<'op> {
             cross_repo_push_source: CrossRepoPushSource::NativeToThisRepo,
             affected_changesets: AffectedChangesets::new(),
             pushvars: None,
+            required_ancestors: Vec::new(),
             log_new_public_commits_to_scribe: false,
             only_log_acl_checks: false,
         }
@@ -142,6 +144,13 @@ impl<'op> UpdateBookmarkOp<'op> {
         self
     }
 
+    /// Require the new target to be a descendant of (or equal to) this
+    /// changeset.
+    pub fn with_required_ancestor(mut self, ancestor: ChangesetId) -> Self {
+        self.required_ancestors.push(ancestor);
+        self
+    }
+
     pub fn with_push_source(mut self, cross_repo_push_source: CrossRepoPushSource) -> Self {
         self.cross_repo_push_source = cross_repo_push_source;
         self
@@ -191,6 +200,20 @@ impl<'op> UpdateBookmarkOp<'op> {
             .check_update_permitted(ctx, repo, self.bookmark, &self.targets)
             .await?;
 
+        for ancestor in self.required_ancestors.iter() {
+            if !repo
+                .commit_graph()
+                .is_ancestor(ctx, *ancestor, self.targets.new)
+                .await?
+            {
+                return Err(BookmarkMovementError::MissingRequiredAncestor {
+                    bookmark: self.bookmark.clone(),
+                    target: self.targets.new,
+                    ancestor: *ancestor,
+                });
+            }
+        }
+
         self.affected_changesets
             .check_restrictions(
                 ctx,
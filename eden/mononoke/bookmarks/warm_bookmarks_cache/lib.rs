@@ -86,6 +86,8 @@ define_stats! {
 
 pub struct WarmBookmarksCache {
     bookmarks: Arc<RwLock<HashMap<BookmarkKey, (ChangesetId, BookmarkKind)>>>,
+    live_updaters: Arc<RwLock<HashMap<BookmarkKey, BookmarkUpdaterState>>>,
+    refreshed_at: Arc<RwLock<Timestamp>>,
     terminate: Option<oneshot::Sender<()>>,
     notify_sync_start: Arc<Notify>,
     notify_sync_complete: Arc<Notify>,
@@ -302,6 +304,33 @@ pub trait BookmarksCache: Send + Sync {
 
     /// Awaits the completion of any ongoing update.
     async fn sync(&self, ctx: &CoreContext);
+
+    /// How up to date the cached value of the bookmark is.
+    async fn staleness(
+        &self,
+        ctx: &CoreContext,
+        bookmark: &BookmarkKey,
+    ) -> Result<BookmarkStaleness, Error>;
+}
+
+/// How up to date the cached value of a bookmark is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookmarkStaleness {
+    /// When the cache last compared its values with the real bookmarks.
+    pub refreshed_at: Timestamp,
+    /// Whether the real bookmark has moved to a commit that isn't warm yet.
+    pub pending_move: bool,
+    /// When the real bookmark moved to the commit that isn't warm yet, if
+    /// known.
+    pub pending_move_at: Option<Timestamp>,
+}
+
+impl BookmarkStaleness {
+    /// The cached value has been known to match the real value until this
+    /// time.
+    pub fn stale_since(&self) -> Timestamp {
+        self.pending_move_at.unwrap_or(self.refreshed_at)
+    }
 }
 
 /// A drop-in replacement for warm bookmark cache that doesn't
@@ -352,6 +381,19 @@ impl BookmarksCache for NoopBookmarksCache {
     }
 
     async fn sync(&self, _ctx: &CoreContext) {}
+
+    async fn staleness(
+        &self,
+        _ctx: &CoreContext,
+        _bookmark: &BookmarkKey,
+    ) -> Result<BookmarkStaleness, Error> {
+        // Nothing is cached, so values are always fresh.
+        Ok(BookmarkStaleness {
+            refreshed_at: Timestamp::now(),
+            pending_move: false,
+            pending_move_at: None,
+        })
+    }
 }
 
 impl WarmBookmarksCache {
@@ -386,15 +428,17 @@ impl WarmBookmarksCache {
 
         let bookmarks_to_watch = Arc::new(RwLock::new(bookmarks_to_watch));
 
-        BookmarksCoordinator::new(
+        let coordinator = BookmarksCoordinator::new(
             bookmarks_to_watch.clone(),
             sub,
             bookmarks.clone(),
             bookmark_update_log.clone(),
             repo_identity.clone(),
             warmers.clone(),
-        )
-        .spawn(
+        );
+        let live_updaters = coordinator.live_updaters.clone();
+        let refreshed_at = coordinator.refreshed_at.clone();
+        coordinator.spawn(
             ctx.clone(),
             receiver,
             notify_sync_start.clone(),
@@ -403,6 +447,8 @@ impl WarmBookmarksCache {
 
         Ok(Self {
             bookmarks: bookmarks_to_watch,
+            live_updaters,
+            refreshed_at,
             terminate: Some(sender),
             notify_sync_start,
             notify_sync_complete,
@@ -468,6 +514,18 @@ impl BookmarksCache for WarmBookmarksCache {
         self.notify_sync_start.notify_one();
         notified.await;
     }
+
+    async fn staleness(
+        &self,
+        _ctx: &CoreContext,
+        bookmark: &BookmarkKey,
+    ) -> Result<BookmarkStaleness, Error> {
+        Ok(bookmark_staleness(
+            &self.live_updaters,
+            &self.refreshed_at,
+            bookmark,
+        ))
+    }
 }
 
 impl Drop for WarmBookmarksCache {
@@ -738,6 +796,7 @@ struct BookmarksCoordinator {
     repo: BookmarksCoordinatorRepo,
     warmers: Arc<Vec<Warmer>>,
     live_updaters: Arc<RwLock<HashMap<BookmarkKey, BookmarkUpdaterState>>>,
+    refreshed_at: Arc<RwLock<Timestamp>>,
     updaters_handles: HashMap<BookmarkKey, JoinHandle<()>>,
 }
 
@@ -762,11 +821,13 @@ impl BookmarksCoordinator {
             repo,
             warmers,
             live_updaters: Arc::new(RwLock::new(HashMap::new())),
+            refreshed_at: Arc::new(RwLock::new(Timestamp::now())),
             updaters_handles: Default::default(),
         }
     }
 
     async fn update(&mut self, ctx: &CoreContext) -> Result<(), Error> {
+        let started_at = Timestamp::now();
         report_delay_and_remove_finished_updaters(
             ctx,
             &self.live_updaters,
@@ -893,6 +954,11 @@ impl BookmarksCoordinator {
             }
         }
 
+        // Updaters for any bookmarks that moved are now live, so the cache
+        // knows about every move up to when the real values were fetched.
+        self.refreshed_at
+            .with_write(|refreshed_at| *refreshed_at = started_at);
+
         Ok(())
     }
 
@@ -1015,6 +1081,24 @@ fn report_delay_and_remove_finished_updaters(
     STATS::max_staleness_secs.set_value(ctx.fb, max_staleness, (reponame.to_owned(),));
 }
 
+fn bookmark_staleness(
+    live_updaters: &RwLock<HashMap<BookmarkKey, BookmarkUpdaterState>>,
+    refreshed_at: &RwLock<Timestamp>,
+    bookmark: &BookmarkKey,
+) -> BookmarkStaleness {
+    let refreshed_at = refreshed_at.with_read(|refreshed_at| *refreshed_at);
+    let pending_move = live_updaters.with_read(|live_updaters| {
+        live_updaters
+            .get(bookmark)
+            .and_then(|state| state.pending_move())
+    });
+    BookmarkStaleness {
+        refreshed_at,
+        pending_move: pending_move.is_some(),
+        pending_move_at: pending_move.flatten(),
+    }
+}
+
 #[derive(Clone)]
 enum BookmarkUpdaterState {
     // Updater has started but it hasn't yet fetched bookmark update log
@@ -1066,6 +1150,21 @@ impl BookmarkUpdaterState {
             Self::Finished { .. } => true,
         }
     }
+
+    /// If the bookmark has moved to a commit that isn't warm yet, when it
+    /// moved, if known.
+    fn pending_move(&self) -> Option<Option<Timestamp>> {
+        match self {
+            Self::Started => Some(None),
+            Self::InProgress {
+                oldest_underived_ts,
+            } => Some(Some(*oldest_underived_ts)),
+            // A failed updater leaves the bookmark behind.
+            Self::Finished {
+                oldest_underived_ts,
+            } => oldest_underived_ts.map(Some),
+        }
+    }
 }
 
 async fn single_bookmark_updater(
@@ -1684,6 +1783,21 @@ mod tests {
         let failing_book = BookmarkKey::new("failingbook")?;
         bookmarks.with_read(|bookmarks| assert_eq!(bookmarks.get(&failing_book), None));
 
+        // The failing bookmark is reported as behind its real value.
+        let staleness = bookmark_staleness(
+            &coordinator.live_updaters,
+            &coordinator.refreshed_at,
+            &failing_book,
+        );
+        assert!(staleness.pending_move);
+        assert!(staleness.pending_move_at.is_some());
+        let staleness = bookmark_staleness(
+            &coordinator.live_updaters,
+            &coordinator.refreshed_at,
+            &master_book,
+        );
+        assert!(!staleness.pending_move);
+
         // Now change the warmer and make sure it derives successfully
         let mut warmers: Vec<Warmer> = Vec::new();
         warmers.push(create_derived_data_warmer::<RootUnodeManifestId>(
@@ -70,7 +70,7 @@ pub async fn subcommand_truncate_segmented_changelog<'a>(
     let config_store = matches.config_store();
     let mysql_options = matches.mysql_options();
     let (_, config) = args::not_shardmanager_compatible::get_config(config_store, matches)?;
-    let storage_config = config.storage_config;
+    let storage_config = config.storage_config.clone();
     let readonly_storage = ReadOnlyStorage(false);
 
     let db_address = match &storage_config.metadata {
@@ -96,10 +96,10 @@ pub async fn subcommand_truncate_segmented_changelog<'a>(
     .await
     .context("failed to construct metadata sql factory")?;
 
-    let segmented_changelog_sql_connections = sql_factory
-        .open::<SegmentedChangelogSqlConnections>()
-        .await
-        .context("error opening segmented changelog sql connections")?;
+    let segmented_changelog_sql_connections =
+        SegmentedChangelogSqlConnections::open(&sql_factory, &config.segmented_changelog_config)
+            .await
+            .context("error opening segmented changelog sql connections")?;
 
     let heads = vec![helpers::csid_resolve(&ctx, &container, rev).await?];
     copy_segmented_changelog(
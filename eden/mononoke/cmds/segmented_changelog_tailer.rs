@@ -153,6 +153,7 @@ async fn async_main(app: MononokeApp) -> Result<(), Error> {
             &blobrepo,
             &config.storage_config.metadata,
             app.mysql_options(),
+            &config.segmented_changelog_config,
             seed_heads,
             prefetched_commits,
             None,
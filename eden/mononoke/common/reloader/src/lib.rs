@@ -66,6 +66,15 @@ pub trait Loader<R> {
     async fn needs_reload(&mut self) -> Result<bool> {
         Ok(true)
     }
+
+    // Check a reloaded R once it has replaced the previous one. If this
+    // fails, the previous one is restored.
+    async fn verify(&mut self, _loaded: &R) -> Result<()>
+    where
+        R: Sync,
+    {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -142,7 +151,16 @@ impl<R: 'static + Send + Sync> Reloader<R> {
                     };
                     if reload_now {
                         match loader.load().await {
-                            Ok(Some(new)) => obj.store(Arc::new(new)),
+                            Ok(Some(new)) => {
+                                let previous = obj.swap(Arc::new(new));
+                                if let Err(err) = loader.verify(&obj.load_full()).await {
+                                    warn!(
+                                        ctx.logger(),
+                                        "Failed to verify reload, restoring previous: {:?}", err
+                                    );
+                                    obj.store(previous);
+                                }
+                            }
                             // Fetch was successful, but there's nothing to reload
                             Ok(None) => {}
                             Err(err) => {
@@ -293,4 +311,44 @@ mod test {
         l.wait_for_update().await;
         assert!(**l.load() > 0);
     }
+
+    #[fbinit::test]
+    async fn test_reload_fail_verify(fb: FacebookInit) {
+        tokio::time::pause();
+        struct NumberLoader {
+            cur: u32,
+        }
+        #[async_trait]
+        impl Loader<u32> for NumberLoader {
+            async fn load(&mut self) -> Result<Option<u32>> {
+                let last = self.cur;
+                self.cur += 1;
+                Ok(Some(last))
+            }
+
+            async fn verify(&mut self, loaded: &u32) -> Result<()> {
+                if *loaded == 1 {
+                    Err(anyhow!("Error"))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+        let loader = NumberLoader { cur: 0 };
+        let l = Reloader::reload_periodically(
+            CoreContext::test_mock(fb),
+            || Duration::from_millis(20),
+            loader,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(**l.load(), 0);
+        tokio::time::advance(Duration::from_millis(21)).await;
+        l.wait_for_update().await;
+        assert_eq!(**l.load(), 0);
+        tokio::time::advance(Duration::from_millis(21)).await;
+        l.wait_for_update().await;
+        assert_eq!(**l.load(), 2);
+    }
 }
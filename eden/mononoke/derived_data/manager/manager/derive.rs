@@ -145,6 +145,14 @@ impl DerivedDataManager {
         Ok(())
     }
 
+    /// The key of the lease taken while deriving `Derivable` for `csid`.
+    pub fn lease_key<Derivable>(&self, csid: ChangesetId) -> String
+    where
+        Derivable: BonsaiDerivable,
+    {
+        format!("repo{}.{}.{}", self.repo_id(), Derivable::NAME, csid)
+    }
+
     /// Perform derivation for a single changeset.
     /// Will fail in case data for parents changeset wasn't derived
     async fn perform_single_derivation<Derivable>(
@@ -166,7 +174,7 @@ impl DerivedDataManager {
             .log_with_msg("Waiting for derived data to be generated", None);
 
         debug!(ctx.logger(), "derive {} for {}", Derivable::NAME, csid);
-        let lease_key = format!("repo{}.{}.{}", self.repo_id(), Derivable::NAME, csid);
+        let lease_key = self.lease_key::<Derivable>(csid);
 
         let ctx = ctx.clone_and_reset();
 
@@ -578,6 +578,36 @@ mod test {
                 NonRootMPath::new(b"dir1/subdir1/subsubdir2")?
             ))
         );
+        assert_eq!(
+            c_skeleton
+                .case_conflicts(&ctx, repo.repo_blobstore(), 10)
+                .await?,
+            vec![
+                vec![
+                    NonRootMPath::new(b"dir1/subdir1/SUBSUBDIR2")?,
+                    NonRootMPath::new(b"dir1/subdir1/subsubdir2")?,
+                ],
+                vec![
+                    NonRootMPath::new(b"dir1/subdir1/SUBSUBDIR3")?,
+                    NonRootMPath::new(b"dir1/subdir1/subsubdir3")?,
+                ],
+                vec![
+                    NonRootMPath::new(b"dir1/subdir1/subsubdir1/FILE1")?,
+                    NonRootMPath::new(b"dir1/subdir1/subsubdir1/file1")?,
+                ],
+                vec![
+                    NonRootMPath::new(b"dir1/subdir2/SUBSUBDIR1")?,
+                    NonRootMPath::new(b"dir1/subdir2/subsubdir1")?,
+                ],
+            ]
+        );
+        assert_eq!(
+            c_skeleton
+                .case_conflicts(&ctx, repo.repo_blobstore(), 1)
+                .await?
+                .len(),
+            1
+        );
         assert_eq!(
             c_skeleton
                 .clone()
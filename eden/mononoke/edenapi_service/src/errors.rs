@@ -98,6 +98,7 @@ impl MononokeErrorExt for MononokeError {
             HookFailure(_) => HttpError::e400,
             PushrebaseConflicts(_) => HttpError::e400,
             AuthorizationError(_) => HttpError::e403,
+            BookmarkPolicyDenied { .. } => HttpError::e403,
             InternalError(_) => HttpError::e500,
             MergeConflicts { .. } => HttpError::e400,
         })(Error::from(self).context(context))
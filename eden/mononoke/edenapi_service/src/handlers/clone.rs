@@ -5,34 +5,149 @@
  * GNU General Public License version 2.
  */
 
+use std::iter;
+
+use anyhow::Context;
+use anyhow::Error;
 use bytes::Bytes;
 use edenapi_types::wire::ToWire;
 use edenapi_types::wire::WireCloneData;
+use edenapi_types::wire::WireCloneDataChunk;
+use edenapi_types::wire::WireFlatSegment;
 use edenapi_types::wire::WireIdMapEntry;
+use futures::future;
+use futures::stream;
+use futures::Stream;
+use futures::StreamExt;
 use gotham::state::FromState;
 use gotham::state::State;
 use gotham_derive::StateData;
 use gotham_derive::StaticResponseExtender;
 use gotham_ext::error::HttpError;
-use gotham_ext::response::BytesBody;
+use gotham_ext::response::ResponseStream;
+use gotham_ext::response::ResponseTryStreamExt;
+use gotham_ext::response::StreamBody;
+use gotham_ext::response::TryIntoResponse;
 use serde::Deserialize;
 use types::HgId;
 
 use crate::context::ServerContext;
+use crate::errors::ErrorKind;
 use crate::errors::MononokeErrorExt;
 use crate::handlers::EdenApiMethod;
+use crate::handlers::Freshness;
 use crate::handlers::HandlerInfo;
 use crate::middleware::RequestContext;
 use crate::utils::cbor;
 use crate::utils::get_repo;
 
+/// Idmap entries per chunk for clients that ask for compression without
+/// giving a chunk size.
+const DEFAULT_CHUNK_SIZE: usize = 100_000;
+
+/// Bounds on the chunk size a client may ask for.  Tiny chunks compress
+/// poorly, and huge ones would be buffered whole before being sent.
+const MIN_CHUNK_SIZE: usize = 1_000;
+const MAX_CHUNK_SIZE: usize = 1_000_000;
+
+const ZSTD_LEVEL: i32 = 3;
+
 #[derive(Debug, Deserialize, StateData, StaticResponseExtender)]
 pub struct CloneParams {
     repo: String,
 }
 
-pub async fn clone_data(state: &mut State) -> Result<BytesBody<Bytes>, HttpError> {
+/// Optional controls for how clone data is sent.  If neither is given the
+/// clone data is sent as a single `WireCloneData`, otherwise as a stream of
+/// `WireCloneDataChunk`.
+#[derive(Debug, Deserialize, StateData, StaticResponseExtender)]
+pub struct CloneQueryString {
+    /// Number of idmap entries per chunk.
+    chunk_size: Option<usize>,
+    idmap_compression: Option<IdMapCompression>,
+    #[serde(default)]
+    freshness: Freshness,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdMapCompression {
+    None,
+    Zstd,
+}
+
+impl CloneQueryString {
+    /// The chunk size and compression to use, or `None` if the client didn't
+    /// ask for a chunked response.
+    fn chunking(&self) -> Option<(usize, IdMapCompression)> {
+        if self.chunk_size.is_none() && self.idmap_compression.is_none() {
+            return None;
+        }
+        let chunk_size = self
+            .chunk_size
+            .unwrap_or(DEFAULT_CHUNK_SIZE)
+            .clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE);
+        let compression = self.idmap_compression.unwrap_or(IdMapCompression::None);
+        Some((chunk_size, compression))
+    }
+}
+
+fn encode_chunk(
+    flat_segments: Vec<WireFlatSegment>,
+    idmap: Vec<WireIdMapEntry>,
+    compression: IdMapCompression,
+) -> Result<Bytes, Error> {
+    let chunk = match compression {
+        IdMapCompression::None => WireCloneDataChunk {
+            flat_segments,
+            idmap,
+            idmap_zstd: None,
+        },
+        IdMapCompression::Zstd => {
+            let idmap = cbor::to_cbor_bytes(&idmap)?;
+            let compressed =
+                zstd::bulk::compress(&idmap, ZSTD_LEVEL).context(ErrorKind::SerializationFailed)?;
+            WireCloneDataChunk {
+                flat_segments,
+                idmap: Vec::new(),
+                idmap_zstd: Some(Bytes::from(compressed)),
+            }
+        }
+    };
+    cbor::to_cbor_bytes(&chunk)
+}
+
+/// Stream the clone data as chunks of at most `chunk_size` idmap entries.
+/// The flat segments go in the first chunk, which is sent even if the idmap
+/// is empty.  Entries are only taken from the idmap as each chunk is
+/// encoded, and encoding runs on the blocking pool as compressing a chunk
+/// can take a while.
+fn chunk_stream(
+    flat_segments: Vec<WireFlatSegment>,
+    idmap: impl Iterator<Item = WireIdMapEntry> + Send + 'static,
+    chunk_size: usize,
+    compression: IdMapCompression,
+) -> impl Stream<Item = Result<Bytes, Error>> + Send + 'static {
+    let mut flat_segments = Some(flat_segments);
+    let mut idmap = idmap.peekable();
+    let chunks = iter::from_fn(move || {
+        if flat_segments.is_none() && idmap.peek().is_none() {
+            return None;
+        }
+        let idmap = idmap.by_ref().take(chunk_size).collect::<Vec<_>>();
+        Some((flat_segments.take().unwrap_or_default(), idmap))
+    });
+    stream::iter(chunks)
+        .map(move |(flat_segments, idmap)| async move {
+            tokio::task::spawn_blocking(move || encode_chunk(flat_segments, idmap, compression))
+                .await?
+        })
+        .buffered(2)
+}
+
+pub async fn clone_data(state: &mut State) -> Result<impl TryIntoResponse, HttpError> {
     let params = CloneParams::take_from(state);
+    let query_string = CloneQueryString::take_from(state);
 
     state.put(HandlerInfo::new(&params.repo, EdenApiMethod::Clone));
 
@@ -42,29 +157,136 @@ pub async fn clone_data(state: &mut State) -> Result<BytesBody<Bytes>, HttpError
     // Note that we have CloneData<HgChangesetId> which doesn't have a direct to wire conversion.
     // This means that we need to manually construct WireCloneData for all the WireHgId entries.
     let clone_data = hg_repo_ctx
-        .segmented_changelog_clone_data()
+        .segmented_changelog_clone_data(query_string.freshness.into())
         .await
         .map_err(|e| e.into_http_error("error getting segmented changelog data"))?;
-    let idmap = clone_data
-        .idmap
+    let idmap = clone_data.idmap.into_iter().map(|(k, v)| WireIdMapEntry {
+        dag_id: k.to_wire(),
+        hg_id: HgId::from(v.into_nodehash()).to_wire(),
+    });
+    let flat_segments = clone_data
+        .flat_segments
+        .segments
         .into_iter()
-        .map(|(k, v)| WireIdMapEntry {
-            dag_id: k.to_wire(),
-            hg_id: HgId::from(v.into_nodehash()).to_wire(),
-        })
-        .collect();
-    let wire_clone_data = WireCloneData {
-        flat_segments: clone_data
-            .flat_segments
-            .segments
-            .into_iter()
-            .collect::<Vec<_>>()
-            .to_wire(),
-        idmap,
+        .collect::<Vec<_>>()
+        .to_wire();
+
+    let stream = match query_string.chunking() {
+        None => {
+            let wire_clone_data = WireCloneData {
+                flat_segments,
+                idmap: idmap.collect(),
+            };
+            let bytes = cbor::to_cbor_bytes(&wire_clone_data).map_err(HttpError::e500)?;
+            let content_length = bytes.len() as u64;
+            ResponseStream::new(stream::once(future::ok::<_, Error>(bytes)).boxed())
+                .set_content_length(content_length)
+        }
+        Some((chunk_size, compression)) => {
+            ResponseStream::new(chunk_stream(flat_segments, idmap, chunk_size, compression).boxed())
+        }
     };
 
-    Ok(BytesBody::new(
-        cbor::to_cbor_bytes(&wire_clone_data).map_err(HttpError::e500)?,
+    Ok(StreamBody::new(
+        stream.capture_first_err(),
         cbor::cbor_mime(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use edenapi_types::wire::WireDagId;
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    fn dag_id(id: u64) -> WireDagId {
+        // Dag ids are plain integers on the wire.
+        serde_cbor::from_slice(&serde_cbor::to_vec(&id).unwrap()).unwrap()
+    }
+
+    fn entries(count: u64) -> Vec<WireIdMapEntry> {
+        (0..count)
+            .map(|id| WireIdMapEntry {
+                dag_id: dag_id(id),
+                hg_id: HgId::from_byte_array([id as u8; 20]).to_wire(),
+            })
+            .collect()
+    }
+
+    fn flat_segments() -> Vec<WireFlatSegment> {
+        vec![WireFlatSegment {
+            low: dag_id(0),
+            high: dag_id(2),
+            parents: Vec::new(),
+        }]
+    }
+
+    async fn decode(
+        idmap: Vec<WireIdMapEntry>,
+        chunk_size: usize,
+        compression: IdMapCompression,
+    ) -> Result<Vec<WireCloneDataChunk>, Error> {
+        chunk_stream(flat_segments(), idmap.into_iter(), chunk_size, compression)
+            .map_ok(|bytes| serde_cbor::from_slice(&bytes).unwrap())
+            .try_collect()
+            .await
+    }
+
+    #[test]
+    fn test_chunking() {
+        let query = |chunk_size, idmap_compression| CloneQueryString {
+            chunk_size,
+            idmap_compression,
+            freshness: Freshness::default(),
+        };
+        assert_eq!(query(None, None).chunking(), None);
+        assert_eq!(
+            query(None, Some(IdMapCompression::Zstd)).chunking(),
+            Some((DEFAULT_CHUNK_SIZE, IdMapCompression::Zstd))
+        );
+        assert_eq!(
+            query(Some(1), None).chunking(),
+            Some((MIN_CHUNK_SIZE, IdMapCompression::None))
+        );
+        assert_eq!(
+            query(Some(usize::MAX), None).chunking(),
+            Some((MAX_CHUNK_SIZE, IdMapCompression::None))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chunk_stream() -> Result<(), Error> {
+        let chunks = decode(entries(5), 2, IdMapCompression::None).await?;
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].flat_segments, flat_segments());
+        assert!(chunks[1..].iter().all(|c| c.flat_segments.is_empty()));
+        assert!(chunks.iter().all(|c| c.idmap_zstd.is_none()));
+        let idmap: Vec<_> = chunks.into_iter().flat_map(|c| c.idmap).collect();
+        assert_eq!(idmap, entries(5));
+
+        // The first chunk is sent even with an empty idmap.
+        let chunks = decode(Vec::new(), 2, IdMapCompression::None).await?;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].flat_segments, flat_segments());
+        assert!(chunks[0].idmap.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chunk_stream_zstd() -> Result<(), Error> {
+        let chunks = decode(entries(5), 3, IdMapCompression::Zstd).await?;
+        assert_eq!(chunks.len(), 2);
+        let mut idmap = Vec::new();
+        for chunk in chunks {
+            assert!(chunk.idmap.is_empty());
+            let compressed = chunk.idmap_zstd.expect("idmap is compressed");
+            let decompressed = zstd::stream::decode_all(&compressed[..])?;
+            idmap.extend(serde_cbor::from_slice::<Vec<WireIdMapEntry>>(
+                &decompressed,
+            )?);
+        }
+        assert_eq!(idmap, entries(5));
+        Ok(())
+    }
+}
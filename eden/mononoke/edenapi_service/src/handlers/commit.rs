@@ -64,6 +64,7 @@ use gotham_ext::response::TryIntoResponse;
 use mercurial_types::HgChangesetId;
 use mercurial_types::HgNodeHash;
 use mononoke_api::CreateInfo;
+use mononoke_api::DagFreshness;
 use mononoke_api::MononokeError;
 use mononoke_api_hg::HgRepoContext;
 use mononoke_types::hash::GitSha1;
@@ -79,10 +80,12 @@ use types::Parents;
 use super::handler::EdenApiContext;
 use super::EdenApiHandler;
 use super::EdenApiMethod;
+use super::Freshness;
 use super::HandlerInfo;
 use super::HandlerResult;
 use crate::context::ServerContext;
 use crate::errors::ErrorKind;
+use crate::errors::MononokeErrorExt;
 use crate::middleware::request_dumper::RequestDumper;
 use crate::middleware::RequestContext;
 use crate::utils::cbor_stream_filtered_errors;
@@ -106,6 +109,13 @@ pub struct HashToLocationParams {
     repo: String,
 }
 
+/// Optional controls for the segmented changelog queries.
+#[derive(Debug, Deserialize, StateData, StaticResponseExtender)]
+pub struct SegmentedChangelogQueryString {
+    #[serde(default)]
+    freshness: Freshness,
+}
+
 #[derive(Debug, Deserialize, StateData, StaticResponseExtender)]
 pub struct RevlogDataParams {
     repo: String,
@@ -123,8 +133,9 @@ async fn translate_location(
     request: CommitLocationToHashRequest,
 ) -> Result<CommitLocationToHashResponse, Error> {
     let location = request.location.map_descendant(|x| x.into());
+    // The dag is brought up to date once for the whole batch, if needed.
     let ancestors: Vec<HgChangesetId> = hg_repo_ctx
-        .location_to_hg_changeset_id(location, request.count)
+        .location_to_hg_changeset_id(location, request.count, DagFreshness::MaybeStale)
         .await
         .context(ErrorKind::CommitLocationToHashRequestFailed)?;
     let hgids = ancestors.into_iter().map(|x| x.into()).collect();
@@ -138,6 +149,7 @@ async fn translate_location(
 
 #[async_trait]
 impl EdenApiHandler for LocationToHashHandler {
+    type QueryStringExtractor = SegmentedChangelogQueryString;
     type Request = CommitLocationToHashRequestBatch;
     type Response = CommitLocationToHashResponse;
 
@@ -154,6 +166,9 @@ impl EdenApiHandler for LocationToHashHandler {
         request: Self::Request,
     ) -> HandlerResult<'async_trait, Self::Response> {
         let repo = ectx.repo();
+        if ectx.query().freshness == Freshness::MustBeFresh {
+            repo.repo().segmented_changelog_ensure_fresh().await?;
+        }
         let hgid_list = request
             .requests
             .into_iter()
@@ -169,8 +184,13 @@ pub async fn hash_to_location(state: &mut State) -> Result<impl TryIntoResponse,
         master_heads: Vec<HgChangesetId>,
         hg_cs_ids: Vec<HgChangesetId>,
     ) -> impl Stream<Item = CommitHashToLocationResponse> {
+        // The dag is brought up to date once for the whole batch, if needed.
         let hgcsid_to_location = hg_repo_ctx
-            .many_changeset_ids_to_locations(master_heads, hg_cs_ids.clone())
+            .many_changeset_ids_to_locations(
+                master_heads,
+                hg_cs_ids.clone(),
+                DagFreshness::MaybeStale,
+            )
             .await;
         let responses = hg_cs_ids.into_iter().map(move |hgcsid| {
             let result = match hgcsid_to_location.as_ref() {
@@ -190,6 +210,7 @@ pub async fn hash_to_location(state: &mut State) -> Result<impl TryIntoResponse,
     }
 
     let params = HashToLocationParams::take_from(state);
+    let query_string = SegmentedChangelogQueryString::take_from(state);
 
     state.put(HandlerInfo::new(
         &params.repo,
@@ -202,6 +223,13 @@ pub async fn hash_to_location(state: &mut State) -> Result<impl TryIntoResponse,
     let rctx = RequestContext::borrow_from(state).clone();
 
     let hg_repo_ctx = get_repo(sctx, &rctx, &params.repo, None).await?;
+    if query_string.freshness == Freshness::MustBeFresh {
+        hg_repo_ctx
+            .repo()
+            .segmented_changelog_ensure_fresh()
+            .await
+            .map_err(|e| e.into_http_error("error updating segmented changelog"))?;
+    }
 
     let batch = parse_wire_request::<WireCommitHashToLocationRequestBatch>(state).await?;
 
@@ -46,6 +46,7 @@ use gotham_ext::state_ext::StateExt;
 use hyper::Body;
 use hyper::Response;
 use mime::Mime;
+use mononoke_api::DagFreshness;
 use serde::Deserialize;
 use serde::Serialize;
 use time_ext::DurationExt;
@@ -172,6 +173,25 @@ impl HandlerInfo {
     }
 }
 
+/// How recent the segmented changelog answering a request must be, as given
+/// by the `freshness` query string parameter.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    #[default]
+    MaybeStale,
+    MustBeFresh,
+}
+
+impl From<Freshness> for DagFreshness {
+    fn from(freshness: Freshness) -> Self {
+        match freshness {
+            Freshness::MaybeStale => DagFreshness::MaybeStale,
+            Freshness::MustBeFresh => DagFreshness::MustBeFresh,
+        }
+    }
+}
+
 /// JSON representation of an error to send to the client.
 #[derive(Clone, Serialize, Debug, Deserialize)]
 struct JsonError {
@@ -454,6 +474,7 @@ pub fn build_router(ctx: ServerContext) -> Router {
         route
             .post("/:repo/commit/hash_to_location")
             .with_path_extractor::<commit::HashToLocationParams>()
+            .with_query_string_extractor::<commit::SegmentedChangelogQueryString>()
             .to(commit_hash_to_location_handler);
         route
             .post("/:repo/commit/revlog_data")
@@ -462,6 +483,7 @@ pub fn build_router(ctx: ServerContext) -> Router {
         route
             .post("/:repo/clone")
             .with_path_extractor::<clone::CloneParams>()
+            .with_query_string_extractor::<clone::CloneQueryString>()
             .to(clone_handler);
         route
             .post("/:repo/pull_fast_forward_master")
@@ -57,6 +57,7 @@ pub use fetch_key::FetchKey;
 pub use incremental_hash::hash_bytes;
 pub use incremental_hash::Blake3IncrementalHasher;
 pub use incremental_hash::GitSha1IncrementalHasher;
+pub use incremental_hash::Hasher;
 pub use incremental_hash::Sha1IncrementalHasher;
 pub use incremental_hash::Sha256IncrementalHasher;
 pub use rechunk::force_rechunk;
@@ -9,6 +9,7 @@ use bookmarks::BookmarkKey;
 use context::CoreContext;
 use fbinit::FacebookInit;
 use fixtures::TestRepoFixture;
+use maplit::hashmap;
 use maplit::hashset;
 use metaconfig_types::BookmarkParams;
 use metaconfig_types::HookManagerParams;
@@ -21,7 +22,10 @@ use tests_utils::BasicTestRepo;
 
 use crate::errors::ErrorKind;
 use crate::hook_loader::load_hooks;
+use crate::HookConfig;
+use crate::HookExecution;
 use crate::HookManager;
+use crate::PushAuthoredBy;
 
 async fn hook_manager_repo(fb: FacebookInit, repo: &BasicTestRepo) -> HookManager {
     let ctx = CoreContext::test_mock(fb);
@@ -59,6 +63,7 @@ async fn test_load_hooks_bad_rust_hook(fb: FacebookInit) {
         hooks_skip_ancestors_of: vec![],
         ensure_ancestor_of: None,
         allow_move_to_public_commits_without_hooks: false,
+        runtime_protections: false,
     }];
 
     config.hooks = vec![HookParams {
@@ -124,6 +129,7 @@ async fn test_load_disabled_hooks_referenced_by_bookmark(fb: FacebookInit) {
         hooks_skip_ancestors_of: vec![],
         ensure_ancestor_of: None,
         allow_move_to_public_commits_without_hooks: false,
+        runtime_protections: false,
     }];
 
     config.hooks = vec![HookParams {
@@ -190,3 +196,82 @@ async fn test_load_hook_with_different_name_and_implementation(fb: FacebookInit)
     .await
     .expect("loading hooks should succeed");
 }
+
+#[fbinit::test]
+async fn test_run_message_hooks(fb: FacebookInit) {
+    let ctx = CoreContext::test_mock(fb);
+    let mut config = RepoConfig::default();
+    config.bookmarks = vec![BookmarkParams {
+        bookmark: BookmarkKey::new("bm1").unwrap().into(),
+        hooks: vec!["message_length".into(), "always_fail".into()],
+        only_fast_forward: false,
+        allowed_users: None,
+        allowed_hipster_group: None,
+        rewrite_dates: None,
+        hooks_skip_ancestors_of: vec![],
+        ensure_ancestor_of: None,
+        allow_move_to_public_commits_without_hooks: false,
+        runtime_protections: false,
+    }];
+    config.hooks = vec![
+        HookParams {
+            name: "message_length".into(),
+            implementation: "limit_commit_message_length".into(),
+            config: HookConfig {
+                strings: hashmap! {"length_limit".to_string() => "10".to_string()},
+                ..Default::default()
+            },
+        },
+        HookParams {
+            name: "always_fail".into(),
+            implementation: "always_fail_changeset".into(),
+            config: Default::default(),
+        },
+    ];
+
+    let mut hm = hook_manager_many_files_dirs_repo(fb).await;
+    load_hooks(
+        fb,
+        &InternalAclProvider::default(),
+        &mut hm,
+        &config,
+        &hashset![],
+    )
+    .await
+    .expect("loading hooks should succeed");
+
+    let bookmark = BookmarkKey::new("bm1").unwrap();
+    // Only hooks that check the message alone are run.
+    let (hooks_run, outcomes) = hm
+        .run_message_hooks_for_bookmark(&ctx, "short", &bookmark, PushAuthoredBy::User)
+        .await
+        .expect("running message hooks should succeed");
+    assert_eq!(hooks_run, vec!["message_length".to_string()]);
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].get_execution(), &HookExecution::Accepted);
+
+    let (_, outcomes) = hm
+        .run_message_hooks_for_bookmark(
+            &ctx,
+            "a message that is too long",
+            &bookmark,
+            PushAuthoredBy::User,
+        )
+        .await
+        .expect("running message hooks should succeed");
+    assert_eq!(outcomes.len(), 1);
+    assert!(outcomes[0].is_rejection());
+
+    // Other bookmarks have no hooks.
+    let (hooks_run, outcomes) = hm
+        .run_message_hooks_for_bookmark(
+            &ctx,
+            "a message that is too long",
+            &BookmarkKey::new("bm2").unwrap(),
+            PushAuthoredBy::User,
+        )
+        .await
+        .expect("running message hooks should succeed");
+    assert!(hooks_run.is_empty());
+    assert!(outcomes.is_empty());
+}
@@ -83,6 +83,10 @@ impl ChangesetHook for BlockCommitMessagePatternHook {
         }
         Ok(HookExecution::Accepted)
     }
+
+    fn checks_message_only(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
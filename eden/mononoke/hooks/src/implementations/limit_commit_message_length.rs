@@ -85,6 +85,10 @@ impl ChangesetHook for LimitCommitMessageLength {
 
         Ok(execution)
     }
+
+    fn checks_message_only(&self) -> bool {
+        true
+    }
 }
 
 fn extract_title<'a>(message: &'a str, max_length: usize) -> &'a str {
@@ -195,6 +195,15 @@ impl HgMutationStore for CachedHgMutationStore {
 
         Ok(mutation_entries_by_changeset)
     }
+
+    async fn all_successors(
+        &self,
+        ctx: &CoreContext,
+        changeset_ids: HashSet<HgChangesetId>,
+    ) -> Result<Vec<HgMutationEntry>> {
+        // Commits can gain successors at any time, so these aren't cached.
+        self.inner_store.all_successors(ctx, changeset_ids).await
+    }
 }
 
 fn get_cache_key(repo_id: RepositoryId, cs: &HgChangesetId) -> String {
@@ -77,6 +77,17 @@ pub trait HgMutationStore: Send + Sync {
         changeset_ids: HashSet<HgChangesetId>,
     ) -> Result<HashMap<HgChangesetId, Vec<HgMutationEntry>>>;
 
+    /// Get all successor information for the given changeset ids.
+    ///
+    /// Returns the entries that describe what the commits were mutated
+    /// into, and what those commits were mutated into in turn.  Successors
+    /// are only known once they have been added to the store.
+    async fn all_successors(
+        &self,
+        ctx: &CoreContext,
+        changeset_ids: HashSet<HgChangesetId>,
+    ) -> Result<Vec<HgMutationEntry>>;
+
     /// Get the repository for which the mutation history is being added
     /// and retrieved.
     fn repo_id(&self) -> RepositoryId;
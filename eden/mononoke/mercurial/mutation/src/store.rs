@@ -450,6 +450,44 @@ impl SqlHgMutationStore {
         }
         Ok(())
     }
+
+    /// Fetch the entries of all successors of the given changesets into the
+    /// entry set, following successors until there are no more, or the chain
+    /// limit is reached.
+    async fn fetch_all_successors(
+        &self,
+        connection: &Connection,
+        entry_set: &mut HgMutationEntrySet,
+        changeset_ids: HashSet<HgChangesetId>,
+    ) -> Result<()> {
+        let mut seen = changeset_ids.clone();
+        let mut to_fetch = changeset_ids;
+        while !to_fetch.is_empty() && entry_set.entries.len() < self.mutation_chain_limit {
+            let chunks = to_fetch
+                .drain()
+                .chunks(SELECT_CHUNK_SIZE)
+                .into_iter()
+                .map(|chunk| chunk.collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            let successors = stream::iter(chunks.into_iter().map(|chunk| async move {
+                SelectSuccessorsByPredecessor::query(connection, &self.repo_id, chunk.as_slice())
+                    .await
+                    .with_context(|| format!("Error fetching successors of: {:?}", chunk))
+            }))
+            .buffered(10)
+            .try_collect::<Vec<_>>()
+            .await?;
+            to_fetch = successors
+                .into_iter()
+                .flatten()
+                .map(|(successor,)| successor)
+                .filter(|successor| seen.insert(*successor))
+                .collect();
+            self.fetch_by_successor(connection, entry_set, &to_fetch)
+                .await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -597,6 +635,40 @@ impl HgMutationStore for SqlHgMutationStore {
         );
         Ok(entries)
     }
+
+    async fn all_successors(
+        &self,
+        ctx: &CoreContext,
+        changeset_ids: HashSet<HgChangesetId>,
+    ) -> Result<Vec<HgMutationEntry>> {
+        let mut entry_set = HgMutationEntrySet::new();
+        self.fetch_all_successors(
+            &self.connections.read_connection,
+            &mut entry_set,
+            changeset_ids.clone(),
+        )
+        .await?;
+        if entry_set.entries.is_empty() {
+            // The replica may not know about successors that were added
+            // recently, so check the master.
+            self.fetch_all_successors(
+                &self.connections.read_master_connection,
+                &mut entry_set,
+                changeset_ids,
+            )
+            .await?;
+        }
+        debug!(
+            ctx.logger(),
+            "Mutation store fetched {} successor entries",
+            entry_set.entries.len(),
+        );
+        ctx.perf_counters().add_to_counter(
+            PerfCounterType::HgMutationStoreNumFetched,
+            entry_set.entries.len() as i64,
+        );
+        Ok(entry_set.entries.into_values().collect())
+    }
 }
 
 mononoke_queries! {
@@ -739,6 +811,14 @@ mononoke_queries! {
         LIMIT {mut_lim}"
     }
 
+    read SelectSuccessorsByPredecessor(repo_id: RepositoryId, >list cs_id: HgChangesetId) -> (
+        HgChangesetId,
+    ) {
+        "SELECT DISTINCT successor
+        FROM hg_mutation_preds
+        WHERE repo_id = {repo_id} AND predecessor IN {cs_id}"
+    }
+
     read SelectSplitsBySuccessor(repo_id: RepositoryId, >list cs_id: HgChangesetId) -> (
         HgChangesetId,
         u64,
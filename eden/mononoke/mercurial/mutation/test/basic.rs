@@ -23,6 +23,7 @@ use mononoke_types_mocks::repo::REPO_ZERO;
 use sql_construct::SqlConstruct;
 
 use crate::util::check_entries;
+use crate::util::check_successor_entries;
 
 pub(crate) fn create_entries() -> HashMap<usize, HgMutationEntry> {
     // Generate the mutation graph:
@@ -304,6 +305,45 @@ async fn add_entries_and_fetch_predecessors(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn add_entries_and_fetch_successors(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let store = SqlHgMutationStoreBuilder::with_sqlite_in_memory()
+        .unwrap()
+        .with_repo_id(REPO_ZERO);
+
+    let entries = create_entries();
+    store
+        .add_entries(
+            &ctx,
+            hashset![make_hg_cs_id(6), make_hg_cs_id(7)],
+            entries.values().cloned().collect(),
+        )
+        .await?;
+
+    check_successor_entries(
+        &store,
+        &ctx,
+        hashset![make_hg_cs_id(1)],
+        &entries,
+        &[2, 4, 5, 6],
+    )
+    .await?;
+    check_successor_entries(
+        &store,
+        &ctx,
+        hashset![make_hg_cs_id(3)],
+        &entries,
+        &[4, 5, 6],
+    )
+    .await?;
+    check_successor_entries(&store, &ctx, hashset![make_hg_cs_id(5)], &entries, &[6]).await?;
+    check_successor_entries(&store, &ctx, hashset![make_hg_cs_id(6)], &entries, &[]).await?;
+    check_successor_entries(&store, &ctx, hashset![make_hg_cs_id(7)], &entries, &[]).await?;
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn check_mutations_are_cut_when_reaching_limit(fb: FacebookInit) -> Result<()> {
     const TEST_MUTATION_LIMIT: usize = 10;
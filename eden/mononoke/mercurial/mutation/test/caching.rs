@@ -80,6 +80,14 @@ impl HgMutationStore for CountedHgMutationStore {
             .all_predecessors_by_changeset(ctx, changeset_ids)
             .await
     }
+
+    async fn all_successors(
+        &self,
+        ctx: &CoreContext,
+        changeset_ids: HashSet<HgChangesetId>,
+    ) -> Result<Vec<HgMutationEntry>> {
+        self.inner_store.all_successors(ctx, changeset_ids).await
+    }
 }
 
 #[fbinit::test]
@@ -54,3 +54,22 @@ pub(crate) async fn check_entries(
     assert_eq!(fetched_entries, expected_entries);
     Ok(())
 }
+
+pub(crate) async fn check_successor_entries(
+    store: &dyn HgMutationStore,
+    ctx: &CoreContext,
+    changeset_ids: HashSet<HgChangesetId>,
+    entries: &HashMap<usize, HgMutationEntry>,
+    indexes: &[usize],
+) -> Result<()> {
+    let mut fetched_entries = store.all_successors(ctx, changeset_ids).await?;
+    let mut expected_entries = get_entries(entries, indexes);
+    fetched_entries.sort_unstable_by(compare_entries);
+    expected_entries.sort_unstable_by(compare_entries);
+    assert_eq!(
+        get_successors(&fetched_entries),
+        get_successors(&expected_entries)
+    );
+    assert_eq!(fetched_entries, expected_entries);
+    Ok(())
+}
@@ -484,6 +484,7 @@ mod test {
     use std::time::Duration;
 
     use bookmarks_types::BookmarkKey;
+    use bookmarks_types::BookmarkPrefix;
     use cached_config::TestSource;
     use maplit::btreemap;
     use maplit::hashmap;
@@ -818,6 +819,7 @@ mod test {
             regex="[^/]*/stable"
             ensure_ancestor_of="master"
             allow_move_to_public_commits_without_hooks=true
+            runtime_protections=true
 
             [[hooks]]
             name="hook1"
@@ -872,10 +874,14 @@ mod test {
             skip_dag_load_at_startup = true
             reload_dag_save_period_secs = 0
             update_to_master_bookmark_period_secs = 120
+            idmap_range_shard_starts = [1000000, 2000000]
             heads_to_include = [
                 { bookmark = "test_bookmark" },
+                { public_bookmarks_with_prefix = "release/" },
             ]
             extra_heads_to_include_in_background_jobs = []
+            read_fallback_to_changesets = true
+            shadow_reads = true
 
             [backup_config]
             verification_enabled = false
@@ -959,6 +965,7 @@ mod test {
         sparse_profiles = { db_address = "sparse_profiles_db_address" }
         bonsai_blob_mapping = { sharded = { shard_map = "blob_mapping_shards", shard_num = 12 } }
         deletion_log = { db_address = "deletion_log" }
+        segmented_changelog_idmap = { sharded = { shard_map = "idmap_shards", shard_num = 2 } }
 
         [main.blobstore.multiplexed_wal]
         multiplex_id = 1
@@ -1069,6 +1076,12 @@ mod test {
                 deletion_log: Some(RemoteDatabaseConfig {
                     db_address: "deletion_log".into(),
                 }),
+                segmented_changelog_idmap: Some(ShardableRemoteDatabaseConfig::Sharded(
+                    ShardedRemoteDatabaseConfig {
+                        shard_map: "idmap_shards".into(),
+                        shard_num: NonZeroUsize::new(2).unwrap(),
+                    },
+                )),
             }),
             ephemeral_blobstore: None,
         };
@@ -1105,6 +1118,7 @@ mod test {
                         hooks_skip_ancestors_of: vec![],
                         ensure_ancestor_of: None,
                         allow_move_to_public_commits_without_hooks: false,
+                        runtime_protections: false,
                     },
                     BookmarkParams {
                         bookmark: Regex::new("[^/]*/stable").unwrap().into(),
@@ -1116,6 +1130,7 @@ mod test {
                         hooks_skip_ancestors_of: vec![],
                         ensure_ancestor_of: Some(BookmarkKey::new("master").unwrap()),
                         allow_move_to_public_commits_without_hooks: true,
+                        runtime_protections: true,
                     },
                 ],
                 hooks: vec![
@@ -1202,6 +1217,9 @@ mod test {
                     service_write_hipster_acl: None,
                     permit_commits_without_parents: false,
                     service_write_restrictions: Default::default(),
+                    enabled_gated_methods: Default::default(),
+                    scuba_table: None,
+                    scuba_category: None,
                 },
                 source_control_service_monitoring: Some(SourceControlServiceMonitoring {
                     bookmarks_to_report_age: vec![
@@ -1233,10 +1251,18 @@ mod test {
                     skip_dag_load_at_startup: true,
                     reload_dag_save_period: None,
                     update_to_master_bookmark_period: Some(Duration::from_secs(120)),
-                    heads_to_include: vec![SegmentedChangelogHeadConfig::Bookmark(
-                        BookmarkKey::new("test_bookmark").unwrap(),
-                    )],
+                    heads_to_include: vec![
+                        SegmentedChangelogHeadConfig::Bookmark(
+                            BookmarkKey::new("test_bookmark").unwrap(),
+                        ),
+                        SegmentedChangelogHeadConfig::PublicBookmarksWithPrefix(
+                            BookmarkPrefix::new("release/").unwrap(),
+                        ),
+                    ],
                     extra_heads_to_include_in_background_jobs: vec![],
+                    read_fallback_to_changesets: true,
+                    idmap_range_shard_starts: vec![1000000, 2000000],
+                    shadow_reads: true,
                 },
                 repo_client_knobs: RepoClientKnobs {
                     allow_short_getpack_history: true,
@@ -1355,6 +1381,9 @@ mod test {
                         vec![],
                     )],
                     extra_heads_to_include_in_background_jobs: vec![],
+                    read_fallback_to_changesets: false,
+                    idmap_range_shard_starts: vec![],
+                    shadow_reads: false,
                 },
                 repo_client_knobs: RepoClientKnobs::default(),
                 phabricator_callsign: Some("WWW".to_string()),
@@ -1612,6 +1641,7 @@ mod test {
                         },
                         bonsai_blob_mapping: None,
                         deletion_log: None,
+                        segmented_changelog_idmap: None,
                     }),
                     ephemeral_blobstore: None,
                 },
@@ -1708,6 +1738,7 @@ mod test {
                         sparse_profiles: RemoteDatabaseConfig { db_address: "test_db".into(), },
                         bonsai_blob_mapping: None,
                         deletion_log: None,
+                        segmented_changelog_idmap: None,
                     }),
 
                     ephemeral_blobstore: None,
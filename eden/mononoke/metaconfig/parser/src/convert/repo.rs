@@ -13,6 +13,7 @@ use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use bookmarks_types::BookmarkKey;
+use bookmarks_types::BookmarkPrefix;
 use metaconfig_types::Address;
 use metaconfig_types::BlameVersion;
 use metaconfig_types::BookmarkOrRegex;
@@ -209,6 +210,7 @@ impl Convert for RawBookmarkConfig {
         let allow_move_to_public_commits_without_hooks = self
             .allow_move_to_public_commits_without_hooks
             .unwrap_or(false);
+        let runtime_protections = self.runtime_protections.unwrap_or(false);
 
         Ok(BookmarkParams {
             bookmark: bookmark_or_regex,
@@ -220,6 +222,7 @@ impl Convert for RawBookmarkConfig {
             hooks_skip_ancestors_of,
             ensure_ancestor_of,
             allow_move_to_public_commits_without_hooks,
+            runtime_protections,
         })
     }
 }
@@ -379,6 +382,13 @@ impl Convert for RawSourceControlServiceParams {
             service_write_hipster_acl: self.service_write_hipster_acl,
             permit_commits_without_parents: self.permit_commits_without_parents,
             service_write_restrictions,
+            enabled_gated_methods: self
+                .enabled_gated_methods
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            scuba_table: self.scuba_table,
+            scuba_category: self.scuba_category,
         })
     }
 }
@@ -521,6 +531,11 @@ impl Convert for RawSegmentedChangelogHeadConfig {
             Self::bonsai_changeset(changeset_id) => {
                 SegmentedChangelogHeadConfig::Changeset(ChangesetId::from_str(&changeset_id)?)
             }
+            Self::public_bookmarks_with_prefix(prefix) => {
+                SegmentedChangelogHeadConfig::PublicBookmarksWithPrefix(BookmarkPrefix::new(
+                    &prefix,
+                )?)
+            }
             Self::UnknownField(_) => {
                 return Err(anyhow!(
                     "Unknown variant of RawSegmentedChangelogHeadConfig!"
@@ -578,6 +593,20 @@ impl Convert for RawSegmentedChangelogConfig {
             )?,
             heads_to_include,
             extra_heads_to_include_in_background_jobs,
+            read_fallback_to_changesets: self
+                .read_fallback_to_changesets
+                .unwrap_or(default.read_fallback_to_changesets),
+            idmap_range_shard_starts: self
+                .idmap_range_shard_starts
+                .map(|starts| {
+                    starts
+                        .into_iter()
+                        .map(|start| start.try_into())
+                        .collect::<Result<Vec<u64>, _>>()
+                })
+                .transpose()?
+                .unwrap_or(default.idmap_range_shard_starts),
+            shadow_reads: self.shadow_reads.unwrap_or(default.shadow_reads),
         })
     }
 }
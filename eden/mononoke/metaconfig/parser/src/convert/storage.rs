@@ -333,6 +333,7 @@ impl Convert for RawMetadataConfig {
                     sparse_profiles: raw.sparse_profiles.convert()?,
                     bonsai_blob_mapping: raw.bonsai_blob_mapping.convert()?,
                     deletion_log: raw.deletion_log.convert()?,
+                    segmented_changelog_idmap: raw.segmented_changelog_idmap.convert()?,
                 },
             )),
             RawMetadataConfig::UnknownField(f) => Err(anyhow!(
@@ -26,6 +26,7 @@ use anyhow::Error;
 use anyhow::Result;
 use ascii::AsciiString;
 use bookmarks_types::BookmarkKey;
+use bookmarks_types::BookmarkPrefix;
 use derive_more::From;
 use derive_more::Into;
 use mononoke_types::path::MPath;
@@ -499,6 +500,9 @@ pub struct BookmarkParams {
     /// because commit is already public, meaning that hooks already
     /// should have been run when the commit was first made public.
     pub allow_move_to_public_commits_without_hooks: bool,
+    /// Whether protections registered at runtime, e.g. the allowed pushers
+    /// of release branches, are checked for this bookmark.
+    pub runtime_protections: bool,
 }
 
 /// The type of the hook
@@ -1046,6 +1050,8 @@ pub struct RemoteMetadataDatabaseConfig {
     pub bonsai_blob_mapping: Option<ShardableRemoteDatabaseConfig>,
     /// Database for deletion log
     pub deletion_log: Option<RemoteDatabaseConfig>,
+    /// Database for segmented changelog idmap range shards
+    pub segmented_changelog_idmap: Option<ShardableRemoteDatabaseConfig>,
 }
 
 /// Configuration for the Metadata database
@@ -1291,9 +1297,25 @@ pub struct SourceControlServiceParams {
 
     /// Whether users can create commits without parents.
     pub permit_commits_without_parents: bool,
+
+    /// Methods that are gated per repo, and which are enabled for this repo.
+    pub enabled_gated_methods: HashSet<String>,
+
+    /// Scuba dataset to log requests for this repo to, so that high traffic
+    /// repos can be kept apart from the server's shared dataset.
+    pub scuba_table: Option<String>,
+
+    /// Category to tag this repo's request samples with, in the
+    /// `repo_category` column.
+    pub scuba_category: Option<String>,
 }
 
 impl SourceControlServiceParams {
+    /// Returns true if the named gated method is enabled for this repo.
+    pub fn gated_method_enabled(&self, method: impl AsRef<str>) -> bool {
+        self.enabled_gated_methods.contains(method.as_ref())
+    }
+
     /// Returns true if the named service is permitted to call the named method.
     pub fn service_write_method_permitted(
         &self,
@@ -1441,6 +1463,9 @@ pub enum SegmentedChangelogHeadConfig {
     Bookmark(BookmarkKey),
     /// A single changeset.
     Changeset(ChangesetId),
+    /// All public bookmarks that start with a prefix, e.g. release branches.
+    /// Matching no bookmarks is not an error.
+    PublicBookmarksWithPrefix(BookmarkPrefix),
 }
 
 impl From<Option<BookmarkKey>> for SegmentedChangelogHeadConfig {
@@ -1531,6 +1556,16 @@ pub struct SegmentedChangelogConfig {
     /// a commit they assume it should know of, and server would do expensive sql requests
     /// (see S242328).
     pub extra_heads_to_include_in_background_jobs: Vec<SegmentedChangelogHeadConfig>,
+    /// Answer location-to-hash lookups that the Dag can't serve, for example
+    /// because the repo is not seeded yet, by walking the changesets instead.
+    pub read_fallback_to_changesets: bool,
+    /// The first DagId of each IdMap range shard, in increasing order.  Ids
+    /// below the first start stay in the unsharded IdMap.  Empty if the
+    /// IdMap is not sharded.
+    pub idmap_range_shard_starts: Vec<u64>,
+    /// Check a sample of the ancestry queries served by the Dag against the
+    /// commit graph, logging any mismatches.
+    pub shadow_reads: bool,
 }
 
 impl Default for SegmentedChangelogConfig {
@@ -1545,6 +1580,9 @@ impl Default for SegmentedChangelogConfig {
                 vec![],
             )],
             extra_heads_to_include_in_background_jobs: vec![],
+            read_fallback_to_changesets: false,
+            idmap_range_shard_starts: vec![],
+            shadow_reads: false,
         }
     }
 }
@@ -5,7 +5,10 @@
  * GNU General Public License version 2.
  */
 
+use std::cmp::Ordering;
+use std::cmp::Reverse;
 use std::collections::BTreeSet;
+use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt;
@@ -52,6 +55,9 @@ use manifest::Entry as ManifestEntry;
 use manifest::ManifestOps;
 use manifest::ManifestOrderedOps;
 use manifest::PathOrPrefix;
+use maplit::hashset;
+use mercurial_mutation::HgMutationEntry;
+use mercurial_mutation::HgMutationStoreRef;
 use mercurial_types::Globalrev;
 use mononoke_types::path::MPath;
 use mononoke_types::BonsaiChangeset;
@@ -61,6 +67,8 @@ use mononoke_types::MPathElement;
 use mononoke_types::NonRootMPath;
 use mononoke_types::SkeletonManifestId;
 use mononoke_types::Svnrev;
+use phases::Phase;
+use phases::PhasesRef;
 use repo_blobstore::RepoBlobstoreArc;
 use repo_blobstore::RepoBlobstoreRef;
 use repo_derived_data::RepoDerivedDataArc;
@@ -138,6 +146,87 @@ pub struct ChangesetHistoryOptions {
     pub until_timestamp: Option<i64>,
     pub descendants_of: Option<ChangesetId>,
     pub exclude_changeset_and_ancestors: Option<ChangesetId>,
+    pub order: ChangesetHistoryOrder,
+}
+
+/// The order in which commit history is returned.
+///
+/// All orders are deterministic, so that paging through history with
+/// `skip` returns consistent results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChangesetHistoryOrder {
+    /// Reverse topological order: commits are always returned before their
+    /// ancestors.  Commits with the same generation number are ordered by
+    /// changeset id.
+    #[default]
+    Topological,
+    /// Most recent committer date first.  Commits that don't track a
+    /// committer date use their author date instead.
+    ReverseChronological,
+    /// Most recent author date first.
+    AuthorDate,
+}
+
+/// An entry in the queue of commits to visit when traversing history in
+/// date order.
+struct DateOrderedHistoryEntry {
+    /// Timestamp, generation and id used for ordering.  Later timestamps
+    /// come first, ties are broken by higher generation and then by lower
+    /// changeset id.
+    key: (i64, Generation, Reverse<ChangesetId>),
+    author_timestamp: i64,
+    changeset: ChangesetContext,
+}
+
+impl DateOrderedHistoryEntry {
+    async fn new(
+        changeset: ChangesetContext,
+        order: ChangesetHistoryOrder,
+    ) -> Result<Self, MononokeError> {
+        let (info, generation) = try_join(changeset.changeset_info(), async {
+            changeset
+                .repo()
+                .repo()
+                .commit_graph()
+                .changeset_generation(changeset.ctx(), changeset.id())
+                .await
+                .map_err(MononokeError::from)
+        })
+        .await?;
+        let author_timestamp = info.author_date().as_chrono().timestamp();
+        let timestamp = match order {
+            ChangesetHistoryOrder::ReverseChronological => info
+                .committer_date()
+                .map_or(author_timestamp, |date| date.as_chrono().timestamp()),
+            _ => author_timestamp,
+        };
+        let key = (timestamp, generation, Reverse(changeset.id()));
+        Ok(Self {
+            key,
+            author_timestamp,
+            changeset,
+        })
+    }
+}
+
+impl PartialEq for DateOrderedHistoryEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for DateOrderedHistoryEntry {}
+
+impl PartialOrd for DateOrderedHistoryEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DateOrderedHistoryEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
 }
 
 #[derive(Clone)]
@@ -288,6 +377,41 @@ impl ChangesetContext {
             .await?)
     }
 
+    /// The recorded Mercurial mutation history for the changeset (if any),
+    /// i.e. the amends, rebases, folds, etc. that produced it.
+    ///
+    /// Entries are returned for every mutation leading to this changeset,
+    /// including mutations of its predecessors.  See
+    /// `hg_mutation_successors` for what the changeset was mutated into.
+    pub async fn hg_mutation_history(&self) -> Result<Vec<HgMutationEntry>, MononokeError> {
+        let hg_cs_id = match self.hg_id().await? {
+            Some(hg_cs_id) => hg_cs_id,
+            None => return Ok(Vec::new()),
+        };
+        Ok(self
+            .repo()
+            .blob_repo()
+            .hg_mutation_store()
+            .all_predecessors(self.ctx(), hashset! {hg_cs_id})
+            .await?)
+    }
+
+    /// The recorded Mercurial mutations of the changeset into later
+    /// changesets, e.g. the amend that replaced it, including mutations of
+    /// those later changesets in turn.
+    pub async fn hg_mutation_successors(&self) -> Result<Vec<HgMutationEntry>, MononokeError> {
+        let hg_cs_id = match self.hg_id().await? {
+            Some(hg_cs_id) => hg_cs_id,
+            None => return Ok(Vec::new()),
+        };
+        Ok(self
+            .repo()
+            .blob_repo()
+            .hg_mutation_store()
+            .all_successors(self.ctx(), hashset! {hg_cs_id})
+            .await?)
+    }
+
     /// Derive a derivable data type for this changeset.
     // Desugared async syntax so we can return a future with static lifetime.
     fn derive<Derivable: BonsaiDerivable>(
@@ -632,6 +756,21 @@ impl ChangesetContext {
         ))
     }
 
+    /// Whether the changeset is public or a draft.
+    pub async fn phase(&self) -> Result<Phase, MononokeError> {
+        let public = self
+            .repo
+            .blob_repo()
+            .phases()
+            .get_public(self.ctx(), vec![self.id], false)
+            .await?;
+        if public.contains(&self.id) {
+            Ok(Phase::Public)
+        } else {
+            Ok(Phase::Draft)
+        }
+    }
+
     /// All mercurial commit extras as (name, value) pairs.
     pub async fn hg_extras(&self) -> Result<Vec<(String, Vec<u8>)>, MononokeError> {
         Ok(self
@@ -668,12 +807,74 @@ impl ChangesetContext {
     /// Returns `true` if this commit is an ancestor of `other_commit`.  A commit is considered its
     /// own ancestor for the purpose of this call.
     pub async fn is_ancestor_of(&self, other_commit: ChangesetId) -> Result<bool, MononokeError> {
-        Ok(self
-            .repo()
-            .repo()
-            .commit_graph()
-            .is_ancestor(self.ctx(), self.id, other_commit)
-            .await?)
+        self.repo().is_ancestor(self.id, other_commit).await
+    }
+
+    /// Returns which of the other commits have this commit as an ancestor.
+    ///
+    /// The commits are checked as a group, and a group is only split when
+    /// some of it descends from this commit, so commits that don't descend
+    /// from it are ruled out together.
+    pub async fn descendants_among(
+        &self,
+        other_commits: impl IntoIterator<Item = ChangesetId>,
+    ) -> Result<HashSet<ChangesetId>, MononokeError> {
+        let commit_graph = self.repo().repo().commit_graph();
+        let other_commits: Vec<_> = other_commits
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let mut descendants = HashSet::new();
+        let mut groups = if other_commits.is_empty() {
+            vec![]
+        } else {
+            vec![other_commits]
+        };
+        while !groups.is_empty() {
+            let contains =
+                future::try_join_all(groups.iter().map(|group| {
+                    commit_graph.is_ancestor_of_any(self.ctx(), self.id, group.clone())
+                }))
+                .await?;
+            let mut next_groups = Vec::new();
+            for (mut group, contains) in groups.into_iter().zip(contains) {
+                if !contains {
+                    continue;
+                }
+                if group.len() == 1 {
+                    descendants.extend(group);
+                } else {
+                    let other_half = group.split_off(group.len() / 2);
+                    next_groups.push(group);
+                    next_groups.push(other_half);
+                }
+            }
+            groups = next_groups;
+        }
+        Ok(descendants)
+    }
+
+    /// Returns a stream of the commits that descend from this commit and
+    /// are ancestors of any of `heads`, not including this commit itself.
+    ///
+    /// Commits are returned newest first, by descending generation and then
+    /// by changeset id.  The order is stable, so a listing can be resumed
+    /// after any commit in it.
+    pub async fn descendants_within(
+        &self,
+        heads: Vec<ChangesetId>,
+    ) -> Result<BoxStream<'static, Result<ChangesetId, MononokeError>>, MononokeError> {
+        let cs_ids = AncestorsStreamBuilder::new(
+            self.repo().repo().commit_graph_arc(),
+            self.ctx().clone(),
+            heads,
+        )
+        .descendants_of(self.id)
+        .exclude_ancestors_of(vec![self.id])
+        .build()
+        .await?;
+        Ok(cs_ids.map_err(MononokeError::from).boxed())
     }
 
     /// Returns the lowest common ancestor of two commits.
@@ -684,13 +885,8 @@ impl ChangesetContext {
         &self,
         other_commit: ChangesetId,
     ) -> Result<Option<ChangesetContext>, MononokeError> {
-        let lca = self
-            .repo()
-            .repo()
-            .commit_graph()
-            .common_base(self.ctx(), self.id, other_commit)
-            .await?;
-        Ok(lca.get(0).map(|id| Self::new(self.repo.clone(), *id)))
+        let lca = self.repo().common_base(self.id, other_commit).await?;
+        Ok(lca.map(|id| Self::new(self.repo.clone(), id)))
     }
 
     pub async fn diff_unordered(
@@ -1335,6 +1531,10 @@ impl ChangesetContext {
         &self,
         opts: ChangesetHistoryOptions,
     ) -> Result<BoxStream<'_, Result<ChangesetContext, MononokeError>>, MononokeError> {
+        if opts.order != ChangesetHistoryOrder::Topological {
+            return self.history_by_date(opts).await;
+        }
+
         let mut ancestors_stream_builder = AncestorsStreamBuilder::new(
             self.repo().repo().commit_graph_arc(),
             self.ctx().clone(),
@@ -1381,6 +1581,92 @@ impl ChangesetContext {
             .boxed())
     }
 
+    /// Returns a stream of `ChangesetContext` for the history of the
+    /// repository from this commit, ordered by date.
+    ///
+    /// Commits are visited best-first: a commit's parents become eligible
+    /// once the commit itself has been returned, and the most recent
+    /// eligible commit is always returned next.  This means that commits
+    /// are returned before their ancestors unless dates are skewed across
+    /// merges.
+    async fn history_by_date(
+        &self,
+        opts: ChangesetHistoryOptions,
+    ) -> Result<BoxStream<'_, Result<ChangesetContext, MononokeError>>, MononokeError> {
+        let commit_graph = self.repo().repo().commit_graph_arc();
+        let ctx = self.ctx().clone();
+
+        let is_included = {
+            cloned!(commit_graph, ctx);
+            let descendants_of = opts.descendants_of;
+            let exclude_changeset_and_ancestors = opts.exclude_changeset_and_ancestors;
+            move |cs_id: ChangesetId| {
+                cloned!(commit_graph, ctx);
+                async move {
+                    if let Some(descendants_of) = descendants_of {
+                        if !commit_graph
+                            .is_ancestor(&ctx, descendants_of, cs_id)
+                            .await?
+                        {
+                            return Ok::<_, MononokeError>(false);
+                        }
+                    }
+                    if let Some(exclude) = exclude_changeset_and_ancestors {
+                        if commit_graph.is_ancestor(&ctx, cs_id, exclude).await? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+            }
+        };
+
+        let mut queue = BinaryHeap::new();
+        let mut seen = HashSet::new();
+        seen.insert(self.id());
+        queue.push(DateOrderedHistoryEntry::new(self.clone(), opts.order).await?);
+
+        let until_timestamp = opts.until_timestamp;
+        let order = opts.order;
+
+        Ok(
+            stream::try_unfold((queue, seen), move |(mut queue, mut seen)| {
+                cloned!(commit_graph, ctx, is_included);
+                async move {
+                    while let Some(entry) = queue.pop() {
+                        // History stops at commits that are too old, in the same
+                        // way as for topologically ordered history.
+                        if until_timestamp.map_or(false, |until| entry.author_timestamp < until) {
+                            continue;
+                        }
+                        // Ancestors of commits that are not included are not
+                        // included either, so there is no need to visit them.
+                        if !is_included(entry.changeset.id()).await? {
+                            continue;
+                        }
+                        let parents = commit_graph
+                            .changeset_parents(&ctx, entry.changeset.id())
+                            .await?;
+                        let new_parents = parents
+                            .into_iter()
+                            .filter(|parent| seen.insert(*parent))
+                            .map(|parent| {
+                                DateOrderedHistoryEntry::new(
+                                    ChangesetContext::new(entry.changeset.repo().clone(), parent),
+                                    order,
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        queue.extend(future::try_join_all(new_parents).await?);
+                        return Ok(Some((entry.changeset, (queue, seen))));
+                    }
+                    Ok::<_, MononokeError>(None)
+                }
+            })
+            .boxed(),
+        )
+    }
+
     pub async fn diff_root_unordered(
         &self,
         path_restrictions: Option<Vec<MononokePath>>,
@@ -1400,6 +1686,8 @@ impl ChangesetContext {
     /// `self` is considered the "root/initial/genesis" changeset
     /// `path_restrictions` if present will narrow down the diff to given paths
     /// `diff_items` what to include in the output (files, dirs or both)
+    ///
+    /// As with `diff`, when dirs are included the root directory is too.
     pub async fn diff_root(
         &self,
         path_restrictions: Option<Vec<MononokePath>>,
@@ -1415,12 +1703,12 @@ impl ChangesetContext {
             .try_filter_map(|(path, entry)| async move {
                 let path: Option<NonRootMPath> = path.into();
                 match (path, entry) {
-                    (Some(mpath), ManifestEntry::Leaf(_)) if diff_files => Ok(Some(mpath)),
-                    (Some(mpath), ManifestEntry::Tree(_)) if diff_trees => Ok(Some(mpath)),
+                    (Some(mpath), ManifestEntry::Leaf(_)) if diff_files => Ok(Some(Some(mpath))),
+                    (path, ManifestEntry::Tree(_)) if diff_trees => Ok(Some(path)),
                     _ => Ok(None),
                 }
             })
-            .map_ok(|mpath| MononokePath::new(Some(mpath)))
+            .map_ok(MononokePath::new)
             .map_err(MononokeError::from)
             .take(limit.unwrap_or(usize::MAX))
             .and_then(|mp| async move {
@@ -1450,4 +1738,57 @@ impl ChangesetContext {
             )
             .await?)
     }
+
+    /// Run a single hook against this commit and its most recent ancestors,
+    /// up to `limit` commits in total, as if they were being landed to the
+    /// bookmark.  Hook bypasses are ignored.
+    ///
+    /// Returns the outcomes for each commit, most recent commit first.
+    pub async fn run_hook_on_history(
+        &self,
+        hook_name: &str,
+        bookmark: impl AsRef<str>,
+        limit: usize,
+    ) -> Result<Vec<(ChangesetId, Vec<HookOutcome>)>, MononokeError> {
+        let hook_manager = self.repo().hook_manager();
+        if !hook_manager.hook_exists(hook_name) {
+            return Err(MononokeError::InvalidRequest(format!(
+                "hook '{}' is not configured for this repo",
+                hook_name
+            )));
+        }
+        let bookmark = BookmarkKey::new(bookmark.as_ref())?;
+        let changesets = self
+            .history(ChangesetHistoryOptions::default())
+            .await?
+            .take(limit)
+            .map_ok(|changeset| async move { changeset.bonsai_changeset().await })
+            .try_buffered(100)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut outcomes: HashMap<ChangesetId, Vec<HookOutcome>> = HashMap::new();
+        for outcome in hook_manager
+            .run_hook_for_bookmark(
+                self.ctx(),
+                hook_name,
+                changesets.iter(),
+                &bookmark,
+                CrossRepoPushSource::NativeToThisRepo,
+                PushAuthoredBy::User,
+            )
+            .await?
+        {
+            outcomes
+                .entry(outcome.get_changeset_id())
+                .or_default()
+                .push(outcome);
+        }
+        Ok(changesets
+            .iter()
+            .map(|cs| {
+                let cs_id = cs.get_changeset_id();
+                (cs_id, outcomes.remove(&cs_id).unwrap_or_default())
+            })
+            .collect())
+    }
 }
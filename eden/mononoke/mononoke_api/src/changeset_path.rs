@@ -6,6 +6,7 @@
  */
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 
 use anyhow::anyhow;
@@ -44,6 +45,7 @@ use mononoke_types::deleted_manifest_common::DeletedManifestCommon;
 use mononoke_types::fsnode::FsnodeFile;
 use mononoke_types::path::MPath;
 use mononoke_types::ChangesetId;
+use mononoke_types::ContentId;
 use mononoke_types::ContentMetadataV2;
 /// Metadata about a file.
 pub use mononoke_types::ContentMetadataV2 as FileMetadata;
@@ -51,6 +53,7 @@ use mononoke_types::FileType;
 use mononoke_types::FileUnodeId;
 use mononoke_types::FsnodeId;
 use mononoke_types::ManifestUnodeId;
+use mononoke_types::NonRootMPath;
 use mononoke_types::SkeletonManifestId;
 use repo_blobstore::RepoBlobstoreRef;
 
@@ -60,6 +63,11 @@ use crate::file::FileContext;
 use crate::path::MononokePath;
 use crate::repo::RepoContext;
 use crate::tree::TreeContext;
+use crate::tree::TreeEntry;
+
+/// The maximum number of versions of a path that `first_introduced` will
+/// walk through.
+pub const FIRST_INTRODUCED_MAX_UNODES: usize = 10_000;
 
 pub struct HistoryEntry {
     pub name: String,
@@ -273,6 +281,70 @@ impl ChangesetPathContentContext {
         Ok(tree)
     }
 
+    /// List the tree at this path and the trees beneath it, breadth first,
+    /// down to `depth` levels.  A depth of 1 lists just this tree.
+    ///
+    /// Each tree is returned with its path and its entries.  Trees are only
+    /// ever listed whole: listing stops before the total number of entries
+    /// would exceed `limit`, in which case the returned flag is `true`.  If
+    /// there is no tree at this path, no trees are returned.
+    ///
+    /// Trees in `exclude`, e.g. because they have already been listed, are
+    /// not returned and don't count towards the limit, but the trees beneath
+    /// them are still listed.
+    pub async fn list_subtrees(
+        &self,
+        depth: usize,
+        limit: usize,
+        exclude: &HashSet<MononokePath>,
+    ) -> Result<(Vec<(MononokePath, Vec<(String, TreeEntry)>)>, bool), MononokeError> {
+        let mut listed = Vec::new();
+        let mut level = match self.tree().await? {
+            Some(tree) => vec![(self.path.clone(), tree)],
+            None => return Ok((listed, false)),
+        };
+        let mut remaining = limit;
+        for _ in 0..depth {
+            if level.is_empty() {
+                break;
+            }
+            // Check the limit as each tree is listed, so that listing stops
+            // soon after the limit is reached, rather than at the end of the
+            // level.
+            let mut listings = stream::iter(level)
+                .map(|(path, tree)| async move {
+                    let entries: Vec<_> = tree.list_elements().await?.collect();
+                    Ok::<_, MononokeError>((path, entries))
+                })
+                .buffered(100);
+            let mut next_level = Vec::new();
+            while let Some((path, entries)) = listings.try_next().await? {
+                let excluded = exclude.contains(&path);
+                if !excluded {
+                    if entries.len() > remaining {
+                        return Ok((listed, true));
+                    }
+                    remaining -= entries.len();
+                }
+                let mut named_entries = Vec::with_capacity(entries.len());
+                for (elem, entry) in entries {
+                    if let TreeEntry::Directory(dir) = &entry {
+                        next_level.push((
+                            path.append(&elem),
+                            TreeContext::new_authorized(self.repo().clone(), *dir.id()),
+                        ));
+                    }
+                    named_entries.push((String::from_utf8_lossy(elem.as_ref()).to_string(), entry));
+                }
+                if !excluded {
+                    listed.push((path, named_entries));
+                }
+            }
+            level = next_level;
+        }
+        Ok((listed, false))
+    }
+
     /// Returns a `FileContext` for the file at this path.  Returns `None` if the path
     /// is not a file in this commit.
     pub async fn file(&self) -> Result<Option<FileContext>, MononokeError> {
@@ -286,6 +358,16 @@ impl ChangesetPathContentContext {
         Ok(file)
     }
 
+    /// Returns the content id of the file at this path, without fetching
+    /// the file.  Returns `None` if the path is not a file in this commit.
+    pub async fn file_content_id(&self) -> Result<Option<ContentId>, MononokeError> {
+        let content_id = match self.fsnode_id().await? {
+            Some(Entry::Leaf(file)) => Some(*file.content_id()),
+            _ => None,
+        };
+        Ok(content_id)
+    }
+
     pub async fn file_content(&self) -> Result<Option<Bytes>, MononokeError> {
         Ok(match self.file().await? {
             Some(context) => Some(context.content_concat().await?),
@@ -511,6 +593,74 @@ impl ChangesetPathHistoryContext {
             .map(|cs_id| ChangesetContext::new(self.repo().clone(), cs_id)))
     }
 
+    /// Returns the commit in which this path first appeared in the ancestry
+    /// of this changeset.  History is not followed across deletions, so this
+    /// is the oldest commit in the path's history.  If there is nothing at
+    /// this path, returns `None`.
+    ///
+    /// The path's unodes are followed back to those with no parents, whose
+    /// linknodes are the commits that introduced the path.  If the path has
+    /// more than `FIRST_INTRODUCED_MAX_UNODES` versions, gives up and returns
+    /// `MononokeError::NotAvailable`.
+    pub async fn first_introduced(&self) -> Result<Option<ChangesetContext>, MononokeError> {
+        let unode_id = match self.unode_id().await? {
+            Some(unode_id) => unode_id,
+            None => return Ok(None),
+        };
+        let ctx = self.changeset.ctx();
+        let blobstore = self.changeset.repo().blob_repo().repo_blobstore();
+
+        let mut visited = HashSet::new();
+        let mut roots = HashSet::new();
+        let mut next = vec![unode_id];
+        while !next.is_empty() {
+            next.retain(|unode_id| visited.insert(*unode_id));
+            if visited.len() > FIRST_INTRODUCED_MAX_UNODES {
+                return Err(MononokeError::NotAvailable(format!(
+                    "{} has too many versions to find where it was first introduced",
+                    self.path
+                )));
+            }
+            let unodes = stream::iter(next.drain(..))
+                .map(|unode_id| async move {
+                    match unode_id {
+                        Entry::Tree(id) => {
+                            let unode = id.load(ctx, blobstore).await?;
+                            let parents = unode.parents().iter().cloned().map(Entry::Tree);
+                            Ok::<_, MononokeError>((parents.collect(), *unode.linknode()))
+                        }
+                        Entry::Leaf(id) => {
+                            let unode = id.load(ctx, blobstore).await?;
+                            let parents = unode.parents().iter().cloned().map(Entry::Leaf);
+                            Ok((parents.collect::<Vec<_>>(), *unode.linknode()))
+                        }
+                    }
+                })
+                .buffered(100)
+                .try_collect::<Vec<_>>()
+                .await?;
+            for (parents, linknode) in unodes {
+                if parents.is_empty() {
+                    roots.insert(linknode);
+                } else {
+                    next.extend(parents);
+                }
+            }
+        }
+
+        // If the path was introduced on more than one branch, the oldest
+        // introduction wins.
+        let roots = try_join_all(roots.into_iter().map(|cs_id| async move {
+            let changeset = ChangesetContext::new(self.repo().clone(), cs_id);
+            Ok::<_, MononokeError>((changeset.generation().await?, changeset))
+        }))
+        .await?;
+        Ok(roots
+            .into_iter()
+            .min_by_key(|(generation, changeset)| (*generation, changeset.id()))
+            .map(|(_, changeset)| changeset))
+    }
+
     /// Blame metadata for this path.
     pub async fn blame(&self, follow_mutable_file_history: bool) -> Result<BlameV2, MononokeError> {
         let ctx = self.changeset.ctx();
@@ -832,4 +982,35 @@ impl ChangesetPathContext {
         };
         Ok(is_tree)
     }
+
+    /// Returns the case conflicts under this path, up to `limit` of them,
+    /// as groups of paths that differ only by case.  Paths that are files
+    /// or don't exist have no case conflicts under them.
+    pub async fn case_conflicts(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<Vec<MononokePath>>, MononokeError> {
+        let skeleton_manifest_id = match self.skeleton_manifest_id().await? {
+            Some(Entry::Tree(skeleton_manifest_id)) => skeleton_manifest_id,
+            _ => return Ok(Vec::new()),
+        };
+        let ctx = self.changeset.ctx();
+        let blobstore = self.repo().blob_repo().repo_blobstore();
+        let conflicts = skeleton_manifest_id
+            .load(ctx, blobstore)
+            .await?
+            .case_conflicts(ctx, blobstore, limit)
+            .await?;
+        Ok(conflicts
+            .into_iter()
+            .map(|paths| {
+                paths
+                    .into_iter()
+                    .map(|path| {
+                        MononokePath::new(NonRootMPath::join_opt(self.path.as_mpath(), &path))
+                    })
+                    .collect()
+            })
+            .collect())
+    }
 }
@@ -5,6 +5,7 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
 use std::ops::Range;
 
 use anyhow::anyhow;
@@ -369,6 +370,29 @@ impl FileGeneratedSpan {
     }
 }
 
+/// Estimate how similar two file contents are, as a percentage, from the
+/// size of the lines they have in common.
+fn content_similarity(a: &[u8], b: &[u8]) -> u8 {
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 100;
+    }
+    let mut lines: HashMap<&[u8], usize> = HashMap::new();
+    for line in a.split_inclusive(|c| *c == b'\n') {
+        *lines.entry(line).or_default() += 1;
+    }
+    let mut common = 0;
+    for line in b.split_inclusive(|c| *c == b'\n') {
+        if let Some(count) = lines.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                common += line.len();
+            }
+        }
+    }
+    (200 * common / total) as u8
+}
+
 impl ChangesetPathDiffContext {
     /// Create a new path diff context that compares the contents of two
     /// changeset paths.
@@ -440,6 +464,55 @@ impl ChangesetPathDiffContext {
         }
     }
 
+    /// Returns true if this is a change to a file's type only, for example
+    /// making it executable, with its content unchanged.
+    pub async fn is_mode_only_change(&self) -> Result<bool, MononokeError> {
+        match self {
+            Self::Changed(base, other) => {
+                let (base_type, base_id, other_type, other_id) = try_join!(
+                    base.file_type(),
+                    base.file_content_id(),
+                    other.file_type(),
+                    other.file_content_id(),
+                )?;
+                Ok(base_id.is_some() && base_id == other_id && base_type != other_type)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// For copies and moves, returns how similar the copied file's content
+    /// is to its source, as a percentage.  This fetches the content of both
+    /// files unless they are identical.  Returns `None` for other
+    /// differences.
+    pub async fn copy_similarity(&self) -> Result<Option<u8>, MononokeError> {
+        let (base, other) = match self {
+            Self::Copied(base, other) | Self::Moved(base, other) => (base, other),
+            _ => return Ok(None),
+        };
+        let (base_id, other_id) = try_join!(base.file_content_id(), other.file_content_id())?;
+        if base_id == other_id {
+            return Ok(Some(100));
+        }
+        let (base_content, other_content) = try_join!(base.file_content(), other.file_content())?;
+        Ok(Some(content_similarity(
+            &base_content.unwrap_or_default(),
+            &other_content.unwrap_or_default(),
+        )))
+    }
+
+    /// Drop the copy information from this difference.  A copy becomes an
+    /// addition of the destination, and a move becomes an addition of the
+    /// destination and a removal of the source.  Other differences are
+    /// returned unchanged.
+    pub fn into_uncopied(self) -> Vec<Self> {
+        match self {
+            Self::Copied(base, _) => vec![Self::Added(base)],
+            Self::Moved(base, other) => vec![Self::Added(base), Self::Removed(other)],
+            path_diff => vec![path_diff],
+        }
+    }
+
     // Helper for getting file information.
     async fn get_file_data(
         path: Option<&ChangesetPathContentContext>,
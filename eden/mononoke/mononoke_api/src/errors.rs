@@ -21,8 +21,10 @@ use bookmarks_movement::HookRejection;
 use derived_data::DerivationError;
 use itertools::Itertools;
 use megarepo_error::MegarepoError;
+use mononoke_types::BlameRejected;
 use pushrebase::PushrebaseError;
 use repo_authorization::AuthorizationError;
+use repo_bookmark_attrs::BookmarkPolicy;
 use thiserror::Error;
 
 use crate::path::MononokePath;
@@ -78,8 +80,19 @@ pub enum MononokeError {
     HookFailure(Vec<HookRejection>),
     #[error("not available: {0}")]
     NotAvailable(String),
+    #[error("not a directory: {0}")]
+    NotADirectory(String),
+    #[error("path too large: {0}")]
+    PathTooLarge(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
     #[error("permission denied: {0}")]
     AuthorizationError(String),
+    #[error("permission denied: {reason}")]
+    BookmarkPolicyDenied {
+        reason: String,
+        policy: BookmarkPolicy,
+    },
     #[error("internal error: {0}")]
     InternalError(#[source] InternalError),
 }
@@ -114,15 +127,14 @@ impl From<DerivationError> for MononokeError {
 impl From<BookmarkMovementError> for MononokeError {
     fn from(e: BookmarkMovementError) -> Self {
         match e {
-            BookmarkMovementError::AuthorizationError(e) => {
-                MononokeError::AuthorizationError(e.to_string())
-            }
+            BookmarkMovementError::AuthorizationError(e) => MononokeError::from(e),
             BookmarkMovementError::HookFailure(rejections) => {
                 MononokeError::HookFailure(rejections)
             }
             BookmarkMovementError::PushrebaseError(PushrebaseError::Conflicts(conflicts)) => {
                 MononokeError::PushrebaseConflicts(conflicts)
             }
+            e @ BookmarkMovementError::TransactionFailed => MononokeError::Conflict(e.to_string()),
             BookmarkMovementError::Error(e) => MononokeError::InternalError(InternalError::from(e)),
             _ => MononokeError::InvalidRequest(e.to_string()),
         }
@@ -132,9 +144,13 @@ impl From<BookmarkMovementError> for MononokeError {
 impl From<AuthorizationError> for MononokeError {
     fn from(e: AuthorizationError) -> Self {
         match e {
-            AuthorizationError::PermissionDenied(e) => {
-                MononokeError::AuthorizationError(e.to_string())
-            }
+            AuthorizationError::PermissionDenied(e) => match e.bookmark_policy() {
+                Some(policy) => MononokeError::BookmarkPolicyDenied {
+                    reason: e.to_string(),
+                    policy: policy.clone(),
+                },
+                None => MononokeError::AuthorizationError(e.to_string()),
+            },
             AuthorizationError::Error(e) => MononokeError::InternalError(InternalError::from(e)),
         }
     }
@@ -144,6 +160,7 @@ impl From<BlameError> for MononokeError {
     fn from(e: BlameError) -> Self {
         use BlameError::*;
         match e {
+            Rejected(BlameRejected::TooBig) => MononokeError::PathTooLarge(e.to_string()),
             NoSuchPath(_) | IsDirectory(_) | Rejected(_) => {
                 MononokeError::InvalidRequest(e.to_string())
             }
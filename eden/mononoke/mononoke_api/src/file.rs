@@ -164,7 +164,7 @@ impl FileContext {
         match ret {
             Ok(Some((stream, size))) => {
                 let size = size.try_into().map_err(|_| {
-                    MononokeError::from(format_err!("content too large: {:?}", self.fetch_key))
+                    MononokeError::PathTooLarge(format!("content too large: {:?}", self.fetch_key))
                 })?;
 
                 let bytes = stream
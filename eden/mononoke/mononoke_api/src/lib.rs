@@ -37,15 +37,19 @@ mod test;
 pub use context::CoreContext;
 pub use context::LoggingContainer;
 pub use context::SessionContainer;
+pub use mercurial_mutation::HgMutationEntry;
+pub use phases::Phase;
 
 pub use crate::changeset::ChangesetContext;
 pub use crate::changeset::ChangesetDiffItem;
 pub use crate::changeset::ChangesetFileOrdering;
 pub use crate::changeset::ChangesetHistoryOptions;
+pub use crate::changeset::ChangesetHistoryOrder;
 pub use crate::changeset::Generation;
 pub use crate::changeset_path::ChangesetPathContentContext;
 pub use crate::changeset_path::ChangesetPathHistoryOptions;
 pub use crate::changeset_path::PathEntry;
+pub use crate::changeset_path::FIRST_INTRODUCED_MAX_UNODES;
 pub use crate::changeset_path_diff::ChangesetPathDiffContext;
 pub use crate::changeset_path_diff::CopyInfo;
 pub use crate::changeset_path_diff::FileContentType;
@@ -70,6 +74,8 @@ pub use crate::repo::create_changeset::CreateInfo;
 pub use crate::repo::land_stack::PushrebaseOutcome;
 pub use crate::repo::BookmarkFreshness;
 pub use crate::repo::BookmarkInfo;
+pub use crate::repo::BookmarkStaleness;
+pub use crate::repo::DagFreshness;
 pub use crate::repo::Repo;
 pub use crate::repo::RepoContext;
 pub use crate::repo::StoreRequest;
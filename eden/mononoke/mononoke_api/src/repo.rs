@@ -18,6 +18,8 @@ use acl_regions::build_disabled_acl_regions;
 use acl_regions::AclRegions;
 use anyhow::anyhow;
 use anyhow::Error;
+use basename_suffix_skeleton_manifest::RootBasenameSuffixSkeletonManifest;
+use blame::RootBlameV2;
 use blobrepo::AsBlobRepo;
 use blobrepo::BlobRepo;
 use blobrepo_hg::BlobRepoHg;
@@ -63,8 +65,10 @@ use context::CoreContext;
 use cross_repo_sync::types::Target;
 use cross_repo_sync::CandidateSelectionHint;
 use cross_repo_sync::CommitSyncContext;
+use cross_repo_sync::CommitSyncOutcome;
 use cross_repo_sync::CommitSyncRepos;
 use cross_repo_sync::CommitSyncer;
+use deleted_manifest::RootDeletedManifestV2Id;
 use derived_data_manager::manager::derive::BatchDeriveOptions;
 use derived_data_manager::BonsaiDerivable;
 use derived_data_manager::DerivableType;
@@ -91,11 +95,14 @@ use futures::try_join;
 use futures::Future;
 use hook_manager::manager::HookManager;
 use hook_manager::manager::HookManagerArc;
+use hooks::HookOutcome;
+use hooks::PushAuthoredBy;
 use itertools::Itertools;
 use live_commit_sync_config::LiveCommitSyncConfig;
 use mercurial_derivation::MappedHgChangesetId;
 use mercurial_mutation::HgMutationStore;
 use mercurial_types::Globalrev;
+use metaconfig_types::CommitSyncConfigVersion;
 use metaconfig_types::HookManagerParams;
 use metaconfig_types::InfinitepushNamespace;
 use metaconfig_types::InfinitepushParams;
@@ -142,6 +149,7 @@ use repo_sparse_profiles::RepoSparseProfiles;
 use repo_sparse_profiles::RepoSparseProfilesArc;
 use revset::AncestorsNodeStream;
 use segmented_changelog::CloneData;
+pub use segmented_changelog::DagFreshness;
 use segmented_changelog::DisabledSegmentedChangelog;
 use segmented_changelog::Location;
 use segmented_changelog::SegmentedChangelog;
@@ -149,6 +157,7 @@ use segmented_changelog::SegmentedChangelogRef;
 use skeleton_manifest::RootSkeletonManifestId;
 use slog::debug;
 use slog::error;
+use slog::warn;
 use sql_construct::SqlConstruct;
 use sql_ext::facebook::MysqlOptions;
 use stats::prelude::*;
@@ -160,6 +169,8 @@ use test_repo_factory::TestRepoFactory;
 use tunables::tunables;
 use unbundle::PushRedirector;
 use unbundle::PushRedirectorArgs;
+use unodes::RootUnodeManifestId;
+pub use warm_bookmarks_cache::BookmarkStaleness;
 use warm_bookmarks_cache::BookmarksCache;
 use warm_bookmarks_cache::WarmBookmarksCacheBuilder;
 use wireproto_handler::PushRedirectorBase;
@@ -170,6 +181,7 @@ use crate::changeset::ChangesetContext;
 use crate::errors::MononokeError;
 use crate::file::FileContext;
 use crate::file::FileId;
+use crate::path::MononokePath;
 use crate::specifiers::ChangesetId;
 use crate::specifiers::ChangesetPrefixSpecifier;
 use crate::specifiers::ChangesetSpecifier;
@@ -755,6 +767,7 @@ pub struct BookmarkInfo {
     pub warm_changeset: ChangesetContext,
     pub fresh_changeset: ChangesetContext,
     pub last_update_timestamp: Timestamp,
+    pub warm_staleness: BookmarkStaleness,
 }
 
 /// A context object representing a query to a particular repo.
@@ -996,24 +1009,39 @@ impl RepoContext {
         bookmark: &BookmarkKey,
         freshness: BookmarkFreshness,
     ) -> Result<Option<ChangesetContext>, MononokeError> {
-        let mut cs_id = match freshness {
-            BookmarkFreshness::MaybeStale => {
-                self.warm_bookmarks_cache().get(&self.ctx, bookmark).await?
+        Ok(self
+            .resolve_bookmark_with_staleness(bookmark, freshness)
+            .await?
+            .map(|(changeset, _staleness)| changeset))
+    }
+
+    /// Resolve a bookmark to a changeset, along with how stale the value
+    /// is if it was served from the warm bookmarks cache.
+    pub async fn resolve_bookmark_with_staleness(
+        &self,
+        bookmark: &BookmarkKey,
+        freshness: BookmarkFreshness,
+    ) -> Result<Option<(ChangesetContext, Option<BookmarkStaleness>)>, MononokeError> {
+        if let BookmarkFreshness::MaybeStale = freshness {
+            let cache = self.warm_bookmarks_cache();
+            if let Some(cs_id) = cache.get(&self.ctx, bookmark).await? {
+                let staleness = cache.staleness(&self.ctx, bookmark).await?;
+                return Ok(Some((
+                    ChangesetContext::new(self.clone(), cs_id),
+                    Some(staleness),
+                )));
             }
-            BookmarkFreshness::MostRecent => None,
-        };
+        }
 
         // If the bookmark wasn't found in the warm bookmarks cache, it might
         // be a scratch bookmark, so always do the look-up.
-        if cs_id.is_none() {
-            cs_id = self
-                .blob_repo()
-                .bookmarks()
-                .get(self.ctx.clone(), bookmark)
-                .await?
-        }
+        let cs_id = self
+            .blob_repo()
+            .bookmarks()
+            .get(self.ctx.clone(), bookmark)
+            .await?;
 
-        Ok(cs_id.map(|cs_id| ChangesetContext::new(self.clone(), cs_id)))
+        Ok(cs_id.map(|cs_id| (ChangesetContext::new(self.clone(), cs_id), None)))
     }
 
     /// Resolve a changeset id by its prefix
@@ -1083,6 +1111,113 @@ impl RepoContext {
             .map_err(|err| err.into()))
     }
 
+    /// Returns `true` if `ancestor` is an ancestor of `descendant`.  A commit
+    /// is considered its own ancestor for the purpose of this call.
+    ///
+    /// Uses the segmented changelog if it knows about both commits, and the
+    /// commit graph otherwise or if the segmented changelog fails.
+    pub async fn is_ancestor(
+        &self,
+        ancestor: ChangesetId,
+        descendant: ChangesetId,
+    ) -> Result<bool, MononokeError> {
+        match self
+            .repo
+            .segmented_changelog()
+            .is_ancestor(&self.ctx, ancestor, descendant)
+            .await
+        {
+            Ok(Some(is_ancestor)) => return Ok(is_ancestor),
+            Ok(None) => {}
+            Err(e) => self.report_segmented_changelog_error("is_ancestor", e),
+        }
+        Ok(self
+            .repo()
+            .commit_graph()
+            .is_ancestor(&self.ctx, ancestor, descendant)
+            .await?)
+    }
+
+    /// Returns the lowest common ancestor of two commits.
+    ///
+    /// In case of ambiguity (can happen with multiple merges of the same
+    /// branches) returns the common ancestor with lowest id out of those with
+    /// highest generation number.  Uses the segmented changelog if it knows
+    /// about both commits and the answer is unambiguous, and the commit graph
+    /// otherwise or if the segmented changelog fails.
+    pub async fn common_base(
+        &self,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<ChangesetId>, MononokeError> {
+        let gcas = self
+            .repo
+            .segmented_changelog()
+            .greatest_common_ancestors(&self.ctx, a, b)
+            .await
+            .unwrap_or_else(|e| {
+                self.report_segmented_changelog_error("greatest_common_ancestors", e);
+                None
+            });
+        // The greatest common ancestors may have different generation
+        // numbers, so only a single one settles the answer.
+        match gcas.as_deref() {
+            Some([]) => return Ok(None),
+            Some([gca]) => return Ok(Some(*gca)),
+            _ => {}
+        }
+        let lca = self
+            .repo()
+            .commit_graph()
+            .common_base(&self.ctx, a, b)
+            .await?;
+        Ok(lca.first().copied())
+    }
+
+    /// Returns the commits that are both descendants of `start` and ancestors
+    /// of `end`, including `start` and `end` themselves, in topological order.
+    /// At most `limit` commits are returned, starting from `start`.
+    ///
+    /// Uses the segmented changelog if it knows about both commits, and the
+    /// commit graph otherwise or if the segmented changelog fails.
+    pub async fn commit_range(
+        &self,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Vec<ChangesetId>, MononokeError> {
+        match self
+            .repo
+            .segmented_changelog()
+            .range(&self.ctx, start, end, limit)
+            .await
+        {
+            Ok(Some(range)) => return Ok(range),
+            Ok(None) => {}
+            Err(e) => self.report_segmented_changelog_error("range", e),
+        }
+        Ok(self
+            .repo()
+            .commit_graph()
+            .range_stream(&self.ctx, start, end)
+            .await?
+            .take(limit)
+            .collect()
+            .await)
+    }
+
+    /// The segmented changelog is only an accelerator for these queries, so
+    /// its errors are logged and the commit graph answers instead.
+    fn report_segmented_changelog_error(&self, query: &str, e: Error) {
+        warn!(
+            self.ctx.logger(),
+            "Segmented changelog {} failed in repo {}, using the commit graph: {:?}",
+            query,
+            self.name(),
+            e
+        );
+    }
+
     /// Get Mercurial ID for multiple changesets
     ///
     /// This is a more efficient version of:
@@ -1186,6 +1321,22 @@ impl RepoContext {
         Ok(mapping)
     }
 
+    /// Get the highest Globalrev assigned in the repo, and the changeset it
+    /// was assigned to.
+    pub async fn latest_globalrev(
+        &self,
+    ) -> Result<Option<(Globalrev, ChangesetId)>, MononokeError> {
+        let mapping = self.blob_repo().bonsai_globalrev_mapping();
+        let globalrev = match mapping.get_max(&self.ctx).await? {
+            Some(globalrev) => globalrev,
+            None => return Ok(None),
+        };
+        let cs_id = mapping
+            .get_bonsai_from_globalrev(&self.ctx, globalrev)
+            .await?;
+        Ok(cs_id.map(|cs_id| (globalrev, cs_id)))
+    }
+
     /// Similar to many_changeset_hg_ids, but returning Svnrevs.
     pub async fn many_changeset_svnrev_ids(
         &self,
@@ -1202,6 +1353,22 @@ impl RepoContext {
         Ok(mapping)
     }
 
+    /// Get changeset ID from Svnrev for multiple changesets
+    pub async fn many_changeset_ids_from_svnrev(
+        &self,
+        changesets: Vec<Svnrev>,
+    ) -> Result<Vec<(Svnrev, ChangesetId)>, MononokeError> {
+        let mapping = self
+            .blob_repo()
+            .bonsai_svnrev_mapping()
+            .get(&self.ctx, changesets.into())
+            .await?
+            .into_iter()
+            .map(|entry| (entry.svnrev, entry.bcs_id))
+            .collect();
+        Ok(mapping)
+    }
+
     pub async fn many_changeset_parents(
         &self,
         changesets: Vec<ChangesetId>,
@@ -1227,8 +1394,9 @@ impl RepoContext {
         let bookmark = BookmarkKey::new(bookmark.as_ref())
             .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?;
 
-        let (maybe_warm_cs_id, maybe_log_entry) = try_join!(
+        let (maybe_warm_cs_id, warm_staleness, maybe_log_entry) = try_join!(
             self.warm_bookmarks_cache().get(&self.ctx, &bookmark),
+            self.warm_bookmarks_cache().staleness(&self.ctx, &bookmark),
             async {
                 let mut entries_stream = self
                     .repo
@@ -1269,6 +1437,7 @@ impl RepoContext {
             warm_changeset,
             fresh_changeset,
             last_update_timestamp,
+            warm_staleness,
         }))
     }
 
@@ -1500,6 +1669,28 @@ impl RepoContext {
             .await
     }
 
+    /// Check a commit message against the hooks for the bookmark that only
+    /// check commit messages, as if a commit with the message were being
+    /// landed to it, before the commit is created.
+    ///
+    /// Returns the names of the hooks that checked the message, and their
+    /// outcomes.
+    pub async fn validate_commit_message(
+        &self,
+        message: &str,
+        bookmark: impl AsRef<str>,
+    ) -> Result<(Vec<String>, Vec<HookOutcome>), MononokeError> {
+        Ok(self
+            .hook_manager()
+            .run_message_hooks_for_bookmark(
+                self.ctx(),
+                message,
+                &BookmarkKey::new(bookmark.as_ref())?,
+                PushAuthoredBy::User,
+            )
+            .await?)
+    }
+
     fn target_repo(&self) -> Target<Repo> {
         Target(self.repo().clone())
     }
@@ -1577,13 +1768,11 @@ impl RepoContext {
         }
     }
 
-    /// Get the equivalent changeset from another repo - it will sync it if needed
-    pub async fn xrepo_commit_lookup(
+    /// Build a syncer for commits from this repo to another repo.
+    fn commit_syncer_to(
         &self,
         other: &Self,
-        specifier: impl Into<ChangesetSpecifier>,
-        maybe_candidate_selection_hint_args: Option<CandidateSelectionHintArgs>,
-    ) -> Result<Option<ChangesetContext>, MononokeError> {
+    ) -> Result<CommitSyncer<ArcSyncedCommitMapping, Repo>, MononokeError> {
         let common_config = self
             .live_commit_sync_config()
             .get_common_config(self.blob_repo().repo_identity().id())
@@ -1594,25 +1783,35 @@ impl RepoContext {
                 ))
             })?;
 
-        let candidate_selection_hint: CandidateSelectionHint<Repo> = self
-            .build_candidate_selection_hint(maybe_candidate_selection_hint_args, other)
-            .await?;
-
         let commit_sync_repos =
             CommitSyncRepos::new(self.repo().clone(), other.repo().clone(), &common_config)?;
 
-        let specifier = specifier.into();
-        let changeset = self.resolve_specifier(specifier).await?.ok_or_else(|| {
-            MononokeError::InvalidRequest(format!("unknown commit specifier {}", specifier))
-        })?;
-
-        let commit_syncer = CommitSyncer::new(
+        Ok(CommitSyncer::new(
             &self.ctx,
             self.synced_commit_mapping().clone(),
             commit_sync_repos,
             self.live_commit_sync_config(),
             self.repo.x_repo_sync_lease().clone(),
-        );
+        ))
+    }
+
+    /// Get the equivalent changeset from another repo - it will sync it if needed
+    pub async fn xrepo_commit_lookup(
+        &self,
+        other: &Self,
+        specifier: impl Into<ChangesetSpecifier>,
+        maybe_candidate_selection_hint_args: Option<CandidateSelectionHintArgs>,
+    ) -> Result<Option<ChangesetContext>, MononokeError> {
+        let commit_syncer = self.commit_syncer_to(other)?;
+
+        let candidate_selection_hint: CandidateSelectionHint<Repo> = self
+            .build_candidate_selection_hint(maybe_candidate_selection_hint_args, other)
+            .await?;
+
+        let specifier = specifier.into();
+        let changeset = self.resolve_specifier(specifier).await?.ok_or_else(|| {
+            MononokeError::InvalidRequest(format!("unknown commit specifier {}", specifier))
+        })?;
 
         let maybe_cs_id = commit_syncer
             .sync_commit(
@@ -1626,6 +1825,54 @@ impl RepoContext {
         Ok(maybe_cs_id.map(|cs_id| ChangesetContext::new(other.clone(), cs_id)))
     }
 
+    /// Map paths in a commit to the equivalent paths in another repo, using
+    /// the commit sync config version the commit was synced with.
+    ///
+    /// Returns the version used and the mapped paths.  Paths that have no
+    /// equivalent in the other repo are omitted.
+    pub async fn xrepo_path_mapping(
+        &self,
+        other: &Self,
+        specifier: impl Into<ChangesetSpecifier>,
+        paths: impl IntoIterator<Item = MononokePath>,
+    ) -> Result<(CommitSyncConfigVersion, HashMap<MononokePath, MononokePath>), MononokeError> {
+        let commit_syncer = self.commit_syncer_to(other)?;
+
+        let specifier = specifier.into();
+        let changeset = self.resolve_specifier(specifier).await?.ok_or_else(|| {
+            MononokeError::InvalidRequest(format!("unknown commit specifier {}", specifier))
+        })?;
+
+        let version = match commit_syncer
+            .get_commit_sync_outcome(&self.ctx, changeset)
+            .await?
+        {
+            Some(CommitSyncOutcome::NotSyncCandidate(version))
+            | Some(CommitSyncOutcome::RewrittenAs(_, version))
+            | Some(CommitSyncOutcome::EquivalentWorkingCopyAncestor(_, version)) => version,
+            None => {
+                return Err(MononokeError::InvalidRequest(format!(
+                    "commit {} has not been synced from {} to {}",
+                    changeset,
+                    self.name(),
+                    other.name()
+                )));
+            }
+        };
+
+        let mover = commit_syncer.get_mover_by_version(&version).await?;
+        let mut mapped_paths = HashMap::new();
+        for path in paths {
+            let mpath = path.as_mpath().ok_or_else(|| {
+                MononokeError::InvalidRequest(String::from("the root path can't be mapped"))
+            })?;
+            if let Some(mapped) = mover(mpath)? {
+                mapped_paths.insert(path, MononokePath::new(Some(mapped)));
+            }
+        }
+        Ok((version, mapped_paths))
+    }
+
     /// Start a write to the repo.
     pub fn start_write(&self) -> Result<(), MononokeError> {
         if self.authz.is_service() {
@@ -1672,8 +1919,9 @@ impl RepoContext {
         &self,
         location: Location<ChangesetId>,
         count: u64,
+        freshness: DagFreshness,
     ) -> Result<Vec<ChangesetId>, MononokeError> {
-        let segmented_changelog = self.repo.segmented_changelog();
+        let segmented_changelog = self.segmented_changelog_with_freshness(freshness).await?;
         let ancestor = segmented_changelog
             .location_to_many_changeset_ids(&self.ctx, location, count)
             .await
@@ -1688,9 +1936,10 @@ impl RepoContext {
         &self,
         master_heads: Vec<ChangesetId>,
         cs_ids: Vec<ChangesetId>,
+        freshness: DagFreshness,
     ) -> Result<HashMap<ChangesetId, Result<Location<ChangesetId>, MononokeError>>, MononokeError>
     {
-        let segmented_changelog = self.repo.segmented_changelog();
+        let segmented_changelog = self.segmented_changelog_with_freshness(freshness).await?;
         let result = segmented_changelog
             .many_changeset_ids_to_locations(&self.ctx, master_heads, cs_ids)
             .await
@@ -1705,8 +1954,9 @@ impl RepoContext {
 
     pub async fn segmented_changelog_clone_data(
         &self,
+        freshness: DagFreshness,
     ) -> Result<(CloneData<ChangesetId>, HashMap<ChangesetId, HgChangesetId>), MononokeError> {
-        let segmented_changelog = self.repo.segmented_changelog();
+        let segmented_changelog = self.segmented_changelog_with_freshness(freshness).await?;
         let clone_data = segmented_changelog
             .clone_data(&self.ctx)
             .await
@@ -1714,6 +1964,30 @@ impl RepoContext {
         Ok(clone_data)
     }
 
+    /// Bring the segmented changelog up to date.  Callers making a batch of
+    /// queries that must be fresh can do this once, then make the queries
+    /// with `DagFreshness::MaybeStale`.
+    pub async fn segmented_changelog_ensure_fresh(&self) -> Result<(), MononokeError> {
+        self.repo
+            .segmented_changelog()
+            .ensure_fresh(&self.ctx)
+            .await
+            .map_err(MononokeError::from)
+    }
+
+    /// The segmented changelog, brought up to date first if the caller needs
+    /// it to be fresh.  Otherwise it may lag behind, e.g. until the next
+    /// periodic reload.
+    async fn segmented_changelog_with_freshness(
+        &self,
+        freshness: DagFreshness,
+    ) -> Result<&dyn SegmentedChangelog, MononokeError> {
+        if freshness == DagFreshness::MustBeFresh {
+            self.segmented_changelog_ensure_fresh().await?;
+        }
+        Ok(self.repo.segmented_changelog())
+    }
+
     pub async fn segmented_changelog_disabled(&self) -> Result<bool, MononokeError> {
         let segmented_changelog = self.repo.segmented_changelog();
         let disabled = segmented_changelog
@@ -1777,6 +2051,102 @@ impl RepoContext {
         }
         Ok(())
     }
+
+    /// Count the ancestors of `csid` (including `csid` itself) that do not
+    /// have the given type of derived data, stopping once `limit` is reached.
+    ///
+    /// Returns `None` if the type is not enabled for this repo.
+    pub async fn count_underived(
+        &self,
+        derivable_type: DerivableType,
+        csid: ChangesetId,
+        limit: u64,
+    ) -> Result<Option<u64>, MononokeError> {
+        match derivable_type {
+            DerivableType::ChangesetInfo => {
+                self.count_underived_impl::<ChangesetInfo>(csid, limit)
+                    .await
+            }
+            DerivableType::Fsnodes => self.count_underived_impl::<RootFsnodeId>(csid, limit).await,
+            DerivableType::HgChangesets => {
+                self.count_underived_impl::<MappedHgChangesetId>(csid, limit)
+                    .await
+            }
+            DerivableType::SkeletonManifests => {
+                self.count_underived_impl::<RootSkeletonManifestId>(csid, limit)
+                    .await
+            }
+            DerivableType::Unodes => {
+                self.count_underived_impl::<RootUnodeManifestId>(csid, limit)
+                    .await
+            }
+            _ => Err(MononokeError::InvalidRequest(format!(
+                "Unsupported derived data type for counting underived commits: {}",
+                derivable_type
+            ))),
+        }
+    }
+
+    /// Whether the given type of derived data has already been derived for
+    /// `csid`.
+    ///
+    /// Returns `None` if the type is not enabled for this repo.
+    pub async fn is_derived(
+        &self,
+        derivable_type: DerivableType,
+        csid: ChangesetId,
+    ) -> Result<Option<bool>, MononokeError> {
+        match derivable_type {
+            DerivableType::BlameV2 => self.is_derived_impl::<RootBlameV2>(csid).await,
+            DerivableType::Bssm => {
+                self.is_derived_impl::<RootBasenameSuffixSkeletonManifest>(csid)
+                    .await
+            }
+            DerivableType::ChangesetInfo => self.is_derived_impl::<ChangesetInfo>(csid).await,
+            DerivableType::DeletedManifests => {
+                self.is_derived_impl::<RootDeletedManifestV2Id>(csid).await
+            }
+            DerivableType::Fsnodes => self.is_derived_impl::<RootFsnodeId>(csid).await,
+            DerivableType::HgChangesets => self.is_derived_impl::<MappedHgChangesetId>(csid).await,
+            DerivableType::SkeletonManifests => {
+                self.is_derived_impl::<RootSkeletonManifestId>(csid).await
+            }
+            DerivableType::Unodes => self.is_derived_impl::<RootUnodeManifestId>(csid).await,
+            _ => Err(MononokeError::InvalidRequest(format!(
+                "Unsupported derived data type for checking derivation: {}",
+                derivable_type
+            ))),
+        }
+    }
+
+    async fn is_derived_impl<Derivable: BonsaiDerivable>(
+        &self,
+        csid: ChangesetId,
+    ) -> Result<Option<bool>, MononokeError> {
+        let manager = self.repo.repo_derived_data().manager();
+        if !manager.config().types.contains(Derivable::NAME) {
+            return Ok(None);
+        }
+        let derived = manager
+            .fetch_derived::<Derivable>(self.ctx(), csid, None)
+            .await?;
+        Ok(Some(derived.is_some()))
+    }
+
+    async fn count_underived_impl<Derivable: BonsaiDerivable>(
+        &self,
+        csid: ChangesetId,
+        limit: u64,
+    ) -> Result<Option<u64>, MononokeError> {
+        let manager = self.repo.repo_derived_data().manager();
+        if !manager.config().types.contains(Derivable::NAME) {
+            return Ok(None);
+        }
+        let count = manager
+            .count_underived::<Derivable>(self.ctx(), csid, Some(limit), None)
+            .await?;
+        Ok(Some(count))
+    }
 }
 
 #[cfg(test)]
@@ -18,6 +18,22 @@ use tunables::tunables;
 use crate::errors::MononokeError;
 use crate::repo::RepoContext;
 
+fn make_create_op<'a>(
+    bookmark: &'a BookmarkKey,
+    target: ChangesetId,
+    pushvars: Option<&'a HashMap<String, Bytes>>,
+) -> CreateBookmarkOp<'a> {
+    let mut op = CreateBookmarkOp::new(bookmark, target, BookmarkUpdateReason::ApiRequest)
+        .with_pushvars(pushvars);
+    if !tunables()
+        .disable_commit_scribe_logging_scs()
+        .unwrap_or_default()
+    {
+        op = op.log_new_public_commits_to_scribe();
+    }
+    op
+}
+
 impl RepoContext {
     /// Create a bookmark.
     pub async fn create_bookmark(
@@ -28,21 +44,6 @@ impl RepoContext {
     ) -> Result<(), MononokeError> {
         self.start_write()?;
 
-        fn make_create_op<'a>(
-            bookmark: &'a BookmarkKey,
-            target: ChangesetId,
-            pushvars: Option<&'a HashMap<String, Bytes>>,
-        ) -> CreateBookmarkOp<'a> {
-            let mut op = CreateBookmarkOp::new(bookmark, target, BookmarkUpdateReason::ApiRequest)
-                .with_pushvars(pushvars);
-            if !tunables()
-                .disable_commit_scribe_logging_scs()
-                .unwrap_or_default()
-            {
-                op = op.log_new_public_commits_to_scribe();
-            }
-            op
-        }
         if let Some(redirector) = self.push_redirector.as_ref() {
             let large_bookmark = redirector.small_to_large_bookmark(bookmark).await?;
             if &large_bookmark == bookmark {
@@ -77,4 +78,34 @@ impl RepoContext {
         }
         Ok(())
     }
+
+    /// Create a public bookmark for a new branch, and register the users
+    /// allowed to move it in the same transaction.
+    pub async fn create_branch(
+        &self,
+        bookmark: &BookmarkKey,
+        target: ChangesetId,
+        allowed_pushers: &[String],
+        pushvars: Option<&HashMap<String, Bytes>>,
+    ) -> Result<(), MononokeError> {
+        self.start_write()?;
+
+        if self.push_redirector.is_some() {
+            return Err(MononokeError::InvalidRequest(format!(
+                "Cannot create branch '{}' in a push-redirected repo",
+                bookmark.name()
+            )));
+        }
+        make_create_op(bookmark, target, pushvars)
+            .only_if_public()
+            .with_allowed_pushers(allowed_pushers)
+            .run(
+                self.ctx(),
+                self.authorization_context(),
+                self.inner_repo(),
+                self.hook_manager().as_ref(),
+            )
+            .await?;
+        Ok(())
+    }
 }
@@ -434,7 +434,7 @@ async fn verify_prefix_files_deleted(
                 && path_changes.get(prefix_path.as_mpath().into())
                     != Some(&CreateChangeType::Deletion)
             {
-                return Err(MononokeError::InvalidRequest(format!(
+                return Err(MononokeError::NotADirectory(format!(
                     concat!(
                         "Creating files inside '{}' requires deleting the file ",
                         "added earlier in the stack at that path"
@@ -456,7 +456,7 @@ async fn verify_prefix_files_deleted(
                         && path_changes.get(prefix_path.path().as_mpath().into())
                             != Some(&CreateChangeType::Deletion)
                     {
-                        Err(MononokeError::InvalidRequest(format!(
+                        Err(MononokeError::NotADirectory(format!(
                             "Creating files inside '{}' requires deleting the file at that path",
                             prefix_path.path()
                         )))
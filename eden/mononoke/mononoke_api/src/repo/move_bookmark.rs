@@ -31,6 +31,51 @@ impl RepoContext {
         old_target: Option<ChangesetId>,
         allow_non_fast_forward: bool,
         pushvars: Option<&HashMap<String, Bytes>>,
+    ) -> Result<(), MononokeError> {
+        self.move_bookmark_impl(
+            bookmark,
+            target,
+            old_target,
+            allow_non_fast_forward,
+            None,
+            pushvars,
+        )
+        .await
+    }
+
+    /// Move a bookmark forward to a commit that includes `must_include`.
+    ///
+    /// The new target must be a descendant of both the bookmark's current
+    /// target and `must_include`.  The bookmark is only moved if it still
+    /// points at the target that was checked, so a concurrent move causes
+    /// this to fail rather than be rolled back.
+    pub async fn move_bookmark_including(
+        &self,
+        bookmark: &BookmarkKey,
+        target: ChangesetId,
+        old_target: Option<ChangesetId>,
+        must_include: ChangesetId,
+        pushvars: Option<&HashMap<String, Bytes>>,
+    ) -> Result<(), MononokeError> {
+        self.move_bookmark_impl(
+            bookmark,
+            target,
+            old_target,
+            false,
+            Some(must_include),
+            pushvars,
+        )
+        .await
+    }
+
+    async fn move_bookmark_impl(
+        &self,
+        bookmark: &BookmarkKey,
+        target: ChangesetId,
+        old_target: Option<ChangesetId>,
+        allow_non_fast_forward: bool,
+        must_include: Option<ChangesetId>,
+        pushvars: Option<&HashMap<String, Bytes>>,
     ) -> Result<(), MononokeError> {
         self.start_write()?;
 
@@ -54,6 +99,7 @@ impl RepoContext {
             target: ChangesetId,
             old_target: ChangesetId,
             allow_non_fast_forward: bool,
+            must_include: Option<ChangesetId>,
             pushvars: Option<&'a HashMap<String, Bytes>>,
         ) -> UpdateBookmarkOp<'a> {
             let mut op = UpdateBookmarkOp::new(
@@ -70,6 +116,9 @@ impl RepoContext {
                 BookmarkUpdateReason::ApiRequest,
             )
             .with_pushvars(pushvars);
+            if let Some(must_include) = must_include {
+                op = op.with_required_ancestor(must_include);
+            }
             if !tunables()
                 .disable_commit_scribe_logging_scs()
                 .unwrap_or_default()
@@ -87,15 +136,25 @@ impl RepoContext {
                 )));
             }
             let ctx = self.ctx();
-            let (target, old_target) = futures::try_join!(
+            let (target, old_target, must_include) = futures::try_join!(
                 redirector.get_small_to_large_commit_equivalent(ctx, target),
                 redirector.get_small_to_large_commit_equivalent(ctx, old_target),
+                async {
+                    match must_include {
+                        Some(cs_id) => redirector
+                            .get_small_to_large_commit_equivalent(ctx, cs_id)
+                            .await
+                            .map(Some),
+                        None => Ok(None),
+                    }
+                },
             )?;
             make_move_op(
                 &large_bookmark,
                 target,
                 old_target,
                 allow_non_fast_forward,
+                must_include,
                 pushvars,
             )
             .run(
@@ -113,6 +172,7 @@ impl RepoContext {
                 target,
                 old_target,
                 allow_non_fast_forward,
+                must_include,
                 pushvars,
             )
             .run(
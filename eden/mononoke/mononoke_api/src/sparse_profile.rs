@@ -246,20 +246,28 @@ pub(crate) async fn fetch(path: String, changeset: &ChangesetContext) -> Result<
         .map(|b| Some(b.to_vec()))
 }
 
+async fn create_profile_matcher(
+    changeset: &ChangesetContext,
+    path: &NonRootMPath,
+) -> Result<sparse::Matcher> {
+    let content = format!("%include {path}");
+    let dummy_source = "repo_root".to_string();
+    let profile = sparse::Root::from_bytes(content.as_bytes(), dummy_source)
+        .with_context(|| format!("while constructing Profile for source {path}"))?;
+    let matcher = profile
+        .matcher(|path| fetch(path, changeset))
+        .await
+        .with_context(|| format!("While constructing matcher for source {path}"))?;
+    Ok(matcher)
+}
+
 async fn create_matchers(
     changeset: &ChangesetContext,
     paths: Vec<NonRootMPath>,
 ) -> Result<HashMap<String, Arc<dyn Matcher + Send + Sync>>> {
     stream::iter(paths)
         .map(|path| async move {
-            let content = format!("%include {path}");
-            let dummy_source = "repo_root".to_string();
-            let profile = sparse::Root::from_bytes(content.as_bytes(), dummy_source)
-                .with_context(|| format!("while constructing Profile for source {path}"))?;
-            let matcher = profile
-                .matcher(|path| fetch(path, changeset))
-                .await
-                .with_context(|| format!("While constructing matcher for source {path}"))?;
+            let matcher = create_profile_matcher(changeset, &path).await?;
             anyhow::Ok((
                 path.to_string(),
                 Arc::new(matcher) as Arc<dyn Matcher + Send + Sync>,
@@ -270,13 +278,93 @@ async fn create_matchers(
         .await
 }
 
+/// A sparse profile resolved at a commit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseProfileFilesSummary {
+    /// The matcher rules the profile expands to once its `%include`s have
+    /// been followed, each annotated with the profiles it came from.
+    pub rules: Vec<String>,
+    /// Number of files in the commit matched by the profile.
+    pub file_count: u64,
+    /// Total size of the files in the commit matched by the profile.
+    pub total_size: u64,
+}
+
+/// Resolve each of the given sparse profiles at the changeset, and summarize
+/// the files they match.
+pub async fn get_profile_files_summary(
+    ctx: &CoreContext,
+    changeset: &ChangesetContext,
+    paths: Vec<NonRootMPath>,
+) -> Result<HashMap<String, SparseProfileFilesSummary>, MononokeError> {
+    let resolved: Vec<_> = stream::iter(paths)
+        .map(|path| async move {
+            let matcher = create_profile_matcher(changeset, &path).await?;
+            let (tree_matchers, rules): (Vec<_>, Vec<_>) =
+                matcher.into_matchers().into_iter().unzip();
+            let matcher = pathmatcher::UnionMatcher::new(
+                tree_matchers
+                    .into_iter()
+                    .map(|m| Arc::new(m) as Arc<dyn Matcher + Send + Sync>)
+                    .collect(),
+            );
+            anyhow::Ok((
+                path.to_string(),
+                rules.concat(),
+                Arc::new(matcher) as Arc<dyn Matcher + Send + Sync>,
+            ))
+        })
+        .buffer_unordered(100)
+        .try_collect()
+        .await?;
+    let mut summaries = HashMap::new();
+    let mut matchers = HashMap::new();
+    for (source, rules, matcher) in resolved {
+        summaries.insert(
+            source.clone(),
+            SparseProfileFilesSummary {
+                rules,
+                ..Default::default()
+            },
+        );
+        matchers.insert(source, matcher);
+    }
+    for (source, totals) in calculate_totals(ctx, changeset, matchers).await? {
+        if let Some(summary) = summaries.get_mut(&source) {
+            summary.file_count = totals.count;
+            summary.total_size = totals.size;
+        }
+    }
+    Ok(summaries)
+}
+
 type Out = HashMap<String, u64>;
 
+#[derive(Clone, Copy, Default)]
+struct FileTotals {
+    count: u64,
+    size: u64,
+}
+
+type Totals = HashMap<String, FileTotals>;
+
 async fn calculate_size<'a>(
     ctx: &'a CoreContext,
     changeset: &'a ChangesetContext,
     matchers: HashMap<String, Arc<dyn Matcher + Send + Sync>>,
 ) -> Result<Out, MononokeError> {
+    Ok(calculate_totals(ctx, changeset, matchers)
+        .await?
+        .into_iter()
+        .map(|(source, totals)| (source, totals.size))
+        .collect())
+}
+
+async fn calculate_totals<'a>(
+    ctx: &'a CoreContext,
+    changeset: &'a ChangesetContext,
+    matchers: HashMap<String, Arc<dyn Matcher + Send + Sync>>,
+) -> Result<Totals, MononokeError> {
     let root_fsnode_id = changeset.root_fsnode_id().await?;
     let root: Option<NonRootMPath> = None;
     bounded_traversal::bounded_traversal(
@@ -286,7 +374,7 @@ async fn calculate_size<'a>(
             cloned!(ctx, matchers);
             let blobstore = changeset.repo().blob_repo().repo_blobstore();
             async move {
-                let mut sizes: Out = HashMap::new();
+                let mut totals: Totals = HashMap::new();
                 let mut next: HashMap<_, HashMap<_, _>> = HashMap::new();
                 let fsnode = fsnode_id.load(&ctx, blobstore).await?;
                 for (base_name, entry) in fsnode.list() {
@@ -297,7 +385,9 @@ async fn calculate_size<'a>(
                         FsnodeEntry::File(leaf) => {
                             for (source, matcher) in &matchers {
                                 if matcher.matches_file(repo_path)? {
-                                    *sizes.entry(source.to_string()).or_insert(0) += leaf.size();
+                                    let totals = totals.entry(source.to_string()).or_default();
+                                    totals.count += 1;
+                                    totals.size += leaf.size();
                                 }
                             }
                         }
@@ -305,8 +395,9 @@ async fn calculate_size<'a>(
                             for (source, matcher) in &matchers {
                                 match matcher.matches_directory(repo_path)? {
                                     DirectoryMatch::Everything => {
-                                        *sizes.entry(source.to_string()).or_insert(0) +=
-                                            tree.summary().descendant_files_total_size;
+                                        let totals = totals.entry(source.to_string()).or_default();
+                                        totals.count += tree.summary().descendant_files_count;
+                                        totals.size += tree.summary().descendant_files_total_size;
                                     }
                                     DirectoryMatch::ShouldTraverse => {
                                         next.entry((Some(path.clone()), *tree.id()))
@@ -321,17 +412,17 @@ async fn calculate_size<'a>(
                 }
 
                 anyhow::Ok((
-                    sizes,
+                    totals,
                     next.into_iter()
                         .map(|((path, fsnode_id), matchers)| (path, fsnode_id, matchers)),
                 ))
             }
             .boxed()
         },
-        |sizes, children| {
+        |totals, children| {
             async move {
                 let t = children.fold(HashMap::new(), fold_maps);
-                Ok(fold_maps(t, sizes))
+                Ok(fold_maps(t, totals))
             }
             .boxed()
         },
@@ -340,9 +431,11 @@ async fn calculate_size<'a>(
     .map_err(MononokeError::from)
 }
 
-fn fold_maps(mut a: Out, b: Out) -> Out {
-    for (source, size) in b {
-        *a.entry(source).or_insert(0) += size;
+fn fold_maps(mut a: Totals, b: Totals) -> Totals {
+    for (source, totals) in b {
+        let entry = a.entry(source).or_default();
+        entry.count += totals.count;
+        entry.size += totals.size;
     }
     a
 }
@@ -23,6 +23,7 @@ use crate::ChangesetDiffItem;
 use crate::ChangesetFileOrdering;
 use crate::ChangesetPathDiffContext;
 use crate::CoreContext;
+use crate::FileType;
 use crate::HgChangesetId;
 use crate::Mononoke;
 use crate::MononokePath;
@@ -76,6 +77,77 @@ async fn test_diff_with_moves(fb: FacebookInit) -> Result<(), Error> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_diff_copy_similarity_and_mode_only_changes(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let repo: Repo = test_repo_factory::build_empty(fb).await?;
+    let root = CreateCommitContext::new_root(&ctx, &repo)
+        .add_file("file_to_move", "one\ntwo\nthree\nfour\n")
+        .add_file("file_to_copy", "copied\n")
+        .add_file("script", "echo hello\n")
+        .commit()
+        .await?;
+
+    let commit = CreateCommitContext::new(&ctx, &repo, vec![root])
+        .add_file_with_copy_info(
+            "file_moved",
+            "one\ntwo\nthree\nfive\n",
+            (root, "file_to_move"),
+        )
+        .delete_file("file_to_move")
+        .add_file_with_copy_info("file_copied", "copied\n", (root, "file_to_copy"))
+        .add_file_with_type("script", "echo hello\n", FileType::Executable)
+        .commit()
+        .await?;
+
+    let mononoke = Mononoke::new_test(vec![("test".to_string(), repo)]).await?;
+
+    let repo = mononoke
+        .repo(ctx.clone(), "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let commit_ctx = repo
+        .changeset(commit)
+        .await?
+        .ok_or_else(|| anyhow!("commit not found"))?;
+    let diff = commit_ctx
+        .diff(
+            &repo.changeset(root).await?.context("commit not found")?,
+            true, /* include_copies_renames */
+            None, /* path_restrictions */
+            btreeset! {ChangesetDiffItem::FILES},
+            ChangesetFileOrdering::Ordered { after: None },
+            None,
+        )
+        .await?;
+
+    assert_eq!(diff.len(), 3);
+    assert!(matches!(diff[0], ChangesetPathDiffContext::Copied(..)));
+    assert_eq!(diff[0].copy_similarity().await?, Some(100));
+    assert!(!diff[0].is_mode_only_change().await?);
+    assert!(matches!(diff[1], ChangesetPathDiffContext::Moved(..)));
+    // "one\n", "two\n" and "three\n" are in common: 14 of 19 bytes.
+    assert_eq!(diff[1].copy_similarity().await?, Some(73));
+    assert!(matches!(diff[2], ChangesetPathDiffContext::Changed(..)));
+    assert_eq!(diff[2].copy_similarity().await?, None);
+    assert!(diff[2].is_mode_only_change().await?);
+
+    let uncopied = diff[1].clone().into_uncopied();
+    assert_eq!(uncopied.len(), 2);
+    match (&uncopied[0], &uncopied[1]) {
+        (ChangesetPathDiffContext::Added(to), ChangesetPathDiffContext::Removed(from)) => {
+            assert_eq!(to.path(), &MononokePath::try_from("file_moved")?);
+            assert_eq!(from.path(), &MononokePath::try_from("file_to_move")?);
+        }
+        _ => {
+            panic!("unexpected diff");
+        }
+    }
+    Ok(())
+}
+
 #[fbinit::test]
 async fn test_diff_with_multiple_copies(fb: FacebookInit) -> Result<(), Error> {
     let ctx = CoreContext::test_mock(fb);
@@ -617,7 +689,7 @@ async fn test_ordered_root_diff(fb: FacebookInit) -> Result<(), Error> {
         .await?;
 
     let files_dirs_list = [
-        "!", "0", "1", "10", "2", "a", "a/a", "a/a/a", "a/a/a/a", "a/a/a/b", "d", "d/e", "d/g",
+        "", "!", "0", "1", "10", "2", "a", "a/a", "a/a/a", "a/a/a/a", "a/a/a/b", "d", "d/e", "d/g",
         "i", "j", "j/k", "j.txt", "p", "r", "r/s", "r/s/t", "r/s/t/u", "r/v", "r/w", "r/w/x",
         "r/y", "z", "é",
     ];
@@ -632,7 +704,7 @@ async fn test_ordered_root_diff(fb: FacebookInit) -> Result<(), Error> {
         )
         .await?;
 
-    let dirs_list = ["a", "a/a", "a/a/a", "d", "j", "r", "r/s", "r/s/t", "r/w"];
+    let dirs_list = ["", "a", "a/a", "a/a/a", "d", "j", "r", "r/s", "r/s/t", "r/w"];
     check_diff_paths(&diff, &dirs_list);
 
     // a non-root commit2
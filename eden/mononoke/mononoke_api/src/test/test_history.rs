@@ -21,6 +21,7 @@ use mononoke_types::DateTime;
 use tests_utils::CreateCommitContext;
 
 use crate::ChangesetHistoryOptions;
+use crate::ChangesetHistoryOrder;
 use crate::ChangesetId;
 use crate::ChangesetPathHistoryOptions;
 use crate::RepoContext;
@@ -368,6 +369,44 @@ async fn commit_path_history(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn commit_path_first_introduced(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let (repo, changesets) = init_repo(&ctx).await?;
+
+    let cs = repo
+        .changeset(changesets["c2"])
+        .await?
+        .expect("changeset exists");
+
+    for (path, expected) in [
+        ("a", "a1"),
+        ("dir1/a", "a2"),
+        ("dir2", "b1"),
+        // "dir3" appears on both branches, the older of the two wins.
+        ("dir3", "b2"),
+        ("c", "c1"),
+    ] {
+        let first_introduced = cs
+            .path_with_history(path)
+            .await?
+            .first_introduced()
+            .await?
+            .map(|cs| cs.id());
+        assert_eq!(
+            first_introduced,
+            Some(changesets[expected]),
+            "path {}",
+            path
+        );
+    }
+
+    let missing = cs.path_with_history("does/not/exist").await?;
+    assert!(missing.first_introduced().await?.is_none());
+
+    Ok(())
+}
+
 async fn assert_history(
     ctx: &CoreContext,
     commit_graph: &CommitGraph,
@@ -589,3 +628,90 @@ async fn commit_history(fb: FacebookInit) -> Result<()> {
 
     Ok(())
 }
+
+#[fbinit::test]
+async fn commit_history_date_order(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let (repo, changesets) = init_repo(&ctx).await?;
+
+    let cs = repo
+        .changeset(changesets["c2"])
+        .await?
+        .expect("changeset exists");
+
+    // Ordering by author date returns the most recent commits first.
+    let history: Vec<_> = cs
+        .history(ChangesetHistoryOptions {
+            order: ChangesetHistoryOrder::AuthorDate,
+            ..Default::default()
+        })
+        .await?
+        .and_then(|cs| async move { Ok(cs.id()) })
+        .try_collect()
+        .await?;
+    assert_eq!(
+        history,
+        vec![
+            changesets["c2"],
+            changesets["m2"],
+            changesets["e3"],
+            changesets["e2"],
+            changesets["b3"],
+            changesets["a4"],
+            changesets["c1"],
+            changesets["e1"],
+            changesets["m1"],
+            changesets["a3"],
+            changesets["b2"],
+            changesets["a2"],
+            changesets["b1"],
+            changesets["a1"],
+        ]
+    );
+
+    // Commits without a committer date use the author date when ordering
+    // reverse-chronologically, and filters still apply.
+    let history: Vec<_> = cs
+        .history(ChangesetHistoryOptions {
+            until_timestamp: Some(2500),
+            exclude_changeset_and_ancestors: Some(changesets["b2"]),
+            order: ChangesetHistoryOrder::ReverseChronological,
+            ..Default::default()
+        })
+        .await?
+        .and_then(|cs| async move { Ok(cs.id()) })
+        .try_collect()
+        .await?;
+    assert_eq!(
+        history,
+        vec![
+            changesets["c2"],
+            changesets["m2"],
+            changesets["e3"],
+            changesets["e2"],
+            changesets["b3"],
+            changesets["a4"],
+            changesets["c1"],
+            changesets["e1"],
+            changesets["m1"],
+            changesets["a3"],
+        ]
+    );
+
+    // Topological order is deterministic, so repeated queries agree.
+    let first: Vec<_> = cs
+        .history(Default::default())
+        .await?
+        .and_then(|cs| async move { Ok(cs.id()) })
+        .try_collect()
+        .await?;
+    let second: Vec<_> = cs
+        .history(Default::default())
+        .await?
+        .and_then(|cs| async move { Ok(cs.id()) })
+        .try_collect()
+        .await?;
+    assert_eq!(first, second);
+
+    Ok(())
+}
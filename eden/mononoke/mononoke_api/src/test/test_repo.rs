@@ -6,6 +6,7 @@
  */
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -66,6 +67,7 @@ use crate::HgChangesetId;
 use crate::HgChangesetIdPrefix;
 use crate::Mononoke;
 use crate::MononokePath;
+use crate::Phase;
 use crate::TreeEntry;
 use crate::TreeId;
 
@@ -171,6 +173,39 @@ async fn commit_info_by_bookmark(fb: FacebookInit) -> Result<(), Error> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn commit_phase(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        Linear::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx.clone(), "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let master = repo
+        .resolve_bookmark(&BookmarkKey::new("master")?, BookmarkFreshness::MostRecent)
+        .await?
+        .expect("bookmark exists");
+    assert_eq!(master.phase().await?, Phase::Public);
+
+    let draft_id = CreateCommitContext::new(&ctx, repo.blob_repo(), vec![master.id()])
+        .add_file("draft", "content")
+        .commit()
+        .await?;
+    let draft = repo
+        .changeset(ChangesetSpecifier::Bonsai(draft_id))
+        .await?
+        .expect("changeset exists");
+    assert_eq!(draft.phase().await?, Phase::Draft);
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn commit_hg_changeset_ids(fb: FacebookInit) -> Result<(), Error> {
     let ctx = CoreContext::test_mock(fb);
@@ -270,6 +305,167 @@ async fn commit_is_ancestor_of(fb: FacebookInit) -> Result<(), Error> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn commit_common_base_and_range(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        BranchUneven::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx, "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let mut ids = Vec::new();
+    for hg_hash in [
+        "5d43888a3c972fe68c224f93d41b30e9f888df7c", // 0: branch 1 near top
+        "d7542c9db7f4c77dab4b315edd328edf1514952f", // 1: branch 1 near bottom
+        "1d8a907f7b4bf50c6a09c16361e2205047ecc5e5", // 2: branch 2
+        "15c40d0abc36d47fb51c8eaec51ac7aad31f669c", // 3: base
+    ] {
+        let changeset = repo
+            .changeset(HgChangesetId::from_str(hg_hash)?)
+            .await?
+            .expect("changeset exists");
+        ids.push(changeset.id());
+    }
+
+    assert_eq!(repo.common_base(ids[0], ids[2]).await?, Some(ids[3]));
+    assert_eq!(repo.common_base(ids[0], ids[1]).await?, Some(ids[1]));
+
+    assert_eq!(
+        repo.commit_range(ids[3], ids[1], 10).await?,
+        vec![ids[3], ids[1]]
+    );
+    assert_eq!(repo.commit_range(ids[3], ids[1], 1).await?, vec![ids[3]]);
+    assert_eq!(repo.commit_range(ids[3], ids[3], 10).await?, vec![ids[3]]);
+    assert_eq!(repo.commit_range(ids[2], ids[0], 10).await?, vec![]);
+    let range = repo.commit_range(ids[1], ids[0], 100).await?;
+    assert_eq!(range.first(), Some(&ids[1]));
+    assert_eq!(range.last(), Some(&ids[0]));
+    for (ancestor, descendant) in range.iter().zip(range.iter().skip(1)) {
+        assert!(repo.is_ancestor(*ancestor, *descendant).await?);
+    }
+    Ok(())
+}
+
+#[fbinit::test]
+async fn commit_descendants_among(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        BranchUneven::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx, "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let mut changesets = Vec::new();
+    for hg_hash in [
+        "5d43888a3c972fe68c224f93d41b30e9f888df7c", // 0: branch 1 near top
+        "d7542c9db7f4c77dab4b315edd328edf1514952f", // 1: branch 1 near bottom
+        "1d8a907f7b4bf50c6a09c16361e2205047ecc5e5", // 2: branch 2
+        "15c40d0abc36d47fb51c8eaec51ac7aad31f669c", // 3: base
+    ] {
+        let changeset = repo
+            .changeset(HgChangesetId::from_str(hg_hash)?)
+            .await?
+            .expect("changeset exists");
+        changesets.push(changeset);
+    }
+    let ids: Vec<_> = changesets.iter().map(|cs| cs.id()).collect();
+    for (index, expected) in [
+        (0usize, vec![0usize]),
+        (1, vec![0, 1]),
+        (2, vec![2]),
+        (3, vec![0, 1, 2, 3]),
+    ] {
+        assert_eq!(
+            changesets[index]
+                .descendants_among(ids.iter().copied())
+                .await?,
+            expected.into_iter().map(|i| ids[i]).collect::<HashSet<_>>(),
+            "descendants of changesets[{}]",
+            index,
+        );
+    }
+    assert!(
+        changesets[0]
+            .descendants_among(vec![ids[1], ids[2], ids[3]])
+            .await?
+            .is_empty()
+    );
+    Ok(())
+}
+
+#[fbinit::test]
+async fn commit_descendants_within(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        BranchUneven::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx, "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let mut ids = Vec::new();
+    for hg_hash in [
+        "5d43888a3c972fe68c224f93d41b30e9f888df7c", // 0: branch 1 near top
+        "d7542c9db7f4c77dab4b315edd328edf1514952f", // 1: branch 1 near bottom
+        "1d8a907f7b4bf50c6a09c16361e2205047ecc5e5", // 2: branch 2
+        "15c40d0abc36d47fb51c8eaec51ac7aad31f669c", // 3: base
+    ] {
+        let changeset = repo
+            .changeset(HgChangesetId::from_str(hg_hash)?)
+            .await?
+            .expect("changeset exists");
+        ids.push(changeset.id());
+    }
+    let base = repo.changeset(ids[3]).await?.expect("changeset exists");
+
+    // Only the history of the given heads is listed, newest first.
+    let descendants: Vec<_> = base
+        .descendants_within(vec![ids[0]])
+        .await?
+        .try_collect()
+        .await?;
+    assert_eq!(descendants.first(), Some(&ids[0]));
+    assert!(descendants.contains(&ids[1]));
+    assert!(!descendants.contains(&ids[2]));
+    assert!(!descendants.contains(&ids[3]));
+
+    // Both branches are listed when both are heads.
+    let descendants: Vec<_> = base
+        .descendants_within(vec![ids[0], ids[2]])
+        .await?
+        .try_collect()
+        .await?;
+    assert!(descendants.contains(&ids[1]));
+    assert!(descendants.contains(&ids[2]));
+
+    // Heads that don't descend from the commit contribute nothing.
+    let near_top = repo.changeset(ids[0]).await?.expect("changeset exists");
+    assert!(
+        near_top
+            .descendants_within(vec![ids[1], ids[2]])
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?
+            .is_empty()
+    );
+    Ok(())
+}
+
 async fn commit_find_files_impl(fb: FacebookInit) -> Result<(), Error> {
     let ctx = CoreContext::test_mock(fb);
     let mononoke = Mononoke::new_test(vec![(
@@ -850,6 +1046,55 @@ async fn commit_path_exists_and_type(fb: FacebookInit) -> Result<(), Error> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn commit_path_case_conflicts(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        Linear::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx.clone(), "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let master_id = resolve_cs_id(&ctx, repo.blob_repo(), "master").await?;
+    let cs_id = CreateCommitContext::new(&ctx, repo.blob_repo(), vec![master_id])
+        .add_file("dir/File", "content")
+        .add_file("dir/file", "content")
+        .add_file("dir/sub/a", "content")
+        .add_file("dir/sub/A", "content")
+        .add_file("other/b", "content")
+        .add_file("other/B", "content")
+        .commit()
+        .await?;
+    let cs = repo.changeset(cs_id).await?.expect("changeset exists");
+
+    let paths = |conflicts: Vec<Vec<MononokePath>>| {
+        conflicts
+            .into_iter()
+            .map(|paths| paths.into_iter().map(|path| path.to_string()).collect())
+            .collect::<Vec<Vec<_>>>()
+    };
+    assert_eq!(
+        paths(cs.path("dir").await?.case_conflicts(10).await?),
+        vec![
+            vec!["dir/File", "dir/file"],
+            vec!["dir/sub/A", "dir/sub/a"],
+        ]
+    );
+    assert_eq!(
+        paths(cs.path("").await?.case_conflicts(1).await?),
+        vec![vec!["dir/File", "dir/file"]]
+    );
+    assert!(cs.path("dir/file").await?.case_conflicts(10).await?.is_empty());
+    assert!(cs.path("nonexistent").await?.case_conflicts(10).await?.is_empty());
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn tree_list(fb: FacebookInit) -> Result<(), Error> {
     let ctx = CoreContext::test_mock(fb);
@@ -972,6 +1217,164 @@ async fn tree_list(fb: FacebookInit) -> Result<(), Error> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn tree_list_subtrees(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        ManyFilesDirs::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx, "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let hash = "b0d1bf77898839595ee0f0cba673dd6e3be9dadaaa78bc6dd2dea97ca6bee77e";
+    let cs_id = ChangesetId::from_str(hash)?;
+    let cs = repo.changeset(cs_id).await?.expect("changeset exists");
+    let dir1 = cs.path_with_content("dir1").await?;
+
+    let summarize = |trees: Vec<(MononokePath, Vec<(String, TreeEntry)>)>| {
+        trees
+            .into_iter()
+            .map(|(path, entries)| (path.to_string(), entries.len()))
+            .collect::<Vec<_>>()
+    };
+
+    // Depth limits how far below the requested path we go.
+    let (trees, truncated) = dir1.list_subtrees(2, 1000, &HashSet::new()).await?;
+    assert!(!truncated);
+    assert_eq!(
+        summarize(trees),
+        vec![(String::from("dir1"), 3), (String::from("dir1/subdir1"), 3)]
+    );
+
+    let (trees, truncated) = dir1.list_subtrees(10, 1000, &HashSet::new()).await?;
+    assert!(!truncated);
+    assert_eq!(
+        summarize(trees),
+        vec![
+            (String::from("dir1"), 3),
+            (String::from("dir1/subdir1"), 3),
+            (String::from("dir1/subdir1/subsubdir1"), 1),
+            (String::from("dir1/subdir1/subsubdir2"), 2),
+        ]
+    );
+
+    // Trees are listed whole, so the limit stops before a tree that won't fit.
+    let (trees, truncated) = dir1.list_subtrees(10, 5, &HashSet::new()).await?;
+    assert!(truncated);
+    assert_eq!(summarize(trees), vec![(String::from("dir1"), 3)]);
+
+    // Files and missing paths have no subtrees.
+    let file = cs.path_with_content("dir1/file_1_in_dir1").await?;
+    let (trees, truncated) = file.list_subtrees(10, 1000, &HashSet::new()).await?;
+    assert!(trees.is_empty());
+    assert!(!truncated);
+
+    // Excluded trees are skipped, and don't count towards the limit, but
+    // the trees beneath them are still listed.
+    let exclude = HashSet::from([MononokePath::try_from("dir1")?]);
+    let (trees, truncated) = dir1.list_subtrees(10, 5, &exclude).await?;
+    assert!(truncated);
+    assert_eq!(
+        summarize(trees),
+        vec![
+            (String::from("dir1/subdir1"), 3),
+            (String::from("dir1/subdir1/subsubdir1"), 1),
+        ]
+    );
+    let exclude = HashSet::from([
+        MononokePath::try_from("dir1")?,
+        MononokePath::try_from("dir1/subdir1")?,
+    ]);
+    let (trees, truncated) = dir1.list_subtrees(10, 3, &exclude).await?;
+    assert!(!truncated);
+    assert_eq!(
+        summarize(trees),
+        vec![
+            (String::from("dir1/subdir1/subsubdir1"), 1),
+            (String::from("dir1/subdir1/subsubdir2"), 2),
+        ]
+    );
+
+    Ok(())
+}
+
+#[fbinit::test]
+async fn tree_list_recursive(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        ManyFilesDirs::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx, "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let hash = "b0d1bf77898839595ee0f0cba673dd6e3be9dadaaa78bc6dd2dea97ca6bee77e";
+    let cs_id = ChangesetId::from_str(hash)?;
+    let cs = repo.changeset(cs_id).await?.expect("changeset exists");
+    let dir1 = cs
+        .path_with_content("dir1")
+        .await?
+        .tree()
+        .await?
+        .expect("dir1 is a tree");
+
+    let paths = |entries: Vec<(MononokePath, TreeEntry)>| {
+        entries
+            .into_iter()
+            .map(|(path, _entry)| path.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Paths are relative to the listed tree, and depth limits how far
+    // below it we go.
+    let (entries, truncated) = dir1.list_recursive(2, 1000).await?;
+    assert!(!truncated);
+    assert_eq!(
+        paths(entries),
+        vec![
+            "file_1_in_dir1",
+            "file_2_in_dir1",
+            "subdir1",
+            "subdir1/file_1",
+            "subdir1/subsubdir1",
+            "subdir1/subsubdir2",
+        ]
+    );
+
+    let (entries, truncated) = dir1.list_recursive(10, 1000).await?;
+    assert!(!truncated);
+    assert_eq!(entries.len(), 9);
+
+    // The limit is strict, even part way through a tree.
+    let (entries, truncated) = dir1.list_recursive(10, 4).await?;
+    assert!(truncated);
+    assert_eq!(
+        paths(entries),
+        vec![
+            "file_1_in_dir1",
+            "file_2_in_dir1",
+            "subdir1",
+            "subdir1/file_1",
+        ]
+    );
+
+    // An exact fit is not truncated.
+    let (entries, truncated) = dir1.list_recursive(10, 9).await?;
+    assert!(!truncated);
+    assert_eq!(entries.len(), 9);
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn file_metadata(fb: FacebookInit) -> Result<(), Error> {
     let ctx = CoreContext::test_mock(fb);
@@ -1380,6 +1783,76 @@ async fn xrepo_commit_lookup_config_changing_live(fb: FacebookInit) -> Result<()
     Ok(())
 }
 
+#[fbinit::test]
+async fn xrepo_path_mapping(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let (mononoke, _cfg_src) = init_x_repo(&ctx).await?;
+
+    let smallrepo = mononoke
+        .repo(ctx.clone(), "smallrepo")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let largerepo = mononoke
+        .repo(ctx.clone(), "largerepo")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+
+    let small_master_cs_id = resolve_cs_id(&ctx, smallrepo.blob_repo(), "master").await?;
+    let (version, mapped) = smallrepo
+        .xrepo_path_mapping(
+            &largerepo,
+            small_master_cs_id,
+            vec![
+                MononokePath::try_from("file3")?,
+                MononokePath::try_from("dir/file")?,
+            ],
+        )
+        .await?;
+    assert_eq!(
+        version,
+        CommitSyncConfigVersion("TEST_VERSION_NAME".to_string())
+    );
+    assert_eq!(
+        mapped,
+        hashmap! {
+            MononokePath::try_from("file3")? => MononokePath::try_from("prefix/file3")?,
+            MononokePath::try_from("dir/file")? => MononokePath::try_from("prefix/dir/file")?,
+        }
+    );
+
+    // Paths outside the small repo's prefix have no equivalent.
+    let large_master_cs_id = resolve_cs_id(&ctx, largerepo.blob_repo(), "master").await?;
+    let (_version, mapped) = largerepo
+        .xrepo_path_mapping(
+            &smallrepo,
+            large_master_cs_id,
+            vec![
+                MononokePath::try_from("prefix/file3")?,
+                MononokePath::try_from("other/file")?,
+            ],
+        )
+        .await?;
+    assert_eq!(
+        mapped,
+        hashmap! {
+            MononokePath::try_from("prefix/file3")? => MononokePath::try_from("file3")?,
+        }
+    );
+
+    // The root path can't be mapped.
+    assert!(
+        smallrepo
+            .xrepo_path_mapping(&largerepo, small_master_cs_id, vec![MononokePath::new(None)])
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
 async fn init_x_repo(
     ctx: &CoreContext,
 ) -> Result<(Mononoke, TestLiveCommitSyncConfigSource), Error> {
@@ -117,6 +117,48 @@ async fn resolve_bookmark(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn resolve_bookmark_with_staleness(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let (repo, changesets) = init_repo(&ctx).await?;
+
+    // Values served from the cache report their staleness.
+    let (trunk, staleness) = repo
+        .resolve_bookmark_with_staleness(
+            &BookmarkKey::new("trunk")?,
+            BookmarkFreshness::MaybeStale,
+        )
+        .await?
+        .unwrap();
+    assert_eq!(trunk.id(), changesets["E"]);
+    let staleness = staleness.expect("trunk is served from the cache");
+    assert!(!staleness.pending_move);
+
+    // Values read directly don't.
+    let (trunk, staleness) = repo
+        .resolve_bookmark_with_staleness(
+            &BookmarkKey::new("trunk")?,
+            BookmarkFreshness::MostRecent,
+        )
+        .await?
+        .unwrap();
+    assert_eq!(trunk.id(), changesets["E"]);
+    assert!(staleness.is_none());
+
+    // Scratch bookmarks aren't in the cache.
+    let (branch, staleness) = repo
+        .resolve_bookmark_with_staleness(
+            &BookmarkKey::new("scratch/branch")?,
+            BookmarkFreshness::MaybeStale,
+        )
+        .await?
+        .unwrap();
+    assert_eq!(branch.id(), changesets["G"]);
+    assert!(staleness.is_none());
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn list_bookmarks(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
@@ -292,7 +292,7 @@ async fn create_commit_bad_changes(fb: FacebookInit) -> Result<(), Error> {
     );
     assert_matches!(
         create_changeset(&repo, changes.clone()).await,
-        Err(MononokeError::InvalidRequest(_))
+        Err(MononokeError::NotADirectory(_))
     );
 
     // Deleting the file means we can now replace it with a directory.
@@ -19,15 +19,36 @@ use bookmarks::Freshness;
 use context::CoreContext;
 use fbinit::FacebookInit;
 use futures::stream::TryStreamExt;
+use metaconfig_types::BookmarkParams;
 use mononoke_types::ChangesetId;
+use regex::Regex;
+use repo_bookmark_attrs::RepoBookmarkAttrsRef;
+use test_repo_factory::TestRepoFactory;
 use tests_utils::drawdag::create_from_dag;
 
+use crate::errors::MononokeError;
 use crate::repo::BookmarkFreshness;
 use crate::repo::Repo;
 use crate::repo::RepoContext;
 
 async fn init_repo(ctx: &CoreContext) -> Result<(RepoContext, BTreeMap<String, ChangesetId>)> {
-    let repo: Repo = test_repo_factory::build_empty(ctx.fb).await?;
+    let repo: Repo = TestRepoFactory::new(ctx.fb)?
+        .with_config_override(|config| {
+            config.bookmarks.push(BookmarkParams {
+                bookmark: Regex::new("^release/").unwrap().into(),
+                hooks: vec![],
+                only_fast_forward: false,
+                rewrite_dates: None,
+                allowed_users: None,
+                allowed_hipster_group: None,
+                hooks_skip_ancestors_of: vec![],
+                ensure_ancestor_of: None,
+                allow_move_to_public_commits_without_hooks: false,
+                runtime_protections: true,
+            });
+        })
+        .build()
+        .await?;
     let changesets = create_from_dag(
         ctx,
         &repo,
@@ -111,6 +132,74 @@ async fn create_bookmark(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn create_branch(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let (repo, changesets) = init_repo(&ctx).await?;
+
+    let key = BookmarkKey::new("release/1.0")?;
+    let allowed_pushers = vec!["release-bot".to_string(), "alice".to_string()];
+    repo.create_branch(&key, changesets["C"], &allowed_pushers, None)
+        .await?;
+    let branch = repo
+        .resolve_bookmark(&key, BookmarkFreshness::MostRecent)
+        .await?
+        .expect("bookmark should be set");
+    assert_eq!(branch.id(), changesets["C"]);
+
+    let protections = repo
+        .inner_repo()
+        .repo_bookmark_attrs()
+        .protections()
+        .expect("protections should be available");
+    let mut pushers = protections
+        .allowed_pushers(&key)
+        .await?
+        .expect("protections should be registered");
+    pushers.sort();
+    assert_eq!(pushers, vec!["alice".to_string(), "release-bot".to_string()]);
+
+    // Only the allowed pushers may move the branch.
+    let bookmark_attrs = repo.inner_repo().repo_bookmark_attrs();
+    assert!(bookmark_attrs.is_allowed_user(&ctx, "alice", &key).await?);
+    assert!(!bookmark_attrs.is_allowed_user(&ctx, "bob", &key).await?);
+    assert!(
+        bookmark_attrs
+            .is_allowed_user(&ctx, "bob", &BookmarkKey::new("trunk")?)
+            .await?
+    );
+
+    // Creating the branch again fails, and doesn't change the protections.
+    assert!(
+        repo.create_branch(&key, changesets["E"], &["bob".to_string()], None)
+            .await
+            .is_err()
+    );
+    let pushers = protections
+        .allowed_pushers(&key)
+        .await?
+        .expect("protections should be registered");
+    assert_eq!(pushers.len(), 2);
+
+    // Bookmarks not configured for runtime protections can't be branches.
+    let key = BookmarkKey::new("other/1.0")?;
+    assert!(
+        repo.create_branch(&key, changesets["C"], &allowed_pushers, None)
+            .await
+            .is_err()
+    );
+
+    // Scratch bookmarks can't be branches.
+    let key = BookmarkKey::new("scratch/branch")?;
+    assert!(
+        repo.create_branch(&key, changesets["G"], &allowed_pushers, None)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn move_bookmark(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
@@ -178,6 +267,56 @@ async fn move_bookmark(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn move_bookmark_including(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let (repo, changesets) = init_repo(&ctx).await?;
+
+    // The new target must include the required commit.
+    let key = BookmarkKey::new("trunk")?;
+    assert!(
+        repo.move_bookmark_including(&key, changesets["D"], None, changesets["E"], None)
+            .await
+            .is_err()
+    );
+    repo.move_bookmark_including(&key, changesets["E"], None, changesets["D"], None)
+        .await?;
+    let trunk = repo
+        .resolve_bookmark(&key, BookmarkFreshness::MostRecent)
+        .await?
+        .expect("bookmark should be set");
+    assert_eq!(trunk.id(), changesets["E"]);
+
+    // The new target must also be a descendant of the current target, even
+    // if it includes the required commit.
+    assert!(
+        repo.move_bookmark_including(&key, changesets["G"], None, changesets["F"], None)
+            .await
+            .is_err()
+    );
+
+    // If the bookmark has moved on from the target that was checked, the
+    // move fails.
+    assert!(matches!(
+        repo.move_bookmark_including(
+            &key,
+            changesets["E"],
+            Some(changesets["C"]),
+            changesets["C"],
+            None
+        )
+        .await,
+        Err(MononokeError::Conflict(_))
+    ));
+    let trunk = repo
+        .resolve_bookmark(&key, BookmarkFreshness::MostRecent)
+        .await?
+        .expect("bookmark should be set");
+    assert_eq!(trunk.id(), changesets["E"]);
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn delete_bookmark(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
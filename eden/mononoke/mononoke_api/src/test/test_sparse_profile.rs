@@ -30,6 +30,7 @@ use types::RepoPath;
 
 use crate::sparse_profile::fetch;
 use crate::sparse_profile::get_profile_delta_size;
+use crate::sparse_profile::get_profile_files_summary;
 use crate::sparse_profile::MonitoringProfiles;
 use crate::sparse_profile::ProfileSizeChange;
 use crate::sparse_profile::SparseProfileMonitoring;
@@ -360,6 +361,53 @@ async fn multiple_sparse_profile_sizes(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn sparse_profile_files_summary(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let mononoke = Mononoke::new_test(vec![(
+        "test".to_string(),
+        ManyFilesDirs::get_custom_test_repo(fb).await,
+    )])
+    .await?;
+    let repo = mononoke
+        .repo(ctx.clone(), "test")
+        .await?
+        .expect("repo exists")
+        .build()
+        .await?;
+    let hg_cs_id = "d261bc7900818dea7c86935b3fb17a33b2e3a6b4".parse::<HgChangesetId>()?;
+
+    let a = init_sparse_profile(&ctx, &repo, hg_cs_id).await?;
+    let changeset_a = ChangesetContext::new(repo, a);
+    let summaries = get_profile_files_summary(
+        &ctx,
+        &changeset_a,
+        vec![
+            NonRootMPath::new("sparse/include")?,
+            NonRootMPath::new("sparse/base")?,
+        ],
+    )
+    .await?;
+
+    // sparse/include matches dir1/subdir1 and, through sparse/base, dir2.
+    let include = &summaries["sparse/include"];
+    assert_eq!(include.file_count, 5);
+    assert_eq!(include.total_size, 45);
+    assert!(
+        include
+            .rules
+            .iter()
+            .any(|rule| rule.starts_with("dir2") && rule.contains("sparse/base"))
+    );
+    assert!(include.rules.iter().any(|rule| rule.starts_with("dir1/subdir1")));
+
+    let base = &summaries["sparse/base"];
+    assert_eq!(base.file_count, 1);
+    assert_eq!(base.total_size, 9);
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn sparse_profile_delta(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
@@ -11,17 +11,25 @@ use anyhow::Error;
 use blobstore::Loadable;
 use blobstore::LoadableError;
 use cloned::cloned;
+use filestore::FetchKey;
+use futures::stream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
 use futures_lazy_shared::LazyShared;
 use mononoke_types::fsnode::Fsnode;
 // An entry within a tree list (either a file or subdirectory).
 pub use mononoke_types::fsnode::FsnodeEntry as TreeEntry;
+use mononoke_types::fsnode::FsnodeFile;
 // Summary information about the files in a tree.
 pub use mononoke_types::fsnode::FsnodeSummary as TreeSummary;
 // Trees are identified by their FsnodeId.
 pub use mononoke_types::FsnodeId as TreeId;
+use mononoke_types::MPathElement;
 use repo_blobstore::RepoBlobstoreRef;
 
 use crate::errors::MononokeError;
+use crate::file::FileContext;
+use crate::path::MononokePath;
 use crate::repo::RepoContext;
 
 #[derive(Clone)]
@@ -110,11 +118,70 @@ impl TreeContext {
     }
 
     pub async fn list(&self) -> Result<impl Iterator<Item = (String, TreeEntry)>, MononokeError> {
-        let fsnode = self.fsnode().await?;
-        let entries = fsnode
-            .into_subentries()
-            .into_iter()
+        let entries = self
+            .list_elements()
+            .await?
             .map(|(elem, entry)| (String::from_utf8_lossy(elem.as_ref()).to_string(), entry));
         Ok(entries)
     }
+
+    /// List the entries of this tree, keeping their names as path elements.
+    pub(crate) async fn list_elements(
+        &self,
+    ) -> Result<impl Iterator<Item = (MPathElement, TreeEntry)>, MononokeError> {
+        let fsnode = self.fsnode().await?;
+        Ok(fsnode.into_subentries().into_iter())
+    }
+
+    /// List the entries of this tree and the trees beneath it, breadth
+    /// first, down to `depth` levels.  A depth of 1 lists just this tree.
+    ///
+    /// Each entry is returned with its path relative to this tree.  At most
+    /// `limit` entries are returned; if there were more, the returned flag
+    /// is `true`.
+    pub async fn list_recursive(
+        &self,
+        depth: usize,
+        limit: usize,
+    ) -> Result<(Vec<(MononokePath, TreeEntry)>, bool), MononokeError> {
+        let mut listed = Vec::new();
+        let mut level = vec![(MononokePath::new(None), self.clone())];
+        for _ in 0..depth {
+            if level.is_empty() {
+                break;
+            }
+            let listings: Vec<_> = stream::iter(level)
+                .map(|(path, tree)| async move {
+                    let entries: Vec<_> = tree.list_elements().await?.collect();
+                    Ok::<_, MononokeError>((path, entries))
+                })
+                .buffered(100)
+                .try_collect()
+                .await?;
+            let mut next_level = Vec::new();
+            for (path, entries) in listings {
+                for (elem, entry) in entries {
+                    if listed.len() == limit {
+                        return Ok((listed, true));
+                    }
+                    let entry_path = path.append(&elem);
+                    if let TreeEntry::Directory(dir) = &entry {
+                        next_level.push((
+                            entry_path.clone(),
+                            TreeContext::new_authorized(self.repo.clone(), *dir.id()),
+                        ));
+                    }
+                    listed.push((entry_path, entry));
+                }
+            }
+            level = next_level;
+        }
+        Ok((listed, false))
+    }
+
+    /// Get the context for a file listed in this tree.  Access to the tree
+    /// implies access to the files it lists.
+    pub fn file(&self, file: &FsnodeFile) -> FileContext {
+        FileContext::new_authorized(self.repo.clone(), FetchKey::Canonical(*file.content_id()))
+    }
 }
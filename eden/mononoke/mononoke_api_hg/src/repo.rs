@@ -63,6 +63,7 @@ use metaconfig_types::RepoConfig;
 use mononoke_api::errors::MononokeError;
 use mononoke_api::path::MononokePath;
 use mononoke_api::repo::RepoContext;
+use mononoke_api::DagFreshness;
 use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
 use mononoke_types::ContentId;
@@ -537,6 +538,7 @@ impl HgRepoContext {
         &self,
         location: Location<HgChangesetId>,
         count: u64,
+        freshness: DagFreshness,
     ) -> Result<Vec<HgChangesetId>, MononokeError> {
         let cs_location = location
             .and_then_descendant(|descendant| async move {
@@ -554,7 +556,7 @@ impl HgRepoContext {
             .await?;
         let result_csids = self
             .repo()
-            .location_to_changeset_id(cs_location, count)
+            .location_to_changeset_id(cs_location, count, freshness)
             .await?;
         let hg_id_futures = result_csids.iter().map(|result_csid| {
             self.blob_repo()
@@ -572,6 +574,7 @@ impl HgRepoContext {
         &self,
         hg_master_heads: Vec<HgChangesetId>,
         hg_ids: Vec<HgChangesetId>,
+        freshness: DagFreshness,
     ) -> Result<HashMap<HgChangesetId, Result<Location<HgChangesetId>, MononokeError>>, MononokeError>
     {
         let all_hg_ids: Vec<_> = hg_ids
@@ -607,7 +610,7 @@ impl HgRepoContext {
 
         let cs_to_blocations = self
             .repo()
-            .many_changeset_ids_to_locations(master_heads, cs_ids)
+            .many_changeset_ids_to_locations(master_heads, cs_ids, freshness)
             .await?;
 
         let bonsai_to_hg: HashMap<ChangesetId, HgChangesetId> = self
@@ -676,8 +679,12 @@ impl HgRepoContext {
 
     pub async fn segmented_changelog_clone_data(
         &self,
+        freshness: DagFreshness,
     ) -> Result<CloneData<HgChangesetId>, MononokeError> {
-        let (m_clone_data, hints) = self.repo().segmented_changelog_clone_data().await?;
+        let (m_clone_data, hints) = self
+            .repo()
+            .segmented_changelog_clone_data(freshness)
+            .await?;
         self.convert_clone_data(m_clone_data, hints).await
     }
 
@@ -144,6 +144,52 @@ impl SkeletonManifest {
         }
     }
 
+    /// Returns the case conflicts in this skeleton manifest and its
+    /// descendants, up to `limit` of them, as groups of paths relative to
+    /// this manifest that differ only by case.  Conflicts are returned in
+    /// depth-first order, with the conflicts in a directory before those in
+    /// its subdirectories.
+    pub async fn case_conflicts<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        blobstore: &'a impl Blobstore,
+        limit: usize,
+    ) -> Result<Vec<Vec<NonRootMPath>>> {
+        let mut conflicts = Vec::new();
+        let mut stack: Vec<(Option<NonRootMPath>, Cow<'_, SkeletonManifest>)> =
+            vec![(None, Cow::Borrowed(self))];
+        while let Some((path, sk_mf)) = stack.pop() {
+            if sk_mf.summary.child_case_conflicts {
+                for names in sk_mf.child_case_conflicts() {
+                    if conflicts.len() >= limit {
+                        return Ok(conflicts);
+                    }
+                    conflicts.push(
+                        names
+                            .into_iter()
+                            .map(|name| NonRootMPath::join_opt_element(path.as_ref(), name))
+                            .collect(),
+                    );
+                }
+            }
+            if sk_mf.summary.descendant_case_conflicts {
+                // Push in reverse so that subdirectories are visited in
+                // name order.
+                for (name, entry) in sk_mf.subentries.iter().rev() {
+                    if let SkeletonManifestEntry::Directory(subdir) = entry {
+                        if subdir.has_case_conflicts() {
+                            stack.push((
+                                Some(NonRootMPath::join_opt_element(path.as_ref(), name)),
+                                Cow::Owned(subdir.id.load(ctx, blobstore).await?),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(conflicts)
+    }
+
     /// Returns the first case conflict that wasn't present in any of the
     /// parents.
     pub async fn first_new_case_conflict<'a>(
@@ -216,6 +262,18 @@ impl SkeletonManifest {
         .await
     }
 
+    /// Returns the groups of immediate children of this skeleton manifest
+    /// whose names differ only by case.
+    fn child_case_conflicts(&self) -> impl Iterator<Item = Vec<&MPathElement>> {
+        let mut lower_map: BTreeMap<String, Vec<_>> = BTreeMap::new();
+        for name in self.subentries.keys() {
+            if let Some(lower_name) = name.to_lowercase_utf8() {
+                lower_map.entry(lower_name).or_default().push(name)
+            }
+        }
+        lower_map.into_values().filter(|names| names.len() > 1)
+    }
+
     /// Returns the first case conflict that is an immediate child of this
     /// skeleton manifest that is not present in any of the parents.
     fn first_new_child_case_conflict(
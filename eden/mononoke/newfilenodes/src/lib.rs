@@ -79,9 +79,15 @@ impl Filenodes for NewFilenodes {
     ) -> Result<FilenodeResult<()>> {
         let ret = self
             .writer
-            .insert_filenodes(ctx, self.repo_id, info, true /* replace */)
+            .insert_filenodes(ctx, self.repo_id, info.clone(), true /* replace */)
             .await
             .with_context(|| ErrorKind::FailAddFilenodes)?;
+        if let FilenodeResult::Present(()) = ret {
+            self.reader
+                .refresh_replaced(ctx, self.repo_id, &info)
+                .await
+                .with_context(|| ErrorKind::FailAddFilenodes)?;
+        }
         Ok(ret)
     }
 
@@ -7,6 +7,7 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::time::Duration;
@@ -356,6 +357,60 @@ impl FilenodesReader {
             self.local_cache.fill_filenode(&key, &c.info)
         }
     }
+
+    /// Overwrite the cached values of filenodes that have been replaced, so
+    /// that readers see the new values rather than the ones cached before.
+    /// Full histories of their paths are reloaded from the master if either
+    /// cache has them.  Histories cached with a limit are left to expire.
+    pub async fn refresh_replaced(
+        &self,
+        ctx: &CoreContext,
+        repo_id: RepositoryId,
+        filenodes: &[PreparedFilenode],
+    ) -> Result<(), Error> {
+        let mut paths = HashSet::new();
+        for c in filenodes {
+            let pwh = PathWithHash::from_repo_path(&c.path);
+            let key = filenode_cache_key(repo_id, &pwh, &c.info.filenode);
+            let cache_filler = FilenodeCacheFiller {
+                local_cache: &self.local_cache,
+                remote_cache: &self.remote_cache,
+                key: &key,
+            };
+            cache_filler.fill(c.info.clone());
+            paths.insert(&c.path);
+        }
+
+        for path in paths {
+            let pwh = PathWithHash::from_repo_path(path);
+            let key = history_cache_key(repo_id, &pwh, None);
+            if self.local_cache.get_history(&key).is_none()
+                && enforce_remote_cache_timeout(self.remote_cache.get_history(&key))
+                    .await
+                    .is_none()
+            {
+                continue;
+            }
+            let cache_filler = HistoryCacheFiller {
+                local_cache: &self.local_cache,
+                remote_cache: &self.remote_cache,
+                key: &key,
+            };
+            select_history_from_sql(
+                &cache_filler,
+                &self.read_master_connections,
+                repo_id,
+                &pwh,
+                &PerfCounterRecorder {
+                    ctx,
+                    counter: PerfCounterType::SqlReadsMaster,
+                },
+                None,
+            )
+            .await?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -209,3 +209,73 @@ async fn test_too_big_caching(fb: FacebookInit) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[fbinit::test]
+async fn test_refresh_replaced(fb: FacebookInit) -> Result<(), Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let (mut reader, writer) = build_reader_writer(vec1![build_shard()?]);
+
+    reader.local_cache = LocalCache::new_mock();
+    reader.remote_cache = RemoteCache::new_mock();
+    let reader = Arc::new(reader);
+
+    let path = RepoPath::file("file")?;
+    let info = filenode();
+    let prepared = |info: &FilenodeInfo| {
+        vec![PreparedFilenode {
+            path: path.clone(),
+            info: info.clone(),
+        }]
+    };
+
+    writer
+        .insert_filenodes(&ctx, REPO_ZERO, prepared(&info), false)
+        .await?
+        .do_not_handle_disabled_filenodes()?;
+
+    // Fill both caches with the original filenode and history.
+    reader
+        .clone()
+        .get_filenode(&ctx, REPO_ZERO, &path, info.filenode)
+        .await?
+        .do_not_handle_disabled_filenodes()?;
+    reader
+        .clone()
+        .get_all_filenodes_for_path(&ctx, REPO_ZERO, &path, None)
+        .await?
+        .do_not_handle_disabled_filenodes()?;
+
+    let repaired = FilenodeInfo {
+        linknode: TWOS_CSID,
+        ..info.clone()
+    };
+    writer
+        .insert_filenodes(&ctx, REPO_ZERO, prepared(&repaired), true)
+        .await?
+        .do_not_handle_disabled_filenodes()?;
+    reader
+        .refresh_replaced(&ctx, REPO_ZERO, &prepared(&repaired))
+        .await?;
+
+    let pwh = PathWithHash::from_repo_path(&path);
+    let key = filenode_cache_key(REPO_ZERO, &pwh, &info.filenode);
+    assert_eq!(
+        reader.local_cache.get_filenode(&key),
+        Some(repaired.clone())
+    );
+
+    let key = history_cache_key(REPO_ZERO, &pwh, None);
+    match reader.local_cache.get_history(&key) {
+        Some(FilenodeRange::Filenodes(history)) => assert_eq!(history, vec![repaired.clone()]),
+        _ => return Err(anyhow!("history not cached")),
+    }
+
+    let res = reader
+        .clone()
+        .get_filenode(&ctx, REPO_ZERO, &path, info.filenode)
+        .await?
+        .do_not_handle_disabled_filenodes()?;
+    assert_eq!(res, Some(repaired));
+
+    Ok(())
+}
@@ -25,7 +25,8 @@ use crate::ArcCommitGraph;
 use crate::CommitGraph;
 
 /// Builder for a reverse topologically ordered stream of changesets that
-/// are ancestors of any set of changesets (heads). This builder allows customizing
+/// are ancestors of any set of changesets (heads). Changesets with the same
+/// generation number are ordered by changeset id. This builder allows customizing
 /// the stream by:
 ///
 /// - excluding ancestors of a set of changesets (common).
@@ -188,6 +189,9 @@ impl AncestorsStreamBuilder {
                             cs_ids_not_excluded.push(cs_id)
                         }
                     }
+                    // Changesets with the same generation are returned in
+                    // changeset id order so that the stream is deterministic.
+                    cs_ids_not_excluded.sort();
 
                     let all_edges = commit_graph
                         .storage
@@ -89,6 +89,12 @@ pub trait ChangesetHook: Send + Sync {
         cross_repo_push_source: CrossRepoPushSource,
         push_authored_by: PushAuthoredBy,
     ) -> Result<HookExecution, Error>;
+
+    /// Whether the hook only checks the changeset's message, so that it can
+    /// be run on a message before the changeset is created.
+    fn checks_message_only(&self) -> bool {
+        false
+    }
 }
 
 /// Trait to be implemented by file hooks.
@@ -26,6 +26,7 @@ use metaconfig_types::HookConfig;
 use metaconfig_types::HookManagerParams;
 use mononoke_types::BasicFileChange;
 use mononoke_types::BonsaiChangeset;
+use mononoke_types::BonsaiChangesetMut;
 use mononoke_types::ChangesetId;
 use mononoke_types::NonRootMPath;
 use permission_checker::AclProvider;
@@ -179,6 +180,10 @@ impl HookManager {
         &self.repo_name
     }
 
+    pub fn hook_exists(&self, hook_name: &str) -> bool {
+        self.hooks.contains_key(hook_name)
+    }
+
     fn hooks_for_bookmark<'a>(
         &'a self,
         bookmark: &BookmarkKey,
@@ -198,6 +203,20 @@ impl HookManager {
         hooks.into_iter()
     }
 
+    /// The names and configs of the hooks that run on pushes to the given
+    /// bookmark.
+    pub fn hook_configs_for_bookmark<'a>(
+        &'a self,
+        bookmark: &BookmarkKey,
+    ) -> impl Iterator<Item = (&'a str, &'a HookConfig)> {
+        self.hooks_for_bookmark(bookmark)
+            .filter_map(move |hook_name| {
+                self.hooks
+                    .get(hook_name)
+                    .map(|hook| (hook_name, hook.get_config()))
+            })
+    }
+
     pub fn all_hooks_bypassed(&self) -> bool {
         self.all_hooks_bypassed
     }
@@ -264,6 +283,111 @@ impl HookManager {
         }
         futs.try_collect().await
     }
+
+    /// Run a single hook against changesets as if they were being pushed to
+    /// the bookmark, whether or not the hook is enabled for that bookmark.
+    /// Bypasses are not honoured, so every changeset is checked.  This is
+    /// for trying out hook logic against existing history.
+    pub async fn run_hook_for_bookmark(
+        &self,
+        ctx: &CoreContext,
+        hook_name: &str,
+        changesets: impl Iterator<Item = &BonsaiChangeset>,
+        bookmark: &BookmarkKey,
+        cross_repo_push_source: CrossRepoPushSource,
+        push_authored_by: PushAuthoredBy,
+    ) -> Result<Vec<HookOutcome>, Error> {
+        debug!(
+            ctx.logger(),
+            "Running hook {} for bookmark {:?}", hook_name, bookmark
+        );
+
+        let hook = self
+            .hooks
+            .get(hook_name)
+            .ok_or_else(|| HookManagerError::NoSuchHook(hook_name.to_string()))?;
+
+        let futs = FuturesUnordered::new();
+        for cs in changesets {
+            let mut scuba = self.scuba.clone();
+            scuba.add("hook", hook_name.to_string());
+            scuba.add("hash", cs.get_changeset_id().to_string());
+            for future in hook.get_futures(
+                ctx,
+                bookmark,
+                &*self.content_provider,
+                hook_name,
+                cs,
+                scuba,
+                cross_repo_push_source,
+                push_authored_by,
+            ) {
+                futs.push(future);
+            }
+        }
+        futs.try_collect().await
+    }
+
+    /// Run the hooks for the bookmark that only check the commit message
+    /// against a message that has not been committed yet, as if a
+    /// changeset with that message were being pushed to the bookmark.
+    /// Hooks bypassed by the message are skipped, as they would be on push.
+    ///
+    /// Returns the names of the hooks that were run, and their outcomes.
+    pub async fn run_message_hooks_for_bookmark(
+        &self,
+        ctx: &CoreContext,
+        message: &str,
+        bookmark: &BookmarkKey,
+        push_authored_by: PushAuthoredBy,
+    ) -> Result<(Vec<String>, Vec<HookOutcome>), Error> {
+        debug!(
+            ctx.logger(),
+            "Running message hooks for bookmark {:?}", bookmark
+        );
+
+        // The hooks only look at the message, so the rest of the changeset
+        // can be empty.
+        let cs = BonsaiChangesetMut {
+            author: ctx.metadata().unix_name().unwrap_or_default().to_string(),
+            message: message.to_string(),
+            ..Default::default()
+        }
+        .freeze()?;
+
+        let mut hooks_run = Vec::new();
+        let futs = FuturesUnordered::new();
+        for hook_name in self.hooks_for_bookmark(bookmark) {
+            let hook = self
+                .hooks
+                .get(hook_name)
+                .ok_or_else(|| HookManagerError::NoSuchHook(hook_name.to_string()))?;
+            if !hook.checks_message_only()
+                || get_bypass_reason(hook.get_config().bypass.as_ref(), message, None).is_some()
+            {
+                continue;
+            }
+
+            let mut scuba = self.scuba.clone();
+            scuba.add("hook", hook_name.to_string());
+            scuba.add("message_preview", true);
+            hooks_run.push(hook_name.to_string());
+            for future in hook.get_futures(
+                ctx,
+                bookmark,
+                &*self.content_provider,
+                hook_name,
+                &cs,
+                scuba,
+                CrossRepoPushSource::NativeToThisRepo,
+                push_authored_by,
+            ) {
+                futs.push(future);
+            }
+        }
+        let outcomes = futs.try_collect().await?;
+        Ok((hooks_run, outcomes))
+    }
 }
 
 fn get_bypass_reason(
@@ -419,6 +543,13 @@ impl Hook {
         }
     }
 
+    fn checks_message_only(&self) -> bool {
+        match self {
+            Self::Changeset(hook, _) => hook.checks_message_only(),
+            Self::File(..) => false,
+        }
+    }
+
     pub fn get_futures<'a: 'cs, 'cs>(
         &'a self,
         ctx: &'a CoreContext,
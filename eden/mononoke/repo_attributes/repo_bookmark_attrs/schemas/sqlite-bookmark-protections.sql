@@ -0,0 +1,13 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+CREATE TABLE IF NOT EXISTS `bookmark_protections` (
+  `repo_id` INTEGER NOT NULL,
+  `name` VARCHAR(512) NOT NULL,
+  `allowed_pusher` VARCHAR(255) NOT NULL,
+  PRIMARY KEY (`repo_id`, `name`, `allowed_pusher`)
+);
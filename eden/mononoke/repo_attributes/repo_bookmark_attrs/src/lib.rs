@@ -9,18 +9,33 @@
 //!
 //! Stores configuration and permission checkers for bookmarks
 
+mod protections;
+
+use std::fmt;
+
 use anyhow::bail;
 use anyhow::Result;
 use bookmarks_types::BookmarkKey;
 use context::CoreContext;
+use metaconfig_types::BookmarkOrRegex;
 use metaconfig_types::BookmarkParams;
 use permission_checker::AclProvider;
 use permission_checker::BoxMembershipChecker;
+use stats::prelude::*;
+
+pub use crate::protections::BookmarkProtections;
+pub use crate::protections::SqlBookmarkProtections;
+
+define_stats! {
+    prefix = "mononoke.bookmark_attrs";
+    protections_check_failed: timeseries(Sum),
+}
 
 /// Repository bookmark attributes.
 #[facet::facet]
 pub struct RepoBookmarkAttrs {
     bookmark_attrs: Vec<BookmarkAttr>,
+    protections: Option<BookmarkProtections>,
 }
 
 impl RepoBookmarkAttrs {
@@ -34,7 +49,10 @@ impl RepoBookmarkAttrs {
             let attr = BookmarkAttr::new(acl_provider, params).await?;
             bookmark_attrs.push(attr);
         }
-        Ok(RepoBookmarkAttrs { bookmark_attrs })
+        Ok(RepoBookmarkAttrs {
+            bookmark_attrs,
+            protections: None,
+        })
     }
 
     /// Construct a new RepoBookmarkAttrs for testing.
@@ -46,7 +64,29 @@ impl RepoBookmarkAttrs {
             let attr = BookmarkAttr::new_test(params)?;
             bookmark_attrs.push(attr);
         }
-        Ok(RepoBookmarkAttrs { bookmark_attrs })
+        Ok(RepoBookmarkAttrs {
+            bookmark_attrs,
+            protections: None,
+        })
+    }
+
+    /// Also check protections registered in the metadata database, for the
+    /// bookmarks configured with `runtime_protections`.
+    pub fn with_protections(mut self, protections: BookmarkProtections) -> Self {
+        self.protections = Some(protections);
+        self
+    }
+
+    /// Protections registered in the metadata database, if available.
+    pub fn protections(&self) -> Option<&BookmarkProtections> {
+        self.protections.as_ref()
+    }
+
+    /// Whether protections registered at runtime are checked for the
+    /// bookmark, and so can be registered for it.
+    pub fn has_runtime_protections(&self, bookmark: &BookmarkKey) -> bool {
+        self.select(bookmark)
+            .any(|attr| attr.params().runtime_protections)
     }
 
     /// Select bookmark params matching provided bookmark
@@ -84,7 +124,24 @@ impl RepoBookmarkAttrs {
         ctx: &CoreContext,
         unixname: &str,
         bookmark: &BookmarkKey,
-    ) -> bool {
+    ) -> Result<bool> {
+        Ok(self
+            .denying_policy(ctx, unixname, bookmark)
+            .await?
+            .is_none())
+    }
+
+    /// Find the policy that prevents the user from moving the specified
+    /// bookmark, or `None` if the user is allowed to move it.
+    ///
+    /// Fails if the bookmark has runtime protections and they couldn't be
+    /// checked, as the user may not be allowed to move it.
+    pub async fn denying_policy(
+        &self,
+        ctx: &CoreContext,
+        unixname: &str,
+        bookmark: &BookmarkKey,
+    ) -> Result<Option<BookmarkPolicy>> {
         for attr in self.select(bookmark) {
             let maybe_allowed = attr
                 .params()
@@ -109,10 +166,65 @@ impl RepoBookmarkAttrs {
                 (None, None) => true,
             };
             if !allowed {
-                return false;
+                return Ok(Some(attr.policy()));
+            }
+        }
+        let protections = self
+            .protections
+            .as_ref()
+            .filter(|_| self.has_runtime_protections(bookmark));
+        if let Some(protections) = protections {
+            let allowed_pushers = protections.allowed_pushers(bookmark).await.map_err(|e| {
+                STATS::protections_check_failed.add_value(1);
+                e.context(format!("Failed to check protections for {}", bookmark))
+            })?;
+            if let Some(allowed_pushers) = allowed_pushers {
+                if !allowed_pushers.iter().any(|pusher| pusher == unixname) {
+                    return Ok(Some(BookmarkPolicy {
+                        name: bookmark.to_string(),
+                        allowed_pushers,
+                        ..Default::default()
+                    }));
+                }
             }
         }
-        true
+        Ok(None)
+    }
+}
+
+/// A bookmark protection policy, and who it allows to move the bookmark.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BookmarkPolicy {
+    /// Name of the policy.  This is the bookmark or bookmark pattern it is
+    /// configured for in the repo config, or the bookmark for protections
+    /// registered in the metadata database.
+    pub name: String,
+    /// Users matching this pattern are allowed.
+    pub allowed_users: Option<String>,
+    /// Members of this hipster group are allowed.
+    pub allowed_group: Option<String>,
+    /// These users are allowed.
+    pub allowed_pushers: Vec<String>,
+}
+
+impl fmt::Display for BookmarkPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut allowed = Vec::new();
+        if let Some(allowed_users) = &self.allowed_users {
+            allowed.push(format!("users matching '{}'", allowed_users));
+        }
+        if let Some(allowed_group) = &self.allowed_group {
+            allowed.push(format!("members of group '{}'", allowed_group));
+        }
+        if !self.allowed_pushers.is_empty() {
+            allowed.push(format!("users {}", self.allowed_pushers.join(", ")));
+        }
+        write!(f, "bookmark policy '{}' allows ", self.name)?;
+        if allowed.is_empty() {
+            f.write_str("no one")
+        } else {
+            f.write_str(&allowed.join(" or "))
+        }
     }
 }
 
@@ -150,4 +262,99 @@ impl BookmarkAttr {
     pub fn membership(&self) -> Option<&BoxMembershipChecker> {
         self.membership.as_ref()
     }
+
+    /// The protection policy these attributes describe.
+    pub fn policy(&self) -> BookmarkPolicy {
+        let name = match &self.params.bookmark {
+            BookmarkOrRegex::Bookmark(bookmark) => bookmark.to_string(),
+            BookmarkOrRegex::Regex(regex) => regex.as_str().to_string(),
+        };
+        BookmarkPolicy {
+            name,
+            allowed_users: self
+                .params
+                .allowed_users
+                .as_ref()
+                .map(|regex| regex.as_str().to_string()),
+            allowed_group: self.params.allowed_hipster_group.clone(),
+            allowed_pushers: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fbinit::FacebookInit;
+    use mononoke_types::RepositoryId;
+    use sql::Connection;
+    use sql_construct::SqlConstruct;
+    use sql_ext::open_sqlite_in_memory;
+    use sql_ext::SqlConnections;
+
+    use super::*;
+
+    fn params(bookmark: &str, runtime_protections: bool) -> BookmarkParams {
+        BookmarkParams {
+            bookmark: BookmarkKey::new(bookmark).unwrap().into(),
+            hooks: vec![],
+            only_fast_forward: false,
+            rewrite_dates: None,
+            allowed_users: None,
+            allowed_hipster_group: None,
+            hooks_skip_ancestors_of: vec![],
+            ensure_ancestor_of: None,
+            allow_move_to_public_commits_without_hooks: false,
+            runtime_protections,
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_runtime_protections(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let protections = BookmarkProtections::new(
+            SqlBookmarkProtections::with_sqlite_in_memory()?,
+            RepositoryId::new(0),
+        );
+        let release = BookmarkKey::new("release")?;
+        let other = BookmarkKey::new("other")?;
+        protections
+            .add_allowed_pushers(&release, &["alice".to_string()])
+            .await?;
+        protections
+            .add_allowed_pushers(&other, &["alice".to_string()])
+            .await?;
+        let attrs = RepoBookmarkAttrs::new_test([params("release", true), params("other", false)])?
+            .with_protections(protections);
+
+        assert!(attrs.has_runtime_protections(&release));
+        assert!(!attrs.has_runtime_protections(&other));
+        assert!(attrs.is_allowed_user(&ctx, "alice", &release).await?);
+        let policy = attrs.denying_policy(&ctx, "bob", &release).await?;
+        assert_eq!(
+            policy.map(|policy| policy.allowed_pushers),
+            Some(vec!["alice".to_string()])
+        );
+        // Protections are only checked for bookmarks configured for them.
+        assert!(attrs.is_allowed_user(&ctx, "bob", &other).await?);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_runtime_protections_unavailable(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        // Without the protections table every lookup fails.
+        let connection = Connection::with_sqlite(open_sqlite_in_memory()?);
+        let sql =
+            SqlBookmarkProtections::from_sql_connections(SqlConnections::new_single(connection));
+        let attrs = RepoBookmarkAttrs::new_test([params("release", true), params("other", false)])?
+            .with_protections(BookmarkProtections::new(sql, RepositoryId::new(0)));
+
+        // Moves of protected bookmarks are denied if the check fails.
+        let release = BookmarkKey::new("release")?;
+        assert!(attrs.is_allowed_user(&ctx, "bob", &release).await.is_err());
+        // Other bookmarks don't depend on the database.
+        let other = BookmarkKey::new("other")?;
+        assert!(attrs.is_allowed_user(&ctx, "bob", &other).await?);
+        Ok(())
+    }
 }
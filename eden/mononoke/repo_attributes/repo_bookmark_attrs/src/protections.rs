@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Bookmark protections stored in the metadata database
+//!
+//! These complement the bookmark attributes in the repo config with
+//! protections registered at runtime, e.g. when a release branch is cut.
+
+use anyhow::Result;
+use bookmarks_types::BookmarkKey;
+use bookmarks_types::BookmarkName;
+use mononoke_types::RepositoryId;
+use sql::Connection;
+use sql::Transaction;
+use sql_construct::SqlConstruct;
+use sql_construct::SqlConstructFromMetadataDatabaseConfig;
+use sql_ext::mononoke_queries;
+use sql_ext::SqlConnections;
+
+mononoke_queries! {
+    write AddAllowedPushers(values: (
+        repo_id: RepositoryId,
+        name: BookmarkName,
+        allowed_pusher: String,
+    )) {
+        insert_or_ignore,
+        "{insert_or_ignore} INTO bookmark_protections (repo_id, name, allowed_pusher) VALUES {values}"
+    }
+
+    read SelectAllowedPushers(repo_id: RepositoryId, name: BookmarkName) -> (String) {
+        "SELECT allowed_pusher FROM bookmark_protections
+         WHERE repo_id = {repo_id} AND name = {name}"
+    }
+}
+
+#[derive(Clone)]
+pub struct SqlBookmarkProtections {
+    write_connection: Connection,
+    read_connection: Connection,
+}
+
+impl SqlConstruct for SqlBookmarkProtections {
+    const LABEL: &'static str = "bookmark-protections";
+
+    const CREATION_QUERY: &'static str = include_str!("../schemas/sqlite-bookmark-protections.sql");
+
+    fn from_sql_connections(connections: SqlConnections) -> Self {
+        Self {
+            write_connection: connections.write_connection,
+            read_connection: connections.read_connection,
+        }
+    }
+}
+
+impl SqlConstructFromMetadataDatabaseConfig for SqlBookmarkProtections {}
+
+/// Protections registered for the bookmarks of a single repo.
+#[derive(Clone)]
+pub struct BookmarkProtections {
+    repo_id: RepositoryId,
+    sql: SqlBookmarkProtections,
+}
+
+impl BookmarkProtections {
+    pub fn new(sql: SqlBookmarkProtections, repo_id: RepositoryId) -> Self {
+        Self { repo_id, sql }
+    }
+
+    /// Users allowed to move the bookmark, or `None` if the bookmark has no
+    /// registered protections.
+    pub async fn allowed_pushers(&self, bookmark: &BookmarkKey) -> Result<Option<Vec<String>>> {
+        let rows =
+            SelectAllowedPushers::query(&self.sql.read_connection, &self.repo_id, bookmark.name())
+                .await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(rows.into_iter().map(|(pusher,)| pusher).collect()))
+    }
+
+    /// Register users allowed to move the bookmark.
+    pub async fn add_allowed_pushers(
+        &self,
+        bookmark: &BookmarkKey,
+        allowed_pushers: &[String],
+    ) -> Result<()> {
+        let txn = self.sql.write_connection.start_transaction().await?;
+        let txn = self
+            .add_allowed_pushers_in_transaction(txn, bookmark, allowed_pushers)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Register users allowed to move the bookmark as part of a larger
+    /// transaction, e.g. the one that creates the bookmark.
+    pub async fn add_allowed_pushers_in_transaction(
+        &self,
+        txn: Transaction,
+        bookmark: &BookmarkKey,
+        allowed_pushers: &[String],
+    ) -> Result<Transaction> {
+        let rows: Vec<_> = allowed_pushers
+            .iter()
+            .map(|pusher| (&self.repo_id, bookmark.name(), pusher))
+            .collect();
+        if rows.is_empty() {
+            return Ok(txn);
+        }
+        let (txn, _) = AddAllowedPushers::query_with_transaction(txn, &rows[..]).await?;
+        Ok(txn)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_allowed_pushers() -> Result<()> {
+        let sql = SqlBookmarkProtections::with_sqlite_in_memory()?;
+        let protections = BookmarkProtections::new(sql.clone(), RepositoryId::new(0));
+        let other_protections = BookmarkProtections::new(sql, RepositoryId::new(1));
+        let release = BookmarkKey::new("release/1.0")?;
+        let main = BookmarkKey::new("main")?;
+
+        assert_eq!(protections.allowed_pushers(&release).await?, None);
+
+        protections
+            .add_allowed_pushers(&release, &["alice".to_string(), "bob".to_string()])
+            .await?;
+        // Adding an existing pusher again is not an error.
+        protections
+            .add_allowed_pushers(&release, &["alice".to_string()])
+            .await?;
+
+        let mut pushers = protections.allowed_pushers(&release).await?.unwrap();
+        pushers.sort();
+        assert_eq!(pushers, vec!["alice".to_string(), "bob".to_string()]);
+        assert_eq!(protections.allowed_pushers(&main).await?, None);
+        assert_eq!(other_protections.allowed_pushers(&release).await?, None);
+
+        Ok(())
+    }
+}
@@ -15,6 +15,7 @@ use metaconfig_types::RepoConfigRef;
 use mononoke_types::path::MPath;
 use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
+use repo_bookmark_attrs::BookmarkPolicy;
 use repo_bookmark_attrs::RepoBookmarkAttrsRef;
 use repo_permission_checker::RepoPermissionCheckerRef;
 
@@ -23,6 +24,7 @@ use crate::error::DeniedAction;
 use crate::error::PermissionDenied;
 
 const GIT_IMPORT_SVC_WRITE_METHOD: &str = "git_import_operations";
+const RUN_HOOK_ON_HISTORY_SVC_WRITE_METHOD: &str = "repo_run_hook_on_history";
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum AuthorizationContext {
@@ -92,6 +94,7 @@ impl AuthorizationContext {
             denied_action,
             context: self.clone(),
             identities: ctx.metadata().identities().clone(),
+            bookmark_policy: None,
         })
     }
 
@@ -388,25 +391,47 @@ impl AuthorizationContext {
         repo: &(impl RepoConfigRef + RepoBookmarkAttrsRef),
         bookmark: &BookmarkKey,
     ) -> AuthorizationCheckOutcome {
-        let permitted = match self {
-            AuthorizationContext::FullAccess => true,
+        // If the bookmark's protections can't be checked then the user may
+        // not be allowed to modify it.
+        self.check_bookmark_modify_with_policy(ctx, repo, bookmark)
+            .await
+            .map_or(AuthorizationCheckOutcome::Denied, |(outcome, _)| outcome)
+    }
+
+    /// Check whether the user is allowed to modify a particular bookmark,
+    /// along with the bookmark policy that denied them, if any.
+    async fn check_bookmark_modify_with_policy(
+        &self,
+        ctx: &CoreContext,
+        repo: &(impl RepoConfigRef + RepoBookmarkAttrsRef),
+        bookmark: &BookmarkKey,
+    ) -> Result<(AuthorizationCheckOutcome, Option<BookmarkPolicy>)> {
+        let (permitted, bookmark_policy) = match self {
+            AuthorizationContext::FullAccess => (true, None),
             AuthorizationContext::Identity | AuthorizationContext::DraftOnlyIdentity => {
                 let user = ctx.metadata().unix_name().unwrap_or("svcscm");
-                repo.repo_bookmark_attrs()
-                    .is_allowed_user(ctx, user, bookmark)
-                    .await
+                let bookmark_policy = repo
+                    .repo_bookmark_attrs()
+                    .denying_policy(ctx, user, bookmark)
+                    .await?;
+                (bookmark_policy.is_none(), bookmark_policy)
 
                 // TODO: Check using ctx.identities, and deny if neither are provided.
             }
             AuthorizationContext::Service(service_name) => {
                 // Check this service is permitted to modify this bookmark.
-                repo.repo_config()
+                let permitted = repo
+                    .repo_config()
                     .source_control_service
-                    .service_write_bookmark_permitted(service_name, bookmark)
+                    .service_write_bookmark_permitted(service_name, bookmark);
+                (permitted, None)
             }
-            AuthorizationContext::ReadOnlyIdentity => false,
+            AuthorizationContext::ReadOnlyIdentity => (false, None),
         };
-        AuthorizationCheckOutcome::from_permitted(permitted)
+        Ok((
+            AuthorizationCheckOutcome::from_permitted(permitted),
+            bookmark_policy,
+        ))
     }
 
     /// Require that the user is allowed to modify (create, update or delete)
@@ -417,11 +442,17 @@ impl AuthorizationContext {
         repo: &(impl RepoConfigRef + RepoBookmarkAttrsRef),
         bookmark: &BookmarkKey,
     ) -> Result<(), AuthorizationError> {
-        self.check_bookmark_modify(ctx, repo, bookmark)
-            .await
-            .permitted_or_else(|| {
-                self.permission_denied(ctx, DeniedAction::BookmarkModification(bookmark.clone()))
+        let (outcome, bookmark_policy) = self
+            .check_bookmark_modify_with_policy(ctx, repo, bookmark)
+            .await?;
+        outcome.permitted_or_else(|| {
+            AuthorizationError::from(PermissionDenied {
+                denied_action: DeniedAction::BookmarkModification(bookmark.clone()),
+                context: self.clone(),
+                identities: ctx.metadata().identities().clone(),
+                bookmark_policy: bookmark_policy.clone(),
             })
+        })
     }
 
     /// Check whether the user is allowed to set the Git mapping for a
@@ -503,6 +534,48 @@ impl AuthorizationContext {
             .await
             .permitted_or_else(|| self.permission_denied(ctx, DeniedAction::GitImportOperation))
     }
+
+    /// Check whether the caller is allowed to run hooks against existing
+    /// commits in the repo's history.
+    pub async fn check_run_hook_on_history(
+        &self,
+        _ctx: &CoreContext,
+        repo: &impl RepoConfigRef,
+    ) -> AuthorizationCheckOutcome {
+        let permitted = match self {
+            AuthorizationContext::FullAccess => true,
+            AuthorizationContext::Identity => {
+                // Users are never allowed to do this.
+                false
+            }
+            AuthorizationContext::Service(service_name) => {
+                // Services are allowed to do this if they are configured to
+                // allow the method.
+                repo.repo_config()
+                    .source_control_service
+                    .service_write_method_permitted(
+                        service_name,
+                        RUN_HOOK_ON_HISTORY_SVC_WRITE_METHOD,
+                    )
+            }
+            AuthorizationContext::ReadOnlyIdentity | AuthorizationContext::DraftOnlyIdentity => {
+                false
+            }
+        };
+        AuthorizationCheckOutcome::from_permitted(permitted)
+    }
+
+    /// Require that the caller is allowed to run hooks against existing
+    /// commits in the repo's history.
+    pub async fn require_run_hook_on_history(
+        &self,
+        ctx: &CoreContext,
+        repo: &impl RepoConfigRef,
+    ) -> Result<(), AuthorizationError> {
+        self.check_run_hook_on_history(ctx, repo)
+            .await
+            .permitted_or_else(|| self.permission_denied(ctx, DeniedAction::RunHookOnHistory))
+    }
 }
 
 /// Write operations that can be performed on a repo.
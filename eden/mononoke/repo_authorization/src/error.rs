@@ -13,6 +13,7 @@ use mononoke_types::path::MPath;
 use mononoke_types::ChangesetId;
 use mononoke_types::NonRootMPath;
 use permission_checker::MononokeIdentitySet;
+use repo_bookmark_attrs::BookmarkPolicy;
 use thiserror::Error;
 
 use crate::context::AuthorizationContext;
@@ -29,6 +30,7 @@ pub enum DeniedAction {
     BookmarkModification(BookmarkKey),
     OverrideGitMapping,
     GitImportOperation,
+    RunHookOnHistory,
 }
 
 impl fmt::Display for DeniedAction {
@@ -57,6 +59,7 @@ impl fmt::Display for DeniedAction {
             DeniedAction::GitImportOperation => {
                 f.write_str("Access for Git-import related operations")
             }
+            DeniedAction::RunHookOnHistory => f.write_str("Running hooks against repo history"),
         }
     }
 }
@@ -66,6 +69,15 @@ pub struct PermissionDenied {
     pub(crate) denied_action: DeniedAction,
     pub(crate) context: AuthorizationContext,
     pub(crate) identities: MononokeIdentitySet,
+    pub(crate) bookmark_policy: Option<BookmarkPolicy>,
+}
+
+impl PermissionDenied {
+    /// The bookmark policy that denied the action, if it was a bookmark
+    /// modification denied by the bookmark's protections.
+    pub fn bookmark_policy(&self) -> Option<&BookmarkPolicy> {
+        self.bookmark_policy.as_ref()
+    }
 }
 
 impl fmt::Display for PermissionDenied {
@@ -80,7 +92,11 @@ impl fmt::Display for PermissionDenied {
             write!(f, "{}{}", delim, id)?;
             delim = ", ";
         }
-        f.write_str("]")
+        f.write_str("]")?;
+        if let Some(bookmark_policy) = &self.bookmark_policy {
+            write!(f, ": {}", bookmark_policy)?;
+        }
+        Ok(())
     }
 }
 
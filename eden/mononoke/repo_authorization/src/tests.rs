@@ -18,16 +18,21 @@ use fbinit::FacebookInit;
 use futures::FutureExt;
 use maplit::hashmap;
 use maplit::hashset;
+use metaconfig_types::BookmarkParams;
+use metaconfig_types::ComparableRegex;
 use metaconfig_types::RepoConfig;
 use metaconfig_types::ServiceWriteRestrictions;
 use mononoke_types::PrefixTrie;
 use permission_checker::MononokeIdentitySet;
+use regex::Regex;
+use repo_bookmark_attrs::BookmarkPolicy;
 use repo_bookmark_attrs::RepoBookmarkAttrs;
 use repo_permission_checker::RepoPermissionChecker;
 use tunables::with_tunables_async;
 use tunables::MononokeTunables;
 
 use crate::AuthorizationContext;
+use crate::AuthorizationError;
 use crate::RepoWriteOperation;
 
 #[facet::container]
@@ -398,6 +403,65 @@ async fn test_service_access(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_user_bookmark_policy(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let checker = Arc::new(TestPermissionChecker {
+        read: true,
+        draft: true,
+        write: true,
+        ..Default::default()
+    });
+    let repo: Repo = test_repo_factory::TestRepoFactory::new(fb)?
+        .with_permission_checker(checker)
+        .with_config_override(|config| {
+            config.bookmarks = vec![BookmarkParams {
+                bookmark: BookmarkKey::new("main").unwrap().into(),
+                hooks: vec![],
+                only_fast_forward: false,
+                rewrite_dates: None,
+                allowed_users: Some(ComparableRegex::new(Regex::new("^alice$").unwrap())),
+                allowed_hipster_group: None,
+                hooks_skip_ancestors_of: vec![],
+                ensure_ancestor_of: None,
+                allow_move_to_public_commits_without_hooks: false,
+                runtime_protections: false,
+            }];
+        })
+        .build()
+        .await?;
+    let authz = AuthorizationContext::new(&ctx);
+
+    // Bookmarks without a policy can be modified.
+    authz
+        .require_bookmark_modify(&ctx, &repo, &BookmarkKey::new("other")?)
+        .await?;
+
+    // The error for main says which policy denied the modification.
+    match authz
+        .require_bookmark_modify(&ctx, &repo, &BookmarkKey::new("main")?)
+        .await
+    {
+        Err(AuthorizationError::PermissionDenied(e)) => {
+            assert_eq!(
+                e.bookmark_policy(),
+                Some(&BookmarkPolicy {
+                    name: String::from("main"),
+                    allowed_users: Some(String::from("^alice$")),
+                    ..Default::default()
+                })
+            );
+            assert!(
+                e.to_string()
+                    .ends_with("bookmark policy 'main' allows users matching '^alice$'")
+            );
+        }
+        other => panic!("expected permission denied, got {:?}", other),
+    }
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn test_user_readonly_instance(fb: FacebookInit) -> () {
     let ctx_session = SessionContainer::builder(fb).readonly(true).build();
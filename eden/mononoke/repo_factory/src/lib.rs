@@ -135,7 +135,9 @@ use repo_blobstore::ArcRepoBlobstoreUnlinkOps;
 use repo_blobstore::RepoBlobstore;
 use repo_blobstore::RepoBlobstoreUnlinkOps;
 use repo_bookmark_attrs::ArcRepoBookmarkAttrs;
+use repo_bookmark_attrs::BookmarkProtections;
 use repo_bookmark_attrs::RepoBookmarkAttrs;
+use repo_bookmark_attrs::SqlBookmarkProtections;
 use repo_cross_repo::ArcRepoCrossRepo;
 use repo_cross_repo::RepoCrossRepo;
 use repo_derived_data::ArcRepoDerivedData;
@@ -160,6 +162,7 @@ use scuba_ext::MononokeScubaSampleBuilder;
 use segmented_changelog::new_server_segmented_changelog;
 use segmented_changelog::new_server_segmented_changelog_manager;
 use segmented_changelog::ArcSegmentedChangelogManager;
+use segmented_changelog::SegmentedChangelogReloadScheduler;
 use segmented_changelog::SegmentedChangelogSqlConnections;
 use segmented_changelog_types::ArcSegmentedChangelog;
 use slog::o;
@@ -241,6 +244,7 @@ pub struct RepoFactory {
     scrub_handler: Arc<dyn ScrubHandler>,
     blobstore_component_sampler: Option<Arc<dyn ComponentSamplingHandler>>,
     bonsai_hg_mapping_overwrite: bool,
+    segmented_changelog_reload_scheduler: SegmentedChangelogReloadScheduler,
 }
 
 impl RepoFactory {
@@ -254,6 +258,7 @@ impl RepoFactory {
             scrub_handler: default_scrub_handler(),
             blobstore_component_sampler: None,
             bonsai_hg_mapping_overwrite: false,
+            segmented_changelog_reload_scheduler: SegmentedChangelogReloadScheduler::default(),
             env,
         }
     }
@@ -292,6 +297,14 @@ impl RepoFactory {
         self
     }
 
+    pub fn with_segmented_changelog_reload_scheduler(
+        &mut self,
+        scheduler: SegmentedChangelogReloadScheduler,
+    ) -> &mut Self {
+        self.segmented_changelog_reload_scheduler = scheduler;
+        self
+    }
+
     pub async fn sql_factory(
         &self,
         config: &MetadataDatabaseConfig,
@@ -1041,13 +1054,19 @@ impl RepoFactory {
         repo_config: &ArcRepoConfig,
         repo_identity: &ArcRepoIdentity,
         changeset_fetcher: &ArcChangesetFetcher,
+        commit_graph: &ArcCommitGraph,
         bookmarks: &ArcBookmarks,
         repo_blobstore: &ArcRepoBlobstore,
     ) -> Result<ArcSegmentedChangelog> {
-        let sql_connections = self
-            .open_sql::<SegmentedChangelogSqlConnections>(repo_config)
-            .await
-            .context(RepoFactoryError::SegmentedChangelog)?;
+        let sql_factory = self
+            .sql_factory(&repo_config.storage_config.metadata)
+            .await?;
+        let sql_connections = SegmentedChangelogSqlConnections::open(
+            &sql_factory,
+            &repo_config.segmented_changelog_config,
+        )
+        .await
+        .context(RepoFactoryError::SegmentedChangelog)?;
         let cache_handler_factory = self.cache_handler_factory("segmented_changelog")?;
         let segmented_changelog = new_server_segmented_changelog(
             &self.ctx(Some(repo_identity)),
@@ -1055,9 +1074,11 @@ impl RepoFactory {
             repo_config.segmented_changelog_config.clone(),
             sql_connections,
             changeset_fetcher.clone(),
+            commit_graph.clone(),
             bookmarks.clone(),
             repo_blobstore.clone(),
             cache_handler_factory,
+            Some(&self.segmented_changelog_reload_scheduler),
         )
         .await
         .context(RepoFactoryError::SegmentedChangelog)?;
@@ -1072,10 +1093,15 @@ impl RepoFactory {
         bookmarks: &ArcBookmarks,
         repo_blobstore: &ArcRepoBlobstore,
     ) -> Result<ArcSegmentedChangelogManager> {
-        let sql_connections = self
-            .open_sql::<SegmentedChangelogSqlConnections>(repo_config)
-            .await
-            .context(RepoFactoryError::SegmentedChangelogManager)?;
+        let sql_factory = self
+            .sql_factory(&repo_config.storage_config.metadata)
+            .await?;
+        let sql_connections = SegmentedChangelogSqlConnections::open(
+            &sql_factory,
+            &repo_config.segmented_changelog_config,
+        )
+        .await
+        .context(RepoFactoryError::SegmentedChangelogManager)?;
         let cache_handler_factory = self.cache_handler_factory("segmented_changelog")?;
         let manager = new_server_segmented_changelog_manager(
             &self.ctx(Some(repo_identity)),
@@ -1428,13 +1454,33 @@ impl RepoFactory {
     pub async fn repo_bookmark_attrs(
         &self,
         repo_config: &ArcRepoConfig,
+        repo_identity: &ArcRepoIdentity,
     ) -> Result<ArcRepoBookmarkAttrs> {
-        let repo_bookmark_attrs = RepoBookmarkAttrs::new(
+        let mut repo_bookmark_attrs = RepoBookmarkAttrs::new(
             self.env.acl_provider.as_ref(),
             repo_config.bookmarks.clone(),
         )
         .await
         .context(RepoFactoryError::RepoBookmarkAttrs)?;
+        // Only repos with bookmarks configured for runtime protections need
+        // the protections table.
+        if repo_config
+            .bookmarks
+            .iter()
+            .any(|params| params.runtime_protections)
+        {
+            let sql_protections = SqlBookmarkProtections::with_metadata_database_config(
+                self.env.fb,
+                &repo_config.storage_config.metadata,
+                &self.env.mysql_options,
+                self.env.readonly_storage.0,
+            )
+            .context(RepoFactoryError::RepoBookmarkAttrs)?;
+            repo_bookmark_attrs = repo_bookmark_attrs.with_protections(BookmarkProtections::new(
+                sql_protections,
+                repo_identity.id(),
+            ));
+        }
         Ok(Arc::new(repo_bookmark_attrs))
     }
 
@@ -97,7 +97,9 @@ use rendezvous::RendezVousOptions;
 use repo_blobstore::ArcRepoBlobstore;
 use repo_blobstore::RepoBlobstore;
 use repo_bookmark_attrs::ArcRepoBookmarkAttrs;
+use repo_bookmark_attrs::BookmarkProtections;
 use repo_bookmark_attrs::RepoBookmarkAttrs;
+use repo_bookmark_attrs::SqlBookmarkProtections;
 use repo_cross_repo::ArcRepoCrossRepo;
 use repo_cross_repo::RepoCrossRepo;
 use repo_derived_data::ArcRepoDerivedData;
@@ -279,6 +281,7 @@ impl TestRepoFactory {
         metadata_con.execute_batch(SqlSyncedCommitMapping::CREATION_QUERY)?;
         metadata_con.execute_batch(SegmentedChangelogSqlConnections::CREATION_QUERY)?;
         metadata_con.execute_batch(SqlRepoLock::CREATION_QUERY)?;
+        metadata_con.execute_batch(SqlBookmarkProtections::CREATION_QUERY)?;
         metadata_con.execute_batch(SqlSparseProfilesSizes::CREATION_QUERY)?;
         metadata_con.execute_batch(StreamingCloneBuilder::CREATION_QUERY)?;
         metadata_con.execute_batch(SqlCommitGraphStorageBuilder::CREATION_QUERY)?;
@@ -812,10 +815,19 @@ impl TestRepoFactory {
     }
 
     /// Repo bookmark attrs
-    pub fn repo_bookmark_attrs(&self, repo_config: &ArcRepoConfig) -> Result<ArcRepoBookmarkAttrs> {
-        Ok(Arc::new(RepoBookmarkAttrs::new_test(
-            repo_config.bookmarks.clone(),
-        )?))
+    pub fn repo_bookmark_attrs(
+        &self,
+        repo_config: &ArcRepoConfig,
+        repo_identity: &ArcRepoIdentity,
+    ) -> Result<ArcRepoBookmarkAttrs> {
+        let protections = BookmarkProtections::new(
+            SqlBookmarkProtections::from_sql_connections(self.metadata_db.clone()),
+            repo_identity.id(),
+        );
+        Ok(Arc::new(
+            RepoBookmarkAttrs::new_test(repo_config.bookmarks.clone())?
+                .with_protections(protections),
+        ))
     }
 
     /// Streaming clone
@@ -1376,6 +1376,7 @@ async fn tail_segmented_changelog(
         repo.as_blob_repo(),
         storage_config_metadata,
         mysql_options,
+        segmented_changelog_config,
         seed_heads,
         stream::empty(), // no prefetched commits
         None,            // no caching
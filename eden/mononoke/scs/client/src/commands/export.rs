@@ -315,6 +315,7 @@ async fn export_tree(
     let tree = thrift::TreeSpecifier::by_id(thrift::TreeIdSpecifier {
         repo,
         id,
+        path_hint: Some(path.clone()),
         ..Default::default()
     });
     let params = thrift::TreeListParams {
@@ -11,6 +11,7 @@ use std::io::Write;
 use anyhow::Result;
 use chrono::naive::NaiveDateTime;
 use chrono::DateTime;
+use clap::ValueEnum;
 use source_control::types as thrift;
 
 use crate::args::commit_id::resolve_commit_ids;
@@ -23,6 +24,13 @@ use crate::library::commit::CommitInfo as CommitInfoOutput;
 use crate::render::Render;
 use crate::ScscApp;
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum HistoryOrder {
+    Topological,
+    ReverseChronological,
+    AuthorDate,
+}
+
 /// Show the history of a commit or a path in a commit
 ///
 /// If a second commit id is provided, the results are limited to descendants
@@ -61,6 +69,9 @@ pub(super) struct CommandArgs {
     #[clap(long)]
     /// Follow mutable overrides to the history that make it more user friendly and 'correct'
     follow_mutable_history: bool,
+    #[clap(long, value_enum, conflicts_with = "path")]
+    /// The order in which to show commits (only for commit history)
+    order: Option<HistoryOrder>,
 }
 
 struct LogOutput {
@@ -138,6 +149,11 @@ pub(super) async fn run(app: ScscApp, args: CommandArgs) -> Result<()> {
     let follow_history_across_deletions = args.history_across_deletions;
     let follow_mutable_file_history = Some(args.follow_mutable_history);
     let identity_schemes = args.scheme_args.clone().into_request_schemes();
+    let order = args.order.map(|order| match order {
+        HistoryOrder::Topological => thrift::HistoryOrder::TOPOLOGICAL,
+        HistoryOrder::ReverseChronological => thrift::HistoryOrder::REVERSE_CHRONOLOGICAL,
+        HistoryOrder::AuthorDate => thrift::HistoryOrder::AUTHOR_DATE,
+    });
 
     let response = match path {
         Some(path) => {
@@ -173,6 +189,7 @@ pub(super) async fn run(app: ScscApp, args: CommandArgs) -> Result<()> {
                 identity_schemes,
                 descendants_of,
                 exclude_changeset_and_ancestors: None,
+                order,
                 ..Default::default()
             };
             conn.commit_history(&commit, &params).await?.history
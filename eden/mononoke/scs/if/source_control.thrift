@@ -60,6 +60,9 @@ enum CommitIdentityScheme {
   EPHEMERAL_BONSAI = 6,
 }
 
+/// Maximum number of identity schemes in a single set of a request.
+const i64 IDENTITY_SCHEMES_MAX_COUNT = 16;
+
 /// A commit stored in an ephemeral bubble
 struct EphemeralBonsai {
   1: binary bonsai_id;
@@ -108,6 +111,9 @@ struct CommitSpecifier {
 /// The UTF-8 path of the file or directory.
 typedef string Path
 
+/// Maximum length in bytes of a path given in a request.
+const i64 PATH_MAX_LENGTH = 4096;
+
 /// Specifies a file or directory within a commit.
 struct CommitPathSpecifier {
   /// The commit within which the file or directory is found.
@@ -124,6 +130,11 @@ struct TreeIdSpecifier {
 
   /// The ID of the tree, obtained from a previous call to the service.
   2: binary id;
+
+  /// The path the tree was found at, if known.  This is logged, and is the
+  /// directory that `TreeListParams.path_regex` matches entry paths
+  /// within.  It is not checked against the tree.
+  3: optional Path path_hint;
 }
 
 union TreeSpecifier {
@@ -207,6 +218,68 @@ struct CommitInfo {
   /// Extra git headers associated with the commit if the commit is a
   /// mirrored version from a git repo.
   10: optional map<small_binary, binary_bytes> git_extra_headers;
+
+  /// How the commit changed the size of the repo, if requested with
+  /// `include_file_count_delta`.
+  11: optional CommitFileCountDelta file_count_delta;
+
+  /// Whether the commit is public or a draft.
+  12: CommitPhase phase;
+}
+
+enum CommitPhase {
+  /// The phase was not reported, e.g. by an older server.
+  UNKNOWN = 0,
+
+  /// The commit is a draft: it is not reachable from any publishing
+  /// bookmark.
+  DRAFT = 1,
+
+  /// The commit is public: it is reachable from a publishing bookmark.
+  PUBLIC = 2,
+}
+
+/// Change in the total file count and size of the repo made by a commit,
+/// relative to its first parent.  Computed from fsnode summaries.
+struct CommitFileCountDelta {
+  /// Change in the number of files in the repo.
+  1: i64 file_count_delta;
+
+  /// Change in the total size of all files in the repo, in bytes.
+  2: i64 total_size_delta;
+}
+
+/// A recorded Mercurial mutation, e.g. an amend or a rebase.
+///
+/// Mutations are recorded against Mercurial commit ids, and the predecessors
+/// may be commits that were never pushed, so all commit ids here are
+/// Mercurial ids.
+struct CommitMutation {
+  /// The commit produced by the mutation.
+  1: CommitId successor;
+
+  /// The commits that were mutated to produce the successor.  Usually a
+  /// single commit, but folds have several.
+  2: list<CommitId> predecessors;
+
+  /// The other commits produced alongside the successor when a commit was
+  /// split.
+  3: list<CommitId> split;
+
+  /// The operation that performed the mutation, e.g. "amend" or "rebase".
+  4: string operation;
+
+  /// The user that performed the mutation.
+  5: string user;
+
+  /// When the mutation was performed.
+  6: i64 date;
+
+  /// The timezone the mutation was performed in, in seconds after UTC.
+  7: i32 tz;
+
+  /// Extra metadata about the mutation.
+  8: map<string, string> extra;
 }
 
 struct BookmarkInfo {
@@ -220,6 +293,23 @@ struct BookmarkInfo {
   /// The timestamp of the last update. This is the update time when the "fresh"
   /// value provided was set.
   3: i64 last_update_timestamp_ns;
+  /// How far the "warm" value may be behind the "fresh" value.
+  4: WarmBookmarkStaleness warm_staleness;
+}
+
+/// How up to date a bookmark value served from the warm bookmarks cache is.
+struct WarmBookmarkStaleness {
+  /// How long the served value may have been behind the real value.  This is
+  /// the time since the bookmark moved to a commit that isn't warm yet, or
+  /// since the cache last checked the real value if it hasn't moved.
+  1: i64 age_ms;
+  /// When the cache last checked the real bookmark values.
+  2: i64 refreshed_timestamp_ns;
+  /// Whether the real bookmark has moved to a commit that isn't warm yet, so
+  /// the served value is behind the real value.
+  3: bool pending_move;
+  /// When the real bookmark moved to the commit that isn't warm yet, if known.
+  4: optional i64 pending_move_timestamp_ns;
 }
 
 enum EntryType {
@@ -337,6 +427,15 @@ struct TreeEntry {
 
   /// The info for the entry (file or sub-directory).
   3: EntryInfo info;
+
+  /// Whether the entry is an executable file.  Not set for links,
+  /// sub-directories or submodules.
+  4: optional bool is_executable;
+
+  /// The target of a symbolic link, if requested with
+  /// `include_link_target`.  Targets that are not valid UTF-8 are converted
+  /// lossily.
+  5: optional string link_target;
 }
 
 struct FilePathInfo {
@@ -373,14 +472,35 @@ struct CommitCompareFile {
   1: optional FilePathInfo base_file;
   2: optional FilePathInfo other_file;
   3: CopyInfo copy_info; /// Different than NONE only when commit is compared with parent
-}
-
+  /// Only the file's type changed (e.g. it became executable), not its content.
+  4: bool mode_only_change;
+  /// The file's content id differs between base_file and other_file, so it
+  /// must be fetched.  Always true for added and removed files.
+  5: bool content_changed;
+  /// The file's type differs between base_file and other_file (e.g. a
+  /// regular file became a symlink).  False for added and removed files.
+  6: bool type_changed;
+}
+
+/// A directory that differs between the commits, reported if TREES was
+/// specified.  Every directory containing a difference is reported, including
+/// the root directory, with the tree ids of both versions.  Clients mirroring
+/// a repo can use these to find what changed by descending from the root with
+/// `tree_list` by tree id, skipping any subtree whose id is unchanged.
 struct CommitCompareTree {
   1: optional TreePathInfo base_tree;
   2: optional TreePathInfo other_tree;
   3: CopyInfo copy_info; /// Different than NONE only when commit is compared with parent
 }
 
+/// How commit_compare reports files whose type changed but content did not.
+enum CommitCompareModeOnlyChanges {
+  /// Report them like any other change.
+  INCLUDE = 0,
+  /// Leave them out of the comparison.
+  EXCLUDE = 1,
+}
+
 enum CommitCompareItem {
   FILES = 0,
   TREES = 1,
@@ -500,6 +620,9 @@ enum CopyInfo {
 enum BlameFormat {
   /// Use the BlameCompact format.
   COMPACT = 1,
+
+  /// Use the BlameAnnotate format.
+  ANNOTATE = 2,
 }
 
 enum BlameFormatOption {
@@ -530,6 +653,7 @@ enum BlameFormatOption {
 
 union Blame {
   2: BlameCompact blame_compact;
+  3: BlameAnnotate blame_annotate;
 }
 
 /// Compact blame format uses look-up tables for items that might be repeated
@@ -588,6 +712,30 @@ struct BlameCompact {
   11: optional i32 distinct_range_count;
 }
 
+/// Default maximum total size in bytes of the lines in a BlameAnnotate.  The
+/// limit in effect is reported by service_limits.
+const i64 COMMIT_PATH_BLAME_ANNOTATE_SIZE_LIMIT = 0x1000000; /// 16MiB
+
+/// Annotate blame format has each line of the file pre-rendered for display,
+/// in the form:
+///
+///   <commit id> <author> <date>: <contents>
+///
+/// The commit id is the first 12 characters of the commit's id in the first
+/// of the requested identity schemes, or of the bonsai id if none were
+/// requested.  The date is the author date, as YYYY-MM-DD.
+///
+/// Format options are ignored.
+struct BlameAnnotate {
+  /// The rendered lines, in file order.
+  1: list<string> lines;
+
+  /// Whether the lines were cut short because the rendered blame would
+  /// exceed the commit_path_blame_annotate_size_limit service limit.  If so,
+  /// `lines` holds the start of the file only.
+  2: bool truncated;
+}
+
 struct BlameCompactLine {
   /// The current line number of this line.
   1: i32 line;
@@ -655,6 +803,25 @@ enum HistoryFormat {
   COMMIT_ID = 2,
 }
 
+/// The order in which commits are returned by history methods.
+///
+/// All orders are deterministic, so paging through history using `skip`
+/// returns consistent results.
+enum HistoryOrder {
+  /// Commits are returned before their ancestors.  Commits with the same
+  /// generation number are ordered by bonsai commit id.
+  TOPOLOGICAL = 0,
+
+  /// Most recent committer date first (author date is used for commits
+  /// with no committer date).  Ties are broken by generation number and
+  /// then by bonsai commit id.
+  REVERSE_CHRONOLOGICAL = 1,
+
+  /// Most recent author date first.  Ties are broken by generation number
+  /// and then by bonsai commit id.
+  AUTHOR_DATE = 2,
+}
+
 union History {
   1: list<CommitInfo> commit_infos;
   2: list<map<CommitIdentityScheme, CommitId>> commit_ids;
@@ -737,18 +904,150 @@ struct SparseProfileDeltaSizes {
   1: map<SparseProfileName, SparseProfileChange> size_changes;
 }
 
+struct SparseProfileFiles {
+  /// The rules the profile expands to once its %include chains have been
+  /// followed.  Each rule is followed by the profiles it came from.
+  1: list<string> rules;
+
+  /// Number of files in the commit matched by the profile.
+  2: i64 file_count;
+
+  /// Total size of the files in the commit matched by the profile.
+  3: i64 total_size;
+}
+
+/// Version of the service API.  This is bumped whenever methods or fields
+/// are added, so that clients can tell which definition a server was built
+/// from.
+const i64 SERVICE_API_VERSION = 6;
+
+/// Optional features of the service.  Servers are rolled out in stages, so
+/// clients should check for a feature with service_capabilities before
+/// relying on it, rather than calling its methods and handling failures.
+enum ServiceCapability {
+  /// Methods that upload content, create commits and modify bookmarks, e.g.
+  /// repo_create_commit and repo_land_stack.  Whether a repo accepts writes
+  /// is still down to the repo's config.
+  WRITE_METHODS = 1,
+  /// The `*_async` methods, which return an AsyncRequestToken whose result
+  /// is fetched with request_poll.
+  ASYNC_REQUESTS = 2,
+  /// Megarepo sync target methods, which return tokens polled with the
+  /// matching `megarepo_*_poll` method.
+  MEGAREPO = 3,
+  /// Methods that import git objects, e.g. upload_git_object.
+  GIT_IMPORT = 4,
+}
+
+struct ServiceCapabilitiesResponse {
+  /// The SERVICE_API_VERSION the server was built with.
+  1: i64 api_version;
+  /// The optional features the server supports.
+  2: set<ServiceCapability> capabilities;
+  /// The names of the methods the server supports that belong to an
+  /// optional feature.
+  3: set<string> methods;
+}
+
+/// Longest window service_usage can report on.  Usage is kept in one
+/// minute buckets for this long.
+const i64 SERVICE_USAGE_MAX_WINDOW_SECS = 3600;
+
+/// Requests a caller made to this server in the window.
+struct IdentityUsage {
+  /// The main identity of the caller.
+  1: string identity;
+  /// Number of requests completed, including failed requests.
+  2: i64 request_count;
+  /// Total size in bytes of the successful responses, as serialized with
+  /// the compact protocol.  This is estimated from a sample of responses.
+  3: i64 response_bytes;
+}
+
+struct ServiceUsageResponse {
+  /// Usage by each caller, heaviest first by request count.
+  1: list<IdentityUsage> usage;
+  /// The window the usage covers, which is the requested window rounded up
+  /// to whole minutes and capped at SERVICE_USAGE_MAX_WINDOW_SECS.
+  2: i64 window_secs;
+}
+
+/// Request limits currently enforced by the service.
+///
+/// The `*_MAX_LIMIT` and `*_LIMIT` constants in this file are the defaults for
+/// these values.  The service may raise or lower them at runtime, so clients
+/// that page through results should size their requests from this rather than
+/// from the constants.
+struct ServiceLimits {
+  /// Maximum `limit` for repo_list_bookmarks.
+  1: i64 repo_list_bookmarks_max_limit;
+  /// Maximum `limit` for repo_stack_info.
+  2: i64 repo_stack_info_max_limit;
+  /// Maximum `limit` for ordered commit_compare.
+  3: i64 commit_compare_ordered_max_limit;
+  /// Maximum total size in bytes of files diffed by commit_file_diffs.
+  4: i64 commit_file_diffs_size_limit;
+  /// Maximum number of paths diffed by commit_file_diffs.
+  5: i64 commit_file_diffs_path_count_limit;
+  /// Maximum `limit` for commit_find_files.
+  6: i64 commit_find_files_max_limit;
+  /// Maximum `limit` for commit_list_descendant_bookmarks.
+  7: i64 commit_list_descendant_bookmarks_max_limit;
+  /// Maximum `limit` for tree_list.
+  8: i64 tree_list_max_limit;
+  /// Maximum `limit` for commit_history and commit_path_history.  Not
+  /// present if history pages are unbounded.
+  9: optional i64 history_max_limit;
+  /// Maximum `limit` for tree_prefetch.
+  10: i64 tree_prefetch_max_limit;
+  /// Maximum total size in bytes of files exported by commit_export_archive.
+  11: i64 commit_export_archive_size_limit;
+  /// Maximum `limit` for commit_list_descendants.
+  12: i64 commit_list_descendants_max_limit;
+  /// Maximum `limit` for repo_list_recent_commits.
+  13: i64 repo_list_recent_commits_max_limit;
+  /// Maximum length in bytes of each path in a request.
+  14: i64 path_max_length;
+  /// Maximum number of files exported by commit_export_archive.
+  15: i64 commit_export_archive_file_count_limit;
+  /// Maximum number of paths changed on each side that commit_landability
+  /// checks for conflicts.
+  16: i64 commit_landability_changed_paths_limit;
+  /// Maximum number of identity schemes in a single set of a request.
+  17: i64 identity_schemes_max_count;
+  /// Maximum total size in bytes of the lines in an annotate blame.
+  18: i64 commit_path_blame_annotate_size_limit;
+}
+
 /// Method parameters structures
 
 struct RepoInfoParams {}
 
 struct ListReposParams {}
 
+struct ServiceLimitsParams {}
+
+struct ServiceCapabilitiesParams {}
+
+struct ServiceUsageParams {
+  /// Report usage over this many seconds up to now.
+  1: i64 window_secs;
+
+  /// Only report this many of the heaviest callers, if set.
+  2: optional i64 limit;
+}
+
 struct RepoResolveBookmarkParams {
   /// The bookmark name to look up.
   1: string bookmark_name;
 
   /// Commit identity schemes to return.
   2: set<CommitIdentityScheme> identity_schemes;
+
+  /// Read the real value of the bookmark rather than the warm value.  Data
+  /// for the commit may not be derived yet, so only use this when the
+  /// current value is needed, e.g. to find a commit that was just landed.
+  3: bool bypass_cache;
 }
 
 struct RepoResolveCommitPrefixParams {
@@ -762,6 +1061,33 @@ struct RepoResolveCommitPrefixParams {
   3: set<CommitIdentityScheme> identity_schemes;
 }
 
+struct RepoResolveGlobalrevParams {
+  /// The globalrev to look up.
+  1: i64 globalrev;
+
+  /// Commit identity schemes to return.
+  2: set<CommitIdentityScheme> identity_schemes;
+}
+
+struct RepoLatestGlobalrevParams {
+  /// Commit identity schemes to return.
+  1: set<CommitIdentityScheme> identity_schemes;
+}
+
+const i64 REPO_COMMITS_IN_GLOBALREV_RANGE_MAX_RANGE = 10000;
+
+struct RepoCommitsInGlobalrevRangeParams {
+  /// The first globalrev in the range.
+  1: i64 start;
+
+  /// The end of the range.  This globalrev is not included, and at most
+  /// REPO_COMMITS_IN_GLOBALREV_RANGE_MAX_RANGE globalrevs may be covered.
+  2: i64 end;
+
+  /// Commit identity schemes to return.
+  3: set<CommitIdentityScheme> identity_schemes;
+}
+
 struct RepoBookmarkInfoParams {
   /// The bookmark name to look up.
   1: string bookmark_name;
@@ -793,6 +1119,19 @@ struct RepoListBookmarksParams {
   5: set<CommitIdentityScheme> identity_schemes;
 }
 
+const i64 REPO_LIST_RECENT_COMMITS_MAX_LIMIT = 1000;
+
+struct RepoListRecentCommitsParams {
+  /// The bookmark to list the recent commits of.
+  1: string bookmark_name;
+
+  /// Number of commits to return, up to REPO_LIST_RECENT_COMMITS_MAX_LIMIT.
+  2: i64 limit;
+
+  /// Commit identity schemes to return in the commit information.
+  3: set<CommitIdentityScheme> identity_schemes;
+}
+
 const i64 REPO_STACK_INFO_MAX_LIMIT = 10000;
 
 struct RepoStackInfoParams {
@@ -807,6 +1146,46 @@ struct RepoStackInfoParams {
   3: i64 limit;
 }
 
+const i64 REPO_PATH_MULTI_COMMIT_INFO_MAX_COMMITS = 1000;
+
+struct RepoPathMultiCommitInfoParams {
+  /// The path to query.
+  1: Path path;
+
+  /// Commits to look the path up in.  At most
+  /// REPO_PATH_MULTI_COMMIT_INFO_MAX_COMMITS may be given.
+  2: list<CommitId> commits;
+
+  /// Commit identity schemes to return.
+  3: set<CommitIdentityScheme> identity_schemes;
+}
+
+/// Derived data coverage is computed by counting underived ancestors, which
+/// stops at this many commits.
+const i64 REPO_STATISTICS_UNDERIVED_COUNT_LIMIT = 100000;
+
+struct RepoStatisticsParams {
+  /// The bookmark to compute statistics at, usually the repo's main
+  /// bookmark.
+  1: string bookmark;
+
+  /// Commit identity schemes to return.
+  2: set<CommitIdentityScheme> identity_schemes;
+}
+
+const i64 REPO_FILE_METADATA_MAX_IDS = 1000;
+
+struct RepoFileMetadataParams {
+  /// Content ids of the files to look up.  At most REPO_FILE_METADATA_MAX_IDS
+  /// may be given.
+  1: list<binary> ids;
+}
+
+struct RepoListHookConfigsParams {
+  /// The bookmark to list the hooks of.
+  1: string bookmark;
+}
+
 enum RepoCreateCommitParamsFileType {
   /// Normal file
   FILE = 1,
@@ -962,6 +1341,25 @@ struct RepoCreateBookmarkParams {
   3: optional string service_identity;
 }
 
+struct RepoCreateBranchFromCommitParams {
+  /// The name of the branch bookmark to create.  It must be a publishing
+  /// bookmark, configured with `runtime_protections` in the repo config.
+  1: string bookmark;
+
+  /// The commit to create the branch at.
+  2: CommitId target;
+
+  /// Users allowed to move the branch bookmark once it is created.  These
+  /// are registered in the same transaction that creates the bookmark.
+  3: list<string> allowed_pushers;
+
+  /// The pushvars to use when creating the bookmark.
+  4: optional map<string, binary> pushvars;
+
+  /// Service identity to use for this bookmark creation.
+  5: optional string service_identity;
+}
+
 struct RepoMoveBookmarkParams {
   /// The name of the bookmark to move.
   1: string bookmark;
@@ -983,6 +1381,16 @@ struct RepoMoveBookmarkParams {
   /// configuration.
   3: bool allow_non_fast_forward_move;
 
+  /// A commit the new target must include.  If provided, the bookmark is
+  /// only moved if the new target is a descendant of both this commit and
+  /// the bookmark's current target, and it is an error to also set
+  /// `allow_non_fast_forward_move`.
+  ///
+  /// These checks are made against the target that the bookmark move
+  /// replaces, so if the bookmark is moved concurrently then this move fails
+  /// rather than rolling back the other move.
+  7: optional CommitId must_include;
+
   /// The pushvars to use when moving the bookmark.
   6: optional map<string, binary> pushvars;
 
@@ -1050,6 +1458,41 @@ struct RepoLandStackParams {
   9: BookmarkKindRestrictions bookmark_restrictions = BookmarkKindRestrictions.ANY_KIND;
 }
 
+union RepoUpdateSubmoduleTarget {
+  /// Point the path at the file with this content id.  An existing file
+  /// keeps its file type; a new path is created as a regular file.
+  1: binary content_id;
+
+  /// Point the path at this git commit hash, as a git submodule.
+  2: binary submodule_commit;
+}
+
+struct RepoUpdateSubmoduleParams {
+  /// The name of the bookmark to update.
+  1: string bookmark;
+
+  /// The path to update.
+  2: Path path;
+
+  /// What the path should point to after the update.
+  3: RepoUpdateSubmoduleTarget target;
+
+  /// The info for the new commit.
+  4: RepoCreateCommitParamsCommitInfo info;
+
+  /// The set of commit identity schemes to return in the response.
+  5: set<CommitIdentityScheme> identity_schemes;
+
+  /// The pushvars to use when landing the commit.
+  6: optional map<string, binary> pushvars;
+
+  /// Service identity to use for the commit creation and bookmark move.
+  7: optional string service_identity;
+
+  /// What kind of bookmark can be pushed
+  8: BookmarkKindRestrictions bookmark_restrictions = BookmarkKindRestrictions.ANY_KIND;
+}
+
 /// Only support the types of derived data that we wish to expose to SCS clients.
 /// This can be extended later if other usecases arrise.
 /// See https://www.internalfb.com/code/fbsource/[f84d7f31d5e251d6b1a4dcacce880e4b29a73652]/fbcode/eden/mononoke/derived_data/remote/if/derived_data_service.thrift?lines=40
@@ -1094,16 +1537,89 @@ struct RepoUploadFileContentParams {
   5: optional string service_identity;
 }
 
+const i64 REPO_RUN_HOOK_ON_HISTORY_MAX_COMMITS = 1000;
+
+struct RepoRunHookOnHistoryParams {
+  /// The name of the hook to run.  It must be configured for the repo, but
+  /// need not be enabled for the bookmark.
+  1: string hook_name;
+
+  /// Run the hook against the most recent commits on this bookmark, as if
+  /// they were being landed to it.
+  2: string bookmark;
+
+  /// Number of commits to run the hook against.  At most
+  /// REPO_RUN_HOOK_ON_HISTORY_MAX_COMMITS.
+  3: i64 limit;
+
+  /// Commit identity schemes to return for rejected commits.
+  4: set<CommitIdentityScheme> identity_schemes;
+
+  /// The identity of the service making the request.
+  5: optional string service_identity;
+}
+
+struct RepoValidateCommitMessageParams {
+  /// The commit message to validate.
+  1: string message;
+
+  /// Validate the message as if a commit with it were being landed to this
+  /// bookmark.
+  2: string bookmark;
+}
+
 struct CommitLookupParams {
   /// Commit identity schemes to return.
   1: set<CommitIdentityScheme> identity_schemes;
 }
 
+const i64 COMMIT_TRANSLATE_IDS_MAX_COMMITS = 10000;
+
+struct CommitTranslateIdsParams {
+  /// Commits to translate, all in `from_scheme`.  At most
+  /// COMMIT_TRANSLATE_IDS_MAX_COMMITS may be given.
+  1: list<CommitId> commit_ids;
+
+  /// The identity scheme of the given commit ids.
+  2: CommitIdentityScheme from_scheme;
+
+  /// Commit identity schemes to translate to.
+  3: set<CommitIdentityScheme> to_schemes;
+}
+
+const i64 COMMIT_MULTIPLE_LOOKUP_MAX_COMMITS = 10000;
+
+struct CommitMultipleLookupParams {
+  /// Commits to look up, in any scheme.  At most
+  /// COMMIT_MULTIPLE_LOOKUP_MAX_COMMITS may be given.
+  1: list<CommitId> commit_ids;
+
+  /// Commit identity schemes to return.
+  2: set<CommitIdentityScheme> identity_schemes;
+}
+
 struct CommitLookupPushrebaseHistoryParams {}
 
+struct CommitMutationsParams {}
+
 struct CommitInfoParams {
   /// Commit identity schemes to return.
   1: set<CommitIdentityScheme> identity_schemes;
+
+  /// Include the change in file count and total file size relative to the
+  /// first parent.  This requires fsnodes to be derived for the commit and
+  /// its first parent.
+  2: bool include_file_count_delta;
+}
+
+struct CommitPhaseParams {}
+
+struct CommitDerivedDataInfoParams {
+  /// Derived data types to report on, named as in
+  /// `RepoStatisticsResponse.derived_data_coverage` (e.g. "Fsnodes",
+  /// "HgChangesets", "BlameV2").  If empty, all supported types are
+  /// reported.
+  1: list<string> derived_data_types;
 }
 
 /// Parameters for the `commit_is_ancestor_of` method.
@@ -1116,6 +1632,20 @@ struct CommitIsAncestorOfParams {
   1: CommitId descendant_commit_id;
 }
 
+/// Most bookmarks commit_contained_in_bookmarks will check.
+const i64 COMMIT_CONTAINED_IN_BOOKMARKS_MAX_BOOKMARKS = 10000;
+
+struct CommitContainedInBookmarksParams {
+  /// Check the public bookmarks with any of these prefixes.  An empty
+  /// prefix matches all public bookmarks.  At least one prefix must be
+  /// given, and at most COMMIT_CONTAINED_IN_BOOKMARKS_MAX_BOOKMARKS
+  /// bookmarks may match.
+  1: list<string> bookmark_prefixes;
+
+  /// Commit identity schemes to return.
+  2: set<CommitIdentityScheme> identity_schemes;
+}
+
 struct CommitCommonBaseWithParams {
   1: CommitId other_commit_id;
   2: set<CommitIdentityScheme> identity_schemes;
@@ -1148,6 +1678,15 @@ struct CommitCompareParams {
   /// Whether to find parents via the commit ancestry, or via mutable copy
   /// information. If not supplied, a default will be chosen for you
   7: optional bool follow_mutable_file_history;
+  /// How to report files whose type changed but content did not.
+  8: CommitCompareModeOnlyChanges mode_only_changes = INCLUDE;
+  /// Minimum similarity, as a percentage from 0 to 100, between a copied or
+  /// renamed file and its source for it to be reported as a copy or rename.
+  /// Less similar copies are reported as additions, and renames as an
+  /// addition and a removal.  Checking similarity fetches file content, so
+  /// leave this unset when speed matters more; set skip_copies_renames to
+  /// skip copy and rename detection entirely.
+  9: optional i32 copy_similarity_threshold;
 }
 
 struct CommitFileDiffsParamsPathPair {
@@ -1184,6 +1723,22 @@ struct CommitFileDiffsParams {
   /// diff format.  For raw diffs, it is the total size in bytes of the returned diffs.
   /// For phabricator diff metadata, it is the number of entries.
   5: optional i64 diff_size_limit;
+  /// Raw diffs only: files larger than this many bytes are not diffed.  A
+  /// placeholder diff is returned instead, marked as omitted with
+  /// FILE_TOO_LARGE, and the file does not count towards
+  /// COMMIT_FILE_DIFFS_SIZE_LIMIT.
+  6: optional i64 file_size_limit;
+  /// Raw diffs only: if set, binary files are not diffed.  A placeholder diff
+  /// is returned instead, marked as omitted with BINARY.  Binary files are
+  /// detected from their metadata, so their content is not fetched.
+  7: optional bool binary_placeholder;
+  /// Index in `paths` of the first pair to diff (default: 0).
+  8: optional i64 page_start;
+  /// Number of pairs to diff, starting from `page_start` (default: all the
+  /// remaining pairs).  The path count and total size limits apply to the
+  /// pairs in the page rather than to all of `paths`, so clients can diff a
+  /// large list of paths over several requests.
+  9: optional i64 page_size;
 }
 
 const i64 COMMIT_FIND_FILES_MAX_LIMIT = 100000;
@@ -1248,6 +1803,10 @@ struct CommitFindFilesParams {
 /// excludes the target commits, a half-open range of commits
 /// `(ancestor, descendant]` can be obtained by setting both of these to
 /// the ancestor commit.
+///
+/// `order` controls the order in which the commits are returned.  Date
+/// orders visit commits best-first, so a commit may be returned before
+/// one of its descendants if commit dates are skewed across a merge.
 struct CommitHistoryParams {
   /// Return history in the given format.
   1: HistoryFormat format;
@@ -1268,6 +1827,8 @@ struct CommitHistoryParams {
   7: optional CommitId descendants_of;
   /// Exclude commit and all of its ancestor from results.
   8: optional CommitId exclude_changeset_and_ancestors;
+  /// The order in which to return commits.  Defaults to TOPOLOGICAL.
+  9: optional HistoryOrder order;
 }
 
 const i64 COMMIT_LIST_DESCENDANT_BOOKMARKS_MAX_LIMIT = 10000;
@@ -1290,6 +1851,33 @@ struct CommitListDescendantBookmarksParams {
   5: set<CommitIdentityScheme> identity_schemes;
 }
 
+const i64 COMMIT_LIST_DESCENDANTS_MAX_LIMIT = 10000;
+
+/// Parameters for the `commit_list_descendants` method.
+///
+/// Lists the descendants of the commit that are in the history of any of
+/// the given bookmarks, not including the commit itself.  Descendants are
+/// listed newest first, by descending generation number and then by bonsai
+/// commit id, so the order is stable across requests.
+///
+/// To fetch the next page, pass any of the ids in `continue_after` from the
+/// previous response as `after`.  If the bookmarks move between requests,
+/// the later pages reflect their new positions.
+struct CommitListDescendantsParams {
+  /// Bookmarks whose history to list descendants from.  At least one
+  /// bookmark must be given.
+  1: list<string> bookmarks;
+
+  /// Maximum number of descendants to return.
+  2: i64 limit;
+
+  /// Continue listing after this descendant, to be used for paging.
+  3: optional CommitId after;
+
+  /// Commit identity schemes to return.
+  4: set<CommitIdentityScheme> identity_schemes;
+}
+
 struct CommitRunHooksParams {
   /// Run the same hooks as when landing to bookmark
   1: string bookmark;
@@ -1297,9 +1885,26 @@ struct CommitRunHooksParams {
   2: optional map<string, binary> pushvars;
 }
 
+/// Maximum number of paths changed by the commit, or on the bookmark, since
+/// their merge base that commit_landability checks for conflicts.  If either
+/// side changed more paths than this, conflicts are reported as unknown.
+const i64 COMMIT_LANDABILITY_CHANGED_PATHS_LIMIT = 100000;
+
+struct CommitLandabilityParams {
+  /// The bookmark the commit would be landed to.
+  1: string bookmark;
+  /// Pushvars that would be used on the push.
+  2: optional map<string, binary> pushvars;
+  /// Commit identity schemes to return.
+  3: set<CommitIdentityScheme> identity_schemes;
+}
+
 struct CommitPathExistsParams {}
 
-struct CommitPathInfoParams {}
+struct CommitPathInfoParams {
+  /// If the path is a symbolic link, include its target in the response.
+  1: bool include_link_target;
+}
 
 struct CommitMultiplePathInfoParams {
   /// List of paths to query.
@@ -1328,6 +1933,21 @@ struct CommitPathBlameParams {
   5: optional bool follow_mutable_file_history;
 }
 
+struct CommitFileAnnotateHistoryParams {
+  /// Commit identity schemes to return, both in the blame and in the
+  /// commit information.
+  1: set<CommitIdentityScheme> identity_schemes;
+
+  /// Options to customize the compact blame.
+  ///
+  /// If not specified, defaults to {INCLUDE_CONTENT}.
+  2: optional set<BlameFormatOption> format_options;
+
+  /// Use mutable copy information to identify ancestry, instead of
+  /// using commit parents to identify ancestry
+  3: optional bool follow_mutable_file_history;
+}
+
 /// Parameters for the `commit_path_history` method.
 ///
 /// By default, this will include all commits that are ancestors of
@@ -1367,6 +1987,19 @@ struct CommitPathLastChangedParams {
   1: set<CommitIdentityScheme> identity_schemes;
 }
 
+struct CommitPathFirstIntroducedParams {
+  /// Commit identity schemes to return.
+  1: set<CommitIdentityScheme> identity_schemes;
+}
+
+const i64 COMMIT_PATH_CASE_CONFLICTS_MAX_LIMIT = 10000;
+
+struct CommitPathCaseConflictsParams {
+  /// Maximum number of case conflicts to return.  Must be at most
+  /// COMMIT_PATH_CASE_CONFLICTS_MAX_LIMIT.
+  1: i64 limit;
+}
+
 struct CommitMultiplePathLastChangedParams {
   /// List of paths to query.
   ///
@@ -1389,6 +2022,36 @@ struct CommitSparseProfileSizeParams {
   1: SparseProfiles profiles;
 }
 
+struct CommitSparseProfileFilesParams {
+  /// Paths of the sparse profiles to resolve.
+  1: list<SparseProfileName> profile_paths;
+}
+
+enum ArchiveFormat {
+  TAR = 1,
+  ZIP = 2,
+}
+
+/// Maximum total size in bytes of the files in an exported archive, before
+/// compression.
+const i64 COMMIT_EXPORT_ARCHIVE_SIZE_LIMIT = 0x4000000; /// 64MiB
+
+/// Maximum number of files in an exported archive.
+const i64 COMMIT_EXPORT_ARCHIVE_FILE_COUNT_LIMIT = 100000;
+
+struct CommitExportArchiveParams {
+  /// Only export files under this path.  Use the empty path to export the
+  /// whole commit.  Paths in the archive are relative to this path.
+  1: Path path_prefix;
+
+  /// The format of the archive.
+  2: ArchiveFormat format;
+
+  /// Compress the archive.  Tar archives are gzipped, and zip archive
+  /// entries are deflated.
+  3: bool compress;
+}
+
 struct TreeExistsParams {}
 
 struct TreeListParams {
@@ -1397,6 +2060,44 @@ struct TreeListParams {
 
   /// Limit to the number of tree entries listed.
   2: i64 limit;
+
+  /// Include the targets of symbolic links in the listed entries.
+  3: bool include_link_target;
+
+  /// Only list entries whose full path matches this regular expression.
+  /// The offset and limit apply to the matching entries, while the count
+  /// in the response is still that of all entries in the tree.  For trees
+  /// specified by id, the tree's `path_hint` must be given.
+  4: optional string path_regex;
+}
+
+struct TreeListRecursiveParams {
+  /// How many levels of directories to list.  A depth of 1 lists just the
+  /// requested directory, like tree_list.
+  1: i64 depth;
+
+  /// Limit to the number of tree entries listed, across all levels.  Can be
+  /// set up to the tree_list limit.
+  2: i64 limit;
+}
+
+const i64 TREE_PREFETCH_MAX_LIMIT = 100000;
+
+struct TreePrefetchParams {
+  /// Directories to prefetch.  Use the empty path for the repo root.
+  1: list<Path> path_prefixes;
+
+  /// How many levels of directories to list below each prefix.  A depth of
+  /// 1 lists just the prefix directories themselves.
+  2: i64 depth;
+
+  /// Limit to the total number of tree entries returned, across all
+  /// prefixes.  Can be set up to TREE_PREFETCH_MAX_LIMIT.
+  3: i64 limit;
+
+  /// Return the trees compressed, in `compressed_trees`, rather than in
+  /// `trees`.
+  4: bool compress;
 }
 
 struct FileExistsParams {}
@@ -1456,6 +2157,16 @@ struct CommitLookupXRepoParams {
   3: optional CandidateSelectionHint candidate_selection_hint;
 }
 
+/// Most paths megarepo_path_mapping will map in one request.
+const i64 MEGAREPO_PATH_MAPPING_MAX_PATHS = 1000;
+
+struct MegarepoPathMappingParams {
+  /// The other repo to map the paths into
+  1: RepoSpecifier other_repo;
+  /// Paths in the commit to map
+  2: list<string> paths;
+}
+
 /// Synchronization target
 struct MegarepoTarget {
   /// Mononoke repository id, where the target is located
@@ -1670,6 +2381,10 @@ struct RepoResolveBookmarkResponse {
 
   /// The bookmarked commit's IDs in the requested schemes (if available).
   2: optional map<CommitIdentityScheme, CommitId> ids;
+
+  /// How up to date the value is, if it was served from the warm bookmarks
+  /// cache.
+  3: optional WarmBookmarkStaleness staleness;
 }
 
 enum RepoResolveCommitPrefixResponseType {
@@ -1685,6 +2400,36 @@ struct RepoResolveCommitPrefixResponse {
   2: optional map<CommitIdentityScheme, CommitId> ids;
 }
 
+struct RepoResolveGlobalrevResponse {
+  /// Whether a commit has been assigned the globalrev.
+  1: bool exists;
+
+  /// The commit's IDs in the requested schemes (if it exists).
+  2: optional map<CommitIdentityScheme, CommitId> ids;
+}
+
+struct RepoLatestGlobalrevResponse {
+  /// The highest globalrev assigned in the repo, if any have been assigned.
+  1: optional i64 globalrev;
+
+  /// The IDs of the commit with that globalrev in the requested schemes.
+  2: optional map<CommitIdentityScheme, CommitId> ids;
+}
+
+struct GlobalrevCommit {
+  /// The globalrev assigned to the commit.
+  1: i64 globalrev;
+
+  /// The commit's IDs in the requested schemes.
+  2: map<CommitIdentityScheme, CommitId> ids;
+}
+
+struct RepoCommitsInGlobalrevRangeResponse {
+  /// The commits assigned globalrevs in the range, in globalrev order.
+  /// Globalrevs in the range that were never assigned are skipped.
+  1: list<GlobalrevCommit> commits;
+}
+
 struct RepoBookmarkInfoResponse {
   /// Bookmark info, null if doesn't exist.
   1: optional BookmarkInfo info;
@@ -1701,6 +2446,15 @@ struct RepoListBookmarksResponse {
   2: optional string continue_after;
 }
 
+struct RepoListRecentCommitsResponse {
+  /// Whether the bookmark exists.
+  1: bool exists;
+
+  /// The commit the bookmark points to and its ancestors, newest first in
+  /// topological order.  Empty if the bookmark doesn't exist.
+  2: list<CommitInfo> commits;
+}
+
 struct RepoStackInfoResponse {
   /// Draft commits in topological order.
   1: list<CommitInfo> draft_commits;
@@ -1715,6 +2469,97 @@ struct RepoStackInfoResponse {
   3: list<map<CommitIdentityScheme, CommitId>> leftover_heads;
 }
 
+struct PathCommitInfo {
+  /// The commit the path was looked up in.
+  1: map<CommitIdentityScheme, CommitId> ids;
+
+  /// Information about the path in this commit.
+  2: CommitPathInfoResponse path_info;
+}
+
+struct RepoPathMultiCommitInfoResponse {
+  /// Information about the path in each of the requested commits, in the
+  /// same order as the request.
+  1: list<PathCommitInfo> commits_info;
+}
+
+struct DerivedDataCoverage {
+  /// The number of ancestors of the bookmark for which this type of derived
+  /// data has not been derived.  At most
+  /// REPO_STATISTICS_UNDERIVED_COUNT_LIMIT.
+  1: i64 underived_count;
+
+  /// The percentage of ancestors of the bookmark for which this type of
+  /// derived data has been derived.  If underived_count reached the limit,
+  /// this is an upper bound.
+  2: double coverage_percent;
+}
+
+struct RepoStatisticsResponse {
+  /// The commit the statistics are for.  This is the commit the bookmark
+  /// points to, unless the bookmark has moved recently, in which case it
+  /// may be an earlier commit while statistics for the new commit are
+  /// computed in the background.
+  1: map<CommitIdentityScheme, CommitId> ids;
+
+  /// The number of ancestors of the bookmark, including the commit itself.
+  2: i64 commit_count;
+
+  /// The number of publishing and pull-default bookmarks in the repo.
+  3: i64 bookmark_count;
+
+  /// The number of files in the bookmarked commit.
+  4: i64 file_count;
+
+  /// The total size of the files in the bookmarked commit.
+  5: i64 total_file_size;
+
+  /// Derivation coverage for each derived data type enabled for the repo,
+  /// keyed by derived data type (e.g. "Fsnodes").
+  6: map<string, DerivedDataCoverage> derived_data_coverage;
+
+  /// When these statistics were computed, in seconds since the epoch.
+  /// Statistics may be cached for a few minutes.
+  7: i64 computed_at;
+}
+
+struct RepoFileMetadataResponse {
+  /// Metadata for each requested file, keyed by content id.  Ids that are
+  /// not in the repo are omitted.
+  1: map<binary, FileInfo> files;
+}
+
+/// Configuration of a hook.  Config values whose names suggest they hold
+/// secrets (e.g. tokens or passwords) are replaced with "<redacted>".
+struct HookConfigInfo {
+  /// The name of the hook.
+  1: string name;
+
+  /// The name of the hook implementation.
+  2: string implementation;
+
+  /// Text in the commit message that bypasses the hook, if any.
+  3: optional string bypass_commit_message;
+
+  /// Pushvar that bypasses the hook, if any, as "NAME=VALUE".
+  4: optional string bypass_pushvar;
+
+  /// Hook options, in JSON format.
+  5: optional string options;
+
+  /// Deprecated config knobs, by type.  32 and 64 bit integer knobs are
+  /// merged.
+  6: map<string, string> strings;
+  7: map<string, i64> ints;
+  8: map<string, list<string>> string_lists;
+  9: map<string, list<i64>> int_lists;
+}
+
+struct RepoListHookConfigsResponse {
+  /// The hooks that run on pushes to the bookmark, ordered by name.
+  1: list<HookConfigInfo> hooks;
+}
+
 struct RepoCreateCommitResponse {
   /// The IDs of the created commit.
   1: map<CommitIdentityScheme, CommitId> ids;
@@ -1727,6 +2572,8 @@ struct RepoCreateStackResponse {
 
 struct RepoCreateBookmarkResponse {}
 
+struct RepoCreateBranchFromCommitResponse {}
+
 struct RepoMoveBookmarkResponse {}
 
 struct RepoDeleteBookmarkResponse {}
@@ -1735,6 +2582,10 @@ struct RepoLandStackResponse {
   1: PushrebaseOutcome pushrebase_outcome;
 }
 
+struct RepoUpdateSubmoduleResponse {
+  1: PushrebaseOutcome pushrebase_outcome;
+}
+
 struct RepoPrepareCommitsResponse {}
 
 struct RepoUploadFileContentResponse {
@@ -1759,10 +2610,21 @@ struct CommitCompareResponse {
   4: optional Path last_path;
 }
 
+/// Why the diff of a pair of files was replaced with a placeholder.
+enum CommitFileDiffsOmittedReason {
+  /// One of the files is larger than the requested `file_size_limit`.
+  FILE_TOO_LARGE = 1,
+  /// One of the files is binary, and `binary_placeholder` was requested.
+  BINARY = 2,
+}
+
 struct CommitFileDiffsResponseElement {
   1: optional Path base_path;
   2: optional Path other_path;
   3: Diff diff;
+  /// Set if the diff is a placeholder because of the requested per-file
+  /// options.
+  4: optional CommitFileDiffsOmittedReason omitted_reason;
 }
 
 struct CommitFileDiffsStoppedAtPair {
@@ -1774,6 +2636,40 @@ struct CommitFileDiffsResponse {
   1: list<CommitFileDiffsResponseElement> path_diffs;
   /// The first pair for which a diff was not returned. Start next request from this pair if you want to resume.
   2: optional CommitFileDiffsStoppedAtPair stopped_at_pair;
+  /// Index in `paths` of the first pair for which a diff was not returned,
+  /// if any pairs remain.  Pass this as `page_start` to get the next page.
+  3: optional i64 next_page_start;
+}
+
+/// Token for a request started by one of the `*_async` methods.
+///
+/// Results are held by the server that started the request, so the token
+/// must be polled on the same server (e.g. over the same connection).
+struct AsyncRequestToken {
+  1: string id;
+}
+
+enum AsyncRequestStatus {
+  /// The request is still running.
+  IN_PROGRESS = 1,
+
+  /// The request has finished and its result is available.
+  COMPLETE = 2,
+}
+
+union AsyncRequestResult {
+  1: CommitCompareResponse commit_compare;
+  2: CommitFileDiffsResponse commit_file_diffs;
+}
+
+struct AsyncRequestPollResponse {
+  1: AsyncRequestStatus status;
+
+  /// How long the request has been running for, or took to complete.
+  2: i64 elapsed_ms;
+
+  /// The result of the request, once it is complete.
+  3: optional AsyncRequestResult result;
 }
 
 struct CommitLookupResponse {
@@ -1784,12 +2680,86 @@ struct CommitLookupResponse {
   2: optional map<CommitIdentityScheme, CommitId> ids;
 }
 
+struct MegarepoPathMappingResponse {
+  /// The path in the other repo for each requested path.  Paths with no
+  /// equivalent in the other repo are omitted.
+  1: map<string, string> paths;
+
+  /// The commit sync config version used to map the paths, i.e. the one
+  /// the commit was synced with.
+  2: string sync_config_version;
+}
+
+struct CommitIdTranslation {
+  /// The commit id, as given in the request.
+  1: CommitId commit_id;
+
+  /// The commit's IDs in the requested schemes (if available).
+  2: map<CommitIdentityScheme, CommitId> ids;
+}
+
+struct CommitTranslateIdsResponse {
+  /// Translations of the commits that were found, in the same order as the
+  /// request.  Commits that aren't known in `from_scheme` are omitted.
+  1: list<CommitIdTranslation> translations;
+}
+
+struct CommitMultipleLookupResult {
+  /// The commit id, as given in the request.
+  1: CommitId commit_id;
+
+  /// Whether the commit exists.
+  2: bool exists;
+
+  /// The commit's IDs in the requested schemes, if it exists.
+  3: optional map<CommitIdentityScheme, CommitId> ids;
+
+  /// Why the commit id could not be looked up, if it is malformed.
+  4: optional string error;
+}
+
+struct CommitMultipleLookupResponse {
+  /// The result for each commit, in the same order as the request.
+  1: list<CommitMultipleLookupResult> results;
+}
+
 struct CommitLookupPushrebaseHistoryResponse {
   1: list<CommitSpecifier> history;
   /// Always equals to the last element of history
   2: CommitSpecifier origin;
 }
 
+enum CommitDerivedDataState {
+  /// The data has been derived for the commit.
+  DERIVED = 1,
+  /// The data has not been derived yet, and will be derived on demand by
+  /// requests that need it, which may be slow.
+  NOT_DERIVED = 2,
+  /// The derived data type is not enabled for the repo.
+  NOT_ENABLED = 3,
+}
+
+struct CommitDerivedDataInfoResponse {
+  /// Derivation state for each requested derived data type.
+  1: map<string, CommitDerivedDataState> derived_data;
+}
+
+struct CommitPhaseResponse {
+  /// Whether the commit is public or a draft.
+  1: CommitPhase phase;
+}
+
+struct CommitMutationsResponse {
+  /// The mutations that led to the commit, including mutations of its
+  /// predecessors.  Empty if the commit has no recorded mutations.
+  1: list<CommitMutation> mutations;
+
+  /// The mutations of the commit into later commits, e.g. the amend that
+  /// replaced it, including mutations of those commits in turn.  Empty if
+  /// the commit hasn't been mutated, or its successors haven't been pushed.
+  2: list<CommitMutation> successor_mutations;
+}
+
 struct CommitFindFilesResponse {
   /// The files that match.
   1: list<string> files;
@@ -1799,6 +2769,12 @@ struct CommitHistoryResponse {
   1: History history;
 }
 
+struct CommitContainedInBookmarksResponse {
+  /// The bookmarks that have the commit as an ancestor, and the commit they
+  /// refer to.
+  1: map<string, map<CommitIdentityScheme, CommitId>> bookmarks;
+}
+
 struct CommitListDescendantBookmarksResponse {
   /// The map of bookmarks that are descendants of this bookmark and
   /// the commit they refer to.
@@ -1810,6 +2786,15 @@ struct CommitListDescendantBookmarksResponse {
   2: optional string continue_after;
 }
 
+struct CommitListDescendantsResponse {
+  /// The descendants, newest first.
+  1: list<map<CommitIdentityScheme, CommitId>> descendants;
+
+  /// If set, there are more descendants.  Provide one of these ids as the
+  /// `after` parameter in a new request to continue listing them.
+  2: optional map<CommitIdentityScheme, CommitId> continue_after;
+}
+
 struct HookOutcomeAccepted {}
 
 struct HookOutcomeRejected {
@@ -1828,6 +2813,64 @@ struct CommitRunHooksResponse {
   1: map<string, HookOutcome> outcomes;
 }
 
+enum LandabilityIssue {
+  /// The commit is already an ancestor of the bookmark.
+  ALREADY_LANDED = 0,
+  /// A hook rejects the commit.
+  HOOK_REJECTED = 1,
+  /// The commit changes paths that have also changed on the bookmark since
+  /// the commit's merge base with it, so rebasing it would conflict.
+  MERGE_CONFLICTS = 2,
+  /// The commit has no common ancestor with the bookmark.
+  NO_COMMON_BASE = 3,
+  /// Too many paths have changed since the commit's merge base with the
+  /// bookmark to check whether rebasing it would conflict.
+  CONFLICTS_UNKNOWN = 4,
+}
+
+struct CommitLandabilityResponse {
+  /// Whether the commit is ready to land, i.e. `issues` is empty.
+  1: bool landable;
+  /// Everything that would prevent the commit from landing.
+  2: list<LandabilityIssue> issues;
+  /// The commit the bookmark currently points to.
+  3: map<CommitIdentityScheme, CommitId> bookmark_ids;
+  /// The merge base of the commit and the bookmark, if they have one.
+  4: optional map<CommitIdentityScheme, CommitId> merge_base_ids;
+  /// Whether the bookmark is an ancestor of the commit, so the commit can
+  /// land without a rebase.
+  5: bool fast_forward;
+  /// The rejections of each hook that rejects the commit.
+  6: map<string, list<HookOutcomeRejected>> hook_rejections;
+  /// The paths changed by both the commit and the bookmark since their
+  /// merge base, or where one changed a file and the other a directory.
+  7: list<Path> conflict_paths;
+}
+
+struct HookRejectedCommit {
+  /// The rejected commit's ids in the requested schemes.
+  1: map<CommitIdentityScheme, CommitId> ids;
+  /// Why the hook rejected the commit.  File hooks may reject a commit once
+  /// for each file.
+  2: list<HookOutcomeRejected> rejections;
+}
+
+struct RepoRunHookOnHistoryResponse {
+  /// Number of commits the hook was run against.
+  1: i64 commits_checked;
+  /// The commits that the hook rejected, most recent first.
+  2: list<HookRejectedCommit> rejected_commits;
+  /// Number of rejections for each rejection description.
+  3: map<string, i64> rejection_counts;
+}
+
+struct RepoValidateCommitMessageResponse {
+  /// Whether every hook that checked the message accepted it.
+  1: bool valid;
+  /// The outcome of each hook that checked the message.
+  2: map<string, HookOutcome> outcomes;
+}
+
 struct CommitPathExistsResponse {
   /// Whether anything exists at this path.
   1: bool exists;
@@ -1848,6 +2891,15 @@ struct CommitPathInfoResponse {
 
   /// The info for the item.
   3: optional EntryInfo info;
+
+  /// Whether the item is an executable file.  Not set for links,
+  /// directories or submodules.
+  4: optional bool is_executable;
+
+  /// The target of a symbolic link, if requested with
+  /// `include_link_target`.  Targets that are not valid UTF-8 are converted
+  /// lossily.
+  5: optional string link_target;
 }
 
 struct CommitMultiplePathInfoResponse {
@@ -1861,6 +2913,16 @@ struct CommitPathBlameResponse {
   1: Blame blame;
 }
 
+struct CommitFileAnnotateHistoryResponse {
+  /// The blame of the file, in compact format.
+  1: BlameCompact blame;
+
+  /// Full information about each of the commits referenced by the blame,
+  /// in the same order as `blame.commit_ids`, so that each line's
+  /// `commit_id_index` also indexes into this list.
+  2: list<CommitInfo> commit_infos;
+}
+
 struct CommitPathHistoryResponse {
   1: History history;
 }
@@ -1884,6 +2946,28 @@ struct CommitPathLastChangedResponse {
   1: optional CommitPathLastChange last_change;
 }
 
+struct CommitPathFirstIntroducedResponse {
+  /// The commit in which this path first appeared in the ancestry of the
+  /// requested commit, i.e. the oldest commit in its history.  Not present
+  /// if nothing exists at this path in the requested commit.
+  1: optional map<CommitIdentityScheme, CommitId> first_introduced_commit;
+}
+
+/// A set of paths that differ only by case.
+struct PathCaseConflict {
+  /// The conflicting paths, in sorted order.
+  1: list<Path> paths;
+}
+
+struct CommitPathCaseConflictsResponse {
+  /// Case conflicts under the path, with the conflicts in a directory
+  /// before those in its subdirectories, and subdirectories in name order.
+  1: list<PathCaseConflict> case_conflicts;
+
+  /// Whether there were more case conflicts than the limit.
+  2: bool truncated;
+}
+
 struct CommitMultiplePathLastChangedResponse {
   /// Last change for the requested paths.
   ///
@@ -1900,6 +2984,21 @@ struct CommitSparseProfileSizeResponse {
   1: SparseProfileSizes profiles_size;
 }
 
+struct CommitSparseProfileFilesResponse {
+  1: map<SparseProfileName, SparseProfileFiles> profiles;
+}
+
+struct CommitExportArchiveResponse {
+  /// The archive, in the requested format.
+  1: binary_bytes archive;
+
+  /// The number of files in the archive.
+  2: i64 file_count;
+
+  /// The total size of the files in the archive, before compression.
+  3: i64 total_size;
+}
+
 struct TreeListResponse {
   /// The directory entries in this directory, at the offset requested,
   /// limited by the limit requested.
@@ -1911,6 +3010,48 @@ struct TreeListResponse {
   2: i64 count;
 }
 
+struct TreeListRecursiveEntry {
+  /// The path of the entry, relative to the listed directory.
+  1: Path path;
+
+  /// The entry itself.
+  2: TreeEntry entry;
+}
+
+struct TreeListRecursiveResponse {
+  /// The entries of the directory and the directories beneath it, breadth
+  /// first, limited by the limit requested.
+  1: list<TreeListRecursiveEntry> entries;
+
+  /// Whether there were more entries than the limit.
+  2: bool truncated;
+}
+
+/// The directories fetched by tree_prefetch.
+struct TreePrefetchTrees {
+  1: map<Path, list<TreeEntry>> trees;
+}
+
+struct TreePrefetchResponse {
+  /// The entries of every directory fetched, keyed by directory path.
+  /// Each directory is listed in full, and entries only carry their names,
+  /// so paths are not repeated for every entry.  Empty if `compress` was
+  /// set.
+  ///
+  /// Prefixes that are not directories in the commit are omitted.
+  1: map<Path, list<TreeEntry>> trees;
+
+  /// True if the limit was reached before every directory within the
+  /// requested depth could be listed.  Directories that did not fit are
+  /// omitted entirely; clients can request them again with the directories
+  /// that were listed removed from the prefixes.
+  2: bool truncated;
+
+  /// If `compress` was set, the directories fetched, as a TreePrefetchTrees
+  /// serialized with the compact protocol and compressed with zstd.
+  3: optional binary compressed_trees;
+}
+
 struct FileDiffResponse {
   /// The differences between the two files.
   1: Diff diff;
@@ -2025,11 +3166,38 @@ enum RequestErrorKind {
   NOT_AVAILABLE = 9,
   NOT_IMPLEMENTED = 10,
   MERGE_CONFLICTS = 11,
+  METHOD_DISABLED_FOR_REPO = 12,
+  // A path used as a directory is a file.
+  NOT_A_DIRECTORY = 13,
+  // A file is too large for the request to be served.
+  PATH_TOO_LARGE = 14,
+  // A write conflicted with a concurrent update, e.g. a bookmark moved
+  // while it was being updated.
+  CONFLICT = 15,
+}
+
+/// A bookmark protection policy from the repo config, and who it allows
+/// to move the bookmark.
+struct BookmarkPolicy {
+  /// The policy's name: the bookmark or bookmark pattern it is configured
+  /// for, or the bookmark for protections registered when it was created.
+  1: string name;
+  /// Users matching this pattern are allowed.
+  2: optional string allowed_users;
+  /// Members of this group are allowed.
+  3: optional string allowed_group;
+  /// These users are allowed.
+  4: list<string> allowed_pushers;
 }
 
 exception RequestError {
   1: RequestErrorKind kind;
   2: string reason;
+  // For MERGE_CONFLICTS, the paths that conflict.
+  3: optional list<string> conflict_paths;
+  // For PERMISSION_DENIED when modifying a bookmark, the bookmark policy
+  // that denied it.
+  4: optional BookmarkPolicy bookmark_policy;
 } (message = "reason")
 
 exception InternalError {
@@ -2092,6 +3260,34 @@ service SourceControlService extends fb303_core.BaseService {
     2: InternalError internal_error,
   );
 
+  /// Get the request limits currently in effect.
+  ServiceLimits service_limits(1: ServiceLimitsParams params) throws (
+    1: RequestError request_error,
+    2: InternalError internal_error,
+  );
+
+  /// Get the API version and the optional features this server supports.
+  ServiceCapabilitiesResponse service_capabilities(
+    1: ServiceCapabilitiesParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Get the requests made to this server by each caller over a recent
+  /// window, so that capacity can be attributed to callers.  Usage is
+  /// per server, so clients wanting totals must aggregate across servers.
+  /// Only callers in the global allowlist may see usage.
+  ServiceUsageResponse service_usage(1: ServiceUsageParams params) throws (
+    1: RequestError request_error,
+    2: InternalError internal_error,
+  );
+
+  /// Poll for the result of a request started by one of the `*_async`
+  /// methods.  If the request failed, its error is thrown.  Results are
+  /// kept for a limited time after the request completes.
+  AsyncRequestPollResponse request_poll(1: AsyncRequestToken token) throws (
+    1: RequestError request_error,
+    2: InternalError internal_error,
+  );
+
   /// Repository methods
   /// ==================
 
@@ -2103,7 +3299,8 @@ service SourceControlService extends fb303_core.BaseService {
 
   /// Resolve a bookmark
   /// The return value may be slightly stale, the served value is only updated
-  /// once all the data for new commits is generated and cache warm.
+  /// once all the data for new commits is generated and cache warm.  Use
+  /// `bypass_cache` to get the real value instead.
   RepoResolveBookmarkResponse repo_resolve_bookmark(
     1: RepoSpecifier repo,
     2: RepoResolveBookmarkParams params,
@@ -2115,6 +3312,24 @@ service SourceControlService extends fb303_core.BaseService {
     2: RepoResolveCommitPrefixParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Resolve a globalrev to the commit it was assigned to
+  RepoResolveGlobalrevResponse repo_resolve_globalrev(
+    1: RepoSpecifier repo,
+    2: RepoResolveGlobalrevParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Get the highest globalrev assigned in the repo
+  RepoLatestGlobalrevResponse repo_latest_globalrev(
+    1: RepoSpecifier repo,
+    2: RepoLatestGlobalrevParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Get the commits assigned globalrevs in a range, in globalrev order
+  RepoCommitsInGlobalrevRangeResponse repo_commits_in_globalrev_range(
+    1: RepoSpecifier repo,
+    2: RepoCommitsInGlobalrevRangeParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Comprehensive information about bookmark (use repo_resolve_bookmark for
   /// simply resolving bookmark value).
   RepoBookmarkInfoResponse repo_bookmark_info(
@@ -2128,6 +3343,15 @@ service SourceControlService extends fb303_core.BaseService {
     2: RepoListBookmarksParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// List the most recent commits on a bookmark, with their commit info.
+  /// This is equivalent to resolving the bookmark and requesting its
+  /// history in COMMIT_INFO format.  As with repo_resolve_bookmark, the
+  /// bookmark's value may be slightly stale.
+  RepoListRecentCommitsResponse repo_list_recent_commits(
+    1: RepoSpecifier repo,
+    2: RepoListRecentCommitsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Generate commit info for all the draft commits
   /// for the given set of heads.and public roots.
   RepoStackInfoResponse repo_stack_info(
@@ -2135,6 +3359,33 @@ service SourceControlService extends fb303_core.BaseService {
     2: RepoStackInfoParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Get information about the same path in many commits, e.g. to follow
+  /// how a file's size changes across release branches.
+  RepoPathMultiCommitInfoResponse repo_path_multi_commit_info(
+    1: RepoSpecifier repo,
+    2: RepoPathMultiCommitInfoParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Get size and derivation statistics for a repository at a bookmark.
+  /// Counting commits can be slow for large repos, so results are cached.
+  RepoStatisticsResponse repo_statistics(
+    1: RepoSpecifier repo,
+    2: RepoStatisticsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Get size and content hashes for many files at once by content id.
+  RepoFileMetadataResponse repo_file_metadata(
+    1: RepoSpecifier repo,
+    2: RepoFileMetadataParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// List the hooks that run on pushes to a bookmark, with their
+  /// configuration.
+  RepoListHookConfigsResponse repo_list_hook_configs(
+    1: RepoSpecifier repo,
+    2: RepoListHookConfigsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Repository write methods
   /// ========================
 
@@ -2159,6 +3410,13 @@ service SourceControlService extends fb303_core.BaseService {
     2: RepoCreateBookmarkParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Create a publishing bookmark for a new branch at a commit, and
+  /// restrict who may move it, in a single transaction.
+  RepoCreateBranchFromCommitResponse repo_create_branch_from_commit(
+    1: RepoSpecifier repo,
+    2: RepoCreateBranchFromCommitParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Move a bookmark.
   RepoMoveBookmarkResponse repo_move_bookmark(
     1: RepoSpecifier repo,
@@ -2182,6 +3440,21 @@ service SourceControlService extends fb303_core.BaseService {
     4: HookRejectionsException hook_rejections,
   );
 
+  /// Create a commit on top of a bookmark that changes only a single path to
+  /// point at new content or a new submodule commit, and land it via
+  /// pushrebase.  This is a shortcut for repo_create_commit followed by
+  /// repo_land_stack for bots that bump pointers.  Hooks run as for
+  /// repo_land_stack.
+  RepoUpdateSubmoduleResponse repo_update_submodule(
+    1: RepoSpecifier repo,
+    2: RepoUpdateSubmoduleParams params,
+  ) throws (
+    1: RequestError request_error,
+    2: InternalError internal_error,
+    3: PushrebaseConflictsException pushrebase_conflicts,
+    4: HookRejectionsException hook_rejections,
+  );
+
   /// Derive data for commits in a repo
   RepoPrepareCommitsResponse repo_prepare_commits(
     1: RepoSpecifier repo,
@@ -2194,6 +3467,25 @@ service SourceControlService extends fb303_core.BaseService {
     2: RepoUploadFileContentParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Run a single hook against the most recent commits on a bookmark, as the
+  /// hook tailer does, and report which commits it rejects.  Hook bypasses
+  /// are ignored.  This lets hook authors check new hook logic against real
+  /// history.  Only services permitted to call this method may use it.
+  RepoRunHookOnHistoryResponse repo_run_hook_on_history(
+    1: RepoSpecifier repo,
+    2: RepoRunHookOnHistoryParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Check a commit message against the bookmark's hooks that only check
+  /// commit messages, before the commit is created, e.g. from a commit
+  /// dialog.  Hooks bypassed by the message are not run, as on push.  A
+  /// message that passes may still be rejected on push by hooks that check
+  /// more than the message.
+  RepoValidateCommitMessageResponse repo_validate_commit_message(
+    1: RepoSpecifier repo,
+    2: RepoValidateCommitMessageParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Commit methods
   /// ==============
 
@@ -2208,12 +3500,38 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitFileDiffsParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// As commit_file_diffs, but runs in the background.  Returns a token
+  /// immediately; use request_poll to get the result.
+  AsyncRequestToken commit_file_diffs_async(
+    1: CommitSpecifier commit,
+    2: CommitFileDiffsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Look-up a commit to see if it exists and find alternative IDs.
   CommitLookupResponse commit_lookup(
     1: CommitSpecifier commit,
     2: CommitLookupParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Translate many commit ids from one identity scheme to others, e.g. to
+  /// convert a database of hg hashes to bonsai or git.  Only the id mappings
+  /// are queried, so this is much cheaper than looking up each commit.
+  /// Bonsai ids are not checked for existence.
+  CommitTranslateIdsResponse commit_translate_ids(
+    1: RepoSpecifier repo,
+    2: CommitTranslateIdsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Look up many commits in one request.
+  ///
+  /// Unlike commit_lookup, a commit that doesn't exist or a commit id that
+  /// is malformed is reported in that commit's result, rather than failing
+  /// the whole request.
+  CommitMultipleLookupResponse commit_multiple_lookup(
+    1: RepoSpecifier repo,
+    2: CommitMultipleLookupParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Look up commit history over Pushrebase mutations. It finishes on commit
   /// version that was originally pushed. Provided commit must be public.
   ///
@@ -2232,18 +3550,49 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitLookupPushrebaseHistoryParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Get the recorded Mercurial mutation history (amends, rebases, folds,
+  /// splits, etc.) that produced this commit, and that it was later
+  /// mutated by.
+  CommitMutationsResponse commit_mutations(
+    1: CommitSpecifier commit,
+    2: CommitMutationsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Get commit info.
   CommitInfo commit_info(
     1: CommitSpecifier commit,
     2: CommitInfoParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Get whether a commit is public or a draft.  This is cheaper than
+  /// `commit_info` for clients that only need the phase.
+  CommitPhaseResponse commit_phase(
+    1: CommitSpecifier commit,
+    2: CommitPhaseParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Report which types of derived data have already been derived for this
+  /// commit, to predict whether requests needing them will be fast.
+  CommitDerivedDataInfoResponse commit_derived_data_info(
+    1: CommitSpecifier commit,
+    2: CommitDerivedDataInfoParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Check if this commit is an ancestor of some other commit.
   bool commit_is_ancestor_of(
     1: CommitSpecifier commit,
     2: CommitIsAncestorOfParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Find which of a set of bookmarks have this commit as an ancestor, e.g.
+  /// to check whether a change has reached a release branch.  The bookmarks
+  /// are checked together, which is cheaper than calling
+  /// `commit_is_ancestor_of` for each of them.
+  CommitContainedInBookmarksResponse commit_contained_in_bookmarks(
+    1: CommitSpecifier commit,
+    2: CommitContainedInBookmarksParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Find the lowest common ancestor of two commits.
   ///
   /// In case of ambiguity (can happen with multiple merges of the same
@@ -2261,6 +3610,14 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitCompareParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// As commit_compare, but runs in the background, for comparisons that
+  /// would not finish within the request timeout.  Returns a token
+  /// immediately; use request_poll to get the result.
+  AsyncRequestToken commit_compare_async(
+    1: CommitSpecifier commit,
+    2: CommitCompareParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Find files within the commit that match criteria.
   CommitFindFilesResponse commit_find_files(
     1: CommitSpecifier commit,
@@ -2277,6 +3634,13 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitListDescendantBookmarksParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// List the descendants of a commit within the history of some bookmarks.
+  /// Useful for searching forward from a known commit, e.g. for bisection.
+  CommitListDescendantsResponse commit_list_descendants(
+    1: CommitSpecifier commit,
+    2: CommitListDescendantsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Run hooks for a commit without landing it. Useful for getting early signal.
   /// It is NOT guaranteed that a push will succeed if all hooks pass,
   /// as things other than hooks can fail - e.g. rebase failures.
@@ -2285,6 +3649,15 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitRunHooksParams params,
   ) throws (1: RequestError request_error, 2: InternalError interal_error);
 
+  /// Check whether a commit is ready to land to a bookmark, combining a hook
+  /// dry run, prediction of conflicts with the bookmark, and ancestry checks.
+  /// As with commit_run_hooks, a landable commit may still fail to land,
+  /// e.g. if the bookmark moves in the meantime.
+  CommitLandabilityResponse commit_landability(
+    1: CommitSpecifier commit,
+    2: CommitLandabilityParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// CommitPath methods
   /// ==============
 
@@ -2311,6 +3684,14 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitPathBlameParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Get the compact blame of a file, along with the commit information of
+  /// every commit it references.  This is everything needed to display the
+  /// blame of a file in a single request.
+  CommitFileAnnotateHistoryResponse commit_file_annotate_history(
+    1: CommitPathSpecifier commit_path,
+    2: CommitFileAnnotateHistoryParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   CommitPathHistoryResponse commit_path_history(
     1: CommitPathSpecifier commit_path,
     2: CommitPathHistoryParams params,
@@ -2321,6 +3702,25 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitPathLastChangedParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Find the commit in which a path was first introduced.
+  ///
+  /// History is followed back from the given commit without crossing
+  /// deletions, so if the path was deleted and later re-added this returns
+  /// the commit that re-added it.
+  CommitPathFirstIntroducedResponse commit_path_first_introduced(
+    1: CommitPathSpecifier commit_path,
+    2: CommitPathFirstIntroducedParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// List paths under a path that differ only by case, which can't be
+  /// checked out together on case-insensitive filesystems.  This uses the
+  /// same case conflict check as pushes to public bookmarks.  A path that
+  /// is a file or doesn't exist has no case conflicts.
+  CommitPathCaseConflictsResponse commit_path_case_conflicts(
+    1: CommitPathSpecifier commit_path,
+    2: CommitPathCaseConflictsParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   CommitMultiplePathLastChangedResponse commit_multiple_path_last_changed(
     1: CommitSpecifier commit,
     2: CommitMultiplePathLastChangedParams params,
@@ -2338,6 +3738,22 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitSparseProfileSizeParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Resolve sparse profiles at a commit, following %include chains, and
+  /// summarize the files each one matches.
+  CommitSparseProfileFilesResponse commit_sparse_profile_files(
+    1: CommitSpecifier commit,
+    2: CommitSparseProfileFilesParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Export the files at a commit as an archive, so that a snapshot of the
+  /// source can be fetched without cloning the repo.  The total size of the
+  /// files must be less than COMMIT_EXPORT_ARCHIVE_SIZE_LIMIT, and there
+  /// must be at most COMMIT_EXPORT_ARCHIVE_FILE_COUNT_LIMIT files.
+  CommitExportArchiveResponse commit_export_archive(
+    1: CommitSpecifier commit,
+    2: CommitExportArchiveParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Tree Methods
   /// ============
 
@@ -2353,6 +3769,25 @@ service SourceControlService extends fb303_core.BaseService {
     2: TreeListParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// List the contents of a directory and the directories beneath it, down
+  /// to a given depth.
+  ///
+  /// This saves clients expanding several levels of a tree from making a
+  /// tree_list request for each directory.
+  TreeListRecursiveResponse tree_list_recursive(
+    1: TreeSpecifier tree,
+    2: TreeListRecursiveParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
+  /// Fetch the entries of entire subtrees of a commit in one request.
+  ///
+  /// This is intended for clients that want to warm many directories ahead
+  /// of access, which would otherwise need a tree_list request for each.
+  TreePrefetchResponse tree_prefetch(
+    1: CommitSpecifier commit,
+    2: TreePrefetchParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// File Methods
   /// ============
 
@@ -2389,6 +3824,13 @@ service SourceControlService extends fb303_core.BaseService {
     2: CommitLookupXRepoParams params,
   ) throws (1: RequestError request_error, 2: InternalError internal_error);
 
+  /// Map paths in a commit to the equivalent paths in another repo, using
+  /// the commit sync config the commit was synced with
+  MegarepoPathMappingResponse megarepo_path_mapping(
+    1: CommitSpecifier commit,
+    2: MegarepoPathMappingParams params,
+  ) throws (1: RequestError request_error, 2: InternalError internal_error);
+
   /// Megarepo Service Methods
   /// ========================
 
@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Requests that run in the background.
+//!
+//! Expensive requests can be started with one of the `*_async` methods, which
+//! return a token immediately.  The request runs to completion on this
+//! server, and the client polls for the result with `request_poll`.  Results
+//! are only held in memory, so they are lost if the server restarts.  Only
+//! the client that started a request can poll for it, and at most
+//! `MAX_BACKGROUND_REQUESTS` are held at once.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use source_control as thrift;
+
+use crate::errors;
+
+/// How long the result of a completed request is kept for.
+const COMPLETED_REQUEST_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Maximum number of requests, in progress or completed, held at once.
+const MAX_BACKGROUND_REQUESTS: usize = 1000;
+
+type BackgroundResult = Result<thrift::AsyncRequestResult, errors::ServiceError>;
+
+enum BackgroundRequestState {
+    InProgress,
+    Complete {
+        result: BackgroundResult,
+        completed: Instant,
+    },
+}
+
+struct BackgroundRequest {
+    /// The identity of the client that started the request.
+    owner: String,
+    started: Instant,
+    state: BackgroundRequestState,
+}
+
+impl BackgroundRequest {
+    fn completed(&self) -> Option<Instant> {
+        match &self.state {
+            BackgroundRequestState::InProgress => None,
+            BackgroundRequestState::Complete { completed, .. } => Some(*completed),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct BackgroundRequests {
+    requests: Arc<Mutex<HashMap<String, BackgroundRequest>>>,
+    max_requests: usize,
+    ttl: Duration,
+}
+
+impl Default for BackgroundRequests {
+    fn default() -> Self {
+        Self::new(MAX_BACKGROUND_REQUESTS, COMPLETED_REQUEST_TTL)
+    }
+}
+
+fn unknown_token(token: &thrift::AsyncRequestToken) -> errors::ServiceError {
+    errors::invalid_request(format!(
+        "unknown request token '{}': the request may have expired, or been started on another server",
+        token.id
+    ))
+    .into()
+}
+
+impl BackgroundRequests {
+    fn new(max_requests: usize, ttl: Duration) -> Self {
+        Self {
+            requests: Arc::new(Mutex::new(HashMap::new())),
+            max_requests,
+            ttl,
+        }
+    }
+
+    // Drop completed requests whose results have expired.
+    fn expire(&self, requests: &mut HashMap<String, BackgroundRequest>) {
+        requests.retain(|_, request| {
+            request
+                .completed()
+                .map_or(true, |completed| completed.elapsed() < self.ttl)
+        });
+    }
+
+    /// Start running a request in the background for the client with the
+    /// given identity, returning the token that can be used to poll for its
+    /// result.  If too many requests are held, the oldest completed result
+    /// is dropped to make room, and if they are all in progress the request
+    /// is refused.
+    pub(crate) fn start(
+        &self,
+        id: String,
+        owner: String,
+        fut: impl Future<Output = BackgroundResult> + Send + 'static,
+    ) -> Result<thrift::AsyncRequestToken, errors::ServiceError> {
+        {
+            let mut requests = self.requests.lock().expect("lock poisoned");
+            self.expire(&mut requests);
+            if requests.len() >= self.max_requests {
+                let oldest = requests
+                    .iter()
+                    .filter_map(|(id, request)| Some((request.completed()?, id.clone())))
+                    .min();
+                match oldest {
+                    Some((_, oldest)) => {
+                        requests.remove(&oldest);
+                    }
+                    None => {
+                        return Err(errors::not_available(format!(
+                            "too many background requests in progress (at most {}), try again later",
+                            self.max_requests
+                        ))
+                        .into());
+                    }
+                }
+            }
+            requests.insert(
+                id.clone(),
+                BackgroundRequest {
+                    owner,
+                    started: Instant::now(),
+                    state: BackgroundRequestState::InProgress,
+                },
+            );
+        }
+
+        let requests = self.requests.clone();
+        let token_id = id.clone();
+        tokio::spawn(async move {
+            // Run the request in its own task so that a panic is reported as
+            // an error to the poller, rather than leaving it in progress.
+            let result = match tokio::spawn(fut).await {
+                Ok(result) => result,
+                Err(e) => Err(errors::internal_error(format!("request failed: {}", e)).into()),
+            };
+            let mut requests = requests.lock().expect("lock poisoned");
+            if let Some(request) = requests.get_mut(&token_id) {
+                request.state = BackgroundRequestState::Complete {
+                    result,
+                    completed: Instant::now(),
+                };
+            }
+        });
+
+        Ok(thrift::AsyncRequestToken {
+            id,
+            ..Default::default()
+        })
+    }
+
+    /// Get the status of a request for the client with the given identity.
+    /// If the request failed, its error is returned.  Requests started by
+    /// other clients are reported as unknown.
+    pub(crate) fn poll(
+        &self,
+        token: &thrift::AsyncRequestToken,
+        caller: &str,
+    ) -> Result<thrift::AsyncRequestPollResponse, errors::ServiceError> {
+        let mut requests = self.requests.lock().expect("lock poisoned");
+        self.expire(&mut requests);
+        let request = requests
+            .get(&token.id)
+            .filter(|request| request.owner == caller)
+            .ok_or_else(|| unknown_token(token))?;
+        match &request.state {
+            BackgroundRequestState::InProgress => Ok(thrift::AsyncRequestPollResponse {
+                status: thrift::AsyncRequestStatus::IN_PROGRESS,
+                elapsed_ms: request.started.elapsed().as_millis() as i64,
+                result: None,
+                ..Default::default()
+            }),
+            BackgroundRequestState::Complete { result, completed } => {
+                let result = result.clone()?;
+                Ok(thrift::AsyncRequestPollResponse {
+                    status: thrift::AsyncRequestStatus::COMPLETE,
+                    elapsed_ms: completed.duration_since(request.started).as_millis() as i64,
+                    result: Some(result),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    fn token(id: &str) -> thrift::AsyncRequestToken {
+        thrift::AsyncRequestToken {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn result() -> BackgroundResult {
+        Ok(thrift::AsyncRequestResult::commit_compare(
+            Default::default(),
+        ))
+    }
+
+    async fn wait_complete(requests: &BackgroundRequests, id: &str, caller: &str) {
+        loop {
+            let response = requests.poll(&token(id), caller).expect("request is known");
+            if response.status == thrift::AsyncRequestStatus::COMPLETE {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_owner_only() -> Result<(), errors::ServiceError> {
+        let requests = BackgroundRequests::default();
+        let (sender, receiver) = oneshot::channel();
+        requests.start("a".to_string(), "alice".to_string(), async move {
+            receiver.await.expect("sender kept");
+            result()
+        })?;
+
+        let response = requests.poll(&token("a"), "alice")?;
+        assert_eq!(response.status, thrift::AsyncRequestStatus::IN_PROGRESS);
+        assert!(requests.poll(&token("a"), "bob").is_err());
+        assert!(requests.poll(&token("b"), "alice").is_err());
+
+        sender.send(()).expect("receiver kept");
+        wait_complete(&requests, "a", "alice").await;
+        assert!(requests.poll(&token("a"), "alice")?.result.is_some());
+        assert!(requests.poll(&token("a"), "bob").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_capacity() -> Result<(), errors::ServiceError> {
+        let requests = BackgroundRequests::new(2, COMPLETED_REQUEST_TTL);
+        requests.start(
+            "done".to_string(),
+            "alice".to_string(),
+            future::ready(result()),
+        )?;
+        wait_complete(&requests, "done", "alice").await;
+        requests.start("a".to_string(), "alice".to_string(), future::pending())?;
+
+        // The completed request makes room for a new one.
+        requests.start("b".to_string(), "alice".to_string(), future::pending())?;
+        assert!(requests.poll(&token("done"), "alice").is_err());
+
+        // With every request in progress, new ones are refused.
+        assert!(requests
+            .start("c".to_string(), "alice".to_string(), future::pending())
+            .is_err());
+        assert!(requests.poll(&token("a"), "alice").is_ok());
+        assert!(requests.poll(&token("b"), "alice").is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expire_on_poll() -> Result<(), errors::ServiceError> {
+        let requests = BackgroundRequests::new(10, Duration::ZERO);
+        let (sender, receiver) = oneshot::channel();
+        requests.start("a".to_string(), "alice".to_string(), async move {
+            receiver.await.expect("sender kept");
+            result()
+        })?;
+        // In progress requests don't expire.
+        assert!(requests.poll(&token("a"), "alice").is_ok());
+
+        sender.send(()).expect("receiver kept");
+        loop {
+            // Once complete, the result expires at once.
+            if requests.poll(&token("a"), "alice").is_err() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(requests.requests.lock().expect("lock poisoned").is_empty());
+        Ok(())
+    }
+}
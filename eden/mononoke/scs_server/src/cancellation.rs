@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Cancellation of work started on behalf of a request.
+//!
+//! When a client disconnects, the thrift server drops the future that is
+//! handling its request, which stops everything that future was polling.
+//! Tasks spawned onto the runtime are not polled by the request future, so
+//! they would carry on until they complete.  Spawn them with
+//! `spawn_cancellable` instead, so that they are aborted with the request.
+//!
+//! Some work deliberately outlives the request that started it: `*_async`
+//! requests run until their result is polled for, and repo statistics are
+//! refreshed for later callers.  Those tasks are spawned directly.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use tokio::task::JoinError;
+use tokio::task::JoinHandle;
+
+/// A spawned task that is aborted when its handle is dropped.
+pub(crate) struct CancellableTask<T>(JoinHandle<T>);
+
+impl<T> Future for CancellableTask<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl<T> Drop for CancellableTask<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Spawn a task that is cancelled if the returned handle is dropped before
+/// the task completes.
+pub(crate) fn spawn_cancellable<F>(fut: F) -> CancellableTask<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    CancellableTask(tokio::task::spawn(fut))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future;
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    /// Spawn a task that never completes, returning its handle, once it has
+    /// started, and a receiver that resolves when the task is dropped.
+    async fn spawn_pending() -> (CancellableTask<()>, oneshot::Receiver<()>) {
+        let (started_sender, started) = oneshot::channel();
+        let (dropped_sender, dropped) = oneshot::channel::<()>();
+        let task = spawn_cancellable(async move {
+            let _dropped_sender = dropped_sender;
+            started_sender.send(()).expect("receiver kept");
+            future::pending::<()>().await
+        });
+        started.await.expect("task started");
+        (task, dropped)
+    }
+
+    #[tokio::test]
+    async fn test_completes() -> Result<(), JoinError> {
+        assert_eq!(spawn_cancellable(async { 42 }).await?, 42);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dropped_task_is_cancelled() {
+        let (task, dropped) = spawn_pending().await;
+        drop(task);
+        // The task is dropped without completing, so its sender is dropped
+        // without sending.
+        tokio::time::timeout(Duration::from_secs(60), dropped)
+            .await
+            .expect("task should be cancelled")
+            .expect_err("task should not complete");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_with_request() {
+        // When a client disconnects, the request future is dropped, which
+        // drops any task it is waiting on.
+        let (task, dropped) = spawn_pending().await;
+        let request = async move {
+            task.await.expect("task should not fail");
+        };
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), request).await;
+        assert!(cancelled.is_err());
+        tokio::time::timeout(Duration::from_secs(60), dropped)
+            .await
+            .expect("task should be cancelled")
+            .expect_err("task should not complete");
+    }
+}
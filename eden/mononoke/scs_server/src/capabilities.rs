@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Optional features supported by the service.
+//!
+//! Clients query these with service_capabilities so that they can detect
+//! what a server supports during staged rollouts.  A method that belongs to
+//! an optional feature must be listed under its capability here.
+
+use std::collections::BTreeSet;
+
+use source_control as thrift;
+
+const WRITE_METHODS: &[&str] = &[
+    "repo_create_commit",
+    "repo_create_stack",
+    "repo_create_bookmark",
+    "repo_create_branch_from_commit",
+    "repo_move_bookmark",
+    "repo_delete_bookmark",
+    "repo_land_stack",
+    "repo_update_submodule",
+    "repo_upload_file_content",
+];
+
+const ASYNC_REQUEST_METHODS: &[&str] = &[
+    "request_poll",
+    "commit_file_diffs_async",
+    "commit_compare_async",
+];
+
+const MEGAREPO_METHODS: &[&str] = &[
+    "megarepo_path_mapping",
+    "megarepo_add_sync_target_config",
+    "megarepo_read_target_config",
+    "megarepo_add_sync_target",
+    "megarepo_add_sync_target_poll",
+    "megarepo_add_branching_sync_target",
+    "megarepo_add_branching_sync_target_poll",
+    "megarepo_change_target_config",
+    "megarepo_change_target_config_poll",
+    "megarepo_sync_changeset",
+    "megarepo_sync_changeset_poll",
+    "megarepo_remerge_source",
+    "megarepo_remerge_source_poll",
+];
+
+const GIT_IMPORT_METHODS: &[&str] = &["upload_git_object", "create_git_tree", "create_git_tag"];
+
+/// The capabilities this server supports, with the methods that belong to
+/// each.
+fn supported() -> [(thrift::ServiceCapability, &'static [&'static str]); 4] {
+    [
+        (thrift::ServiceCapability::WRITE_METHODS, WRITE_METHODS),
+        (
+            thrift::ServiceCapability::ASYNC_REQUESTS,
+            ASYNC_REQUEST_METHODS,
+        ),
+        (thrift::ServiceCapability::MEGAREPO, MEGAREPO_METHODS),
+        (thrift::ServiceCapability::GIT_IMPORT, GIT_IMPORT_METHODS),
+    ]
+}
+
+/// The API version and capabilities, for reporting to clients.
+pub(crate) fn service_capabilities() -> thrift::ServiceCapabilitiesResponse {
+    let mut capabilities = BTreeSet::new();
+    let mut methods = BTreeSet::new();
+    for (capability, capability_methods) in supported() {
+        capabilities.insert(capability);
+        methods.extend(capability_methods.iter().map(|method| method.to_string()));
+    }
+    thrift::ServiceCapabilitiesResponse {
+        api_version: thrift::consts::SERVICE_API_VERSION,
+        capabilities,
+        methods,
+        ..Default::default()
+    }
+}
@@ -14,6 +14,9 @@ use mononoke_api::MononokeError;
 use source_control as thrift;
 use source_control::services::source_control_service as service;
 
+use crate::limits;
+
+#[derive(Clone)]
 pub(crate) enum ServiceError {
     Request(thrift::RequestError),
     Internal(thrift::InternalError),
@@ -54,11 +57,17 @@ impl LoggableError for ServiceError {
 impl ServiceError {
     pub fn context(self, context: &str) -> Self {
         match self {
-            Self::Request(thrift::RequestError { kind, reason, .. }) => {
+            Self::Request(thrift::RequestError {
+                kind,
+                reason,
+                conflict_paths,
+                ..
+            }) => {
                 let reason = format!("{}: {}", context, reason);
                 Self::Request(thrift::RequestError {
                     kind,
                     reason,
+                    conflict_paths,
                     ..Default::default()
                 })
             }
@@ -144,11 +153,16 @@ impl From<MononokeError> for ServiceError {
                 reason,
                 ..Default::default()
             }),
-            error @ MononokeError::MergeConflicts { .. } => Self::Request(thrift::RequestError {
-                kind: thrift::RequestErrorKind::MERGE_CONFLICTS,
-                reason: error.to_string(),
-                ..Default::default()
-            }),
+            MononokeError::MergeConflicts { ref conflict_paths } => {
+                Self::Request(thrift::RequestError {
+                    kind: thrift::RequestErrorKind::MERGE_CONFLICTS,
+                    reason: e.to_string(),
+                    conflict_paths: Some(
+                        conflict_paths.iter().map(|path| path.to_string()).collect(),
+                    ),
+                    ..Default::default()
+                })
+            }
             error @ MononokeError::ServicePermissionDenied { .. } => {
                 Self::Request(thrift::RequestError {
                     kind: thrift::RequestErrorKind::PERMISSION_DENIED,
@@ -161,11 +175,40 @@ impl From<MononokeError> for ServiceError {
                 reason: error.to_string(),
                 ..Default::default()
             }),
+            MononokeError::BookmarkPolicyDenied { ref policy, .. } => {
+                Self::Request(thrift::RequestError {
+                    kind: thrift::RequestErrorKind::PERMISSION_DENIED,
+                    reason: e.to_string(),
+                    bookmark_policy: Some(thrift::BookmarkPolicy {
+                        name: policy.name.clone(),
+                        allowed_users: policy.allowed_users.clone(),
+                        allowed_group: policy.allowed_group.clone(),
+                        allowed_pushers: policy.allowed_pushers.clone(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            }
             error @ MononokeError::NotAvailable(_) => Self::Request(thrift::RequestError {
                 kind: thrift::RequestErrorKind::NOT_AVAILABLE,
                 reason: error.to_string(),
                 ..Default::default()
             }),
+            error @ MononokeError::NotADirectory(_) => Self::Request(thrift::RequestError {
+                kind: thrift::RequestErrorKind::NOT_A_DIRECTORY,
+                reason: error.to_string(),
+                ..Default::default()
+            }),
+            error @ MononokeError::PathTooLarge(_) => Self::Request(thrift::RequestError {
+                kind: thrift::RequestErrorKind::PATH_TOO_LARGE,
+                reason: error.to_string(),
+                ..Default::default()
+            }),
+            error @ MononokeError::Conflict(_) => Self::Request(thrift::RequestError {
+                kind: thrift::RequestErrorKind::CONFLICT,
+                reason: error.to_string(),
+                ..Default::default()
+            }),
             error @ MononokeError::HookFailure(_) => Self::Request(thrift::RequestError {
                 kind: thrift::RequestErrorKind::INVALID_REQUEST,
                 reason: error.to_string(),
@@ -213,47 +256,81 @@ macro_rules! impl_into_thrift_error {
 }
 
 impl_into_thrift_error!(service::ListReposExn);
+impl_into_thrift_error!(service::ServiceLimitsExn);
+impl_into_thrift_error!(service::ServiceCapabilitiesExn);
+impl_into_thrift_error!(service::ServiceUsageExn);
+impl_into_thrift_error!(service::RequestPollExn);
 impl_into_thrift_error!(service::RepoInfoExn);
 impl_into_thrift_error!(service::RepoResolveBookmarkExn);
+impl_into_thrift_error!(service::RepoResolveGlobalrevExn);
+impl_into_thrift_error!(service::RepoLatestGlobalrevExn);
+impl_into_thrift_error!(service::RepoCommitsInGlobalrevRangeExn);
 impl_into_thrift_error!(service::RepoResolveCommitPrefixExn);
 impl_into_thrift_error!(service::RepoListBookmarksExn);
+impl_into_thrift_error!(service::RepoListRecentCommitsExn);
 impl_into_thrift_error!(service::RepoCreateCommitExn);
 impl_into_thrift_error!(service::RepoCreateStackExn);
 impl_into_thrift_error!(service::RepoCreateBookmarkExn);
+impl_into_thrift_error!(service::RepoCreateBranchFromCommitExn);
 impl_into_thrift_error!(service::RepoMoveBookmarkExn);
 impl_into_thrift_error!(service::RepoDeleteBookmarkExn);
 impl_into_thrift_error!(service::RepoLandStackExn);
+impl_into_thrift_error!(service::RepoUpdateSubmoduleExn);
 impl_into_thrift_error!(service::RepoBookmarkInfoExn);
 impl_into_thrift_error!(service::RepoStackInfoExn);
+impl_into_thrift_error!(service::RepoPathMultiCommitInfoExn);
+impl_into_thrift_error!(service::RepoStatisticsExn);
+impl_into_thrift_error!(service::RepoFileMetadataExn);
+impl_into_thrift_error!(service::RepoListHookConfigsExn);
 impl_into_thrift_error!(service::RepoPrepareCommitsExn);
 impl_into_thrift_error!(service::RepoUploadFileContentExn);
+impl_into_thrift_error!(service::RepoRunHookOnHistoryExn);
+impl_into_thrift_error!(service::RepoValidateCommitMessageExn);
 impl_into_thrift_error!(service::CommitCommonBaseWithExn);
 impl_into_thrift_error!(service::CommitFileDiffsExn);
+impl_into_thrift_error!(service::CommitFileDiffsAsyncExn);
 impl_into_thrift_error!(service::CommitLookupExn);
 impl_into_thrift_error!(service::CommitLookupPushrebaseHistoryExn);
+impl_into_thrift_error!(service::CommitTranslateIdsExn);
+impl_into_thrift_error!(service::CommitMultipleLookupExn);
+impl_into_thrift_error!(service::CommitMutationsExn);
 impl_into_thrift_error!(service::CommitInfoExn);
+impl_into_thrift_error!(service::CommitPhaseExn);
+impl_into_thrift_error!(service::CommitDerivedDataInfoExn);
 impl_into_thrift_error!(service::CommitCompareExn);
+impl_into_thrift_error!(service::CommitCompareAsyncExn);
 impl_into_thrift_error!(service::CommitIsAncestorOfExn);
+impl_into_thrift_error!(service::CommitContainedInBookmarksExn);
 impl_into_thrift_error!(service::CommitFindFilesExn);
 impl_into_thrift_error!(service::CommitHistoryExn);
 impl_into_thrift_error!(service::CommitListDescendantBookmarksExn);
+impl_into_thrift_error!(service::CommitListDescendantsExn);
 impl_into_thrift_error!(service::CommitRunHooksExn);
+impl_into_thrift_error!(service::CommitLandabilityExn);
 impl_into_thrift_error!(service::CommitPathExistsExn);
 impl_into_thrift_error!(service::CommitPathInfoExn);
 impl_into_thrift_error!(service::CommitMultiplePathInfoExn);
 impl_into_thrift_error!(service::CommitPathBlameExn);
+impl_into_thrift_error!(service::CommitFileAnnotateHistoryExn);
 impl_into_thrift_error!(service::CommitPathHistoryExn);
 impl_into_thrift_error!(service::CommitPathLastChangedExn);
+impl_into_thrift_error!(service::CommitPathFirstIntroducedExn);
+impl_into_thrift_error!(service::CommitPathCaseConflictsExn);
 impl_into_thrift_error!(service::CommitMultiplePathLastChangedExn);
 impl_into_thrift_error!(service::CommitSparseProfileDeltaExn);
 impl_into_thrift_error!(service::CommitSparseProfileSizeExn);
+impl_into_thrift_error!(service::CommitSparseProfileFilesExn);
+impl_into_thrift_error!(service::CommitExportArchiveExn);
 impl_into_thrift_error!(service::TreeExistsExn);
 impl_into_thrift_error!(service::TreeListExn);
+impl_into_thrift_error!(service::TreeListRecursiveExn);
+impl_into_thrift_error!(service::TreePrefetchExn);
 impl_into_thrift_error!(service::FileExistsExn);
 impl_into_thrift_error!(service::FileInfoExn);
 impl_into_thrift_error!(service::FileContentChunkExn);
 impl_into_thrift_error!(service::FileDiffExn);
 impl_into_thrift_error!(service::CommitLookupXrepoExn);
+impl_into_thrift_error!(service::MegarepoPathMappingExn);
 impl_into_thrift_error!(service::MegarepoAddSyncTargetConfigExn);
 impl_into_thrift_error!(service::MegarepoReadTargetConfigExn);
 impl_into_thrift_error!(service::MegarepoAddSyncTargetExn);
@@ -335,19 +412,43 @@ pub(crate) fn diff_input_too_big(total_size: u64) -> thrift::RequestError {
         kind: thrift::RequestErrorKind::INVALID_REQUEST_INPUT_TOO_BIG,
         reason: format!(
             "only {} bytes of files (in total) can be diffed in one request, you asked for {} bytes",
-            thrift::consts::COMMIT_FILE_DIFFS_SIZE_LIMIT,
+            limits::commit_file_diffs_size_limit(),
             total_size,
         ),
         ..Default::default()
     }
 }
 
+pub(crate) fn archive_too_big(total_size: u64) -> thrift::RequestError {
+    thrift::RequestError {
+        kind: thrift::RequestErrorKind::INVALID_REQUEST_INPUT_TOO_BIG,
+        reason: format!(
+            "only {} bytes of files (in total) can be exported in one archive, this archive has at least {} bytes",
+            limits::commit_export_archive_size_limit(),
+            total_size,
+        ),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn archive_too_many_files(file_count: u64) -> thrift::RequestError {
+    thrift::RequestError {
+        kind: thrift::RequestErrorKind::INVALID_REQUEST_INPUT_TOO_BIG,
+        reason: format!(
+            "only {} files can be exported in one archive, this archive has at least {} files",
+            limits::commit_export_archive_file_count_limit(),
+            file_count,
+        ),
+        ..Default::default()
+    }
+}
+
 pub(crate) fn diff_input_too_many_paths(path_count: usize) -> thrift::RequestError {
     thrift::RequestError {
         kind: thrift::RequestErrorKind::INVALID_REQUEST_TOO_MANY_PATHS,
         reason: format!(
             "only at most {} paths can be diffed in one request, you asked for {}",
-            thrift::consts::COMMIT_FILE_DIFFS_PATH_COUNT_LIMIT,
+            limits::commit_file_diffs_path_count_limit(),
             path_count,
         ),
         ..Default::default()
@@ -362,6 +463,22 @@ pub(crate) fn not_available(reason: String) -> thrift::RequestError {
     }
 }
 
+pub(crate) fn permission_denied(reason: String) -> thrift::RequestError {
+    thrift::RequestError {
+        kind: thrift::RequestErrorKind::PERMISSION_DENIED,
+        reason,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn method_disabled_for_repo(method: &str, repo: &str) -> thrift::RequestError {
+    thrift::RequestError {
+        kind: thrift::RequestErrorKind::METHOD_DISABLED_FOR_REPO,
+        reason: format!("method {} is not enabled for repo {}", method, repo),
+        ..Default::default()
+    }
+}
+
 #[allow(unused)]
 pub(crate) fn not_implemented(reason: String) -> thrift::RequestError {
     thrift::RequestError {
@@ -19,11 +19,15 @@ use fb303_core::services::base_service::GetStatusExn;
 #[derive(Clone)]
 pub struct BaseServiceImpl {
     will_exit: Arc<AtomicBool>,
+    warming_up: Arc<AtomicBool>,
 }
 
 impl BaseServiceImpl {
-    pub fn new(will_exit: Arc<AtomicBool>) -> Self {
-        Self { will_exit }
+    pub fn new(will_exit: Arc<AtomicBool>, warming_up: Arc<AtomicBool>) -> Self {
+        Self {
+            will_exit,
+            warming_up,
+        }
     }
 }
 
@@ -34,18 +38,22 @@ impl BaseService for BaseServiceImpl {
     }
 
     async fn getStatus(&self) -> Result<fb303_status, GetStatusExn> {
-        if !self.will_exit.load(Ordering::Relaxed) {
-            Ok(fb303_status::ALIVE)
-        } else {
+        if self.will_exit.load(Ordering::Relaxed) {
             Ok(fb303_status::STOPPING)
+        } else if self.warming_up.load(Ordering::Relaxed) {
+            Ok(fb303_status::STARTING)
+        } else {
+            Ok(fb303_status::ALIVE)
         }
     }
 
     async fn getStatusDetails(&self) -> Result<String, GetStatusDetailsExn> {
-        if !self.will_exit.load(Ordering::Relaxed) {
-            Ok("Alive and running.".to_string())
-        } else {
+        if self.will_exit.load(Ordering::Relaxed) {
             Ok("Shutting down.".to_string())
+        } else if self.warming_up.load(Ordering::Relaxed) {
+            Ok("Warming up repos.".to_string())
+        } else {
+            Ok("Alive and running.".to_string())
         }
     }
 }
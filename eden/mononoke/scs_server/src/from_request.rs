@@ -28,6 +28,7 @@ use mononoke_api::specifiers::Globalrev;
 use mononoke_api::specifiers::Svnrev;
 use mononoke_api::BookmarkKey;
 use mononoke_api::CandidateSelectionHintArgs;
+use mononoke_api::ChangesetHistoryOrder;
 use mononoke_api::ChangesetId;
 use mononoke_api::ChangesetIdPrefix;
 use mononoke_api::ChangesetPrefixSpecifier;
@@ -66,6 +67,35 @@ impl FromRequest<str> for BookmarkKey {
     }
 }
 
+impl FromRequest<String> for MononokePath {
+    fn from_request(path: &String) -> Result<MononokePath, thrift::RequestError> {
+        let invalid =
+            |reason: &str| errors::invalid_request(format!("invalid path '{}': {}", path, reason));
+        // The empty path is the root.  Otherwise, check for the mistakes
+        // that clients commonly make before parsing, so that they get a
+        // consistent error.
+        if !path.is_empty() {
+            for element in path.split('/') {
+                match element {
+                    "" => {
+                        return Err(invalid(
+                            "paths cannot have empty components or leading or trailing slashes",
+                        ));
+                    }
+                    "." | ".." => {
+                        return Err(invalid("paths cannot contain '.' or '..' components"));
+                    }
+                    _ if element.contains('\0') => {
+                        return Err(invalid("paths cannot contain NUL bytes"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        MononokePath::try_from(path).map_err(|e| invalid(&e.to_string()))
+    }
+}
+
 impl FromRequest<thrift::CrossRepoPushSource> for CrossRepoPushSource {
     fn from_request(
         push_source: &thrift::CrossRepoPushSource,
@@ -226,6 +256,22 @@ impl FromRequest<thrift::CopyInfo> for CopyInfo {
     }
 }
 
+impl FromRequest<thrift::HistoryOrder> for ChangesetHistoryOrder {
+    fn from_request(order: &thrift::HistoryOrder) -> Result<Self, thrift::RequestError> {
+        match *order {
+            thrift::HistoryOrder::TOPOLOGICAL => Ok(ChangesetHistoryOrder::Topological),
+            thrift::HistoryOrder::REVERSE_CHRONOLOGICAL => {
+                Ok(ChangesetHistoryOrder::ReverseChronological)
+            }
+            thrift::HistoryOrder::AUTHOR_DATE => Ok(ChangesetHistoryOrder::AuthorDate),
+            val => Err(errors::invalid_request(format!(
+                "unsupported history order ({})",
+                val
+            ))),
+        }
+    }
+}
+
 impl FromRequest<thrift::RepoResolveCommitPrefixParams> for ChangesetPrefixSpecifier {
     fn from_request(
         params: &thrift::RepoResolveCommitPrefixParams,
@@ -318,12 +364,7 @@ impl FromRequest<thrift::RepoCreateCommitParamsFileCopyInfo> for CreateCopyInfo
     fn from_request(
         copy_info: &thrift::RepoCreateCommitParamsFileCopyInfo,
     ) -> Result<Self, thrift::RequestError> {
-        let path = MononokePath::try_from(&copy_info.path).map_err(|e| {
-            errors::invalid_request(format!(
-                "invalid copy-from path '{}': {}",
-                copy_info.path, e
-            ))
-        })?;
+        let path = MononokePath::from_request(&copy_info.path)?;
         let parent_index = usize::try_from(copy_info.parent_index).map_err(|e| {
             errors::invalid_request(format!(
                 "invalid copy-from parent index '{}': {}",
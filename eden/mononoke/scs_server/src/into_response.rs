@@ -15,6 +15,7 @@ use futures::try_join;
 use itertools::Itertools;
 use maplit::btreemap;
 use mononoke_api::BookmarkInfo;
+use mononoke_api::BookmarkStaleness;
 use mononoke_api::ChangesetContext;
 use mononoke_api::ChangesetId;
 use mononoke_api::ChangesetPathContentContext;
@@ -24,10 +25,14 @@ use mononoke_api::FileGeneratedStatus;
 use mononoke_api::FileMetadata;
 use mononoke_api::FileType;
 use mononoke_api::HeaderlessUnifiedDiff;
+use mononoke_api::HgChangesetId;
+use mononoke_api::HgMutationEntry;
 use mononoke_api::MetadataDiff;
 use mononoke_api::MetadataDiffFileInfo;
 use mononoke_api::MetadataDiffLinesCount;
 use mononoke_api::MononokeError;
+use mononoke_api::PathEntry;
+use mononoke_api::Phase;
 use mononoke_api::PushrebaseOutcome;
 use mononoke_api::RepoContext;
 use mononoke_api::TreeEntry;
@@ -88,6 +93,15 @@ impl IntoResponse<thrift::EntryType> for FileType {
     }
 }
 
+/// Whether a file is executable, for entry types that can be.
+fn is_executable(file_type: FileType) -> Option<bool> {
+    match file_type {
+        FileType::Regular => Some(false),
+        FileType::Executable => Some(true),
+        FileType::Symlink | FileType::GitSubmodule => None,
+    }
+}
+
 impl IntoResponse<Option<thrift::MetadataDiffFileType>> for Option<FileType> {
     fn into_response(self) -> Option<thrift::MetadataDiffFileType> {
         match self {
@@ -103,7 +117,7 @@ impl IntoResponse<Option<thrift::MetadataDiffFileType>> for Option<FileType> {
 impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
     fn into_response(self) -> thrift::TreeEntry {
         let (name, entry) = self;
-        let (r#type, info) = match entry {
+        let (r#type, is_executable, info) = match entry {
             TreeEntry::Directory(dir) => {
                 let summary = dir.summary();
                 let info = thrift::TreeInfo {
@@ -117,7 +131,7 @@ impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
                     descendant_files_total_size: summary.descendant_files_total_size as i64,
                     ..Default::default()
                 };
-                (thrift::EntryType::TREE, thrift::EntryInfo::tree(info))
+                (thrift::EntryType::TREE, None, thrift::EntryInfo::tree(info))
             }
             TreeEntry::File(file) => {
                 let info = thrift::FileInfo {
@@ -129,6 +143,7 @@ impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
                 };
                 (
                     file.file_type().into_response(),
+                    is_executable(*file.file_type()),
                     thrift::EntryInfo::file(info),
                 )
             }
@@ -137,6 +152,45 @@ impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
             name,
             r#type,
             info,
+            is_executable,
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoResponse<thrift::CommitMutation> for HgMutationEntry {
+    fn into_response(self) -> thrift::CommitMutation {
+        let hg_id = |id: &HgChangesetId| thrift::CommitId::hg(id.as_ref().into());
+        thrift::CommitMutation {
+            successor: hg_id(self.successor()),
+            predecessors: self.predecessors().iter().map(hg_id).collect(),
+            split: self.split().iter().map(hg_id).collect(),
+            operation: self.op().to_string(),
+            user: self.user().to_string(),
+            date: self.timestamp(),
+            tz: self.timezone(),
+            extra: self.extra().iter().cloned().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoResponse<thrift::CommitPhase> for Phase {
+    fn into_response(self) -> thrift::CommitPhase {
+        match self {
+            Phase::Draft => thrift::CommitPhase::DRAFT,
+            Phase::Public => thrift::CommitPhase::PUBLIC,
+        }
+    }
+}
+
+impl IntoResponse<thrift::WarmBookmarkStaleness> for BookmarkStaleness {
+    fn into_response(self) -> thrift::WarmBookmarkStaleness {
+        thrift::WarmBookmarkStaleness {
+            age_ms: self.stale_since().since_millis(),
+            refreshed_timestamp_ns: self.refreshed_at.timestamp_nanos(),
+            pending_move: self.pending_move,
+            pending_move_timestamp_ns: self.pending_move_at.map(|ts| ts.timestamp_nanos()),
             ..Default::default()
         }
     }
@@ -323,6 +377,42 @@ impl AsyncIntoResponse<thrift::TreePathInfo> for &ChangesetPathContentContext {
     }
 }
 
+#[async_trait]
+impl AsyncIntoResponse<thrift::CommitPathInfoResponse> for &ChangesetPathContentContext {
+    async fn into_response(self) -> Result<thrift::CommitPathInfoResponse, errors::ServiceError> {
+        let response = match self.entry().await? {
+            PathEntry::NotPresent => thrift::CommitPathInfoResponse {
+                exists: false,
+                r#type: None,
+                info: None,
+                ..Default::default()
+            },
+            PathEntry::Tree(tree) => {
+                let summary = tree.summary().await?;
+                thrift::CommitPathInfoResponse {
+                    exists: true,
+                    r#type: Some(thrift::EntryType::TREE),
+                    info: Some(thrift::EntryInfo::tree(
+                        (*tree.id(), summary).into_response(),
+                    )),
+                    ..Default::default()
+                }
+            }
+            PathEntry::File(file, file_type) => {
+                let metadata = file.metadata().await?;
+                thrift::CommitPathInfoResponse {
+                    exists: true,
+                    r#type: Some(file_type.into_response()),
+                    info: Some(thrift::EntryInfo::file(metadata.into_response())),
+                    is_executable: is_executable(file_type),
+                    ..Default::default()
+                }
+            }
+        };
+        Ok(response)
+    }
+}
+
 #[async_trait]
 impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
     /// The additional data is the set of commit identity schemes to be
@@ -352,16 +442,18 @@ impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
                 .collect())
         }
 
-        let (ids, message, date, author, parents, hg_extra, git_extra_headers, generation) = try_join!(
-            map_commit_identity(&self, identity_schemes),
-            self.message(),
-            self.author_date(),
-            self.author(),
-            map_parent_identities(&self, identity_schemes),
-            self.hg_extras(),
-            self.git_extra_headers(),
-            self.generation(),
-        )?;
+        let (ids, message, date, author, parents, hg_extra, git_extra_headers, generation, phase) =
+            try_join!(
+                map_commit_identity(&self, identity_schemes),
+                self.message(),
+                self.author_date(),
+                self.author(),
+                map_parent_identities(&self, identity_schemes),
+                self.hg_extras(),
+                self.git_extra_headers(),
+                self.generation(),
+                self.phase(),
+            )?;
         Ok(thrift::CommitInfo {
             ids,
             message,
@@ -377,6 +469,7 @@ impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
                     .collect()
             }),
             generation: generation.value() as i64,
+            phase: phase.into_response(),
             ..Default::default()
         })
     }
@@ -529,6 +622,7 @@ impl AsyncIntoResponseWith<thrift::BookmarkInfo> for BookmarkInfo {
             warm_ids,
             fresh_ids,
             last_update_timestamp_ns: self.last_update_timestamp.timestamp_nanos(),
+            warm_staleness: self.warm_staleness.into_response(),
             ..Default::default()
         })
     }
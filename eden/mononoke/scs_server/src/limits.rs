@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Request limits enforced by the service.
+//!
+//! Each limit defaults to the constant in the thrift definition, and can be
+//! overridden at runtime through tunables without restarting the server.
+//! Unset or non-positive tunables fall back to the default.
+
+use source_control as thrift;
+use tunables::tunables;
+
+fn override_or(value: Option<i64>, default: i64) -> i64 {
+    match value {
+        Some(value) if value > 0 => value,
+        _ => default,
+    }
+}
+
+pub(crate) fn repo_list_bookmarks_max_limit() -> i64 {
+    override_or(
+        tunables().scs_repo_list_bookmarks_max_limit(),
+        thrift::consts::REPO_LIST_BOOKMARKS_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn repo_list_recent_commits_max_limit() -> i64 {
+    override_or(
+        tunables().scs_repo_list_recent_commits_max_limit(),
+        thrift::consts::REPO_LIST_RECENT_COMMITS_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn repo_stack_info_max_limit() -> i64 {
+    override_or(
+        tunables().scs_repo_stack_info_max_limit(),
+        thrift::consts::REPO_STACK_INFO_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn commit_compare_ordered_max_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_compare_ordered_max_limit(),
+        thrift::consts::COMMIT_COMPARE_ORDERED_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn commit_file_diffs_size_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_file_diffs_size_limit(),
+        thrift::consts::COMMIT_FILE_DIFFS_SIZE_LIMIT,
+    )
+}
+
+pub(crate) fn commit_file_diffs_path_count_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_file_diffs_path_count_limit(),
+        thrift::consts::COMMIT_FILE_DIFFS_PATH_COUNT_LIMIT,
+    )
+}
+
+pub(crate) fn commit_find_files_max_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_find_files_max_limit(),
+        thrift::consts::COMMIT_FIND_FILES_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn commit_list_descendant_bookmarks_max_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_list_descendant_bookmarks_max_limit(),
+        thrift::consts::COMMIT_LIST_DESCENDANT_BOOKMARKS_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn commit_list_descendants_max_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_list_descendants_max_limit(),
+        thrift::consts::COMMIT_LIST_DESCENDANTS_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn tree_list_max_limit() -> i64 {
+    override_or(
+        tunables().scs_tree_list_max_limit(),
+        thrift::consts::TREE_LIST_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn tree_prefetch_max_limit() -> i64 {
+    override_or(
+        tunables().scs_tree_prefetch_max_limit(),
+        thrift::consts::TREE_PREFETCH_MAX_LIMIT,
+    )
+}
+
+pub(crate) fn commit_export_archive_size_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_export_archive_size_limit(),
+        thrift::consts::COMMIT_EXPORT_ARCHIVE_SIZE_LIMIT,
+    )
+}
+
+pub(crate) fn commit_export_archive_file_count_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_export_archive_file_count_limit(),
+        thrift::consts::COMMIT_EXPORT_ARCHIVE_FILE_COUNT_LIMIT,
+    )
+}
+
+pub(crate) fn commit_landability_changed_paths_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_landability_changed_paths_limit(),
+        thrift::consts::COMMIT_LANDABILITY_CHANGED_PATHS_LIMIT,
+    )
+}
+
+pub(crate) fn path_max_length() -> i64 {
+    override_or(
+        tunables().scs_path_max_length(),
+        thrift::consts::PATH_MAX_LENGTH,
+    )
+}
+
+pub(crate) fn commit_path_blame_annotate_size_limit() -> i64 {
+    override_or(
+        tunables().scs_commit_path_blame_annotate_size_limit(),
+        thrift::consts::COMMIT_PATH_BLAME_ANNOTATE_SIZE_LIMIT,
+    )
+}
+
+pub(crate) fn identity_schemes_max_count() -> i64 {
+    override_or(
+        tunables().scs_identity_schemes_max_count(),
+        thrift::consts::IDENTITY_SCHEMES_MAX_COUNT,
+    )
+}
+
+/// History pages have no compile-time cap, so this is `None` unless a cap
+/// has been configured.
+pub(crate) fn history_max_limit() -> Option<i64> {
+    tunables()
+        .scs_history_max_limit()
+        .filter(|limit| *limit > 0)
+}
+
+/// The limits currently in effect, for reporting to clients.
+pub(crate) fn service_limits() -> thrift::ServiceLimits {
+    thrift::ServiceLimits {
+        repo_list_bookmarks_max_limit: repo_list_bookmarks_max_limit(),
+        repo_stack_info_max_limit: repo_stack_info_max_limit(),
+        commit_compare_ordered_max_limit: commit_compare_ordered_max_limit(),
+        commit_file_diffs_size_limit: commit_file_diffs_size_limit(),
+        commit_file_diffs_path_count_limit: commit_file_diffs_path_count_limit(),
+        commit_find_files_max_limit: commit_find_files_max_limit(),
+        commit_list_descendant_bookmarks_max_limit: commit_list_descendant_bookmarks_max_limit(),
+        commit_list_descendants_max_limit: commit_list_descendants_max_limit(),
+        tree_list_max_limit: tree_list_max_limit(),
+        history_max_limit: history_max_limit(),
+        tree_prefetch_max_limit: tree_prefetch_max_limit(),
+        commit_export_archive_size_limit: commit_export_archive_size_limit(),
+        commit_export_archive_file_count_limit: commit_export_archive_file_count_limit(),
+        repo_list_recent_commits_max_limit: repo_list_recent_commits_max_limit(),
+        path_max_length: path_max_length(),
+        commit_landability_changed_paths_limit: commit_landability_changed_paths_limit(),
+        identity_schemes_max_count: identity_schemes_max_count(),
+        commit_path_blame_annotate_size_limit: commit_path_blame_annotate_size_limit(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+    use tunables::with_tunables;
+    use tunables::MononokeTunables;
+
+    use super::*;
+
+    #[test]
+    fn test_default_limits() {
+        with_tunables(MononokeTunables::default(), || {
+            assert_eq!(tree_list_max_limit(), thrift::consts::TREE_LIST_MAX_LIMIT);
+            assert_eq!(path_max_length(), thrift::consts::PATH_MAX_LENGTH);
+            assert_eq!(history_max_limit(), None);
+            let limits = service_limits();
+            assert_eq!(
+                limits.repo_list_bookmarks_max_limit,
+                thrift::consts::REPO_LIST_BOOKMARKS_MAX_LIMIT
+            );
+            assert_eq!(limits.history_max_limit, None);
+        });
+    }
+
+    #[test]
+    fn test_overridden_limits() {
+        let tunables = MononokeTunables::default();
+        tunables.update_ints(&hashmap! {
+            "scs_tree_list_max_limit".to_string() => 10,
+            "scs_history_max_limit".to_string() => 20,
+            "scs_commit_find_files_max_limit".to_string() => 0,
+            "scs_path_max_length".to_string() => -1,
+            "scs_commit_path_blame_annotate_size_limit".to_string() => 1024,
+        });
+        with_tunables(tunables, || {
+            assert_eq!(tree_list_max_limit(), 10);
+            assert_eq!(history_max_limit(), Some(20));
+            assert_eq!(commit_path_blame_annotate_size_limit(), 1024);
+            // Non-positive overrides fall back to the default
+            assert_eq!(
+                commit_find_files_max_limit(),
+                thrift::consts::COMMIT_FIND_FILES_MAX_LIMIT
+            );
+            assert_eq!(path_max_length(), thrift::consts::PATH_MAX_LENGTH);
+
+            // The reported limits are the ones in effect
+            let limits = service_limits();
+            assert_eq!(limits.tree_list_max_limit, 10);
+            assert_eq!(limits.history_max_limit, Some(20));
+            assert_eq!(
+                limits.commit_find_files_max_limit,
+                thrift::consts::COMMIT_FIND_FILES_MAX_LIMIT
+            );
+        });
+    }
+}
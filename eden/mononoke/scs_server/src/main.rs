@@ -51,12 +51,16 @@ use srserver::ThriftServer;
 use srserver::ThriftServerBuilder;
 use tokio::task;
 
+mod background;
+mod cancellation;
+mod capabilities;
 mod commit_id;
 mod errors;
 mod facebook;
 mod from_request;
 mod history;
 mod into_response;
+mod limits;
 mod metadata;
 mod methods;
 mod monitoring;
@@ -65,6 +69,9 @@ mod scuba_params;
 mod scuba_response;
 mod source_control_impl;
 mod specifiers;
+mod usage;
+mod validate_params;
+mod warmup;
 
 const SERVICE_NAME: &str = "mononoke_scs_server";
 const SM_CLEANUP_TIMEOUT_SECS: u64 = 60;
@@ -87,6 +94,12 @@ struct ScsServerArgs {
     bound_address_file: Option<String>,
     #[clap(flatten)]
     sharded_executor_args: ShardedExecutorArgs,
+    /// Repos to warm up at startup before reporting the server as alive
+    #[clap(long = "warmup-repo")]
+    warmup_repos: Vec<String>,
+    /// Number of repos to warm up at the same time
+    #[clap(long, default_value_t = 4)]
+    warmup_concurrency: usize,
 }
 
 /// Struct representing the Source Control Service process when sharding by
@@ -213,13 +226,17 @@ fn main(fb: FacebookInit) -> Result<(), Error> {
     let megarepo_api = Arc::new(runtime.block_on(MegarepoApi::new(&app, mononoke.clone()))?);
 
     let will_exit = Arc::new(AtomicBool::new(false));
+    let warming_up = Arc::new(AtomicBool::new(!args.warmup_repos.is_empty()));
 
     // Initialize the FB303 Thrift stack.
 
     let fb303_base = {
-        cloned!(will_exit);
+        cloned!(will_exit, warming_up);
         move |proto| {
-            make_BaseService_server(proto, facebook::BaseServiceImpl::new(will_exit.clone()))
+            make_BaseService_server(
+                proto,
+                facebook::BaseServiceImpl::new(will_exit.clone(), warming_up.clone()),
+            )
         }
     };
     let acl_provider = DefaultAclProvider::new(fb);
@@ -232,7 +249,7 @@ fn main(fb: FacebookInit) -> Result<(), Error> {
         mononoke.clone(),
         megarepo_api,
         logger.clone(),
-        scuba_builder,
+        scuba_builder.clone(),
         args.scribe_logging_args.get_scribe(fb)?,
         security_checker,
         &app.repo_configs().common,
@@ -248,9 +265,24 @@ fn main(fb: FacebookInit) -> Result<(), Error> {
     };
     let monitoring_forever = {
         let monitoring_ctx = CoreContext::new_with_logger(fb, logger.clone());
-        monitoring::monitoring_stats_submitter(monitoring_ctx, mononoke)
+        monitoring::monitoring_stats_submitter(monitoring_ctx, mononoke.clone())
     };
     runtime.spawn(monitoring_forever);
+    runtime.spawn(usage::usage_scuba_exporter(
+        source_control_server.usage.clone(),
+        scuba_builder,
+    ));
+
+    if !args.warmup_repos.is_empty() {
+        let warmup_ctx = CoreContext::new_with_logger(fb, logger.clone());
+        runtime.spawn(warmup::warm_up_repos(
+            warmup_ctx,
+            mononoke,
+            args.warmup_repos,
+            args.warmup_concurrency,
+            warming_up,
+        ));
+    }
 
     let thrift: ThriftServer = ThriftServerBuilder::new(fb)
         .with_name(SERVICE_NAME)
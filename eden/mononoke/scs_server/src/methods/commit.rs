@@ -8,10 +8,14 @@
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use bookmarks::BookmarkKey;
 use bytes::Bytes;
 use context::CoreContext;
+use derived_data_manager::DerivableType;
+use futures::future;
 use futures::stream;
 use futures::stream::FuturesOrdered;
 use futures::stream::StreamExt;
@@ -22,28 +26,33 @@ use hooks::HookOutcome;
 use itertools::Either;
 use itertools::Itertools;
 use maplit::btreeset;
+use mononoke_api::BookmarkFreshness;
 use mononoke_api::CandidateSelectionHintArgs;
 use mononoke_api::ChangesetContext;
 use mononoke_api::ChangesetDiffItem;
 use mononoke_api::ChangesetFileOrdering;
 use mononoke_api::ChangesetHistoryOptions;
+use mononoke_api::ChangesetHistoryOrder;
 use mononoke_api::ChangesetId;
 use mononoke_api::ChangesetPathContentContext;
 use mononoke_api::ChangesetPathDiffContext;
 use mononoke_api::ChangesetSpecifier;
 use mononoke_api::CopyInfo;
+use mononoke_api::FileMetadata;
 use mononoke_api::MetadataDiff;
 use mononoke_api::MononokeError;
 use mononoke_api::MononokePath;
 use mononoke_api::RepoContext;
 use mononoke_api::UnifiedDiff;
 use mononoke_api::UnifiedDiffMode;
+use permission_checker::MononokeIdentitySetExt;
 use source_control as thrift;
 
+use crate::cancellation::spawn_cancellable;
 use crate::commit_id::map_commit_identities;
 use crate::commit_id::map_commit_identity;
+use crate::commit_id::CommitIdExt;
 use crate::errors;
-use crate::errors::ServiceErrorResultExt;
 use crate::from_request::check_range_and_convert;
 use crate::from_request::validate_timestamp;
 use crate::from_request::FromRequest;
@@ -51,42 +60,31 @@ use crate::history::collect_history;
 use crate::into_response::AsyncIntoResponse;
 use crate::into_response::AsyncIntoResponseWith;
 use crate::into_response::IntoResponse;
+use crate::limits;
 use crate::source_control_impl::SourceControlServiceImpl;
 
 // Magic number used when we want to limit concurrency with buffer_unordered.
 const CONCURRENCY_LIMIT: usize = 100;
 
+/// Derived data types reported by `commit_derived_data_info` if the request
+/// doesn't name any.
+const DERIVED_DATA_INFO_TYPES: &[DerivableType] = &[
+    DerivableType::BlameV2,
+    DerivableType::Bssm,
+    DerivableType::ChangesetInfo,
+    DerivableType::DeletedManifests,
+    DerivableType::Fsnodes,
+    DerivableType::HgChangesets,
+    DerivableType::SkeletonManifests,
+    DerivableType::Unodes,
+];
+
 enum CommitComparePath {
     File(thrift::CommitCompareFile),
     Tree(thrift::CommitCompareTree),
 }
 
 impl CommitComparePath {
-    /// The main path that this comparison applies to.
-    fn path(&self) -> Result<&str, errors::ServiceError> {
-        // Use the base path where available.  If it is not available, then
-        // this is a deletion and the other path should be used.
-        match self {
-            CommitComparePath::File(file) => file
-                .base_file
-                .as_ref()
-                .or(file.other_file.as_ref())
-                .map(|file| file.path.as_str())
-                .ok_or_else(|| {
-                    errors::internal_error("programming error, file entry has no file").into()
-                }),
-
-            CommitComparePath::Tree(tree) => tree
-                .base_tree
-                .as_ref()
-                .or(tree.other_tree.as_ref())
-                .map(|tree| tree.path.as_str())
-                .ok_or_else(|| {
-                    errors::internal_error("programming error, tree entry has no tree").into()
-                }),
-        }
-    }
-
     async fn from_path_diff(
         path_diff: ChangesetPathDiffContext,
     ) -> Result<Self, errors::ServiceError> {
@@ -96,10 +94,20 @@ impl CommitComparePath {
                 path_diff.other().into_response()
             )?;
             let copy_info = path_diff.copy_info().into_response();
+            let mode_only_change = path_diff.is_mode_only_change().await?;
+            let (content_changed, type_changed) = match (&base_file, &other_file) {
+                (Some(base), Some(other)) => {
+                    (base.info.id != other.info.id, base.r#type != other.r#type)
+                }
+                _ => (true, false),
+            };
             Ok(CommitComparePath::File(thrift::CommitCompareFile {
                 base_file,
                 other_file,
                 copy_info,
+                mode_only_change,
+                content_changed,
+                type_changed,
                 ..Default::default()
             }))
         } else {
@@ -125,8 +133,8 @@ async fn add_mutable_renames(
         if let Some(paths) = &params.paths {
             let paths: Vec<_> = paths
                 .iter()
-                .map(MononokePath::try_from)
-                .collect::<Result<_, MononokeError>>()?;
+                .map(MononokePath::from_request)
+                .collect::<Result<_, _>>()?;
             base_changeset
                 .add_mutable_renames(paths.into_iter())
                 .await?;
@@ -135,9 +143,54 @@ async fn add_mutable_renames(
     Ok(())
 }
 
+/// Helper for commit_compare to leave out mode-only changes and to report
+/// copies and renames that are less similar than the threshold as additions
+/// and removals.
+async fn apply_compare_options(
+    diff: Vec<ChangesetPathDiffContext>,
+    exclude_mode_only_changes: bool,
+    copy_similarity_threshold: Option<u8>,
+) -> Result<Vec<ChangesetPathDiffContext>, errors::ServiceError> {
+    if !exclude_mode_only_changes && copy_similarity_threshold.is_none() {
+        return Ok(diff);
+    }
+    let diff = stream::iter(diff)
+        .map(|path_diff| async move {
+            if exclude_mode_only_changes && path_diff.is_mode_only_change().await? {
+                return Ok(Vec::new());
+            }
+            if let Some(threshold) = copy_similarity_threshold {
+                if let Some(similarity) = path_diff.copy_similarity().await? {
+                    if similarity < threshold {
+                        return Ok(path_diff.into_uncopied());
+                    }
+                }
+            }
+            Ok::<_, MononokeError>(vec![path_diff])
+        })
+        .buffered(CONCURRENCY_LIMIT)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(diff.into_iter().flatten().collect())
+}
+
+async fn file_metadata(
+    path: Option<&ChangesetPathContentContext>,
+) -> Result<Option<FileMetadata>, errors::ServiceError> {
+    if let Some(path) = path {
+        if let Some(file) = path.file().await? {
+            return Ok(Some(file.metadata().await?));
+        }
+    }
+    Ok(None)
+}
+
 struct CommitFileDiffsItem {
+    /// Index of the pair in the request's paths.
+    index: usize,
     path_diff_context: ChangesetPathDiffContext,
     placeholder: bool,
+    omitted_reason: Option<thrift::CommitFileDiffsOmittedReason>,
 }
 
 impl CommitFileDiffsItem {
@@ -153,24 +206,48 @@ impl CommitFileDiffsItem {
         if self.placeholder {
             Ok(0)
         } else {
-            async fn file_size(
-                path: Option<&ChangesetPathContentContext>,
-            ) -> Result<u64, errors::ServiceError> {
-                if let Some(path) = path {
-                    if let Some(file) = path.file().await? {
-                        return Ok(file.metadata().await?.total_size);
-                    }
-                }
-                Ok(0)
-            }
-            let (base_size, other_size) = try_join!(
-                file_size(self.path_diff_context.base()),
-                file_size(self.path_diff_context.other())
+            let (base_metadata, other_metadata) = try_join!(
+                file_metadata(self.path_diff_context.base()),
+                file_metadata(self.path_diff_context.other())
             )?;
+            let base_size = base_metadata.map_or(0, |metadata| metadata.total_size);
+            let other_size = other_metadata.map_or(0, |metadata| metadata.total_size);
             Ok(base_size.saturating_add(other_size))
         }
     }
 
+    /// Replace the diff with a placeholder if either file is larger than
+    /// `file_size_limit`, or is binary and `binary_placeholder` is set.
+    async fn apply_file_options(
+        mut self,
+        file_size_limit: Option<u64>,
+        binary_placeholder: bool,
+    ) -> Result<Self, errors::ServiceError> {
+        if self.placeholder {
+            return Ok(self);
+        }
+        let (base_metadata, other_metadata) = try_join!(
+            file_metadata(self.path_diff_context.base()),
+            file_metadata(self.path_diff_context.other())
+        )?;
+        let metadata = base_metadata.iter().chain(other_metadata.iter());
+        let too_large = file_size_limit.map_or(false, |limit| {
+            metadata.clone().any(|metadata| metadata.total_size > limit)
+        });
+        let omitted_reason = if too_large {
+            Some(thrift::CommitFileDiffsOmittedReason::FILE_TOO_LARGE)
+        } else if binary_placeholder && metadata.clone().any(|metadata| metadata.is_binary) {
+            Some(thrift::CommitFileDiffsOmittedReason::BINARY)
+        } else {
+            None
+        };
+        if omitted_reason.is_some() {
+            self.placeholder = true;
+            self.omitted_reason = omitted_reason;
+        }
+        Ok(self)
+    }
+
     async fn response_element(
         &self,
         format: thrift::DiffFormat,
@@ -229,18 +306,65 @@ impl CommitFileDiffsResponseElement {
                 base_path: item.path_diff_context.base().map(|p| p.path().to_string()),
                 other_path: item.path_diff_context.other().map(|p| p.path().to_string()),
                 diff: diff.into_response(),
+                omitted_reason: item.omitted_reason,
                 ..Default::default()
             },
             Self::MetadataDiff { metadata_diff } => thrift::CommitFileDiffsResponseElement {
                 base_path: item.path_diff_context.base().map(|p| p.path().to_string()),
                 other_path: item.path_diff_context.other().map(|p| p.path().to_string()),
                 diff: metadata_diff.into_response(),
+                omitted_reason: item.omitted_reason,
                 ..Default::default()
             },
         }
     }
 }
 
+/// File count and total file size of the whole repo at a commit.
+async fn repo_file_totals(changeset: &ChangesetContext) -> Result<(u64, u64), MononokeError> {
+    match changeset.root().await?.tree().await? {
+        Some(tree) => {
+            let summary = tree.summary().await?;
+            Ok((
+                summary.descendant_files_count,
+                summary.descendant_files_total_size,
+            ))
+        }
+        None => Ok((0, 0)),
+    }
+}
+
+async fn file_count_delta(
+    repo: &RepoContext,
+    changeset: &ChangesetContext,
+) -> Result<thrift::CommitFileCountDelta, MononokeError> {
+    let parent_totals = async {
+        match changeset.parents().await?.first() {
+            Some(parent_id) => {
+                let parent = repo
+                    .changeset(ChangesetSpecifier::Bonsai(*parent_id))
+                    .await?
+                    .ok_or_else(|| {
+                        MononokeError::InvalidRequest(format!(
+                            "parent commit {} not found",
+                            parent_id
+                        ))
+                    })?;
+                repo_file_totals(&parent).await
+            }
+            // A root commit adds everything
+            None => Ok((0, 0)),
+        }
+    };
+    let ((file_count, total_size), (parent_file_count, parent_total_size)) =
+        try_join!(repo_file_totals(changeset), parent_totals)?;
+    Ok(thrift::CommitFileCountDelta {
+        file_count_delta: file_count as i64 - parent_file_count as i64,
+        total_size_delta: total_size as i64 - parent_total_size as i64,
+        ..Default::default()
+    })
+}
+
 impl SourceControlServiceImpl {
     /// Returns the lowest common ancestor of two commits.
     ///
@@ -295,6 +419,244 @@ impl SourceControlServiceImpl {
         }
     }
 
+    /// Translate many commit ids from one identity scheme to others.
+    pub(crate) async fn commit_translate_ids(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::CommitTranslateIdsParams,
+    ) -> Result<thrift::CommitTranslateIdsResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let specifiers = params
+            .commit_ids
+            .iter()
+            .map(|commit_id| {
+                if commit_id.scheme() != params.from_scheme {
+                    return Err(errors::invalid_request(format!(
+                        "commit id {} is not in scheme {}",
+                        commit_id.to_string(),
+                        params.from_scheme
+                    )));
+                }
+                ChangesetSpecifier::from_request(commit_id)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Resolve all of the commits with a single mapping query.
+        let cs_ids: HashMap<ChangesetSpecifier, ChangesetId> = match params.from_scheme {
+            thrift::CommitIdentityScheme::BONSAI => specifiers
+                .iter()
+                .filter_map(|specifier| match specifier {
+                    ChangesetSpecifier::Bonsai(cs_id) => Some((*specifier, *cs_id)),
+                    _ => None,
+                })
+                .collect(),
+            thrift::CommitIdentityScheme::HG => {
+                let hg_ids = specifiers
+                    .iter()
+                    .filter_map(|specifier| match specifier {
+                        ChangesetSpecifier::Hg(hg_id) => Some(*hg_id),
+                        _ => None,
+                    })
+                    .collect();
+                repo.many_changeset_ids_from_hg(hg_ids)
+                    .await?
+                    .into_iter()
+                    .map(|(hg_id, cs_id)| (ChangesetSpecifier::Hg(hg_id), cs_id))
+                    .collect()
+            }
+            thrift::CommitIdentityScheme::GIT => {
+                let git_sha1s = specifiers
+                    .iter()
+                    .filter_map(|specifier| match specifier {
+                        ChangesetSpecifier::GitSha1(git_sha1) => Some(*git_sha1),
+                        _ => None,
+                    })
+                    .collect();
+                repo.many_changeset_ids_from_git_sha1(git_sha1s)
+                    .await?
+                    .into_iter()
+                    .map(|(git_sha1, cs_id)| (ChangesetSpecifier::GitSha1(git_sha1), cs_id))
+                    .collect()
+            }
+            thrift::CommitIdentityScheme::GLOBALREV => {
+                let globalrevs = specifiers
+                    .iter()
+                    .filter_map(|specifier| match specifier {
+                        ChangesetSpecifier::Globalrev(globalrev) => Some(*globalrev),
+                        _ => None,
+                    })
+                    .collect();
+                repo.many_changeset_ids_from_globalrev(globalrevs)
+                    .await?
+                    .into_iter()
+                    .map(|(globalrev, cs_id)| (ChangesetSpecifier::Globalrev(globalrev), cs_id))
+                    .collect()
+            }
+            thrift::CommitIdentityScheme::SVNREV => {
+                let svnrevs = specifiers
+                    .iter()
+                    .filter_map(|specifier| match specifier {
+                        ChangesetSpecifier::Svnrev(svnrev) => Some(*svnrev),
+                        _ => None,
+                    })
+                    .collect();
+                repo.many_changeset_ids_from_svnrev(svnrevs)
+                    .await?
+                    .into_iter()
+                    .map(|(svnrev, cs_id)| (ChangesetSpecifier::Svnrev(svnrev), cs_id))
+                    .collect()
+            }
+            scheme => {
+                return Err(errors::invalid_request(format!(
+                    "cannot translate from commit identity scheme {}",
+                    scheme
+                ))
+                .into());
+            }
+        };
+
+        // Map to the other schemes with one query per scheme.
+        let unique_cs_ids = cs_ids
+            .values()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let identities = map_commit_identities(&repo, unique_cs_ids, &params.to_schemes).await?;
+        let translations = params
+            .commit_ids
+            .into_iter()
+            .zip(specifiers)
+            .filter_map(|(commit_id, specifier)| {
+                let ids = identities.get(cs_ids.get(&specifier)?)?.clone();
+                Some(thrift::CommitIdTranslation {
+                    commit_id,
+                    ids,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        Ok(thrift::CommitTranslateIdsResponse {
+            translations,
+            ..Default::default()
+        })
+    }
+
+    /// Look up many commits, reporting unknown and malformed commit ids in
+    /// their own results rather than failing the whole request.
+    pub(crate) async fn commit_multiple_lookup(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::CommitMultipleLookupParams,
+    ) -> Result<thrift::CommitMultipleLookupResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let specifiers: Vec<_> = params
+            .commit_ids
+            .iter()
+            .map(ChangesetSpecifier::from_request)
+            .collect();
+
+        let mut hg_ids = Vec::new();
+        let mut git_sha1s = Vec::new();
+        let mut globalrevs = Vec::new();
+        let mut svnrevs = Vec::new();
+        let mut bonsais = Vec::new();
+        for specifier in specifiers.iter().flatten() {
+            match specifier {
+                ChangesetSpecifier::Hg(hg_id) => hg_ids.push(*hg_id),
+                ChangesetSpecifier::GitSha1(git_sha1) => git_sha1s.push(*git_sha1),
+                ChangesetSpecifier::Globalrev(globalrev) => globalrevs.push(*globalrev),
+                ChangesetSpecifier::Svnrev(svnrev) => svnrevs.push(*svnrev),
+                ChangesetSpecifier::Bonsai(_) | ChangesetSpecifier::EphemeralBonsai(..) => {
+                    bonsais.push(*specifier)
+                }
+            }
+        }
+
+        // Resolve the commits with one mapping query per scheme.  Bonsai
+        // commits only need checking that they exist.
+        let (from_hg, from_git_sha1, from_globalrev, from_svnrev, from_bonsai) = try_join!(
+            repo.many_changeset_ids_from_hg(hg_ids),
+            repo.many_changeset_ids_from_git_sha1(git_sha1s),
+            repo.many_changeset_ids_from_globalrev(globalrevs),
+            repo.many_changeset_ids_from_svnrev(svnrevs),
+            stream::iter(bonsais)
+                .map(|specifier| {
+                    let repo = &repo;
+                    async move {
+                        let changeset = repo.changeset(specifier).await?;
+                        Ok::<_, MononokeError>(changeset.map(|cs| (specifier, cs.id())))
+                    }
+                })
+                .buffered(100)
+                .try_collect::<Vec<_>>(),
+        )?;
+        let mut cs_ids: HashMap<ChangesetSpecifier, ChangesetId> = HashMap::new();
+        cs_ids.extend(
+            from_hg
+                .into_iter()
+                .map(|(hg_id, cs_id)| (ChangesetSpecifier::Hg(hg_id), cs_id)),
+        );
+        cs_ids.extend(
+            from_git_sha1
+                .into_iter()
+                .map(|(git_sha1, cs_id)| (ChangesetSpecifier::GitSha1(git_sha1), cs_id)),
+        );
+        cs_ids.extend(
+            from_globalrev
+                .into_iter()
+                .map(|(globalrev, cs_id)| (ChangesetSpecifier::Globalrev(globalrev), cs_id)),
+        );
+        cs_ids.extend(
+            from_svnrev
+                .into_iter()
+                .map(|(svnrev, cs_id)| (ChangesetSpecifier::Svnrev(svnrev), cs_id)),
+        );
+        cs_ids.extend(from_bonsai.into_iter().flatten());
+
+        let unique_cs_ids = cs_ids
+            .values()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let identities =
+            map_commit_identities(&repo, unique_cs_ids, &params.identity_schemes).await?;
+        let results = params
+            .commit_ids
+            .into_iter()
+            .zip(specifiers)
+            .map(|(commit_id, specifier)| match specifier {
+                Ok(specifier) => {
+                    let ids = cs_ids
+                        .get(&specifier)
+                        .and_then(|cs_id| identities.get(cs_id))
+                        .cloned();
+                    thrift::CommitMultipleLookupResult {
+                        commit_id,
+                        exists: ids.is_some(),
+                        ids,
+                        error: None,
+                        ..Default::default()
+                    }
+                }
+                Err(e) => thrift::CommitMultipleLookupResult {
+                    commit_id,
+                    exists: false,
+                    ids: None,
+                    error: Some(e.reason),
+                    ..Default::default()
+                },
+            })
+            .collect();
+        Ok(thrift::CommitMultipleLookupResponse {
+            results,
+            ..Default::default()
+        })
+    }
+
     /// Get diff.
     pub(crate) async fn commit_file_diffs(
         &self,
@@ -302,10 +664,21 @@ impl SourceControlServiceImpl {
         commit: thrift::CommitSpecifier,
         params: thrift::CommitFileDiffsParams,
     ) -> Result<thrift::CommitFileDiffsResponse, errors::ServiceError> {
-        // Check the path count limit
-        if params.paths.len() as i64 > thrift::consts::COMMIT_FILE_DIFFS_PATH_COUNT_LIMIT {
-            Err(errors::diff_input_too_many_paths(params.paths.len()))?;
-        }
+        // Select the page of paths to diff
+        let path_count = params.paths.len();
+        let page_start: usize = params
+            .page_start
+            .map(|start| check_range_and_convert("page_start", start, 0..=path_count as i64))
+            .transpose()?
+            .unwrap_or(0);
+        let page_len = match params.page_size {
+            Some(page_size) => {
+                let page_size: usize = check_range_and_convert("page_size", page_size, 1..)?;
+                page_size.min(path_count - page_start)
+            }
+            None => path_count - page_start,
+        };
+        let page_end = page_start + page_len;
 
         // Resolve the CommitSpecfier into ChangesetContext
         let (base_commit, other_commit) = match params.other_commit_id {
@@ -329,12 +702,15 @@ impl SourceControlServiceImpl {
         let paths = params
             .paths
             .into_iter()
-            .map(|path_pair| {
+            .enumerate()
+            .skip(page_start)
+            .take(page_len)
+            .map(|(index, path_pair)| {
                 Ok((
+                    index,
                     match path_pair.base_path {
                         Some(path) => {
-                            let mpath = MononokePath::try_from(&path)
-                                .context("invalid base commit path")?;
+                            let mpath = MononokePath::from_request(&path)?;
                             base_commit_paths.push(mpath.clone());
                             Some(mpath)
                         }
@@ -343,8 +719,7 @@ impl SourceControlServiceImpl {
                     match &other_commit {
                         Some(_other_commit) => match path_pair.other_path {
                             Some(path) => {
-                                let mpath = MononokePath::try_from(&path)
-                                    .context("invalid other commit path")?;
+                                let mpath = MononokePath::from_request(&path)?;
                                 other_commit_paths.push(mpath.clone());
                                 Some(mpath)
                             }
@@ -388,7 +763,7 @@ impl SourceControlServiceImpl {
 
         let items = paths
             .into_iter()
-            .map(|(base_path, other_path, copy_info, placeholder)| {
+            .map(|(index, base_path, other_path, copy_info, placeholder)| {
                 let base_path = match base_path {
                     Some(base_path) => {
                         Some(base_commit_contexts.get(&base_path).ok_or_else(|| {
@@ -422,12 +797,32 @@ impl SourceControlServiceImpl {
                     copy_info,
                 )?;
                 Ok(CommitFileDiffsItem {
+                    index,
                     path_diff_context,
                     placeholder,
+                    omitted_reason: None,
                 })
             })
             .collect::<Result<Vec<_>, errors::ServiceError>>()?;
 
+        // Replace the diffs of large or binary files with placeholders
+        let file_size_limit: Option<u64> = params
+            .file_size_limit
+            .map(|limit| check_range_and_convert("file_size_limit", limit, 0..))
+            .transpose()?;
+        let binary_placeholder = params.binary_placeholder.unwrap_or(false);
+        let items = if params.format == thrift::DiffFormat::RAW_DIFF
+            && (file_size_limit.is_some() || binary_placeholder)
+        {
+            stream::iter(items)
+                .map(|item| item.apply_file_options(file_size_limit, binary_placeholder))
+                .buffered(CONCURRENCY_LIMIT)
+                .try_collect::<Vec<_>>()
+                .await?
+        } else {
+            items
+        };
+
         // Check the total file size limit
         let total_input_size = stream::iter(items.iter())
             .map(|item| item.total_size())
@@ -439,7 +834,7 @@ impl SourceControlServiceImpl {
             )
             .await?;
 
-        if total_input_size > thrift::consts::COMMIT_FILE_DIFFS_SIZE_LIMIT as u64 {
+        if total_input_size > limits::commit_file_diffs_size_limit() as u64 {
             Err(errors::diff_input_too_big(total_input_size))?;
         }
 
@@ -450,6 +845,7 @@ impl SourceControlServiceImpl {
             .transpose()?;
         let mut size_so_far = 0usize;
         let mut stopped_at_pair = None;
+        let mut next_page_start = None;
 
         let path_diffs = stream::iter(items)
             .map(|item| async move {
@@ -465,6 +861,7 @@ impl SourceControlServiceImpl {
                     if size_so_far > diff_size_limit {
                         limit_reached = true;
                         stopped_at_pair = Some(item.to_stopped_at_pair());
+                        next_page_start = Some(item.index as i64);
                     }
                 }
                 async move { Ok(!limit_reached) }
@@ -473,13 +870,36 @@ impl SourceControlServiceImpl {
             .try_collect()
             .await?;
 
+        if next_page_start.is_none() && page_end < path_count {
+            next_page_start = Some(page_end as i64);
+        }
+
         Ok(thrift::CommitFileDiffsResponse {
             path_diffs,
             stopped_at_pair,
+            next_page_start,
             ..Default::default()
         })
     }
 
+    /// Start diffing files between two commits in the background.
+    pub(crate) async fn commit_file_diffs_async(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitFileDiffsParams,
+    ) -> Result<thrift::AsyncRequestToken, errors::ServiceError> {
+        // Check the commit exists before returning a token
+        self.repo_changeset(ctx.clone(), &commit).await?;
+        let id = ctx.session().metadata().session_id().to_string();
+        let owner = ctx.metadata().identities().main_client_identity();
+        let this = self.clone();
+        self.background_requests.start(id, owner, async move {
+            let response = this.commit_file_diffs(ctx, commit, params).await?;
+            Ok(thrift::AsyncRequestResult::commit_file_diffs(response))
+        })
+    }
+
     /// Get commit info.
     pub(crate) async fn commit_info(
         &self,
@@ -487,8 +907,99 @@ impl SourceControlServiceImpl {
         commit: thrift::CommitSpecifier,
         params: thrift::CommitInfoParams,
     ) -> Result<thrift::CommitInfo, errors::ServiceError> {
+        let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let file_count_delta = if params.include_file_count_delta {
+            Some(file_count_delta(&repo, &changeset).await?)
+        } else {
+            None
+        };
+        let mut commit_info: thrift::CommitInfo = changeset
+            .into_response_with(&params.identity_schemes)
+            .await?;
+        commit_info.file_count_delta = file_count_delta;
+        Ok(commit_info)
+    }
+
+    /// Get whether a commit is public or a draft.
+    pub(crate) async fn commit_phase(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        _params: thrift::CommitPhaseParams,
+    ) -> Result<thrift::CommitPhaseResponse, errors::ServiceError> {
+        let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        Ok(thrift::CommitPhaseResponse {
+            phase: changeset.phase().await?.into_response(),
+            ..Default::default()
+        })
+    }
+
+    /// Reports which types of derived data are already derived for the commit.
+    pub(crate) async fn commit_derived_data_info(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitDerivedDataInfoParams,
+    ) -> Result<thrift::CommitDerivedDataInfoResponse, errors::ServiceError> {
+        let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let derivable_types = if params.derived_data_types.is_empty() {
+            DERIVED_DATA_INFO_TYPES.to_vec()
+        } else {
+            params
+                .derived_data_types
+                .iter()
+                .map(|name| {
+                    DerivableType::from_str(name).map_err(|_| {
+                        errors::invalid_request(format!("unknown derived data type: {}", name))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let derived_data = stream::iter(derivable_types)
+            .map(|derivable_type| {
+                let repo = &repo;
+                let cs_id = changeset.id();
+                async move {
+                    let state = match repo.is_derived(derivable_type.clone(), cs_id).await? {
+                        Some(true) => thrift::CommitDerivedDataState::DERIVED,
+                        Some(false) => thrift::CommitDerivedDataState::NOT_DERIVED,
+                        None => thrift::CommitDerivedDataState::NOT_ENABLED,
+                    };
+                    Ok::<_, MononokeError>((derivable_type.to_string(), state))
+                }
+            })
+            .buffered(CONCURRENCY_LIMIT)
+            .try_collect::<BTreeMap<_, _>>()
+            .await?;
+        Ok(thrift::CommitDerivedDataInfoResponse {
+            derived_data,
+            ..Default::default()
+        })
+    }
+
+    /// Returns the recorded mutation history of the commit.
+    pub(crate) async fn commit_mutations(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        _params: thrift::CommitMutationsParams,
+    ) -> Result<thrift::CommitMutationsResponse, errors::ServiceError> {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
-        changeset.into_response_with(&params.identity_schemes).await
+        let (mutations, successor_mutations) = try_join!(
+            changeset.hg_mutation_history(),
+            changeset.hg_mutation_successors(),
+        )?;
+        Ok(thrift::CommitMutationsResponse {
+            mutations: mutations
+                .into_iter()
+                .map(IntoResponse::into_response)
+                .collect(),
+            successor_mutations: successor_mutations
+                .into_iter()
+                .map(IntoResponse::into_response)
+                .collect(),
+            ..Default::default()
+        })
     }
 
     /// Returns `true` if this commit is an ancestor of `other_commit`.
@@ -505,6 +1016,55 @@ impl SourceControlServiceImpl {
         Ok(is_ancestor_of)
     }
 
+    /// Find which of the bookmarks with the given prefixes have this commit
+    /// as an ancestor.
+    pub(crate) async fn commit_contained_in_bookmarks(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitContainedInBookmarksParams,
+    ) -> Result<thrift::CommitContainedInBookmarksResponse, errors::ServiceError> {
+        if params.bookmark_prefixes.is_empty() {
+            return Err(errors::invalid_request("no bookmark prefixes given").into());
+        }
+        let max_bookmarks = thrift::consts::COMMIT_CONTAINED_IN_BOOKMARKS_MAX_BOOKMARKS as u64;
+        let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let mut bookmarks = BTreeMap::new();
+        for prefix in params.bookmark_prefixes.iter() {
+            let prefix = Some(prefix.as_str()).filter(|prefix| !prefix.is_empty());
+            bookmarks.extend(
+                repo.list_bookmarks(false, prefix, None, Some(max_bookmarks + 1))
+                    .await?
+                    .try_collect::<Vec<_>>()
+                    .await?,
+            );
+            if bookmarks.len() as u64 > max_bookmarks {
+                return Err(errors::invalid_request(format!(
+                    "too many bookmarks match the prefixes (max {})",
+                    max_bookmarks
+                ))
+                .into());
+            }
+        }
+
+        let descendants = changeset
+            .descendants_among(bookmarks.values().copied())
+            .await?;
+        bookmarks.retain(|_name, cs_id| descendants.contains(cs_id));
+
+        let ids = bookmarks.values().copied().collect();
+        let id_mapping = map_commit_identities(&repo, ids, &params.identity_schemes).await?;
+        let bookmarks = bookmarks
+            .into_iter()
+            .map(|(name, cs_id)| (name, id_mapping.get(&cs_id).cloned().unwrap_or_default()))
+            .collect();
+
+        Ok(thrift::CommitContainedInBookmarksResponse {
+            bookmarks,
+            ..Default::default()
+        })
+    }
+
     /// Given a base changeset, find the "other" changeset from parent information
     /// including mutable history if appropriate
     ///
@@ -572,6 +1132,15 @@ impl SourceControlServiceImpl {
             }
         };
 
+        let exclude_mode_only_changes =
+            params.mode_only_changes == thrift::CommitCompareModeOnlyChanges::EXCLUDE;
+        let copy_similarity_threshold: Option<u8> = params
+            .copy_similarity_threshold
+            .map(|threshold| {
+                check_range_and_convert("copy_similarity_threshold", threshold, 0..=100)
+            })
+            .transpose()?;
+
         let mut last_path = None;
         let mut diff_items: BTreeSet<_> = params
             .compare_items
@@ -615,6 +1184,12 @@ impl SourceControlServiceImpl {
                             .await?
                     }
                 };
+                let diff = apply_compare_options(
+                    diff,
+                    exclude_mode_only_changes,
+                    copy_similarity_threshold,
+                )
+                .await?;
                 stream::iter(diff)
                     .map(CommitComparePath::from_path_diff)
                     .buffer_unordered(CONCURRENCY_LIMIT)
@@ -627,21 +1202,10 @@ impl SourceControlServiceImpl {
                     })
             }
             Some(ordered_params) => {
-                let limit: usize = check_range_and_convert(
-                    "limit",
-                    ordered_params.limit,
-                    0..=source_control::COMMIT_COMPARE_ORDERED_MAX_LIMIT,
-                )?;
+                let limit: usize = check_range_and_convert("limit", ordered_params.limit, 0..)?;
                 let after = ordered_params
                     .after_path
-                    .map(|after| {
-                        MononokePath::try_from(&after).map_err(|e| {
-                            errors::invalid_request(format!(
-                                "invalid continuation path '{}': {}",
-                                after, e
-                            ))
-                        })
-                    })
+                    .map(|after| MononokePath::from_request(&after))
                     .transpose()?;
                 let diff = match other_changeset {
                     Some(ref other_changeset) => {
@@ -667,17 +1231,25 @@ impl SourceControlServiceImpl {
                             .await?
                     }
                 };
+                // Page by the unfiltered diff, so that leaving out items
+                // doesn't end the paging early.
+                if diff.len() >= limit {
+                    if let Some(path_diff) = diff.last() {
+                        last_path = Some(path_diff.path().path().to_string());
+                    }
+                }
+                let diff = apply_compare_options(
+                    diff,
+                    exclude_mode_only_changes,
+                    copy_similarity_threshold,
+                )
+                .await?;
                 let diff_items = diff
                     .into_iter()
                     .map(CommitComparePath::from_path_diff)
                     .collect::<FuturesOrdered<_>>()
                     .try_collect::<Vec<_>>()
                     .await?;
-                if diff_items.len() >= limit {
-                    if let Some(item) = diff_items.last() {
-                        last_path = Some(item.path()?.to_string());
-                    }
-                }
                 diff_items.into_iter().partition_map(|diff| match diff {
                     CommitComparePath::File(entry) => Either::Left(entry),
                     CommitComparePath::Tree(entry) => Either::Right(entry),
@@ -700,6 +1272,24 @@ impl SourceControlServiceImpl {
         })
     }
 
+    /// Start comparing two commits in the background.
+    pub(crate) async fn commit_compare_async(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitCompareParams,
+    ) -> Result<thrift::AsyncRequestToken, errors::ServiceError> {
+        // Check the commit exists before returning a token
+        self.repo_changeset(ctx.clone(), &commit).await?;
+        let id = ctx.session().metadata().session_id().to_string();
+        let owner = ctx.metadata().identities().main_client_identity();
+        let this = self.clone();
+        self.background_requests.start(id, owner, async move {
+            let response = this.commit_compare(ctx, commit, params).await?;
+            Ok(thrift::AsyncRequestResult::commit_compare(response))
+        })
+    }
+
     /// Returns files that match the criteria
     pub(crate) async fn commit_find_files(
         &self,
@@ -708,29 +1298,19 @@ impl SourceControlServiceImpl {
         params: thrift::CommitFindFilesParams,
     ) -> Result<thrift::CommitFindFilesResponse, errors::ServiceError> {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
-        let limit: usize = check_range_and_convert(
-            "limit",
-            params.limit,
-            0..=source_control::COMMIT_FIND_FILES_MAX_LIMIT,
-        )?;
+        let limit: usize = check_range_and_convert("limit", params.limit, 0..)?;
         let prefixes: Option<Vec<_>> = match params.prefixes {
             Some(prefixes) => Some(
                 prefixes
                     .into_iter()
-                    .map(|prefix| {
-                        MononokePath::try_from(&prefix).map_err(|e| {
-                            errors::invalid_request(format!("invalid prefix '{}': {}", prefix, e))
-                        })
-                    })
+                    .map(|prefix| MononokePath::from_request(&prefix))
                     .collect::<Result<Vec<_>, _>>()?,
             ),
             None => None,
         };
         let ordering = match &params.after {
             Some(after) => {
-                let after = Some(MononokePath::try_from(after).map_err(|e| {
-                    errors::invalid_request(format!("invalid continuation path '{}': {}", after, e))
-                })?);
+                let after = Some(MononokePath::from_request(after)?);
                 ChangesetFileOrdering::Ordered { after }
             }
             None => ChangesetFileOrdering::Unordered,
@@ -811,11 +1391,19 @@ impl SourceControlServiceImpl {
             .into());
         }
 
+        let order = params
+            .order
+            .as_ref()
+            .map(ChangesetHistoryOrder::from_request)
+            .transpose()?
+            .unwrap_or_default();
+
         let history_stream = changeset
             .history(ChangesetHistoryOptions {
                 until_timestamp: after_timestamp,
                 descendants_of,
                 exclude_changeset_and_ancestors,
+                order,
             })
             .await?;
         let history = collect_history(
@@ -841,11 +1429,7 @@ impl SourceControlServiceImpl {
         commit: thrift::CommitSpecifier,
         params: thrift::CommitListDescendantBookmarksParams,
     ) -> Result<thrift::CommitListDescendantBookmarksResponse, errors::ServiceError> {
-        let limit = match check_range_and_convert(
-            "limit",
-            params.limit,
-            0..=source_control::COMMIT_LIST_DESCENDANT_BOOKMARKS_MAX_LIMIT,
-        )? {
+        let limit = match check_range_and_convert("limit", params.limit, 0..)? {
             0 => None,
             limit => Some(limit),
         };
@@ -891,7 +1475,7 @@ impl SourceControlServiceImpl {
                 move |bookmark| {
                     let changeset = changeset.clone();
                     async move {
-                        tokio::task::spawn(filter_descendant(changeset, bookmark))
+                        spawn_cancellable(filter_descendant(changeset, bookmark))
                             .await
                             .map_err(anyhow::Error::from)?
                     }
@@ -921,6 +1505,94 @@ impl SourceControlServiceImpl {
         })
     }
 
+    pub(crate) async fn commit_list_descendants(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitListDescendantsParams,
+    ) -> Result<thrift::CommitListDescendantsResponse, errors::ServiceError> {
+        let limit: usize = check_range_and_convert("limit", params.limit, 1..)?;
+        if params.bookmarks.is_empty() {
+            return Err(errors::invalid_request("at least one bookmark is required").into());
+        }
+        let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let heads = future::try_join_all(params.bookmarks.iter().map(|bookmark| {
+            let repo = &repo;
+            async move {
+                let key = BookmarkKey::new(bookmark).map_err(errors::invalid_request)?;
+                let changeset = repo
+                    .resolve_bookmark(&key, BookmarkFreshness::MaybeStale)
+                    .await?
+                    .ok_or_else(|| {
+                        errors::invalid_request(format!("bookmark not found: {}", bookmark))
+                    })?;
+                Ok::<_, errors::ServiceError>(changeset.id())
+            }
+        }))
+        .await?;
+
+        let after = match &params.after {
+            Some(after) => {
+                let after = self.changeset_id(&repo, after).await?;
+                // Listing resumes by skipping to `after`, so it must be one
+                // of the descendants or the whole history would be skipped.
+                let after_changeset = repo
+                    .changeset(after)
+                    .await?
+                    .ok_or_else(|| errors::commit_not_found(after.to_string()))?;
+                if after == changeset.id()
+                    || !changeset.is_ancestor_of(after).await?
+                    || after_changeset
+                        .descendants_among(heads.iter().copied())
+                        .await?
+                        .is_empty()
+                {
+                    return Err(errors::invalid_request(format!(
+                        "{} is not a descendant of the commit within the bookmarks",
+                        after
+                    ))
+                    .into());
+                }
+                Some(after)
+            }
+            None => None,
+        };
+
+        let mut found_after = after.is_none();
+        let mut descendants: Vec<ChangesetId> = changeset
+            .descendants_within(heads)
+            .await?
+            .try_filter(move |cs_id| {
+                let include = found_after;
+                if Some(*cs_id) == after {
+                    found_after = true;
+                }
+                future::ready(include)
+            })
+            .take(limit + 1)
+            .try_collect()
+            .await?;
+        let more = descendants.len() > limit;
+        descendants.truncate(limit);
+
+        let id_mapping =
+            map_commit_identities(&repo, descendants.clone(), &params.identity_schemes).await?;
+        let continue_after = match descendants.last() {
+            Some(last) if more => Some(id_mapping.get(last).cloned().unwrap_or_default()),
+            _ => None,
+        };
+        let descendants = descendants
+            .iter()
+            .map(|cs_id| id_mapping.get(cs_id).cloned().unwrap_or_default())
+            .collect();
+
+        Ok(thrift::CommitListDescendantsResponse {
+            descendants,
+            continue_after,
+            ..Default::default()
+        })
+    }
+
     pub(crate) async fn commit_run_hooks(
         &self,
         ctx: CoreContext,
@@ -1012,4 +1684,36 @@ impl SourceControlServiceImpl {
             }),
         }
     }
+
+    /// Map paths in a commit to the equivalent paths in another repo
+    pub(crate) async fn megarepo_path_mapping(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::MegarepoPathMappingParams,
+    ) -> Result<thrift::MegarepoPathMappingResponse, errors::ServiceError> {
+        let repo = self.repo(ctx.clone(), &commit.repo).await?;
+        let other_repo = self.repo(ctx, &params.other_repo).await?;
+        let paths = params
+            .paths
+            .iter()
+            .map(MononokePath::from_request)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (version, mapped_paths) = repo
+            .xrepo_path_mapping(
+                &other_repo,
+                ChangesetSpecifier::from_request(&commit.id)?,
+                paths,
+            )
+            .await?;
+        Ok(thrift::MegarepoPathMappingResponse {
+            paths: mapped_paths
+                .into_iter()
+                .map(|(path, mapped)| (path.to_string(), mapped.to_string()))
+                .collect(),
+            sync_config_version: version.0,
+            ..Default::default()
+        })
+    }
 }
@@ -0,0 +1,458 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::ffi::OsStr;
+use std::io::Cursor;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use bytes::Bytes;
+use context::CoreContext;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::future;
+use futures::stream::BoxStream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
+use mononoke_api::ChangesetContext;
+use mononoke_api::ChangesetFileOrdering;
+use mononoke_api::FileContext;
+use mononoke_api::FileType;
+use mononoke_api::MononokePath;
+use mononoke_api::PathEntry;
+use source_control as thrift;
+use tokio::sync::mpsc;
+
+use crate::errors;
+use crate::from_request::FromRequest;
+use crate::limits;
+use crate::source_control_impl::SourceControlServiceImpl;
+
+/// Number of files to fetch concurrently when building an archive.  This
+/// also bounds the number of fetched files waiting to be written.
+const ARCHIVE_FETCH_CONCURRENCY: usize = 100;
+
+/// Modification time recorded for tar entries, so that exporting the same
+/// commit always gives the same archive.  Zip archives can't represent
+/// times before 1980, so they use the zip crate's default of 1980-01-01.
+const TAR_MTIME: u64 = 0;
+
+/// A file found under the prefix, before its content has been fetched.
+struct ArchiveFile {
+    name: String,
+    file: FileContext,
+    file_type: FileType,
+    size: u64,
+}
+
+/// A file ready to be written to the archive.
+struct ArchiveEntry {
+    name: String,
+    file_type: FileType,
+    content: Bytes,
+}
+
+impl ArchiveEntry {
+    fn mode(&self) -> u32 {
+        match self.file_type {
+            FileType::Executable => 0o755,
+            FileType::Symlink => 0o777,
+            FileType::Regular | FileType::GitSubmodule => 0o644,
+        }
+    }
+}
+
+/// Running totals for the files added to an archive, which fail as soon as
+/// either limit is exceeded, so that listing can stop early.
+struct ArchiveBudget {
+    size_limit: u64,
+    file_count_limit: u64,
+    total_size: u64,
+    file_count: u64,
+}
+
+impl ArchiveBudget {
+    fn new(size_limit: u64, file_count_limit: u64) -> Self {
+        Self {
+            size_limit,
+            file_count_limit,
+            total_size: 0,
+            file_count: 0,
+        }
+    }
+
+    fn charge(&mut self, size: u64) -> Result<(), errors::ServiceError> {
+        self.file_count += 1;
+        self.total_size = self.total_size.saturating_add(size);
+        if self.file_count > self.file_count_limit {
+            return Err(errors::archive_too_many_files(self.file_count).into());
+        }
+        if self.total_size > self.size_limit {
+            return Err(errors::archive_too_big(self.total_size).into());
+        }
+        Ok(())
+    }
+}
+
+/// The name of a file in the archive, relative to the prefix.  Exporting a
+/// single file gives an archive containing just that file's name.
+fn archive_name(prefix: &str, path: &str) -> String {
+    match path.strip_prefix(prefix) {
+        Some("") => path.rsplit('/').next().unwrap_or(path).to_string(),
+        Some(name) if prefix.is_empty() => name.to_string(),
+        Some(name) => name.trim_start_matches('/').to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Find the files under `prefix`, in order, with their sizes.  Git
+/// submodules are skipped, as they have no content to export.
+async fn archive_files<'a>(
+    changeset: &'a ChangesetContext,
+    prefix: &MononokePath,
+) -> Result<BoxStream<'a, Result<ArchiveFile, errors::ServiceError>>, errors::ServiceError> {
+    let prefix_str = prefix.to_string();
+    let prefixes = prefix.as_mpath().map(|_| vec![prefix.clone()]);
+    Ok(changeset
+        .find_files(
+            prefixes,
+            None,
+            None,
+            ChangesetFileOrdering::Ordered { after: None },
+        )
+        .await?
+        .map_err(errors::ServiceError::from)
+        .map_ok(move |path| {
+            let path_str = path.to_string();
+            let name = archive_name(&prefix_str, &path_str);
+            async move {
+                match changeset.path_with_content(path).await?.entry().await? {
+                    PathEntry::File(_file, FileType::GitSubmodule) => Ok(None),
+                    PathEntry::File(file, file_type) => {
+                        let size = file.metadata().await?.total_size;
+                        Ok(Some(ArchiveFile {
+                            name,
+                            file,
+                            file_type,
+                            size,
+                        }))
+                    }
+                    _ => Err(errors::internal_error(format!(
+                        "path '{}' was found but is not a file",
+                        path_str
+                    ))
+                    .into()),
+                }
+            }
+        })
+        .try_buffered(ARCHIVE_FETCH_CONCURRENCY)
+        .try_filter_map(future::ok)
+        .boxed())
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    entries: impl Iterator<Item = ArchiveEntry>,
+) -> std::io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(entry.mode());
+        header.set_mtime(TAR_MTIME);
+        if entry.file_type == FileType::Symlink {
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            let target = Path::new(OsStr::from_bytes(&entry.content));
+            builder.append_link(&mut header, &entry.name, target)?;
+        } else {
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(entry.content.len() as u64);
+            builder.append_data(&mut header, &entry.name, entry.content.as_ref())?;
+        }
+    }
+    builder.into_inner()
+}
+
+fn write_zip(
+    entries: impl Iterator<Item = ArchiveEntry>,
+    compress: bool,
+) -> zip::result::ZipResult<Vec<u8>> {
+    let method = if compress {
+        zip::CompressionMethod::Deflated
+    } else {
+        zip::CompressionMethod::Stored
+    };
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for entry in entries {
+        let options = zip::write::FileOptions::default()
+            .compression_method(method)
+            .last_modified_time(zip::DateTime::default())
+            .unix_permissions(entry.mode())
+            .large_file(entry.content.len() as u64 >= u32::MAX as u64);
+        if entry.file_type == FileType::Symlink {
+            let target = String::from_utf8_lossy(&entry.content).into_owned();
+            writer.add_symlink(entry.name, target, options)?;
+        } else {
+            writer.start_file(entry.name, options)?;
+            writer.write_all(&entry.content)?;
+        }
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+fn unsupported_format(format: thrift::ArchiveFormat) -> errors::ServiceError {
+    errors::invalid_request(format!("unsupported archive format: {}", format)).into()
+}
+
+/// Write the entries to an archive as they arrive.
+fn write_archive(
+    entries: impl Iterator<Item = ArchiveEntry>,
+    format: thrift::ArchiveFormat,
+    compress: bool,
+) -> Result<Vec<u8>, errors::ServiceError> {
+    match format {
+        thrift::ArchiveFormat::TAR if compress => {
+            write_tar(GzEncoder::new(Vec::new(), Compression::default()), entries)
+                .and_then(|encoder| encoder.finish())
+                .map_err(|e| errors::internal_error(e).into())
+        }
+        thrift::ArchiveFormat::TAR => {
+            write_tar(Vec::new(), entries).map_err(|e| errors::internal_error(e).into())
+        }
+        thrift::ArchiveFormat::ZIP => {
+            write_zip(entries, compress).map_err(|e| errors::internal_error(e).into())
+        }
+        format => Err(unsupported_format(format)),
+    }
+}
+
+impl SourceControlServiceImpl {
+    /// Export the files at a commit as a tar or zip archive.
+    pub(crate) async fn commit_export_archive(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitExportArchiveParams,
+    ) -> Result<thrift::CommitExportArchiveResponse, errors::ServiceError> {
+        let format = params.format;
+        let compress = params.compress;
+        match format {
+            thrift::ArchiveFormat::TAR | thrift::ArchiveFormat::ZIP => {}
+            format => return Err(unsupported_format(format)),
+        }
+        let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let prefix = MononokePath::from_request(&params.path_prefix)?;
+
+        // Entries are written off the runtime as their content arrives, as
+        // compression is CPU-bound.  Dropping the sender (including when
+        // this request is cancelled) ends the archive.
+        let (sender, mut receiver) = mpsc::channel(ARCHIVE_FETCH_CONCURRENCY);
+        let writer = tokio::task::spawn_blocking(move || {
+            write_archive(
+                std::iter::from_fn(|| receiver.blocking_recv()),
+                format,
+                compress,
+            )
+        });
+
+        // Each file is charged against the budget before its content is
+        // fetched, so listing stops as soon as the archive is too big.
+        let mut budget = ArchiveBudget::new(
+            limits::commit_export_archive_size_limit() as u64,
+            limits::commit_export_archive_file_count_limit() as u64,
+        );
+        let sent = archive_files(&changeset, &prefix)
+            .await?
+            .and_then(|file| future::ready(budget.charge(file.size).map(|()| file)))
+            .map_ok(|file| async move {
+                Ok::<_, errors::ServiceError>(ArchiveEntry {
+                    name: file.name,
+                    file_type: file.file_type,
+                    content: file.file.content_concat().await?,
+                })
+            })
+            .try_buffered(ARCHIVE_FETCH_CONCURRENCY)
+            .try_for_each(|entry| {
+                let sender = &sender;
+                async move {
+                    sender
+                        .send(entry)
+                        .await
+                        .map_err(|_| errors::internal_error("archive writer stopped").into())
+                }
+            })
+            .await;
+        drop(sender);
+
+        // If the writer failed, its error is the reason sending failed.
+        let archive = writer.await.map_err(errors::internal_error)??;
+        sent?;
+
+        Ok(thrift::CommitExportArchiveResponse {
+            archive: Bytes::from(archive),
+            file_count: budget.file_count as i64,
+            total_size: budget.total_size as i64,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn entries() -> Vec<ArchiveEntry> {
+        vec![
+            ArchiveEntry {
+                name: "README".to_string(),
+                file_type: FileType::Regular,
+                content: Bytes::from_static(b"hello\n"),
+            },
+            ArchiveEntry {
+                name: "bin/run".to_string(),
+                file_type: FileType::Executable,
+                content: Bytes::from_static(b"#!/bin/sh\n"),
+            },
+            ArchiveEntry {
+                name: format!("{}/long", "d".repeat(150)),
+                file_type: FileType::Regular,
+                content: Bytes::from(vec![b'x'; 1000]),
+            },
+            ArchiveEntry {
+                name: "link".to_string(),
+                file_type: FileType::Symlink,
+                content: Bytes::from(format!("{}/target", "t".repeat(150))),
+            },
+            ArchiveEntry {
+                name: "empty".to_string(),
+                file_type: FileType::Regular,
+                content: Bytes::new(),
+            },
+        ]
+    }
+
+    fn check_tar<R: Read>(archive: R) {
+        let expected = entries();
+        let mut archive = tar::Archive::new(archive);
+        let mut count = 0;
+        for (entry, expected) in archive.entries().unwrap().zip(expected.iter()) {
+            let mut entry = entry.unwrap();
+            count += 1;
+            assert_eq!(
+                entry.path().unwrap().to_str().unwrap(),
+                expected.name.as_str()
+            );
+            assert_eq!(entry.header().mode().unwrap(), expected.mode());
+            assert_eq!(entry.header().mtime().unwrap(), TAR_MTIME);
+            if expected.file_type == FileType::Symlink {
+                assert_eq!(entry.header().entry_type(), tar::EntryType::Symlink);
+                let target = entry.link_name().unwrap().unwrap();
+                assert_eq!(target.as_os_str().as_bytes(), expected.content.as_ref());
+            } else {
+                assert_eq!(entry.header().entry_type(), tar::EntryType::Regular);
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content).unwrap();
+                assert_eq!(content, expected.content.as_ref());
+            }
+        }
+        assert_eq!(count, expected.len());
+    }
+
+    fn check_zip(archive: Vec<u8>, compress: bool) {
+        let expected = entries();
+        let mut archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        assert_eq!(archive.len(), expected.len());
+        for (index, expected) in expected.iter().enumerate() {
+            let mut file = archive.by_index(index).unwrap();
+            assert_eq!(file.name(), expected.name.as_str());
+            let (kind, method) = match expected.file_type {
+                FileType::Symlink => (0o120000, zip::CompressionMethod::Stored),
+                _ if compress => (0o100000, zip::CompressionMethod::Deflated),
+                _ => (0o100000, zip::CompressionMethod::Stored),
+            };
+            assert_eq!(file.unix_mode(), Some(kind | expected.mode()));
+            if !expected.content.is_empty() {
+                assert_eq!(file.compression(), method);
+            }
+            // Symlinks store their target as their content.
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).unwrap();
+            assert_eq!(content, expected.content.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_tar_round_trip() {
+        let archive =
+            write_archive(entries().into_iter(), thrift::ArchiveFormat::TAR, false).unwrap();
+        check_tar(archive.as_slice());
+    }
+
+    #[test]
+    fn test_tar_gz_round_trip() {
+        let archive =
+            write_archive(entries().into_iter(), thrift::ArchiveFormat::TAR, true).unwrap();
+        check_tar(GzDecoder::new(archive.as_slice()));
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        for compress in [false, true] {
+            let archive =
+                write_archive(entries().into_iter(), thrift::ArchiveFormat::ZIP, compress).unwrap();
+            check_zip(archive, compress);
+        }
+    }
+
+    #[test]
+    fn test_archive_is_deterministic() {
+        for format in [thrift::ArchiveFormat::TAR, thrift::ArchiveFormat::ZIP] {
+            let first = write_archive(entries().into_iter(), format, true).unwrap();
+            let second = write_archive(entries().into_iter(), format, true).unwrap();
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let archive = write_archive(std::iter::empty(), thrift::ArchiveFormat::TAR, false).unwrap();
+        let mut archive = tar::Archive::new(archive.as_slice());
+        assert_eq!(archive.entries().unwrap().count(), 0);
+
+        let archive = write_archive(std::iter::empty(), thrift::ArchiveFormat::ZIP, false).unwrap();
+        let archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        assert_eq!(archive.len(), 0);
+    }
+
+    #[test]
+    fn test_archive_name() {
+        assert_eq!(archive_name("", "a/b/c"), "a/b/c");
+        assert_eq!(archive_name("a", "a/b/c"), "b/c");
+        assert_eq!(archive_name("a/b", "a/b/c"), "c");
+        assert_eq!(archive_name("a/b/c", "a/b/c"), "c");
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut budget = ArchiveBudget::new(10, 3);
+        budget.charge(4).unwrap();
+        budget.charge(6).unwrap();
+        assert!(budget.charge(1).is_err());
+        assert_eq!(budget.total_size, 11);
+
+        let mut budget = ArchiveBudget::new(10, 2);
+        budget.charge(0).unwrap();
+        budget.charge(0).unwrap();
+        assert!(budget.charge(0).is_err());
+        assert_eq!(budget.file_count, 3);
+    }
+}
@@ -0,0 +1,224 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use bookmarks::BookmarkKey;
+use bytes::Bytes;
+use context::CoreContext;
+use futures::try_join;
+use hooks::HookExecution;
+use hooks::HookOutcome;
+use maplit::btreeset;
+use mononoke_api::BookmarkFreshness;
+use mononoke_api::ChangesetContext;
+use mononoke_api::ChangesetDiffItem;
+use mononoke_api::ChangesetFileOrdering;
+use mononoke_api::MononokeError;
+use mononoke_api::MononokePath;
+use source_control as thrift;
+
+use crate::commit_id::map_commit_identity;
+use crate::errors;
+use crate::limits;
+use crate::source_control_impl::SourceControlServiceImpl;
+
+/// Paths of the files changed in `changeset` since `base`, or `None` if
+/// more than `limit` files changed.
+async fn changed_paths(
+    changeset: &ChangesetContext,
+    base: &ChangesetContext,
+    limit: usize,
+) -> Result<Option<Vec<MononokePath>>, MononokeError> {
+    let changed: Vec<_> = changeset
+        .diff(
+            base,
+            false,
+            None,
+            btreeset! { ChangesetDiffItem::FILES },
+            ChangesetFileOrdering::Unordered,
+            Some(limit + 1),
+        )
+        .await?
+        .into_iter()
+        .map(|diff| diff.path().path().clone())
+        .collect();
+    Ok((changed.len() <= limit).then_some(changed))
+}
+
+/// The paths in `changed` that conflict with a path in `other_changed`,
+/// i.e. are the same path, or one is a file in a directory that is the
+/// other.
+fn conflicting_paths(
+    changed: Vec<MononokePath>,
+    other_changed: Vec<MononokePath>,
+) -> Vec<MononokePath> {
+    let other_dirs: HashSet<MononokePath> = other_changed
+        .iter()
+        .flat_map(|path| path.prefixes())
+        .collect();
+    let other_changed: HashSet<MononokePath> = other_changed.into_iter().collect();
+    let mut conflicts: Vec<_> = changed
+        .into_iter()
+        .filter(|path| {
+            other_changed.contains(path)
+                || other_dirs.contains(path)
+                || path
+                    .prefixes()
+                    .any(|prefix| other_changed.contains(&prefix))
+        })
+        .collect();
+    conflicts.sort();
+    conflicts
+}
+
+impl SourceControlServiceImpl {
+    /// Whether a commit is ready to land to a bookmark, and if not, why not.
+    pub(crate) async fn commit_landability(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitLandabilityParams,
+    ) -> Result<thrift::CommitLandabilityResponse, errors::ServiceError> {
+        let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let bookmark = BookmarkKey::new(&params.bookmark).map_err(errors::invalid_request)?;
+        let bookmark_changeset = repo
+            .resolve_bookmark(&bookmark, BookmarkFreshness::MostRecent)
+            .await?
+            .ok_or_else(|| {
+                errors::invalid_request(format!("bookmark not found: {}", params.bookmark))
+            })?;
+        let pushvars: Option<HashMap<String, Bytes>> = params
+            .pushvars
+            .map(|p| p.into_iter().map(|(k, v)| (k, Bytes::from(v))).collect());
+
+        let (already_landed, fast_forward, merge_base, hook_outcomes) = try_join!(
+            changeset.is_ancestor_of(bookmark_changeset.id()),
+            bookmark_changeset.is_ancestor_of(changeset.id()),
+            changeset.common_base_with(bookmark_changeset.id()),
+            changeset.run_hooks(&params.bookmark, pushvars.as_ref()),
+        )?;
+
+        let mut issues = Vec::new();
+        if already_landed {
+            issues.push(thrift::LandabilityIssue::ALREADY_LANDED);
+        }
+
+        let mut hook_rejections: BTreeMap<String, Vec<thrift::HookOutcomeRejected>> =
+            BTreeMap::new();
+        for outcome in hook_outcomes {
+            let (name, execution) = match outcome {
+                HookOutcome::FileHook(id, exec) => (id.hook_name, exec),
+                HookOutcome::ChangesetHook(id, exec) => (id.hook_name, exec),
+            };
+            if let HookExecution::Rejected(rej) = execution {
+                hook_rejections
+                    .entry(name)
+                    .or_default()
+                    .push(thrift::HookOutcomeRejected {
+                        description: rej.description.to_string(),
+                        long_description: rej.long_description,
+                        ..Default::default()
+                    });
+            }
+        }
+        if !hook_rejections.is_empty() {
+            issues.push(thrift::LandabilityIssue::HOOK_REJECTED);
+        }
+
+        // Landing rebases the commit onto the bookmark, so the commit's
+        // changes since the merge base must not overlap the bookmark's.  The
+        // bookmark may have moved a long way since the merge base, so only
+        // a limited number of changes are compared.
+        let mut conflict_paths = Vec::new();
+        let merge_base_ids = match &merge_base {
+            Some(merge_base) => {
+                if !already_landed && !fast_forward {
+                    let limit = limits::commit_landability_changed_paths_limit() as usize;
+                    let (changed, bookmark_changed) = try_join!(
+                        changed_paths(&changeset, merge_base, limit),
+                        changed_paths(&bookmark_changeset, merge_base, limit),
+                    )?;
+                    match (changed, bookmark_changed) {
+                        (Some(changed), Some(bookmark_changed)) => {
+                            conflict_paths = conflicting_paths(changed, bookmark_changed);
+                            if !conflict_paths.is_empty() {
+                                issues.push(thrift::LandabilityIssue::MERGE_CONFLICTS);
+                            }
+                        }
+                        _ => issues.push(thrift::LandabilityIssue::CONFLICTS_UNKNOWN),
+                    }
+                }
+                Some(map_commit_identity(merge_base, &params.identity_schemes).await?)
+            }
+            None => {
+                issues.push(thrift::LandabilityIssue::NO_COMMON_BASE);
+                None
+            }
+        };
+
+        Ok(thrift::CommitLandabilityResponse {
+            landable: issues.is_empty(),
+            issues,
+            bookmark_ids: map_commit_identity(&bookmark_changeset, &params.identity_schemes)
+                .await?,
+            merge_base_ids,
+            fast_forward,
+            hook_rejections,
+            conflict_paths: conflict_paths
+                .into_iter()
+                .map(|path| path.to_string())
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<MononokePath> {
+        paths
+            .iter()
+            .map(|path| MononokePath::try_from(*path).expect("valid path"))
+            .collect()
+    }
+
+    #[test]
+    fn test_conflicting_paths() {
+        // The same file changed on both sides.
+        assert_eq!(
+            conflicting_paths(paths(&["a/b", "c"]), paths(&["a/b", "d"])),
+            paths(&["a/b"])
+        );
+        // A file on one side is a directory on the other.
+        assert_eq!(
+            conflicting_paths(paths(&["a"]), paths(&["a/b/c"])),
+            paths(&["a"])
+        );
+        assert_eq!(
+            conflicting_paths(paths(&["a/b/c"]), paths(&["a"])),
+            paths(&["a/b/c"])
+        );
+        // Different files in the same directory don't conflict, nor do
+        // paths that only share a name prefix.
+        assert_eq!(
+            conflicting_paths(paths(&["a/b", "ab"]), paths(&["a/c", "a"])),
+            paths(&["a/b"])
+        );
+        assert!(conflicting_paths(paths(&["a/b", "ab/c"]), paths(&["a/c", "abc"])).is_empty());
+        // Conflicts are sorted.
+        assert_eq!(
+            conflicting_paths(paths(&["z", "m", "a"]), paths(&["a", "m", "z"])),
+            paths(&["a", "m", "z"])
+        );
+        assert!(conflicting_paths(vec![], paths(&["a"])).is_empty());
+    }
+}
@@ -7,34 +7,399 @@
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
-use borrowed::borrowed;
 use bytes::Bytes;
 use context::CoreContext;
 use dedupmap::DedupMap;
 use futures::future;
+use futures::stream;
+use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use futures::try_join;
 use maplit::btreeset;
+use mononoke_api::ChangesetContext;
 use mononoke_api::ChangesetPathHistoryOptions;
 use mononoke_api::ChangesetSpecifier;
+use mononoke_api::FileContext;
+use mononoke_api::FileType;
 use mononoke_api::MononokeError;
 use mononoke_api::MononokePath;
 use mononoke_api::PathEntry;
+use mononoke_api::RepoContext;
 use source_control as thrift;
 
 use crate::commit_id::map_commit_identities;
 use crate::commit_id::map_commit_identity;
+use crate::commit_id::CommitIdExt;
 use crate::errors;
 use crate::from_request::check_range_and_convert;
 use crate::from_request::validate_timestamp;
+use crate::from_request::FromRequest;
 use crate::history::collect_history;
-use crate::into_response::IntoResponse;
+use crate::into_response::AsyncIntoResponse;
+use crate::into_response::AsyncIntoResponseWith;
+use crate::limits;
 use crate::source_control_impl::SourceControlServiceImpl;
 
 const BLAME_TITLE_MAX_LENGTH: usize = 128;
+const BLAME_ANNOTATE_ID_LENGTH: usize = 12;
+const COMMIT_INFO_CONCURRENCY: usize = 100;
+
+/// Read the target of a symbolic link from its contents.
+pub(crate) async fn link_target(file: &FileContext) -> Result<String, errors::ServiceError> {
+    let content = file.content_concat().await?;
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Compute the compact blame of a file.  Also returns the changesets of the
+/// commits in the blame, in the same order as its `commit_ids`.
+async fn blame_compact(
+    repo: &RepoContext,
+    changeset: &ChangesetContext,
+    path: MononokePath,
+    identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+    format_options: Option<BTreeSet<thrift::BlameFormatOption>>,
+    follow_mutable_file_history: bool,
+) -> Result<(thrift::BlameCompact, Vec<ChangesetContext>), errors::ServiceError> {
+    let path = changeset.path_with_history(path).await?;
+
+    let options = format_options.unwrap_or_else(|| {
+        btreeset! { thrift::BlameFormatOption::INCLUDE_CONTENTS }
+    });
+    let option_include_contents = options.contains(&thrift::BlameFormatOption::INCLUDE_CONTENTS);
+    let option_include_title = options.contains(&thrift::BlameFormatOption::INCLUDE_TITLE);
+    let option_include_message = options.contains(&thrift::BlameFormatOption::INCLUDE_MESSAGE);
+    let option_include_parent = options.contains(&thrift::BlameFormatOption::INCLUDE_PARENT);
+    let option_include_commit_numbers =
+        options.contains(&thrift::BlameFormatOption::INCLUDE_COMMIT_NUMBERS);
+
+    // Changeset ids in the order they will be returned.
+    let mut indexed_csids = Vec::new();
+
+    // Mapped commit ids in that same order.
+    let mut commit_ids = Vec::new();
+
+    // The small number suitable for each commit, in that same order.
+    let mut commit_numbers = Vec::new();
+
+    // The index into these vectors of each changeset.
+    let mut commit_id_indexes = HashMap::new();
+
+    let mut paths = DedupMap::new();
+    let mut authors = DedupMap::new();
+    let mut dates = DedupMap::new();
+    let mut titles = DedupMap::new();
+    let mut messages = DedupMap::new();
+
+    // Fetch the blame, and optionally its associated content.
+    let (blame, content) = if option_include_contents {
+        path.blame_with_content(follow_mutable_file_history).await?
+    } else {
+        (path.blame(follow_mutable_file_history).await?, Bytes::new())
+    };
+
+    // Map all the changeset IDs into the requested identity schemes.  Keep a mapping of
+    // which bonsai changeset ID corresponds to which mapped commit ID index, so we can look
+    // them up later.
+    let csids_and_nums = blame
+        .changeset_ids()
+        .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
+        .collect::<Vec<_>>();
+    let csids = csids_and_nums
+        .iter()
+        .map(|(csid, _)| *csid)
+        .collect::<Vec<_>>();
+    let mut mapped_commit_ids =
+        map_commit_identities(repo, csids.clone(), identity_schemes).await?;
+    for (id, num) in csids_and_nums {
+        if let Some(mapped_ids) = mapped_commit_ids.remove(&id) {
+            let index = commit_ids.len();
+            commit_ids.push(mapped_ids);
+            commit_numbers.push(num as i32);
+            commit_id_indexes.insert(id, index);
+            indexed_csids.push(id);
+        }
+    }
+
+    // Collect author and date fields from the commit info.
+    let mut info: HashMap<_, _> = future::try_join_all(csids.iter().map(move |csid| async move {
+        let changeset = repo
+            .changeset(ChangesetSpecifier::Bonsai(*csid))
+            .await?
+            .ok_or_else(|| {
+                MononokeError::InvalidRequest(format!("failed to resolve commit: {}", csid))
+            })?;
+        let (date, author, message) = try_join!(
+            changeset.author_date(),
+            changeset.author(),
+            changeset.message(),
+        )?;
+        let title: String = message
+            .chars()
+            .take(BLAME_TITLE_MAX_LENGTH)
+            .take_while(|ch| *ch != '\n')
+            .collect();
+
+        Ok::<_, MononokeError>((*csid, (author, date, message, title, changeset)))
+    }))
+    .await?
+    .into_iter()
+    .collect();
+
+    // Collect parent information for each changeset if requested.
+    let parent_commit_ids = if option_include_parent {
+        let changeset_parents = repo.many_changeset_parents(csids.clone()).await?;
+        let all_parent_csids = changeset_parents
+            .iter()
+            .flat_map(|(_, parents)| parents)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .copied()
+            .collect::<Vec<_>>();
+        let parent_commit_ids_map =
+            map_commit_identities(repo, all_parent_csids, identity_schemes).await?;
+        let mut parent_commit_ids = Vec::with_capacity(indexed_csids.len());
+        for csid in &indexed_csids {
+            let parents = changeset_parents
+                .get(csid)
+                .ok_or_else(|| errors::internal_error(format!("missing parents for {}", csid)))?;
+            let mut changeset_parent_commit_ids = Vec::with_capacity(parents.len());
+            for parent in parents {
+                changeset_parent_commit_ids.push(
+                    parent_commit_ids_map
+                        .get(parent)
+                        .ok_or_else(|| {
+                            errors::internal_error(format!(
+                                "missing parent commit ids for {}",
+                                parent
+                            ))
+                        })?
+                        .clone(),
+                );
+            }
+            parent_commit_ids.push(changeset_parent_commit_ids);
+        }
+        Some(parent_commit_ids)
+    } else {
+        None
+    };
+
+    let mut content_iter = content.as_ref().split(|c| *c == b'\n');
+
+    let lines = blame
+        .lines()
+        .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
+        .enumerate()
+        .map(|(line, blame_line)| -> Result<_, thrift::RequestError> {
+            let commit_id_index =
+                commit_id_indexes
+                    .get(blame_line.changeset_id)
+                    .ok_or_else(|| {
+                        errors::commit_not_found(format!(
+                            "failed to resolve commit: {}",
+                            blame_line.changeset_id
+                        ))
+                    })?;
+            let (author, date, message, title, _changeset) =
+                info.get(blame_line.changeset_id).ok_or_else(|| {
+                    errors::commit_not_found(format!(
+                        "failed to resolve commit: {}",
+                        blame_line.changeset_id
+                    ))
+                })?;
+            let mut thrift_blame_line = thrift::BlameCompactLine {
+                line: (line + 1) as i32,
+                contents: None,
+                commit_id_index: *commit_id_index as i32,
+                path_index: paths.insert(&blame_line.path.to_string()) as i32,
+                author_index: authors.insert(author) as i32,
+                date_index: dates.insert(Cow::Borrowed(date)) as i32,
+                origin_line: (blame_line.origin_offset + 1) as i32,
+                title_index: None,
+                message_index: None,
+                ..Default::default()
+            };
+            if option_include_contents {
+                if let Some(content_line) = content_iter.next() {
+                    thrift_blame_line.contents =
+                        Some(String::from_utf8_lossy(content_line).into_owned());
+                }
+            }
+            if option_include_title {
+                thrift_blame_line.title_index = Some(titles.insert(title) as i32);
+            }
+            if option_include_message {
+                thrift_blame_line.message_index = Some(messages.insert(message) as i32);
+            }
+            if option_include_parent {
+                if let Some(parent) = &blame_line.parent {
+                    thrift_blame_line.parent_index = Some(parent.parent_index as i32);
+                    thrift_blame_line.parent_start_line = Some((parent.offset + 1) as i32);
+                    thrift_blame_line.parent_range_length = Some(parent.length as i32);
+                    thrift_blame_line.parent_path_index = parent
+                        .renamed_from_path
+                        .map(|path| paths.insert(&path.to_string()) as i32);
+                }
+            }
+            Ok(thrift_blame_line)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let paths = paths.into_items();
+    let authors = authors.into_items();
+    let titles = Some(titles.into_items()).filter(|titles| !titles.is_empty());
+    let messages = Some(messages.into_items()).filter(|messages| !messages.is_empty());
+    let commit_numbers = option_include_commit_numbers.then_some(commit_numbers);
+    let approx_commit_count = option_include_commit_numbers
+        .then(|| blame.changeset_count().ok())
+        .flatten()
+        .map(|c| c as i32);
+    let distinct_range_count = Some(
+        blame
+            .range_count()
+            .map_err(|e| MononokeError::InvalidRequest(e.to_string()))? as i32,
+    );
+    let dates = dates
+        .into_items()
+        .into_iter()
+        .map(|date| thrift::DateTime {
+            timestamp: date.timestamp(),
+            tz: date.offset().local_minus_utc(),
+            ..Default::default()
+        })
+        .collect();
+    let blame = thrift::BlameCompact {
+        lines,
+        commit_ids,
+        paths,
+        authors,
+        dates,
+        titles,
+        messages,
+        parent_commit_ids,
+        commit_numbers,
+        approx_commit_count,
+        distinct_range_count,
+        ..Default::default()
+    };
+
+    let changesets = indexed_csids
+        .iter()
+        .map(|csid| {
+            info.remove(csid)
+                .map(|(_, _, _, _, changeset)| changeset)
+                .ok_or_else(|| errors::internal_error(format!("missing changeset for {}", csid)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((blame, changesets))
+}
+
+/// Fetch the commit info of each commit in a blame, reusing the changesets
+/// loaded for the blame.
+async fn blame_commit_infos(
+    changesets: Vec<ChangesetContext>,
+    identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+) -> Result<Vec<thrift::CommitInfo>, errors::ServiceError> {
+    stream::iter(changesets)
+        .map(|changeset| changeset.into_response_with(identity_schemes))
+        .buffered(COMMIT_INFO_CONCURRENCY)
+        .try_collect()
+        .await
+}
+
+/// Compute the annotate blame of a file, with each line rendered for
+/// display.  Rendering stops once the lines would exceed `size_limit` bytes
+/// in total, in which case the result is marked as truncated.
+async fn blame_annotate(
+    repo: &RepoContext,
+    changeset: &ChangesetContext,
+    path: MononokePath,
+    identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+    follow_mutable_file_history: bool,
+    size_limit: usize,
+) -> Result<thrift::BlameAnnotate, errors::ServiceError> {
+    let path = changeset.path_with_history(path).await?;
+    let (blame, content) = path.blame_with_content(follow_mutable_file_history).await?;
+
+    let csids = blame
+        .changeset_ids()
+        .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
+        .map(|(csid, _)| csid)
+        .collect::<Vec<_>>();
+
+    // Identify each commit by its id in the first requested scheme,
+    // falling back to the bonsai id if it doesn't have one.
+    let scheme = identity_schemes
+        .iter()
+        .next()
+        .copied()
+        .unwrap_or(thrift::CommitIdentityScheme::BONSAI);
+    let mut mapped_commit_ids =
+        map_commit_identities(repo, csids.clone(), &btreeset! { scheme }).await?;
+    let commit_ids = csids.into_iter().map(|csid| {
+        let commit_id = mapped_commit_ids
+            .remove(&csid)
+            .and_then(|mut ids| ids.remove(&scheme))
+            .map_or_else(|| csid.to_string(), |id| id.to_string());
+        (csid, commit_id)
+    });
+    let info: HashMap<_, _> = stream::iter(commit_ids)
+        .map(|(csid, commit_id)| async move {
+            let changeset = repo
+                .changeset(ChangesetSpecifier::Bonsai(csid))
+                .await?
+                .ok_or_else(|| {
+                    MononokeError::InvalidRequest(format!("failed to resolve commit: {}", csid))
+                })?;
+            let (date, author) = try_join!(changeset.author_date(), changeset.author())?;
+            let commit_id: String = commit_id.chars().take(BLAME_ANNOTATE_ID_LENGTH).collect();
+            let date = date.format("%Y-%m-%d").to_string();
+            Ok::<_, MononokeError>((csid, (commit_id, author, date)))
+        })
+        .buffered(COMMIT_INFO_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut size = 0;
+    let mut truncated = false;
+    let mut lines = Vec::new();
+    let mut content_iter = content.as_ref().split(|c| *c == b'\n');
+    for blame_line in blame
+        .lines()
+        .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
+    {
+        let (commit_id, author, date) = info.get(blame_line.changeset_id).ok_or_else(|| {
+            errors::commit_not_found(format!(
+                "failed to resolve commit: {}",
+                blame_line.changeset_id
+            ))
+        })?;
+        let contents = content_iter.next().unwrap_or_default();
+        let line = format!(
+            "{} {} {}: {}",
+            commit_id,
+            author,
+            date,
+            String::from_utf8_lossy(contents)
+        );
+        size += line.len();
+        if size > size_limit {
+            truncated = true;
+            break;
+        }
+        lines.push(line);
+    }
+
+    Ok(thrift::BlameAnnotate {
+        lines,
+        truncated,
+        ..Default::default()
+    })
+}
 
 impl SourceControlServiceImpl {
     /// Determine whether anything exists at this path.
@@ -45,7 +410,9 @@ impl SourceControlServiceImpl {
         _params: thrift::CommitPathExistsParams,
     ) -> Result<thrift::CommitPathExistsResponse, errors::ServiceError> {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-        let path = changeset.path(&commit_path.path).await?;
+        let path = changeset
+            .path(MononokePath::from_request(&commit_path.path)?)
+            .await?;
         Ok(thrift::CommitPathExistsResponse {
             exists: path.exists().await?,
             file_exists: path.is_file().await?,
@@ -59,54 +426,18 @@ impl SourceControlServiceImpl {
         &self,
         ctx: CoreContext,
         commit_path: thrift::CommitPathSpecifier,
-        _params: thrift::CommitPathInfoParams,
+        params: thrift::CommitPathInfoParams,
     ) -> Result<thrift::CommitPathInfoResponse, errors::ServiceError> {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-        let path = changeset.path_with_content(&commit_path.path).await?;
-        let response = match path.entry().await? {
-            PathEntry::NotPresent => thrift::CommitPathInfoResponse {
-                exists: false,
-                r#type: None,
-                info: None,
-                ..Default::default()
-            },
-            PathEntry::Tree(tree) => {
-                let summary = tree.summary().await?;
-                let tree_info = thrift::TreeInfo {
-                    id: tree.id().as_ref().to_vec(),
-                    simple_format_sha1: summary.simple_format_sha1.as_ref().to_vec(),
-                    simple_format_sha256: summary.simple_format_sha256.as_ref().to_vec(),
-                    child_files_count: summary.child_files_count as i64,
-                    child_files_total_size: summary.child_files_total_size as i64,
-                    child_dirs_count: summary.child_dirs_count as i64,
-                    descendant_files_count: summary.descendant_files_count as i64,
-                    descendant_files_total_size: summary.descendant_files_total_size as i64,
-                    ..Default::default()
-                };
-                thrift::CommitPathInfoResponse {
-                    exists: true,
-                    r#type: Some(thrift::EntryType::TREE),
-                    info: Some(thrift::EntryInfo::tree(tree_info)),
-                    ..Default::default()
-                }
-            }
-            PathEntry::File(file, file_type) => {
-                let metadata = file.metadata().await?;
-                let file_info = thrift::FileInfo {
-                    id: metadata.content_id.as_ref().to_vec(),
-                    file_size: metadata.total_size as i64,
-                    content_sha1: metadata.sha1.as_ref().to_vec(),
-                    content_sha256: metadata.sha256.as_ref().to_vec(),
-                    ..Default::default()
-                };
-                thrift::CommitPathInfoResponse {
-                    exists: true,
-                    r#type: Some(file_type.into_response()),
-                    info: Some(thrift::EntryInfo::file(file_info)),
-                    ..Default::default()
-                }
+        let path = changeset
+            .path_with_content(MononokePath::from_request(&commit_path.path)?)
+            .await?;
+        let mut response: thrift::CommitPathInfoResponse = (&path).into_response().await?;
+        if params.include_link_target {
+            if let PathEntry::File(file, FileType::Symlink) = path.entry().await? {
+                response.link_target = Some(link_target(&file).await?);
             }
-        };
+        }
         Ok(response)
     }
 
@@ -119,8 +450,7 @@ impl SourceControlServiceImpl {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
         let mut paths = vec![];
         for path in params.paths {
-            let strpath = path.as_str();
-            let mpath = MononokePath::try_from(strpath)?;
+            let mpath = MononokePath::from_request(&path)?;
             paths.push(mpath);
         }
 
@@ -128,41 +458,8 @@ impl SourceControlServiceImpl {
             .paths_with_content(paths.into_iter())
             .await?
             .map_ok(|context| async move {
-                let context_path = context.path().to_string();
-
-                match context.entry().await? {
-                    PathEntry::NotPresent => {
-                        let not_present_elem = thrift::CommitPathInfoResponse {
-                            exists: false,
-                            r#type: None,
-                            info: None,
-                            ..Default::default()
-                        };
-                        Result::<_, errors::ServiceError>::Ok((context_path, not_present_elem))
-                    }
-                    PathEntry::Tree(tree) => {
-                        let summary = tree.summary().await?;
-                        let tree_elem = thrift::CommitPathInfoResponse {
-                            exists: true,
-                            r#type: Some(thrift::EntryType::TREE),
-                            info: Some(thrift::EntryInfo::tree(
-                                (*tree.id(), summary).into_response(),
-                            )),
-                            ..Default::default()
-                        };
-                        Result::<_, errors::ServiceError>::Ok((context_path, tree_elem))
-                    }
-                    PathEntry::File(file, file_type) => {
-                        let metadata = file.metadata().await?;
-                        let file_elem = thrift::CommitPathInfoResponse {
-                            exists: true,
-                            r#type: Some(file_type.into_response()),
-                            info: Some(thrift::EntryInfo::file(metadata.into_response())),
-                            ..Default::default()
-                        };
-                        Result::<_, errors::ServiceError>::Ok((context_path, file_elem))
-                    }
-                }
+                let path_info: thrift::CommitPathInfoResponse = (&context).into_response().await?;
+                Result::<_, errors::ServiceError>::Ok((context.path().to_string(), path_info))
             })
             .map_err(errors::ServiceError::from)
             .try_buffer_unordered(100)
@@ -186,6 +483,10 @@ impl SourceControlServiceImpl {
                 self.commit_path_blame_compact(ctx, commit_path, params)
                     .await
             }
+            thrift::BlameFormat::ANNOTATE => {
+                self.commit_path_blame_annotate(ctx, commit_path, params)
+                    .await
+            }
             other_format => Err(errors::invalid_request(format!(
                 "unsupported blame format {}",
                 other_format
@@ -201,234 +502,65 @@ impl SourceControlServiceImpl {
         params: thrift::CommitPathBlameParams,
     ) -> Result<thrift::CommitPathBlameResponse, errors::ServiceError> {
         let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-        borrowed!(repo);
-        let path = changeset.path_with_history(&commit_path.path).await?;
-
-        let options = params.format_options.unwrap_or_else(|| {
-            btreeset! { thrift::BlameFormatOption::INCLUDE_CONTENTS }
-        });
-        let option_include_contents =
-            options.contains(&thrift::BlameFormatOption::INCLUDE_CONTENTS);
-        let option_include_title = options.contains(&thrift::BlameFormatOption::INCLUDE_TITLE);
-        let option_include_message = options.contains(&thrift::BlameFormatOption::INCLUDE_MESSAGE);
-        let option_include_parent = options.contains(&thrift::BlameFormatOption::INCLUDE_PARENT);
-        let option_include_commit_numbers =
-            options.contains(&thrift::BlameFormatOption::INCLUDE_COMMIT_NUMBERS);
-
-        let follow_mutable_file_history = params.follow_mutable_file_history.unwrap_or(false);
-
-        // Changeset ids in the order they will be returned.
-        let mut indexed_csids = Vec::new();
-
-        // Mapped commit ids in that same order.
-        let mut commit_ids = Vec::new();
-
-        // The small number suitable for each commit, in that same order.
-        let mut commit_numbers = Vec::new();
-
-        // The index into these vectors of each changeset.
-        let mut commit_id_indexes = HashMap::new();
-
-        let mut paths = DedupMap::new();
-        let mut authors = DedupMap::new();
-        let mut dates = DedupMap::new();
-        let mut titles = DedupMap::new();
-        let mut messages = DedupMap::new();
-
-        // Fetch the blame, and optionally its associated content.
-        let (blame, content) = if option_include_contents {
-            path.blame_with_content(follow_mutable_file_history).await?
-        } else {
-            (path.blame(follow_mutable_file_history).await?, Bytes::new())
-        };
-
-        // Map all the changeset IDs into the requested identity schemes.  Keep a mapping of
-        // which bonsai changeset ID corresponds to which mapped commit ID index, so we can look
-        // them up later.
-        let csids_and_nums = blame
-            .changeset_ids()
-            .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
-            .collect::<Vec<_>>();
-        let csids = csids_and_nums
-            .iter()
-            .map(|(csid, _)| *csid)
-            .collect::<Vec<_>>();
-        let mut mapped_commit_ids =
-            map_commit_identities(repo, csids.clone(), &params.identity_schemes).await?;
-        for (id, num) in csids_and_nums {
-            if let Some(mapped_ids) = mapped_commit_ids.remove(&id) {
-                let index = commit_ids.len();
-                commit_ids.push(mapped_ids);
-                commit_numbers.push(num as i32);
-                commit_id_indexes.insert(id, index);
-                indexed_csids.push(id);
-            }
-        }
-
-        // Collect author and date fields from the commit info.
-        let info: HashMap<_, _> = future::try_join_all(csids.iter().map(move |csid| async move {
-            let changeset = repo
-                .changeset(ChangesetSpecifier::Bonsai(*csid))
-                .await?
-                .ok_or_else(|| {
-                    MononokeError::InvalidRequest(format!("failed to resolve commit: {}", csid))
-                })?;
-            let (date, author, message) = try_join!(
-                changeset.author_date(),
-                changeset.author(),
-                changeset.message(),
-            )?;
-            let title: String = message
-                .chars()
-                .take(BLAME_TITLE_MAX_LENGTH)
-                .take_while(|ch| *ch != '\n')
-                .collect();
-
-            Ok::<_, MononokeError>((*csid, (author, date, message, title)))
-        }))
-        .await?
-        .into_iter()
-        .collect();
-
-        // Collect parent information for each changeset if requested.
-        let parent_commit_ids = if option_include_parent {
-            let changeset_parents = repo.many_changeset_parents(csids.clone()).await?;
-            let all_parent_csids = changeset_parents
-                .iter()
-                .flat_map(|(_, parents)| parents)
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .copied()
-                .collect::<Vec<_>>();
-            let parent_commit_ids_map =
-                map_commit_identities(repo, all_parent_csids, &params.identity_schemes).await?;
-            let mut parent_commit_ids = Vec::with_capacity(indexed_csids.len());
-            for csid in indexed_csids {
-                let parents = changeset_parents.get(&csid).ok_or_else(|| {
-                    errors::internal_error(format!("missing parents for {}", csid))
-                })?;
-                let mut changeset_parent_commit_ids = Vec::with_capacity(parents.len());
-                for parent in parents {
-                    changeset_parent_commit_ids.push(
-                        parent_commit_ids_map
-                            .get(parent)
-                            .ok_or_else(|| {
-                                errors::internal_error(format!(
-                                    "missing parent commit ids for {}",
-                                    parent
-                                ))
-                            })?
-                            .clone(),
-                    );
-                }
-                parent_commit_ids.push(changeset_parent_commit_ids);
-            }
-            Some(parent_commit_ids)
-        } else {
-            None
-        };
-
-        let mut content_iter = content.as_ref().split(|c| *c == b'\n');
+        let (blame, _changesets) = blame_compact(
+            &repo,
+            &changeset,
+            MononokePath::from_request(&commit_path.path)?,
+            &params.identity_schemes,
+            params.format_options,
+            params.follow_mutable_file_history.unwrap_or(false),
+        )
+        .await?;
+        Ok(thrift::CommitPathBlameResponse {
+            blame: thrift::Blame::blame_compact(blame),
+            ..Default::default()
+        })
+    }
 
-        let lines = blame
-            .lines()
-            .map_err(|e| MononokeError::InvalidRequest(e.to_string()))?
-            .enumerate()
-            .map(|(line, blame_line)| -> Result<_, thrift::RequestError> {
-                let commit_id_index =
-                    commit_id_indexes
-                        .get(blame_line.changeset_id)
-                        .ok_or_else(|| {
-                            errors::commit_not_found(format!(
-                                "failed to resolve commit: {}",
-                                blame_line.changeset_id
-                            ))
-                        })?;
-                let (author, date, message, title) =
-                    info.get(blame_line.changeset_id).ok_or_else(|| {
-                        errors::commit_not_found(format!(
-                            "failed to resolve commit: {}",
-                            blame_line.changeset_id
-                        ))
-                    })?;
-                let mut thrift_blame_line = thrift::BlameCompactLine {
-                    line: (line + 1) as i32,
-                    contents: None,
-                    commit_id_index: *commit_id_index as i32,
-                    path_index: paths.insert(&blame_line.path.to_string()) as i32,
-                    author_index: authors.insert(author) as i32,
-                    date_index: dates.insert(Cow::Borrowed(date)) as i32,
-                    origin_line: (blame_line.origin_offset + 1) as i32,
-                    title_index: None,
-                    message_index: None,
-                    ..Default::default()
-                };
-                if option_include_contents {
-                    if let Some(content_line) = content_iter.next() {
-                        thrift_blame_line.contents =
-                            Some(String::from_utf8_lossy(content_line).into_owned());
-                    }
-                }
-                if option_include_title {
-                    thrift_blame_line.title_index = Some(titles.insert(title) as i32);
-                }
-                if option_include_message {
-                    thrift_blame_line.message_index = Some(messages.insert(message) as i32);
-                }
-                if option_include_parent {
-                    if let Some(parent) = &blame_line.parent {
-                        thrift_blame_line.parent_index = Some(parent.parent_index as i32);
-                        thrift_blame_line.parent_start_line = Some((parent.offset + 1) as i32);
-                        thrift_blame_line.parent_range_length = Some(parent.length as i32);
-                        thrift_blame_line.parent_path_index = parent
-                            .renamed_from_path
-                            .map(|path| paths.insert(&path.to_string()) as i32);
-                    }
-                }
-                Ok(thrift_blame_line)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let paths = paths.into_items();
-        let authors = authors.into_items();
-        let titles = Some(titles.into_items()).filter(|titles| !titles.is_empty());
-        let messages = Some(messages.into_items()).filter(|messages| !messages.is_empty());
-        let commit_numbers = option_include_commit_numbers.then_some(commit_numbers);
-        let approx_commit_count = option_include_commit_numbers
-            .then(|| blame.changeset_count().ok())
-            .flatten()
-            .map(|c| c as i32);
-        let distinct_range_count = Some(
-            blame
-                .range_count()
-                .map_err(|e| MononokeError::InvalidRequest(e.to_string()))? as i32,
-        );
-        let dates = dates
-            .into_items()
-            .into_iter()
-            .map(|date| thrift::DateTime {
-                timestamp: date.timestamp(),
-                tz: date.offset().local_minus_utc(),
-                ..Default::default()
-            })
-            .collect();
-        let blame = thrift::BlameCompact {
-            lines,
-            commit_ids,
-            paths,
-            authors,
-            dates,
-            titles,
-            messages,
-            parent_commit_ids,
-            commit_numbers,
-            approx_commit_count,
-            distinct_range_count,
+    /// Get the compact blame of a file, along with the commit info of each
+    /// of the commits it references, in the same order as its `commit_ids`.
+    pub(crate) async fn commit_file_annotate_history(
+        &self,
+        ctx: CoreContext,
+        commit_path: thrift::CommitPathSpecifier,
+        params: thrift::CommitFileAnnotateHistoryParams,
+    ) -> Result<thrift::CommitFileAnnotateHistoryResponse, errors::ServiceError> {
+        let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
+        let (blame, changesets) = blame_compact(
+            &repo,
+            &changeset,
+            MononokePath::from_request(&commit_path.path)?,
+            &params.identity_schemes,
+            params.format_options,
+            params.follow_mutable_file_history.unwrap_or(false),
+        )
+        .await?;
+        let commit_infos = blame_commit_infos(changesets, &params.identity_schemes).await?;
+        Ok(thrift::CommitFileAnnotateHistoryResponse {
+            blame,
+            commit_infos,
             ..Default::default()
-        };
+        })
+    }
 
+    async fn commit_path_blame_annotate(
+        &self,
+        ctx: CoreContext,
+        commit_path: thrift::CommitPathSpecifier,
+        params: thrift::CommitPathBlameParams,
+    ) -> Result<thrift::CommitPathBlameResponse, errors::ServiceError> {
+        let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
+        let annotate = blame_annotate(
+            &repo,
+            &changeset,
+            MononokePath::from_request(&commit_path.path)?,
+            &params.identity_schemes,
+            params.follow_mutable_file_history.unwrap_or(false),
+            limits::commit_path_blame_annotate_size_limit() as usize,
+        )
+        .await?;
         Ok(thrift::CommitPathBlameResponse {
-            blame: thrift::Blame::blame_compact(blame),
+            blame: thrift::Blame::blame_annotate(annotate),
             ..Default::default()
         })
     }
@@ -440,7 +572,9 @@ impl SourceControlServiceImpl {
         params: thrift::CommitPathHistoryParams,
     ) -> Result<thrift::CommitPathHistoryResponse, errors::ServiceError> {
         let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-        let path = changeset.path_with_history(&commit_path.path).await?;
+        let path = changeset
+            .path_with_history(MononokePath::from_request(&commit_path.path)?)
+            .await?;
         let (descendants_of, exclude_changeset_and_ancestors) = try_join!(
             async {
                 if let Some(descendants_of) = &params.descendants_of {
@@ -523,7 +657,9 @@ impl SourceControlServiceImpl {
         params: thrift::CommitPathLastChangedParams,
     ) -> Result<thrift::CommitPathLastChangedResponse, errors::ServiceError> {
         let (_repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-        let path = changeset.path_with_history(&commit_path.path).await?;
+        let path = changeset
+            .path_with_history(MononokePath::from_request(&commit_path.path)?)
+            .await?;
         match path.last_modified().await? {
             Some(last_modified) => {
                 let last_modified =
@@ -558,6 +694,56 @@ impl SourceControlServiceImpl {
         }
     }
 
+    pub(crate) async fn commit_path_first_introduced(
+        &self,
+        ctx: CoreContext,
+        commit_path: thrift::CommitPathSpecifier,
+        params: thrift::CommitPathFirstIntroducedParams,
+    ) -> Result<thrift::CommitPathFirstIntroducedResponse, errors::ServiceError> {
+        let (_repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
+        let path = changeset
+            .path_with_history(MononokePath::from_request(&commit_path.path)?)
+            .await?;
+        let first_introduced_commit = match path.first_introduced().await? {
+            Some(first_introduced) => {
+                Some(map_commit_identity(&first_introduced, &params.identity_schemes).await?)
+            }
+            None => None,
+        };
+        Ok(thrift::CommitPathFirstIntroducedResponse {
+            first_introduced_commit,
+            ..Default::default()
+        })
+    }
+
+    pub(crate) async fn commit_path_case_conflicts(
+        &self,
+        ctx: CoreContext,
+        commit_path: thrift::CommitPathSpecifier,
+        params: thrift::CommitPathCaseConflictsParams,
+    ) -> Result<thrift::CommitPathCaseConflictsResponse, errors::ServiceError> {
+        let limit: usize = check_range_and_convert("limit", params.limit, 0..)?;
+        let (_repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
+        let path = changeset
+            .path(MononokePath::from_request(&commit_path.path)?)
+            .await?;
+        // Fetch one more than the limit to find out if there are more.
+        let mut case_conflicts = path.case_conflicts(limit + 1).await?;
+        let truncated = case_conflicts.len() > limit;
+        case_conflicts.truncate(limit);
+        Ok(thrift::CommitPathCaseConflictsResponse {
+            case_conflicts: case_conflicts
+                .into_iter()
+                .map(|paths| thrift::PathCaseConflict {
+                    paths: paths.into_iter().map(|path| path.to_string()).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+            truncated,
+            ..Default::default()
+        })
+    }
+
     pub(crate) async fn commit_multiple_path_last_changed(
         &self,
         ctx: CoreContext,
@@ -567,8 +753,7 @@ impl SourceControlServiceImpl {
         let (repo, changeset) = self.repo_changeset(ctx, &commit).await?;
         let mut paths = HashSet::with_capacity(params.paths.len());
         for path in params.paths {
-            let strpath = path.as_str();
-            let mpath = MononokePath::try_from(strpath)?;
+            let mpath = MononokePath::from_request(&path)?;
             paths.insert(mpath);
         }
 
@@ -646,3 +831,135 @@ impl SourceControlServiceImpl {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use fbinit::FacebookInit;
+    use maplit::btreemap;
+    use mononoke_api::Repo;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_blame_commit_infos(fb: FacebookInit) -> Result<(), errors::ServiceError> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo: Repo = test_repo_factory::build_empty(fb)
+            .await
+            .map_err(MononokeError::from)?;
+        let first = CreateCommitContext::new_root(&ctx, &repo)
+            .add_file("file", "a\nb\n")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let second = CreateCommitContext::new(&ctx, &repo, vec![first])
+            .add_file("file", "a\nc\n")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let repo = RepoContext::new_test(ctx, Arc::new(repo)).await?;
+        let changeset = repo
+            .changeset(ChangesetSpecifier::Bonsai(second))
+            .await?
+            .expect("changeset exists");
+
+        let identity_schemes = btreeset! { thrift::CommitIdentityScheme::BONSAI };
+        let (blame, changesets) = blame_compact(
+            &repo,
+            &changeset,
+            MononokePath::try_from("file")?,
+            &identity_schemes,
+            None,
+            false,
+        )
+        .await?;
+        let commit_infos = blame_commit_infos(changesets, &identity_schemes).await?;
+
+        // The commit infos are in the same order as the blame's commits.
+        let ids = commit_infos
+            .into_iter()
+            .map(|commit_info| commit_info.ids)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, blame.commit_ids);
+
+        let bonsai = |cs_id: mononoke_api::ChangesetId| {
+            btreemap! {
+                thrift::CommitIdentityScheme::BONSAI =>
+                    thrift::CommitId::bonsai(cs_id.as_ref().to_vec()),
+            }
+        };
+        let line_ids = blame
+            .lines
+            .iter()
+            .map(|line| blame.commit_ids[line.commit_id_index as usize].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(line_ids, vec![bonsai(first), bonsai(second)]);
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_blame_annotate(fb: FacebookInit) -> Result<(), errors::ServiceError> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo: Repo = test_repo_factory::build_empty(fb)
+            .await
+            .map_err(MononokeError::from)?;
+        let first = CreateCommitContext::new_root(&ctx, &repo)
+            .add_file("file", "a\nb\n")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let second = CreateCommitContext::new(&ctx, &repo, vec![first])
+            .add_file("file", "a\nc\n")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let repo = RepoContext::new_test(ctx, Arc::new(repo)).await?;
+        let changeset = repo
+            .changeset(ChangesetSpecifier::Bonsai(second))
+            .await?
+            .expect("changeset exists");
+
+        let mut expected = Vec::new();
+        for (cs_id, contents) in [(first, "a"), (second, "c")] {
+            let changeset = repo
+                .changeset(ChangesetSpecifier::Bonsai(cs_id))
+                .await?
+                .expect("changeset exists");
+            let (date, author) = try_join!(changeset.author_date(), changeset.author())?;
+            expected.push(format!(
+                "{} {} {}: {}",
+                &cs_id.to_string()[..BLAME_ANNOTATE_ID_LENGTH],
+                author,
+                date.format("%Y-%m-%d"),
+                contents,
+            ));
+        }
+
+        let identity_schemes = btreeset! { thrift::CommitIdentityScheme::BONSAI };
+        let annotate = |size_limit| {
+            blame_annotate(
+                &repo,
+                &changeset,
+                MononokePath::try_from("file").expect("valid path"),
+                &identity_schemes,
+                false,
+                size_limit,
+            )
+        };
+
+        // Each line is prefixed with its commit, author and date.
+        let full = annotate(usize::MAX).await?;
+        assert_eq!(full.lines, expected);
+        assert!(!full.truncated);
+
+        // Lines that would go over the size limit are left out.
+        let truncated = annotate(expected[0].len()).await?;
+        assert_eq!(truncated.lines, expected[..1]);
+        assert!(truncated.truncated);
+
+        Ok(())
+    }
+}
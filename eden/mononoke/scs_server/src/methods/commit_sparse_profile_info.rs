@@ -9,9 +9,11 @@ use anyhow::Result;
 use context::CoreContext;
 use itertools::Itertools;
 use mononoke_api::sparse_profile::get_profile_delta_size;
+use mononoke_api::sparse_profile::get_profile_files_summary;
 use mononoke_api::sparse_profile::MonitoringProfiles;
 use mononoke_api::sparse_profile::ProfileSizeChange;
 use mononoke_api::sparse_profile::SparseProfileMonitoring;
+use mononoke_types::NonRootMPath;
 use source_control as thrift;
 
 use crate::errors;
@@ -111,6 +113,43 @@ impl SourceControlServiceImpl {
             ..Default::default()
         })
     }
+
+    pub(crate) async fn commit_sparse_profile_files(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::CommitSparseProfileFilesParams,
+    ) -> Result<thrift::CommitSparseProfileFilesResponse, errors::ServiceError> {
+        let (_repo, changeset) = self.repo_changeset(ctx.clone(), &commit).await?;
+        let paths = params
+            .profile_paths
+            .iter()
+            .map(|path| {
+                NonRootMPath::new(path).map_err(|e| {
+                    errors::invalid_request(format!("invalid profile path '{}': {}", path, e))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let summaries = get_profile_files_summary(&ctx, &changeset, paths).await?;
+        let profiles = summaries
+            .into_iter()
+            .map(|(source, summary)| {
+                (
+                    source,
+                    thrift::SparseProfileFiles {
+                        rules: summary.rules,
+                        file_count: summary.file_count as i64,
+                        total_size: summary.total_size as i64,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+        Ok(thrift::CommitSparseProfileFilesResponse {
+            profiles,
+            ..Default::default()
+        })
+    }
 }
 
 async fn convert_profiles_params(
@@ -51,11 +51,7 @@ impl SourceControlServiceImpl {
         params: thrift::FileContentChunkParams,
     ) -> Result<thrift::FileChunk, errors::ServiceError> {
         let offset: u64 = check_range_and_convert("offset", params.offset, 0..)?;
-        let size: u64 = check_range_and_convert(
-            "size",
-            params.size,
-            0..=source_control::FILE_CONTENT_CHUNK_SIZE_LIMIT,
-        )?;
+        let size: u64 = check_range_and_convert("size", params.size, 0..)?;
         match self.repo_file(ctx, &file).await? {
             (_repo, Some(file)) => {
                 let metadata = file.metadata().await?;
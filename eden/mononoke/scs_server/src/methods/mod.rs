@@ -6,12 +6,18 @@
  */
 
 use context::CoreContext;
+use permission_checker::MononokeIdentitySetExt;
 use source_control as thrift;
 
+use crate::capabilities;
 use crate::errors;
+use crate::from_request::check_range_and_convert;
+use crate::limits;
 use crate::source_control_impl::SourceControlServiceImpl;
 
 pub(crate) mod commit;
+pub(crate) mod commit_export_archive;
+pub(crate) mod commit_landability;
 pub(crate) mod commit_lookup_pushrebase_history;
 pub(crate) mod commit_path;
 pub(crate) mod commit_sparse_profile_info;
@@ -38,4 +44,58 @@ impl SourceControlServiceImpl {
             .collect();
         Ok(rsp)
     }
+
+    pub(crate) async fn service_limits(
+        &self,
+        _ctx: CoreContext,
+        _params: thrift::ServiceLimitsParams,
+    ) -> Result<thrift::ServiceLimits, errors::ServiceError> {
+        Ok(limits::service_limits())
+    }
+
+    pub(crate) async fn service_capabilities(
+        &self,
+        _ctx: CoreContext,
+        _params: thrift::ServiceCapabilitiesParams,
+    ) -> Result<thrift::ServiceCapabilitiesResponse, errors::ServiceError> {
+        Ok(capabilities::service_capabilities())
+    }
+
+    pub(crate) async fn service_usage(
+        &self,
+        ctx: CoreContext,
+        params: thrift::ServiceUsageParams,
+    ) -> Result<thrift::ServiceUsageResponse, errors::ServiceError> {
+        // Usage reveals who else is calling the service, so is restricted.
+        if !self
+            .usage_checker
+            .check_set(ctx.metadata().identities(), &["service_usage"])
+            .await
+        {
+            return Err(errors::permission_denied(
+                "service usage is only available to allowlisted callers".to_string(),
+            )
+            .into());
+        }
+        let window_secs: u64 = check_range_and_convert("window_secs", params.window_secs, 1..)?;
+        let (mut usage, window_secs) = self.usage.usage(window_secs);
+        if let Some(limit) = params.limit {
+            let limit: usize = check_range_and_convert("limit", limit, 0..)?;
+            usage.truncate(limit);
+        }
+        Ok(thrift::ServiceUsageResponse {
+            usage,
+            window_secs: window_secs as i64,
+            ..Default::default()
+        })
+    }
+
+    pub(crate) async fn request_poll(
+        &self,
+        ctx: CoreContext,
+        token: thrift::AsyncRequestToken,
+    ) -> Result<thrift::AsyncRequestPollResponse, errors::ServiceError> {
+        let caller = ctx.metadata().identities().main_client_identity();
+        self.background_requests.poll(&token, &caller)
+    }
 }
@@ -6,6 +6,7 @@
  */
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
 use bookmarks::BookmarkKey;
 use bytes::Bytes;
@@ -17,9 +18,12 @@ use futures::stream::FuturesOrdered;
 use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use futures::try_join;
+use hooks::HookExecution;
 use maplit::btreemap;
 use metaconfig_types::CommitIdentityScheme;
+use mononoke_api::specifiers::Globalrev;
 use mononoke_api::BookmarkFreshness;
+use mononoke_api::ChangesetHistoryOptions;
 use mononoke_api::ChangesetId;
 use mononoke_api::ChangesetPrefixSpecifier;
 use mononoke_api::ChangesetSpecifier;
@@ -48,10 +52,62 @@ use crate::errors::ServiceErrorResultExt;
 use crate::from_request::check_range_and_convert;
 use crate::from_request::convert_pushvars;
 use crate::from_request::FromRequest;
+use crate::into_response::AsyncIntoResponse;
 use crate::into_response::AsyncIntoResponseWith;
+use crate::into_response::IntoResponse;
 use crate::source_control_impl::SourceControlServiceImpl;
 
+mod hook_configs;
 mod land_stack;
+pub(crate) mod statistics;
+mod update_submodule;
+
+/// Maximum number of commits looked up concurrently by
+/// `repo_path_multi_commit_info`.
+const PATH_MULTI_COMMIT_INFO_CONCURRENCY: usize = 100;
+
+/// Look up a path in each of the given commits, in the same order as the
+/// commits.
+async fn path_multi_commit_info(
+    repo: &RepoContext,
+    path: &str,
+    commits: &[thrift::CommitId],
+    identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+) -> Result<Vec<thrift::PathCommitInfo>, errors::ServiceError> {
+    stream::iter(commits)
+        .map(|commit| async move {
+            let changeset = repo
+                .changeset(ChangesetSpecifier::from_request(commit)?)
+                .await?
+                .ok_or_else(|| {
+                    errors::commit_not_found(format!(
+                        "repo={} commit={}",
+                        repo.name(),
+                        commit.to_string()
+                    ))
+                })?;
+            let (ids, path_info) = try_join!(
+                async {
+                    Ok::<_, errors::ServiceError>(
+                        map_commit_identity(&changeset, identity_schemes).await?,
+                    )
+                },
+                async {
+                    let path = changeset.path_with_content(path).await?;
+                    let path_info: thrift::CommitPathInfoResponse = (&path).into_response().await?;
+                    Ok(path_info)
+                },
+            )?;
+            Ok::<_, errors::ServiceError>(thrift::PathCommitInfo {
+                ids,
+                path_info,
+                ..Default::default()
+            })
+        })
+        .buffered(PATH_MULTI_COMMIT_INFO_CONCURRENCY)
+        .try_collect()
+        .await
+}
 
 impl SourceControlServiceImpl {
     /// Detailed repo info.
@@ -88,7 +144,8 @@ impl SourceControlServiceImpl {
     /// Resolve a bookmark to a changeset.
     ///
     /// Returns whether the bookmark exists, and the IDs of the changeset in
-    /// the requested indentity schemes.
+    /// the requested indentity schemes.  Values served from the warm
+    /// bookmarks cache include how stale they may be.
     pub(crate) async fn repo_resolve_bookmark(
         &self,
         ctx: CoreContext,
@@ -96,18 +153,24 @@ impl SourceControlServiceImpl {
         params: thrift::RepoResolveBookmarkParams,
     ) -> Result<thrift::RepoResolveBookmarkResponse, errors::ServiceError> {
         let repo = self.repo(ctx, &repo).await?;
+        let freshness = if params.bypass_cache {
+            BookmarkFreshness::MostRecent
+        } else {
+            BookmarkFreshness::MaybeStale
+        };
         match repo
-            .resolve_bookmark(
+            .resolve_bookmark_with_staleness(
                 &BookmarkKey::new(&params.bookmark_name).map_err(Into::<MononokeError>::into)?,
-                BookmarkFreshness::MaybeStale,
+                freshness,
             )
             .await?
         {
-            Some(cs) => {
+            Some((cs, staleness)) => {
                 let ids = map_commit_identity(&cs, &params.identity_schemes).await?;
                 Ok(thrift::RepoResolveBookmarkResponse {
                     exists: true,
                     ids: Some(ids),
+                    staleness: staleness.map(IntoResponse::into_response),
                     ..Default::default()
                 })
             }
@@ -119,6 +182,96 @@ impl SourceControlServiceImpl {
         }
     }
 
+    /// Resolve a globalrev to a changeset.
+    pub(crate) async fn repo_resolve_globalrev(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoResolveGlobalrevParams,
+    ) -> Result<thrift::RepoResolveGlobalrevResponse, errors::ServiceError> {
+        let globalrev = Globalrev::new(params.globalrev.try_into().map_err(|_| {
+            errors::invalid_request(format!(
+                "cannot parse globalrev {} to u64",
+                params.globalrev
+            ))
+        })?);
+        let repo = self.repo(ctx, &repo).await?;
+        match repo
+            .changeset(ChangesetSpecifier::Globalrev(globalrev))
+            .await?
+        {
+            Some(cs) => {
+                let ids = map_commit_identity(&cs, &params.identity_schemes).await?;
+                Ok(thrift::RepoResolveGlobalrevResponse {
+                    exists: true,
+                    ids: Some(ids),
+                    ..Default::default()
+                })
+            }
+            None => Ok(thrift::RepoResolveGlobalrevResponse {
+                exists: false,
+                ids: None,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Get the highest globalrev assigned in a repo.
+    pub(crate) async fn repo_latest_globalrev(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoLatestGlobalrevParams,
+    ) -> Result<thrift::RepoLatestGlobalrevResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        match repo.latest_globalrev().await? {
+            Some((globalrev, cs_id)) => {
+                let ids = map_commit_identities(&repo, vec![cs_id], &params.identity_schemes)
+                    .await?
+                    .remove(&cs_id);
+                Ok(thrift::RepoLatestGlobalrevResponse {
+                    globalrev: Some(globalrev.id() as i64),
+                    ids,
+                    ..Default::default()
+                })
+            }
+            None => Ok(thrift::RepoLatestGlobalrevResponse {
+                globalrev: None,
+                ids: None,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Get the changesets assigned globalrevs in a range.
+    pub(crate) async fn repo_commits_in_globalrev_range(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoCommitsInGlobalrevRangeParams,
+    ) -> Result<thrift::RepoCommitsInGlobalrevRangeResponse, errors::ServiceError> {
+        let start: u64 = check_range_and_convert("start", params.start, 0..)?;
+        let end: u64 = check_range_and_convert("end", params.end, params.start..)?;
+        let repo = self.repo(ctx, &repo).await?;
+        let globalrevs = (start..end).map(Globalrev::new).collect();
+        let mut globalrev_cs_ids = repo.many_changeset_ids_from_globalrev(globalrevs).await?;
+        globalrev_cs_ids.sort_unstable_by_key(|(globalrev, _)| *globalrev);
+        let cs_ids = globalrev_cs_ids.iter().map(|(_, cs_id)| *cs_id).collect();
+        let mut ids = map_commit_identities(&repo, cs_ids, &params.identity_schemes).await?;
+        let commits = globalrev_cs_ids
+            .into_iter()
+            .map(|(globalrev, cs_id)| thrift::GlobalrevCommit {
+                globalrev: globalrev.id() as i64,
+                ids: ids.remove(&cs_id).unwrap_or_default(),
+                ..Default::default()
+            })
+            .collect();
+        Ok(thrift::RepoCommitsInGlobalrevRangeResponse {
+            commits,
+            ..Default::default()
+        })
+    }
+
     /// Resolve a prefix and its identity scheme to a changeset.
     ///
     /// Returns the IDs of the changeset in the requested identity schemes.
@@ -221,11 +374,7 @@ impl SourceControlServiceImpl {
         repo: thrift::RepoSpecifier,
         params: thrift::RepoListBookmarksParams,
     ) -> Result<thrift::RepoListBookmarksResponse, errors::ServiceError> {
-        let limit = match check_range_and_convert(
-            "limit",
-            params.limit,
-            0..=source_control::REPO_LIST_BOOKMARKS_MAX_LIMIT,
-        )? {
+        let limit = match check_range_and_convert("limit", params.limit, 0..)? {
             0 => None,
             limit => Some(limit),
         };
@@ -267,6 +416,51 @@ impl SourceControlServiceImpl {
         })
     }
 
+    /// List the most recent commits on a bookmark.
+    ///
+    /// Returns the commit the bookmark points to and its ancestors, up to
+    /// the limit, in reverse topological order.
+    pub(crate) async fn repo_list_recent_commits(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoListRecentCommitsParams,
+    ) -> Result<thrift::RepoListRecentCommitsResponse, errors::ServiceError> {
+        let limit: usize = check_range_and_convert("limit", params.limit, 0..)?;
+        let repo = self.repo(ctx, &repo).await?;
+        let bookmark =
+            BookmarkKey::new(&params.bookmark_name).map_err(Into::<MononokeError>::into)?;
+        let changeset = match repo
+            .resolve_bookmark(&bookmark, BookmarkFreshness::MaybeStale)
+            .await?
+        {
+            Some(changeset) => changeset,
+            None => {
+                return Ok(thrift::RepoListRecentCommitsResponse {
+                    exists: false,
+                    commits: Vec::new(),
+                    ..Default::default()
+                });
+            }
+        };
+
+        let identity_schemes = &params.identity_schemes;
+        let commits = changeset
+            .history(ChangesetHistoryOptions::default())
+            .await?
+            .take(limit)
+            .map(|changeset| async move { changeset?.into_response_with(identity_schemes).await })
+            .buffered(100)
+            .try_collect()
+            .await?;
+
+        Ok(thrift::RepoListRecentCommitsResponse {
+            exists: true,
+            commits,
+            ..Default::default()
+        })
+    }
+
     async fn convert_create_commit_parents(
         repo: &RepoContext,
         parents: &[thrift::CommitId],
@@ -404,9 +598,7 @@ impl SourceControlServiceImpl {
         let changes = changes
             .into_iter()
             .map(|(path, change)| async move {
-                let path = MononokePath::try_from(&path).map_err(|e| {
-                    errors::invalid_request(format!("invalid path '{}': {}", path, e))
-                })?;
+                let path = MononokePath::from_request(&path)?;
                 let change = Self::convert_create_commit_change(repo, change).await?;
                 Ok::<_, errors::ServiceError>((path, change))
             })
@@ -540,13 +732,7 @@ impl SourceControlServiceImpl {
         params: thrift::RepoStackInfoParams,
     ) -> Result<thrift::RepoStackInfoResponse, errors::ServiceError> {
         let repo = self.repo(ctx, &repo).await?;
-
-        // Check the limit
-        let limit = check_range_and_convert(
-            "limit",
-            params.limit,
-            0..=thrift::consts::REPO_STACK_INFO_MAX_LIMIT,
-        )?;
+        let limit = check_range_and_convert("limit", params.limit, 0..)?;
 
         // parse changeset specifiers from params
         let head_specifiers = params
@@ -636,6 +822,64 @@ impl SourceControlServiceImpl {
         }
     }
 
+    /// Returns information about the same path in each of the given commits.
+    pub(crate) async fn repo_path_multi_commit_info(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoPathMultiCommitInfoParams,
+    ) -> Result<thrift::RepoPathMultiCommitInfoResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let commits_info = path_multi_commit_info(
+            &repo,
+            &params.path,
+            &params.commits,
+            &params.identity_schemes,
+        )
+        .await?;
+
+        Ok(thrift::RepoPathMultiCommitInfoResponse {
+            commits_info,
+            ..Default::default()
+        })
+    }
+
+    /// Returns filestore metadata for each of the given content ids.
+    pub(crate) async fn repo_file_metadata(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoFileMetadataParams,
+    ) -> Result<thrift::RepoFileMetadataResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let ids = params
+            .ids
+            .iter()
+            .map(FileId::from_request)
+            .collect::<Result<Vec<_>, _>>()?;
+        let files = stream::iter(ids.into_iter().map(|id| {
+            let repo = &repo;
+            async move {
+                let metadata = match repo.file(id).await? {
+                    Some(file) => {
+                        Some((id.as_ref().to_vec(), file.metadata().await?.into_response()))
+                    }
+                    None => None,
+                };
+                Ok::<_, errors::ServiceError>(metadata)
+            }
+        }))
+        .buffered(100)
+        .try_filter_map(|metadata| async move { Ok(metadata) })
+        .try_collect::<BTreeMap<_, _>>()
+        .await?;
+
+        Ok(thrift::RepoFileMetadataResponse {
+            files,
+            ..Default::default()
+        })
+    }
+
     pub(crate) async fn repo_create_bookmark(
         &self,
         ctx: CoreContext,
@@ -663,6 +907,40 @@ impl SourceControlServiceImpl {
         })
     }
 
+    pub(crate) async fn repo_create_branch_from_commit(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoCreateBranchFromCommitParams,
+    ) -> Result<thrift::RepoCreateBranchFromCommitResponse, errors::ServiceError> {
+        if params.allowed_pushers.is_empty() {
+            return Err(errors::invalid_request(
+                "at least one allowed pusher must be given for a branch",
+            )
+            .into());
+        }
+        let repo = self
+            .repo_for_service(ctx, &repo, params.service_identity)
+            .await?;
+        let target = &params.target;
+        let changeset = repo
+            .changeset(ChangesetSpecifier::from_request(target)?)
+            .await?
+            .ok_or_else(|| errors::commit_not_found(target.to_string()))?;
+        let pushvars = convert_pushvars(params.pushvars);
+
+        repo.create_branch(
+            &BookmarkKey::new(&params.bookmark).map_err(Into::<MononokeError>::into)?,
+            changeset.id(),
+            &params.allowed_pushers,
+            pushvars.as_ref(),
+        )
+        .await?;
+        Ok(thrift::RepoCreateBranchFromCommitResponse {
+            ..Default::default()
+        })
+    }
+
     pub(crate) async fn repo_move_bookmark(
         &self,
         ctx: CoreContext,
@@ -687,16 +965,49 @@ impl SourceControlServiceImpl {
             ),
             None => None,
         };
+        let must_include_changeset_id = match &params.must_include {
+            Some(must_include) => {
+                if params.allow_non_fast_forward_move {
+                    return Err(errors::invalid_request(
+                        "must_include cannot be combined with allow_non_fast_forward_move",
+                    )
+                    .into());
+                }
+                Some(
+                    repo.changeset(ChangesetSpecifier::from_request(must_include)?)
+                        .await
+                        .context("failed to resolve must_include commit")?
+                        .ok_or_else(|| errors::commit_not_found(must_include.to_string()))?
+                        .id(),
+                )
+            }
+            None => None,
+        };
         let pushvars = convert_pushvars(params.pushvars);
+        let bookmark = BookmarkKey::new(&params.bookmark).map_err(Into::<MononokeError>::into)?;
 
-        repo.move_bookmark(
-            &BookmarkKey::new(&params.bookmark).map_err(Into::<MononokeError>::into)?,
-            changeset.id(),
-            old_changeset_id,
-            params.allow_non_fast_forward_move,
-            pushvars.as_ref(),
-        )
-        .await?;
+        match must_include_changeset_id {
+            Some(must_include) => {
+                repo.move_bookmark_including(
+                    &bookmark,
+                    changeset.id(),
+                    old_changeset_id,
+                    must_include,
+                    pushvars.as_ref(),
+                )
+                .await?
+            }
+            None => {
+                repo.move_bookmark(
+                    &bookmark,
+                    changeset.id(),
+                    old_changeset_id,
+                    params.allow_non_fast_forward_move,
+                    pushvars.as_ref(),
+                )
+                .await?
+            }
+        }
         Ok(thrift::RepoMoveBookmarkResponse {
             ..Default::default()
         })
@@ -819,4 +1130,189 @@ impl SourceControlServiceImpl {
             ..Default::default()
         })
     }
+
+    /// Run a hook against the recent history of a bookmark.
+    ///
+    /// Returns the commits the hook rejected and counts of the rejections.
+    pub(crate) async fn repo_run_hook_on_history(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoRunHookOnHistoryParams,
+    ) -> Result<thrift::RepoRunHookOnHistoryResponse, errors::ServiceError> {
+        let repo = self
+            .repo_for_service(ctx, &repo, params.service_identity)
+            .await?;
+        repo.authorization_context()
+            .require_run_hook_on_history(repo.ctx(), repo.inner_repo())
+            .await
+            .map_err(MononokeError::from)?;
+        let changeset = repo
+            .resolve_bookmark(
+                &BookmarkKey::new(&params.bookmark).map_err(Into::<MononokeError>::into)?,
+                BookmarkFreshness::MaybeStale,
+            )
+            .await?
+            .ok_or_else(|| {
+                errors::invalid_request(format!("bookmark not found: {}", params.bookmark))
+            })?;
+        let outcomes = changeset
+            .run_hook_on_history(&params.hook_name, &params.bookmark, params.limit as usize)
+            .await?;
+
+        let commits_checked = outcomes.len() as i64;
+        let mut rejection_counts = BTreeMap::new();
+        let mut rejected = Vec::new();
+        for (cs_id, cs_outcomes) in outcomes {
+            let rejections = cs_outcomes
+                .into_iter()
+                .filter_map(|outcome| match outcome.get_execution() {
+                    HookExecution::Accepted => None,
+                    HookExecution::Rejected(info) => Some(thrift::HookOutcomeRejected {
+                        description: info.description.to_string(),
+                        long_description: info.long_description.clone(),
+                        ..Default::default()
+                    }),
+                })
+                .collect::<Vec<_>>();
+            if !rejections.is_empty() {
+                for rejection in rejections.iter() {
+                    *rejection_counts
+                        .entry(rejection.description.clone())
+                        .or_insert(0) += 1;
+                }
+                rejected.push((cs_id, rejections));
+            }
+        }
+
+        let mut ids = map_commit_identities(
+            &repo,
+            rejected.iter().map(|(cs_id, _)| *cs_id).collect(),
+            &params.identity_schemes,
+        )
+        .await?;
+        let rejected_commits = rejected
+            .into_iter()
+            .map(|(cs_id, rejections)| thrift::HookRejectedCommit {
+                ids: ids.remove(&cs_id).unwrap_or_default(),
+                rejections,
+                ..Default::default()
+            })
+            .collect();
+        Ok(thrift::RepoRunHookOnHistoryResponse {
+            commits_checked,
+            rejected_commits,
+            rejection_counts,
+            ..Default::default()
+        })
+    }
+
+    /// Check a commit message against the message hooks for a bookmark.
+    pub(crate) async fn repo_validate_commit_message(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoValidateCommitMessageParams,
+    ) -> Result<thrift::RepoValidateCommitMessageResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let (hooks_run, outcomes) = repo
+            .validate_commit_message(&params.message, &params.bookmark)
+            .await?;
+
+        let mut outcomes_map: BTreeMap<String, thrift::HookOutcome> = hooks_run
+            .into_iter()
+            .map(|hook_name| {
+                (
+                    hook_name,
+                    thrift::HookOutcome::accepted(thrift::HookOutcomeAccepted {
+                        ..Default::default()
+                    }),
+                )
+            })
+            .collect();
+        let mut valid = true;
+        for outcome in outcomes {
+            let name = outcome.get_hook_name().to_string();
+            if let HookExecution::Rejected(info) = outcome.get_execution() {
+                valid = false;
+                let rejection = thrift::HookOutcomeRejected {
+                    description: info.description.to_string(),
+                    long_description: info.long_description.clone(),
+                    ..Default::default()
+                };
+                match outcomes_map
+                    .entry(name)
+                    .or_insert_with(|| thrift::HookOutcome::rejections(vec![]))
+                {
+                    thrift::HookOutcome::rejections(rejections) => rejections.push(rejection),
+                    obj => *obj = thrift::HookOutcome::rejections(vec![rejection]),
+                }
+            }
+        }
+
+        Ok(thrift::RepoValidateCommitMessageResponse {
+            valid,
+            outcomes: outcomes_map,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use fbinit::FacebookInit;
+    use maplit::btreeset;
+    use mononoke_api::Repo;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_path_multi_commit_info(fb: FacebookInit) -> Result<(), errors::ServiceError> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo: Repo = test_repo_factory::build_empty(fb)
+            .await
+            .map_err(MononokeError::from)?;
+        let first = CreateCommitContext::new_root(&ctx, &repo)
+            .add_file("other", "other")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let second = CreateCommitContext::new(&ctx, &repo, vec![first])
+            .add_file("file", "a")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let third = CreateCommitContext::new(&ctx, &repo, vec![second])
+            .add_file("file", "b")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let repo = RepoContext::new_test(ctx, Arc::new(repo)).await?;
+
+        // Request the commits out of history order to check the results
+        // follow the order of the request.
+        let commits = vec![third, first, second]
+            .into_iter()
+            .map(|cs_id| thrift::CommitId::bonsai(cs_id.as_ref().to_vec()))
+            .collect::<Vec<_>>();
+        let identity_schemes = btreeset! { thrift::CommitIdentityScheme::BONSAI };
+        let infos = path_multi_commit_info(&repo, "file", &commits, &identity_schemes).await?;
+
+        let ids = infos
+            .iter()
+            .map(|info| info.ids[&thrift::CommitIdentityScheme::BONSAI].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, commits);
+        let exists = infos
+            .iter()
+            .map(|info| info.path_info.exists)
+            .collect::<Vec<_>>();
+        assert_eq!(exists, vec![true, false, true]);
+        assert_ne!(infos[0].path_info.info, infos[2].path_info.info);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::BTreeMap;
+
+use bookmarks::BookmarkKey;
+use context::CoreContext;
+use metaconfig_types::HookConfig;
+use serde_json::Value;
+use source_control as thrift;
+
+use crate::errors;
+use crate::source_control_impl::SourceControlServiceImpl;
+
+const REDACTED: &str = "<redacted>";
+
+/// Config values whose names contain any of these are not returned.
+const SECRET_KEY_MARKERS: &[&str] = &["secret", "token", "password", "credential"];
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Options that aren't valid JSON can't be redacted, so are omitted.
+fn redact_options(options: &str) -> Option<String> {
+    let mut value: Value = serde_json::from_str(options).ok()?;
+    redact_json(&mut value);
+    Some(value.to_string())
+}
+
+fn redact_map<V>(
+    map: impl IntoIterator<Item = (String, V)>,
+    redacted: impl Fn() -> V,
+) -> BTreeMap<String, V> {
+    map.into_iter()
+        .map(|(key, value)| {
+            let value = if is_secret(&key) { redacted() } else { value };
+            (key, value)
+        })
+        .collect()
+}
+
+fn hook_config_info(
+    name: &str,
+    implementation: &str,
+    config: &HookConfig,
+) -> thrift::HookConfigInfo {
+    let bypass = config.bypass.as_ref();
+    let ints = config
+        .ints
+        .iter()
+        .map(|(key, value)| (key.clone(), *value as i64))
+        .chain(config.ints_64.clone());
+    let int_lists = config
+        .int_lists
+        .iter()
+        .map(|(key, values)| (key.clone(), values.iter().map(|v| *v as i64).collect()))
+        .chain(config.int_64_lists.clone());
+    thrift::HookConfigInfo {
+        name: name.to_string(),
+        implementation: implementation.to_string(),
+        bypass_commit_message: bypass
+            .and_then(|bypass| bypass.commit_message_bypass())
+            .cloned(),
+        bypass_pushvar: bypass
+            .and_then(|bypass| bypass.pushvar_bypass())
+            .map(|(name, value)| format!("{}={}", name, value)),
+        options: config.options.as_deref().and_then(redact_options),
+        strings: redact_map(config.strings.clone(), || REDACTED.to_string()),
+        ints: redact_map(ints, || 0),
+        string_lists: redact_map(config.string_lists.clone(), || vec![REDACTED.to_string()]),
+        int_lists: redact_map(int_lists, Vec::new),
+        ..Default::default()
+    }
+}
+
+impl SourceControlServiceImpl {
+    /// The hooks that run on pushes to a bookmark, with their configuration.
+    pub(crate) async fn repo_list_hook_configs(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoListHookConfigsParams,
+    ) -> Result<thrift::RepoListHookConfigsResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let bookmark = BookmarkKey::new(&params.bookmark).map_err(errors::invalid_request)?;
+        let implementations: BTreeMap<_, _> = repo
+            .config()
+            .hooks
+            .iter()
+            .map(|hook| (hook.name.as_str(), hook.implementation.as_str()))
+            .collect();
+
+        let hook_manager = repo.hook_manager();
+        let mut hooks: Vec<_> = hook_manager
+            .hook_configs_for_bookmark(&bookmark)
+            .map(|(name, config)| {
+                let implementation = implementations.get(name).copied().unwrap_or(name);
+                hook_config_info(name, implementation, config)
+            })
+            .collect();
+        hooks.sort_by(|a, b| a.name.cmp(&b.name));
+        hooks.dedup_by(|a, b| a.name == b.name);
+
+        Ok(thrift::RepoListHookConfigsResponse {
+            hooks,
+            ..Default::default()
+        })
+    }
+}
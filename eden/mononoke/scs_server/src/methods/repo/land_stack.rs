@@ -15,6 +15,7 @@ use mononoke_api::ChangesetSpecifier;
 use mononoke_api::MononokeError;
 use pushrebase::PushrebaseConflict;
 use service::RepoLandStackExn;
+use service::RepoUpdateSubmoduleExn;
 use source_control as thrift;
 use source_control::services::source_control_service as service;
 
@@ -28,7 +29,9 @@ use crate::from_request::FromRequest;
 use crate::into_response::AsyncIntoResponseWith;
 use crate::source_control_impl::SourceControlServiceImpl;
 
-enum LandStackError {
+/// Errors from landing commits, which may be reported to the client as
+/// hook rejections or pushrebase conflicts.
+pub(super) enum LandStackError {
     Service(errors::ServiceError),
     PushrebaseConflicts(Vec<PushrebaseConflict>),
     HookRejections(Vec<HookRejection>),
@@ -86,35 +89,42 @@ fn convert_rejection(rejection: HookRejection) -> thrift::HookRejection {
     }
 }
 
-impl From<LandStackError> for RepoLandStackExn {
-    fn from(e: LandStackError) -> RepoLandStackExn {
-        match e {
-            LandStackError::Service(e) => e.into(),
-            LandStackError::HookRejections(rejections) => {
-                RepoLandStackExn::hook_rejections(thrift::HookRejectionsException {
-                    reason: reason_rejections(&rejections),
-                    rejections: rejections.into_iter().map(convert_rejection).collect(),
-                    ..Default::default()
-                })
-            }
-            LandStackError::PushrebaseConflicts(conflicts) => {
-                RepoLandStackExn::pushrebase_conflicts(thrift::PushrebaseConflictsException {
-                    reason: reason_conflicts(&conflicts),
-                    conflicts: conflicts
-                        .into_iter()
-                        .map(|c| thrift::PushrebaseConflict {
-                            left: c.left.to_string(),
-                            right: c.right.to_string(),
+macro_rules! impl_from_land_stack_error {
+    ($exn:ident) => {
+        impl From<LandStackError> for $exn {
+            fn from(e: LandStackError) -> $exn {
+                match e {
+                    LandStackError::Service(e) => e.into(),
+                    LandStackError::HookRejections(rejections) => {
+                        $exn::hook_rejections(thrift::HookRejectionsException {
+                            reason: reason_rejections(&rejections),
+                            rejections: rejections.into_iter().map(convert_rejection).collect(),
+                            ..Default::default()
+                        })
+                    }
+                    LandStackError::PushrebaseConflicts(conflicts) => {
+                        $exn::pushrebase_conflicts(thrift::PushrebaseConflictsException {
+                            reason: reason_conflicts(&conflicts),
+                            conflicts: conflicts
+                                .into_iter()
+                                .map(|c| thrift::PushrebaseConflict {
+                                    left: c.left.to_string(),
+                                    right: c.right.to_string(),
+                                    ..Default::default()
+                                })
+                                .collect(),
                             ..Default::default()
                         })
-                        .collect(),
-                    ..Default::default()
-                })
+                    }
+                }
             }
         }
-    }
+    };
 }
 
+impl_from_land_stack_error!(RepoLandStackExn);
+impl_from_land_stack_error!(RepoUpdateSubmoduleExn);
+
 impl LoggableError for LandStackError {
     fn status_and_description(&self) -> (Status, String) {
         match self {
@@ -0,0 +1,435 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use bookmarks::BookmarkKey;
+use context::CoreContext;
+use derived_data_manager::DerivableType;
+use futures::future;
+use futures::future::BoxFuture;
+use futures::future::FutureExt;
+use futures::future::Shared;
+use futures::stream::TryStreamExt;
+use mononoke_api::BookmarkFreshness;
+use mononoke_api::ChangesetContext;
+use mononoke_api::ChangesetId;
+use mononoke_api::RepoContext;
+use source_control as thrift;
+
+use crate::commit_id::map_commit_identity;
+use crate::errors;
+use crate::source_control_impl::SourceControlServiceImpl;
+
+/// How long computed statistics are served for before being recomputed.
+const REPO_STATISTICS_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of repo and bookmark pairs to keep statistics for.
+const REPO_STATISTICS_CACHE_MAX_ENTRIES: usize = 1000;
+
+/// Derived data types to report coverage for, if they are enabled.
+const COVERAGE_DERIVED_DATA_TYPES: &[DerivableType] = &[
+    DerivableType::ChangesetInfo,
+    DerivableType::Fsnodes,
+    DerivableType::HgChangesets,
+    DerivableType::SkeletonManifests,
+    DerivableType::Unodes,
+];
+
+#[derive(Clone)]
+struct CachedRepoStatistics {
+    head: ChangesetId,
+    commit_count: u64,
+    computed: Instant,
+    response: thrift::RepoStatisticsResponse,
+}
+
+type RepoStatisticsKey = (String, String);
+
+type RepoStatisticsRefresh =
+    Shared<BoxFuture<'static, Result<CachedRepoStatistics, errors::ServiceError>>>;
+
+#[derive(Default)]
+struct RepoStatisticsCacheState {
+    entries: HashMap<RepoStatisticsKey, CachedRepoStatistics>,
+    refreshing: HashMap<RepoStatisticsKey, RepoStatisticsRefresh>,
+}
+
+/// Statistics computed for each repo and bookmark.  Counting commits is
+/// expensive, so statistics are computed in the background, at most once at
+/// a time for each repo and bookmark, and when the bookmark moves forwards
+/// only the new commits are counted.
+#[derive(Clone)]
+pub(crate) struct RepoStatisticsCache {
+    state: Arc<Mutex<RepoStatisticsCacheState>>,
+    max_entries: usize,
+}
+
+impl Default for RepoStatisticsCache {
+    fn default() -> Self {
+        Self::new(REPO_STATISTICS_CACHE_MAX_ENTRIES)
+    }
+}
+
+impl RepoStatisticsCache {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RepoStatisticsCacheState::default())),
+            max_entries,
+        }
+    }
+
+    /// Get statistics for a key whose head is now `head`.  Up to date
+    /// statistics are returned immediately.  Out of date statistics are also
+    /// returned immediately, while they are refreshed in the background.  If
+    /// there are no statistics yet, waits for them to be computed.
+    async fn get_or_refresh<Fut>(
+        &self,
+        key: RepoStatisticsKey,
+        head: ChangesetId,
+        compute: impl FnOnce(Option<CachedRepoStatistics>) -> Fut,
+    ) -> Result<CachedRepoStatistics, errors::ServiceError>
+    where
+        Fut: Future<Output = Result<CachedRepoStatistics, errors::ServiceError>> + Send + 'static,
+    {
+        let refresh = {
+            let mut state = self.state.lock().expect("lock poisoned");
+            let cached = state.entries.get(&key).cloned();
+            if let Some(cached) = &cached {
+                if cached.head == head && cached.computed.elapsed() < REPO_STATISTICS_CACHE_TTL {
+                    return Ok(cached.clone());
+                }
+            }
+            let refresh = match state.refreshing.get(&key) {
+                Some(refresh) => refresh.clone(),
+                None => {
+                    let cache = self.clone();
+                    let refresh_key = key.clone();
+                    let fut = compute(cached.clone());
+                    let refresh = async move {
+                        let result = fut.await;
+                        cache.finish_refresh(refresh_key, &result);
+                        result
+                    }
+                    .boxed()
+                    .shared();
+                    // Run the refresh to completion even if the request that
+                    // started it goes away.
+                    tokio::spawn(refresh.clone());
+                    state.refreshing.insert(key, refresh.clone());
+                    refresh
+                }
+            };
+            match cached {
+                Some(cached) => return Ok(cached),
+                None => refresh,
+            }
+        };
+        refresh.await
+    }
+
+    fn finish_refresh(
+        &self,
+        key: RepoStatisticsKey,
+        result: &Result<CachedRepoStatistics, errors::ServiceError>,
+    ) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        state.refreshing.remove(&key);
+        if let Ok(statistics) = result {
+            if !state.entries.contains_key(&key) && state.entries.len() >= self.max_entries {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, statistics)| statistics.computed)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+            state.entries.insert(key, statistics.clone());
+        }
+    }
+}
+
+/// Count the ancestors of `changeset`, starting from a previous count if the
+/// previously counted head is an ancestor.
+async fn count_commits(
+    repo: &RepoContext,
+    changeset: &ChangesetContext,
+    previous: Option<&CachedRepoStatistics>,
+) -> Result<u64, errors::ServiceError> {
+    let (base_count, excludes) = match previous {
+        Some(previous) if changeset.id() == previous.head => return Ok(previous.commit_count),
+        Some(previous) => match repo.changeset(previous.head).await? {
+            Some(previous_changeset)
+                if previous_changeset.is_ancestor_of(changeset.id()).await? =>
+            {
+                (previous.commit_count, vec![previous.head])
+            }
+            _ => (0, vec![]),
+        },
+        None => (0, vec![]),
+    };
+    let new_count = repo
+        .difference_of_unions_of_ancestors(vec![changeset.id()], excludes)
+        .await?
+        .try_fold(0u64, |count, _| async move { Ok(count + 1) })
+        .await?;
+    Ok(base_count + new_count)
+}
+
+async fn compute_repo_statistics(
+    repo: &RepoContext,
+    changeset: &ChangesetContext,
+    commit_count: u64,
+) -> Result<thrift::RepoStatisticsResponse, errors::ServiceError> {
+    let bookmark_count = repo
+        .list_bookmarks(false, None, None, None)
+        .await?
+        .try_fold(0u64, |count, _| async move { Ok(count + 1) })
+        .await?;
+
+    let (file_count, total_file_size) = match changeset.root().await?.tree().await? {
+        Some(tree) => {
+            let summary = tree.summary().await?;
+            (
+                summary.descendant_files_count,
+                summary.descendant_files_total_size,
+            )
+        }
+        None => (0, 0),
+    };
+
+    let limit = thrift::consts::REPO_STATISTICS_UNDERIVED_COUNT_LIMIT as u64;
+    let underived_counts =
+        future::try_join_all(COVERAGE_DERIVED_DATA_TYPES.iter().map(|derivable_type| {
+            repo.count_underived(derivable_type.clone(), changeset.id(), limit)
+        }))
+        .await?;
+    let mut derived_data_coverage = BTreeMap::new();
+    for (derivable_type, underived_count) in COVERAGE_DERIVED_DATA_TYPES
+        .iter()
+        .zip(underived_counts.into_iter())
+    {
+        if let Some(underived_count) = underived_count {
+            let coverage_percent = if commit_count == 0 {
+                100.0
+            } else {
+                100.0 * commit_count.saturating_sub(underived_count) as f64 / commit_count as f64
+            };
+            derived_data_coverage.insert(
+                derivable_type.to_string(),
+                thrift::DerivedDataCoverage {
+                    underived_count: underived_count as i64,
+                    coverage_percent,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let computed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(errors::internal_error)?
+        .as_secs() as i64;
+
+    Ok(thrift::RepoStatisticsResponse {
+        commit_count: commit_count as i64,
+        bookmark_count: bookmark_count as i64,
+        file_count: file_count as i64,
+        total_file_size: total_file_size as i64,
+        derived_data_coverage,
+        computed_at,
+        ..Default::default()
+    })
+}
+
+impl SourceControlServiceImpl {
+    /// Size and derivation statistics for a repo at a bookmark.
+    pub(crate) async fn repo_statistics(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoStatisticsParams,
+    ) -> Result<thrift::RepoStatisticsResponse, errors::ServiceError> {
+        let repo = self.repo(ctx, &repo).await?;
+        let bookmark = BookmarkKey::new(&params.bookmark).map_err(errors::invalid_request)?;
+        let changeset = repo
+            .resolve_bookmark(&bookmark, BookmarkFreshness::MaybeStale)
+            .await?
+            .ok_or_else(|| {
+                errors::invalid_request(format!("bookmark not found: {}", params.bookmark))
+            })?;
+
+        let key = (repo.name().to_string(), params.bookmark.clone());
+        let statistics = self
+            .repo_statistics_cache
+            .get_or_refresh(key, changeset.id(), {
+                let repo = repo.clone();
+                let changeset = changeset.clone();
+                move |previous| async move {
+                    let commit_count = count_commits(&repo, &changeset, previous.as_ref()).await?;
+                    let response = compute_repo_statistics(&repo, &changeset, commit_count).await?;
+                    Ok(CachedRepoStatistics {
+                        head: changeset.id(),
+                        commit_count,
+                        computed: Instant::now(),
+                        response,
+                    })
+                }
+            })
+            .await?;
+
+        // The statistics may be for an earlier commit while the statistics
+        // for the bookmark's current commit are computed.
+        let changeset = if statistics.head == changeset.id() {
+            changeset
+        } else {
+            repo.changeset(statistics.head)
+                .await?
+                .ok_or_else(|| errors::internal_error("statistics commit not found"))?
+        };
+        Ok(thrift::RepoStatisticsResponse {
+            ids: map_commit_identity(&changeset, &params.identity_schemes).await?,
+            ..statistics.response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    fn key(bookmark: &str) -> RepoStatisticsKey {
+        ("repo".to_string(), bookmark.to_string())
+    }
+
+    fn head(n: u8) -> ChangesetId {
+        ChangesetId::from_bytes([n; 32]).expect("valid changeset id")
+    }
+
+    fn statistics(head: ChangesetId, commit_count: u64) -> CachedRepoStatistics {
+        CachedRepoStatistics {
+            head,
+            commit_count,
+            computed: Instant::now(),
+            response: thrift::RepoStatisticsResponse {
+                commit_count: commit_count as i64,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_shared_and_in_background() -> Result<(), errors::ServiceError> {
+        let cache = RepoStatisticsCache::default();
+        let computed = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = oneshot::channel::<()>();
+        let receiver = receiver.shared();
+
+        // Concurrent misses share one computation.
+        let compute = |n: u64| {
+            let computed = computed.clone();
+            let receiver = receiver.clone();
+            move |previous: Option<CachedRepoStatistics>| async move {
+                assert!(previous.is_none());
+                computed.fetch_add(1, Ordering::SeqCst);
+                receiver.await.expect("sender kept");
+                Ok(statistics(head(1), n))
+            }
+        };
+        let first = cache.get_or_refresh(key("main"), head(1), compute(1));
+        let second = cache.get_or_refresh(key("main"), head(1), compute(2));
+        sender.send(()).expect("receiver kept");
+        let (first, second) = futures::try_join!(first, second)?;
+        assert_eq!(first.commit_count, 1);
+        assert_eq!(second.commit_count, 1);
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+
+        // Up to date statistics are served from the cache.
+        let cached = cache
+            .get_or_refresh(key("main"), head(1), |_| async {
+                Err(errors::internal_error("statistics are up to date").into())
+            })
+            .await?;
+        assert_eq!(cached.commit_count, 1);
+
+        // When the bookmark moves, the previous statistics are served while
+        // the new ones are computed from them.
+        let (sender, receiver) = oneshot::channel::<()>();
+        let stale = cache
+            .get_or_refresh(key("main"), head(2), move |previous| async move {
+                let previous = previous.expect("previous statistics");
+                receiver.await.expect("sender kept");
+                Ok(statistics(head(2), previous.commit_count + 1))
+            })
+            .await?;
+        assert_eq!(stale.head, head(1));
+        sender.send(()).expect("receiver kept");
+        loop {
+            let refreshed = cache
+                .get_or_refresh(key("main"), head(2), |previous| async move {
+                    Ok(previous.expect("previous statistics"))
+                })
+                .await?;
+            if refreshed.head == head(2) {
+                assert_eq!(refreshed.commit_count, 2);
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_bounded() -> Result<(), errors::ServiceError> {
+        let cache = RepoStatisticsCache::new(2);
+        for (n, bookmark) in ["a", "b", "c"].into_iter().enumerate() {
+            cache
+                .get_or_refresh(key(bookmark), head(1), move |_| async move {
+                    Ok(statistics(head(1), n as u64))
+                })
+                .await?;
+        }
+        let state = cache.state.lock().expect("lock poisoned");
+        assert_eq!(state.entries.len(), 2);
+        assert!(!state.entries.contains_key(&key("a")));
+        assert!(state.refreshing.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_not_cached() -> Result<(), errors::ServiceError> {
+        let cache = RepoStatisticsCache::default();
+        let result = cache
+            .get_or_refresh(key("main"), head(1), |_| async {
+                Err(errors::internal_error("failed").into())
+            })
+            .await;
+        assert!(result.is_err());
+        let statistics = cache
+            .get_or_refresh(key("main"), head(1), |_| async {
+                Ok(statistics(head(1), 1))
+            })
+            .await?;
+        assert_eq!(statistics.commit_count, 1);
+        Ok(())
+    }
+}
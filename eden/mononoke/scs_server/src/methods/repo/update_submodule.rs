@@ -0,0 +1,265 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::BTreeMap;
+
+use bookmarks::BookmarkKey;
+use bookmarks_movement::BookmarkKindRestrictions;
+use bytes::Bytes;
+use context::CoreContext;
+use hooks::PushAuthoredBy;
+use mononoke_api::BookmarkFreshness;
+use mononoke_api::ChangesetContext;
+use mononoke_api::CreateChange;
+use mononoke_api::CreateChangeFile;
+use mononoke_api::CreateInfo;
+use mononoke_api::FileId;
+use mononoke_api::FileType;
+use mononoke_api::MononokePath;
+use mononoke_api::PathEntry;
+use mononoke_api::RepoContext;
+use mononoke_types::hash::GitSha1;
+use source_control as thrift;
+use source_control::services::source_control_service as service;
+
+use super::land_stack::LandStackError;
+use crate::errors;
+use crate::errors::LoggableError;
+use crate::errors::ServiceErrorResultExt;
+use crate::from_request::convert_pushvars;
+use crate::from_request::FromRequest;
+use crate::into_response::AsyncIntoResponseWith;
+use crate::source_control_impl::SourceControlServiceImpl;
+
+impl SourceControlServiceImpl {
+    /// Work out the change that points `path` at `target` in `base`.
+    async fn convert_update_submodule_target(
+        repo: &RepoContext,
+        base: &ChangesetContext,
+        path: &MononokePath,
+        target: thrift::RepoUpdateSubmoduleTarget,
+    ) -> Result<CreateChange, errors::ServiceError> {
+        let change = match target {
+            thrift::RepoUpdateSubmoduleTarget::content_id(id) => {
+                let file_id = FileId::from_request(&id)?;
+                let file = repo
+                    .file(file_id)
+                    .await?
+                    .ok_or_else(|| errors::file_not_found(file_id.to_string()))?;
+                let file_type = match base.path_with_content(path.clone()).await?.entry().await? {
+                    PathEntry::NotPresent => FileType::Regular,
+                    PathEntry::File(_file, file_type) => file_type,
+                    PathEntry::Tree(_tree) => {
+                        return Err(errors::invalid_request(format!(
+                            "path '{}' is a directory",
+                            path
+                        ))
+                        .into());
+                    }
+                };
+                CreateChangeFile::Existing {
+                    file_id: file.id().await?,
+                    file_type,
+                    maybe_size: None,
+                }
+            }
+            thrift::RepoUpdateSubmoduleTarget::submodule_commit(commit) => {
+                // Submodule entries store the binary commit hash as their
+                // content.
+                GitSha1::from_request(&commit)?;
+                CreateChangeFile::New {
+                    bytes: Bytes::from(commit),
+                    file_type: FileType::GitSubmodule,
+                }
+            }
+            thrift::RepoUpdateSubmoduleTarget::UnknownField(t) => {
+                return Err(errors::invalid_request(format!(
+                    "submodule target type not supported: {}",
+                    t
+                ))
+                .into());
+            }
+        };
+        Ok(CreateChange::Tracked(change, None))
+    }
+
+    async fn impl_repo_update_submodule(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoUpdateSubmoduleParams,
+    ) -> Result<thrift::RepoUpdateSubmoduleResponse, LandStackError> {
+        let push_authored_by = if params.service_identity.is_some() {
+            PushAuthoredBy::Service
+        } else {
+            PushAuthoredBy::User
+        };
+        let repo = self
+            .repo_for_service(ctx, &repo, params.service_identity)
+            .await?;
+        let bookmark = BookmarkKey::new(&params.bookmark).map_err(errors::invalid_request)?;
+        let base = repo
+            .resolve_bookmark(&bookmark, BookmarkFreshness::MostRecent)
+            .await
+            .context("failed to resolve bookmark")?
+            .ok_or_else(|| {
+                errors::invalid_request(format!("bookmark not found: {}", params.bookmark))
+            })?;
+        let path = MononokePath::from_request(&params.path)?;
+        let change =
+            Self::convert_update_submodule_target(&repo, &base, &path, params.target).await?;
+        let info = CreateInfo::from_request(&params.info)?;
+
+        let changeset = repo
+            .create_changeset(
+                vec![base.id()],
+                info,
+                BTreeMap::from([(path, change)]),
+                None,
+            )
+            .await?;
+
+        let pushvars = convert_pushvars(params.pushvars);
+        let bookmark_restrictions =
+            BookmarkKindRestrictions::from_request(&params.bookmark_restrictions)?;
+        let pushrebase_outcome = repo
+            .land_stack(
+                &params.bookmark,
+                changeset.id(),
+                base.id(),
+                pushvars.as_ref(),
+                bookmark_restrictions,
+                push_authored_by,
+            )
+            .await?
+            .into_response_with(&(repo.clone(), params.identity_schemes, None))
+            .await?;
+
+        Ok(thrift::RepoUpdateSubmoduleResponse {
+            pushrebase_outcome,
+            ..Default::default()
+        })
+    }
+
+    /// Update a single path to point at new content or a new submodule
+    /// commit, and land the change onto a bookmark.
+    pub(crate) async fn repo_update_submodule(
+        &self,
+        ctx: CoreContext,
+        repo: thrift::RepoSpecifier,
+        params: thrift::RepoUpdateSubmoduleParams,
+    ) -> Result<
+        thrift::RepoUpdateSubmoduleResponse,
+        impl Into<service::RepoUpdateSubmoduleExn> + LoggableError,
+    > {
+        self.impl_repo_update_submodule(ctx, repo, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use fbinit::FacebookInit;
+    use mononoke_api::MononokeError;
+    use mononoke_api::Repo;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+
+    async fn convert(
+        repo: &RepoContext,
+        base: &ChangesetContext,
+        path: &str,
+        target: thrift::RepoUpdateSubmoduleTarget,
+    ) -> Result<Option<CreateChangeFile>, errors::ServiceError> {
+        let path = MononokePath::try_from(path)?;
+        let change =
+            SourceControlServiceImpl::convert_update_submodule_target(repo, base, &path, target)
+                .await?;
+        match change {
+            CreateChange::Tracked(file, None) => Ok(Some(file)),
+            _ => Ok(None),
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_convert_update_submodule_target(
+        fb: FacebookInit,
+    ) -> Result<(), errors::ServiceError> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo: Repo = test_repo_factory::build_empty(fb)
+            .await
+            .map_err(MononokeError::from)?;
+        let cs_id = CreateCommitContext::new_root(&ctx, &repo)
+            .add_file_with_type("exec", "content", FileType::Executable)
+            .add_file("dir/file", "content")
+            .commit()
+            .await
+            .map_err(MononokeError::from)?;
+        let repo = RepoContext::new_test(ctx, Arc::new(repo)).await?;
+        let base = repo
+            .changeset(cs_id)
+            .await?
+            .ok_or_else(|| errors::internal_error("commit not found"))?;
+        let file_id = base
+            .path_with_content("exec")
+            .await?
+            .file()
+            .await?
+            .ok_or_else(|| errors::internal_error("file not found"))?
+            .id()
+            .await?;
+        let content_id =
+            || thrift::RepoUpdateSubmoduleTarget::content_id(file_id.as_ref().to_vec());
+
+        // Existing files keep their type.
+        match convert(&repo, &base, "exec", content_id()).await? {
+            Some(CreateChangeFile::Existing {
+                file_id: id,
+                file_type,
+                ..
+            }) => {
+                assert_eq!(id, file_id);
+                assert_eq!(file_type, FileType::Executable);
+            }
+            _ => panic!("expected existing file"),
+        }
+
+        // New paths are created as regular files.
+        match convert(&repo, &base, "new", content_id()).await? {
+            Some(CreateChangeFile::Existing { file_type, .. }) => {
+                assert_eq!(file_type, FileType::Regular);
+            }
+            _ => panic!("expected existing file"),
+        }
+
+        // Directories can't be replaced.
+        assert!(convert(&repo, &base, "dir", content_id()).await.is_err());
+
+        // Unknown content can't be used.
+        let unknown = thrift::RepoUpdateSubmoduleTarget::content_id(vec![0; 32]);
+        assert!(convert(&repo, &base, "exec", unknown).await.is_err());
+
+        // Submodule commits are stored as the binary hash.
+        let commit = vec![0x12; 20];
+        let target = thrift::RepoUpdateSubmoduleTarget::submodule_commit(commit.clone());
+        match convert(&repo, &base, "submodule", target).await? {
+            Some(CreateChangeFile::New { bytes, file_type }) => {
+                assert_eq!(bytes, Bytes::from(commit));
+                assert_eq!(file_type, FileType::GitSubmodule);
+            }
+            _ => panic!("expected new file"),
+        }
+
+        // Submodule commits must be valid git hashes.
+        let target = thrift::RepoUpdateSubmoduleTarget::submodule_commit(vec![0x12; 3]);
+        assert!(convert(&repo, &base, "submodule", target).await.is_err());
+
+        Ok(())
+    }
+}
@@ -5,14 +5,69 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
 use context::CoreContext;
+use fbthrift::compact_protocol;
+use futures::stream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
+use mononoke_api::FileType;
+use mononoke_api::MononokePath;
+use mononoke_api::TreeEntry;
+use regex::Regex;
 use source_control as thrift;
 
 use crate::errors;
 use crate::from_request::check_range_and_convert;
+use crate::from_request::FromRequest;
 use crate::into_response::IntoResponse;
+use crate::methods::commit_path::link_target;
 use crate::source_control_impl::SourceControlServiceImpl;
 
+/// Serialize the trees fetched by tree_prefetch and compress them with zstd.
+fn compress_trees(
+    trees: BTreeMap<String, Vec<thrift::TreeEntry>>,
+) -> Result<Vec<u8>, errors::ServiceError> {
+    let serialized = compact_protocol::serialize(&thrift::TreePrefetchTrees {
+        trees,
+        ..Default::default()
+    });
+    Ok(zstd::encode_all(serialized.as_ref(), 0).map_err(errors::internal_error)?)
+}
+
+/// Matches the entries of a tree by their full path.
+struct PathFilter {
+    /// The path of the tree, with a trailing slash unless it is the root.
+    prefix: String,
+    regex: Regex,
+}
+
+impl PathFilter {
+    fn new(tree: &thrift::TreeSpecifier, path_regex: &str) -> Result<Self, errors::ServiceError> {
+        let path = match tree {
+            thrift::TreeSpecifier::by_commit_path(commit_path) => Some(&commit_path.path),
+            thrift::TreeSpecifier::by_id(tree_id) => tree_id.path_hint.as_ref(),
+            _ => None,
+        }
+        .ok_or_else(|| errors::invalid_request("path_regex requires the path of the tree"))?;
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path.trim_end_matches('/'))
+        };
+        let regex = Regex::new(path_regex).map_err(|e| {
+            errors::invalid_request(format!("invalid path_regex '{}': {}", path_regex, e))
+        })?;
+        Ok(Self { prefix, regex })
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(&format!("{}{}", self.prefix, name))
+    }
+}
+
 impl SourceControlServiceImpl {
     /// Determine whether a tree exists.
     pub(crate) async fn tree_exists(
@@ -32,22 +87,50 @@ impl SourceControlServiceImpl {
         tree: thrift::TreeSpecifier,
         params: thrift::TreeListParams,
     ) -> Result<thrift::TreeListResponse, errors::ServiceError> {
+        let path_filter = params
+            .path_regex
+            .as_ref()
+            .map(|path_regex| PathFilter::new(&tree, path_regex))
+            .transpose()?;
         let (_repo, tree) = self.repo_tree(ctx, &tree).await?;
         let offset: usize = check_range_and_convert("offset", params.offset, 0..)?;
-        let limit: usize = check_range_and_convert(
-            "limit",
-            params.limit,
-            0..=source_control::TREE_LIST_MAX_LIMIT,
-        )?;
+        let limit: usize = check_range_and_convert("limit", params.limit, 0..)?;
         if let Some(tree) = tree {
             let summary = tree.summary().await?;
             let entries = tree
                 .list()
                 .await?
+                .filter(|(name, _)| {
+                    path_filter
+                        .as_ref()
+                        .map_or(true, |path_filter| path_filter.matches(name))
+                })
                 .skip(offset)
-                .take(limit)
-                .map(IntoResponse::into_response)
-                .collect();
+                .take(limit);
+            let entries = if params.include_link_target {
+                stream::iter(entries)
+                    .map(|(name, entry)| {
+                        let tree = &tree;
+                        async move {
+                            let link_file = match &entry {
+                                TreeEntry::File(file) if *file.file_type() == FileType::Symlink => {
+                                    Some(tree.file(file))
+                                }
+                                _ => None,
+                            };
+                            let mut response: thrift::TreeEntry = (name, entry).into_response();
+                            if let Some(file) = link_file {
+                                response.link_target = Some(link_target(&file).await?);
+                            }
+                            Ok::<_, errors::ServiceError>(response)
+                        }
+                    })
+                    .buffered(100)
+                    .try_collect()
+                    .await?
+            } else {
+                entries.map(IntoResponse::into_response).collect()
+            };
             let response = thrift::TreeListResponse {
                 entries,
                 count: (summary.child_files_count + summary.child_dirs_count) as i64,
@@ -63,4 +146,153 @@ impl SourceControlServiceImpl {
             })
         }
     }
+
+    /// List the contents of a directory and the directories beneath it.
+    pub(crate) async fn tree_list_recursive(
+        &self,
+        ctx: CoreContext,
+        tree: thrift::TreeSpecifier,
+        params: thrift::TreeListRecursiveParams,
+    ) -> Result<thrift::TreeListRecursiveResponse, errors::ServiceError> {
+        let (_repo, tree) = self.repo_tree(ctx, &tree).await?;
+        let depth: usize = check_range_and_convert("depth", params.depth, 1..)?;
+        let limit: usize = check_range_and_convert("limit", params.limit, 0..)?;
+        let (entries, truncated) = match tree {
+            Some(tree) => tree.list_recursive(depth, limit).await?,
+            // Listing a path that is not a directory just returns an empty list.
+            None => (Vec::new(), false),
+        };
+        let entries = entries
+            .into_iter()
+            .map(|(path, entry)| {
+                let name = path.as_mpath().map_or_else(String::new, |path| {
+                    String::from_utf8_lossy(path.basename().as_ref()).to_string()
+                });
+                thrift::TreeListRecursiveEntry {
+                    path: path.to_string(),
+                    entry: (name, entry).into_response(),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        Ok(thrift::TreeListRecursiveResponse {
+            entries,
+            truncated,
+            ..Default::default()
+        })
+    }
+
+    /// List the directories beneath a set of path prefixes in a single
+    /// request, so that clients can warm their caches without walking the
+    /// tree one directory at a time.
+    pub(crate) async fn tree_prefetch(
+        &self,
+        ctx: CoreContext,
+        commit: thrift::CommitSpecifier,
+        params: thrift::TreePrefetchParams,
+    ) -> Result<thrift::TreePrefetchResponse, errors::ServiceError> {
+        let (_repo, changeset) = self.repo_changeset(ctx, &commit).await?;
+        let depth: usize = check_range_and_convert("depth", params.depth, 1..)?;
+        let mut remaining: usize = check_range_and_convert("limit", params.limit, 0..)?;
+        let mut trees = BTreeMap::new();
+        // Overlapping prefixes would list some trees more than once, so
+        // trees that have been listed are excluded from later prefixes.
+        let mut listed = HashSet::new();
+        let mut truncated = false;
+        for prefix in params.path_prefixes.iter() {
+            let path = changeset
+                .path_with_content(MononokePath::from_request(prefix)?)
+                .await?;
+            let (subtrees, prefix_truncated) =
+                path.list_subtrees(depth, remaining, &listed).await?;
+            for (path, entries) in subtrees {
+                remaining -= entries.len();
+                let entries: Vec<thrift::TreeEntry> = entries
+                    .into_iter()
+                    .map(IntoResponse::into_response)
+                    .collect();
+                trees.insert(path.to_string(), entries);
+                listed.insert(path);
+            }
+            if prefix_truncated {
+                truncated = true;
+                break;
+            }
+        }
+        if params.compress {
+            let compressed_trees = tokio::task::spawn_blocking(move || compress_trees(trees))
+                .await
+                .map_err(errors::internal_error)??;
+            Ok(thrift::TreePrefetchResponse {
+                compressed_trees: Some(compressed_trees),
+                truncated,
+                ..Default::default()
+            })
+        } else {
+            Ok(thrift::TreePrefetchResponse {
+                trees,
+                truncated,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_filter() -> Result<(), anyhow::Error> {
+        let error = |_| anyhow::anyhow!("invalid request");
+        let by_commit_path = |path: &str| {
+            thrift::TreeSpecifier::by_commit_path(thrift::CommitPathSpecifier {
+                path: path.to_string(),
+                ..Default::default()
+            })
+        };
+        let by_id = |path_hint: Option<&str>| {
+            thrift::TreeSpecifier::by_id(thrift::TreeIdSpecifier {
+                path_hint: path_hint.map(str::to_string),
+                ..Default::default()
+            })
+        };
+
+        let filter =
+            PathFilter::new(&by_commit_path("dir/sub"), r"^dir/sub/.*\.rs$").map_err(error)?;
+        assert!(filter.matches("lib.rs"));
+        assert!(!filter.matches("lib.py"));
+
+        // Entries of the root tree have no directory prefix.
+        let filter = PathFilter::new(&by_commit_path(""), "^lib").map_err(error)?;
+        assert!(filter.matches("lib.rs"));
+
+        let filter = PathFilter::new(&by_id(Some("dir/")), "^dir/lib").map_err(error)?;
+        assert!(filter.matches("lib.rs"));
+        assert!(!filter.matches("src"));
+
+        // By-id trees need a path hint, and the regex must be valid.
+        assert!(PathFilter::new(&by_id(None), ".*").is_err());
+        assert!(PathFilter::new(&by_commit_path("dir"), "(").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_trees() -> Result<(), anyhow::Error> {
+        let entry = thrift::TreeEntry {
+            name: "file".to_string(),
+            ..Default::default()
+        };
+        let trees = BTreeMap::from([
+            (String::new(), vec![entry.clone()]),
+            ("dir".to_string(), vec![entry.clone(), entry]),
+        ]);
+        let compressed =
+            compress_trees(trees.clone()).map_err(|_| anyhow::anyhow!("compression failed"))?;
+        let decompressed = zstd::decode_all(compressed.as_slice())?;
+        let decoded: thrift::TreePrefetchTrees =
+            compact_protocol::deserialize(decompressed.as_slice())?;
+        assert_eq!(decoded.trees, trees);
+        Ok(())
+    }
 }
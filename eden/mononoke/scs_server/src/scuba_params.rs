@@ -38,6 +38,25 @@ impl AddScubaParams for BTreeSet<thrift::CommitIdentityScheme> {
 
 impl AddScubaParams for thrift::ListReposParams {}
 
+impl AddScubaParams for thrift::ServiceLimitsParams {}
+
+impl AddScubaParams for thrift::ServiceCapabilitiesParams {}
+
+impl AddScubaParams for thrift::ServiceUsageParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_window_secs", self.window_secs);
+        if let Some(limit) = self.limit {
+            scuba.add("param_limit", limit);
+        }
+    }
+}
+
+impl AddScubaParams for thrift::AsyncRequestToken {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_token", self.id.as_str());
+    }
+}
+
 impl AddScubaParams for thrift::RepoCreateCommitParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add(
@@ -112,6 +131,20 @@ impl AddScubaParams for thrift::RepoCreateBookmarkParams {
     }
 }
 
+impl AddScubaParams for thrift::RepoCreateBranchFromCommitParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+        scuba.add("commit", self.target.to_string());
+        scuba.add(
+            "param_allowed_pushers",
+            self.allowed_pushers.iter().collect::<ScubaValue>(),
+        );
+        if let Some(service_identity) = self.service_identity.as_deref() {
+            scuba.add("service_identity", service_identity);
+        }
+    }
+}
+
 impl AddScubaParams for thrift::RepoMoveBookmarkParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("bookmark_name", self.bookmark.as_str());
@@ -123,6 +156,9 @@ impl AddScubaParams for thrift::RepoMoveBookmarkParams {
             "param_allow_non_fast_forward_move",
             self.allow_non_fast_forward_move as i32,
         );
+        if let Some(must_include) = &self.must_include {
+            scuba.add("param_must_include", must_include.to_string());
+        }
         if let Some(service_identity) = self.service_identity.as_deref() {
             scuba.add("service_identity", service_identity);
         }
@@ -162,6 +198,17 @@ impl AddScubaParams for thrift::RepoLandStackParams {
     }
 }
 
+impl AddScubaParams for thrift::RepoUpdateSubmoduleParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+        scuba.add("path", self.path.as_str());
+        self.identity_schemes.add_scuba_params(scuba);
+        if let Some(service_identity) = self.service_identity.as_deref() {
+            scuba.add("service_identity", service_identity);
+        }
+    }
+}
+
 impl AddScubaParams for thrift::RepoListBookmarksParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_include_scratch", self.include_scratch as i32);
@@ -174,10 +221,40 @@ impl AddScubaParams for thrift::RepoListBookmarksParams {
     }
 }
 
+impl AddScubaParams for thrift::RepoListRecentCommitsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark_name.as_str());
+        scuba.add("param_limit", self.limit);
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
 impl AddScubaParams for thrift::RepoResolveBookmarkParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("bookmark_name", self.bookmark_name.as_str());
         self.identity_schemes.add_scuba_params(scuba);
+        scuba.add("param_bypass_cache", self.bypass_cache);
+    }
+}
+
+impl AddScubaParams for thrift::RepoResolveGlobalrevParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_globalrev", self.globalrev);
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
+impl AddScubaParams for thrift::RepoLatestGlobalrevParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
+impl AddScubaParams for thrift::RepoCommitsInGlobalrevRangeParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_start", self.start);
+        scuba.add("param_end", self.end);
+        self.identity_schemes.add_scuba_params(scuba);
     }
 }
 
@@ -198,6 +275,37 @@ impl AddScubaParams for thrift::RepoResolveCommitPrefixParams {
 
 impl AddScubaParams for thrift::RepoStackInfoParams {}
 
+impl AddScubaParams for thrift::RepoPathMultiCommitInfoParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_path", self.path.as_str());
+        scuba.add(
+            "param_commits",
+            self.commits
+                .iter()
+                .map(CommitIdExt::to_string)
+                .collect::<ScubaValue>(),
+        );
+    }
+}
+
+impl AddScubaParams for thrift::RepoStatisticsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+    }
+}
+
+impl AddScubaParams for thrift::RepoFileMetadataParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_id_count", self.ids.len());
+    }
+}
+
+impl AddScubaParams for thrift::RepoListHookConfigsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+    }
+}
+
 impl AddScubaParams for thrift::RepoPrepareCommitsParams {}
 
 impl AddScubaParams for thrift::RepoUploadFileContentParams {
@@ -206,6 +314,25 @@ impl AddScubaParams for thrift::RepoUploadFileContentParams {
     }
 }
 
+impl AddScubaParams for thrift::RepoRunHookOnHistoryParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_hook_name", self.hook_name.as_str());
+        scuba.add("bookmark_name", self.bookmark.as_str());
+        scuba.add("param_limit", self.limit);
+        self.identity_schemes.add_scuba_params(scuba);
+        if let Some(service_identity) = self.service_identity.as_deref() {
+            scuba.add("service_identity", service_identity);
+        }
+    }
+}
+
+impl AddScubaParams for thrift::RepoValidateCommitMessageParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+        scuba.add("param_message_len", self.message.len());
+    }
+}
+
 impl AddScubaParams for thrift::CommitCompareParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         if let Some(other_commit_id) = self.other_commit_id.as_ref() {
@@ -221,6 +348,13 @@ impl AddScubaParams for thrift::CommitCompareParams {
             scuba.add("param_limit", ordered_params.limit);
         }
         scuba.add("param_skip_copies_renames", self.skip_copies_renames as i32);
+        scuba.add(
+            "param_mode_only_changes",
+            self.mode_only_changes.to_string(),
+        );
+        if let Some(threshold) = self.copy_similarity_threshold {
+            scuba.add("param_copy_similarity_threshold", threshold);
+        }
         self.identity_schemes.add_scuba_params(scuba);
     }
 }
@@ -233,6 +367,15 @@ impl AddScubaParams for thrift::CommitFileDiffsParams {
         );
         scuba.add("param_format", self.format.to_string());
         scuba.add("param_context", self.context);
+        if let Some(file_size_limit) = self.file_size_limit {
+            scuba.add("param_file_size_limit", file_size_limit);
+        }
+        if let Some(page_start) = self.page_start {
+            scuba.add("param_page_start", page_start);
+        }
+        if let Some(page_size) = self.page_size {
+            scuba.add("param_page_size", page_size);
+        }
     }
 }
 
@@ -257,6 +400,21 @@ impl AddScubaParams for thrift::CommitFindFilesParams {
 impl AddScubaParams for thrift::CommitInfoParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         self.identity_schemes.add_scuba_params(scuba);
+        scuba.add(
+            "param_include_file_count_delta",
+            self.include_file_count_delta as i32,
+        );
+    }
+}
+
+impl AddScubaParams for thrift::CommitPhaseParams {}
+
+impl AddScubaParams for thrift::CommitDerivedDataInfoParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add(
+            "param_derived_data_types",
+            self.derived_data_types.iter().collect::<ScubaValue>(),
+        );
     }
 }
 
@@ -266,6 +424,16 @@ impl AddScubaParams for thrift::CommitIsAncestorOfParams {
     }
 }
 
+impl AddScubaParams for thrift::CommitContainedInBookmarksParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add(
+            "param_bookmark_prefixes",
+            self.bookmark_prefixes.iter().collect::<ScubaValue>(),
+        );
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
 impl AddScubaParams for thrift::CommitCommonBaseWithParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("other_commit", self.other_commit_id.to_string());
@@ -280,6 +448,23 @@ impl AddScubaParams for thrift::CommitLookupParams {
 
 impl AddScubaParams for thrift::CommitLookupPushrebaseHistoryParams {}
 
+impl AddScubaParams for thrift::CommitTranslateIdsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_from_scheme", self.from_scheme.to_string());
+        scuba.add("param_commit_count", self.commit_ids.len());
+        self.to_schemes.add_scuba_params(scuba);
+    }
+}
+
+impl AddScubaParams for thrift::CommitMultipleLookupParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_commit_count", self.commit_ids.len());
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
+impl AddScubaParams for thrift::CommitMutationsParams {}
+
 impl AddScubaParams for thrift::CommitHistoryParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_format", self.format.to_string());
@@ -300,6 +485,9 @@ impl AddScubaParams for thrift::CommitHistoryParams {
                 exclude_changeset_and_ancestors.to_string(),
             );
         }
+        if let Some(order) = &self.order {
+            scuba.add("param_order", order.to_string());
+        }
         self.identity_schemes.add_scuba_params(scuba);
     }
 }
@@ -316,12 +504,33 @@ impl AddScubaParams for thrift::CommitListDescendantBookmarksParams {
     }
 }
 
+impl AddScubaParams for thrift::CommitListDescendantsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add(
+            "param_bookmarks",
+            self.bookmarks.iter().collect::<ScubaValue>(),
+        );
+        scuba.add("param_limit", self.limit);
+        if let Some(after) = &self.after {
+            scuba.add("param_after", after.to_string());
+        }
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
 impl AddScubaParams for thrift::CommitRunHooksParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("bookmark_name", self.bookmark.as_str());
     }
 }
 
+impl AddScubaParams for thrift::CommitLandabilityParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("bookmark_name", self.bookmark.as_str());
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
 impl AddScubaParams for thrift::CommitLookupXRepoParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("other_repo", self.other_repo.name.as_str());
@@ -329,6 +538,13 @@ impl AddScubaParams for thrift::CommitLookupXRepoParams {
     }
 }
 
+impl AddScubaParams for thrift::MegarepoPathMappingParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("other_repo", self.other_repo.name.as_str());
+        scuba.add("param_paths", self.paths.iter().collect::<ScubaValue>());
+    }
+}
+
 impl AddScubaParams for thrift::CommitPathBlameParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_format", self.format.to_string());
@@ -336,6 +552,12 @@ impl AddScubaParams for thrift::CommitPathBlameParams {
     }
 }
 
+impl AddScubaParams for thrift::CommitFileAnnotateHistoryParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
 impl AddScubaParams for thrift::CommitPathHistoryParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_format", self.format.to_string());
@@ -366,7 +588,11 @@ impl AddScubaParams for thrift::CommitPathHistoryParams {
 
 impl AddScubaParams for thrift::CommitPathExistsParams {}
 
-impl AddScubaParams for thrift::CommitPathInfoParams {}
+impl AddScubaParams for thrift::CommitPathInfoParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_include_link_target", self.include_link_target);
+    }
+}
 
 impl AddScubaParams for thrift::RepoInfoParams {}
 
@@ -382,6 +608,18 @@ impl AddScubaParams for thrift::CommitPathLastChangedParams {
     }
 }
 
+impl AddScubaParams for thrift::CommitPathFirstIntroducedParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        self.identity_schemes.add_scuba_params(scuba);
+    }
+}
+
+impl AddScubaParams for thrift::CommitPathCaseConflictsParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_limit", self.limit);
+    }
+}
+
 impl AddScubaParams for thrift::CommitMultiplePathLastChangedParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_paths", self.paths.iter().collect::<ScubaValue>());
@@ -428,6 +666,23 @@ impl AddScubaParams for thrift::CommitSparseProfileSizeParams {
     }
 }
 
+impl AddScubaParams for thrift::CommitSparseProfileFilesParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add(
+            "param_paths",
+            self.profile_paths.iter().collect::<ScubaValue>(),
+        );
+    }
+}
+
+impl AddScubaParams for thrift::CommitExportArchiveParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_path_prefix", self.path_prefix.as_str());
+        scuba.add("param_format", self.format.to_string());
+        scuba.add("param_compress", self.compress);
+    }
+}
+
 impl AddScubaParams for thrift::FileContentChunkParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_offset", self.offset);
@@ -453,6 +708,29 @@ impl AddScubaParams for thrift::TreeListParams {
     fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
         scuba.add("param_offset", self.offset);
         scuba.add("param_limit", self.limit);
+        scuba.add("param_include_link_target", self.include_link_target);
+        if let Some(path_regex) = &self.path_regex {
+            scuba.add("param_path_regex", path_regex.as_str());
+        }
+    }
+}
+
+impl AddScubaParams for thrift::TreeListRecursiveParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("param_depth", self.depth);
+        scuba.add("param_limit", self.limit);
+    }
+}
+
+impl AddScubaParams for thrift::TreePrefetchParams {
+    fn add_scuba_params(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add(
+            "param_path_prefixes",
+            self.path_prefixes.iter().collect::<ScubaValue>(),
+        );
+        scuba.add("param_depth", self.depth);
+        scuba.add("param_limit", self.limit);
+        scuba.add("param_compress", self.compress);
     }
 }
 
@@ -22,6 +22,16 @@ impl AddScubaResponse for bool {}
 
 impl AddScubaResponse for Vec<thrift::Repo> {}
 
+impl AddScubaResponse for thrift::ServiceLimits {}
+
+impl AddScubaResponse for thrift::ServiceCapabilitiesResponse {}
+
+impl AddScubaResponse for thrift::ServiceUsageResponse {}
+
+impl AddScubaResponse for thrift::AsyncRequestToken {}
+
+impl AddScubaResponse for thrift::AsyncRequestPollResponse {}
+
 impl AddScubaResponse for thrift::RepoInfo {}
 
 impl AddScubaResponse for thrift::RepoCreateCommitResponse {
@@ -47,15 +57,42 @@ impl AddScubaResponse for thrift::RepoCreateStackResponse {
 
 impl AddScubaResponse for thrift::RepoCreateBookmarkResponse {}
 
+impl AddScubaResponse for thrift::RepoCreateBranchFromCommitResponse {}
+
 impl AddScubaResponse for thrift::RepoMoveBookmarkResponse {}
 
 impl AddScubaResponse for thrift::RepoDeleteBookmarkResponse {}
 
 impl AddScubaResponse for thrift::RepoLandStackResponse {}
 
+impl AddScubaResponse for thrift::RepoUpdateSubmoduleResponse {}
+
 impl AddScubaResponse for thrift::RepoListBookmarksResponse {}
 
-impl AddScubaResponse for thrift::RepoResolveBookmarkResponse {}
+impl AddScubaResponse for thrift::RepoListRecentCommitsResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_commit_count", self.commits.len());
+    }
+}
+
+impl AddScubaResponse for thrift::RepoResolveBookmarkResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        if let Some(staleness) = &self.staleness {
+            scuba.add("response_staleness_ms", staleness.age_ms);
+            scuba.add("response_pending_move", staleness.pending_move);
+        }
+    }
+}
+
+impl AddScubaResponse for thrift::RepoResolveGlobalrevResponse {}
+
+impl AddScubaResponse for thrift::RepoLatestGlobalrevResponse {}
+
+impl AddScubaResponse for thrift::RepoCommitsInGlobalrevRangeResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_commit_count", self.commits.len());
+    }
+}
 
 impl AddScubaResponse for thrift::RepoResolveCommitPrefixResponse {}
 
@@ -63,6 +100,14 @@ impl AddScubaResponse for thrift::RepoBookmarkInfoResponse {}
 
 impl AddScubaResponse for thrift::RepoStackInfoResponse {}
 
+impl AddScubaResponse for thrift::RepoPathMultiCommitInfoResponse {}
+
+impl AddScubaResponse for thrift::RepoStatisticsResponse {}
+
+impl AddScubaResponse for thrift::RepoFileMetadataResponse {}
+
+impl AddScubaResponse for thrift::RepoListHookConfigsResponse {}
+
 impl AddScubaResponse for thrift::RepoPrepareCommitsResponse {}
 
 impl AddScubaResponse for thrift::RepoUploadFileContentResponse {
@@ -71,6 +116,19 @@ impl AddScubaResponse for thrift::RepoUploadFileContentResponse {
     }
 }
 
+impl AddScubaResponse for thrift::RepoRunHookOnHistoryResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_commits_checked", self.commits_checked);
+        scuba.add("response_commits_rejected", self.rejected_commits.len());
+    }
+}
+
+impl AddScubaResponse for thrift::RepoValidateCommitMessageResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_valid", self.valid);
+    }
+}
+
 impl AddScubaResponse for thrift::CommitCompareResponse {}
 
 impl AddScubaResponse for thrift::CommitFileDiffsResponse {
@@ -97,6 +155,14 @@ impl AddScubaResponse for thrift::CommitFileDiffsResponse {
         if non_text_files > 0 {
             scuba.add("non_text_files", non_text_files);
         }
+        let omitted_files = self
+            .path_diffs
+            .iter()
+            .filter(|response| response.omitted_reason.is_some())
+            .count();
+        if omitted_files > 0 {
+            scuba.add("omitted_files", omitted_files);
+        }
     }
 }
 
@@ -104,18 +170,43 @@ impl AddScubaResponse for thrift::CommitFindFilesResponse {}
 
 impl AddScubaResponse for thrift::CommitInfo {}
 
+impl AddScubaResponse for thrift::CommitPhaseResponse {}
+
+impl AddScubaResponse for thrift::CommitDerivedDataInfoResponse {}
+
 impl AddScubaResponse for thrift::CommitLookupResponse {}
 
+impl AddScubaResponse for thrift::MegarepoPathMappingResponse {}
+
 impl AddScubaResponse for thrift::CommitLookupPushrebaseHistoryResponse {}
 
+impl AddScubaResponse for thrift::CommitTranslateIdsResponse {}
+
+impl AddScubaResponse for thrift::CommitMultipleLookupResponse {}
+
+impl AddScubaResponse for thrift::CommitMutationsResponse {}
+
 impl AddScubaResponse for thrift::CommitHistoryResponse {}
 
 impl AddScubaResponse for thrift::CommitListDescendantBookmarksResponse {}
 
+impl AddScubaResponse for thrift::CommitListDescendantsResponse {}
+
+impl AddScubaResponse for thrift::CommitContainedInBookmarksResponse {}
+
 impl AddScubaResponse for thrift::CommitRunHooksResponse {}
 
+impl AddScubaResponse for thrift::CommitLandabilityResponse {}
+
 impl AddScubaResponse for thrift::CommitPathBlameResponse {}
 
+impl AddScubaResponse for thrift::CommitFileAnnotateHistoryResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_line_count", self.blame.lines.len());
+        scuba.add("response_commit_count", self.commit_infos.len());
+    }
+}
+
 impl AddScubaResponse for thrift::CommitPathHistoryResponse {}
 
 impl AddScubaResponse for thrift::CommitPathExistsResponse {}
@@ -126,12 +217,31 @@ impl AddScubaResponse for thrift::CommitMultiplePathInfoResponse {}
 
 impl AddScubaResponse for thrift::CommitPathLastChangedResponse {}
 
+impl AddScubaResponse for thrift::CommitPathFirstIntroducedResponse {}
+
+impl AddScubaResponse for thrift::CommitPathCaseConflictsResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_case_conflict_count", self.case_conflicts.len());
+        scuba.add("response_truncated", self.truncated);
+    }
+}
+
 impl AddScubaResponse for thrift::CommitMultiplePathLastChangedResponse {}
 
 impl AddScubaResponse for thrift::CommitSparseProfileDeltaResponse {}
 
 impl AddScubaResponse for thrift::CommitSparseProfileSizeResponse {}
 
+impl AddScubaResponse for thrift::CommitSparseProfileFilesResponse {}
+
+impl AddScubaResponse for thrift::CommitExportArchiveResponse {
+    fn add_scuba_response(&self, scuba: &mut MononokeScubaSampleBuilder) {
+        scuba.add("response_file_count", self.file_count);
+        scuba.add("response_total_size", self.total_size);
+        scuba.add("response_archive_size", self.archive.len());
+    }
+}
+
 impl AddScubaResponse for thrift::FileChunk {}
 
 impl AddScubaResponse for thrift::FileInfo {}
@@ -140,6 +250,10 @@ impl AddScubaResponse for thrift::FileDiffResponse {}
 
 impl AddScubaResponse for thrift::TreeListResponse {}
 
+impl AddScubaResponse for thrift::TreeListRecursiveResponse {}
+
+impl AddScubaResponse for thrift::TreePrefetchResponse {}
+
 // TODO: report cs_ids and actual error where possible
 impl AddScubaResponse for thrift::MegarepoRemergeSourceResult {}
 
@@ -5,12 +5,15 @@
  * GNU General Public License version 2.
  */
 
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::future::Future;
 use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Mutex;
 
+use bufsize::SizeCounter;
 use clientinfo::ClientEntryPoint;
 use clientinfo::ClientInfo;
 use clientinfo::CLIENT_INFO_HEADER;
@@ -18,6 +21,9 @@ use connection_security_checker::ConnectionSecurityChecker;
 use ephemeral_blobstore::BubbleId;
 use ephemeral_blobstore::RepoEphemeralStore;
 use fbinit::FacebookInit;
+use fbthrift::compact_protocol;
+use fbthrift::compact_protocol::CompactProtocolSerializer;
+use fbthrift::serialize::Serialize as ThriftSerialize;
 use futures::future::BoxFuture;
 use futures::try_join;
 use futures::FutureExt;
@@ -29,6 +35,7 @@ use login_objects_thrift::EnvironmentType;
 use maplit::hashset;
 use megarepo_api::MegarepoApi;
 use metaconfig_types::CommonConfig;
+use metaconfig_types::SourceControlServiceParams;
 use metadata::Metadata;
 use mononoke_api::ChangesetContext;
 use mononoke_api::ChangesetId;
@@ -37,6 +44,7 @@ use mononoke_api::CoreContext;
 use mononoke_api::FileContext;
 use mononoke_api::FileId;
 use mononoke_api::Mononoke;
+use mononoke_api::MononokePath;
 use mononoke_api::RepoContext;
 use mononoke_api::SessionContainer;
 use mononoke_api::TreeContext;
@@ -44,8 +52,11 @@ use mononoke_api::TreeId;
 use mononoke_types::hash::Sha1;
 use mononoke_types::hash::Sha256;
 use once_cell::sync::Lazy;
+use permission_checker::BoxPermissionChecker;
 use permission_checker::MononokeIdentity;
 use permission_checker::MononokeIdentitySet;
+use permission_checker::MononokeIdentitySetExt;
+use permission_checker::PermissionCheckerBuilder;
 use repo_authorization::AuthorizationContext;
 use scribe_ext::Scribe;
 use scuba_ext::MononokeScubaSampleBuilder;
@@ -59,14 +70,18 @@ use stats::prelude::*;
 use time_ext::DurationExt;
 use tunables::tunables;
 
+use crate::background::BackgroundRequests;
 use crate::commit_id::CommitIdExt;
 use crate::errors;
 use crate::errors::ServiceErrorResultExt;
 use crate::errors::Status;
 use crate::from_request::FromRequest;
+use crate::methods::repo::statistics::RepoStatisticsCache;
 use crate::scuba_params::AddScubaParams;
 use crate::scuba_response::AddScubaResponse;
 use crate::specifiers::SpecifierExt;
+use crate::usage::UsageTracker;
+use crate::validate_params::ValidateParams;
 
 const FORWARDED_IDENTITIES_HEADER: &str = "scm_forwarded_identities";
 const FORWARDED_CLIENT_IP_HEADER: &str = "scm_forwarded_client_ip";
@@ -91,6 +106,28 @@ define_stats! {
 
 static POPULAR_METHODS: Lazy<HashSet<&'static str>> = Lazy::new(|| hashset! {});
 
+/// Methods that are only available for repos which enable them in their
+/// `source_control_service.enabled_gated_methods` config, so that they can be
+/// rolled out gradually.
+static GATED_METHODS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    hashset! {
+        "repo_create_branch_from_commit",
+        "repo_update_submodule",
+    }
+});
+
+/// Check that a method is enabled by a repo's config, if it is gated.
+fn check_method_enabled_for_repo(
+    name: &str,
+    reponame: &str,
+    params: &SourceControlServiceParams,
+) -> Result<(), errors::ServiceError> {
+    if GATED_METHODS.contains(name) && !params.gated_method_enabled(name) {
+        return Err(errors::method_disabled_for_repo(name, reponame).into());
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub(crate) struct SourceControlServiceImpl {
     pub(crate) fb: FacebookInit,
@@ -101,6 +138,14 @@ pub(crate) struct SourceControlServiceImpl {
     pub(crate) identity: Identity,
     pub(crate) scribe: Scribe,
     identity_proxy_checker: Arc<ConnectionSecurityChecker>,
+    pub(crate) background_requests: BackgroundRequests,
+    pub(crate) repo_statistics_cache: RepoStatisticsCache,
+    pub(crate) usage: UsageTracker,
+    /// Checks who may see the usage of the service by each caller.
+    pub(crate) usage_checker: Arc<BoxPermissionChecker>,
+    /// Scuba builders for the datasets that repos route their requests to,
+    /// by dataset name.
+    repo_scuba_builders: Arc<Mutex<HashMap<String, MononokeScubaSampleBuilder>>>,
 }
 
 pub(crate) struct SourceControlServiceThriftImpl(SourceControlServiceImpl);
@@ -118,6 +163,14 @@ impl SourceControlServiceImpl {
     ) -> Self {
         scuba_builder.add_common_server_data();
 
+        let mut usage_allowlist = MononokeIdentitySet::new();
+        for identity in &common_config.global_allowlist {
+            usage_allowlist.insert(MononokeIdentity::new(&identity.id_type, &identity.id_data));
+        }
+        let usage_checker = PermissionCheckerBuilder::new()
+            .allow_allowlist(usage_allowlist)
+            .build();
+
         Self {
             fb,
             mononoke,
@@ -130,6 +183,11 @@ impl SourceControlServiceImpl {
             ),
             scribe,
             identity_proxy_checker: Arc::new(identity_proxy_checker),
+            background_requests: BackgroundRequests::default(),
+            repo_statistics_cache: RepoStatisticsCache::default(),
+            usage: UsageTracker::default(),
+            usage_checker: Arc::new(usage_checker),
+            repo_scuba_builders: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -144,6 +202,9 @@ impl SourceControlServiceImpl {
         specifier: Option<&dyn SpecifierExt>,
         params: &dyn AddScubaParams,
     ) -> Result<CoreContext, errors::ServiceError> {
+        if let Some(reponame) = specifier.and_then(|specifier| specifier.scuba_reponame()) {
+            self.check_method_enabled(name, &reponame)?;
+        }
         let session = self.create_session(req_ctxt).await?;
         let identities = session.metadata().identities();
         let mut scuba = self.create_scuba(name, req_ctxt, specifier, params, identities)?;
@@ -156,6 +217,51 @@ impl SourceControlServiceImpl {
         Ok(ctx)
     }
 
+    /// Check that a method is enabled for a repo, if it is gated.
+    fn check_method_enabled(&self, name: &str, reponame: &str) -> Result<(), errors::ServiceError> {
+        // Unknown repos are reported when the method looks up the repo.
+        match self.mononoke.raw_repo(reponame) {
+            Some(repo) => {
+                check_method_enabled_for_repo(name, reponame, &repo.config().source_control_service)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The scuba sample builder for requests to a repo.  Repos can log to
+    /// their own dataset, so that high traffic repos don't drown out the
+    /// others in the shared one, and can tag their samples with a category.
+    fn repo_scuba_builder(
+        &self,
+        reponame: Option<&str>,
+    ) -> Result<MononokeScubaSampleBuilder, errors::ServiceError> {
+        let repo = match reponame.and_then(|reponame| self.mononoke.raw_repo(reponame)) {
+            Some(repo) => repo,
+            None => return Ok(self.scuba_builder.clone()),
+        };
+        let params = &repo.config().source_control_service;
+        let mut scuba = match &params.scuba_table {
+            Some(table) => {
+                let mut builders = self.repo_scuba_builders.lock().expect("lock poisoned");
+                match builders.get(table) {
+                    Some(builder) => builder.clone(),
+                    None => {
+                        let mut builder = MononokeScubaSampleBuilder::new(self.fb, table)
+                            .map_err(errors::internal_error)?;
+                        builder.add_common_server_data();
+                        builders.insert(table.clone(), builder.clone());
+                        builder
+                    }
+                }
+            }
+            None => self.scuba_builder.clone(),
+        };
+        if let Some(category) = &params.scuba_category {
+            scuba.add("repo_category", category.as_str());
+        }
+        Ok(scuba)
+    }
+
     /// Create and configure a scuba sample builder for a request.
     fn create_scuba(
         &self,
@@ -165,13 +271,14 @@ impl SourceControlServiceImpl {
         params: &dyn AddScubaParams,
         identities: &MononokeIdentitySet,
     ) -> Result<MononokeScubaSampleBuilder, errors::ServiceError> {
-        let mut scuba = self.scuba_builder.clone().with_seq("seq");
+        let reponame = specifier.and_then(|specifier| specifier.scuba_reponame());
+        let mut scuba = self.repo_scuba_builder(reponame.as_deref())?.with_seq("seq");
         scuba.add("type", "thrift");
         scuba.add("method", name);
+        if let Some(reponame) = reponame {
+            scuba.add("reponame", reponame);
+        }
         if let Some(specifier) = specifier {
-            if let Some(reponame) = specifier.scuba_reponame() {
-                scuba.add("reponame", reponame);
-            }
             if let Some(commit) = specifier.scuba_commit() {
                 scuba.add("commit", commit);
             }
@@ -493,7 +600,9 @@ impl SourceControlServiceImpl {
         let (repo, tree) = match tree {
             thrift::TreeSpecifier::by_commit_path(commit_path) => {
                 let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-                let path = changeset.path_with_content(&commit_path.path).await?;
+                let path = changeset
+                    .path_with_content(MononokePath::from_request(&commit_path.path)?)
+                    .await?;
                 (repo, path.tree().await?)
             }
             thrift::TreeSpecifier::by_id(tree_id) => {
@@ -528,7 +637,9 @@ impl SourceControlServiceImpl {
         let (repo, file) = match file {
             thrift::FileSpecifier::by_commit_path(commit_path) => {
                 let (repo, changeset) = self.repo_changeset(ctx, &commit_path.commit).await?;
-                let path = changeset.path_with_content(&commit_path.path).await?;
+                let path = changeset
+                    .path_with_content(MononokePath::from_request(&commit_path.path)?)
+                    .await?;
                 (repo, path.file().await?)
             }
             thrift::FileSpecifier::by_id(file_id) => {
@@ -570,16 +681,28 @@ impl SourceControlServiceImpl {
     }
 }
 
-fn log_result<T: AddScubaResponse>(
+/// Log the result of a request.  If a sampling rate is given, the response
+/// is measured, and its size scaled by the rate is returned.
+fn log_result<T>(
     ctx: CoreContext,
     stats: &FutureStats,
     result: &Result<T, impl errors::LoggableError>,
-) {
+    size_sampling_rate: Option<u64>,
+) -> u64
+where
+    T: AddScubaResponse + ThriftSerialize<CompactProtocolSerializer<SizeCounter>>,
+{
     let mut scuba = ctx.scuba().clone();
 
+    let mut response_size = 0;
     let (status, error, invalid_request, internal_failure) = match result {
         Ok(response) => {
             response.add_scuba_response(&mut scuba);
+            if let Some(rate) = size_sampling_rate {
+                let size = compact_protocol::serialize_size(response) as u64;
+                scuba.add("response_size", size);
+                response_size = size * rate;
+            }
             ("SUCCESS", None, 0, 0)
         }
         Err(err) => {
@@ -610,6 +733,7 @@ fn log_result<T: AddScubaResponse>(
         scuba.add("error", error.as_str());
     }
     scuba.log_with_msg("Request complete", None);
+    response_size
 }
 
 fn log_cancelled(ctx: &CoreContext, stats: &FutureStats) {
@@ -641,8 +765,22 @@ macro_rules! create_ctx {
     };
 }
 
+// Define a macro to check the thrift parameters against the request limits.
+macro_rules! validate_params {
+    ( $params_name:ident ) => {
+        $params_name.validate()
+    };
+
+    ( $obj_name:ident, $params_name:ident ) => {
+        $obj_name
+            .validate()
+            .and_then(|()| $params_name.validate())
+    };
+}
+
 // Define a macro that generates a non-async wrapper that delegates to the
-// async implementation of the method.
+// async implementation of the method, once its parameters have been
+// validated.
 //
 // The implementations of the methods can be found in the `methods` module.
 macro_rules! impl_thrift_methods {
@@ -662,12 +800,17 @@ macro_rules! impl_thrift_methods {
                     let ctx = create_ctx!(self.0, $method_name, req_ctxt, $( $param_name ),*).await?;
                     ctx.scuba().clone().log_with_msg("Request start", None);
                     STATS::total_request_start.add_value(1);
-                    let (stats, res) = (self.0)
-                        .$method_name(ctx.clone(), $( $param_name ),* )
-                        .timed()
-                        .on_cancel_with_data(|stats| log_cancelled(&ctx, &stats))
-                        .await;
-                    log_result(ctx, &stats, &res);
+                    let (stats, res) = async {
+                        validate_params!($( $param_name ),*)?;
+                        (self.0).$method_name(ctx.clone(), $( $param_name ),* ).await
+                    }
+                    .timed()
+                    .on_cancel_with_data(|stats| log_cancelled(&ctx, &stats))
+                    .await;
+                    let identity = ctx.metadata().identities().main_client_identity();
+                    let size_sampling_rate = (self.0).usage.sample_response_size();
+                    let response_size = log_result(ctx, &stats, &res, size_sampling_rate);
+                    (self.0).usage.record(identity, response_size);
                     let method = stringify!($method_name).to_string();
                     STATS::method_completion_time_ms.add_value(stats.completion_time.as_millis_unchecked() as i64, (method,));
                     res.map_err(Into::into)
@@ -686,6 +829,22 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::ListReposParams,
         ) -> Result<Vec<thrift::Repo>, service::ListReposExn>;
 
+        async fn service_limits(
+            params: thrift::ServiceLimitsParams,
+        ) -> Result<thrift::ServiceLimits, service::ServiceLimitsExn>;
+
+        async fn service_capabilities(
+            params: thrift::ServiceCapabilitiesParams,
+        ) -> Result<thrift::ServiceCapabilitiesResponse, service::ServiceCapabilitiesExn>;
+
+        async fn service_usage(
+            params: thrift::ServiceUsageParams,
+        ) -> Result<thrift::ServiceUsageResponse, service::ServiceUsageExn>;
+
+        async fn request_poll(
+            params: thrift::AsyncRequestToken,
+        ) -> Result<thrift::AsyncRequestPollResponse, service::RequestPollExn>;
+
         async fn repo_info(
             repo: thrift::RepoSpecifier,
             params: thrift::RepoInfoParams,
@@ -696,6 +855,21 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::RepoResolveBookmarkParams,
         ) -> Result<thrift::RepoResolveBookmarkResponse, service::RepoResolveBookmarkExn>;
 
+        async fn repo_resolve_globalrev(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoResolveGlobalrevParams,
+        ) -> Result<thrift::RepoResolveGlobalrevResponse, service::RepoResolveGlobalrevExn>;
+
+        async fn repo_latest_globalrev(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoLatestGlobalrevParams,
+        ) -> Result<thrift::RepoLatestGlobalrevResponse, service::RepoLatestGlobalrevExn>;
+
+        async fn repo_commits_in_globalrev_range(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoCommitsInGlobalrevRangeParams,
+        ) -> Result<thrift::RepoCommitsInGlobalrevRangeResponse, service::RepoCommitsInGlobalrevRangeExn>;
+
         async fn repo_resolve_commit_prefix(
             repo: thrift::RepoSpecifier,
             params: thrift::RepoResolveCommitPrefixParams,
@@ -706,6 +880,11 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::RepoListBookmarksParams,
         ) -> Result<thrift::RepoListBookmarksResponse, service::RepoListBookmarksExn>;
 
+        async fn repo_list_recent_commits(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoListRecentCommitsParams,
+        ) -> Result<thrift::RepoListRecentCommitsResponse, service::RepoListRecentCommitsExn>;
+
         async fn commit_common_base_with(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitCommonBaseWithParams,
@@ -721,26 +900,66 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::CommitLookupPushrebaseHistoryParams,
         ) -> Result<thrift::CommitLookupPushrebaseHistoryResponse, service::CommitLookupPushrebaseHistoryExn>;
 
+        async fn commit_translate_ids(
+            repo: thrift::RepoSpecifier,
+            params: thrift::CommitTranslateIdsParams,
+        ) -> Result<thrift::CommitTranslateIdsResponse, service::CommitTranslateIdsExn>;
+
+        async fn commit_multiple_lookup(
+            repo: thrift::RepoSpecifier,
+            params: thrift::CommitMultipleLookupParams,
+        ) -> Result<thrift::CommitMultipleLookupResponse, service::CommitMultipleLookupExn>;
+
+        async fn commit_mutations(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitMutationsParams,
+        ) -> Result<thrift::CommitMutationsResponse, service::CommitMutationsExn>;
+
         async fn commit_file_diffs(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitFileDiffsParams,
         ) -> Result<thrift::CommitFileDiffsResponse, service::CommitFileDiffsExn>;
 
+        async fn commit_file_diffs_async(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitFileDiffsParams,
+        ) -> Result<thrift::AsyncRequestToken, service::CommitFileDiffsAsyncExn>;
+
         async fn commit_info(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitInfoParams,
         ) -> Result<thrift::CommitInfo, service::CommitInfoExn>;
 
+        async fn commit_phase(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitPhaseParams,
+        ) -> Result<thrift::CommitPhaseResponse, service::CommitPhaseExn>;
+
+        async fn commit_derived_data_info(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitDerivedDataInfoParams,
+        ) -> Result<thrift::CommitDerivedDataInfoResponse, service::CommitDerivedDataInfoExn>;
+
         async fn commit_is_ancestor_of(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitIsAncestorOfParams,
         ) -> Result<bool, service::CommitIsAncestorOfExn>;
 
+        async fn commit_contained_in_bookmarks(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitContainedInBookmarksParams,
+        ) -> Result<thrift::CommitContainedInBookmarksResponse, service::CommitContainedInBookmarksExn>;
+
         async fn commit_compare(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitCompareParams,
         ) -> Result<thrift::CommitCompareResponse, service::CommitCompareExn>;
 
+        async fn commit_compare_async(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitCompareParams,
+        ) -> Result<thrift::AsyncRequestToken, service::CommitCompareAsyncExn>;
+
         async fn commit_find_files(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitFindFilesParams,
@@ -756,16 +975,31 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::CommitListDescendantBookmarksParams,
         ) -> Result<thrift::CommitListDescendantBookmarksResponse, service::CommitListDescendantBookmarksExn>;
 
+        async fn commit_list_descendants(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitListDescendantsParams,
+        ) -> Result<thrift::CommitListDescendantsResponse, service::CommitListDescendantsExn>;
+
         async fn commit_run_hooks(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitRunHooksParams,
         ) -> Result<thrift::CommitRunHooksResponse, service::CommitRunHooksExn>;
 
+        async fn commit_landability(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitLandabilityParams,
+        ) -> Result<thrift::CommitLandabilityResponse, service::CommitLandabilityExn>;
+
         async fn commit_lookup_xrepo(
             commit: thrift::CommitSpecifier,
             params: thrift::CommitLookupXRepoParams,
         ) -> Result<thrift::CommitLookupResponse, service::CommitLookupXrepoExn>;
 
+        async fn megarepo_path_mapping(
+            commit: thrift::CommitSpecifier,
+            params: thrift::MegarepoPathMappingParams,
+        ) -> Result<thrift::MegarepoPathMappingResponse, service::MegarepoPathMappingExn>;
+
         async fn commit_path_exists(
             commit_path: thrift::CommitPathSpecifier,
             params: thrift::CommitPathExistsParams,
@@ -786,6 +1020,11 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::CommitPathBlameParams,
         ) -> Result<thrift::CommitPathBlameResponse, service::CommitPathBlameExn>;
 
+        async fn commit_file_annotate_history(
+            commit_path: thrift::CommitPathSpecifier,
+            params: thrift::CommitFileAnnotateHistoryParams,
+        ) -> Result<thrift::CommitFileAnnotateHistoryResponse, service::CommitFileAnnotateHistoryExn>;
+
         async fn commit_path_history(
             commit_path: thrift::CommitPathSpecifier,
             params: thrift::CommitPathHistoryParams,
@@ -796,6 +1035,16 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::CommitPathLastChangedParams,
         ) -> Result<thrift::CommitPathLastChangedResponse, service::CommitPathLastChangedExn>;
 
+        async fn commit_path_first_introduced(
+            commit_path: thrift::CommitPathSpecifier,
+            params: thrift::CommitPathFirstIntroducedParams,
+        ) -> Result<thrift::CommitPathFirstIntroducedResponse, service::CommitPathFirstIntroducedExn>;
+
+        async fn commit_path_case_conflicts(
+            commit_path: thrift::CommitPathSpecifier,
+            params: thrift::CommitPathCaseConflictsParams,
+        ) -> Result<thrift::CommitPathCaseConflictsResponse, service::CommitPathCaseConflictsExn>;
+
         async fn commit_multiple_path_last_changed(
             commit_path: thrift::CommitSpecifier,
             params: thrift::CommitMultiplePathLastChangedParams,
@@ -816,11 +1065,31 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::CommitSparseProfileSizeParams,
         ) -> Result<thrift::CommitSparseProfileSizeResponse, service::CommitSparseProfileSizeExn>;
 
+        async fn commit_sparse_profile_files(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitSparseProfileFilesParams,
+        ) -> Result<thrift::CommitSparseProfileFilesResponse, service::CommitSparseProfileFilesExn>;
+
+        async fn commit_export_archive(
+            commit: thrift::CommitSpecifier,
+            params: thrift::CommitExportArchiveParams,
+        ) -> Result<thrift::CommitExportArchiveResponse, service::CommitExportArchiveExn>;
+
         async fn tree_list(
             tree: thrift::TreeSpecifier,
             params: thrift::TreeListParams,
         ) -> Result<thrift::TreeListResponse, service::TreeListExn>;
 
+        async fn tree_list_recursive(
+            tree: thrift::TreeSpecifier,
+            params: thrift::TreeListRecursiveParams,
+        ) -> Result<thrift::TreeListRecursiveResponse, service::TreeListRecursiveExn>;
+
+        async fn tree_prefetch(
+            commit: thrift::CommitSpecifier,
+            params: thrift::TreePrefetchParams,
+        ) -> Result<thrift::TreePrefetchResponse, service::TreePrefetchExn>;
+
         async fn file_exists(
             file: thrift::FileSpecifier,
             _params: thrift::FileExistsParams,
@@ -861,11 +1130,36 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::RepoStackInfoParams,
         ) -> Result<thrift::RepoStackInfoResponse, service::RepoStackInfoExn>;
 
+        async fn repo_path_multi_commit_info(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoPathMultiCommitInfoParams,
+        ) -> Result<thrift::RepoPathMultiCommitInfoResponse, service::RepoPathMultiCommitInfoExn>;
+
+        async fn repo_statistics(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoStatisticsParams,
+        ) -> Result<thrift::RepoStatisticsResponse, service::RepoStatisticsExn>;
+
+        async fn repo_file_metadata(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoFileMetadataParams,
+        ) -> Result<thrift::RepoFileMetadataResponse, service::RepoFileMetadataExn>;
+
+        async fn repo_list_hook_configs(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoListHookConfigsParams,
+        ) -> Result<thrift::RepoListHookConfigsResponse, service::RepoListHookConfigsExn>;
+
         async fn repo_create_bookmark(
             repo: thrift::RepoSpecifier,
             params: thrift::RepoCreateBookmarkParams,
         ) -> Result<thrift::RepoCreateBookmarkResponse, service::RepoCreateBookmarkExn>;
 
+        async fn repo_create_branch_from_commit(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoCreateBranchFromCommitParams,
+        ) -> Result<thrift::RepoCreateBranchFromCommitResponse, service::RepoCreateBranchFromCommitExn>;
+
         async fn repo_move_bookmark(
             repo: thrift::RepoSpecifier,
             params: thrift::RepoMoveBookmarkParams,
@@ -881,6 +1175,11 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::RepoLandStackParams,
         ) -> Result<thrift::RepoLandStackResponse, service::RepoLandStackExn>;
 
+        async fn repo_update_submodule(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoUpdateSubmoduleParams,
+        ) -> Result<thrift::RepoUpdateSubmoduleResponse, service::RepoUpdateSubmoduleExn>;
+
         async fn repo_prepare_commits(
             repo: thrift::RepoSpecifier,
             params: thrift::RepoPrepareCommitsParams,
@@ -891,6 +1190,16 @@ impl SourceControlService for SourceControlServiceThriftImpl {
             params: thrift::RepoUploadFileContentParams,
         ) -> Result<thrift::RepoUploadFileContentResponse, service::RepoUploadFileContentExn>;
 
+        async fn repo_run_hook_on_history(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoRunHookOnHistoryParams,
+        ) -> Result<thrift::RepoRunHookOnHistoryResponse, service::RepoRunHookOnHistoryExn>;
+
+        async fn repo_validate_commit_message(
+            repo: thrift::RepoSpecifier,
+            params: thrift::RepoValidateCommitMessageParams,
+        ) -> Result<thrift::RepoValidateCommitMessageResponse, service::RepoValidateCommitMessageExn>;
+
         async fn megarepo_add_sync_target_config(
             params: thrift::MegarepoAddConfigParams,
         ) -> Result<thrift::MegarepoAddConfigResponse, service::MegarepoAddSyncTargetConfigExn>;
@@ -955,3 +1264,46 @@ impl SourceControlService for SourceControlServiceThriftImpl {
         ) -> Result<thrift::CreateGitTagResponse, service::CreateGitTagExn>;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_disabled(result: Result<(), errors::ServiceError>) -> bool {
+        match result {
+            Err(errors::ServiceError::Request(e)) => {
+                e.kind == thrift::RequestErrorKind::METHOD_DISABLED_FOR_REPO
+            }
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_gated_methods() {
+        let disabled = SourceControlServiceParams::default();
+        for method in GATED_METHODS.iter() {
+            assert!(
+                is_disabled(check_method_enabled_for_repo(method, "repo", &disabled)),
+                "{} should be disabled unless enabled for the repo",
+                method
+            );
+            let enabled = SourceControlServiceParams {
+                enabled_gated_methods: hashset! { method.to_string() },
+                ..Default::default()
+            };
+            assert!(check_method_enabled_for_repo(method, "repo", &enabled).is_ok());
+        }
+        // Enabling one gated method doesn't enable the others.
+        let enabled = SourceControlServiceParams {
+            enabled_gated_methods: hashset! { "repo_update_submodule".to_string() },
+            ..Default::default()
+        };
+        assert!(is_disabled(check_method_enabled_for_repo(
+            "repo_create_branch_from_commit",
+            "repo",
+            &enabled
+        )));
+        // Methods that aren't gated are always enabled.
+        assert!(check_method_enabled_for_repo("repo_info", "repo", &disabled).is_ok());
+    }
+}
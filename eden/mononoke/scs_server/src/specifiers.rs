@@ -103,7 +103,7 @@ impl SpecifierExt for thrift::TreeSpecifier {
     fn scuba_path(&self) -> Option<String> {
         match self {
             thrift::TreeSpecifier::by_commit_path(commit_path) => commit_path.scuba_path(),
-            thrift::TreeSpecifier::by_id(_tree_id) => None,
+            thrift::TreeSpecifier::by_id(tree_id) => tree_id.path_hint.clone(),
             thrift::TreeSpecifier::UnknownField(_) => None,
         }
     }
@@ -0,0 +1,290 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Usage of the service by each caller.
+//!
+//! Completed requests are counted against the caller's main identity in one
+//! minute buckets, kept for SERVICE_USAGE_MAX_WINDOW_SECS.  Completed buckets
+//! are also exported to scuba, for attributing usage over longer periods.
+//!
+//! Requests are spread over several shards, each with its own buckets, so
+//! that concurrent requests rarely contend on the same lock.  Measuring a
+//! response means serializing it again, so only a sample of responses are
+//! measured, and each measured response counts for the whole sample.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use futures::StreamExt;
+use scuba_ext::MononokeScubaSampleBuilder;
+use source_control as thrift;
+use tunables::tunables;
+
+const BUCKET_SECS: u64 = 60;
+const MAX_BUCKETS: usize =
+    (thrift::consts::SERVICE_USAGE_MAX_WINDOW_SECS as u64 / BUCKET_SECS) as usize;
+const SHARDS: usize = 16;
+
+/// Measure the size of one in this many responses, unless overridden by the
+/// `scs_usage_response_size_sampling_rate` tunable.
+const DEFAULT_RESPONSE_SIZE_SAMPLING_RATE: u64 = 100;
+
+#[derive(Clone, Copy, Default)]
+struct UsageCounts {
+    requests: u64,
+    response_bytes: u64,
+}
+
+struct UsageBucket {
+    start: u64,
+    by_identity: HashMap<String, UsageCounts>,
+}
+
+type UsageBuckets = Mutex<VecDeque<UsageBucket>>;
+
+#[derive(Clone)]
+pub(crate) struct UsageTracker {
+    shards: Arc<[UsageBuckets]>,
+    /// Number of requests recorded, used to pick shards and samples.
+    recorded: Arc<AtomicU64>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            recorded: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl UsageTracker {
+    /// Whether the size of the next response should be measured.  If so,
+    /// returns the number of responses that the measured size stands for.
+    pub(crate) fn sample_response_size(&self) -> Option<u64> {
+        let rate = match tunables().scs_usage_response_size_sampling_rate() {
+            Some(rate) if rate > 0 => rate as u64,
+            _ => DEFAULT_RESPONSE_SIZE_SAMPLING_RATE,
+        };
+        (self.recorded.load(Ordering::Relaxed) % rate == 0).then_some(rate)
+    }
+
+    /// Record a completed request.
+    pub(crate) fn record(&self, identity: String, response_bytes: u64) {
+        self.record_at(now_secs(), identity, response_bytes)
+    }
+
+    fn record_at(&self, now: u64, identity: String, response_bytes: u64) {
+        let start = now - now % BUCKET_SECS;
+        let shard = self.recorded.fetch_add(1, Ordering::Relaxed) as usize % self.shards.len();
+        let mut buckets = self.shards[shard].lock().expect("lock poisoned");
+        if buckets.back().map_or(true, |bucket| bucket.start < start) {
+            buckets.push_back(UsageBucket {
+                start,
+                by_identity: HashMap::new(),
+            });
+            // Drop buckets that have fallen out of the longest window.
+            let oldest_start = start.saturating_sub((MAX_BUCKETS as u64 - 1) * BUCKET_SECS);
+            while buckets
+                .front()
+                .map_or(false, |bucket| bucket.start < oldest_start)
+            {
+                buckets.pop_front();
+            }
+        }
+        let bucket = buckets.back_mut().expect("bucket just added");
+        let counts = bucket.by_identity.entry(identity).or_default();
+        counts.requests += 1;
+        counts.response_bytes += response_bytes;
+    }
+
+    /// Usage by each caller over the window, heaviest first, along with
+    /// the window actually covered.
+    pub(crate) fn usage(&self, window_secs: u64) -> (Vec<thrift::IdentityUsage>, u64) {
+        self.usage_at(now_secs(), window_secs)
+    }
+
+    fn usage_at(&self, now: u64, window_secs: u64) -> (Vec<thrift::IdentityUsage>, u64) {
+        let num_buckets = window_secs
+            .div_ceil(BUCKET_SECS)
+            .clamp(1, MAX_BUCKETS as u64);
+        // The current bucket is partial, so counts as one of the buckets.
+        let first_start = (now - now % BUCKET_SECS).saturating_sub((num_buckets - 1) * BUCKET_SECS);
+        let mut totals: HashMap<String, UsageCounts> = HashMap::new();
+        for shard in self.shards.iter() {
+            let buckets = shard.lock().expect("lock poisoned");
+            for bucket in buckets.iter().filter(|bucket| bucket.start >= first_start) {
+                for (identity, counts) in bucket.by_identity.iter() {
+                    let total = totals.entry(identity.clone()).or_default();
+                    total.requests += counts.requests;
+                    total.response_bytes += counts.response_bytes;
+                }
+            }
+        }
+        let mut usage: Vec<_> = totals
+            .into_iter()
+            .map(|(identity, counts)| thrift::IdentityUsage {
+                identity,
+                request_count: counts.requests as i64,
+                response_bytes: counts.response_bytes as i64,
+                ..Default::default()
+            })
+            .collect();
+        usage.sort_by(|a, b| {
+            b.request_count
+                .cmp(&a.request_count)
+                .then_with(|| a.identity.cmp(&b.identity))
+        });
+        (usage, num_buckets * BUCKET_SECS)
+    }
+
+    /// Buckets that completed after `after`, oldest first, with the counts
+    /// from each shard combined.
+    fn completed_since(&self, now: u64, after: u64) -> Vec<(u64, Vec<(String, UsageCounts)>)> {
+        let current_start = now - now % BUCKET_SECS;
+        let mut completed: BTreeMap<u64, HashMap<String, UsageCounts>> = BTreeMap::new();
+        for shard in self.shards.iter() {
+            let buckets = shard.lock().expect("lock poisoned");
+            for bucket in buckets
+                .iter()
+                .filter(|bucket| bucket.start > after && bucket.start < current_start)
+            {
+                let totals = completed.entry(bucket.start).or_default();
+                for (identity, counts) in bucket.by_identity.iter() {
+                    let total = totals.entry(identity.clone()).or_default();
+                    total.requests += counts.requests;
+                    total.response_bytes += counts.response_bytes;
+                }
+            }
+        }
+        completed
+            .into_iter()
+            .map(|(start, counts)| (start, counts.into_iter().collect()))
+            .collect()
+    }
+}
+
+/// Log each caller's usage to scuba as each bucket completes.
+pub(crate) async fn usage_scuba_exporter(
+    tracker: UsageTracker,
+    scuba_builder: MononokeScubaSampleBuilder,
+) {
+    let now = now_secs();
+    // Export from the bucket in progress, which is the first to complete.
+    let mut last_exported = (now - now % BUCKET_SECS).saturating_sub(1);
+    tokio_shim::time::interval_stream(Duration::from_secs(BUCKET_SECS))
+        .for_each(|_| {
+            for (start, counts) in tracker.completed_since(now_secs(), last_exported) {
+                for (identity, counts) in counts {
+                    let mut scuba = scuba_builder.clone();
+                    scuba
+                        .add("type", "usage")
+                        .add("identity", identity)
+                        .add("usage_start", start)
+                        .add("usage_secs", BUCKET_SECS)
+                        .add("request_count", counts.requests)
+                        .add("response_bytes", counts.response_bytes)
+                        .log_with_msg("Usage by identity", None);
+                }
+                last_exported = start;
+            }
+            async {}
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(tracker: &UsageTracker, now: u64, window_secs: u64) -> (Vec<(String, i64, i64)>, u64) {
+        let (usage, window_secs) = tracker.usage_at(now, window_secs);
+        let usage = usage
+            .into_iter()
+            .map(|usage| (usage.identity, usage.request_count, usage.response_bytes))
+            .collect();
+        (usage, window_secs)
+    }
+
+    #[test]
+    fn test_bucket_rollover() {
+        let tracker = UsageTracker::default();
+        let start = 1_000 * BUCKET_SECS;
+        tracker.record_at(start, "alice".to_string(), 10);
+        tracker.record_at(start + 1, "bob".to_string(), 20);
+        tracker.record_at(start + BUCKET_SECS - 1, "alice".to_string(), 30);
+        tracker.record_at(start + BUCKET_SECS, "alice".to_string(), 40);
+
+        // The window covers the current bucket only.
+        let now = start + BUCKET_SECS + 1;
+        assert_eq!(
+            usage(&tracker, now, 1),
+            (vec![("alice".to_string(), 1, 40)], BUCKET_SECS)
+        );
+        // The window covers the previous bucket as well.
+        assert_eq!(
+            usage(&tracker, now, BUCKET_SECS + 1),
+            (
+                vec![("alice".to_string(), 3, 80), ("bob".to_string(), 1, 20)],
+                2 * BUCKET_SECS
+            )
+        );
+
+        // Only the first bucket has completed.
+        let completed = tracker.completed_since(now, 0);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].0, start);
+        assert!(tracker.completed_since(now, start).is_empty());
+    }
+
+    #[test]
+    fn test_window_clamping() {
+        let tracker = UsageTracker::default();
+        let start = 1_000 * BUCKET_SECS;
+        for bucket in 0..(MAX_BUCKETS as u64 + 10) {
+            tracker.record_at(start + bucket * BUCKET_SECS, "alice".to_string(), 1);
+        }
+        let now = start + (MAX_BUCKETS as u64 + 9) * BUCKET_SECS;
+
+        // Windows are rounded up to whole buckets.
+        assert_eq!(
+            usage(&tracker, now, 0),
+            (vec![("alice".to_string(), 1, 1)], BUCKET_SECS)
+        );
+        assert_eq!(
+            usage(&tracker, now, BUCKET_SECS + 1),
+            (vec![("alice".to_string(), 2, 2)], 2 * BUCKET_SECS)
+        );
+
+        // Windows are capped at the longest window, and older buckets are
+        // dropped.
+        let max_window = thrift::consts::SERVICE_USAGE_MAX_WINDOW_SECS as u64;
+        let expected = (
+            vec![("alice".to_string(), MAX_BUCKETS as i64, MAX_BUCKETS as i64)],
+            max_window,
+        );
+        assert_eq!(usage(&tracker, now, max_window), expected);
+        assert_eq!(usage(&tracker, now, 10 * max_window), expected);
+        for shard in tracker.shards.iter() {
+            assert!(shard.lock().expect("lock poisoned").len() <= MAX_BUCKETS);
+        }
+    }
+}
@@ -0,0 +1,804 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Validation of method parameters against the request limits.
+//!
+//! Each method's parameters are checked before the method is run, so that
+//! requests over the limits are rejected before they do any work, e.g.
+//! before the repo is loaded.  This is the only place the limits on
+//! parameters are checked: the methods themselves only convert the values
+//! they use, e.g. to check that a `limit` is not negative.
+
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::ops::RangeBounds;
+
+use source_control as thrift;
+
+use crate::errors;
+use crate::from_request::check_range_and_convert;
+use crate::limits;
+
+/// A trait for checking a thrift `Params` struct, or the specifier a method
+/// is called on, against the request limits.
+///
+/// There is deliberately no default implementation: each new `Params`
+/// struct must say which limits apply to it, or be listed in `no_limits!`
+/// if there are none.
+pub(crate) trait ValidateParams: Send + Sync {
+    fn validate(&self) -> Result<(), errors::ServiceError>;
+}
+
+fn check_range<R>(name: &'static str, value: i64, range: R) -> Result<(), errors::ServiceError>
+where
+    R: Debug + RangeBounds<i64>,
+{
+    check_range_and_convert::<_, i64, _>(name, value, range).map(|_| ())
+}
+
+fn check_count(name: &str, count: usize, max: i64) -> Result<(), errors::ServiceError> {
+    if count as i64 > max {
+        return Err(errors::invalid_request(format!(
+            "too many {} requested: {} (max {})",
+            name, count, max
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn check_identity_schemes(
+    schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+) -> Result<(), errors::ServiceError> {
+    check_count(
+        "identity schemes",
+        schemes.len(),
+        limits::identity_schemes_max_count(),
+    )
+}
+
+fn check_path(path: &str) -> Result<(), errors::ServiceError> {
+    let max_length = limits::path_max_length();
+    if path.len() as i64 > max_length {
+        return Err(errors::invalid_request(format!(
+            "path too long: {} bytes (max {})",
+            path.len(),
+            max_length
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn check_paths<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(), errors::ServiceError> {
+    paths.into_iter().try_for_each(|path| check_path(path))
+}
+
+// Implement `ValidateParams` for types with nothing to check.
+macro_rules! no_limits {
+    ( $( $type:ty ),* $(,)? ) => {
+        $(
+            impl ValidateParams for $type {
+                fn validate(&self) -> Result<(), errors::ServiceError> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+// Implement `ValidateParams` for types whose only limit is on the number of
+// `identity_schemes`.
+macro_rules! identity_schemes_only {
+    ( $( $type:ty ),* $(,)? ) => {
+        $(
+            impl ValidateParams for $type {
+                fn validate(&self) -> Result<(), errors::ServiceError> {
+                    check_identity_schemes(&self.identity_schemes)
+                }
+            }
+        )*
+    };
+}
+
+no_limits! {
+    thrift::RepoSpecifier,
+    thrift::CommitSpecifier,
+    thrift::ListReposParams,
+    thrift::ServiceLimitsParams,
+    thrift::ServiceCapabilitiesParams,
+    thrift::ServiceUsageParams,
+    thrift::AsyncRequestToken,
+    thrift::RepoInfoParams,
+    thrift::RepoListHookConfigsParams,
+    thrift::RepoCreateBookmarkParams,
+    thrift::RepoCreateBranchFromCommitParams,
+    thrift::RepoMoveBookmarkParams,
+    thrift::RepoDeleteBookmarkParams,
+    thrift::RepoPrepareCommitsParams,
+    thrift::RepoUploadFileContentParams,
+    thrift::RepoValidateCommitMessageParams,
+    thrift::CommitPhaseParams,
+    thrift::CommitDerivedDataInfoParams,
+    thrift::CommitIsAncestorOfParams,
+    thrift::CommitRunHooksParams,
+    thrift::CommitPathExistsParams,
+    thrift::CommitPathInfoParams,
+    thrift::TreeExistsParams,
+    thrift::FileExistsParams,
+    thrift::FileInfoParams,
+    thrift::FileDiffParams,
+    thrift::MegarepoAddConfigParams,
+    thrift::MegarepoReadConfigParams,
+    thrift::MegarepoAddTargetParams,
+    thrift::MegarepoAddTargetToken,
+    thrift::MegarepoAddBranchingTargetParams,
+    thrift::MegarepoAddBranchingTargetToken,
+    thrift::MegarepoChangeTargetConfigParams,
+    thrift::MegarepoChangeConfigToken,
+    thrift::MegarepoSyncChangesetParams,
+    thrift::MegarepoSyncChangesetToken,
+    thrift::MegarepoRemergeSourceParams,
+    thrift::MegarepoRemergeSourceToken,
+    thrift::UploadGitObjectParams,
+    thrift::CreateGitTreeParams,
+    thrift::CreateGitTagParams,
+    thrift::CommitMutationsParams,
+    thrift::CommitInfoParams,
+}
+
+identity_schemes_only! {
+    thrift::RepoResolveBookmarkParams,
+    thrift::RepoResolveGlobalrevParams,
+    thrift::RepoLatestGlobalrevParams,
+    thrift::RepoResolveCommitPrefixParams,
+    thrift::RepoBookmarkInfoParams,
+    thrift::RepoStatisticsParams,
+    thrift::CommitCommonBaseWithParams,
+    thrift::CommitLookupParams,
+    thrift::CommitLookupPushrebaseHistoryParams,
+    thrift::CommitContainedInBookmarksParams,
+    thrift::CommitLandabilityParams,
+    thrift::CommitLookupXRepoParams,
+    thrift::CommitPathBlameParams,
+    thrift::CommitFileAnnotateHistoryParams,
+    thrift::CommitPathLastChangedParams,
+    thrift::CommitPathFirstIntroducedParams,
+}
+
+impl ValidateParams for thrift::CommitPathSpecifier {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_path(&self.path)
+    }
+}
+
+impl ValidateParams for thrift::TreeSpecifier {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        match self {
+            thrift::TreeSpecifier::by_commit_path(commit_path) => commit_path.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ValidateParams for thrift::FileSpecifier {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        match self {
+            thrift::FileSpecifier::by_commit_path(commit_path) => commit_path.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ValidateParams for thrift::RepoCommitsInGlobalrevRangeParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        let max_range = thrift::consts::REPO_COMMITS_IN_GLOBALREV_RANGE_MAX_RANGE;
+        let range = self.end.saturating_sub(self.start);
+        if range > max_range {
+            return Err(errors::invalid_request(format!(
+                "globalrev range too large: {} (max {})",
+                range, max_range
+            ))
+            .into());
+        }
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoListBookmarksParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            0..=limits::repo_list_bookmarks_max_limit(),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoListRecentCommitsParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            0..=limits::repo_list_recent_commits_max_limit(),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoStackInfoParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range("limit", self.limit, 0..=limits::repo_stack_info_max_limit())?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoPathMultiCommitInfoParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_path(&self.path)?;
+        check_count(
+            "commits",
+            self.commits.len(),
+            thrift::consts::REPO_PATH_MULTI_COMMIT_INFO_MAX_COMMITS,
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoFileMetadataParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_count(
+            "ids",
+            self.ids.len(),
+            thrift::consts::REPO_FILE_METADATA_MAX_IDS,
+        )
+    }
+}
+
+impl ValidateParams for thrift::RepoCreateCommitParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_paths(self.changes.keys())?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoCreateStackParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_paths(self.commits.iter().flat_map(|commit| commit.changes.keys()))?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoLandStackParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_identity_schemes(&self.identity_schemes)?;
+        if let Some(old_identity_schemes) = &self.old_identity_schemes {
+            check_identity_schemes(old_identity_schemes)?;
+        }
+        Ok(())
+    }
+}
+
+impl ValidateParams for thrift::RepoUpdateSubmoduleParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_path(&self.path)?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::RepoRunHookOnHistoryParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            1..=thrift::consts::REPO_RUN_HOOK_ON_HISTORY_MAX_COMMITS,
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitTranslateIdsParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_count(
+            "commits",
+            self.commit_ids.len(),
+            thrift::consts::COMMIT_TRANSLATE_IDS_MAX_COMMITS,
+        )?;
+        check_identity_schemes(&self.to_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitMultipleLookupParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_count(
+            "commits",
+            self.commit_ids.len(),
+            thrift::consts::COMMIT_MULTIPLE_LOOKUP_MAX_COMMITS,
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitFileDiffsParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        // The number of paths is limited per page, so only the paths in the
+        // requested page count towards the limit.
+        let path_count = self.paths.len() as i64;
+        let page_start = self.page_start.unwrap_or(0).clamp(0, path_count);
+        let page_len = match self.page_size {
+            Some(page_size) => page_size.clamp(0, path_count - page_start),
+            None => path_count - page_start,
+        };
+        if page_len > limits::commit_file_diffs_path_count_limit() {
+            return Err(errors::diff_input_too_many_paths(page_len as usize).into());
+        }
+        check_paths(
+            self.paths
+                .iter()
+                .flat_map(|pair| pair.base_path.iter().chain(pair.other_path.iter())),
+        )
+    }
+}
+
+impl ValidateParams for thrift::CommitCompareParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        if let Some(paths) = &self.paths {
+            check_paths(paths)?;
+        }
+        if let Some(ordered_params) = &self.ordered_params {
+            check_range(
+                "limit",
+                ordered_params.limit,
+                0..=limits::commit_compare_ordered_max_limit(),
+            )?;
+            check_paths(&ordered_params.after_path)?;
+        }
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitFindFilesParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            0..=limits::commit_find_files_max_limit(),
+        )?;
+        check_paths(self.prefixes.iter().flatten())
+    }
+}
+
+impl ValidateParams for thrift::CommitHistoryParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit.into(),
+            0..=limits::history_max_limit().unwrap_or(i64::MAX),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitListDescendantBookmarksParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            0..=limits::commit_list_descendant_bookmarks_max_limit(),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitListDescendantsParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            1..=limits::commit_list_descendants_max_limit(),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::MegarepoPathMappingParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_count(
+            "paths",
+            self.paths.len(),
+            thrift::consts::MEGAREPO_PATH_MAPPING_MAX_PATHS,
+        )?;
+        check_paths(&self.paths)
+    }
+}
+
+impl ValidateParams for thrift::CommitMultiplePathInfoParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_paths(&self.paths)
+    }
+}
+
+impl ValidateParams for thrift::CommitMultiplePathLastChangedParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_paths(&self.paths)?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitPathHistoryParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit.into(),
+            0..=limits::history_max_limit().unwrap_or(i64::MAX),
+        )?;
+        check_identity_schemes(&self.identity_schemes)
+    }
+}
+
+impl ValidateParams for thrift::CommitPathCaseConflictsParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "limit",
+            self.limit,
+            0..=thrift::consts::COMMIT_PATH_CASE_CONFLICTS_MAX_LIMIT,
+        )
+    }
+}
+
+impl ValidateParams for thrift::SparseProfiles {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        match self {
+            thrift::SparseProfiles::profiles(profiles) => check_paths(profiles),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ValidateParams for thrift::CommitSparseProfileDeltaParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        self.profiles.validate()
+    }
+}
+
+impl ValidateParams for thrift::CommitSparseProfileSizeParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        self.profiles.validate()
+    }
+}
+
+impl ValidateParams for thrift::CommitSparseProfileFilesParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_paths(&self.profile_paths)
+    }
+}
+
+impl ValidateParams for thrift::CommitExportArchiveParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_path(&self.path_prefix)
+    }
+}
+
+impl ValidateParams for thrift::TreeListParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range("limit", self.limit, 0..=limits::tree_list_max_limit())
+    }
+}
+
+impl ValidateParams for thrift::TreeListRecursiveParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range("limit", self.limit, 0..=limits::tree_list_max_limit())
+    }
+}
+
+impl ValidateParams for thrift::TreePrefetchParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range("limit", self.limit, 0..=limits::tree_prefetch_max_limit())?;
+        check_paths(&self.path_prefixes)
+    }
+}
+
+impl ValidateParams for thrift::FileContentChunkParams {
+    fn validate(&self) -> Result<(), errors::ServiceError> {
+        check_range(
+            "size",
+            self.size,
+            0..=thrift::consts::FILE_CONTENT_CHUNK_SIZE_LIMIT,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that `make(value)` is valid exactly for values in `min..=max`.
+    fn assert_range<T: ValidateParams>(make: impl Fn(i64) -> T, min: i64, max: i64) {
+        assert!(make(min).validate().is_ok());
+        assert!(make(max).validate().is_ok());
+        assert!(make(min - 1).validate().is_err());
+        assert!(make(max + 1).validate().is_err());
+    }
+
+    /// Check that `make(count)` is valid exactly for counts up to `max`.
+    fn assert_count<T: ValidateParams>(make: impl Fn(usize) -> T, max: i64) {
+        assert!(make(0).validate().is_ok());
+        assert!(make(max as usize).validate().is_ok());
+        assert!(make(max as usize + 1).validate().is_err());
+    }
+
+    fn path(len: usize) -> String {
+        "a".repeat(len)
+    }
+
+    #[test]
+    fn test_paths() {
+        let max = limits::path_max_length() as usize;
+        let make = |len| thrift::CommitPathSpecifier {
+            path: path(len),
+            ..Default::default()
+        };
+        assert!(make(max).validate().is_ok());
+        assert!(make(max + 1).validate().is_err());
+        assert!(thrift::TreeSpecifier::by_commit_path(make(max + 1))
+            .validate()
+            .is_err());
+        assert!(thrift::FileSpecifier::by_commit_path(make(max + 1))
+            .validate()
+            .is_err());
+        assert!(thrift::TreePrefetchParams {
+            path_prefixes: vec![path(1), path(max + 1)],
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(thrift::CommitExportArchiveParams {
+            path_prefix: path(max + 1),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_repo_limits() {
+        assert_range(
+            |end| thrift::RepoCommitsInGlobalrevRangeParams {
+                start: 1,
+                end: end + 1,
+                ..Default::default()
+            },
+            0,
+            thrift::consts::REPO_COMMITS_IN_GLOBALREV_RANGE_MAX_RANGE,
+        );
+        assert_range(
+            |limit| thrift::RepoListBookmarksParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::repo_list_bookmarks_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::RepoListRecentCommitsParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::repo_list_recent_commits_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::RepoStackInfoParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::repo_stack_info_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::RepoRunHookOnHistoryParams {
+                limit,
+                ..Default::default()
+            },
+            1,
+            thrift::consts::REPO_RUN_HOOK_ON_HISTORY_MAX_COMMITS,
+        );
+        assert_count(
+            |count| thrift::RepoPathMultiCommitInfoParams {
+                commits: vec![Default::default(); count],
+                ..Default::default()
+            },
+            thrift::consts::REPO_PATH_MULTI_COMMIT_INFO_MAX_COMMITS,
+        );
+        assert_count(
+            |count| thrift::RepoFileMetadataParams {
+                ids: vec![Default::default(); count],
+                ..Default::default()
+            },
+            thrift::consts::REPO_FILE_METADATA_MAX_IDS,
+        );
+    }
+
+    #[test]
+    fn test_commit_limits() {
+        assert_count(
+            |count| thrift::CommitTranslateIdsParams {
+                commit_ids: vec![Default::default(); count],
+                ..Default::default()
+            },
+            thrift::consts::COMMIT_TRANSLATE_IDS_MAX_COMMITS,
+        );
+        assert_count(
+            |count| thrift::CommitMultipleLookupParams {
+                commit_ids: vec![Default::default(); count],
+                ..Default::default()
+            },
+            thrift::consts::COMMIT_MULTIPLE_LOOKUP_MAX_COMMITS,
+        );
+        assert_range(
+            |limit| thrift::CommitCompareParams {
+                ordered_params: Some(thrift::CommitCompareOrderedParams {
+                    limit,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            0,
+            limits::commit_compare_ordered_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::CommitFindFilesParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::commit_find_files_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::CommitListDescendantBookmarksParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::commit_list_descendant_bookmarks_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::CommitListDescendantsParams {
+                limit,
+                ..Default::default()
+            },
+            1,
+            limits::commit_list_descendants_max_limit(),
+        );
+        assert_count(
+            |count| thrift::MegarepoPathMappingParams {
+                paths: vec![path(1); count],
+                ..Default::default()
+            },
+            thrift::consts::MEGAREPO_PATH_MAPPING_MAX_PATHS,
+        );
+        assert_range(
+            |limit| thrift::CommitPathCaseConflictsParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            thrift::consts::COMMIT_PATH_CASE_CONFLICTS_MAX_LIMIT,
+        );
+    }
+
+    #[test]
+    fn test_identity_schemes() {
+        let schemes = |count: usize| {
+            (0..count as i32)
+                .map(thrift::CommitIdentityScheme)
+                .collect::<BTreeSet<_>>()
+        };
+        assert_count(
+            |count| thrift::RepoResolveBookmarkParams {
+                identity_schemes: schemes(count),
+                ..Default::default()
+            },
+            limits::identity_schemes_max_count(),
+        );
+        assert_count(
+            |count| thrift::CommitTranslateIdsParams {
+                to_schemes: schemes(count),
+                ..Default::default()
+            },
+            limits::identity_schemes_max_count(),
+        );
+        assert_count(
+            |count| thrift::RepoLandStackParams {
+                old_identity_schemes: Some(schemes(count)),
+                ..Default::default()
+            },
+            limits::identity_schemes_max_count(),
+        );
+        // Sets are checked alongside the method's other limits.
+        assert!(thrift::RepoListBookmarksParams {
+            identity_schemes: schemes(limits::identity_schemes_max_count() as usize + 1),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_history_limits() {
+        // History is unbounded unless a limit is configured.
+        let history = |limit| thrift::CommitHistoryParams {
+            limit,
+            ..Default::default()
+        };
+        assert!(history(i32::MAX).validate().is_ok());
+        assert!(history(-1).validate().is_err());
+        let path_history = |limit| thrift::CommitPathHistoryParams {
+            limit,
+            ..Default::default()
+        };
+        assert!(path_history(i32::MAX).validate().is_ok());
+        assert!(path_history(-1).validate().is_err());
+    }
+
+    #[test]
+    fn test_commit_file_diffs_path_count() {
+        let max = limits::commit_file_diffs_path_count_limit();
+        let make = |count: i64, page_start, page_size| thrift::CommitFileDiffsParams {
+            paths: vec![Default::default(); count as usize],
+            page_start,
+            page_size,
+            ..Default::default()
+        };
+        assert!(make(max, None, None).validate().is_ok());
+        assert!(make(max + 1, None, None).validate().is_err());
+        // Only the requested page counts towards the limit.
+        assert!(make(max * 2, None, Some(max)).validate().is_ok());
+        assert!(make(max * 2, None, Some(max + 1)).validate().is_err());
+        assert!(make(max * 2, Some(max), None).validate().is_ok());
+        assert!(make(max * 2, Some(max - 1), None).validate().is_err());
+    }
+
+    #[test]
+    fn test_tree_and_file_limits() {
+        assert_range(
+            |limit| thrift::TreeListParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::tree_list_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::TreeListRecursiveParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::tree_list_max_limit(),
+        );
+        assert_range(
+            |limit| thrift::TreePrefetchParams {
+                limit,
+                ..Default::default()
+            },
+            0,
+            limits::tree_prefetch_max_limit(),
+        );
+        assert_range(
+            |size| thrift::FileContentChunkParams {
+                size,
+                ..Default::default()
+            },
+            0,
+            thrift::consts::FILE_CONTENT_CHUNK_SIZE_LIMIT,
+        );
+    }
+}
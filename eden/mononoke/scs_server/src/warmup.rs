@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::format_err;
+use anyhow::Error;
+use commit_graph::CommitGraphRef;
+use futures::stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use mononoke_api::CoreContext;
+use mononoke_api::Mononoke;
+use slog::info;
+use slog::warn;
+
+/// Number of bookmarks per repo whose commits are loaded concurrently.
+const BOOKMARK_CONCURRENCY: usize = 10;
+
+/// Warm up the given repos so that the first requests after startup don't
+/// pay for cold caches.  At most `concurrency` repos are warmed at once, to
+/// avoid overloading the backends when many servers restart together.
+///
+/// `warming_up` is cleared once all repos have been warmed, whether or not
+/// warming succeeded.
+pub async fn warm_up_repos(
+    ctx: CoreContext,
+    mononoke: Arc<Mononoke>,
+    repos: Vec<String>,
+    concurrency: usize,
+    warming_up: Arc<AtomicBool>,
+) {
+    info!(ctx.logger(), "Warming up {} repos", repos.len());
+    stream::iter(repos)
+        .for_each_concurrent(concurrency.max(1), |repo_name| {
+            let ctx = &ctx;
+            let mononoke = &mononoke;
+            async move {
+                match warm_up_repo(ctx, mononoke, &repo_name).await {
+                    Ok(bookmark_count) => info!(
+                        ctx.logger(),
+                        "Warmed up repo {} with {} bookmarks", repo_name, bookmark_count
+                    ),
+                    Err(e) => warn!(
+                        ctx.logger(),
+                        "Failed to warm up repo {}: {:?}", repo_name, e
+                    ),
+                }
+            }
+        })
+        .await;
+    info!(ctx.logger(), "Warm-up complete");
+    warming_up.store(false, Ordering::Relaxed);
+}
+
+/// Build a repo context, list its bookmarks and load the commit graph
+/// entries for each bookmarked commit.  Returns the number of bookmarks.
+async fn warm_up_repo(
+    ctx: &CoreContext,
+    mononoke: &Mononoke,
+    repo_name: &str,
+) -> Result<usize, Error> {
+    let repo = mononoke
+        .repo(ctx.clone(), repo_name)
+        .await?
+        .ok_or_else(|| format_err!("repo not found"))?
+        .build()
+        .await?;
+    let bookmarks = repo
+        .list_bookmarks(false, None, None, None)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    let commit_graph = repo.repo().commit_graph();
+    stream::iter(bookmarks.iter())
+        .map(|(_name, cs_id)| async move {
+            commit_graph.changeset_generation(ctx, *cs_id).await?;
+            Ok::<_, Error>(())
+        })
+        .buffer_unordered(BOOKMARK_CONCURRENCY)
+        .try_collect::<()>()
+        .await?;
+    Ok(bookmarks.len())
+}
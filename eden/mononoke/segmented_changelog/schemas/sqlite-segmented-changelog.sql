@@ -21,6 +21,21 @@ CREATE TABLE IF NOT EXISTS segmented_changelog_version (
   idmap_version INTEGER NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS segmented_changelog_version_history (
+  repo_id INTEGER NOT NULL,
+  iddag_version VARBINARY(32) NOT NULL,
+  idmap_version INTEGER NOT NULL,
+  created_at BIGINT NOT NULL,
+  PRIMARY KEY (repo_id, iddag_version, idmap_version)
+);
+
+CREATE TABLE IF NOT EXISTS segmented_changelog_version_pin (
+  repo_id INTEGER PRIMARY KEY,
+  iddag_version VARBINARY(32) NOT NULL,
+  idmap_version INTEGER NOT NULL,
+  pinned_at BIGINT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS segmented_changelog_idmap_copy_mappings (
   repo_id INTEGER NOT NULL,
   idmap_version INTEGER NOT NULL,
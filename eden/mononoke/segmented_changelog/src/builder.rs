@@ -7,20 +7,32 @@
 
 use std::sync::Arc;
 
+use anyhow::bail;
 use anyhow::Context;
+use anyhow::Error;
 use anyhow::Result;
 use blobstore::Blobstore;
+use blobstore_factory::MetadataSqlFactory;
 use bookmarks::ArcBookmarks;
 use caching_ext::CacheHandlerFactory;
 use changeset_fetcher::ArcChangesetFetcher;
+use commit_graph::ArcCommitGraph;
 use context::CoreContext;
+use metaconfig_types::RemoteMetadataDatabaseConfig;
 use metaconfig_types::SegmentedChangelogConfig;
+use metaconfig_types::ShardableRemoteDatabaseConfig;
 use mononoke_types::RepositoryId;
 use repo_identity::RepoIdentity;
+use slog::info;
+use slog::warn;
 use sql_construct::SqlConstruct;
 use sql_construct::SqlConstructFromMetadataDatabaseConfig;
+use sql_construct::SqlShardableConstructFromMetadataDatabaseConfig;
+use sql_construct::SqlShardedConstruct;
 use sql_ext::replication::NoReplicaLagMonitor;
+use sql_ext::replication::ReplicaLagMonitor;
 use sql_ext::SqlConnections;
+use sql_ext::SqlShardedConnections;
 
 use crate::iddag::IdDagSaveStore;
 use crate::idmap::CacheHandlers;
@@ -30,16 +42,22 @@ use crate::manager::SegmentedChangelogManager;
 use crate::manager::SegmentedChangelogType;
 use crate::on_demand::OnDemandUpdateSegmentedChangelog;
 use crate::periodic_reload::PeriodicReloadSegmentedChangelog;
+use crate::reload_scheduler::SegmentedChangelogReloadScheduler;
 use crate::seedheads_from_config;
+use crate::shadow::ShadowReadSegmentedChangelog;
 use crate::version_store::SegmentedChangelogVersionStore;
 use crate::CloneHints;
+use crate::DagId;
 use crate::DisabledSegmentedChangelog;
 use crate::InProcessIdDag;
 use crate::JobType;
 use crate::SegmentedChangelog;
+use crate::SegmentedChangelogLoadError;
 
+/// The SQL connections for segmented changelog, and for the IdMap range
+/// shards, if the IdMap is sharded.
 #[derive(Clone)]
-pub struct SegmentedChangelogSqlConnections(pub SqlConnections);
+pub struct SegmentedChangelogSqlConnections(pub SqlConnections, Vec<(DagId, SqlConnections)>);
 
 impl SqlConstruct for SegmentedChangelogSqlConnections {
     const LABEL: &'static str = "segmented_changelog";
@@ -47,12 +65,109 @@ impl SqlConstruct for SegmentedChangelogSqlConnections {
     const CREATION_QUERY: &'static str = include_str!("../schemas/sqlite-segmented-changelog.sql");
 
     fn from_sql_connections(connections: SqlConnections) -> Self {
-        Self(connections)
+        Self(connections, Vec::new())
     }
 }
 
 impl SqlConstructFromMetadataDatabaseConfig for SegmentedChangelogSqlConnections {}
 
+impl SegmentedChangelogSqlConnections {
+    /// Open the connections for a repo, including the IdMap range shards
+    /// if its config has any.
+    pub async fn open(
+        sql_factory: &MetadataSqlFactory,
+        config: &SegmentedChangelogConfig,
+    ) -> Result<Self> {
+        let connections = sql_factory.open::<Self>().await?;
+        if config.idmap_range_shard_starts.is_empty() {
+            return Ok(connections);
+        }
+        let shards = sql_factory
+            .open_shardable::<SegmentedChangelogIdMapShardConnections>()
+            .await
+            .context("opening segmented changelog idmap shards")?;
+        connections.with_idmap_range_shards(&config.idmap_range_shard_starts, shards.0)
+    }
+
+    /// Use range shards for the IdMap, starting at each of `starts`.  A
+    /// single shard is used for every range, e.g. for a local database.
+    pub fn with_idmap_range_shards(
+        mut self,
+        starts: &[u64],
+        shards: Vec<SqlConnections>,
+    ) -> Result<Self> {
+        if shards.len() != 1 && shards.len() != starts.len() {
+            bail!(
+                "segmented changelog has {} idmap range shard starts but {} shards",
+                starts.len(),
+                shards.len()
+            );
+        }
+        self.1 = starts
+            .iter()
+            .zip(shards.iter().cycle())
+            .map(|(start, connections)| (DagId(*start), connections.clone()))
+            .collect();
+        Ok(self)
+    }
+
+    pub(crate) fn idmap_factory(
+        &self,
+        replica_lag_monitor: Arc<dyn ReplicaLagMonitor>,
+        repo_id: RepositoryId,
+    ) -> IdMapFactory {
+        IdMapFactory::new(self.0.clone(), replica_lag_monitor, repo_id)
+            .with_range_shards(self.1.clone())
+    }
+
+    /// The connections holding IdMap tables: the unsharded table, then
+    /// each range shard.
+    pub(crate) fn idmap_connections(&self) -> impl Iterator<Item = &SqlConnections> {
+        std::iter::once(&self.0).chain(self.1.iter().map(|(_, connections)| connections))
+    }
+}
+
+/// The shards of the database holding the IdMap range shards.
+struct SegmentedChangelogIdMapShardConnections(Vec<SqlConnections>);
+
+impl SqlShardedConstruct for SegmentedChangelogIdMapShardConnections {
+    const LABEL: &'static str = "segmented_changelog_idmap";
+
+    const CREATION_QUERY: &'static str = include_str!("../schemas/sqlite-segmented-changelog.sql");
+
+    fn from_sql_shard_connections(connections: SqlShardedConnections) -> Self {
+        let SqlShardedConnections {
+            read_connections,
+            read_master_connections,
+            write_connections,
+        } = connections;
+        Self(
+            write_connections
+                .into_iter()
+                .zip(read_connections)
+                .zip(read_master_connections)
+                .map(
+                    |((write_connection, read_connection), read_master_connection)| {
+                        SqlConnections {
+                            write_connection,
+                            read_connection,
+                            read_master_connection,
+                        }
+                    },
+                )
+                .collect(),
+        )
+    }
+}
+
+impl SqlShardableConstructFromMetadataDatabaseConfig for SegmentedChangelogIdMapShardConnections {
+    fn remote_database_config(
+        remote: &RemoteMetadataDatabaseConfig,
+    ) -> Option<&ShardableRemoteDatabaseConfig> {
+        remote.segmented_changelog_idmap.as_ref()
+    }
+}
+
 pub fn new_test_segmented_changelog(
     ctx: CoreContext,
     repo_id: RepositoryId,
@@ -91,7 +206,7 @@ pub async fn new_server_segmented_changelog_manager<'a>(
     let seed_heads = seedheads_from_config(ctx, &config, JobType::Server)
         .context("finding segmented changelog heads")?;
     let replica_lag_monitor = Arc::new(NoReplicaLagMonitor());
-    let mut idmap_factory = IdMapFactory::new(connections.0.clone(), replica_lag_monitor, repo_id);
+    let mut idmap_factory = connections.idmap_factory(replica_lag_monitor, repo_id);
     if let Some(cache_handler_factory) = cache_handler_factory {
         idmap_factory =
             idmap_factory.with_cache_handlers(CacheHandlers::new(cache_handler_factory));
@@ -111,7 +226,8 @@ pub async fn new_server_segmented_changelog_manager<'a>(
             update_to_master_bookmark_period: config.update_to_master_bookmark_period,
         },
         Some(clone_hints),
-    );
+    )
+    .with_read_fallback(config.read_fallback_to_changesets);
     Ok(manager)
 }
 
@@ -121,9 +237,11 @@ pub async fn new_server_segmented_changelog<'a>(
     config: SegmentedChangelogConfig,
     connections: SegmentedChangelogSqlConnections,
     changeset_fetcher: ArcChangesetFetcher,
+    commit_graph: ArcCommitGraph,
     bookmarks: ArcBookmarks,
     blobstore: Arc<dyn Blobstore>,
     cache_handler_factory: Option<CacheHandlerFactory>,
+    reload_scheduler: Option<&SegmentedChangelogReloadScheduler>,
 ) -> Result<Arc<dyn SegmentedChangelog + Send + Sync>> {
     if !config.enabled {
         return Ok(Arc::new(DisabledSegmentedChangelog::new()));
@@ -148,6 +266,7 @@ pub async fn new_server_segmented_changelog<'a>(
         )?));
     }
     let reload_dag_save_period = config.reload_dag_save_period;
+    let shadow_reads = config.shadow_reads;
     let manager = new_server_segmented_changelog_manager(
         ctx,
         repo_identity,
@@ -160,15 +279,57 @@ pub async fn new_server_segmented_changelog<'a>(
     )
     .await?;
     let name = repo_identity.name().to_string();
+    // Without a dag to start from, e.g. because the repo isn't seeded yet,
+    // the read fallback can still answer location lookups.
+    let unloaded = manager.with_fallback(Arc::new(DisabledSegmentedChangelog::new()));
+    let read_fallback = manager.read_fallback();
     let sc = match reload_dag_save_period {
-        None => {
-            let (sc, _sc_version) = manager.load(ctx).await?;
-            sc
+        None => manager
+            .load(ctx)
+            .await
+            .map(|(sc, _sc_version)| sc)
+            .map_err(Error::from),
+        Some(reload_period) => PeriodicReloadSegmentedChangelog::start_from_manager(
+            ctx,
+            reload_period,
+            manager,
+            name,
+            reload_scheduler,
+        )
+        .await
+        .map(|sc| Arc::new(sc) as Arc<dyn SegmentedChangelog + Send + Sync>),
+    };
+    let sc = match sc {
+        Ok(sc) => sc,
+        Err(e) if read_fallback => {
+            let not_seeded = e
+                .downcast_ref::<SegmentedChangelogLoadError>()
+                .map_or(false, SegmentedChangelogLoadError::is_not_seeded);
+            if not_seeded {
+                // Expected until the repo is seeded, so not worth a warning.
+                info!(
+                    ctx.logger(),
+                    "repo {}: segmented changelog is not seeded, serving from read fallback",
+                    repo_identity.id(),
+                );
+            } else {
+                warn!(
+                    ctx.logger(),
+                    "repo {}: failed to load segmented changelog, serving from read fallback: {:?}",
+                    repo_identity.id(),
+                    e
+                );
+            }
+            unloaded
         }
-        Some(reload_period) => Arc::new(
-            PeriodicReloadSegmentedChangelog::start_from_manager(ctx, reload_period, manager, name)
-                .await?,
-        ),
+        Err(e) => return Err(e),
     };
-    Ok(sc)
+    if shadow_reads {
+        Ok(Arc::new(ShadowReadSegmentedChangelog::new(
+            sc,
+            commit_graph,
+        )))
+    } else {
+        Ok(sc)
+    }
 }
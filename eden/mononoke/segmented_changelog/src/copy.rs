@@ -18,7 +18,6 @@ use sql_ext::replication::ReplicaLagMonitor;
 
 use crate::iddag::IdDagSaveStore;
 use crate::idmap::IdMap;
-use crate::idmap::SqlIdMap;
 use crate::types::SegmentedChangelogVersion;
 use crate::version_store::SegmentedChangelogVersionStore;
 use crate::InProcessIdDag;
@@ -55,12 +54,8 @@ pub async fn copy_segmented_changelog(
         .await
         .with_context(|| format!("repo {}: failed to load iddag", repo_id))?;
 
-    let idmap = SqlIdMap::new(
-        connections.0,
-        replica_lag_monitor,
-        repo_id,
-        sc_version.idmap_version,
-    );
+    let idmap_factory = connections.idmap_factory(replica_lag_monitor, repo_id);
+    let idmap = idmap_factory.for_writer(ctx, sc_version.idmap_version)?;
 
     let dag_limit = idmap
         .find_many_dag_ids(ctx, heads.clone())
@@ -70,7 +65,9 @@ pub async fn copy_segmented_changelog(
         .with_context(|| format!("repo {}: no valid heads in {:?}", repo_id, heads))?;
 
     let new_idmap_version = sc_version.idmap_version.bump();
-    let _new_idmap = idmap.copy(dag_limit, new_idmap_version).await?;
+    idmap_factory
+        .copy(ctx, sc_version.idmap_version, dag_limit, new_idmap_version)
+        .await?;
 
     // Build an IdDag - we can use the old IdDag's shape to speed things up,
     // as we know that the new IdDag is a subset of the old one.
@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Error;
+use mononoke_types::RepositoryId;
+use thiserror::Error;
+
+/// Why a repo's segmented changelog could not be loaded.
+///
+/// A repo that was never seeded needs seeding rather than alerting, so it is
+/// reported separately from failures to read a dag that should be there.
+#[derive(Debug, Error)]
+pub enum SegmentedChangelogLoadError {
+    #[error("repo {0}: segmented changelog metadata not found, maybe repo is not seeded")]
+    NotSeeded(RepositoryId),
+    #[error("repo {0}: error loading segmented changelog version")]
+    VersionStoreUnavailable(RepositoryId, #[source] Error),
+    #[error("repo {0}: failed to load iddag")]
+    IdDagCorrupt(RepositoryId, #[source] Error),
+    #[error("repo {0}: failed to load idmap")]
+    IdMapUnavailable(RepositoryId, #[source] Error),
+}
+
+impl SegmentedChangelogLoadError {
+    pub fn is_not_seeded(&self) -> bool {
+        matches!(self, Self::NotSeeded(_))
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Read fallback for Segmented Changelog.
+//!
+//! While a repo is being rolled out, the dag may not be able to answer a
+//! location-to-hash lookup: the repo may not be seeded yet, or the location
+//! may refer to changesets the loaded dag doesn't have.  Rather than failing
+//! the request, the lookup is answered by following first parents with the
+//! changeset fetcher, and the fallback is logged.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use changeset_fetcher::ArcChangesetFetcher;
+use context::CoreContext;
+use mercurial_types::HgChangesetId;
+use mononoke_types::ChangesetId;
+use mononoke_types::RepositoryId;
+use slog::warn;
+use stats::prelude::*;
+
+use crate::parents::first_parent_walk;
+use crate::CloneData;
+use crate::Location;
+use crate::SegmentedChangelog;
+
+define_stats! {
+    prefix = "mononoke.segmented_changelog.fallback";
+    location_fallback: timeseries(Sum),
+    location_fallback_failed: timeseries(Sum),
+}
+
+/// The most changesets a fallback lookup will visit before giving up.
+const MAX_FALLBACK_TRAVERSAL: u64 = 10_000;
+
+pub struct FallbackSegmentedChangelog {
+    inner: Arc<dyn SegmentedChangelog + Send + Sync>,
+    changeset_fetcher: ArcChangesetFetcher,
+    repo_id: RepositoryId,
+}
+
+impl FallbackSegmentedChangelog {
+    pub fn new(
+        inner: Arc<dyn SegmentedChangelog + Send + Sync>,
+        changeset_fetcher: ArcChangesetFetcher,
+        repo_id: RepositoryId,
+    ) -> Self {
+        Self {
+            inner,
+            changeset_fetcher,
+            repo_id,
+        }
+    }
+}
+
+#[async_trait]
+impl SegmentedChangelog for FallbackSegmentedChangelog {
+    async fn location_to_many_changeset_ids(
+        &self,
+        ctx: &CoreContext,
+        location: Location<ChangesetId>,
+        count: u64,
+    ) -> Result<Vec<ChangesetId>> {
+        let dag_err = match self
+            .inner
+            .location_to_many_changeset_ids(ctx, location, count)
+            .await
+        {
+            Ok(cs_ids) => return Ok(cs_ids),
+            Err(e) => e,
+        };
+
+        STATS::location_fallback.add_value(1);
+        warn!(
+            ctx.logger(),
+            "repo {}: falling back to changesets for {:?} count {}: {:?}",
+            self.repo_id,
+            location,
+            count,
+            dag_err
+        );
+        let changeset_fetcher = &self.changeset_fetcher;
+        let result = first_parent_walk(
+            location,
+            count,
+            MAX_FALLBACK_TRAVERSAL,
+            |cs_id| async move {
+                let parents = changeset_fetcher.get_parents(ctx, cs_id).await?;
+                Ok(parents.first().copied())
+            },
+        )
+        .await;
+        ctx.scuba()
+            .clone()
+            .add("repo_id", self.repo_id.id())
+            .add(
+                "fallback_request",
+                format!("{:?} count {}", location, count),
+            )
+            .add("fallback_success", matches!(result, Ok(Some(_))))
+            .log_with_msg(
+                "Segmented changelog read fallback",
+                Some(format!("{:?}", dag_err)),
+            );
+
+        match result {
+            Ok(Some(cs_ids)) => Ok(cs_ids),
+            // Too far to walk, so the dag's error is the more useful one.
+            Ok(None) => {
+                STATS::location_fallback_failed.add_value(1);
+                Err(dag_err)
+            }
+            Err(e) => {
+                STATS::location_fallback_failed.add_value(1);
+                Err(dag_err.context(format!("fallback to changesets also failed: {:?}", e)))
+            }
+        }
+    }
+
+    async fn many_changeset_ids_to_locations(
+        &self,
+        ctx: &CoreContext,
+        master_heads: Vec<ChangesetId>,
+        cs_ids: Vec<ChangesetId>,
+    ) -> Result<HashMap<ChangesetId, Result<Location<ChangesetId>>>> {
+        self.inner
+            .many_changeset_ids_to_locations(ctx, master_heads, cs_ids)
+            .await
+    }
+
+    async fn clone_data(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<(CloneData<ChangesetId>, HashMap<ChangesetId, HgChangesetId>)> {
+        self.inner.clone_data(ctx).await
+    }
+
+    async fn pull_data(
+        &self,
+        ctx: &CoreContext,
+        common: Vec<ChangesetId>,
+        missing: Vec<ChangesetId>,
+    ) -> Result<CloneData<ChangesetId>> {
+        self.inner.pull_data(ctx, common, missing).await
+    }
+
+    async fn disabled(&self, ctx: &CoreContext) -> Result<bool> {
+        self.inner.disabled(ctx).await
+    }
+
+    async fn is_ancestor(
+        &self,
+        ctx: &CoreContext,
+        ancestor: ChangesetId,
+        descendant: ChangesetId,
+    ) -> Result<Option<bool>> {
+        self.inner.is_ancestor(ctx, ancestor, descendant).await
+    }
+
+    async fn greatest_common_ancestors(
+        &self,
+        ctx: &CoreContext,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        self.inner.greatest_common_ancestors(ctx, a, b).await
+    }
+
+    async fn range(
+        &self,
+        ctx: &CoreContext,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        self.inner.range(ctx, start, end, limit).await
+    }
+
+    async fn build_up_to_heads(&self, ctx: &CoreContext, heads: &[ChangesetId]) -> Result<bool> {
+        self.inner.build_up_to_heads(ctx, heads).await
+    }
+
+    async fn ensure_fresh(&self, ctx: &CoreContext) -> Result<()> {
+        self.inner.ensure_fresh(ctx).await
+    }
+}
@@ -0,0 +1,666 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Garbage collection of old Segmented Changelog versions.
+//!
+//! Every update saves a new IdDag to the blobstore, and every reseed writes a
+//! new IdMap (and its clone hints) to SQL.  Once servers have moved on to a
+//! newer version, nothing reads the old ones.  GC keeps the most recent
+//! versions, and any version set within a retention window, so that servers
+//! which have not reloaded yet and rollbacks keep working, and deletes the
+//! rest.
+//!
+//! Versions are found using the version history recorded by
+//! `SegmentedChangelogVersionStore`.  IdDags saved before history was
+//! recorded, or whose history failed to be recorded, can't be found, so are
+//! left alone.  IdMaps are found by listing the IdMap table, and the table in
+//! each IdMap range shard, so old IdMaps are collected regardless.
+
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use blobstore::BlobstoreUnlinkOps;
+use context::CoreContext;
+use context::PerfCounterType;
+use mononoke_types::RepositoryId;
+use slog::info;
+use sql_ext::mononoke_queries;
+
+use crate::builder::SegmentedChangelogSqlConnections;
+use crate::iddag::IdDagSaveStore;
+use crate::types::IdDagVersion;
+use crate::types::IdMapVersion;
+use crate::types::SegmentedChangelogVersion;
+use crate::version_store::SegmentedChangelogVersionStore;
+
+mononoke_queries! {
+    read SelectIdMapVersions(repo_id: RepositoryId) -> (IdMapVersion) {
+        "
+        SELECT DISTINCT version
+        FROM segmented_changelog_idmap
+        WHERE repo_id = {repo_id}
+        "
+    }
+
+    read SelectCopyMappings(repo_id: RepositoryId) -> (IdMapVersion, IdMapVersion) {
+        "
+        SELECT idmap_version, copied_version
+        FROM segmented_changelog_idmap_copy_mappings
+        WHERE repo_id = {repo_id}
+        "
+    }
+
+    read SelectCloneHintVersions(repo_id: RepositoryId) -> (IdMapVersion) {
+        "
+        SELECT DISTINCT idmap_version
+        FROM segmented_changelog_clone_hints
+        WHERE repo_id = {repo_id}
+        "
+    }
+
+    read SelectCloneHintBlobs(repo_id: RepositoryId, version: IdMapVersion) -> (Vec<u8>) {
+        "
+        SELECT blob_name
+        FROM segmented_changelog_clone_hints
+        WHERE repo_id = {repo_id} AND idmap_version = {version}
+        "
+    }
+
+    write DeleteIdMapVersionBatch(repo_id: RepositoryId, version: IdMapVersion, limit: u64) {
+        none,
+        mysql(
+            "
+            DELETE FROM segmented_changelog_idmap
+            WHERE repo_id = {repo_id} AND version = {version}
+            LIMIT {limit}
+            "
+        )
+        sqlite(
+            "
+            DELETE FROM segmented_changelog_idmap
+            WHERE rowid IN (
+                SELECT rowid FROM segmented_changelog_idmap
+                WHERE repo_id = {repo_id} AND version = {version}
+                LIMIT {limit}
+            )
+            "
+        )
+    }
+
+    write DeleteCopyMappings(repo_id: RepositoryId, version: IdMapVersion) {
+        none,
+        "
+        DELETE FROM segmented_changelog_idmap_copy_mappings
+        WHERE repo_id = {repo_id} AND idmap_version = {version}
+        "
+    }
+
+    write DeleteCloneHints(repo_id: RepositoryId, version: IdMapVersion) {
+        none,
+        "
+        DELETE FROM segmented_changelog_clone_hints
+        WHERE repo_id = {repo_id} AND idmap_version = {version}
+        "
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SegmentedChangelogGcOptions {
+    /// Number of most recently set versions to keep.
+    pub keep_last: usize,
+    /// Keep any version that was set more recently than this.
+    pub retention: Duration,
+    /// Work out what would be deleted, but don't delete anything.
+    pub dry_run: bool,
+    /// Maximum number of IdMap entries to delete in one query.  IdMaps have
+    /// an entry per commit, so are deleted a batch at a time to avoid long
+    /// running transactions and replication lag.
+    pub delete_batch_size: u64,
+}
+
+/// What was (or, for a dry run, would have been) deleted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SegmentedChangelogGcReport {
+    pub retained_versions: Vec<SegmentedChangelogVersion>,
+    pub deleted_versions: Vec<SegmentedChangelogVersion>,
+    pub deleted_iddag_versions: Vec<IdDagVersion>,
+    pub deleted_idmap_versions: Vec<IdMapVersion>,
+    pub deleted_clone_hint_blobs: usize,
+}
+
+pub struct SegmentedChangelogGc {
+    repo_id: RepositoryId,
+    connections: SegmentedChangelogSqlConnections,
+    version_store: SegmentedChangelogVersionStore,
+    blobstore: Arc<dyn BlobstoreUnlinkOps>,
+}
+
+impl SegmentedChangelogGc {
+    pub fn new(
+        repo_id: RepositoryId,
+        connections: SegmentedChangelogSqlConnections,
+        blobstore: Arc<dyn BlobstoreUnlinkOps>,
+    ) -> Self {
+        let version_store = SegmentedChangelogVersionStore::new(connections.0.clone(), repo_id);
+        Self {
+            repo_id,
+            connections,
+            version_store,
+            blobstore,
+        }
+    }
+
+    pub async fn run(
+        &self,
+        ctx: &CoreContext,
+        options: &SegmentedChangelogGcOptions,
+    ) -> Result<SegmentedChangelogGcReport> {
+        let current = match self.version_store.get(ctx).await? {
+            Some(current) => current,
+            None => {
+                info!(
+                    ctx.logger(),
+                    "repo {}: no segmented changelog version, nothing to collect", self.repo_id
+                );
+                return Ok(SegmentedChangelogGcReport::default());
+            }
+        };
+
+        // Servers may be pinned to an older version, so keep it too.  The pin
+        // is read from master, as a pin missed because of replication lag
+        // would be deleted.  If it can't be read then nothing is safe to
+        // delete.
+        let pinned = self
+            .version_store
+            .get_pin_master(ctx)
+            .await
+            .with_context(|| {
+                format!(
+                    "repo {}: failed to read the pinned version, not collecting",
+                    self.repo_id
+                )
+            })?
+            .map(|(version, _pinned_at)| version);
+
+        let mut report = SegmentedChangelogGcReport::default();
+        for (index, (version, created_at)) in self
+            .version_store
+            .history(ctx)
+            .await?
+            .into_iter()
+            .enumerate()
+        {
+            let recent = created_at.since_seconds() < options.retention.as_secs() as i64;
+            let in_use = version == current || Some(version) == pinned;
+            if index < options.keep_last || recent || in_use {
+                report.retained_versions.push(version);
+            } else {
+                report.deleted_versions.push(version);
+            }
+        }
+        for version in std::iter::once(current).chain(pinned) {
+            if !report.retained_versions.contains(&version) {
+                report.retained_versions.push(version);
+            }
+        }
+
+        let retained_iddag_versions: HashSet<_> = report
+            .retained_versions
+            .iter()
+            .map(|version| version.iddag_version)
+            .collect();
+        report.deleted_iddag_versions = report
+            .deleted_versions
+            .iter()
+            .map(|version| version.iddag_version)
+            .filter(|iddag_version| !retained_iddag_versions.contains(iddag_version))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let retained_idmap_versions = self.retained_idmap_versions(ctx, &report).await?;
+        report.deleted_idmap_versions = self
+            .all_idmap_versions(ctx)
+            .await?
+            .into_iter()
+            // Newer IdMaps may be in the middle of being built by the seeder.
+            .filter(|version| {
+                *version < current.idmap_version && !retained_idmap_versions.contains(version)
+            })
+            .collect();
+
+        for version in report.deleted_idmap_versions.iter() {
+            let blobs = SelectCloneHintBlobs::query(
+                &self.connections.0.read_master_connection,
+                &self.repo_id,
+                version,
+            )
+            .await?;
+            report.deleted_clone_hint_blobs += blobs.len();
+            if !options.dry_run {
+                for (blob_name,) in blobs {
+                    let key = String::from_utf8(blob_name)
+                        .context("clone hint blob name should be UTF-8")?;
+                    self.unlink_if_present(ctx, &key).await?;
+                }
+                self.delete_idmap_version(ctx, *version, options.delete_batch_size.max(1))
+                    .await?;
+            }
+        }
+
+        if !options.dry_run {
+            for iddag_version in report.deleted_iddag_versions.iter() {
+                self.unlink_if_present(ctx, &IdDagSaveStore::blobstore_key(*iddag_version))
+                    .await?;
+            }
+            for version in report.deleted_versions.iter() {
+                self.version_store.delete_history(ctx, *version).await?;
+            }
+        }
+
+        info!(
+            ctx.logger(),
+            "repo {}: {}segmented changelog gc retained {} versions, deleted {} versions ({} iddags, {} idmaps, {} clone hint blobs)",
+            self.repo_id,
+            if options.dry_run { "[dry run] " } else { "" },
+            report.retained_versions.len(),
+            report.deleted_versions.len(),
+            report.deleted_iddag_versions.len(),
+            report.deleted_idmap_versions.len(),
+            report.deleted_clone_hint_blobs,
+        );
+        Ok(report)
+    }
+
+    /// IdMap versions used by retained versions, including the versions
+    /// they were copied from.
+    async fn retained_idmap_versions(
+        &self,
+        ctx: &CoreContext,
+        report: &SegmentedChangelogGcReport,
+    ) -> Result<HashSet<IdMapVersion>> {
+        // Each IdMap table has its own copy mappings.
+        let mut copy_mappings = Vec::new();
+        for connections in self.connections.idmap_connections() {
+            ctx.perf_counters()
+                .increment_counter(PerfCounterType::SqlReadsMaster);
+            copy_mappings.extend(
+                SelectCopyMappings::query(&connections.read_master_connection, &self.repo_id)
+                    .await?,
+            );
+        }
+        let mut retained: HashSet<_> = report
+            .retained_versions
+            .iter()
+            .map(|version| version.idmap_version)
+            .collect();
+        let mut to_visit: Vec<_> = retained.iter().copied().collect();
+        while let Some(version) = to_visit.pop() {
+            for (idmap_version, copied_version) in copy_mappings.iter() {
+                if *idmap_version == version && retained.insert(*copied_version) {
+                    to_visit.push(*copied_version);
+                }
+            }
+        }
+        Ok(retained)
+    }
+
+    async fn all_idmap_versions(&self, ctx: &CoreContext) -> Result<BTreeSet<IdMapVersion>> {
+        let mut versions = BTreeSet::new();
+        for connections in self.connections.idmap_connections() {
+            ctx.perf_counters()
+                .increment_counter(PerfCounterType::SqlReadsMaster);
+            versions.extend(
+                SelectIdMapVersions::query(&connections.read_master_connection, &self.repo_id)
+                    .await?
+                    .into_iter()
+                    .map(|(version,)| version),
+            );
+        }
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlReadsMaster);
+        versions.extend(
+            SelectCloneHintVersions::query(
+                &self.connections.0.read_master_connection,
+                &self.repo_id,
+            )
+            .await?
+            .into_iter()
+            .map(|(version,)| version),
+        );
+        Ok(versions)
+    }
+
+    async fn delete_idmap_version(
+        &self,
+        ctx: &CoreContext,
+        version: IdMapVersion,
+        batch_size: u64,
+    ) -> Result<()> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlWrites);
+        DeleteCloneHints::query(
+            &self.connections.0.write_connection,
+            &self.repo_id,
+            &version,
+        )
+        .await?;
+        for connections in self.connections.idmap_connections() {
+            ctx.perf_counters()
+                .increment_counter(PerfCounterType::SqlWrites);
+            let conn = &connections.write_connection;
+            DeleteCopyMappings::query(conn, &self.repo_id, &version).await?;
+            loop {
+                ctx.perf_counters()
+                    .increment_counter(PerfCounterType::SqlWrites);
+                let result =
+                    DeleteIdMapVersionBatch::query(conn, &self.repo_id, &version, &batch_size)
+                        .await
+                        .with_context(|| format!("failed to delete idmap version {}", version))?;
+                if result.affected_rows() < batch_size {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn unlink_if_present(&self, ctx: &CoreContext, key: &str) -> Result<()> {
+        // Unlinking a missing key is an error, and a previous GC may have
+        // been interrupted after unlinking.
+        if self
+            .blobstore
+            .is_present(ctx, key)
+            .await?
+            .assume_not_found_if_unsure()
+        {
+            self.blobstore
+                .unlink(ctx, key)
+                .await
+                .with_context(|| format!("failed to unlink {}", key))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blobstore::Blobstore;
+    use blobstore::BlobstoreBytes;
+    use fbinit::FacebookInit;
+    use memblob::Memblob;
+    use mononoke_types_mocks::changesetid::ONES_CSID;
+    use mononoke_types_mocks::changesetid::THREES_CSID;
+    use mononoke_types_mocks::changesetid::TWOS_CSID;
+    use sql_construct::SqlConstruct;
+    use sql_ext::replication::NoReplicaLagMonitor;
+
+    use super::*;
+    use crate::idmap::IdMap;
+    use crate::idmap::SqlIdMap;
+    use crate::DagId;
+
+    #[fbinit::test]
+    async fn test_gc(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+        let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let blobstore = Arc::new(Memblob::default());
+        let version_store = SegmentedChangelogVersionStore::new(conns.0.clone(), repo_id);
+        let idmap = |version| {
+            SqlIdMap::new(
+                conns.0.clone(),
+                Arc::new(NoReplicaLagMonitor()),
+                repo_id,
+                IdMapVersion(version),
+            )
+        };
+
+        let versions: Vec<_> = (1..=4)
+            .map(|n: u64| {
+                SegmentedChangelogVersion::new(
+                    IdDagVersion::from_serialized_bytes(n.to_string()),
+                    IdMapVersion(n),
+                )
+            })
+            .collect();
+        for version in versions.iter() {
+            blobstore
+                .put(
+                    &ctx,
+                    IdDagSaveStore::blobstore_key(version.iddag_version),
+                    BlobstoreBytes::from_bytes("iddag"),
+                )
+                .await?;
+            // Enough entries that IdMaps are deleted in several batches.
+            let idmap = idmap(version.idmap_version.0);
+            for (dag_id, cs_id) in [ONES_CSID, TWOS_CSID, THREES_CSID].into_iter().enumerate() {
+                idmap.insert(&ctx, DagId(dag_id as u64), cs_id).await?;
+            }
+            version_store.set(&ctx, *version).await?;
+        }
+        // An IdMap that is still being built.
+        idmap(5).insert(&ctx, DagId(0), ONES_CSID).await?;
+
+        let gc = SegmentedChangelogGc::new(repo_id, conns.clone(), blobstore.clone());
+        let mut options = SegmentedChangelogGcOptions {
+            keep_last: 2,
+            retention: Duration::ZERO,
+            dry_run: true,
+            delete_batch_size: 2,
+        };
+        let expected = SegmentedChangelogGcReport {
+            retained_versions: vec![versions[3], versions[2]],
+            deleted_versions: vec![versions[1], versions[0]],
+            deleted_iddag_versions: {
+                let mut iddag_versions = vec![versions[0].iddag_version, versions[1].iddag_version];
+                iddag_versions.sort();
+                iddag_versions
+            },
+            deleted_idmap_versions: vec![IdMapVersion(1), IdMapVersion(2)],
+            deleted_clone_hint_blobs: 0,
+        };
+
+        assert_eq!(gc.run(&ctx, &options).await?, expected);
+        assert_eq!(version_store.history(&ctx).await?.len(), 4);
+        assert!(blobstore
+            .get(
+                &ctx,
+                &IdDagSaveStore::blobstore_key(versions[0].iddag_version)
+            )
+            .await?
+            .is_some());
+        assert_eq!(
+            idmap(1).find_changeset_id(&ctx, DagId(0)).await?,
+            Some(ONES_CSID)
+        );
+
+        options.dry_run = false;
+        assert_eq!(gc.run(&ctx, &options).await?, expected);
+        assert_eq!(version_store.history(&ctx).await?.len(), 2);
+        for (version, present) in versions.iter().zip([false, false, true, true]) {
+            assert_eq!(
+                blobstore
+                    .get(&ctx, &IdDagSaveStore::blobstore_key(version.iddag_version))
+                    .await?
+                    .is_some(),
+                present
+            );
+        }
+        for dag_id in 0..3 {
+            assert_eq!(idmap(1).find_changeset_id(&ctx, DagId(dag_id)).await?, None);
+            assert_eq!(idmap(2).find_changeset_id(&ctx, DagId(dag_id)).await?, None);
+        }
+        assert_eq!(
+            idmap(3).find_changeset_id(&ctx, DagId(0)).await?,
+            Some(ONES_CSID)
+        );
+        assert_eq!(
+            idmap(5).find_changeset_id(&ctx, DagId(0)).await?,
+            Some(ONES_CSID)
+        );
+
+        // Nothing left to collect.
+        let report = gc.run(&ctx, &options).await?;
+        assert!(report.deleted_versions.is_empty());
+        assert!(report.deleted_idmap_versions.is_empty());
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_gc_keeps_copied_idmap(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+        let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let version_store = SegmentedChangelogVersionStore::new(conns.0.clone(), repo_id);
+        let idmap = |version| {
+            SqlIdMap::new(
+                conns.0.clone(),
+                Arc::new(NoReplicaLagMonitor()),
+                repo_id,
+                IdMapVersion(version),
+            )
+        };
+
+        idmap(1).insert(&ctx, DagId(0), ONES_CSID).await?;
+        version_store
+            .set(
+                &ctx,
+                SegmentedChangelogVersion::new(
+                    IdDagVersion::from_serialized_bytes(b"1"),
+                    IdMapVersion(1),
+                ),
+            )
+            .await?;
+        idmap(1).copy(DagId(0), IdMapVersion(2)).await?;
+        version_store
+            .set(
+                &ctx,
+                SegmentedChangelogVersion::new(
+                    IdDagVersion::from_serialized_bytes(b"2"),
+                    IdMapVersion(2),
+                ),
+            )
+            .await?;
+
+        let gc = SegmentedChangelogGc::new(repo_id, conns.clone(), Arc::new(Memblob::default()));
+        let options = SegmentedChangelogGcOptions {
+            keep_last: 1,
+            retention: Duration::ZERO,
+            dry_run: false,
+            delete_batch_size: 1000,
+        };
+        let report = gc.run(&ctx, &options).await?;
+        assert_eq!(report.deleted_versions.len(), 1);
+        assert!(report.deleted_idmap_versions.is_empty());
+        assert_eq!(
+            idmap(2).find_changeset_id(&ctx, DagId(0)).await?,
+            Some(ONES_CSID)
+        );
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_gc_keeps_pinned_version(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+        let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let version_store = SegmentedChangelogVersionStore::new(conns.0.clone(), repo_id);
+        let versions: Vec<_> = (1..=3)
+            .map(|n: u64| {
+                SegmentedChangelogVersion::new(
+                    IdDagVersion::from_serialized_bytes(n.to_string()),
+                    IdMapVersion(n),
+                )
+            })
+            .collect();
+        for version in versions.iter() {
+            version_store.set(&ctx, *version).await?;
+        }
+        version_store.pin(&ctx, versions[0]).await?;
+
+        let gc = SegmentedChangelogGc::new(repo_id, conns.clone(), Arc::new(Memblob::default()));
+        let options = SegmentedChangelogGcOptions {
+            keep_last: 1,
+            retention: Duration::ZERO,
+            dry_run: true,
+            delete_batch_size: 1000,
+        };
+        let report = gc.run(&ctx, &options).await?;
+        assert_eq!(report.retained_versions, vec![versions[2], versions[0]]);
+        assert_eq!(report.deleted_versions, vec![versions[1]]);
+
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_gc_range_sharded_idmap(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+        let base = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let shard = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let conns = base
+            .clone()
+            .with_idmap_range_shards(&[1], vec![shard.0.clone()])?;
+        let version_store = SegmentedChangelogVersionStore::new(conns.0.clone(), repo_id);
+        let idmap_factory = conns.idmap_factory(Arc::new(NoReplicaLagMonitor()), repo_id);
+        let table = |conns: &SegmentedChangelogSqlConnections, version| {
+            SqlIdMap::new(
+                conns.0.clone(),
+                Arc::new(NoReplicaLagMonitor()),
+                repo_id,
+                IdMapVersion(version),
+            )
+        };
+
+        for n in 1..=2u64 {
+            idmap_factory
+                .for_writer(&ctx, IdMapVersion(n))?
+                .insert_many(&ctx, vec![(DagId(0), ONES_CSID), (DagId(1), TWOS_CSID)])
+                .await?;
+            version_store
+                .set(
+                    &ctx,
+                    SegmentedChangelogVersion::new(
+                        IdDagVersion::from_serialized_bytes(n.to_string()),
+                        IdMapVersion(n),
+                    ),
+                )
+                .await?;
+        }
+
+        let gc = SegmentedChangelogGc::new(repo_id, conns, Arc::new(Memblob::default()));
+        let options = SegmentedChangelogGcOptions {
+            keep_last: 1,
+            retention: Duration::ZERO,
+            dry_run: false,
+            delete_batch_size: 1000,
+        };
+        let report = gc.run(&ctx, &options).await?;
+        assert_eq!(report.deleted_idmap_versions, vec![IdMapVersion(1)]);
+
+        // The old version is deleted from the shard as well.
+        assert_eq!(table(&base, 1).get_last_entry(&ctx).await?, None);
+        assert_eq!(table(&shard, 1).get_last_entry(&ctx).await?, None);
+        assert_eq!(
+            table(&base, 2).get_last_entry(&ctx).await?,
+            Some((DagId(0), ONES_CSID))
+        );
+        assert_eq!(
+            table(&shard, 2).get_last_entry(&ctx).await?,
+            Some((DagId(1), TWOS_CSID))
+        );
+
+        Ok(())
+    }
+}
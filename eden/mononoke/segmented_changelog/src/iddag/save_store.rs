@@ -36,7 +36,7 @@ impl IdDagSaveStore {
     ) -> Result<Option<InProcessIdDag>> {
         let bytes_opt = self
             .blobstore
-            .get(ctx, &self.key(iddag_version))
+            .get(ctx, &Self::blobstore_key(iddag_version))
             .await
             .with_context(|| {
                 format!(
@@ -89,7 +89,7 @@ impl IdDagSaveStore {
         self.blobstore
             .put(
                 ctx,
-                self.key(iddag_version),
+                Self::blobstore_key(iddag_version),
                 BlobstoreBytes::from_bytes(buffer),
             )
             .await
@@ -98,7 +98,7 @@ impl IdDagSaveStore {
         Ok(iddag_version)
     }
 
-    fn key(&self, iddag_version: IdDagVersion) -> String {
+    pub(crate) fn blobstore_key(iddag_version: IdDagVersion) -> String {
         format!("segmented_changelog_iddag.blake2.{}", iddag_version.0)
     }
 }
@@ -7,6 +7,7 @@
 
 mod cache;
 mod mem;
+mod sharded;
 mod shared_traits;
 mod sql;
 
@@ -28,6 +29,7 @@ pub use self::cache::CacheHandlers;
 pub use self::cache::CachedIdMap;
 pub use self::mem::ConcurrentMemIdMap;
 pub use self::mem::MemIdMap;
+pub use self::sharded::ShardedIdMap;
 pub use self::shared_traits::cs_id_from_vertex_name;
 pub use self::shared_traits::vertex_name_from_cs_id;
 pub use self::shared_traits::IdMapWrapper;
@@ -293,13 +295,14 @@ impl IdMap for OverlayIdMap {
 }
 
 // The builder for the standard IdMap
-// Our layers are: SqlIdMap, CachedIdMap, OverlayIdMap
+// Our layers are: SqlIdMap (optionally range sharded by ShardedIdMap), CachedIdMap, OverlayIdMap
 #[derive(Clone)]
 pub struct IdMapFactory {
     connections: SqlConnections,
     replica_lag_monitor: Arc<dyn ReplicaLagMonitor>,
     repo_id: RepositoryId,
     cache_handlers: Option<CacheHandlers>,
+    range_shards: Vec<(DagId, SqlConnections)>,
 }
 
 impl IdMapFactory {
@@ -313,23 +316,35 @@ impl IdMapFactory {
             replica_lag_monitor,
             repo_id,
             cache_handlers: None,
+            range_shards: Vec::new(),
         }
     }
 
     // Writes go to the SQL table.
-    pub fn for_writer(&self, ctx: &CoreContext, version: IdMapVersion) -> Arc<dyn IdMap> {
-        let sql_idmap = SqlIdMap::new(
-            self.connections.clone(),
-            self.replica_lag_monitor.clone(),
-            self.repo_id,
-            version,
-        );
+    pub fn for_writer(&self, ctx: &CoreContext, version: IdMapVersion) -> Result<Arc<dyn IdMap>> {
+        let sql_idmap = |connections: &SqlConnections| -> Arc<dyn IdMap> {
+            Arc::new(SqlIdMap::new(
+                connections.clone(),
+                self.replica_lag_monitor.clone(),
+                self.repo_id,
+                version,
+            ))
+        };
         slog::debug!(
             ctx.logger(),
-            "segmented changelog idmap instantiated - version: {}",
-            version
+            "segmented changelog idmap instantiated - version: {}, range shards: {}",
+            version,
+            self.range_shards.len()
         );
-        let mut idmap: Arc<dyn IdMap> = Arc::new(sql_idmap);
+        let mut idmap = sql_idmap(&self.connections);
+        if !self.range_shards.is_empty() {
+            let shards = self
+                .range_shards
+                .iter()
+                .map(|(start, connections)| (*start, sql_idmap(connections)))
+                .collect();
+            idmap = Arc::new(ShardedIdMap::new(idmap, shards)?);
+        }
         if let Some(cache_handlers) = &self.cache_handlers {
             idmap = Arc::new(CachedIdMap::new(
                 idmap,
@@ -338,7 +353,7 @@ impl IdMapFactory {
                 version,
             ));
         }
-        idmap
+        Ok(idmap)
     }
 
     // Servers have an overlay idmap which means that all their updates to the idmap stay confined
@@ -349,7 +364,7 @@ impl IdMapFactory {
         version: IdMapVersion,
         iddag: &InProcessIdDag,
     ) -> Result<Arc<dyn IdMap>> {
-        let overlay = OverlayIdMap::from_iddag_and_idmap(iddag, self.for_writer(ctx, version))?;
+        let overlay = OverlayIdMap::from_iddag_and_idmap(iddag, self.for_writer(ctx, version)?)?;
         Ok(Arc::new(overlay))
     }
 
@@ -357,6 +372,73 @@ impl IdMapFactory {
         self.cache_handlers = Some(cache_handlers);
         self
     }
+
+    /// Spread the idmap over several databases by DagId range.  Each shard
+    /// holds the DagIds from the given start up to the next shard's start.
+    /// DagIds below the first start stay in the unsharded table, so an
+    /// existing repo can migrate by starting the first shard above its
+    /// last assigned DagId.
+    pub fn with_range_shards(mut self, mut range_shards: Vec<(DagId, SqlConnections)>) -> Self {
+        range_shards.sort_by_key(|(start, _)| *start);
+        self.range_shards = range_shards;
+        self
+    }
+
+    /// The DagId ranges held by the unsharded table and each range shard,
+    /// as `(start, end, connections)`, where the last range has no end.
+    fn ranges(&self) -> impl Iterator<Item = (DagId, Option<DagId>, &SqlConnections)> {
+        let starts =
+            std::iter::once(DagId(0)).chain(self.range_shards.iter().map(|(start, _)| *start));
+        let ends = self
+            .range_shards
+            .iter()
+            .map(|(start, _)| Some(*start))
+            .chain(std::iter::once(None));
+        let connections = std::iter::once(&self.connections)
+            .chain(self.range_shards.iter().map(|(_, connections)| connections));
+        starts
+            .zip(ends)
+            .zip(connections)
+            .map(|((start, end), connections)| (start, end, connections))
+    }
+
+    /// Copy the idmap at `version`, up to and including `dag_limit`, to
+    /// `new_version`.  Each shard copies the part of its range up to the
+    /// limit, so the copy is sharded the same way as the original.
+    pub async fn copy(
+        &self,
+        ctx: &CoreContext,
+        version: IdMapVersion,
+        dag_limit: DagId,
+        new_version: IdMapVersion,
+    ) -> Result<()> {
+        for (start, end, connections) in self.ranges() {
+            if start > dag_limit {
+                break;
+            }
+            let limit = match end {
+                Some(end) if end <= dag_limit => DagId(end.0 - 1),
+                _ => dag_limit,
+            };
+            slog::debug!(
+                ctx.logger(),
+                "copying segmented changelog idmap version {} to {} for DagIds {} to {}",
+                version,
+                new_version,
+                start,
+                limit,
+            );
+            SqlIdMap::new(
+                connections.clone(),
+                self.replica_lag_monitor.clone(),
+                self.repo_id,
+                version,
+            )
+            .copy(limit, new_version)
+            .await?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
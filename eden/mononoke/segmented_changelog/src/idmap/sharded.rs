@@ -0,0 +1,376 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use context::CoreContext;
+use futures::future::try_join_all;
+use mononoke_types::ChangesetId;
+
+use crate::idmap::IdMap;
+use crate::types::IdMapVersion;
+use crate::DagId;
+
+/// An IdMap split into shards by ranges of DagId, so that inserts for a large
+/// repo are spread over several backends.
+///
+/// Each shard holds the DagIds from its start up to the start of the next
+/// shard.  DagIds below the first shard's start are held by the base IdMap,
+/// which lets an existing unsharded IdMap keep serving the ids it already
+/// has while new ids are assigned to the shards.
+pub struct ShardedIdMap {
+    base: Arc<dyn IdMap>,
+    /// Shards ordered by the first DagId they hold.
+    shards: Vec<(DagId, Arc<dyn IdMap>)>,
+}
+
+impl ShardedIdMap {
+    pub fn new(base: Arc<dyn IdMap>, shards: Vec<(DagId, Arc<dyn IdMap>)>) -> Result<Self> {
+        let mut prev_start = DagId(0);
+        for (start, _) in shards.iter() {
+            if *start <= prev_start {
+                bail!(
+                    "idmap shard starts must be increasing and above 0, got {} after {}",
+                    start,
+                    prev_start
+                );
+            }
+            prev_start = *start;
+        }
+        Ok(Self { base, shards })
+    }
+
+    fn shard_index(&self, dag_id: DagId) -> usize {
+        // Index 0 is the base IdMap, shard i is at index i + 1.
+        self.shards.partition_point(|(start, _)| *start <= dag_id)
+    }
+
+    fn idmap(&self, index: usize) -> &Arc<dyn IdMap> {
+        match index {
+            0 => &self.base,
+            i => &self.shards[i - 1].1,
+        }
+    }
+
+    fn all_idmaps_newest_first(&self) -> impl Iterator<Item = &Arc<dyn IdMap>> {
+        self.shards
+            .iter()
+            .rev()
+            .map(|(_, idmap)| idmap)
+            .chain(std::iter::once(&self.base))
+    }
+
+    /// Any shard may hold a changeset, but most lookups are for recent
+    /// changesets, so the shards are asked newest first, each only for the
+    /// changesets that haven't been found yet.
+    async fn find_dag_ids_newest_first(
+        &self,
+        ctx: &CoreContext,
+        mut cs_ids: Vec<ChangesetId>,
+        maybe_stale: bool,
+    ) -> Result<HashMap<ChangesetId, DagId>> {
+        let mut found = HashMap::with_capacity(cs_ids.len());
+        for idmap in self.all_idmaps_newest_first() {
+            if cs_ids.is_empty() {
+                break;
+            }
+            let shard_found = if maybe_stale {
+                idmap
+                    .find_many_dag_ids_maybe_stale(ctx, cs_ids.clone())
+                    .await?
+            } else {
+                idmap.find_many_dag_ids(ctx, cs_ids.clone()).await?
+            };
+            cs_ids.retain(|cs_id| !shard_found.contains_key(cs_id));
+            found.extend(shard_found);
+        }
+        Ok(found)
+    }
+
+    fn route<T>(
+        &self,
+        items: Vec<T>,
+        dag_id: impl Fn(&T) -> DagId,
+    ) -> impl Iterator<Item = (&Arc<dyn IdMap>, Vec<T>)> {
+        let mut by_shard: Vec<Vec<T>> = (0..=self.shards.len()).map(|_| Vec::new()).collect();
+        for item in items {
+            by_shard[self.shard_index(dag_id(&item))].push(item);
+        }
+        by_shard
+            .into_iter()
+            .enumerate()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(index, items)| (self.idmap(index), items))
+    }
+}
+
+#[async_trait]
+impl IdMap for ShardedIdMap {
+    async fn insert_many(
+        &self,
+        ctx: &CoreContext,
+        mappings: Vec<(DagId, ChangesetId)>,
+    ) -> Result<()> {
+        try_join_all(
+            self.route(mappings, |(dag_id, _)| *dag_id)
+                .map(|(idmap, mappings)| idmap.insert_many(ctx, mappings)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn find_many_changeset_ids(
+        &self,
+        ctx: &CoreContext,
+        dag_ids: Vec<DagId>,
+    ) -> Result<HashMap<DagId, ChangesetId>> {
+        let results = try_join_all(
+            self.route(dag_ids, |dag_id| *dag_id)
+                .map(|(idmap, dag_ids)| idmap.find_many_changeset_ids(ctx, dag_ids)),
+        )
+        .await?;
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    async fn find_many_dag_ids(
+        &self,
+        ctx: &CoreContext,
+        cs_ids: Vec<ChangesetId>,
+    ) -> Result<HashMap<ChangesetId, DagId>> {
+        self.find_dag_ids_newest_first(ctx, cs_ids, false).await
+    }
+
+    async fn find_many_dag_ids_maybe_stale(
+        &self,
+        ctx: &CoreContext,
+        cs_ids: Vec<ChangesetId>,
+    ) -> Result<HashMap<ChangesetId, DagId>> {
+        self.find_dag_ids_newest_first(ctx, cs_ids, true).await
+    }
+
+    async fn get_last_entry(&self, ctx: &CoreContext) -> Result<Option<(DagId, ChangesetId)>> {
+        // Shards above the last assigned id are empty, so the last entry is
+        // in the highest shard that has any.
+        for index in (0..=self.shards.len()).rev() {
+            if let Some(entry) = self.idmap(index).get_last_entry(ctx).await? {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    fn idmap_version(&self) -> Option<IdMapVersion> {
+        self.base.idmap_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use maplit::hashmap;
+    use mononoke_types::RepositoryId;
+    use mononoke_types_mocks::changesetid::AS_CSID;
+    use mononoke_types_mocks::changesetid::FOURS_CSID;
+    use mononoke_types_mocks::changesetid::ONES_CSID;
+    use mononoke_types_mocks::changesetid::THREES_CSID;
+    use mononoke_types_mocks::changesetid::TWOS_CSID;
+    use sql_construct::SqlConstruct;
+    use sql_ext::replication::NoReplicaLagMonitor;
+
+    use super::*;
+    use crate::builder::SegmentedChangelogSqlConnections;
+    use crate::idmap::ConcurrentMemIdMap;
+    use crate::idmap::SqlIdMap;
+
+    #[fbinit::test]
+    async fn test_sharded_idmap_routing(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let base: Arc<dyn IdMap> = Arc::new(ConcurrentMemIdMap::new());
+        let shard_a: Arc<dyn IdMap> = Arc::new(ConcurrentMemIdMap::new());
+        let shard_b: Arc<dyn IdMap> = Arc::new(ConcurrentMemIdMap::new());
+
+        // Entries written before sharding stay in the base idmap.
+        base.insert(&ctx, DagId(0), AS_CSID).await?;
+
+        let sharded = ShardedIdMap::new(
+            base.clone(),
+            vec![(DagId(2), shard_a.clone()), (DagId(4), shard_b.clone())],
+        )?;
+        assert_eq!(
+            sharded.get_last_entry(&ctx).await?,
+            Some((DagId(0), AS_CSID))
+        );
+
+        sharded
+            .insert_many(
+                &ctx,
+                vec![
+                    (DagId(1), ONES_CSID),
+                    (DagId(2), TWOS_CSID),
+                    (DagId(3), THREES_CSID),
+                ],
+            )
+            .await?;
+        assert_eq!(
+            base.find_changeset_id(&ctx, DagId(1)).await?,
+            Some(ONES_CSID)
+        );
+        assert_eq!(
+            shard_a.find_changeset_id(&ctx, DagId(2)).await?,
+            Some(TWOS_CSID)
+        );
+        assert_eq!(
+            shard_a.find_changeset_id(&ctx, DagId(3)).await?,
+            Some(THREES_CSID)
+        );
+        assert_eq!(shard_b.get_last_entry(&ctx).await?, None);
+        assert_eq!(
+            sharded.get_last_entry(&ctx).await?,
+            Some((DagId(3), THREES_CSID))
+        );
+
+        sharded.insert(&ctx, DagId(4), FOURS_CSID).await?;
+        assert_eq!(
+            sharded
+                .find_many_changeset_ids(&ctx, vec![DagId(0), DagId(3), DagId(4), DagId(5)])
+                .await?,
+            hashmap![DagId(0) => AS_CSID, DagId(3) => THREES_CSID, DagId(4) => FOURS_CSID]
+        );
+        assert_eq!(
+            sharded
+                .find_many_dag_ids(&ctx, vec![AS_CSID, TWOS_CSID, FOURS_CSID])
+                .await?,
+            hashmap![AS_CSID => DagId(0), TWOS_CSID => DagId(2), FOURS_CSID => DagId(4)]
+        );
+        assert_eq!(
+            sharded.get_last_entry(&ctx).await?,
+            Some((DagId(4), FOURS_CSID))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sharded_idmap_rejects_unordered_shards() {
+        let idmap = || -> Arc<dyn IdMap> { Arc::new(ConcurrentMemIdMap::new()) };
+        assert!(ShardedIdMap::new(idmap(), vec![(DagId(0), idmap())]).is_err());
+        assert!(
+            ShardedIdMap::new(idmap(), vec![(DagId(4), idmap()), (DagId(2), idmap())]).is_err()
+        );
+    }
+
+    #[fbinit::test]
+    async fn test_range_sharded_sql_idmap(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo_id = RepositoryId::new(0);
+        let base = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let shard_a = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let shard_b = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let conns = base
+            .clone()
+            .with_idmap_range_shards(&[2, 4], vec![shard_a.0.clone(), shard_b.0.clone()])?;
+        let table = |conns: &SegmentedChangelogSqlConnections, version| {
+            SqlIdMap::new(
+                conns.0.clone(),
+                Arc::new(NoReplicaLagMonitor()),
+                repo_id,
+                IdMapVersion(version),
+            )
+        };
+
+        let writer = conns
+            .idmap_factory(Arc::new(NoReplicaLagMonitor()), repo_id)
+            .for_writer(&ctx, IdMapVersion(1))?;
+        writer
+            .insert_many(
+                &ctx,
+                vec![
+                    (DagId(0), AS_CSID),
+                    (DagId(1), ONES_CSID),
+                    (DagId(2), TWOS_CSID),
+                    (DagId(3), THREES_CSID),
+                    (DagId(4), FOURS_CSID),
+                ],
+            )
+            .await?;
+
+        // Each table only holds its own range.
+        assert_eq!(
+            table(&base, 1).get_last_entry(&ctx).await?,
+            Some((DagId(1), ONES_CSID))
+        );
+        assert_eq!(
+            table(&shard_a, 1).get_last_entry(&ctx).await?,
+            Some((DagId(3), THREES_CSID))
+        );
+        assert_eq!(
+            table(&shard_b, 1).get_last_entry(&ctx).await?,
+            Some((DagId(4), FOURS_CSID))
+        );
+
+        // Readers built from the same connections see the whole idmap.
+        let factory = conns.idmap_factory(Arc::new(NoReplicaLagMonitor()), repo_id);
+        let reader = factory.for_writer(&ctx, IdMapVersion(1))?;
+        assert_eq!(
+            reader
+                .find_many_dag_ids(&ctx, vec![AS_CSID, TWOS_CSID, FOURS_CSID])
+                .await?,
+            hashmap![AS_CSID => DagId(0), TWOS_CSID => DagId(2), FOURS_CSID => DagId(4)]
+        );
+        assert_eq!(
+            reader
+                .find_many_changeset_ids(&ctx, vec![DagId(1), DagId(3), DagId(5)])
+                .await?,
+            hashmap![DagId(1) => ONES_CSID, DagId(3) => THREES_CSID]
+        );
+        assert_eq!(
+            reader.get_last_entry(&ctx).await?,
+            Some((DagId(4), FOURS_CSID))
+        );
+
+        // Copies are sharded in the same way, and stop at the limit.
+        factory
+            .copy(&ctx, IdMapVersion(1), DagId(3), IdMapVersion(2))
+            .await?;
+        let copy = factory.for_writer(&ctx, IdMapVersion(2))?;
+        assert_eq!(
+            copy.find_many_changeset_ids(&ctx, vec![DagId(0), DagId(2), DagId(3), DagId(4)])
+                .await?,
+            hashmap![DagId(0) => AS_CSID, DagId(2) => TWOS_CSID, DagId(3) => THREES_CSID]
+        );
+        assert_eq!(
+            copy.find_many_dag_ids(&ctx, vec![ONES_CSID, FOURS_CSID])
+                .await?,
+            hashmap![ONES_CSID => DagId(1)]
+        );
+        assert_eq!(
+            copy.get_last_entry(&ctx).await?,
+            Some((DagId(3), THREES_CSID))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_shard_count_must_match() -> Result<()> {
+        let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let shard = || SegmentedChangelogSqlConnections::with_sqlite_in_memory().map(|c| c.0);
+        // A single shard is used for every range.
+        assert!(conns
+            .clone()
+            .with_idmap_range_shards(&[2, 4], vec![shard()?])
+            .is_ok());
+        assert!(conns
+            .with_idmap_range_shards(&[2, 4, 6], vec![shard()?, shard()?])
+            .is_err());
+        Ok(())
+    }
+}
@@ -20,6 +20,9 @@ use mononoke_types::ChangesetId;
 mod builder;
 mod clone_hints;
 mod copy;
+mod errors;
+mod fallback;
+mod gc;
 mod iddag;
 mod idmap;
 mod logging;
@@ -28,7 +31,10 @@ mod on_demand;
 mod owned;
 mod parents;
 mod periodic_reload;
+mod progress;
 mod read_only;
+mod reload_scheduler;
+mod shadow;
 mod sql_types;
 mod tailer;
 pub mod types;
@@ -42,6 +48,7 @@ use mercurial_types::HgChangesetId;
 pub use segmented_changelog_types::dag;
 pub use segmented_changelog_types::ArcSegmentedChangelog;
 pub use segmented_changelog_types::CloneData;
+pub use segmented_changelog_types::DagFreshness;
 pub use segmented_changelog_types::DagId;
 pub use segmented_changelog_types::DagIdSet;
 pub use segmented_changelog_types::FirstAncestorConstraint;
@@ -61,11 +68,18 @@ pub use crate::builder::new_test_segmented_changelog;
 pub use crate::builder::SegmentedChangelogSqlConnections;
 pub use crate::clone_hints::CloneHints;
 pub use crate::copy::copy_segmented_changelog;
+pub use crate::errors::SegmentedChangelogLoadError;
+pub use crate::gc::SegmentedChangelogGc;
+pub use crate::gc::SegmentedChangelogGcOptions;
+pub use crate::gc::SegmentedChangelogGcReport;
 // public for benchmarking
 pub use crate::idmap::ConcurrentMemIdMap;
 pub use crate::idmap::IdMap;
 pub use crate::manager::ArcSegmentedChangelogManager;
 pub use crate::manager::SegmentedChangelogManager;
+pub use crate::manager::SegmentedChangelogManagerRef;
+pub use crate::progress::BuildProgressSnapshot;
+pub use crate::reload_scheduler::SegmentedChangelogReloadScheduler;
 pub use crate::tailer::OperationMode;
 pub use crate::tailer::SegmentedChangelogTailer;
 pub use crate::update::seedheads_from_config;
@@ -140,6 +154,25 @@ impl SegmentedChangelog for DisabledSegmentedChangelog {
         // None means inconclusive result, it can be returned safely
         Ok(None)
     }
+
+    async fn greatest_common_ancestors(
+        &self,
+        _ctx: &CoreContext,
+        _a: ChangesetId,
+        _b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        Ok(None)
+    }
+
+    async fn range(
+        &self,
+        _ctx: &CoreContext,
+        _start: ChangesetId,
+        _end: ChangesetId,
+        _limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        Ok(None)
+    }
 }
 
 #[macro_export]
@@ -149,6 +182,13 @@ macro_rules! segmented_changelog_delegate {
         segmented_changelog_delegate!($type, |&$self, $ctx: &CoreContext| $delegate);
     };
     ($type:ident, |&$self:ident, $ctx:ident: &CoreContext| $delegate:block) => {
+        segmented_changelog_delegate!($type, |&$self, $ctx: &CoreContext| $delegate, ensure_fresh {
+            let delegate = $delegate;
+            delegate.ensure_fresh($ctx).await
+        });
+    };
+    // Delegates everything except for ensure_fresh, which runs the given block.
+    ($type:ident, |&$self:ident, $ctx:ident: &CoreContext| $delegate:block, ensure_fresh $ensure_fresh:block) => {
         #[async_trait]
         impl SegmentedChangelog for $type {
             async fn location_to_many_changeset_ids(
@@ -208,10 +248,33 @@ macro_rules! segmented_changelog_delegate {
                 delegate.is_ancestor($ctx, ancestor, descendant).await
             }
 
+            async fn greatest_common_ancestors(
+                &$self,
+                $ctx: &CoreContext,
+                a: ChangesetId,
+                b: ChangesetId,
+            ) -> Result<Option<Vec<ChangesetId>>> {
+                let delegate = $delegate;
+                delegate.greatest_common_ancestors($ctx, a, b).await
+            }
+
+            async fn range(
+                &$self,
+                $ctx: &CoreContext,
+                start: ChangesetId,
+                end: ChangesetId,
+                limit: usize,
+            ) -> Result<Option<Vec<ChangesetId>>> {
+                let delegate = $delegate;
+                delegate.range($ctx, start, end, limit).await
+            }
+
             async fn build_up_to_heads(&$self, $ctx: &CoreContext, heads: &[ChangesetId]) -> Result<bool> {
                 let delegate = $delegate;
                 delegate.build_up_to_heads($ctx, heads).await
             }
+
+            async fn ensure_fresh(&$self, $ctx: &CoreContext) -> Result<()> $ensure_fresh
         }
     };
 }
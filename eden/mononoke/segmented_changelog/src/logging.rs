@@ -49,6 +49,34 @@ pub fn log_new_segmented_changelog_version(
     }
 }
 
+pub fn log_segmented_changelog_version_pin(
+    ctx: &CoreContext,
+    repo_id: RepositoryId,
+    pinned_version: Option<SegmentedChangelogVersion>,
+) {
+    match pinned_version {
+        Some(sc_version) => slog::warn!(
+            ctx.logger(),
+            "segmented changelog version pinned, idmap_version: {}, iddag_version: {}",
+            sc_version.idmap_version,
+            sc_version.iddag_version,
+        ),
+        None => slog::info!(ctx.logger(), "segmented changelog version unpinned"),
+    }
+    if let Some(mut sample_builder) = new_sample_builder(ctx) {
+        sample_builder
+            .add("type", "pin")
+            .add("repo_id", repo_id.id())
+            .add("pinned", pinned_version.is_some());
+        if let Some(sc_version) = pinned_version {
+            sample_builder
+                .add("idmap_version", sc_version.idmap_version.0)
+                .add("iddag_version", format!("{}", sc_version.iddag_version.0));
+        }
+        sample_builder.log(); // note that logging may fail
+    }
+}
+
 fn new_sample_builder(ctx: &CoreContext) -> Option<MononokeScubaSampleBuilder> {
     // We construct a completely new scuba sample builder to log to the version scuba table so we
     // check the context to verify if we are in an environment where we are allowed to log.
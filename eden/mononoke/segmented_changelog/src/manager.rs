@@ -9,17 +9,20 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::format_err;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
 use bookmarks::Bookmarks;
 use changeset_fetcher::ArcChangesetFetcher;
 use context::CoreContext;
+use futures::future::join;
 use futures_stats::TimedFutureExt;
 use mononoke_types::ChangesetId;
 use mononoke_types::RepositoryId;
+use mononoke_types::Timestamp;
+use stats::prelude::*;
 
+use crate::fallback::FallbackSegmentedChangelog;
 use crate::iddag::IdDagSaveStore;
 use crate::idmap::IdMapFactory;
 use crate::on_demand::OnDemandUpdateSegmentedChangelog;
@@ -32,6 +35,12 @@ use crate::CloneHints;
 use crate::Location;
 use crate::SeedHead;
 use crate::SegmentedChangelog;
+use crate::SegmentedChangelogLoadError;
+
+define_stats! {
+    prefix = "mononoke.segmented_changelog.manager";
+    version_pin_read_failure: timeseries(Sum),
+}
 
 pub enum SegmentedChangelogType {
     OnDemand {
@@ -52,6 +61,7 @@ pub struct SegmentedChangelogManager {
     seed_heads: Vec<SeedHead>,
     segmented_changelog_type: SegmentedChangelogType,
     clone_hints: Option<CloneHints>,
+    read_fallback: bool,
 }
 
 impl SegmentedChangelogManager {
@@ -76,23 +86,71 @@ impl SegmentedChangelogManager {
             seed_heads,
             segmented_changelog_type,
             clone_hints,
+            read_fallback: false,
+        }
+    }
+
+    /// Answer location lookups that the loaded dag can't serve by walking
+    /// the changesets instead.
+    pub fn with_read_fallback(mut self, read_fallback: bool) -> Self {
+        self.read_fallback = read_fallback;
+        self
+    }
+
+    pub fn read_fallback(&self) -> bool {
+        self.read_fallback
+    }
+
+    /// Wrap a segmented changelog in the read fallback, if enabled.
+    pub fn with_fallback(
+        &self,
+        sc: Arc<dyn SegmentedChangelog + Send + Sync>,
+    ) -> Arc<dyn SegmentedChangelog + Send + Sync> {
+        if self.read_fallback {
+            Arc::new(FallbackSegmentedChangelog::new(
+                sc,
+                Arc::clone(&self.changeset_fetcher),
+                self.repo_id,
+            ))
+        } else {
+            sc
         }
     }
 
     pub async fn load(
         &self,
         ctx: &CoreContext,
-    ) -> Result<(
-        Arc<dyn SegmentedChangelog + Send + Sync>,
-        SegmentedChangelogVersion,
-    )> {
+    ) -> Result<
+        (
+            Arc<dyn SegmentedChangelog + Send + Sync>,
+            SegmentedChangelogVersion,
+        ),
+        SegmentedChangelogLoadError,
+    > {
+        let (sc, sc_version, _head) = self.load_with_head(ctx).await?;
+        Ok((sc, sc_version))
+    }
+
+    /// Load the served version, along with the head of its dag, which can be
+    /// queried to warm the dag up or to check that it serves requests.
+    pub async fn load_with_head(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<
+        (
+            Arc<dyn SegmentedChangelog + Send + Sync>,
+            SegmentedChangelogVersion,
+            Option<ChangesetId>,
+        ),
+        SegmentedChangelogLoadError,
+    > {
         let monitored = async {
-            let (asc, sc_version): (Arc<dyn SegmentedChangelog + Send + Sync>, _) =
+            let (asc, sc_version, head): (Arc<dyn SegmentedChangelog + Send + Sync>, _, _) =
                 match self.segmented_changelog_type {
                     SegmentedChangelogType::OnDemand {
                         update_to_master_bookmark_period,
                     } => {
-                        let (on_demand, sc_version) = self.load_ondemand_update(ctx).await?;
+                        let (on_demand, sc_version, head) = self.load_ondemand_update(ctx).await?;
                         let on_demand: Arc<dyn SegmentedChangelog + Send + Sync> =
                             match update_to_master_bookmark_period {
                                 None => on_demand,
@@ -100,15 +158,16 @@ impl SegmentedChangelogManager {
                                     on_demand.with_periodic_update_to_master_bookmark(ctx, period),
                                 ),
                             };
-                        (on_demand, sc_version)
+                        (on_demand, sc_version, head)
                     }
                     #[cfg(test)]
                     SegmentedChangelogType::Owned => {
                         let (sc, sc_version) = self.load_owned(ctx).await?;
-                        (Arc::new(sc), sc_version)
+                        let head = self.owned_head(ctx, &sc).await?;
+                        (Arc::new(sc), sc_version, head)
                     }
                 };
-            Ok((asc, sc_version))
+            Ok((self.with_fallback(asc), sc_version, head))
         };
 
         let (stats, ret) = monitored.timed().await;
@@ -125,42 +184,73 @@ impl SegmentedChangelogManager {
     async fn load_ondemand_update(
         &self,
         ctx: &CoreContext,
-    ) -> Result<(
-        Arc<OnDemandUpdateSegmentedChangelog>,
-        SegmentedChangelogVersion,
-    )> {
-        let (owned, sc_version) = self.load_owned(ctx).await.with_context(|| {
-            format!("repo {}: failed to load segmented changelog", self.repo_id)
-        })?;
-        Ok((
-            Arc::new(OnDemandUpdateSegmentedChangelog::new(
-                ctx.clone(),
-                self.repo_id,
-                owned.iddag,
-                owned.idmap,
-                Arc::clone(&self.changeset_fetcher),
-                Arc::clone(&self.bookmarks),
-                self.seed_heads.clone(),
-                self.clone_hints.clone(),
-            )?),
-            sc_version,
-        ))
+    ) -> Result<
+        (
+            Arc<OnDemandUpdateSegmentedChangelog>,
+            SegmentedChangelogVersion,
+            Option<ChangesetId>,
+        ),
+        SegmentedChangelogLoadError,
+    > {
+        let (owned, sc_version) = self.load_owned(ctx).await?;
+        let head = self.owned_head(ctx, &owned).await?;
+        // Building the dag from the loaded iddag and idmap fails if they
+        // don't agree with each other.
+        let on_demand = OnDemandUpdateSegmentedChangelog::new(
+            ctx.clone(),
+            self.repo_id,
+            owned.iddag,
+            owned.idmap,
+            Arc::clone(&self.changeset_fetcher),
+            Arc::clone(&self.bookmarks),
+            self.seed_heads.clone(),
+            self.clone_hints.clone(),
+        )
+        .map_err(|e| SegmentedChangelogLoadError::IdDagCorrupt(self.repo_id, e))?;
+        Ok((Arc::new(on_demand), sc_version, head))
+    }
+
+    // The head of the loaded iddag.  The idmap only maps the dag ids that
+    // are in the iddag, so this is also the idmap's last entry.
+    async fn owned_head(
+        &self,
+        ctx: &CoreContext,
+        owned: &OwnedSegmentedChangelog,
+    ) -> Result<Option<ChangesetId>, SegmentedChangelogLoadError> {
+        let last_entry = owned
+            .idmap
+            .get_last_entry(ctx)
+            .await
+            .map_err(|e| SegmentedChangelogLoadError::IdMapUnavailable(self.repo_id, e))?;
+        Ok(last_entry.map(|(_dag_id, cs_id)| cs_id))
     }
 
     // public for builder only
     pub async fn load_owned(
         &self,
         ctx: &CoreContext,
-    ) -> Result<(OwnedSegmentedChangelog, SegmentedChangelogVersion)> {
-        let sc_version = self.latest_version(ctx).await?;
+    ) -> Result<(OwnedSegmentedChangelog, SegmentedChangelogVersion), SegmentedChangelogLoadError>
+    {
+        let (sc_version, pinned) = self.served_version(ctx).await?;
+        if pinned {
+            slog::warn!(
+                ctx.logger(),
+                "segmented changelog version is pinned, ignoring newer versions - repo_id: {}, \
+                idmap_version: {}, iddag_version: {}",
+                self.repo_id,
+                sc_version.idmap_version,
+                sc_version.iddag_version,
+            );
+        }
         let iddag = self
             .iddag_save_store
             .load(ctx, sc_version.iddag_version)
             .await
-            .with_context(|| format!("repo {}: failed to load iddag", self.repo_id))?;
+            .map_err(|e| SegmentedChangelogLoadError::IdDagCorrupt(self.repo_id, e))?;
         let idmap = self
             .idmap_factory
-            .for_server(ctx, sc_version.idmap_version, &iddag)?;
+            .for_server(ctx, sc_version.idmap_version, &iddag)
+            .map_err(|e| SegmentedChangelogLoadError::IdMapUnavailable(self.repo_id, e))?;
         slog::debug!(
             ctx.logger(),
             "segmented changelog dag successfully loaded - repo_id: {}, idmap_version: {}, \
@@ -173,22 +263,85 @@ impl SegmentedChangelogManager {
         Ok((owned, sc_version))
     }
 
-    pub async fn latest_version(&self, ctx: &CoreContext) -> Result<SegmentedChangelogVersion> {
+    /// The version that servers should load, and whether it is pinned.
+    /// This is the pinned version if there is one, otherwise the latest
+    /// saved version.  The pin is read alongside the latest version, and a
+    /// failure to read it is logged and treated as not pinned, so the pin
+    /// can't stop servers loading.
+    pub async fn served_version(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<(SegmentedChangelogVersion, bool), SegmentedChangelogLoadError> {
+        let (pin, latest) = join(self.version_pin(ctx), self.latest_saved_version(ctx)).await;
+        match pin {
+            Ok(Some((pinned_version, _pinned_at))) => return Ok((pinned_version, true)),
+            Ok(None) => {}
+            Err(err) => {
+                STATS::version_pin_read_failure.add_value(1);
+                slog::warn!(
+                    ctx.logger(),
+                    "failed to read segmented changelog version pin, loading latest version - \
+                    repo_id: {}: {:?}",
+                    self.repo_id,
+                    err,
+                );
+            }
+        }
+        Ok((latest?, false))
+    }
+
+    /// The latest version saved by the seeder or tailer, whether or not it
+    /// is served.
+    pub async fn latest_saved_version(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<SegmentedChangelogVersion, SegmentedChangelogLoadError> {
         self.sc_version_store
             .get(ctx)
             .await
-            .with_context(|| {
-                format!(
-                    "repo {}: error loading segmented changelog version",
-                    self.repo_id
-                )
-            })?
-            .ok_or_else(|| {
-                format_err!(
-                    "repo {}: segmented changelog metadata not found, maybe repo is not seeded",
-                    self.repo_id
-                )
-            })
+            .map_err(|e| SegmentedChangelogLoadError::VersionStoreUnavailable(self.repo_id, e))?
+            .ok_or(SegmentedChangelogLoadError::NotSeeded(self.repo_id))
+    }
+
+    /// The pinned version, and when it was pinned, if servers are pinned
+    /// to a version.
+    pub async fn version_pin(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<Option<(SegmentedChangelogVersion, Timestamp)>, SegmentedChangelogLoadError> {
+        self.sc_version_store
+            .get_pin(ctx)
+            .await
+            .map_err(|e| SegmentedChangelogLoadError::VersionStoreUnavailable(self.repo_id, e))
+    }
+
+    /// Pin servers to a known-good version, ignoring newer versions until
+    /// unpinned.  Servers pick the pin up at their next reload.  The
+    /// version's IdDag and IdMap are checked to load before pinning.
+    pub async fn pin_version(
+        &self,
+        ctx: &CoreContext,
+        sc_version: SegmentedChangelogVersion,
+    ) -> Result<()> {
+        let iddag = self
+            .iddag_save_store
+            .load(ctx, sc_version.iddag_version)
+            .await
+            .with_context(|| format!("repo {}: failed to load iddag to pin", self.repo_id))?;
+        let idmap = self
+            .idmap_factory
+            .for_server(ctx, sc_version.idmap_version, &iddag)?;
+        idmap
+            .get_last_entry(ctx)
+            .await?
+            .with_context(|| format!("repo {}: idmap to pin is empty", self.repo_id))?;
+        self.sc_version_store.pin(ctx, sc_version).await
+    }
+
+    /// Remove the pin, so that servers load the latest version again at
+    /// their next reload.  Returns whether a version was pinned.
+    pub async fn unpin_version(&self, ctx: &CoreContext) -> Result<bool> {
+        self.sc_version_store.unpin(ctx).await
     }
 
     /// Checks if given changeset is indexed by given segmented changelog version.
@@ -446,6 +446,29 @@ impl SegmentedChangelog for OnDemandUpdateSegmentedChangelog {
         read_dag.is_ancestor(ctx, ancestor, descendant).await
     }
 
+    async fn greatest_common_ancestors(
+        &self,
+        ctx: &CoreContext,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        let namedag = self.namedag.read().await;
+        let read_dag = ReadOnlySegmentedChangelog::new(namedag.dag(), namedag.map().clone_idmap());
+        read_dag.greatest_common_ancestors(ctx, a, b).await
+    }
+
+    async fn range(
+        &self,
+        ctx: &CoreContext,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        let namedag = self.namedag.read().await;
+        let read_dag = ReadOnlySegmentedChangelog::new(namedag.dag(), namedag.map().clone_idmap());
+        read_dag.range(ctx, start, end, limit).await
+    }
+
     async fn build_up_to_heads(&self, ctx: &CoreContext, heads: &[ChangesetId]) -> Result<bool> {
         if !self.are_heads_assigned(ctx, heads).await? {
             self.build_up_to_client_heads(ctx, heads).await?;
@@ -462,6 +485,12 @@ impl SegmentedChangelog for OnDemandUpdateSegmentedChangelog {
         }
         Ok(true)
     }
+
+    async fn ensure_fresh(&self, ctx: &CoreContext) -> Result<()> {
+        self.build_up_to_bookmark(ctx)
+            .await
+            .context("error while getting an up to date dag")
+    }
 }
 
 pub struct PeriodicUpdateSegmentedChangelog {
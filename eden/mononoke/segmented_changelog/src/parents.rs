@@ -5,8 +5,13 @@
  * GNU General Public License version 2.
  */
 
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::format_err;
 use changeset_fetcher::ArcChangesetFetcher;
 use context::CoreContext;
+use mononoke_types::ChangesetId;
 
 use crate::dag::errors::BackendError;
 use crate::dag::namedag::MemNameDag;
@@ -15,10 +20,13 @@ use crate::dag::Result;
 use crate::dag::VertexName;
 use crate::idmap::cs_id_from_vertex_name;
 use crate::idmap::vertex_name_from_cs_id;
+use crate::progress::BuildProgress;
+use crate::Location;
 
 pub struct FetchParents {
     ctx: CoreContext,
     changeset_fetcher: ArcChangesetFetcher,
+    progress: Option<Arc<BuildProgress>>,
 }
 
 impl FetchParents {
@@ -26,14 +34,25 @@ impl FetchParents {
         Self {
             ctx,
             changeset_fetcher,
+            progress: None,
         }
     }
+
+    /// Record each changeset whose parents are fetched in `progress`.  The
+    /// parents of each changeset are fetched once as it is added.
+    pub fn with_progress(mut self, progress: Arc<BuildProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
 }
 
 #[async_trait::async_trait]
 impl Parents for FetchParents {
     async fn parent_names(&self, name: VertexName) -> Result<Vec<VertexName>> {
         let cs_id = cs_id_from_vertex_name(&name);
+        if let Some(progress) = &self.progress {
+            progress.record(cs_id);
+        }
         let parents = self
             .changeset_fetcher
             .get_parents(&self.ctx, cs_id)
@@ -48,3 +67,35 @@ impl Parents for FetchParents {
         Ok(MemNameDag::new())
     }
 }
+
+/// Resolve a location by following first parents, without the dag.
+/// `first_parent` steps from a changeset to its first parent.  Returns `None`
+/// if that would visit more than `max_traversal` changesets.
+pub(crate) async fn first_parent_walk<F, Fut>(
+    location: Location<ChangesetId>,
+    count: u64,
+    max_traversal: u64,
+    mut first_parent: F,
+) -> anyhow::Result<Option<Vec<ChangesetId>>>
+where
+    F: FnMut(ChangesetId) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<ChangesetId>>>,
+{
+    let steps = location.distance + count;
+    if steps > max_traversal {
+        return Ok(None);
+    }
+    let mut cs_id = location.descendant;
+    let mut cs_ids = Vec::with_capacity(count as usize);
+    for step in 0..steps {
+        if step >= location.distance {
+            cs_ids.push(cs_id);
+        }
+        if step + 1 < steps {
+            cs_id = first_parent(cs_id)
+                .await?
+                .ok_or_else(|| format_err!("{} has no parents", cs_id))?;
+        }
+    }
+    Ok(Some(cs_ids))
+}
@@ -7,8 +7,11 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
+use anyhow::ensure;
+use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
 use context::CoreContext;
@@ -23,6 +26,7 @@ use tokio::sync::Notify;
 use tunables::tunables;
 
 use crate::manager::SegmentedChangelogManager;
+use crate::reload_scheduler::SegmentedChangelogReloadScheduler;
 use crate::segmented_changelog_delegate;
 use crate::types::SegmentedChangelogVersion;
 use crate::CloneData;
@@ -30,37 +34,118 @@ use crate::Location;
 use crate::SegmentedChangelog;
 
 struct SegmentedChangelogLoader {
-    manager: SegmentedChangelogManager,
+    manager: Arc<SegmentedChangelogManager>,
     ctx: CoreContext,
-    last_loaded_version: Option<SegmentedChangelogVersion>,
+    last_loaded_version: Arc<Mutex<Option<SegmentedChangelogVersion>>>,
+    last_loaded_head: Option<ChangesetId>,
+}
+
+/// Compares the version of the loaded dag with the latest saved version.
+struct VersionCheck {
+    manager: Arc<SegmentedChangelogManager>,
+    last_loaded_version: Arc<Mutex<Option<SegmentedChangelogVersion>>>,
+}
+
+impl VersionCheck {
+    /// Whether a newer version than the loaded one has been saved.  If the
+    /// loaded version isn't known, a reload is already due.
+    async fn newer_version_saved(&self, ctx: &CoreContext) -> Result<bool> {
+        let (served_version, _pinned) = self.manager.served_version(ctx).await?;
+        let last_loaded_version = *self.last_loaded_version.lock().expect("lock poisoned");
+        Ok(last_loaded_version.map_or(false, |version| version != served_version))
+    }
 }
 
 type LoadedSegmentedChangelog = Arc<dyn SegmentedChangelog + Send + Sync>;
 
+/// Resolve the location of a changeset in the dag and back.  This goes
+/// through both the iddag and the idmap, so it also sets up the idmap's
+/// connections and fills its caches.
+async fn query_head(
+    ctx: &CoreContext,
+    sc: &(dyn SegmentedChangelog + Send + Sync),
+    head: ChangesetId,
+) -> Result<()> {
+    let location = sc
+        .changeset_id_to_location(ctx, vec![head], head)
+        .await?
+        .with_context(|| format!("{} has no location in the dag", head))?;
+    let cs_id = sc.location_to_changeset_id(ctx, location).await?;
+    ensure!(
+        cs_id == head,
+        "location of {} resolved to {} instead",
+        head,
+        cs_id
+    );
+    Ok(())
+}
+
 #[async_trait]
 impl Loader<LoadedSegmentedChangelog> for SegmentedChangelogLoader {
     async fn load(&mut self) -> Result<Option<LoadedSegmentedChangelog>> {
-        let (sc, sc_version) = self.manager.load(&self.ctx).await?;
-        self.last_loaded_version = Some(sc_version);
+        let (sc, sc_version, head) = self.manager.load_with_head(&self.ctx).await?;
+        // Warm the new dag up before it replaces the current one, so that
+        // requests are never served by a dag that is still cold.
+        if let Some(head) = head {
+            query_head(&self.ctx, sc.as_ref(), head)
+                .await
+                .context("warming up segmented changelog")?;
+        }
+        *self.last_loaded_version.lock().expect("lock poisoned") = Some(sc_version);
+        self.last_loaded_head = head;
         Ok(Some(sc))
     }
 
     async fn needs_reload(&mut self) -> Result<bool> {
-        Ok(self.last_loaded_version != Some(self.manager.latest_version(&self.ctx).await?))
+        let (served_version, _pinned) = self.manager.served_version(&self.ctx).await?;
+        Ok(*self.last_loaded_version.lock().expect("lock poisoned") != Some(served_version))
+    }
+
+    async fn verify(&mut self, loaded: &LoadedSegmentedChangelog) -> Result<()> {
+        let head = match self.last_loaded_head {
+            Some(head) => head,
+            None => return Ok(()),
+        };
+        let res = query_head(&self.ctx, loaded.as_ref(), head).await;
+        if res.is_err() {
+            // The previous dag is restored, so load this version again
+            // at the next reload.
+            *self.last_loaded_version.lock().expect("lock poisoned") = None;
+        }
+        res.context("verifying reloaded segmented changelog")
     }
 }
 
 pub struct PeriodicReloadSegmentedChangelog {
     reloader: Reloader<LoadedSegmentedChangelog>,
+    force_reload_notify: Arc<Notify>,
+    version_check: Option<VersionCheck>,
     abort_handle: AbortHandle,
 }
 
 impl PeriodicReloadSegmentedChangelog {
+    /// Start reloading every `period`.  With a scheduler, the reloads are
+    /// staggered against and share load slots with the other repos using it,
+    /// otherwise the first reload is randomly skewed.
     pub async fn start<L: Loader<LoadedSegmentedChangelog> + Send + Sync + 'static>(
         ctx: &CoreContext,
         period: Duration,
         loader: L,
         name: String,
+        scheduler: Option<&SegmentedChangelogReloadScheduler>,
+    ) -> Result<Self> {
+        Self::start_with_version_check(ctx, period, loader, name, scheduler, None).await
+    }
+
+    async fn start_with_version_check<
+        L: Loader<LoadedSegmentedChangelog> + Send + Sync + 'static,
+    >(
+        ctx: &CoreContext,
+        period: Duration,
+        loader: L,
+        name: String,
+        scheduler: Option<&SegmentedChangelogReloadScheduler>,
+        version_check: Option<VersionCheck>,
     ) -> Result<Self> {
         let force_reload_notify = Arc::new(Notify::new());
 
@@ -95,14 +180,31 @@ impl PeriodicReloadSegmentedChangelog {
         let (fut, abort_handle) = abortable(fut);
         tokio::spawn(fut);
 
+        let reloader = match scheduler {
+            Some(scheduler) => {
+                Reloader::reload_periodically_with_force_reload(
+                    ctx.clone(),
+                    scheduler.register(period),
+                    scheduler.limit_loads(loader),
+                    force_reload_notify.clone(),
+                )
+                .await?
+            }
+            None => {
+                Reloader::reload_periodically_with_skew_and_force_reload(
+                    ctx.clone(),
+                    period,
+                    loader,
+                    force_reload_notify.clone(),
+                )
+                .await?
+            }
+        };
+
         Ok(Self {
-            reloader: Reloader::reload_periodically_with_skew_and_force_reload(
-                ctx.clone(),
-                period,
-                loader,
-                force_reload_notify,
-            )
-            .await?,
+            reloader,
+            force_reload_notify,
+            version_check,
             abort_handle,
         })
     }
@@ -112,16 +214,25 @@ impl PeriodicReloadSegmentedChangelog {
         period: Duration,
         manager: SegmentedChangelogManager,
         name: String,
+        scheduler: Option<&SegmentedChangelogReloadScheduler>,
     ) -> Result<Self> {
-        Self::start(
+        let manager = Arc::new(manager);
+        let last_loaded_version = Arc::new(Mutex::new(None));
+        Self::start_with_version_check(
             ctx,
             period,
             SegmentedChangelogLoader {
-                manager,
+                manager: manager.clone(),
                 ctx: ctx.clone(),
-                last_loaded_version: None,
+                last_loaded_version: last_loaded_version.clone(),
+                last_loaded_head: None,
             },
             name,
+            scheduler,
+            Some(VersionCheck {
+                manager,
+                last_loaded_version,
+            }),
         )
         .await
     }
@@ -134,7 +245,18 @@ impl PeriodicReloadSegmentedChangelog {
 
 segmented_changelog_delegate!(
     PeriodicReloadSegmentedChangelog,
-    |&self, ctx: &CoreContext| { self.reloader.load() }
+    |&self, ctx: &CoreContext| { self.reloader.load() },
+    ensure_fresh {
+        if let Some(version_check) = &self.version_check {
+            if version_check.newer_version_saved(ctx).await? {
+                // The reload happens in the background, meanwhile the loaded
+                // dag is updated below.
+                info!(ctx.logger(), "reloading segmented changelog for fresh request");
+                self.force_reload_notify.notify_waiters();
+            }
+        }
+        self.reloader.load().ensure_fresh(ctx).await
+    }
 );
 
 impl Drop for PeriodicReloadSegmentedChangelog {
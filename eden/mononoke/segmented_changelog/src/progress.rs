@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use context::CoreContext;
+use mononoke_types::ChangesetId;
+use mononoke_types::RepositoryId;
+use slog::info;
+use stats::prelude::*;
+use tunables::tunables;
+
+define_stats! {
+    prefix = "mononoke.segmented_changelog.build";
+    processed: dynamic_singleton_counter("{}.processed", (repo_id: i32)),
+    expected: dynamic_singleton_counter("{}.expected", (repo_id: i32)),
+    percent: dynamic_singleton_counter("{}.percent", (repo_id: i32)),
+    eta_secs: dynamic_singleton_counter("{}.eta_secs", (repo_id: i32)),
+}
+
+const DEFAULT_LOG_SAMPLING_RATE: u64 = 5000;
+
+/// A point-in-time view of the progress of a build.
+#[derive(Clone, Debug)]
+pub struct BuildProgressSnapshot {
+    /// Whether the build is seeding a new segmented changelog, rather than
+    /// tailing an existing one.
+    pub seeding: bool,
+    /// Number of changesets added to the segmented changelog so far.
+    pub processed: u64,
+    /// Estimated number of changesets that will be added.
+    pub expected: u64,
+    /// The changeset most recently added.
+    pub current: Option<ChangesetId>,
+    pub elapsed: Duration,
+    /// Estimated time until the build completes, if it can be estimated.
+    pub eta: Option<Duration>,
+    pub finished: bool,
+}
+
+impl BuildProgressSnapshot {
+    /// Percentage of the expected changesets that have been processed.  The
+    /// expected count is an estimate, so this stays below 100 until the
+    /// build finishes.
+    pub fn percent(&self) -> f64 {
+        if self.finished || self.expected == 0 {
+            100.0
+        } else {
+            (100.0 * self.processed as f64 / self.expected as f64).min(99.9)
+        }
+    }
+}
+
+struct BuildProgressState {
+    processed: u64,
+    current: Option<ChangesetId>,
+    finished: bool,
+}
+
+/// Tracks how far seeding or tailing has got through the changesets it has
+/// to add, so that long builds report progress through logs and counters
+/// rather than running silently.
+pub struct BuildProgress {
+    ctx: CoreContext,
+    repo_id: RepositoryId,
+    seeding: bool,
+    expected: u64,
+    started: Instant,
+    state: Mutex<BuildProgressState>,
+}
+
+impl BuildProgress {
+    pub fn new(ctx: CoreContext, repo_id: RepositoryId, seeding: bool, expected: u64) -> Self {
+        let progress = Self {
+            ctx,
+            repo_id,
+            seeding,
+            expected,
+            started: Instant::now(),
+            state: Mutex::new(BuildProgressState {
+                processed: 0,
+                current: None,
+                finished: false,
+            }),
+        };
+        progress.report(&progress.snapshot());
+        progress
+    }
+
+    /// Record that a changeset is being added.
+    pub fn record(&self, cs_id: ChangesetId) {
+        let processed = {
+            let mut state = self.state.lock().expect("lock poisoned");
+            state.processed += 1;
+            state.current = Some(cs_id);
+            state.processed
+        };
+        let sampling_rate = tunables()
+            .segmented_changelog_tailer_log_sampling_rate()
+            .unwrap_or_default();
+        let sampling_rate = if sampling_rate <= 0 {
+            DEFAULT_LOG_SAMPLING_RATE
+        } else {
+            sampling_rate as u64
+        };
+        if processed % sampling_rate == 0 {
+            self.report(&self.snapshot());
+        }
+    }
+
+    /// Record that all changesets have been added.
+    pub fn finish(&self) {
+        self.state.lock().expect("lock poisoned").finished = true;
+        self.report(&self.snapshot());
+    }
+
+    pub fn snapshot(&self) -> BuildProgressSnapshot {
+        let state = self.state.lock().expect("lock poisoned");
+        let elapsed = self.started.elapsed();
+        let eta = if state.finished {
+            Some(Duration::ZERO)
+        } else if state.processed == 0 || state.processed >= self.expected {
+            None
+        } else {
+            let remaining = (self.expected - state.processed) as f64;
+            Some(elapsed.mul_f64(remaining / state.processed as f64))
+        };
+        BuildProgressSnapshot {
+            seeding: self.seeding,
+            processed: state.processed,
+            expected: self.expected,
+            current: state.current,
+            elapsed,
+            eta,
+            finished: state.finished,
+        }
+    }
+
+    fn report(&self, snapshot: &BuildProgressSnapshot) {
+        let repo_id = (self.repo_id.id(),);
+        let fb = self.ctx.fb;
+        STATS::processed.set_value(fb, snapshot.processed as i64, repo_id);
+        STATS::expected.set_value(fb, snapshot.expected as i64, repo_id);
+        STATS::percent.set_value(fb, snapshot.percent() as i64, repo_id);
+        STATS::eta_secs.set_value(
+            fb,
+            snapshot.eta.map_or(-1, |eta| eta.as_secs() as i64),
+            repo_id,
+        );
+        info!(
+            self.ctx.logger(),
+            "{} segmented changelog: {} of ~{} changesets ({:.1}%), current {}, elapsed {}s, eta {}",
+            if self.seeding { "seeding" } else { "tailing" },
+            snapshot.processed,
+            snapshot.expected,
+            snapshot.percent(),
+            snapshot
+                .current
+                .map_or_else(|| "none".to_string(), |cs_id| cs_id.to_string()),
+            snapshot.elapsed.as_secs(),
+            snapshot
+                .eta
+                .map_or_else(|| "unknown".to_string(), |eta| format!("{}s", eta.as_secs())),
+        );
+    }
+}
@@ -205,28 +205,50 @@ impl<'a> SegmentedChangelog for ReadOnlySegmentedChangelog<'a> {
         ancestor: ChangesetId,
         descendant: ChangesetId,
     ) -> Result<Option<bool>> {
-        let request_ids = self
-            .idmap
-            .find_many_dag_ids_maybe_stale(ctx, vec![ancestor, descendant])
-            .await?;
-        let ancestor_id = if let Some(ancestor_id) = request_ids.get(&ancestor) {
-            ancestor_id
-        } else {
-            return Ok(None);
-        };
-        let descendant_id = if let Some(descendant_id) = request_ids.get(&descendant) {
-            descendant_id
-        } else {
-            return Ok(None);
-        };
+        let (ancestor_id, descendant_id) =
+            match self.known_dag_id_pair(ctx, ancestor, descendant).await? {
+                Some(ids) => ids,
+                None => return Ok(None),
+            };
+        Ok(Some(self.iddag.is_ancestor(ancestor_id, descendant_id)?))
+    }
 
-        // Even though the ids exist, our local DAG might not have them.
-        let all = self.iddag.all()?;
-        if !all.contains(descendant_id.clone()) || !all.contains(ancestor_id.clone()) {
-            return Ok(None);
-        }
+    async fn greatest_common_ancestors(
+        &self,
+        ctx: &CoreContext,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        let (a_id, b_id) = match self.known_dag_id_pair(ctx, a, b).await? {
+            Some(ids) => ids,
+            None => return Ok(None),
+        };
+        let gca_ids = self.iddag.gca_all(DagIdSet::from_spans(vec![a_id, b_id]))?;
+        let mut cs_ids = self
+            .changeset_ids_in_order(ctx, gca_ids.iter_desc().collect())
+            .await?;
+        cs_ids.sort();
+        Ok(Some(cs_ids))
+    }
 
-        Ok(Some(self.iddag.is_ancestor(*ancestor_id, *descendant_id)?))
+    async fn range(
+        &self,
+        ctx: &CoreContext,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        let (start_id, end_id) = match self.known_dag_id_pair(ctx, start, end).await? {
+            Some(ids) => ids,
+            None => return Ok(None),
+        };
+        // Dag ids are assigned in topological order, so ascending ids are
+        // ancestors first.  Only the ids within the limit are mapped.
+        let range_ids = self.iddag.range(start_id.into(), end_id.into())?;
+        let cs_ids = self
+            .changeset_ids_in_order(ctx, range_ids.iter_asc().take(limit).collect())
+            .await?;
+        Ok(Some(cs_ids))
     }
 
     async fn disabled(&self, _ctx: &CoreContext) -> Result<bool> {
@@ -239,6 +261,52 @@ impl<'a> ReadOnlySegmentedChangelog<'a> {
         Self { iddag, idmap }
     }
 
+    /// Look up the dag ids of a pair of changesets.  Returns None if either
+    /// is not in the dag.
+    async fn known_dag_id_pair(
+        &self,
+        ctx: &CoreContext,
+        first: ChangesetId,
+        second: ChangesetId,
+    ) -> Result<Option<(DagId, DagId)>> {
+        let request_ids = self
+            .idmap
+            .find_many_dag_ids_maybe_stale(ctx, vec![first, second])
+            .await?;
+        let (first_id, second_id) = match (request_ids.get(&first), request_ids.get(&second)) {
+            (Some(first_id), Some(second_id)) => (*first_id, *second_id),
+            _ => return Ok(None),
+        };
+
+        // Even though the ids exist, our local DAG might not have them.
+        let all = self.iddag.all()?;
+        if !all.contains(first_id) || !all.contains(second_id) {
+            return Ok(None);
+        }
+        Ok(Some((first_id, second_id)))
+    }
+
+    /// Map dag ids to changeset ids, keeping their order.
+    async fn changeset_ids_in_order(
+        &self,
+        ctx: &CoreContext,
+        dag_ids: Vec<DagId>,
+    ) -> Result<Vec<ChangesetId>> {
+        let mut mapping = self
+            .idmap
+            .find_many_changeset_ids(ctx, dag_ids.clone())
+            .await
+            .context("error retrieving mappings for dag ids")?;
+        dag_ids
+            .into_iter()
+            .map(|dag_id| {
+                mapping
+                    .remove(&dag_id)
+                    .ok_or_else(|| format_err!("failed to find changeset id for {}", dag_id))
+            })
+            .collect()
+    }
+
     pub(crate) async fn known_location_to_many_changeset_ids(
         &self,
         ctx: &CoreContext,
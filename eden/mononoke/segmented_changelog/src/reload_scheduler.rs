@@ -0,0 +1,139 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reloader::Loader;
+use tokio::sync::Semaphore;
+
+/// Default number of segmented changelogs that may load at once.
+const DEFAULT_MAX_CONCURRENT_LOADS: usize = 4;
+
+struct SchedulerInner {
+    registered: AtomicU64,
+    load_permits: Arc<Semaphore>,
+}
+
+/// Schedules the periodic reloads of segmented changelogs for all the repos
+/// served by one process.
+///
+/// Each repo's reloads are offset within its reload period, so that repos
+/// started together don't all reload together, and only a bounded number of
+/// dags are loaded at once.  Offsets are assigned in registration order so
+/// that they stay evenly spread however many repos are registered.
+#[derive(Clone)]
+pub struct SegmentedChangelogReloadScheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl Default for SegmentedChangelogReloadScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_LOADS)
+    }
+}
+
+impl SegmentedChangelogReloadScheduler {
+    pub fn new(max_concurrent_loads: usize) -> Self {
+        Self {
+            inner: Arc::new(SchedulerInner {
+                registered: AtomicU64::new(0),
+                load_permits: Arc::new(Semaphore::new(max_concurrent_loads.max(1))),
+            }),
+        }
+    }
+
+    /// Register a repo that reloads every `period`, returning the interval
+    /// getter for its reloader.  The first interval is lengthened by the
+    /// repo's offset, later ones are the plain period.
+    pub(crate) fn register(&self, period: Duration) -> impl FnMut() -> Duration + Send + 'static {
+        let index = self.inner.registered.fetch_add(1, Ordering::Relaxed);
+        let offset = period.mul_f64(spread(index));
+        let mut first = true;
+        move || {
+            if first {
+                first = false;
+                period + offset
+            } else {
+                period
+            }
+        }
+    }
+
+    /// Wrap a loader so that its loads wait for a free load slot.
+    pub(crate) fn limit_loads<L>(&self, loader: L) -> ScheduledLoader<L> {
+        ScheduledLoader {
+            inner: loader,
+            load_permits: self.inner.load_permits.clone(),
+        }
+    }
+}
+
+/// Fraction of the period to offset the repo with the given registration
+/// index by.  This is the base 2 van der Corput sequence, i.e. 0, 1/2, 1/4,
+/// 3/4, 1/8 ..., which keeps any number of leading entries evenly spaced.
+fn spread(index: u64) -> f64 {
+    index.reverse_bits() as f64 / 2f64.powi(64)
+}
+
+pub(crate) struct ScheduledLoader<L> {
+    inner: L,
+    load_permits: Arc<Semaphore>,
+}
+
+#[async_trait]
+impl<R, L> Loader<R> for ScheduledLoader<L>
+where
+    L: Loader<R> + Send + Sync,
+    R: Send + 'static,
+{
+    async fn load(&mut self) -> Result<Option<R>> {
+        let _permit = self.load_permits.acquire().await?;
+        self.inner.load().await
+    }
+
+    async fn needs_reload(&mut self) -> Result<bool> {
+        self.inner.needs_reload().await
+    }
+
+    async fn verify(&mut self, loaded: &R) -> Result<()>
+    where
+        R: Sync,
+    {
+        self.inner.verify(loaded).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread() {
+        let offsets: Vec<f64> = (0..8).map(spread).collect();
+        assert_eq!(
+            offsets,
+            vec![0.0, 0.5, 0.25, 0.75, 0.125, 0.625, 0.375, 0.875]
+        );
+    }
+
+    #[test]
+    fn test_register_offsets_first_interval() {
+        let scheduler = SegmentedChangelogReloadScheduler::new(1);
+        let period = Duration::from_secs(60);
+        let mut first = scheduler.register(period);
+        let mut second = scheduler.register(period);
+        assert_eq!(first(), period);
+        assert_eq!(first(), period);
+        assert_eq!(second(), Duration::from_secs(90));
+        assert_eq!(second(), period);
+    }
+}
@@ -0,0 +1,402 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Shadow reads for Segmented Changelog.
+//!
+//! For repos with shadow reads enabled, a sample of ancestry queries answered
+//! by the dag are recomputed using the commit graph, which is the path these
+//! queries take without Segmented Changelog.  Any disagreement is logged to
+//! scuba.  The shadow computation runs in the background, so it doesn't
+//! affect the latency of the request that triggered it, and at most
+//! `MAX_CONCURRENT_SHADOW_READS` run at once; samples taken while that many
+//! are running are skipped.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use commit_graph::ArcCommitGraph;
+use commit_graph::CommitGraph;
+use context::CoreContext;
+use mercurial_types::HgChangesetId;
+use mononoke_types::ChangesetId;
+use rand::Rng;
+use slog::warn;
+use stats::prelude::*;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tunables::tunables;
+
+use crate::parents::first_parent_walk;
+use crate::CloneData;
+use crate::Location;
+use crate::SegmentedChangelog;
+
+define_stats! {
+    prefix = "mononoke.segmented_changelog.shadow";
+    checked: timeseries(Sum),
+    mismatch: timeseries(Sum),
+    inconclusive: timeseries(Sum),
+    failed: timeseries(Sum),
+    skipped: timeseries(Sum),
+}
+
+/// The most changesets a single shadow computation will visit before giving
+/// up on the comparison.
+const MAX_SHADOW_TRAVERSAL: u64 = 10_000;
+
+/// The most shadow computations that may run in the background at once.
+const MAX_CONCURRENT_SHADOW_READS: usize = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+enum ShadowOutcome {
+    Match,
+    /// The commit graph disagreed with the dag, and gave this answer.
+    Mismatch(String),
+    /// The comparison was too expensive to make.
+    Inconclusive,
+}
+
+pub struct ShadowReadSegmentedChangelog {
+    inner: Arc<dyn SegmentedChangelog + Send + Sync>,
+    commit_graph: ArcCommitGraph,
+    in_flight: Arc<Semaphore>,
+}
+
+impl ShadowReadSegmentedChangelog {
+    pub fn new(
+        inner: Arc<dyn SegmentedChangelog + Send + Sync>,
+        commit_graph: ArcCommitGraph,
+    ) -> Self {
+        Self {
+            inner,
+            commit_graph,
+            in_flight: Arc::new(Semaphore::new(MAX_CONCURRENT_SHADOW_READS)),
+        }
+    }
+
+    /// Decide whether to shadow this request, returning the permit the
+    /// shadow computation must hold while it runs.
+    fn start_shadow(&self) -> Option<OwnedSemaphorePermit> {
+        if !should_sample() {
+            return None;
+        }
+        match self.in_flight.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                STATS::skipped.add_value(1);
+                None
+            }
+        }
+    }
+}
+
+fn should_sample() -> bool {
+    let sampling_rate = tunables()
+        .segmented_changelog_shadow_read_sampling_rate()
+        .unwrap_or_default();
+    sampling_rate > 0 && rand::thread_rng().gen_range(0..sampling_rate) == 0
+}
+
+fn report(
+    ctx: &CoreContext,
+    method: &str,
+    request: String,
+    dag_result: String,
+    outcome: Result<ShadowOutcome>,
+) {
+    STATS::checked.add_value(1);
+    match outcome {
+        Ok(ShadowOutcome::Match) => {}
+        Ok(ShadowOutcome::Mismatch(commit_graph_result)) => {
+            STATS::mismatch.add_value(1);
+            ctx.scuba()
+                .clone()
+                .add("shadow_method", method)
+                .add("shadow_request", request)
+                .add("shadow_dag_result", dag_result)
+                .add("shadow_commit_graph_result", commit_graph_result)
+                .log_with_msg("Segmented changelog shadow read mismatch", None);
+        }
+        Ok(ShadowOutcome::Inconclusive) => STATS::inconclusive.add_value(1),
+        Err(e) => {
+            STATS::failed.add_value(1);
+            warn!(
+                ctx.logger(),
+                "segmented changelog shadow read failed: {:?}", e
+            );
+        }
+    }
+}
+
+async fn check_is_ancestor(
+    ctx: &CoreContext,
+    commit_graph: &CommitGraph,
+    ancestor: ChangesetId,
+    descendant: ChangesetId,
+    dag_result: bool,
+) -> Result<ShadowOutcome> {
+    let commit_graph_result = commit_graph.is_ancestor(ctx, ancestor, descendant).await?;
+    if commit_graph_result == dag_result {
+        Ok(ShadowOutcome::Match)
+    } else {
+        Ok(ShadowOutcome::Mismatch(commit_graph_result.to_string()))
+    }
+}
+
+/// Resolve a location by following first parents in the commit graph, and
+/// compare with the changesets the dag resolved it to.
+async fn check_location_to_many_changeset_ids(
+    ctx: &CoreContext,
+    commit_graph: &CommitGraph,
+    location: Location<ChangesetId>,
+    count: u64,
+    dag_cs_ids: &[ChangesetId],
+) -> Result<ShadowOutcome> {
+    let cs_ids = first_parent_walk(location, count, MAX_SHADOW_TRAVERSAL, |cs_id| async move {
+        let parents = commit_graph.changeset_parents(ctx, cs_id).await?;
+        Ok(parents.first().copied())
+    })
+    .await?;
+    let cs_ids = match cs_ids {
+        Some(cs_ids) => cs_ids,
+        None => return Ok(ShadowOutcome::Inconclusive),
+    };
+    if cs_ids == dag_cs_ids {
+        Ok(ShadowOutcome::Match)
+    } else {
+        Ok(ShadowOutcome::Mismatch(format!("{:?}", cs_ids)))
+    }
+}
+
+#[async_trait]
+impl SegmentedChangelog for ShadowReadSegmentedChangelog {
+    async fn location_to_many_changeset_ids(
+        &self,
+        ctx: &CoreContext,
+        location: Location<ChangesetId>,
+        count: u64,
+    ) -> Result<Vec<ChangesetId>> {
+        let cs_ids = self
+            .inner
+            .location_to_many_changeset_ids(ctx, location, count)
+            .await?;
+        if let Some(permit) = self.start_shadow() {
+            let ctx = ctx.clone();
+            let commit_graph = self.commit_graph.clone();
+            let dag_cs_ids = cs_ids.clone();
+            tokio::spawn(async move {
+                let _permit = permit;
+                let outcome = check_location_to_many_changeset_ids(
+                    &ctx,
+                    &commit_graph,
+                    location,
+                    count,
+                    &dag_cs_ids,
+                )
+                .await;
+                report(
+                    &ctx,
+                    "location_to_many_changeset_ids",
+                    format!("{:?} count {}", location, count),
+                    format!("{:?}", dag_cs_ids),
+                    outcome,
+                );
+            });
+        }
+        Ok(cs_ids)
+    }
+
+    async fn many_changeset_ids_to_locations(
+        &self,
+        ctx: &CoreContext,
+        master_heads: Vec<ChangesetId>,
+        cs_ids: Vec<ChangesetId>,
+    ) -> Result<HashMap<ChangesetId, Result<Location<ChangesetId>>>> {
+        self.inner
+            .many_changeset_ids_to_locations(ctx, master_heads, cs_ids)
+            .await
+    }
+
+    async fn clone_data(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<(CloneData<ChangesetId>, HashMap<ChangesetId, HgChangesetId>)> {
+        self.inner.clone_data(ctx).await
+    }
+
+    async fn pull_data(
+        &self,
+        ctx: &CoreContext,
+        common: Vec<ChangesetId>,
+        missing: Vec<ChangesetId>,
+    ) -> Result<CloneData<ChangesetId>> {
+        self.inner.pull_data(ctx, common, missing).await
+    }
+
+    async fn disabled(&self, ctx: &CoreContext) -> Result<bool> {
+        self.inner.disabled(ctx).await
+    }
+
+    async fn is_ancestor(
+        &self,
+        ctx: &CoreContext,
+        ancestor: ChangesetId,
+        descendant: ChangesetId,
+    ) -> Result<Option<bool>> {
+        let result = self.inner.is_ancestor(ctx, ancestor, descendant).await?;
+        // Inconclusive answers fall back to other paths anyway, so there is
+        // nothing to compare.
+        if let Some(dag_result) = result {
+            if let Some(permit) = self.start_shadow() {
+                let ctx = ctx.clone();
+                let commit_graph = self.commit_graph.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    let outcome =
+                        check_is_ancestor(&ctx, &commit_graph, ancestor, descendant, dag_result)
+                            .await;
+                    report(
+                        &ctx,
+                        "is_ancestor",
+                        format!("{} -> {}", ancestor, descendant),
+                        dag_result.to_string(),
+                        outcome,
+                    );
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    async fn greatest_common_ancestors(
+        &self,
+        ctx: &CoreContext,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        self.inner.greatest_common_ancestors(ctx, a, b).await
+    }
+
+    async fn range(
+        &self,
+        ctx: &CoreContext,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>> {
+        self.inner.range(ctx, start, end, limit).await
+    }
+
+    async fn build_up_to_heads(&self, ctx: &CoreContext, heads: &[ChangesetId]) -> Result<bool> {
+        self.inner.build_up_to_heads(ctx, heads).await
+    }
+
+    async fn ensure_fresh(&self, ctx: &CoreContext) -> Result<()> {
+        self.inner.ensure_fresh(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use commit_graph::CommitGraphArc;
+    use fbinit::FacebookInit;
+    use fixtures::Linear;
+    use fixtures::TestRepoFixture;
+    use maplit::hashmap;
+    use tests_utils::resolve_cs_id;
+    use tunables::with_tunables;
+    use tunables::MononokeTunables;
+
+    use super::*;
+    use crate::DisabledSegmentedChangelog;
+
+    #[fbinit::test]
+    async fn test_check_is_ancestor(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo = Linear::getrepo(fb).await;
+        let commit_graph = repo.commit_graph_arc();
+        let head = resolve_cs_id(&ctx, &repo, "79a13814c5ce7330173ec04d279bf95ab3f652fb").await?;
+        let older = resolve_cs_id(&ctx, &repo, "d0a361e9022d226ae52f689667bd7d212a19cfe0").await?;
+
+        assert_eq!(
+            check_is_ancestor(&ctx, &commit_graph, older, head, true).await?,
+            ShadowOutcome::Match
+        );
+        assert_eq!(
+            check_is_ancestor(&ctx, &commit_graph, head, older, false).await?,
+            ShadowOutcome::Match
+        );
+        assert_eq!(
+            check_is_ancestor(&ctx, &commit_graph, older, head, false).await?,
+            ShadowOutcome::Mismatch("true".to_string())
+        );
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_check_location(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo = Linear::getrepo(fb).await;
+        let commit_graph = repo.commit_graph_arc();
+        let known = resolve_cs_id(&ctx, &repo, "d0a361e9022d226ae52f689667bd7d212a19cfe0").await?;
+        let expected =
+            resolve_cs_id(&ctx, &repo, "3e0e761030db6e479a7fb58b12881883f9f8c63f").await?;
+        let location = Location::new(known, 2);
+
+        assert_eq!(
+            check_location_to_many_changeset_ids(&ctx, &commit_graph, location, 1, &[expected])
+                .await?,
+            ShadowOutcome::Match
+        );
+        assert_eq!(
+            check_location_to_many_changeset_ids(&ctx, &commit_graph, location, 1, &[known])
+                .await?,
+            ShadowOutcome::Mismatch(format!("{:?}", vec![expected]))
+        );
+        assert_eq!(
+            check_location_to_many_changeset_ids(
+                &ctx,
+                &commit_graph,
+                Location::new(known, MAX_SHADOW_TRAVERSAL),
+                1,
+                &[],
+            )
+            .await?,
+            ShadowOutcome::Inconclusive
+        );
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_shadow_reads_bounded(fb: FacebookInit) -> Result<()> {
+        let repo = Linear::getrepo(fb).await;
+        let sc = ShadowReadSegmentedChangelog::new(
+            Arc::new(DisabledSegmentedChangelog::new()),
+            repo.commit_graph_arc(),
+        );
+
+        // Unsampled requests are never shadowed.
+        assert!(sc.start_shadow().is_none());
+
+        let tunables = MononokeTunables::default();
+        tunables.update_ints(&hashmap! {
+            "segmented_changelog_shadow_read_sampling_rate".to_string() => 1,
+        });
+        with_tunables(tunables, || {
+            let permits = (0..MAX_CONCURRENT_SHADOW_READS)
+                .map(|_| sc.start_shadow().expect("shadow read should start"))
+                .collect::<Vec<_>>();
+            // Every permit is taken, so further samples are skipped.
+            assert!(sc.start_shadow().is_none());
+            drop(permits);
+            assert!(sc.start_shadow().is_some());
+        });
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@
  */
 
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -33,6 +34,7 @@ use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use futures_stats::TimedFutureExt;
 use metaconfig_types::MetadataDatabaseConfig;
+use metaconfig_types::SegmentedChangelogConfig;
 use mononoke_types::Generation;
 use mononoke_types::RepositoryId;
 use phases::PhasesArc;
@@ -56,6 +58,8 @@ use crate::idmap::CacheHandlers;
 use crate::idmap::IdMapFactory;
 use crate::owned::OwnedSegmentedChangelog;
 use crate::parents::FetchParents;
+use crate::progress::BuildProgress;
+use crate::progress::BuildProgressSnapshot;
 use crate::types::IdMapVersion;
 use crate::types::SegmentedChangelogVersion;
 use crate::update::server_namedag;
@@ -102,6 +106,7 @@ pub struct SegmentedChangelogTailer {
     idmap_factory: IdMapFactory,
     clone_hints: CloneHints,
     bonsai_hg_mapping: Arc<dyn BonsaiHgMapping>,
+    progress: Mutex<Option<Arc<BuildProgress>>>,
 }
 
 impl SegmentedChangelogTailer {
@@ -120,7 +125,7 @@ impl SegmentedChangelogTailer {
         let clone_hints = CloneHints::new(connections.0.clone(), repo_id, blobstore.clone());
         let sc_version_store = SegmentedChangelogVersionStore::new(connections.0.clone(), repo_id);
         let iddag_save_store = IdDagSaveStore::new(repo_id, blobstore);
-        let mut idmap_factory = IdMapFactory::new(connections.0, replica_lag_monitor, repo_id);
+        let mut idmap_factory = connections.idmap_factory(replica_lag_monitor, repo_id);
         if let Some(cache_handler_factory) = cache_handler_factory {
             let cache_handlers = CacheHandlers::new(cache_handler_factory);
             idmap_factory = idmap_factory.with_cache_handlers(cache_handlers);
@@ -136,14 +141,25 @@ impl SegmentedChangelogTailer {
             idmap_factory,
             clone_hints,
             bonsai_hg_mapping,
+            progress: Mutex::new(None),
         }
     }
 
+    /// Progress of the current or most recent update.
+    pub fn progress(&self) -> Option<BuildProgressSnapshot> {
+        self.progress
+            .lock()
+            .expect("lock poisoned")
+            .as_ref()
+            .map(|progress| progress.snapshot())
+    }
+
     pub async fn build_from(
         ctx: &CoreContext,
         blobrepo: &BlobRepo,
         storage_config_metadata: &MetadataDatabaseConfig,
         mysql_options: &MysqlOptions,
+        config: &SegmentedChangelogConfig,
         seed_heads: Vec<SeedHead>,
         prefetched_commits: impl Stream<Item = Result<ChangesetEntry, Error>>,
         cache_handler_factory: Option<CacheHandlerFactory>,
@@ -173,15 +189,15 @@ impl SegmentedChangelogTailer {
         .await
         .with_context(|| format!("constructing metadata sql factory for repo {}", repo_id))?;
 
-        let segmented_changelog_sql_connections = sql_factory
-            .open::<SegmentedChangelogSqlConnections>()
-            .await
-            .with_context(|| {
-                format!(
-                    "error constructing segmented changelog sql connections for repo {}",
-                    repo_id
-                )
-            })?;
+        let segmented_changelog_sql_connections =
+            SegmentedChangelogSqlConnections::open(&sql_factory, config)
+                .await
+                .with_context(|| {
+                    format!(
+                        "error constructing segmented changelog sql connections for repo {}",
+                        repo_id
+                    )
+                })?;
 
         let changeset_fetcher = Arc::new(
             PrefetchedChangesetsFetcher::new(
@@ -320,7 +336,7 @@ impl SegmentedChangelogTailer {
                 set.count(),
             );
         }
-        let idmap = self.idmap_factory.for_writer(ctx, idmap_version);
+        let idmap = self.idmap_factory.for_writer(ctx, idmap_version)?;
 
         let mut namedag = server_namedag(ctx.clone(), iddag, idmap)?;
 
@@ -336,36 +352,41 @@ impl SegmentedChangelogTailer {
             .try_collect()
             .await?;
 
-        let changeset_fetcher = {
-            let namedag_max_gen = stream::iter(head_commits.iter().map(Ok::<_, Error>))
-                .try_fold(0, {
-                    let fetcher = &self.changeset_fetcher;
-                    move |max, cs_id| async move {
-                        let gen = fetcher.get_generation_number(ctx, *cs_id).await?;
-                        Ok(max.max(gen.value()))
-                    }
-                })
-                .await?;
-            let heads_min_gen = stream::iter(
-                heads
-                    .vertexes()
-                    .iter()
-                    .map(|name| Ok::<_, Error>(cs_id_from_vertex_name(name))),
-            )
-            .try_fold(Generation::max_gen().value(), {
+        let namedag_max_gen = stream::iter(head_commits.iter().map(Ok::<_, Error>))
+            .try_fold(0, {
                 let fetcher = &self.changeset_fetcher;
-                move |min, cs_id| async move {
-                    let gen = fetcher.get_generation_number(ctx, cs_id).await?;
-                    Ok(min.min(gen.value()))
+                move |max, cs_id| async move {
+                    let gen = fetcher.get_generation_number(ctx, *cs_id).await?;
+                    Ok(max.max(gen.value()))
                 }
             })
             .await?;
+        let (heads_min_gen, heads_max_gen) = stream::iter(
+            heads
+                .vertexes()
+                .iter()
+                .map(|name| Ok::<_, Error>(cs_id_from_vertex_name(name))),
+        )
+        .try_fold((Generation::max_gen().value(), 0), {
+            let fetcher = &self.changeset_fetcher;
+            move |(min, max), cs_id| async move {
+                let gen = fetcher.get_generation_number(ctx, cs_id).await?;
+                Ok((min.min(gen.value()), max.max(gen.value())))
+            }
+        })
+        .await?;
+        // Generation numbers count the longest path, so underestimate the
+        // number of changesets where history branches.  Prefer the bounds of
+        // the changesets table where we load them.
+        let mut expected = heads_max_gen.saturating_sub(namedag_max_gen);
 
+        let changeset_fetcher = {
             if heads_min_gen.saturating_sub(namedag_max_gen) > 1000 {
                 let repo_bounds = self
                     .bulk_fetch
                     .get_repo_bounds_after_commits(ctx, head_commits)
                     .await?;
+                expected = expected.max(repo_bounds.1.saturating_sub(repo_bounds.0));
                 info!(ctx.logger(), "prefetching changeset entries",);
                 let mut counter = 0usize;
                 // This has the potential to cause OOM by fetching a large
@@ -397,7 +418,15 @@ impl SegmentedChangelogTailer {
             }
         };
 
-        let parent_fetcher = FetchParents::new(ctx.clone(), changeset_fetcher);
+        let progress = Arc::new(BuildProgress::new(
+            ctx.clone(),
+            self.repo_id,
+            seeding,
+            expected,
+        ));
+        *self.progress.lock().expect("lock poisoned") = Some(progress.clone());
+        let parent_fetcher =
+            FetchParents::new(ctx.clone(), changeset_fetcher).with_progress(progress.clone());
 
         info!(ctx.logger(), "starting the actual update");
         // Note on memory use: we do not flush the changes out in the middle
@@ -405,6 +434,7 @@ impl SegmentedChangelogTailer {
         // Thus, if OOMs happen here, the IdMap may need to flush writes to the DB
         // at interesting points.
         let changed = namedag.add_heads(&parent_fetcher, &heads).await?;
+        progress.finish();
 
         self.clone_hints
             .add_hints(
@@ -47,8 +47,11 @@ use phases::PhasesRef;
 use repo_blobstore::RepoBlobstoreRef;
 use repo_identity::RepoIdentityRef;
 use revset::AncestorsNodeStream;
+use sql::Connection;
 use sql_construct::SqlConstruct;
+use sql_ext::open_sqlite_in_memory;
 use sql_ext::replication::NoReplicaLagMonitor;
+use sql_ext::SqlConnections;
 use tests_utils::resolve_cs_id;
 use tests_utils::CreateCommitContext;
 use tunables::override_tunables;
@@ -72,10 +75,12 @@ use crate::types::IdMapVersion;
 use crate::types::SegmentedChangelogVersion;
 use crate::version_store::SegmentedChangelogVersionStore;
 use crate::CloneHints;
+use crate::DisabledSegmentedChangelog;
 use crate::InProcessIdDag;
 use crate::Location;
 use crate::SeedHead;
 use crate::SegmentedChangelog;
+use crate::SegmentedChangelogLoadError;
 use crate::SegmentedChangelogRef;
 
 #[async_trait::async_trait]
@@ -396,6 +401,34 @@ async fn test_is_ancestor(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_greatest_common_ancestors_and_range(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let repo = MergeUneven::get_inner_repo(fb).await;
+    let sc = repo.segmented_changelog();
+    let blobrepo = repo.as_blob_repo();
+
+    // Graph looks like:
+    // a -> b -> c
+    //  \-> d -> e
+    let a = resolve_cs_id(&ctx, &blobrepo, "15c40d0abc36d47fb51c8eaec51ac7aad31f669c").await?;
+    let b = resolve_cs_id(&ctx, &blobrepo, "d7542c9db7f4c77dab4b315edd328edf1514952f").await?;
+    let c = resolve_cs_id(&ctx, &blobrepo, "b65231269f651cfe784fd1d97ef02a049a37b8a0").await?;
+    let e = resolve_cs_id(&ctx, &blobrepo, "1d8a907f7b4bf50c6a09c16361e2205047ecc5e5").await?;
+
+    assert_eq!(sc.greatest_common_ancestors(&ctx, c, e).await?, None);
+    assert_eq!(sc.range(&ctx, a, c, 10).await?, None);
+    assert!(sc.build_up_to_heads(&ctx, &[c, e]).await?);
+    assert_eq!(sc.greatest_common_ancestors(&ctx, c, e).await?, Some(vec![a]));
+    assert_eq!(sc.greatest_common_ancestors(&ctx, b, c).await?, Some(vec![b]));
+    assert_eq!(sc.range(&ctx, a, c, 10).await?, Some(vec![a, b, c]));
+    assert_eq!(sc.range(&ctx, a, c, 2).await?, Some(vec![a, b]));
+    assert_eq!(sc.range(&ctx, b, e, 10).await?, Some(vec![]));
+    assert_eq!(sc.range(&ctx, a, a, 10).await?, Some(vec![a]));
+
+    Ok(())
+}
+
 async fn validate_location_to_changeset_ids(
     ctx: CoreContext,
     blobrepo: BlobRepo,
@@ -496,6 +529,57 @@ async fn test_location_to_changeset_id_invalid_req(fb: FacebookInit) -> Result<(
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_location_to_changeset_ids_read_fallback(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let blobrepo = Linear::getrepo(fb).await;
+    let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+    let master = resolve_cs_id(&ctx, &blobrepo, "79a13814c5ce7330173ec04d279bf95ab3f652fb").await?;
+    let expected = try_join_all(
+        [
+            "0ed509bf086fadcb8a8a5384dc3b550729b0fc17",
+            "eed3a8c0ec67b6a6fe2eb3543334df3f0b4f202b",
+            "cb15ca4a43a59acff5388cea9648c162afde8372",
+        ]
+        .into_iter()
+        .map(|id| resolve_cs_id(&ctx, &blobrepo, id)),
+    )
+    .await?;
+    let location = Location::new(master, 4);
+
+    // Nothing is seeded yet, so only the fallback can answer.
+    let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned)
+        .await?
+        .with_read_fallback(true);
+    assert!(matches!(
+        manager.load(&ctx).await,
+        Err(SegmentedChangelogLoadError::NotSeeded(_))
+    ));
+    let sc = manager.with_fallback(Arc::new(DisabledSegmentedChangelog::new()));
+    assert_eq!(
+        sc.location_to_many_changeset_ids(&ctx, location, 3).await?,
+        expected
+    );
+
+    // The loaded dag doesn't include master, so the lookup falls back.
+    seed(&ctx, &blobrepo, &conns, expected[0]).await?;
+    let (sc, _sc_version) = manager.load(&ctx).await?;
+    assert_eq!(
+        sc.location_to_many_changeset_ids(&ctx, location, 3).await?,
+        expected
+    );
+
+    // Without the fallback the dag's error is returned.
+    let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned).await?;
+    let (sc, _sc_version) = manager.load(&ctx).await?;
+    assert!(
+        sc.location_to_many_changeset_ids(&ctx, location, 3)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
 async fn validate_changeset_id_to_location(
     ctx: CoreContext,
     blobrepo: BlobRepo,
@@ -1033,6 +1117,7 @@ async fn test_periodic_reload(fb: FacebookInit) -> Result<()> {
         Duration::from_secs(5),
         manager,
         blobrepo.repo_identity().name().to_string(),
+        None,
     )
     .await?;
 
@@ -1077,6 +1162,54 @@ async fn test_periodic_reload(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_periodic_reload_ensure_fresh(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let blobrepo = Arc::new(Linear::getrepo(fb).await);
+    let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+
+    let start_hg_id = "607314ef579bd2407752361ba1b0c1729d08b281"; // commit 4
+    let start_cs_id = resolve_cs_id(&ctx, &blobrepo, start_hg_id).await?;
+
+    seed(&ctx, &blobrepo, &conns, start_cs_id).await?;
+
+    tokio::time::pause();
+    let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned).await?;
+    let sc = PeriodicReloadSegmentedChangelog::start_from_manager(
+        &ctx,
+        Duration::from_secs(3600),
+        manager,
+        blobrepo.repo_identity().name().to_string(),
+        None,
+    )
+    .await?;
+
+    // Nothing newer is saved, so no reload is needed.
+    sc.ensure_fresh(&ctx).await?;
+    assert!(
+        tokio::time::timeout(Duration::from_secs(15), sc.wait_for_update())
+            .await
+            .is_err()
+    );
+
+    // A newer version is saved, but isn't due to be reloaded for an hour.
+    let tailer = new_tailer_for_tailing(&blobrepo, &conns).await?;
+    let _ = tailer.once(&ctx, false).await?;
+    assert_eq!(sc.head(&ctx).await?, start_cs_id);
+
+    // Wait for the reload alongside the request that triggers it.
+    let (fresh, updated) = futures::join!(
+        sc.ensure_fresh(&ctx),
+        tokio::time::timeout(Duration::from_secs(15), sc.wait_for_update()),
+    );
+    fresh?;
+    updated?;
+    let master = resolve_cs_id(&ctx, &blobrepo, "79a13814c5ce7330173ec04d279bf95ab3f652fb").await?;
+    assert_eq!(sc.head(&ctx).await?, master);
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn test_manager_check_if_indexed(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
@@ -1093,7 +1226,7 @@ async fn test_manager_check_if_indexed(fb: FacebookInit) -> Result<()> {
     let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned).await?;
 
     // Changeset is not part of segmented changelog before tailer run.
-    let version = manager.latest_version(&ctx).await?;
+    let (version, _pinned) = manager.served_version(&ctx).await?;
     assert!(
         !manager
             .check_if_changeset_indexed(&ctx, &version, master)
@@ -1103,7 +1236,7 @@ async fn test_manager_check_if_indexed(fb: FacebookInit) -> Result<()> {
     let tailer = new_tailer_for_tailing(&blobrepo, &conns).await?;
     let _ = tailer.once(&ctx, false).await?;
     // Changeset is part of segmented changelog after tailer run.
-    let version = manager.latest_version(&ctx).await?;
+    let (version, _pinned) = manager.served_version(&ctx).await?;
     assert!(
         manager
             .check_if_changeset_indexed(&ctx, &version, master)
@@ -1112,6 +1245,66 @@ async fn test_manager_check_if_indexed(fb: FacebookInit) -> Result<()> {
     Ok(())
 }
 
+#[fbinit::test]
+async fn test_manager_pinned_version(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let blobrepo = Arc::new(Linear::getrepo(fb).await);
+    let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+
+    let start_cs_id =
+        resolve_cs_id(&ctx, &blobrepo, "607314ef579bd2407752361ba1b0c1729d08b281").await?;
+    let master = resolve_cs_id(&ctx, &blobrepo, "79a13814c5ce7330173ec04d279bf95ab3f652fb").await?;
+
+    seed(&ctx, &blobrepo, &conns, start_cs_id).await?;
+    let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned).await?;
+    let (seeded_version, pinned) = manager.served_version(&ctx).await?;
+    assert!(!pinned);
+    manager.pin_version(&ctx, seeded_version).await?;
+
+    // Versions saved after the pin are ignored.
+    let tailer = new_tailer_for_tailing(&blobrepo, &conns).await?;
+    let _ = tailer.once(&ctx, false).await?;
+    assert_eq!(manager.served_version(&ctx).await?, (seeded_version, true));
+    let (_sc, sc_version, head) = manager.load_with_head(&ctx).await?;
+    assert_eq!(sc_version, seeded_version);
+    assert_eq!(head, Some(start_cs_id));
+
+    assert!(manager.unpin_version(&ctx).await?);
+    let (_sc, sc_version, head) = manager.load_with_head(&ctx).await?;
+    assert_ne!(sc_version, seeded_version);
+    assert_eq!(head, Some(master));
+    assert_eq!(manager.version_pin(&ctx).await?, None);
+
+    Ok(())
+}
+
+#[fbinit::test]
+async fn test_manager_version_pin_unreadable(fb: FacebookInit) -> Result<()> {
+    let ctx = CoreContext::test_mock(fb);
+    let blobrepo = Arc::new(Linear::getrepo(fb).await);
+    // Without the pin table, reading the pin fails.
+    let conn = open_sqlite_in_memory()?;
+    conn.execute_batch(SegmentedChangelogSqlConnections::CREATION_QUERY)?;
+    conn.execute_batch("DROP TABLE segmented_changelog_version_pin")?;
+    let conns = SegmentedChangelogSqlConnections::from_sql_connections(
+        SqlConnections::new_single(Connection::with_sqlite(conn)),
+    );
+
+    let start_cs_id =
+        resolve_cs_id(&ctx, &blobrepo, "607314ef579bd2407752361ba1b0c1729d08b281").await?;
+    seed(&ctx, &blobrepo, &conns, start_cs_id).await?;
+    let manager = get_manager(&blobrepo, &conns, vec![], SegmentedChangelogType::Owned).await?;
+
+    assert!(manager.version_pin(&ctx).await.is_err());
+    let latest_version = manager.latest_saved_version(&ctx).await?;
+    assert_eq!(manager.served_version(&ctx).await?, (latest_version, false));
+    let (_sc, sc_version, head) = manager.load_with_head(&ctx).await?;
+    assert_eq!(sc_version, latest_version);
+    assert_eq!(head, Some(start_cs_id));
+
+    Ok(())
+}
+
 #[fbinit::test]
 async fn test_mismatched_heads(fb: FacebookInit) -> Result<()> {
     let ctx = CoreContext::test_mock(fb);
@@ -62,6 +62,9 @@ impl IntoVertexList for SeedHead {
                 all_bookmarks_except_with_options(ctx, exceptions, bookmarks).await
             }
             Self::Bookmark(name) => bookmark_with_options(ctx, name, bookmarks).await,
+            Self::PublicBookmarksWithPrefix(prefix) => {
+                bookmarks_with_prefix_with_options(ctx, prefix, bookmarks).await
+            }
         }
     }
 }
@@ -159,6 +162,28 @@ async fn all_bookmarks_except_with_options(
         .into())
 }
 
+async fn bookmarks_with_prefix_with_options(
+    ctx: &CoreContext,
+    prefix: &BookmarkPrefix,
+    bookmarks: &dyn Bookmarks,
+) -> Result<VertexListWithOptions> {
+    Ok(bookmarks
+        .list(
+            ctx.clone(),
+            Freshness::MaybeStale,
+            prefix,
+            BookmarkCategory::ALL,
+            BookmarkKind::ALL_PUBLISHING,
+            &BookmarkPagination::FromStart,
+            u64::MAX,
+        )
+        .map_ok(|(_bookmark, cs)| head_with_options(&cs))
+        .try_collect::<Vec<_>>()
+        .await
+        .with_context(|| format!("error while listing bookmarks with prefix {}", prefix))?
+        .into())
+}
+
 async fn bookmark_with_options(
     ctx: &CoreContext,
     bookmark: &BookmarkKey,
@@ -220,6 +245,35 @@ mod tests {
         Ok(())
     }
 
+    #[fbinit::test]
+    async fn test_bookmarks_with_prefix_with_options(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let repo = prep_branch_wide_repo(fb).await?;
+
+        let res = bookmarks_with_prefix_with_options(
+            &ctx,
+            &BookmarkPrefix::new("sec")?,
+            repo.bookmarks(),
+        )
+        .await?;
+        assert_eq!(
+            res.vertexes(),
+            vec![VertexName::from_hex(
+                b"5ec506306edb84a4a47f901a55cedeec3113eb118bfae119982f45382481e3dc"
+            )?,]
+        );
+
+        // A prefix matching no bookmarks gives no heads rather than an error.
+        let res = bookmarks_with_prefix_with_options(
+            &ctx,
+            &BookmarkPrefix::new("release/")?,
+            repo.bookmarks(),
+        )
+        .await?;
+        assert!(res.vertexes().is_empty());
+        Ok(())
+    }
+
     #[fbinit::test]
     async fn test_all_bookmarks_except_with_options(fb: FacebookInit) -> Result<()> {
         let ctx = CoreContext::test_mock(fb);
@@ -11,11 +11,15 @@ use anyhow::Result;
 use context::CoreContext;
 use context::PerfCounterType;
 use mononoke_types::RepositoryId;
+use mononoke_types::Timestamp;
+use slog::warn;
+use sql::Connection;
 use sql_ext::mononoke_queries;
 use sql_ext::SqlConnections;
 use stats::prelude::*;
 
 use crate::logging::log_new_segmented_changelog_version;
+use crate::logging::log_segmented_changelog_version_pin;
 use crate::types::IdDagVersion;
 use crate::types::IdMapVersion;
 use crate::types::SegmentedChangelogVersion;
@@ -25,6 +29,9 @@ define_stats! {
     set: timeseries(Sum),
     update: timeseries(Sum),
     get: timeseries(Sum),
+    history: timeseries(Sum),
+    record_history_failure: timeseries(Sum),
+    get_pin: timeseries(Sum),
 }
 
 /// Specifies the versions for the latest SegmentedChangelogVersion. The version contains IdDag and
@@ -55,6 +62,7 @@ impl SegmentedChangelogVersionStore {
         )
         .await
         .with_context(|| format!("failed to set segmented changelog version {:?}", version))?;
+        self.record_history(ctx, version).await;
         log_new_segmented_changelog_version(ctx, self.repo_id, version);
         Ok(())
     }
@@ -86,6 +94,7 @@ impl SegmentedChangelogVersionStore {
                 version
             );
         }
+        self.record_history(ctx, version).await;
         log_new_segmented_changelog_version(ctx, self.repo_id, version);
         Ok(())
     }
@@ -97,6 +106,147 @@ impl SegmentedChangelogVersionStore {
         let rows = SelectVersion::query(&self.connections.read_connection, &self.repo_id).await?;
         Ok(rows.into_iter().next().map(|r| r.into()))
     }
+
+    /// Record that a version was set.  The version itself has already been
+    /// written, so failing to record it only means GC can't find it, and is
+    /// logged rather than returned.
+    async fn record_history(&self, ctx: &CoreContext, version: SegmentedChangelogVersion) {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlWrites);
+        if let Err(err) = InsertVersionHistory::query(
+            &self.connections.write_connection,
+            &self.repo_id,
+            &version.iddag_version,
+            &version.idmap_version,
+            &Timestamp::now(),
+        )
+        .await
+        {
+            STATS::record_history_failure.add_value(1);
+            warn!(
+                ctx.logger(),
+                "repo {}: failed to record segmented changelog version history {:?}: {:?}",
+                self.repo_id,
+                version,
+                err
+            );
+        }
+    }
+
+    /// Every version that has been set for this repo, along with the last
+    /// time it was set, newest first.  Versions set before history was
+    /// recorded are not included.
+    pub async fn history(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<Vec<(SegmentedChangelogVersion, Timestamp)>> {
+        STATS::history.add_value(1);
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlReadsMaster);
+        let rows =
+            SelectVersionHistory::query(&self.connections.read_master_connection, &self.repo_id)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(iddag_version, idmap_version, created_at)| {
+                (
+                    SegmentedChangelogVersion::new(iddag_version, idmap_version),
+                    created_at,
+                )
+            })
+            .collect())
+    }
+
+    /// Pin the version that servers load to `version`, ignoring versions
+    /// set after it until unpinned.  This is an emergency lever for when a
+    /// bad version has been set.
+    pub async fn pin(&self, ctx: &CoreContext, version: SegmentedChangelogVersion) -> Result<()> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlWrites);
+        SetVersionPin::query(
+            &self.connections.write_connection,
+            &self.repo_id,
+            &version.iddag_version,
+            &version.idmap_version,
+            &Timestamp::now(),
+        )
+        .await
+        .with_context(|| format!("failed to pin segmented changelog version {:?}", version))?;
+        log_segmented_changelog_version_pin(ctx, self.repo_id, Some(version));
+        Ok(())
+    }
+
+    /// Remove the pin, so that servers load the latest version again.
+    /// Returns whether a version was pinned.
+    pub async fn unpin(&self, ctx: &CoreContext) -> Result<bool> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlWrites);
+        let result = DeleteVersionPin::query(&self.connections.write_connection, &self.repo_id)
+            .await
+            .context("failed to unpin segmented changelog version")?;
+        let unpinned = result.affected_rows() > 0;
+        if unpinned {
+            log_segmented_changelog_version_pin(ctx, self.repo_id, None);
+        }
+        Ok(unpinned)
+    }
+
+    /// The pinned version, and when it was pinned.
+    pub async fn get_pin(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<Option<(SegmentedChangelogVersion, Timestamp)>> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlReadsReplica);
+        self.select_pin(&self.connections.read_connection).await
+    }
+
+    /// The pinned version, and when it was pinned, read from master so that
+    /// a pin set moments ago is seen.  Use this when acting on the pin, e.g.
+    /// to decide what is safe to delete.
+    pub async fn get_pin_master(
+        &self,
+        ctx: &CoreContext,
+    ) -> Result<Option<(SegmentedChangelogVersion, Timestamp)>> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlReadsMaster);
+        self.select_pin(&self.connections.read_master_connection)
+            .await
+    }
+
+    async fn select_pin(
+        &self,
+        connection: &Connection,
+    ) -> Result<Option<(SegmentedChangelogVersion, Timestamp)>> {
+        STATS::get_pin.add_value(1);
+        let rows = SelectVersionPin::query(connection, &self.repo_id).await?;
+        Ok(rows
+            .into_iter()
+            .next()
+            .map(|(iddag_version, idmap_version, pinned_at)| {
+                (
+                    SegmentedChangelogVersion::new(iddag_version, idmap_version),
+                    pinned_at,
+                )
+            }))
+    }
+
+    pub(crate) async fn delete_history(
+        &self,
+        ctx: &CoreContext,
+        version: SegmentedChangelogVersion,
+    ) -> Result<()> {
+        ctx.perf_counters()
+            .increment_counter(PerfCounterType::SqlWrites);
+        DeleteVersionHistory::query(
+            &self.connections.write_connection,
+            &self.repo_id,
+            &version.iddag_version,
+            &version.idmap_version,
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 mononoke_queries! {
@@ -132,6 +282,69 @@ mononoke_queries! {
         WHERE repo_id = {repo_id}
         "
     }
+
+    write InsertVersionHistory(
+        repo_id: RepositoryId,
+        iddag_version: IdDagVersion,
+        idmap_version: IdMapVersion,
+        created_at: Timestamp,
+    ) {
+        none,
+        "
+        REPLACE INTO segmented_changelog_version_history (repo_id, iddag_version, idmap_version, created_at)
+        VALUES ({repo_id}, {iddag_version}, {idmap_version}, {created_at})
+        "
+    }
+
+    read SelectVersionHistory(repo_id: RepositoryId) -> (IdDagVersion, IdMapVersion, Timestamp) {
+        "
+        SELECT iddag_version, idmap_version, created_at
+        FROM segmented_changelog_version_history
+        WHERE repo_id = {repo_id}
+        ORDER BY created_at DESC, idmap_version DESC
+        "
+    }
+
+    write DeleteVersionHistory(
+        repo_id: RepositoryId,
+        iddag_version: IdDagVersion,
+        idmap_version: IdMapVersion,
+    ) {
+        none,
+        "
+        DELETE FROM segmented_changelog_version_history
+        WHERE repo_id = {repo_id} AND iddag_version = {iddag_version} AND idmap_version = {idmap_version}
+        "
+    }
+
+    write SetVersionPin(
+        repo_id: RepositoryId,
+        iddag_version: IdDagVersion,
+        idmap_version: IdMapVersion,
+        pinned_at: Timestamp,
+    ) {
+        none,
+        "
+        REPLACE INTO segmented_changelog_version_pin (repo_id, iddag_version, idmap_version, pinned_at)
+        VALUES ({repo_id}, {iddag_version}, {idmap_version}, {pinned_at})
+        "
+    }
+
+    write DeleteVersionPin(repo_id: RepositoryId) {
+        none,
+        "
+        DELETE FROM segmented_changelog_version_pin
+        WHERE repo_id = {repo_id}
+        "
+    }
+
+    read SelectVersionPin(repo_id: RepositoryId) -> (IdDagVersion, IdMapVersion, Timestamp) {
+        "
+        SELECT iddag_version, idmap_version, pinned_at
+        FROM segmented_changelog_version_pin
+        WHERE repo_id = {repo_id}
+        "
+    }
 }
 
 #[cfg(test)]
@@ -207,4 +420,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[fbinit::test]
+    async fn test_pin(fb: FacebookInit) -> Result<()> {
+        let ctx = CoreContext::test_mock(fb);
+        let conns = SegmentedChangelogSqlConnections::with_sqlite_in_memory()?;
+        let version_store = SegmentedChangelogVersionStore::new(conns.0, RepositoryId::new(0));
+
+        let vm1 = SegmentedChangelogVersion::new(
+            IdDagVersion::from_serialized_bytes(b"a"),
+            IdMapVersion(1),
+        );
+        let vm2 = SegmentedChangelogVersion::new(
+            IdDagVersion::from_serialized_bytes(b"b"),
+            IdMapVersion(2),
+        );
+
+        assert_eq!(version_store.get_pin(&ctx).await?, None);
+        assert!(!version_store.unpin(&ctx).await?);
+        version_store.set(&ctx, vm1).await?;
+        version_store.pin(&ctx, vm1).await?;
+        version_store.set(&ctx, vm2).await?;
+        // Setting a newer version doesn't move the pin.
+        assert_eq!(version_store.get(&ctx).await?, Some(vm2));
+        assert_eq!(
+            version_store
+                .get_pin(&ctx)
+                .await?
+                .map(|(version, _)| version),
+            Some(vm1)
+        );
+        assert_eq!(
+            version_store
+                .get_pin_master(&ctx)
+                .await?
+                .map(|(version, _)| version),
+            Some(vm1)
+        );
+        assert!(version_store.unpin(&ctx).await?);
+        assert_eq!(version_store.get_pin(&ctx).await?, None);
+        assert_eq!(version_store.get_pin_master(&ctx).await?, None);
+
+        Ok(())
+    }
 }
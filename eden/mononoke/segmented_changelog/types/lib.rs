@@ -28,6 +28,18 @@ use mononoke_types::ChangesetId;
 use mononoke_types::RepositoryId;
 use thiserror::Error;
 
+/// How recent the dag answering a query must be.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DagFreshness {
+    /// The dag may lag behind the latest saved version and the master
+    /// bookmark, e.g. until the next periodic reload.
+    #[default]
+    MaybeStale,
+    /// The dag is brought up to date before answering, at some cost in
+    /// latency.
+    MustBeFresh,
+}
+
 #[facet::facet]
 #[async_trait]
 pub trait SegmentedChangelog: Send + Sync {
@@ -239,12 +251,42 @@ pub trait SegmentedChangelog: Send + Sync {
         descendant: ChangesetId,
     ) -> Result<Option<bool>>;
 
+    /// Find the greatest common ancestors of `a` and `b`, that is, the heads
+    /// of the commits that are ancestors of both, sorted by changeset id.
+    /// Returns None in case segmented changelog doesn't know about either of those commit.
+    async fn greatest_common_ancestors(
+        &self,
+        ctx: &CoreContext,
+        a: ChangesetId,
+        b: ChangesetId,
+    ) -> Result<Option<Vec<ChangesetId>>>;
+
+    /// Find the commits that are both descendants of `start` and ancestors of
+    /// `end`, including `start` and `end` themselves, in topological order.
+    /// At most `limit` commits are returned, starting from `start`.
+    /// Returns None in case segmented changelog doesn't know about either of those commit.
+    async fn range(
+        &self,
+        ctx: &CoreContext,
+        start: ChangesetId,
+        end: ChangesetId,
+        limit: usize,
+    ) -> Result<Option<Vec<ChangesetId>>>;
+
     /// Try update segmented changelog to given heads. No-op by default. Useful
     /// for tests. Returns: `true` if update was successful; `false` if the
     /// implementation doesn't support updates; an error otherwise.
     async fn build_up_to_heads(&self, _ctx: &CoreContext, _heads: &[ChangesetId]) -> Result<bool> {
         Ok(false)
     }
+
+    /// Bring the dag up to date before answering queries that request
+    /// `DagFreshness::MustBeFresh`, e.g. by checking for a newer saved
+    /// version or updating to the master bookmark. No-op by default, for
+    /// implementations that are always up to date.
+    async fn ensure_fresh(&self, _ctx: &CoreContext) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Error)]
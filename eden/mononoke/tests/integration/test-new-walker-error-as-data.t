@@ -36,7 +36,7 @@ Check we get an error due to the missing aliases
   $ mononoke_walker scrub -I deep -q -b master_bookmark 2>&1 | strip_glog
   Walking edge types * (glob)
   Walking node types * (glob)
-  Execution error: Could not step to OutgoingEdge { label: FileContentMetadataV2ToGitSha1Alias, target: AliasContentMapping(AliasKey(GitSha1(GitSha1(96d80cd6c4e7158dbebd0849f4fb7ce513e5828c)))), path: None } via Some(EmptyRoute) in repo repo
+  Execution error: Edge to OutgoingEdge { label: FileContentMetadataV2ToGitSha1Alias, target: AliasContentMapping(AliasKey(GitSha1(GitSha1(96d80cd6c4e7158dbebd0849f4fb7ce513e5828c)))), path: None } via Some(EmptyRoute) in repo repo points at a missing key
   
   Caused by:
       alias.gitsha1.96d80cd6c4e7158dbebd0849f4fb7ce513e5828c is missing
@@ -62,7 +62,7 @@ Check scuba data
 Check error-as-data-edge-type, should get an error on FileContentMetadataV2ToGitSha1Alias as have not converted its errors to data
   $ mononoke_walker -l loaded scrub -q --error-as-data-node-type AliasContentMapping --error-as-data-edge-type FileContentMetadataV2ToSha1Alias -I deep -b master_bookmark 2>&1 | strip_glog
   Error as data enabled, walk results may not be complete. Errors as data enabled for node types [AliasContentMapping] edge types [FileContentMetadataV2ToSha1Alias]
-  Execution error: Could not step to OutgoingEdge { label: FileContentMetadataV2ToGitSha1Alias, target: AliasContentMapping(AliasKey(GitSha1(GitSha1(96d80cd6c4e7158dbebd0849f4fb7ce513e5828c)))), path: None } via Some(EmptyRoute) in repo repo
+  Execution error: Edge to OutgoingEdge { label: FileContentMetadataV2ToGitSha1Alias, target: AliasContentMapping(AliasKey(GitSha1(GitSha1(96d80cd6c4e7158dbebd0849f4fb7ce513e5828c)))), path: None } via Some(EmptyRoute) in repo repo points at a missing key
   
   Caused by:
       alias.gitsha1.96d80cd6c4e7158dbebd0849f4fb7ce513e5828c is missing
@@ -7,6 +7,7 @@
 
 mod graph;
 mod list_ancestors;
+mod segmented_version;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -23,9 +24,11 @@ use mononoke_app::args::RepoArgs;
 use mononoke_app::MononokeApp;
 use repo_blobstore::RepoBlobstore;
 use repo_identity::RepoIdentity;
+use segmented_changelog::SegmentedChangelogManager;
 
 use self::graph::ChangelogGraphArgs;
 use self::list_ancestors::ChangelogListAncestorsArgs;
+use self::segmented_version::ChangelogSegmentedVersionArgs;
 
 /// Manipulate changelogs
 #[derive(Parser)]
@@ -65,6 +68,9 @@ pub struct Repo {
 
     #[facet]
     changeset_fetcher: dyn ChangesetFetcher,
+
+    #[facet]
+    segmented_changelog_manager: SegmentedChangelogManager,
 }
 
 #[derive(Subcommand)]
@@ -74,6 +80,9 @@ pub enum ChangelogSubcommand {
 
     /// List ancestors of a commit
     ListAncestors(ChangelogListAncestorsArgs),
+
+    /// Show or pin the served segmented changelog version
+    SegmentedVersion(ChangelogSegmentedVersionArgs),
 }
 
 pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
@@ -89,6 +98,9 @@ pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<()> {
         ChangelogSubcommand::ListAncestors(list_ancestors_args) => {
             list_ancestors::list_ancestors(&ctx, &repo, list_ancestors_args).await?
         }
+        ChangelogSubcommand::SegmentedVersion(segmented_version_args) => {
+            segmented_version::segmented_version(&ctx, &repo, segmented_version_args).await?
+        }
     }
 
     Ok(())
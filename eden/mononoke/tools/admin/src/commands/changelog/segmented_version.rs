@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::Result;
+use clap::Args;
+use clap::Subcommand;
+use context::CoreContext;
+use mononoke_types::hash::Blake2;
+use segmented_changelog::types::IdDagVersion;
+use segmented_changelog::types::IdMapVersion;
+use segmented_changelog::types::SegmentedChangelogVersion;
+use segmented_changelog::SegmentedChangelogManagerRef;
+
+use super::Repo;
+
+#[derive(Args)]
+pub struct ChangelogSegmentedVersionArgs {
+    #[clap(subcommand)]
+    subcommand: SegmentedVersionSubcommand,
+}
+
+#[derive(Subcommand)]
+pub enum SegmentedVersionSubcommand {
+    /// Show the served segmented changelog version and any pin
+    Show,
+
+    /// Pin the served segmented changelog version, so that servers stop
+    /// picking up new versions until it is unpinned
+    Pin(PinArgs),
+
+    /// Remove the pin, so that servers serve the latest version again
+    Unpin,
+}
+
+#[derive(Args)]
+pub struct PinArgs {
+    /// IdDag version to pin
+    #[clap(long)]
+    iddag_version: Blake2,
+
+    /// IdMap version to pin
+    #[clap(long)]
+    idmap_version: u64,
+}
+
+fn format_version(version: &SegmentedChangelogVersion) -> String {
+    format!(
+        "iddag {}, idmap {}",
+        version.iddag_version, version.idmap_version
+    )
+}
+
+pub async fn segmented_version(
+    ctx: &CoreContext,
+    repo: &Repo,
+    args: ChangelogSegmentedVersionArgs,
+) -> Result<()> {
+    let manager = repo.segmented_changelog_manager();
+    match args.subcommand {
+        SegmentedVersionSubcommand::Show => {
+            let latest = manager.latest_saved_version(ctx).await?;
+            println!("Latest saved version: {}", format_version(&latest));
+            match manager.version_pin(ctx).await? {
+                Some((pinned, pinned_at)) => println!(
+                    "Pinned version: {} (pinned {}s ago)",
+                    format_version(&pinned),
+                    pinned_at.since_seconds()
+                ),
+                None => println!("Not pinned, serving the latest saved version"),
+            }
+        }
+        SegmentedVersionSubcommand::Pin(pin_args) => {
+            let version = SegmentedChangelogVersion::new(
+                IdDagVersion(pin_args.iddag_version),
+                IdMapVersion(pin_args.idmap_version),
+            );
+            manager.pin_version(ctx, version).await?;
+            println!("Pinned version: {}", format_version(&version));
+        }
+        SegmentedVersionSubcommand::Unpin => {
+            if manager.unpin_version(ctx).await? {
+                println!("Unpinned, serving the latest saved version");
+            } else {
+                println!("Not pinned");
+            }
+        }
+    }
+    Ok(())
+}
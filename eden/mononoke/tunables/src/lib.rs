@@ -113,6 +113,8 @@ pub struct MononokeTunables {
     scs_other_methods_sampling_rate: TunableI64,
     // When false error logs are never sampled
     scs_error_log_sampling: TunableBool,
+    // Measure the size of one in this many responses for usage reporting
+    scs_usage_response_size_sampling_rate: TunableI64,
     redacted_logging_sampling_rate: TunableI64,
     repo_client_bookmarks_timeout_secs: TunableI64,
     repo_client_clone_timeout_secs: TunableI64,
@@ -127,6 +129,27 @@ pub struct MononokeTunables {
     disable_running_hooks_in_pushredirected_repo: TunableBool,
     scs_request_read_qps: TunableI64,
     scs_request_write_qps: TunableI64,
+    // SCS request limits. When unset the defaults from the thrift
+    // definition are used.
+    scs_repo_list_bookmarks_max_limit: TunableI64,
+    scs_repo_list_recent_commits_max_limit: TunableI64,
+    scs_repo_stack_info_max_limit: TunableI64,
+    scs_commit_compare_ordered_max_limit: TunableI64,
+    scs_commit_file_diffs_size_limit: TunableI64,
+    scs_commit_file_diffs_path_count_limit: TunableI64,
+    scs_commit_find_files_max_limit: TunableI64,
+    scs_commit_list_descendant_bookmarks_max_limit: TunableI64,
+    scs_commit_list_descendants_max_limit: TunableI64,
+    scs_tree_list_max_limit: TunableI64,
+    scs_tree_prefetch_max_limit: TunableI64,
+    scs_commit_export_archive_size_limit: TunableI64,
+    scs_commit_export_archive_file_count_limit: TunableI64,
+    scs_commit_landability_changed_paths_limit: TunableI64,
+    scs_path_max_length: TunableI64,
+    scs_identity_schemes_max_count: TunableI64,
+    scs_commit_path_blame_annotate_size_limit: TunableI64,
+    // Unbounded when unset
+    scs_history_max_limit: TunableI64,
     // All blobstore read request with size bigger than
     // this threshold will be logged to scuba
     blobstore_read_size_logging_threshold: TunableI64,
@@ -230,6 +253,11 @@ pub struct MononokeTunables {
     // How many commits to walk back from the client heads before failing to rebuild SC
     segmented_changelog_client_max_commits_to_traverse: TunableI64,
 
+    // For repos with segmented changelog shadow reads enabled, recompute 1 in N
+    // ancestry queries via the commit graph and log any mismatches. Disabled
+    // when unset or non-positive.
+    segmented_changelog_shadow_read_sampling_rate: TunableI64,
+
     // What timeout to use when doing filenode lookup.
     // Usually filenode lookup is used while generating hg changesets
     filenode_lookup_timeout_ms: TunableI64,
@@ -0,0 +1,18 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+CREATE TABLE IF NOT EXISTS walker_chunk_leases (
+  repo_id INTEGER NOT NULL,
+  lease_name VARCHAR(255) NOT NULL,
+  lower_bound BIGINT NOT NULL,
+  upper_bound BIGINT NOT NULL,
+  owner VARCHAR(255) NOT NULL,
+  acquire_timestamp BIGINT NOT NULL,
+  expire_timestamp BIGINT NOT NULL,
+  complete_timestamp BIGINT NULL,
+  UNIQUE (repo_id, lease_name, lower_bound, upper_bound)
+);
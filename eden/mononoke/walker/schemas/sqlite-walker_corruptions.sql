@@ -0,0 +1,22 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+CREATE TABLE IF NOT EXISTS walker_corruptions (
+  repo_id INTEGER NOT NULL,
+  node_type VARCHAR(255) NOT NULL,
+  node_key VARCHAR(512) NOT NULL,
+  check_type VARCHAR(255) NOT NULL,
+  edge_type VARCHAR(255) NOT NULL,
+  src_node_type VARCHAR(255) NULL,
+  src_node_key VARCHAR(512) NULL,
+  first_seen_timestamp BIGINT NOT NULL,
+  last_seen_timestamp BIGINT NOT NULL,
+  times_seen BIGINT NOT NULL,
+  status VARCHAR(32) NOT NULL,
+  resolved_timestamp BIGINT NULL,
+  UNIQUE (repo_id, node_type, node_key)
+);
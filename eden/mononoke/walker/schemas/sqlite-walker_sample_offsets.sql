@@ -0,0 +1,14 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+CREATE TABLE IF NOT EXISTS walker_sample_offsets (
+  repo_id INTEGER NOT NULL,
+  rotation_name VARCHAR(255) NOT NULL,
+  sample_offset BIGINT NOT NULL,
+  update_timestamp BIGINT NOT NULL,
+  UNIQUE (repo_id, rotation_name)
+);
@@ -7,6 +7,8 @@
 
 use std::collections::HashSet;
 
+use clap::Arg;
+use clap::ArgAction;
 use clap::ValueEnum;
 use once_cell::sync::Lazy;
 use strum::AsRefStr;
@@ -120,3 +122,14 @@ impl ChunkByPublicArg {
         args.iter().cloned().map(NodeType::from).collect()
     }
 }
+
+/// Lets a boolean flag also take an explicit value, so that `--flag=false`
+/// can turn off an option that `--walk-config` turns on. A bare `--flag`
+/// still means true. Applied with `mut_arg` on the args struct.
+pub fn bool_flag(arg: Arg) -> Arg {
+    arg.value_name("BOOL")
+        .default_value("false")
+        .default_missing_value("true")
+        .action(ArgAction::Set)
+        .num_args(0..=1)
+}
@@ -126,6 +126,7 @@ const DEEP_INCLUDE_EDGE_TYPES: &[EdgeType] = &[
     EdgeType::HgChangesetToHgParent,
     EdgeType::HgChangesetToHgManifest,
     EdgeType::HgChangesetToHgManifestFileNode,
+    EdgeType::HgChangesetToHgConvertRevision,
     EdgeType::HgChangesetViaBonsaiToHgChangeset,
     EdgeType::HgManifestToHgFileEnvelope,
     EdgeType::HgManifestToHgFileNode,
@@ -141,6 +142,7 @@ const DEEP_INCLUDE_EDGE_TYPES: &[EdgeType] = &[
     EdgeType::HgManifestFileNodeToHgCopyfromFileNode,
     // Content
     EdgeType::FileContentToFileContentMetadataV2,
+    EdgeType::FileContentToChunk,
     EdgeType::FileContentMetadataV2ToSha1Alias,
     EdgeType::FileContentMetadataV2ToSha256Alias,
     EdgeType::FileContentMetadataV2ToGitSha1Alias,
@@ -198,6 +200,7 @@ const SHALLOW_INCLUDE_EDGE_TYPES: &[EdgeType] = &[
     EdgeType::HgBonsaiMappingToChangeset,
     EdgeType::HgChangesetToHgManifest,
     EdgeType::HgChangesetToHgManifestFileNode,
+    EdgeType::HgChangesetToHgConvertRevision,
     EdgeType::HgChangesetViaBonsaiToHgChangeset,
     EdgeType::HgManifestToHgFileEnvelope,
     EdgeType::HgManifestToHgFileNode,
@@ -206,6 +209,7 @@ const SHALLOW_INCLUDE_EDGE_TYPES: &[EdgeType] = &[
     EdgeType::HgFileEnvelopeToFileContent,
     // Content
     EdgeType::FileContentToFileContentMetadataV2,
+    EdgeType::FileContentToChunk,
     EdgeType::FileContentMetadataV2ToSha1Alias,
     EdgeType::FileContentMetadataV2ToSha256Alias,
     EdgeType::FileContentMetadataV2ToGitSha1Alias,
@@ -9,49 +9,121 @@ pub mod arg_types;
 mod graph_arg_types;
 pub mod hash_validation;
 pub mod progress;
+pub mod record;
 pub mod sampling;
 pub mod scrub;
 pub mod tail_args;
 pub mod validate;
+pub mod walk_config;
 pub mod walk_params;
 pub mod walk_root;
 
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Error;
 use clap::Args;
 pub use graph_arg_types::NodeTypeArg;
 pub use hash_validation::HashValidationArgs;
 pub use progress::ProgressArgs;
+pub use record::RecordReplayArgs;
+pub use sampling::SampleModeArg;
 pub use sampling::SamplingArgs;
+pub use scrub::ScrubBlobAgeArgs;
 pub use scrub::ScrubOutputNodeArgs;
 pub use scrub::ScrubPackLogArgs;
+pub use scrub::ScrubTierArg;
 use strum::AsRefStr;
 use strum::EnumString;
 use strum::EnumVariantNames;
 pub use tail_args::CheckpointArgs;
 pub use tail_args::ChunkingArgs;
 pub use tail_args::TailArgs;
+pub use validate::LinknodeRepairArgs;
 pub use validate::ValidateCheckTypeArgs;
+pub use walk_config::apply_walk_config;
 pub use walk_params::WalkerGraphArgs;
 pub use walk_params::WalkerGraphParams;
 pub use walk_root::WalkRootArgs;
 
+use crate::args::arg_types::bool_flag;
+use crate::detail::graph::NodeType;
+
 #[derive(Args, Debug)]
+#[clap(
+    mut_arg("quiet", bool_flag),
+    mut_arg("enable_derive", bool_flag),
+    mut_arg("stop_at_public", bool_flag),
+    mut_arg("limit_data_fetch", bool_flag)
+)]
 pub struct WalkerCommonArgs {
+    /// JSON or TOML file describing the walk: roots, node and edge types,
+    /// sampling, throttling and output options. Flags given on the command
+    /// line override values from the file.
+    #[clap(long)]
+    pub walk_config: Option<PathBuf>,
     /// Log a lot less
     #[clap(long, short = 'q')]
     pub quiet: bool,
     /// Use redaction from config. Default is redaction off.
     #[clap(long)]
     pub enable_redaction: bool,
+    /// With redaction enabled, check that redacted blobs are still present
+    /// in the blobstore, reporting them as missing if not.
+    #[clap(long, requires = "enable_redaction")]
+    pub check_redacted_present: bool,
     /// Maximum number of walk step tasks to attempt to execute at once.
     #[clap(long, default_value = "4096")]
     pub scheduled_max: usize,
     /// Enable derivation of data (e.g. hg, file metadata).
     #[clap(long)]
     pub enable_derive: bool,
+    /// Don't step from a changeset to its public parents. Walks rooted at
+    /// draft commits (e.g. scratch bookmarks) then only cover draft history.
+    /// Roots are walked even if they are public.
+    #[clap(long)]
+    pub stop_at_public: bool,
+    /// Don't step from a changeset to parents more than this many
+    /// generations below the lowest root, e.g. to only walk recent history
+    /// of a bookmark.
+    #[clap(long)]
+    pub max_ancestor_depth: Option<u64>,
+    /// File of node keys to skip loading because they are known to be bad,
+    /// one per line. Lines starting with `regex:` match keys by regex.
+    /// Skipped nodes are counted separately rather than as errors.
+    #[clap(long)]
+    pub skip_keys_file: Option<PathBuf>,
+    /// Number of times to retry a step that failed with a transient error,
+    /// e.g. a blobstore timeout, before recording it as a failure.
+    #[clap(long, default_value_t = 3)]
+    pub step_retries: u32,
+    /// Delay before the first retry of a step in milliseconds. The delay
+    /// doubles on each further retry.
+    #[clap(long, default_value_t = 100)]
+    pub step_retry_base_delay_ms: u64,
+    /// Maximum delay between retries of a step in milliseconds.
+    #[clap(long, default_value_t = 5000)]
+    pub step_retry_max_delay_ms: u64,
+    /// Stop expanding the walk once this many nodes have been walked across
+    /// all repos. Steps already scheduled still complete.
+    #[clap(long)]
+    pub max_nodes: Option<u64>,
+    /// Stop expanding the walk once walk steps have loaded this many bytes
+    /// across all repos. Steps already scheduled still complete.
+    #[clap(long)]
+    pub max_bytes: Option<u64>,
     /// Limit the amount of data fetched from stores, by not streaming
     /// large files to the end. Only used by `scrub` subcommand.
     #[clap(long)]
     pub limit_data_fetch: bool,
+    /// Derive and store file content metadata found missing during the walk,
+    /// rather than only reporting it. Requires writable storage.
+    #[clap(long)]
+    pub backfill_content_metadata: bool,
+    /// Most file content metadata blobs to backfill across all repos in the
+    /// job. Metadata still missing over the cap is reported as missing.
+    #[clap(long, requires = "backfill_content_metadata")]
+    pub backfill_content_metadata_max: Option<u64>,
 
     /// Id of a storage group to operate over, e.g. manifold_xdb_multiplex
     #[clap(long)]
@@ -74,6 +146,83 @@ pub struct WalkerCommonArgs {
     pub progress: ProgressArgs,
     #[clap(flatten, next_help_heading = "TAILING OPTIONS")]
     pub tailing: TailArgs,
+    #[clap(flatten, next_help_heading = "RECORD AND REPLAY OPTIONS")]
+    pub record_replay: RecordReplayArgs,
+}
+
+impl WalkerCommonArgs {
+    /// Check for flags that don't apply to the subcommand or to the rest of
+    /// the walk.  This runs after any walk config is applied, so covers
+    /// options set there too, which clap can't check.
+    pub fn validate(&self, subcommand: WalkerSubcommand) -> Result<(), Error> {
+        if self.progress.progress_sample_rate == 0 {
+            bail!("--progress-sample-rate must be at least 1");
+        }
+        if self.limit_data_fetch && subcommand != WalkerSubcommand::Scrub {
+            bail!(
+                "--limit-data-fetch only applies to the scrub subcommand, not {}",
+                subcommand.as_ref()
+            );
+        }
+        if self
+            .tailing
+            .sample_rotation
+            .sample_offset_rotation_name
+            .is_some()
+            && !subcommand.samples()
+        {
+            bail!(
+                "--sample-offset-rotation-name only applies to subcommands that sample (scrub, corpus and compression-benefit), not {}",
+                subcommand.as_ref()
+            );
+        }
+        if self.tailing.corruption_log.record_corruptions && subcommand != WalkerSubcommand::Scrub {
+            bail!(
+                "--record-corruptions only applies to the scrub subcommand, not {}",
+                subcommand.as_ref()
+            );
+        }
+        if self.backfill_content_metadata {
+            if self.record_replay.replay_dir.is_some() {
+                bail!("--backfill-content-metadata can't write to a replayed recording");
+            }
+            if subcommand != WalkerSubcommand::Scrub {
+                bail!(
+                    "--backfill-content-metadata only applies to the scrub subcommand, not {}",
+                    subcommand.as_ref()
+                );
+            }
+            let node_types = self.graph_params.parse_args()?.include_node_types;
+            if !node_types.contains(&NodeType::FileContentMetadataV2) {
+                bail!(
+                    "--backfill-content-metadata needs FileContentMetadataV2 nodes in the walk, include them with --include-node-type FileContentMetadataV2"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The walker subcommands, for checking that the flags given apply to the
+/// one being run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AsRefStr)]
+#[strum(serialize_all = "kebab-case")]
+pub enum WalkerSubcommand {
+    CompressionBenefit,
+    Corpus,
+    NodeLookup,
+    Scrub,
+    Validate,
+}
+
+impl WalkerSubcommand {
+    /// Whether the subcommand samples the nodes it walks.
+    pub fn samples(&self) -> bool {
+        match self {
+            Self::CompressionBenefit | Self::Corpus | Self::Scrub => true,
+            Self::NodeLookup | Self::Validate => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, AsRefStr, EnumVariantNames, EnumString)]
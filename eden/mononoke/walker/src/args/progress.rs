@@ -5,13 +5,21 @@
  * GNU General Public License version 2.
  */
 
+use std::path::PathBuf;
 use std::time::Duration;
 
 use clap::Args;
 
+use crate::args::arg_types::bool_flag;
+use crate::detail::heartbeat::HeartbeatOptions;
 use crate::detail::progress::ProgressOptions;
 
 #[derive(Args, Debug)]
+#[clap(
+    mut_arg("report_edge_stats", bool_flag),
+    mut_arg("report_content_dedup", bool_flag),
+    mut_arg("report_queue_stats", bool_flag)
+)]
 pub struct ProgressArgs {
     /// Minimum interval between progress reports in seconds.
     #[clap(long, default_value_t = 5)]
@@ -20,6 +28,35 @@ pub struct ProgressArgs {
     /// Only log if progress-interval has passed.
     #[clap(long, default_value_t = 100)]
     pub progress_sample_rate: u64,
+    /// At the end of each walk, log the load latency and bytes read for
+    /// each edge type.
+    #[clap(long)]
+    pub report_edge_stats: bool,
+    /// At the end of each walk, log the number of FileContent nodes walked
+    /// against the distinct content sha256s seen.  The sha256s come from
+    /// FileContentMetadataV2 nodes, so those must be walked too.
+    #[clap(long)]
+    pub report_content_dedup: bool,
+    /// Most distinct sha256s to hold in memory for --report-content-dedup.
+    /// Beyond this the distinct count is a lower bound.
+    #[clap(long, default_value_t = 1_000_000)]
+    pub content_dedup_max_distinct: usize,
+    /// With each progress report, log the number of steps queued and not
+    /// yet finished, and how often the visited set found a node already
+    /// visited, overall and by type.
+    #[clap(long)]
+    pub report_queue_stats: bool,
+    /// Periodically write a JSON heartbeat record with the walk's progress
+    /// counters and a sequence number to this file.
+    #[clap(long)]
+    pub heartbeat_file: Option<PathBuf>,
+    /// Interval between heartbeat records in seconds.
+    #[clap(long, default_value_t = 30)]
+    pub heartbeat_interval: u64,
+    /// Exit the process if a running walk makes no progress for this many
+    /// seconds. Time between tailing runs does not count.
+    #[clap(long)]
+    pub max_stall_seconds: Option<u64>,
 }
 
 impl ProgressArgs {
@@ -27,6 +64,23 @@ impl ProgressArgs {
         ProgressOptions {
             sample_rate: self.progress_sample_rate,
             interval: Duration::from_secs(self.progress_interval),
+            report_edge_stats: self.report_edge_stats,
+            report_content_dedup: self.report_content_dedup,
+            content_dedup_max_distinct: self.content_dedup_max_distinct,
+            report_queue_stats: self.report_queue_stats,
         }
     }
+
+    /// Heartbeats are only reported if a heartbeat file or stall limit is
+    /// set.
+    pub fn parse_heartbeat_args(&self) -> Option<HeartbeatOptions> {
+        if self.heartbeat_file.is_none() && self.max_stall_seconds.is_none() {
+            return None;
+        }
+        Some(HeartbeatOptions {
+            file: self.heartbeat_file.clone(),
+            interval: Duration::from_secs(self.heartbeat_interval),
+            max_stall: self.max_stall_seconds.map(Duration::from_secs),
+        })
+    }
 }
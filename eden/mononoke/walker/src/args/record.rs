@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::args::graph_arg_types::NodeTypeArg;
+use crate::detail::record::RecordFilter;
+use crate::detail::record::RecordOptions;
+
+#[derive(Args, Debug)]
+pub struct RecordReplayArgs {
+    /// Record the blobs loaded by the walk to this directory, so the walk
+    /// can be replayed later with --replay-dir.
+    #[clap(long, conflicts_with = "replay_dir")]
+    pub record_dir: Option<PathBuf>,
+    /// Only record the blobs of these node types. Blobs not belonging to a
+    /// single node type, e.g. derived data mappings, are always recorded.
+    /// Replays should then only include these node types.
+    #[clap(long, requires = "record_dir")]
+    pub record_node_type: Vec<NodeTypeArg>,
+    /// Read blobs from a directory recorded with --record-dir rather than
+    /// from the repo's blobstores. Metadata is not recorded, so the storage
+    /// config must use a local metadata database, see --storage-id.
+    #[clap(long, conflicts_with = "inner_blobstore_id")]
+    pub replay_dir: Option<PathBuf>,
+}
+
+impl RecordReplayArgs {
+    pub fn parse_record_args(&self) -> Option<RecordOptions> {
+        let node_types = if self.record_node_type.is_empty() {
+            None
+        } else {
+            Some(NodeTypeArg::parse_args(&self.record_node_type))
+        };
+        self.record_dir.as_ref().map(|dir| RecordOptions {
+            dir: dir.clone(),
+            filter: RecordFilter { node_types },
+        })
+    }
+}
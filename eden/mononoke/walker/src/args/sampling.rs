@@ -5,13 +5,29 @@
  * GNU General Public License version 2.
  */
 
+use anyhow::bail;
 use anyhow::Error;
 use clap::Args;
+use clap::ValueEnum;
 use regex::Regex;
 
 use crate::args::graph_arg_types::NodeTypeArg;
+use crate::args::WalkerCommonArgs;
+use crate::detail::progress::sort_by_string;
+use crate::detail::sampling::is_size_filtered;
+use crate::detail::sampling::SampleMode;
 use crate::detail::sampling::SamplingOptions;
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SampleModeArg {
+    /// Pick nodes by their sampling fingerprint, so the same nodes are
+    /// sampled on every run.
+    Fingerprint,
+    /// Pick each node independently at random with probability
+    /// 1/sample-rate, seeded by --sample-seed.
+    Random,
+}
+
 #[derive(Args, Debug)]
 pub struct SamplingArgs {
     /// Pass 1 to try all nodes, 120 to do 1 in 120, etc.
@@ -21,6 +37,13 @@ pub struct SamplingArgs {
     /// to cycle through an entire repo in N pieces.
     #[clap(long, default_value = "0")]
     pub sample_offset: u64,
+    /// How to pick the nodes to sample.
+    #[clap(long, value_enum, default_value_t = SampleModeArg::Fingerprint)]
+    pub sample_mode: SampleModeArg,
+    /// Seed for --sample-mode=random.  Walks with the same seed make the same
+    /// sampling decisions, provided they visit nodes in the same order.
+    #[clap(long, default_value = "0")]
+    pub sample_seed: u64,
     /// Node types to exclude from the sample.
     #[clap(long, short = 'S')]
     pub exclude_sample_node_type: Vec<NodeTypeArg>,
@@ -30,6 +53,10 @@ pub struct SamplingArgs {
     /// If provided, only sample paths that match.
     #[clap(long)]
     pub sample_path_regex: Option<Regex>,
+    /// Only sample file contents and manifests at least this many bytes in
+    /// size.  Smaller ones are still walked, but not included in the sample.
+    #[clap(long)]
+    pub sample_min_size_bytes: Option<u64>,
 }
 
 impl SamplingArgs {
@@ -40,11 +67,55 @@ impl SamplingArgs {
             &self.exclude_sample_node_type,
         );
         let exclude_types = NodeTypeArg::parse_args(&self.exclude_sample_node_type);
+        let sample_mode = match self.sample_mode {
+            SampleModeArg::Fingerprint => SampleMode::Fingerprint,
+            SampleModeArg::Random => SampleMode::Random {
+                seed: self.sample_seed,
+            },
+        };
         Ok(SamplingOptions {
             sample_rate,
             sample_offset: self.sample_offset,
+            sample_mode,
             node_types,
             exclude_types,
+            min_size_bytes: self.sample_min_size_bytes,
         })
     }
+    /// Check for sampling flags that conflict with each other, or that
+    /// can't select anything given the node types sampled.
+    pub fn validate(&self, common_args: &WalkerCommonArgs) -> Result<(), Error> {
+        match self.sample_mode {
+            SampleModeArg::Fingerprint if self.sample_seed != 0 => {
+                bail!("--sample-seed only applies with --sample-mode random")
+            }
+            SampleModeArg::Random if self.sample_offset != 0 => {
+                bail!("--sample-offset only applies with --sample-mode fingerprint")
+            }
+            _ => {}
+        }
+
+        let walk_node_types = common_args.graph_params.parse_args()?.include_node_types;
+        let mut options = self.parse_args(1)?;
+        options.retain_or_default(&walk_node_types);
+        let sampled = options.node_types;
+        if sampled.is_empty() {
+            bail!(
+                "None of the node types to sample are walked, include them in the walk with --include-node-type"
+            );
+        }
+        if self.sample_path_regex.is_some() && !sampled.iter().any(|t| t.allow_repo_path()) {
+            bail!(
+                "--sample-path-regex can't match any of the sampled node types {:?}, as none of them have paths. Sample node types with paths, e.g. FileContent or HgManifest",
+                sort_by_string(&sampled)
+            );
+        }
+        if self.sample_min_size_bytes.is_some() && !sampled.iter().any(|t| is_size_filtered(*t)) {
+            bail!(
+                "--sample-min-size-bytes only applies to file contents and manifests, but the sampled node types are {:?}",
+                sort_by_string(&sampled)
+            );
+        }
+        Ok(())
+    }
 }
@@ -6,15 +6,41 @@
  */
 
 use std::collections::HashSet;
+use std::path::PathBuf;
 
+use anyhow::bail;
 use anyhow::Error;
 use clap::Args;
+use clap::ValueEnum;
 use fbinit::FacebookInit;
+use scribe_ext::Scribe;
 use scuba_ext::MononokeScubaSampleBuilder;
 
 use crate::args::graph_arg_types::NodeTypeArg;
+use crate::detail::blob_age::BlobAgeFilter;
+use crate::detail::blob_age::DAY_SECS;
 use crate::detail::graph::NodeType;
+use crate::detail::pack::PackInfoLogDest;
 use crate::detail::pack::PackInfoLogOptions;
+use crate::detail::scrub::ScrubTier;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ScrubTierArg {
+    /// Scrub everything in the walk, including file content.
+    Deep,
+    /// Only check hg manifests, file envelopes and filenodes parse, never
+    /// loading file content.
+    Sparse,
+}
+
+impl From<ScrubTierArg> for ScrubTier {
+    fn from(arg: ScrubTierArg) -> ScrubTier {
+        match arg {
+            ScrubTierArg::Deep => ScrubTier::Deep,
+            ScrubTierArg::Sparse => ScrubTier::Sparse,
+        }
+    }
+}
 
 #[derive(Args, Debug)]
 pub struct ScrubOutputNodeArgs {
@@ -49,6 +75,18 @@ pub struct ScrubPackLogArgs {
     /// A log file to write Scuba pack info logs to (primarily useful in testing)
     #[clap(long, requires = "include_pack_log_node_type")]
     pub pack_log_scuba_file: Option<String>,
+    /// Scribe category to stream pack info records to as JSON, instead of
+    /// logging them to scuba
+    #[clap(
+        long,
+        requires = "include_pack_log_node_type",
+        conflicts_with_all = ["pack_log_scuba_table", "pack_log_scuba_file"]
+    )]
+    pub pack_log_scribe_category: Option<String>,
+    /// A directory to write scribe pack info records to instead of scribe
+    /// (primarily useful in testing)
+    #[clap(long, requires = "pack_log_scribe_category")]
+    pub pack_log_scribe_dir: Option<PathBuf>,
 }
 
 impl ScrubPackLogArgs {
@@ -59,18 +97,63 @@ impl ScrubPackLogArgs {
         );
 
         if !log_node_types.is_empty() {
-            let mut scuba_builder =
-                MononokeScubaSampleBuilder::with_opt_table(fb, self.pack_log_scuba_table.clone())?;
-            if let Some(scuba_log_file) = &self.pack_log_scuba_file {
-                scuba_builder = scuba_builder.with_log_file(scuba_log_file)?;
-            }
+            let log_dest = if let Some(category) = &self.pack_log_scribe_category {
+                let scribe = match &self.pack_log_scribe_dir {
+                    Some(dir) => Scribe::new_to_file(dir.clone()),
+                    None => Scribe::new(fb),
+                };
+                PackInfoLogDest::Scribe {
+                    scribe,
+                    category: category.clone(),
+                }
+            } else {
+                let mut scuba_builder = MononokeScubaSampleBuilder::with_opt_table(
+                    fb,
+                    self.pack_log_scuba_table.clone(),
+                )?;
+                if let Some(scuba_log_file) = &self.pack_log_scuba_file {
+                    scuba_builder = scuba_builder.with_log_file(scuba_log_file)?;
+                }
+                PackInfoLogDest::Scuba(scuba_builder)
+            };
 
             return Ok(Some(PackInfoLogOptions {
                 log_node_types,
-                log_dest: scuba_builder,
+                log_dest,
             }));
         }
 
         Ok(None)
     }
 }
+
+#[derive(Args, Debug)]
+pub struct ScrubBlobAgeArgs {
+    /// Only count blobs created at least this many days ago.  Blobs from
+    /// stores that don't record creation times are always counted.
+    #[clap(long)]
+    pub min_blob_age: Option<u64>,
+    /// Only count blobs created at most this many days ago.  Blobs from
+    /// stores that don't record creation times are always counted.
+    #[clap(long)]
+    pub max_blob_age: Option<u64>,
+}
+
+impl ScrubBlobAgeArgs {
+    pub fn parse_args(&self) -> Result<BlobAgeFilter, Error> {
+        if let (Some(min), Some(max)) = (self.min_blob_age, self.max_blob_age) {
+            if min > max {
+                bail!(
+                    "--min-blob-age {} is greater than --max-blob-age {}",
+                    min,
+                    max
+                );
+            }
+        }
+        let to_secs = |days: u64| days as i64 * DAY_SECS;
+        Ok(BlobAgeFilter {
+            min_age_secs: self.min_blob_age.map(to_secs),
+            max_age_secs: self.max_blob_age.map(to_secs),
+        })
+    }
+}
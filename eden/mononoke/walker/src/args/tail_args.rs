@@ -11,6 +11,7 @@ use anyhow::Error;
 use bulkops::Direction;
 use clap::Args;
 use fbinit::FacebookInit;
+use hostname::get_hostname;
 use metaconfig_types::MetadataDatabaseConfig;
 use sql_construct::SqlConstruct;
 use sql_construct::SqlConstructFromMetadataDatabaseConfig;
@@ -22,6 +23,12 @@ use crate::args::arg_types::DEFAULT_INTERNED_TYPES_STR;
 use crate::args::graph_arg_types::NodeTypeArg;
 use crate::detail::checkpoint::CheckpointsByName;
 use crate::detail::checkpoint::SqlCheckpoints;
+use crate::detail::corruption_log::CorruptionLog;
+use crate::detail::corruption_log::SqlCorruptions;
+use crate::detail::lease::ChunkLeases;
+use crate::detail::lease::SqlChunkLeases;
+use crate::detail::sample_rotation::SampleOffsetRotation;
+use crate::detail::sample_rotation::SqlSampleOffsets;
 use crate::detail::tail::ChunkingParams;
 use crate::detail::tail::ClearStateParams;
 use crate::detail::tail::TailParams;
@@ -39,6 +46,12 @@ pub struct TailArgs {
 
     #[clap(flatten)]
     pub chunking: ChunkingArgs,
+
+    #[clap(flatten)]
+    pub sample_rotation: SampleRotationArgs,
+
+    #[clap(flatten)]
+    pub corruption_log: CorruptionLogArgs,
 }
 
 impl TailArgs {
@@ -52,6 +65,12 @@ impl TailArgs {
             tail_secs: self.tail_interval.clone(),
             chunking: self.chunking.parse_args(fb, dbconfig, mysql_options)?,
             state_max_age: Duration::from_secs(self.state_max_age),
+            sample_rotation: self
+                .sample_rotation
+                .parse_args(fb, dbconfig, mysql_options)?,
+            corruption_log: self
+                .corruption_log
+                .parse_args(fb, dbconfig, mysql_options)?,
         })
     }
 }
@@ -101,6 +120,9 @@ pub struct ChunkingArgs {
 
     #[clap(flatten)]
     pub checkpoint: CheckpointArgs,
+
+    #[clap(flatten)]
+    pub lease: LeaseArgs,
 }
 
 impl ChunkingArgs {
@@ -146,6 +168,7 @@ impl ChunkingArgs {
             direction,
             clear_state,
             checkpoints: self.checkpoint.parse_args(fb, dbconfig, mysql_options)?,
+            leases: self.lease.parse_args(fb, dbconfig, mysql_options)?,
             allow_remaining_deferred: self.allow_remaining_deferred,
             repo_lower_bound_override: self.repo_lower_bound,
             repo_upper_bound_override: self.repo_upper_bound,
@@ -190,3 +213,150 @@ impl CheckpointArgs {
         }
     }
 }
+
+#[derive(Args, Debug)]
+pub struct LeaseArgs {
+    /// Name of the chunk leases to share the walk with other walkers.
+    /// Walkers with the same lease name and chunking options lease chunks
+    /// from each other, so that each chunk is walked by one of them.
+    #[clap(long, conflicts_with = "checkpoint_name")]
+    pub lease_name: Option<String>,
+    /// Path for sqlite lease db if using sqlite
+    #[clap(long, requires = "lease_name")]
+    pub lease_path: Option<String>,
+    /// How long a chunk lease lasts without being renewed, in seconds.
+    /// Chunks leased by walkers that stop renewing are taken over by others.
+    /// Leases are renewed every third of this, so it must be at least 1.
+    #[clap(long, default_value = "300", value_parser = clap::value_parser!(u64).range(1..))]
+    pub lease_duration: u64,
+    /// How long a completed chunk is skipped by other walkers before it can
+    /// be walked again, in seconds. Default is set to 5 days.
+    #[clap(long, default_value = "432000")]
+    pub lease_rewalk_interval: u64,
+}
+
+impl LeaseArgs {
+    pub fn parse_args(
+        &self,
+        fb: FacebookInit,
+        dbconfig: &MetadataDatabaseConfig,
+        mysql_options: &MysqlOptions,
+    ) -> Result<Option<ChunkLeases>, Error> {
+        if let Some(lease_name) = &self.lease_name {
+            let sql_leases = if let Some(lease_path) = &self.lease_path {
+                SqlChunkLeases::with_sqlite_path(lease_path, false)?
+            } else {
+                SqlChunkLeases::with_metadata_database_config(fb, dbconfig, mysql_options, false)?
+            };
+            let owner = format!("{}:{}", get_hostname()?, std::process::id());
+
+            Ok(Some(ChunkLeases::new(
+                lease_name.clone(),
+                owner,
+                Duration::from_secs(self.lease_duration),
+                Duration::from_secs(self.lease_rewalk_interval),
+                sql_leases,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SampleRotationArgs {
+    /// For sampling walks, name under which to record the sample offset
+    /// walked for each repo.  Each run walks the offset after the one last
+    /// recorded, overriding --sample-offset, so that successive runs cycle
+    /// through the repo in sample-rate pieces.
+    #[clap(long, conflicts_with = "tail_interval")]
+    pub sample_offset_rotation_name: Option<String>,
+    /// Path for sqlite sample offset rotation db if using sqlite
+    #[clap(long, requires = "sample_offset_rotation_name")]
+    pub sample_offset_rotation_path: Option<String>,
+}
+
+impl SampleRotationArgs {
+    pub fn parse_args(
+        &self,
+        fb: FacebookInit,
+        dbconfig: &MetadataDatabaseConfig,
+        mysql_options: &MysqlOptions,
+    ) -> Result<Option<SampleOffsetRotation>, Error> {
+        if let Some(rotation_name) = &self.sample_offset_rotation_name {
+            let sql_sample_offsets = if let Some(path) = &self.sample_offset_rotation_path {
+                SqlSampleOffsets::with_sqlite_path(path, false)?
+            } else {
+                SqlSampleOffsets::with_metadata_database_config(fb, dbconfig, mysql_options, false)?
+            };
+
+            Ok(Some(SampleOffsetRotation::new(
+                rotation_name.clone(),
+                sql_sample_offsets,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct CorruptionLogArgs {
+    /// Record nodes found missing or failing hash validation in the
+    /// walker_corruptions table, and mark them resolved when a later walk
+    /// finds them intact.
+    #[clap(long)]
+    pub record_corruptions: bool,
+    /// Path for sqlite corruptions db if using sqlite
+    #[clap(long, requires = "record_corruptions")]
+    pub corruption_db_path: Option<String>,
+}
+
+impl CorruptionLogArgs {
+    pub fn parse_args(
+        &self,
+        fb: FacebookInit,
+        dbconfig: &MetadataDatabaseConfig,
+        mysql_options: &MysqlOptions,
+    ) -> Result<Option<CorruptionLog>, Error> {
+        if self.record_corruptions {
+            let sql_corruptions = if let Some(path) = &self.corruption_db_path {
+                SqlCorruptions::with_sqlite_path(path, false)?
+            } else {
+                SqlCorruptions::with_metadata_database_config(fb, dbconfig, mysql_options, false)?
+            };
+
+            Ok(Some(CorruptionLog::new(sql_corruptions)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::args::WalkerCommonArgs;
+
+    #[derive(Parser)]
+    struct TestArgs {
+        #[clap(flatten)]
+        common: WalkerCommonArgs,
+    }
+
+    fn parse_lease_duration(args: &[&str]) -> Result<u64, clap::Error> {
+        let args = TestArgs::try_parse_from(std::iter::once("walker").chain(args.iter().copied()))?;
+        Ok(args.common.tailing.chunking.lease.lease_duration)
+    }
+
+    #[test]
+    fn test_lease_duration() -> Result<(), Error> {
+        assert_eq!(parse_lease_duration(&[])?, 300);
+        assert_eq!(parse_lease_duration(&["--lease-duration", "1"])?, 1);
+        // A zero duration would renew leases in a busy loop
+        assert!(parse_lease_duration(&["--lease-duration", "0"]).is_err());
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@ use strum::AsRefStr;
 use strum::EnumString;
 use strum::EnumVariantNames;
 
+use crate::detail::linknode_repair::LinknodeRepairOptions;
 use crate::detail::validate::CheckType;
 use crate::detail::validate::DEFAULT_CHECK_TYPES;
 
@@ -46,6 +47,7 @@ pub enum CheckTypeArg {
     ChangesetPhaseIsPublic,
     HgLinkNodePopulated,
     FileContentIsLfs,
+    HgConvertRevisionMapped,
 }
 
 fn parse_check_type_args(check_type_args: &[CheckTypeArg]) -> HashSet<CheckType> {
@@ -66,7 +68,44 @@ fn parse_check_type_args(check_type_args: &[CheckTypeArg]) -> HashSet<CheckType>
             CheckTypeArg::FileContentIsLfs => {
                 check_types.insert(CheckType::FileContentIsLfs);
             }
+            CheckTypeArg::HgConvertRevisionMapped => {
+                check_types.insert(CheckType::HgConvertRevisionMapped);
+            }
         }
     }
     check_types
 }
+
+#[derive(Args, Debug)]
+pub struct LinknodeRepairArgs {
+    /// Rewrite the linknodes of hg filenodes that point at a changeset that
+    /// doesn't contain the filenode, to the changeset that introduced it.
+    #[clap(long)]
+    pub repair_linknodes: bool,
+    /// Only log the linknode repairs that would be made.
+    #[clap(long, requires = "repair_linknodes")]
+    pub repair_linknodes_dry_run: bool,
+    /// Number of repaired filenodes to write at once.
+    #[clap(long, default_value = "1000")]
+    pub repair_linknodes_batch_size: usize,
+    /// Maximum number of changesets to step back through looking for the
+    /// changeset that introduced a filenode.
+    #[clap(long, default_value = "10000")]
+    pub repair_linknodes_max_search_depth: u64,
+    /// Maximum number of linknodes to check.  Each check looks the filenode
+    /// up in the manifest of its linknode, so this bounds the extra load of
+    /// the repair.  Filenodes found after it are skipped.
+    #[clap(long, default_value = "1000000")]
+    pub repair_linknodes_max_checks: u64,
+}
+
+impl LinknodeRepairArgs {
+    pub fn parse_args(&self) -> Option<LinknodeRepairOptions> {
+        self.repair_linknodes.then_some(LinknodeRepairOptions {
+            dry_run: self.repair_linknodes_dry_run,
+            batch_size: self.repair_linknodes_batch_size,
+            max_search_depth: self.repair_linknodes_max_search_depth,
+            max_checks: self.repair_linknodes_max_checks,
+        })
+    }
+}
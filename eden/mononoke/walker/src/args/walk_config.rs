@@ -0,0 +1,478 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::bail;
+use anyhow::format_err;
+use anyhow::Context;
+use anyhow::Error;
+use bookmarks::BookmarkKey;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use clap::ValueEnum;
+use mononoke_app::MononokeApp;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::args::OutputFormat;
+use crate::args::SampleModeArg;
+use crate::args::SamplingArgs;
+use crate::args::WalkerCommonArgs;
+
+/// A walk definition read from the file passed to `--walk-config`.
+///
+/// Every field is optional. Values given on the command line take precedence
+/// over values from the file, and anything set in neither place keeps its
+/// usual default. Boolean flags accept `=false` so the command line can turn
+/// off an option the file turns on. Node and edge types use the same names
+/// as the command line, including the pre-configured sets such as `deep` or
+/// `hg`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WalkConfig {
+    pub roots: RootsConfig,
+    pub graph: GraphConfig,
+    pub sampling: SamplingConfig,
+    pub throttle: ThrottleConfig,
+    pub output: OutputConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RootsConfig {
+    pub bookmarks: Vec<String>,
+    pub walk_roots: Vec<String>,
+    pub bookmark_log_hours: Option<u64>,
+    pub diff_base_roots: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphConfig {
+    pub include_node_types: Option<Vec<String>>,
+    pub exclude_node_types: Option<Vec<String>>,
+    pub include_edge_types: Option<Vec<String>>,
+    pub exclude_edge_types: Option<Vec<String>>,
+    pub error_as_data_node_types: Option<Vec<String>>,
+    pub error_as_data_edge_types: Option<Vec<String>>,
+    pub stop_at_public: Option<bool>,
+    pub max_ancestor_depth: Option<u64>,
+    pub enable_derive: Option<bool>,
+    pub skip_keys_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplingConfig {
+    pub sample_rate: Option<u64>,
+    pub sample_offset: Option<u64>,
+    pub sample_mode: Option<String>,
+    pub sample_seed: Option<u64>,
+    pub include_sample_node_types: Option<Vec<String>>,
+    pub exclude_sample_node_types: Option<Vec<String>>,
+    pub sample_path_regex: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThrottleConfig {
+    pub scheduled_max: Option<usize>,
+    pub blobstore_sampling_multiplier: Option<u64>,
+    pub limit_data_fetch: Option<bool>,
+    pub max_nodes: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub quiet: Option<bool>,
+    pub output_format: Option<String>,
+    pub progress_interval: Option<u64>,
+    pub progress_sample_rate: Option<u64>,
+    pub report_edge_stats: Option<bool>,
+    pub report_content_dedup: Option<bool>,
+    pub report_queue_stats: Option<bool>,
+}
+
+impl WalkConfig {
+    /// Load a walk config, choosing the format from the file extension.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read walk config {}", path.display()))?;
+        let config = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(Error::from),
+            Some("toml") => toml::from_str(&contents).map_err(Error::from),
+            _ => bail!(
+                "Walk config {} must have a .json or .toml extension",
+                path.display()
+            ),
+        };
+        config.with_context(|| format!("Failed to parse walk config {}", path.display()))
+    }
+
+    /// Fill in any arguments that were not given on the command line from
+    /// this config. `sampling` and `output_format` are only passed by
+    /// subcommands that have those options.
+    pub fn apply(
+        &self,
+        matches: &ArgMatches,
+        common: &mut WalkerCommonArgs,
+        sampling: Option<&mut SamplingArgs>,
+        output_format: Option<&mut OutputFormat>,
+    ) -> Result<(), Error> {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+        if unset("bookmark") && !self.roots.bookmarks.is_empty() {
+            common.walk_roots.bookmark = parse_all::<BookmarkKey>(&self.roots.bookmarks)?;
+        }
+        if unset("walk_root") && !self.roots.walk_roots.is_empty() {
+            common.walk_roots.walk_root = self.roots.walk_roots.clone();
+        }
+        if unset("bookmark_log_hours") && self.roots.bookmark_log_hours.is_some() {
+            common.walk_roots.bookmark_log_hours = self.roots.bookmark_log_hours;
+        }
+        if unset("diff_base_root") && !self.roots.diff_base_roots.is_empty() {
+            common.walk_roots.diff_base_root = self.roots.diff_base_roots.clone();
+        }
+
+        let graph = &self.graph;
+        let graph_args = &mut common.graph_params;
+        set_parsed(
+            unset("include_node_type"),
+            &graph.include_node_types,
+            &mut graph_args.include_node_type,
+        )?;
+        set_parsed(
+            unset("exclude_node_type"),
+            &graph.exclude_node_types,
+            &mut graph_args.exclude_node_type,
+        )?;
+        set_parsed(
+            unset("include_edge_type"),
+            &graph.include_edge_types,
+            &mut graph_args.include_edge_type,
+        )?;
+        set_parsed(
+            unset("exclude_edge_type"),
+            &graph.exclude_edge_types,
+            &mut graph_args.exclude_edge_type,
+        )?;
+        set_parsed(
+            unset("error_as_data_node_type"),
+            &graph.error_as_data_node_types,
+            &mut graph_args.error_as_data_node_type,
+        )?;
+        set_parsed(
+            unset("error_as_data_edge_type"),
+            &graph.error_as_data_edge_types,
+            &mut graph_args.error_as_data_edge_type,
+        )?;
+        set(
+            unset("stop_at_public"),
+            graph.stop_at_public,
+            &mut common.stop_at_public,
+        );
+        if unset("max_ancestor_depth") && graph.max_ancestor_depth.is_some() {
+            common.max_ancestor_depth = graph.max_ancestor_depth;
+        }
+        set(
+            unset("enable_derive"),
+            graph.enable_derive,
+            &mut common.enable_derive,
+        );
+        if unset("skip_keys_file") && graph.skip_keys_file.is_some() {
+            common.skip_keys_file = graph.skip_keys_file.clone();
+        }
+
+        let throttle = &self.throttle;
+        set(
+            unset("scheduled_max"),
+            throttle.scheduled_max,
+            &mut common.scheduled_max,
+        );
+        set(
+            unset("blobstore_sampling_multiplier"),
+            throttle.blobstore_sampling_multiplier,
+            &mut common.blobstore_sampling_multiplier,
+        );
+        set(
+            unset("limit_data_fetch"),
+            throttle.limit_data_fetch,
+            &mut common.limit_data_fetch,
+        );
+        if unset("max_nodes") && throttle.max_nodes.is_some() {
+            common.max_nodes = throttle.max_nodes;
+        }
+        if unset("max_bytes") && throttle.max_bytes.is_some() {
+            common.max_bytes = throttle.max_bytes;
+        }
+
+        let output = &self.output;
+        set(unset("quiet"), output.quiet, &mut common.quiet);
+        set(
+            unset("progress_interval"),
+            output.progress_interval,
+            &mut common.progress.progress_interval,
+        );
+        set(
+            unset("progress_sample_rate"),
+            output.progress_sample_rate,
+            &mut common.progress.progress_sample_rate,
+        );
+        set(
+            unset("report_edge_stats"),
+            output.report_edge_stats,
+            &mut common.progress.report_edge_stats,
+        );
+        set(
+            unset("report_content_dedup"),
+            output.report_content_dedup,
+            &mut common.progress.report_content_dedup,
+        );
+        set(
+            unset("report_queue_stats"),
+            output.report_queue_stats,
+            &mut common.progress.report_queue_stats,
+        );
+        match (output_format, &output.output_format) {
+            (Some(output_format), Some(format)) if unset("output_format") => {
+                *output_format = OutputFormat::from_str(format)
+                    .map_err(|_| format_err!("Invalid output_format {} in walk config", format))?;
+            }
+            (None, Some(_)) => bail!("output_format is not supported by this subcommand"),
+            _ => {}
+        }
+
+        let sampling_config = &self.sampling;
+        match sampling {
+            Some(sampling) => {
+                if unset("sample_rate") && sampling_config.sample_rate.is_some() {
+                    sampling.sample_rate = sampling_config.sample_rate;
+                }
+                set(
+                    unset("sample_offset"),
+                    sampling_config.sample_offset,
+                    &mut sampling.sample_offset,
+                );
+                if let Some(mode) = &sampling_config.sample_mode {
+                    if unset("sample_mode") {
+                        sampling.sample_mode =
+                            SampleModeArg::from_str(mode, true).map_err(|_| {
+                                format_err!("Invalid sample_mode {} in walk config", mode)
+                            })?;
+                    }
+                }
+                set(
+                    unset("sample_seed"),
+                    sampling_config.sample_seed,
+                    &mut sampling.sample_seed,
+                );
+                set_parsed(
+                    unset("include_sample_node_type"),
+                    &sampling_config.include_sample_node_types,
+                    &mut sampling.include_sample_node_type,
+                )?;
+                set_parsed(
+                    unset("exclude_sample_node_type"),
+                    &sampling_config.exclude_sample_node_types,
+                    &mut sampling.exclude_sample_node_type,
+                )?;
+                if let Some(regex) = &sampling_config.sample_path_regex {
+                    if unset("sample_path_regex") {
+                        sampling.sample_path_regex = Some(Regex::new(regex)?);
+                    }
+                }
+            }
+            None => {
+                if sampling_config.sample_rate.is_some()
+                    || sampling_config.sample_offset.is_some()
+                    || sampling_config.sample_mode.is_some()
+                    || sampling_config.sample_seed.is_some()
+                    || sampling_config.include_sample_node_types.is_some()
+                    || sampling_config.exclude_sample_node_types.is_some()
+                    || sampling_config.sample_path_regex.is_some()
+                {
+                    bail!("sampling options are not supported by this subcommand");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Load the walk config named by `--walk-config`, if any, and apply it to the
+/// subcommand's arguments.
+pub fn apply_walk_config(
+    app: &MononokeApp,
+    common: &mut WalkerCommonArgs,
+    sampling: Option<&mut SamplingArgs>,
+    output_format: Option<&mut OutputFormat>,
+) -> Result<(), Error> {
+    let config = match &common.walk_config {
+        Some(path) => WalkConfig::load(path)?,
+        None => return Ok(()),
+    };
+    let (_name, matches) = app
+        .matches()
+        .subcommand()
+        .ok_or_else(|| format_err!("Walk config requires a subcommand"))?;
+    config.apply(matches, common, sampling, output_format)
+}
+
+fn set<T>(unset: bool, value: Option<T>, target: &mut T) {
+    if let Some(value) = value {
+        if unset {
+            *target = value;
+        }
+    }
+}
+
+fn set_parsed<T>(
+    unset: bool,
+    values: &Option<Vec<String>>,
+    target: &mut Vec<T>,
+) -> Result<(), Error>
+where
+    T: FromStr<Err = Error>,
+{
+    if let Some(values) = values {
+        if unset {
+            *target = parse_all(values)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_all<T>(values: &[String]) -> Result<Vec<T>, Error>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    values
+        .iter()
+        .map(|value| T::from_str(value).map_err(Into::into))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use clap::FromArgMatches;
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestArgs {
+        #[clap(flatten)]
+        common: WalkerCommonArgs,
+        #[clap(flatten)]
+        sampling: SamplingArgs,
+    }
+
+    fn apply(config: &str, args: &[&str]) -> Result<TestArgs, Error> {
+        let config: WalkConfig = toml::from_str(config)?;
+        let matches = TestArgs::command()
+            .no_binary_name(true)
+            .try_get_matches_from(args)?;
+        let mut parsed = TestArgs::from_arg_matches(&matches)?;
+        config.apply(
+            &matches,
+            &mut parsed.common,
+            Some(&mut parsed.sampling),
+            None,
+        )?;
+        Ok(parsed)
+    }
+
+    const CONFIG: &str = r#"
+        [graph]
+        include_node_types = ["Bookmark", "Changeset"]
+        enable_derive = true
+
+        [throttle]
+        scheduled_max = 10
+
+        [sampling]
+        sample_rate = 7
+
+        [output]
+        quiet = false
+        report_edge_stats = true
+    "#;
+
+    #[test]
+    fn test_config_fills_unset_args() -> Result<(), Error> {
+        let args = apply(CONFIG, &[])?;
+        assert_eq!(args.common.graph_params.include_node_type.len(), 2);
+        assert!(args.common.enable_derive);
+        assert_eq!(args.common.scheduled_max, 10);
+        assert_eq!(args.sampling.sample_rate, Some(7));
+        assert!(!args.common.quiet);
+        assert!(args.common.progress.report_edge_stats);
+        // Not in the config, so keeps its default
+        assert!(!args.common.stop_at_public);
+        assert_eq!(args.common.blobstore_sampling_multiplier, 100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() -> Result<(), Error> {
+        let args = apply(
+            CONFIG,
+            &[
+                "--include-node-type",
+                "Bookmark",
+                "--scheduled-max",
+                "20",
+                "--sample-rate",
+                "1",
+                "--quiet",
+            ],
+        )?;
+        assert_eq!(args.common.graph_params.include_node_type.len(), 1);
+        assert_eq!(args.common.scheduled_max, 20);
+        assert_eq!(args.sampling.sample_rate, Some(1));
+        assert!(args.common.quiet);
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_line_turns_off_config_flags() -> Result<(), Error> {
+        let args = apply(
+            CONFIG,
+            &["--enable-derive=false", "--report-edge-stats=false"],
+        )?;
+        assert!(!args.common.enable_derive);
+        assert!(!args.common.progress.report_edge_stats);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() -> Result<(), Error> {
+        assert!(toml::from_str::<WalkConfig>("[graph]\nunknown = 1").is_err());
+        assert!(apply("[graph]\ninclude_node_types = [\"NotANodeType\"]", &[]).is_err());
+        assert!(apply("[sampling]\nsample_mode = \"sometimes\"", &[]).is_err());
+
+        // Sampling options are rejected by subcommands that don't sample
+        let config: WalkConfig = toml::from_str("[sampling]\nsample_rate = 1")?;
+        let matches = TestArgs::command()
+            .no_binary_name(true)
+            .try_get_matches_from(Vec::<&str>::new())?;
+        let mut parsed = TestArgs::from_arg_matches(&matches)?;
+        assert!(config
+            .apply(&matches, &mut parsed.common, None, None)
+            .is_err());
+        Ok(())
+    }
+}
@@ -5,6 +5,8 @@
  * GNU General Public License version 2.
  */
 
+use std::time::Duration;
+
 use anyhow::Error;
 use bookmarks::BookmarkKey;
 use clap::Args;
@@ -23,6 +25,28 @@ pub struct WalkRootArgs {
     /// Bookmark:master or HgChangeset:7712b62acdc858689504945ac8965a303ded6626
     #[clap(long, short = 'r')]
     pub walk_root: Vec<String>,
+    /// Walk the commits that publishing bookmarks were moved to by bookmark
+    /// update log entries from the last N hours, stopping at history that
+    /// was already reachable from them before then.
+    #[clap(long)]
+    pub bookmark_log_hours: Option<u64>,
+    /// Root(s) of the base of a differential walk, in the same format as
+    /// --walk-root, e.g. Changeset:<old master>.  Only nodes reachable from
+    /// the walk roots but not from these are walked.
+    #[clap(long)]
+    pub diff_base_root: Vec<String>,
+}
+
+fn parse_roots(roots: &[String]) -> Result<Vec<OutgoingEdge>, Error> {
+    let roots: Result<Vec<_>, Error> = roots.iter().map(|root| parse_node(root)).collect();
+    Ok(roots?
+        .into_iter()
+        .filter_map(|node| {
+            node.get_type()
+                .root_edge_type()
+                .map(|et| OutgoingEdge::new(et, node))
+        })
+        .collect())
 }
 
 impl WalkRootArgs {
@@ -36,18 +60,18 @@ impl WalkRootArgs {
             .collect();
         walk_roots.append(&mut bookmarks);
 
-        let roots: Result<Vec<_>, Error> =
-            self.walk_root.iter().map(|root| parse_node(root)).collect();
-        let mut roots = roots?
-            .into_iter()
-            .filter_map(|node| {
-                node.get_type()
-                    .root_edge_type()
-                    .map(|et| OutgoingEdge::new(et, node))
-            })
-            .collect();
+        let mut roots = parse_roots(&self.walk_root)?;
         walk_roots.append(&mut roots);
 
         Ok(walk_roots)
     }
+
+    pub fn parse_diff_base_args(&self) -> Result<Vec<OutgoingEdge>, Error> {
+        parse_roots(&self.diff_base_root)
+    }
+
+    pub fn parse_bookmark_log_window(&self) -> Option<Duration> {
+        self.bookmark_log_hours
+            .map(|hours| Duration::from_secs(hours * 60 * 60))
+    }
 }
@@ -0,0 +1,350 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::format_err;
+use anyhow::Error;
+use blobrepo::BlobRepo;
+use blobstore_factory::SqlTierInfo;
+use context::CoreContext;
+use futures::future;
+use futures::stream;
+use futures::stream::Stream;
+use futures::stream::StreamExt;
+use futures::stream::TryStreamExt;
+use repo_derived_data::RepoDerivedDataRef;
+use repo_identity::RepoIdentityRef;
+use scuba_ext::MononokeScubaSampleBuilder;
+use slog::Logger;
+use strum::IntoEnumIterator;
+
+use crate::commands::JobWalkParams;
+use crate::detail::budget::WalkBudget;
+use crate::detail::content_dedup::ContentDedupStats;
+use crate::detail::edge_stats::EdgeLoadStats;
+use crate::detail::graph::EdgeType;
+use crate::detail::graph::Node;
+use crate::detail::graph::NodeData;
+use crate::detail::graph::NodeType;
+use crate::detail::graph::SqlShardInfo;
+use crate::detail::heartbeat::Heartbeat;
+use crate::detail::metadata_backfill::MetadataBackfill;
+use crate::detail::progress::PendingSteps;
+use crate::detail::retry::StepRetryOptions;
+use crate::detail::skip_keys::KnownBadKeys;
+use crate::detail::state::WalkState;
+use crate::detail::walk::walk_exact;
+use crate::detail::walk::OutgoingEdge;
+use crate::detail::walk::RepoWalkParams;
+use crate::detail::walk::RepoWalkTypeParams;
+
+const EMBEDDED_WALK: &str = "embedded";
+
+/// Builds a walk of one repo for use from other binaries, without going
+/// through the walker's command line setup.
+///
+/// By default every node and edge type is walked, except derived data types
+/// not enabled for the repo.  Each node is visited at most once and the walk
+/// yields every visited node along with its data, if loaded.
+pub struct WalkBuilder {
+    repo: BlobRepo,
+    logger: Logger,
+    roots: Vec<Node>,
+    include_node_types: HashSet<NodeType>,
+    include_edge_types: HashSet<EdgeType>,
+    required_node_data_types: HashSet<NodeType>,
+    scheduled_max: usize,
+    enable_derive: bool,
+    stop_at_public: bool,
+    max_ancestor_depth: Option<u64>,
+    retry_options: StepRetryOptions,
+    sql_shard_info: SqlShardInfo,
+}
+
+impl WalkBuilder {
+    pub fn new(repo: BlobRepo, logger: Logger) -> Self {
+        Self {
+            repo,
+            logger,
+            roots: Vec::new(),
+            include_node_types: NodeType::iter().collect(),
+            include_edge_types: EdgeType::iter().collect(),
+            required_node_data_types: HashSet::new(),
+            scheduled_max: 4096,
+            enable_derive: false,
+            stop_at_public: false,
+            max_ancestor_depth: None,
+            retry_options: StepRetryOptions {
+                max_retries: 3,
+                base_delay: Duration::from_millis(100),
+                max_delay: Duration::from_millis(5000),
+            },
+            sql_shard_info: SqlShardInfo {
+                filenodes: SqlTierInfo {
+                    tier_name: String::new(),
+                    shard_num: None,
+                },
+                active_keys_per_shard: None,
+            },
+        }
+    }
+
+    /// Add a node to start the walk from.  The node's type must be usable as
+    /// a root, e.g. a bookmark or changeset.
+    pub fn with_root(mut self, root: Node) -> Self {
+        self.roots.push(root);
+        self
+    }
+
+    /// Only walk these node types.
+    pub fn with_node_types(mut self, node_types: impl IntoIterator<Item = NodeType>) -> Self {
+        self.include_node_types = node_types.into_iter().collect();
+        self
+    }
+
+    /// Only walk these edge types.
+    pub fn with_edge_types(mut self, edge_types: impl IntoIterator<Item = EdgeType>) -> Self {
+        self.include_edge_types = edge_types.into_iter().collect();
+        self
+    }
+
+    /// Always yield the data for these node types.  For other types the data
+    /// may be dropped once the node's outgoing edges are known.
+    pub fn with_required_node_data(
+        mut self,
+        node_types: impl IntoIterator<Item = NodeType>,
+    ) -> Self {
+        self.required_node_data_types = node_types.into_iter().collect();
+        self
+    }
+
+    /// Maximum number of walk steps to run at once.
+    pub fn with_scheduled_max(mut self, scheduled_max: usize) -> Self {
+        self.scheduled_max = scheduled_max;
+        self
+    }
+
+    /// Derive data that is missing rather than reporting it as missing.
+    pub fn with_enable_derive(mut self, enable_derive: bool) -> Self {
+        self.enable_derive = enable_derive;
+        self
+    }
+
+    /// Don't step from a changeset to its public parents.
+    pub fn with_stop_at_public(mut self, stop_at_public: bool) -> Self {
+        self.stop_at_public = stop_at_public;
+        self
+    }
+
+    /// Don't step from a changeset to parents more than this many
+    /// generations below the lowest root.
+    pub fn with_max_ancestor_depth(mut self, max_ancestor_depth: Option<u64>) -> Self {
+        self.max_ancestor_depth = max_ancestor_depth;
+        self
+    }
+
+    pub fn with_retry_options(mut self, retry_options: StepRetryOptions) -> Self {
+        self.retry_options = retry_options;
+        self
+    }
+
+    /// Limit the number of concurrent steps per sql shard.
+    pub fn with_sql_shard_info(mut self, sql_shard_info: SqlShardInfo) -> Self {
+        self.sql_shard_info = sql_shard_info;
+        self
+    }
+
+    /// Start the walk, yielding each visited node and its data.
+    pub fn walk(
+        self,
+        ctx: CoreContext,
+    ) -> impl Stream<Item = Result<(Node, Option<NodeData>), Error>> {
+        let repo_params = match self.repo_params() {
+            Ok(repo_params) => repo_params,
+            Err(e) => return stream::once(future::err(e)).left_stream(),
+        };
+        let job_params = JobWalkParams {
+            enable_derive: self.enable_derive,
+            stop_at_public: self.stop_at_public,
+            max_ancestor_depth: self.max_ancestor_depth,
+            skip_keys: Arc::new(KnownBadKeys::default()),
+            check_redacted_present: false,
+            quiet: true,
+            error_as_data_node_types: HashSet::new(),
+            error_as_data_edge_types: HashSet::new(),
+            repo_count: 1,
+            heartbeat: Heartbeat::default(),
+            budget: WalkBudget::default(),
+            metadata_backfill: MetadataBackfill::default(),
+            retry_options: self.retry_options,
+        };
+        let type_params = RepoWalkTypeParams {
+            required_node_data_types: self.required_node_data_types,
+            ..Default::default()
+        };
+        let visitor = Arc::new(WalkState::new(
+            repo_params.include_node_types.clone(),
+            repo_params.include_edge_types.clone(),
+            HashSet::new(),
+            self.enable_derive,
+            None,
+        ));
+
+        walk_exact(ctx, visitor, job_params, repo_params, type_params)
+            .map_ok(|(node, node_data, _stats)| (node, node_data))
+            .right_stream()
+    }
+
+    fn repo_params(&self) -> Result<RepoWalkParams, Error> {
+        let walk_roots = self
+            .roots
+            .iter()
+            .map(|root| {
+                let edge_type = root.get_type().root_edge_type().ok_or_else(|| {
+                    format_err!("Cannot start a walk from node type {:?}", root.get_type())
+                })?;
+                Ok(OutgoingEdge::new(edge_type, root.clone()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // Only walk derived node types that the repo is configured to contain
+        let derived_data_config = self.repo.repo_derived_data().config();
+        let mut include_node_types = self.include_node_types.clone();
+        include_node_types.retain(|t| {
+            t.derived_data_name()
+                .map_or(true, |name| derived_data_config.is_enabled(name))
+        });
+
+        Ok(RepoWalkParams {
+            repo: self.repo.clone(),
+            logger: self.logger.clone(),
+            scuba_builder: MononokeScubaSampleBuilder::with_discard(),
+            scheduled_max: self.scheduled_max,
+            sql_shard_info: self.sql_shard_info.clone(),
+            walk_roots,
+            bookmark_log_window: None,
+            diff_base_roots: vec![],
+            include_node_types,
+            include_edge_types: self.include_edge_types.clone(),
+            hash_validation_node_types: HashSet::new(),
+            edge_stats: EdgeLoadStats::new(
+                self.logger.clone(),
+                EMBEDDED_WALK,
+                self.repo.repo_identity().name().to_string(),
+                false,
+            ),
+            content_dedup: ContentDedupStats::new(
+                self.logger.clone(),
+                self.repo.repo_identity().name().to_string(),
+                None,
+            ),
+            corruption_log: None,
+            pending_steps: PendingSteps::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+
+    use super::*;
+    use crate::detail::graph::ChangesetKey;
+    use crate::detail::graph::UnitKey;
+    use crate::testlib::generate_repo;
+    use crate::testlib::walk_repo;
+    use crate::testlib::SyntheticRepo;
+    use crate::testlib::SyntheticRepoShape;
+    use crate::testlib::WalkCounts;
+
+    async fn walk(
+        fb: FacebookInit,
+        repo: &SyntheticRepo,
+        build: impl FnOnce(WalkBuilder) -> WalkBuilder,
+    ) -> Result<WalkCounts, Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let builder = WalkBuilder::new(repo.repo.clone(), ctx.logger().clone());
+        build(builder)
+            .walk(ctx)
+            .try_fold(WalkCounts::default(), |mut counts, (node, _)| async move {
+                *counts.visited.entry(node.get_type()).or_default() += 1;
+                Ok(counts)
+            })
+            .await
+    }
+
+    #[fbinit::test]
+    async fn test_walk_from_bookmark(fb: FacebookInit) -> Result<(), Error> {
+        let repo = generate_repo(fb, &SyntheticRepoShape::default()).await?;
+        let counts = walk_repo(
+            fb,
+            &repo,
+            [
+                NodeType::Bookmark,
+                NodeType::Changeset,
+                NodeType::FileContent,
+            ],
+        )
+        .await?;
+        assert_eq!(counts.visited(NodeType::Bookmark), 1);
+        assert_eq!(counts.visited(NodeType::Changeset), repo.changesets);
+        assert_eq!(counts.visited(NodeType::FileContent), repo.contents);
+        assert_eq!(counts.content_bytes, repo.content_bytes);
+        // Types not asked for are not walked
+        assert_eq!(counts.visited(NodeType::FileContentMetadataV2), 0);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_walk_edge_types(fb: FacebookInit) -> Result<(), Error> {
+        let repo = generate_repo(fb, &SyntheticRepoShape::default()).await?;
+        let counts = walk(fb, &repo, |builder| {
+            builder
+                .with_root(Node::Bookmark(repo.bookmark.clone()))
+                .with_node_types([NodeType::Bookmark, NodeType::Changeset])
+                .with_edge_types([EdgeType::BookmarkToChangeset])
+        })
+        .await?;
+        // Without the parent edges only the bookmarked changeset is walked
+        assert_eq!(counts.visited(NodeType::Bookmark), 1);
+        assert_eq!(counts.visited(NodeType::Changeset), 1);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_walk_from_changeset_with_depth(fb: FacebookInit) -> Result<(), Error> {
+        let repo = generate_repo(fb, &SyntheticRepoShape::default()).await?;
+        let counts = walk(fb, &repo, |builder| {
+            builder
+                .with_root(Node::Changeset(ChangesetKey {
+                    inner: repo.head,
+                    filenode_known_derived: false,
+                }))
+                .with_node_types([NodeType::Changeset])
+                .with_max_ancestor_depth(Some(2))
+                .with_scheduled_max(1)
+        })
+        .await?;
+        // The root and two generations of parents on a linear repo
+        assert_eq!(counts.visited(NodeType::Changeset), 3);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_walk_invalid_root(fb: FacebookInit) -> Result<(), Error> {
+        let repo = generate_repo(fb, &SyntheticRepoShape::default()).await?;
+        let result = walk(fb, &repo, |builder| {
+            builder.with_root(Node::Root(UnitKey()))
+        })
+        .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -6,20 +6,28 @@
  */
 
 use std::collections::HashSet;
+use std::sync::Arc;
 
+use crate::detail::budget::WalkBudget;
 use crate::detail::graph::EdgeType;
 use crate::detail::graph::NodeType;
+use crate::detail::heartbeat::Heartbeat;
+use crate::detail::metadata_backfill::MetadataBackfill;
 use crate::detail::progress::ProgressStateCountByType;
 use crate::detail::progress::ProgressStateMutex;
 use crate::detail::progress::ProgressSummary;
+use crate::detail::retry::StepRetryOptions;
+use crate::detail::skip_keys::KnownBadKeys;
 use crate::detail::state::StepStats;
 use crate::detail::tail::TailParams;
 use crate::detail::walk::RepoWalkParams;
 
 pub const SCRUB: &str = "scrub";
+pub const SCRUB_SPARSE: &str = "scrub_sparse";
 pub const COMPRESSION_BENEFIT: &str = "compression_benefit";
 pub const VALIDATE: &str = "validate";
 pub const CORPUS: &str = "corpus";
+pub const NODE_LOOKUP: &str = "node_lookup";
 
 // Per repo things we don't pass into the walk
 #[derive(Clone)]
@@ -33,10 +41,18 @@ pub struct RepoSubcommandParams {
 #[derive(Clone)]
 pub struct JobWalkParams {
     pub enable_derive: bool,
+    pub stop_at_public: bool,
+    pub max_ancestor_depth: Option<u64>,
+    pub skip_keys: Arc<KnownBadKeys>,
+    pub check_redacted_present: bool,
     pub quiet: bool,
     pub error_as_data_node_types: HashSet<NodeType>,
     pub error_as_data_edge_types: HashSet<EdgeType>,
     pub repo_count: usize,
+    pub heartbeat: Heartbeat,
+    pub budget: WalkBudget,
+    pub metadata_backfill: MetadataBackfill,
+    pub retry_options: StepRetryOptions,
 }
 
 #[derive(Clone)]
@@ -48,6 +64,7 @@ pub struct JobParams {
 mononoke_app::subcommands! {
     mod compression_benefit;
     mod corpus;
+    mod node_lookup;
     mod scrub;
     mod validate;
 }
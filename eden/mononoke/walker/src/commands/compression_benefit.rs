@@ -24,13 +24,17 @@ use sharding_ext::RepoShard;
 use slog::info;
 use slog::Logger;
 
+use crate::args::apply_walk_config;
+use crate::args::NodeTypeArg;
 use crate::args::SamplingArgs;
 use crate::args::WalkerCommonArgs;
+use crate::args::WalkerSubcommand;
 use crate::commands::JobParams;
 use crate::commands::COMPRESSION_BENEFIT;
 use crate::detail::graph::Node;
 use crate::detail::sampling::WalkSampleMapping;
 use crate::detail::sizing::compression_benefit;
+use crate::detail::sizing::DictionaryOptions;
 use crate::detail::sizing::SizingCommand;
 use crate::detail::sizing::SizingSample;
 use crate::setup::setup_common;
@@ -46,6 +50,18 @@ pub struct CommandArgs {
     #[clap(long, default_value = "3")]
     pub compression_level: i32,
 
+    /// Node types to train a zstd dictionary for, e.g. HgManifest or
+    /// HgFileEnvelope.  Samples of these types after the training ones are
+    /// also compressed with the dictionary and reported separately.
+    #[clap(long)]
+    pub dictionary_node_type: Vec<NodeTypeArg>,
+    /// Number of samples of each node type to train its dictionary on.
+    #[clap(long, default_value = "1000")]
+    pub dictionary_training_samples: usize,
+    /// Maximum size of each dictionary, in bytes.
+    #[clap(long, default_value = "112640")]
+    pub dictionary_max_size: usize,
+
     #[clap(flatten, next_help_heading = "SAMPLING OPTIONS")]
     pub sampling: SamplingArgs,
 
@@ -166,6 +182,9 @@ async fn setup_sizing(
 ) -> Result<(JobParams, SizingCommand), Error> {
     let CommandArgs {
         compression_level,
+        dictionary_node_type,
+        dictionary_training_samples,
+        dictionary_max_size,
         sampling,
         common_args,
     } = args;
@@ -183,6 +202,11 @@ async fn setup_sizing(
 
     let command = SizingCommand {
         compression_level: *compression_level,
+        dictionary_options: DictionaryOptions {
+            node_types: NodeTypeArg::parse_args(dictionary_node_type),
+            training_samples: *dictionary_training_samples,
+            max_size: *dictionary_max_size,
+        },
         progress_options: common_args.progress.parse_args(),
         sampling_options: sampling.parse_args(100 /* default_sample_rate */)?,
         sampler,
@@ -190,7 +214,11 @@ async fn setup_sizing(
     Ok((job_params, command))
 }
 
-pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<(), Error> {
+pub async fn run(app: MononokeApp, mut args: CommandArgs) -> Result<(), Error> {
+    apply_walk_config(&app, &mut args.common_args, Some(&mut args.sampling), None)?;
+    args.common_args
+        .validate(WalkerSubcommand::CompressionBenefit)?;
+    args.sampling.validate(&args.common_args)?;
     let walker_args = &app.args::<WalkerArgs>()?;
     match &walker_args.sharded_service_name {
         Some(service_name) => run_sharded(app, args, service_name.to_string()).await,
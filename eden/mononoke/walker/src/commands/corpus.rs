@@ -24,8 +24,10 @@ use sharding_ext::RepoShard;
 use slog::info;
 use slog::Logger;
 
+use crate::args::apply_walk_config;
 use crate::args::SamplingArgs;
 use crate::args::WalkerCommonArgs;
+use crate::args::WalkerSubcommand;
 use crate::commands::JobParams;
 use crate::commands::CORPUS;
 use crate::detail::corpus::corpus;
@@ -199,7 +201,10 @@ async fn setup_corpus(
     Ok((job_params, command))
 }
 
-pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<(), Error> {
+pub async fn run(app: MononokeApp, mut args: CommandArgs) -> Result<(), Error> {
+    apply_walk_config(&app, &mut args.common_args, Some(&mut args.sampling), None)?;
+    args.common_args.validate(WalkerSubcommand::Corpus)?;
+    args.sampling.validate(&args.common_args)?;
     let walker_args = &app.args::<WalkerArgs>()?;
     match &walker_args.sharded_service_name {
         Some(service_name) => run_sharded(app, args, service_name.to_string()).await,
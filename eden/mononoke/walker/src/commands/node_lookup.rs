@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use anyhow::format_err;
+use anyhow::Error;
+use clap::Parser;
+use mononoke_app::MononokeApp;
+
+use crate::args::OutputFormat;
+use crate::args::WalkerCommonArgs;
+use crate::args::WalkerSubcommand;
+use crate::commands::NODE_LOOKUP;
+use crate::detail::node_lookup::node_lookup;
+use crate::detail::parse_node::parse_node;
+use crate::setup::setup_common;
+use crate::WalkerArgs;
+
+/// Load a single node and print its data and outgoing edges, without
+/// walking any further.
+#[derive(Parser)]
+pub struct CommandArgs {
+    /// Node to look up in format <NodeType>:<node_key>, e.g.
+    /// HgChangeset:7712b62acdc858689504945ac8965a303ded6626 or
+    /// HgManifest:<manifest_id>:<path>
+    pub node: String,
+
+    /// Set the output format
+    #[clap(long, short = 'F', default_value = "PrettyDebug")]
+    pub output_format: OutputFormat,
+
+    #[clap(flatten)]
+    pub common_args: WalkerCommonArgs,
+}
+
+pub async fn run(app: MononokeApp, mut args: CommandArgs) -> Result<(), Error> {
+    args.common_args.validate(WalkerSubcommand::NodeLookup)?;
+    let node = parse_node(&args.node)?;
+    if node.get_type().root_edge_type().is_none() {
+        return Err(format_err!(
+            "Cannot look up nodes of type {}",
+            node.get_type()
+        ));
+    }
+    // The node is the only root, and its edges are reported but not walked
+    let walk_roots = &mut args.common_args.walk_roots;
+    walk_roots.bookmark.clear();
+    walk_roots.walk_root = vec![args.node.clone()];
+    walk_roots.bookmark_log_hours = None;
+    walk_roots.diff_base_root.clear();
+
+    let walker_args = &app.args::<WalkerArgs>()?;
+    let job_params = setup_common(
+        NODE_LOOKUP,
+        &app,
+        &walker_args.repos,
+        &args.common_args,
+        None, // blobstore sampler
+        None, // blobstore component sampler
+    )
+    .await?;
+    node_lookup(app.fb, job_params, args.output_format).await
+}
@@ -5,11 +5,13 @@
  * GNU General Public License version 2.
  */
 
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::OnceLock;
 
+use anyhow::bail;
 use anyhow::Context;
 use anyhow::Error;
 use async_trait::async_trait;
@@ -24,18 +26,24 @@ use sharding_ext::RepoShard;
 use slog::info;
 use slog::Logger;
 
+use crate::args::apply_walk_config;
 use crate::args::OutputFormat;
 use crate::args::SamplingArgs;
+use crate::args::ScrubBlobAgeArgs;
 use crate::args::ScrubOutputNodeArgs;
 use crate::args::ScrubPackLogArgs;
+use crate::args::ScrubTierArg;
 use crate::args::WalkerCommonArgs;
+use crate::args::WalkerSubcommand;
 use crate::commands::JobParams;
-use crate::commands::SCRUB;
+use crate::detail::blob_age::BlobAgeHistogram;
 use crate::detail::graph::Node;
+use crate::detail::key_map::SampleKeyMap;
 use crate::detail::sampling::WalkSampleMapping;
 use crate::detail::scrub::scrub_objects;
 use crate::detail::scrub::ScrubCommand;
 use crate::detail::scrub::ScrubSample;
+use crate::detail::scrub::ScrubTier;
 use crate::setup::setup_common;
 use crate::WalkerArgs;
 
@@ -50,12 +58,33 @@ pub struct CommandArgs {
     #[clap(long, short = 'F', default_value = "PrettyDebug")]
     pub output_format: OutputFormat,
 
+    /// Sparse only checks that hg manifests and filenodes parse and skips
+    /// file content, so it is cheap enough to run daily between deep scrubs.
+    /// Its stats are reported under scrub_sparse rather than scrub.
+    #[clap(long, value_enum, default_value_t = ScrubTierArg::Deep)]
+    pub scrub_tier: ScrubTierArg,
+
+    /// Recompute the sha1 and sha256 of sampled file content as it is read,
+    /// and check them against the stored content metadata and alias blobs.
+    /// Can't be used with --limit-data-fetch or the sparse tier.
+    #[clap(long)]
+    pub verify_content_hashes: bool,
+
     #[clap(flatten)]
     pub output_nodes: ScrubOutputNodeArgs,
 
     #[clap(flatten)]
     pub pack_log_info: ScrubPackLogArgs,
 
+    /// Append a JSON record to this file for each blobstore key loaded for
+    /// a sampled node, with the repo path and type of the node, to match hot
+    /// paths to hot key ranges in the storage backend.
+    #[clap(long)]
+    pub sample_key_map_file: Option<PathBuf>,
+
+    #[clap(flatten, next_help_heading = "BLOB AGE OPTIONS")]
+    pub blob_age: ScrubBlobAgeArgs,
+
     #[clap(flatten, next_help_heading = "SAMPLING OPTIONS")]
     pub sampling: SamplingArgs,
 
@@ -63,6 +92,26 @@ pub struct CommandArgs {
     pub common_args: WalkerCommonArgs,
 }
 
+impl CommandArgs {
+    fn validate(&self) -> Result<(), Error> {
+        self.common_args.validate(WalkerSubcommand::Scrub)?;
+        self.sampling.validate(&self.common_args)?;
+        if self.verify_content_hashes {
+            if let ScrubTierArg::Sparse = self.scrub_tier {
+                bail!(
+                    "--verify-content-hashes needs file content, which --scrub-tier sparse never loads"
+                );
+            }
+            if self.common_args.limit_data_fetch {
+                bail!(
+                    "--verify-content-hashes needs file content read in full, it can't be used with --limit-data-fetch"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Struct representing the Walker Scrub BP.
 pub struct WalkerScrubProcess {
     app: MononokeApp,
@@ -171,9 +220,10 @@ async fn setup_scrub(
     app: &MononokeApp,
     args: &CommandArgs,
 ) -> Result<(JobParams, ScrubCommand), Error> {
+    let tier = ScrubTier::from(args.scrub_tier);
     let component_sampler = Arc::new(WalkSampleMapping::<Node, ScrubSample>::new());
     let job_params = setup_common(
-        SCRUB,
+        tier.stats_key(),
         app,
         repos,
         &args.common_args,
@@ -184,24 +234,43 @@ async fn setup_scrub(
 
     let CommandArgs {
         output_format,
+        scrub_tier: _,
+        verify_content_hashes,
         output_nodes,
         pack_log_info,
+        sample_key_map_file,
+        blob_age,
         sampling,
         common_args,
     } = args;
     let command = ScrubCommand {
+        tier,
         limit_data_fetch: common_args.limit_data_fetch,
+        verify_content_hashes: *verify_content_hashes,
         output_format: output_format.clone(),
         output_node_types: output_nodes.parse_args(),
         progress_options: common_args.progress.parse_args(),
         sampling_options: sampling.parse_args(1)?,
         pack_info_log_options: pack_log_info.parse_args(app.fb)?,
+        sample_key_map: sample_key_map_file
+            .as_deref()
+            .map(SampleKeyMap::open)
+            .transpose()?,
+        blob_age_filter: blob_age.parse_args()?,
+        blob_age_histogram: BlobAgeHistogram::default(),
         sampler: component_sampler,
     };
     Ok((job_params, command))
 }
 
-pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<(), Error> {
+pub async fn run(app: MononokeApp, mut args: CommandArgs) -> Result<(), Error> {
+    apply_walk_config(
+        &app,
+        &mut args.common_args,
+        Some(&mut args.sampling),
+        Some(&mut args.output_format),
+    )?;
+    args.validate()?;
     let walker_args = &app.args::<WalkerArgs>()?;
     match &walker_args.sharded_service_name {
         Some(service_name) => run_sharded(app, args, service_name.to_string()).await,
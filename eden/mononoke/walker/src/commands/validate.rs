@@ -24,8 +24,11 @@ use sharding_ext::RepoShard;
 use slog::info;
 use slog::Logger;
 
+use crate::args::apply_walk_config;
+use crate::args::LinknodeRepairArgs;
 use crate::args::ValidateCheckTypeArgs;
 use crate::args::WalkerCommonArgs;
+use crate::args::WalkerSubcommand;
 use crate::commands::JobParams;
 use crate::commands::VALIDATE;
 use crate::detail::validate::validate;
@@ -42,6 +45,9 @@ pub struct CommandArgs {
     #[clap(flatten)]
     pub check_types: ValidateCheckTypeArgs,
 
+    #[clap(flatten)]
+    pub linknode_repair: LinknodeRepairArgs,
+
     #[clap(flatten)]
     pub common_args: WalkerCommonArgs,
 }
@@ -159,6 +165,7 @@ async fn setup_validate(
 ) -> Result<(JobParams, ValidateCommand), Error> {
     let CommandArgs {
         check_types,
+        linknode_repair,
         common_args,
     } = args;
     let job_params = setup_common(
@@ -174,11 +181,14 @@ async fn setup_validate(
     let command = ValidateCommand {
         include_check_types: check_types.parse_args(),
         progress_options: common_args.progress.parse_args(),
+        linknode_repair: linknode_repair.parse_args(),
     };
     Ok((job_params, command))
 }
 
-pub async fn run(app: MononokeApp, args: CommandArgs) -> Result<(), Error> {
+pub async fn run(app: MononokeApp, mut args: CommandArgs) -> Result<(), Error> {
+    apply_walk_config(&app, &mut args.common_args, None, None)?;
+    args.common_args.validate(WalkerSubcommand::Validate)?;
     let walker_args = &app.args::<WalkerArgs>()?;
     match &walker_args.sharded_service_name {
         Some(service_name) => run_sharded(app, args, service_name.to_string()).await,
@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use fbinit::FacebookInit;
+use slog::info;
+use slog::Logger;
+use stats::prelude::*;
+
+use crate::detail::graph::NodeType;
+use crate::detail::log;
+
+define_stats! {
+    prefix = "mononoke.walker";
+    walk_blob_age_by_type: dynamic_singleton_counter("{}.blob_age.{}.{}.{}", (subcommand: &'static str, repo: String, node_type: &'static str, bucket: &'static str)),
+}
+
+pub const DAY_SECS: i64 = 24 * 60 * 60;
+
+// Upper bounds in days of the age histogram buckets, each with its name.
+// Blobs older than the last bound are counted in a final bucket.
+const AGE_BUCKETS: &[(i64, &str)] = &[
+    (1, "lt_1d"),
+    (7, "lt_7d"),
+    (30, "lt_30d"),
+    (90, "lt_90d"),
+    (365, "lt_365d"),
+];
+const OLDEST_BUCKET: &str = "ge_365d";
+
+/// Limits which blobs are counted by the age of the blob, from the creation
+/// time recorded by the blobstore.  Blobs from stores that don't record a
+/// creation time are always counted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlobAgeFilter {
+    pub min_age_secs: Option<i64>,
+    pub max_age_secs: Option<i64>,
+}
+
+impl BlobAgeFilter {
+    pub fn matches(&self, age_secs: i64) -> bool {
+        self.min_age_secs.map_or(true, |min| age_secs >= min)
+            && self.max_age_secs.map_or(true, |max| age_secs <= max)
+    }
+}
+
+/// Counts of the blobs loaded for each node type, bucketed by age.
+#[derive(Clone, Default)]
+pub struct BlobAgeHistogram {
+    counts: Arc<Mutex<HashMap<NodeType, [u64; AGE_BUCKETS.len() + 1]>>>,
+}
+
+fn bucket_index(age_secs: i64) -> usize {
+    AGE_BUCKETS
+        .iter()
+        .position(|(days, _)| age_secs < days * DAY_SECS)
+        .unwrap_or(AGE_BUCKETS.len())
+}
+
+fn bucket_name(index: usize) -> &'static str {
+    AGE_BUCKETS
+        .get(index)
+        .map_or(OLDEST_BUCKET, |(_, name)| name)
+}
+
+impl BlobAgeHistogram {
+    pub fn record(&self, node_type: NodeType, age_secs: i64) {
+        let mut counts = self.counts.lock().expect("lock poisoned");
+        counts.entry(node_type).or_default()[bucket_index(age_secs)] += 1;
+    }
+
+    /// Log and report the histogram, if any blob ages were recorded, and
+    /// start counting afresh.
+    pub fn report(
+        &self,
+        fb: FacebookInit,
+        logger: &Logger,
+        subcommand_stats_key: &'static str,
+        repo_name: &str,
+    ) {
+        let counts = std::mem::take(&mut *self.counts.lock().expect("lock poisoned"));
+        if counts.is_empty() {
+            return;
+        }
+        let mut by_type: Vec<_> = counts.into_iter().collect();
+        by_type.sort_by_cached_key(|(node_type, _)| node_type.to_string());
+        let detail = by_type
+            .iter()
+            .map(|(node_type, buckets)| {
+                let buckets = buckets
+                    .iter()
+                    .map(|count| count.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("{}:{}", node_type, buckets)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let header = (0..=AGE_BUCKETS.len())
+            .map(bucket_name)
+            .collect::<Vec<_>>()
+            .join(",");
+        info!(
+            logger,
+            #log::SIZING,
+            "Blob ages; Type:{} {}",
+            header,
+            detail,
+        );
+        for (node_type, buckets) in by_type {
+            for (index, count) in buckets.iter().enumerate() {
+                STATS::walk_blob_age_by_type.set_value(
+                    fb,
+                    *count as i64,
+                    (
+                        subcommand_stats_key,
+                        repo_name.to_string(),
+                        node_type.into(),
+                        bucket_name(index),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let filter = BlobAgeFilter {
+            min_age_secs: Some(DAY_SECS),
+            max_age_secs: Some(7 * DAY_SECS),
+        };
+        assert!(!filter.matches(0));
+        assert!(filter.matches(DAY_SECS));
+        assert!(filter.matches(7 * DAY_SECS));
+        assert!(!filter.matches(7 * DAY_SECS + 1));
+        assert!(BlobAgeFilter::default().matches(0));
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(bucket_name(bucket_index(0)), "lt_1d");
+        assert_eq!(bucket_name(bucket_index(DAY_SECS)), "lt_7d");
+        assert_eq!(bucket_name(bucket_index(364 * DAY_SECS)), "lt_365d");
+        assert_eq!(bucket_name(bucket_index(365 * DAY_SECS)), "ge_365d");
+    }
+}
@@ -8,17 +8,23 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use anyhow::Error;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
 use blobstore::BlobstoreMetadata;
 use context::CoreContext;
 use metaconfig_types::BlobConfig;
 use metaconfig_types::BlobstoreId;
 use mononoke_types::repo::REPO_PREFIX_REGEX;
+use mononoke_types::BlobstoreBytes;
 use mononoke_types::RepositoryId;
 use multiplexedblob::LoggingScrubHandler;
 use multiplexedblob::ScrubHandler;
+use samplingblob::ComponentSamplingHandler;
 use scuba::value::NullScubaValue;
 use scuba::value::ScubaValue;
 use scuba_ext::MononokeScubaSampleBuilder;
@@ -35,6 +41,10 @@ define_stats! {
     prefix = "mononoke.walker";
     scrub_repaired: dynamic_timeseries("{}.blobstore.{}.{}.repaired", (subcommand: &'static str, blobstore_id: String, repo: String); Rate, Sum),
     scrub_repair_required: dynamic_timeseries("{}.blobstore.{}.{}.repair_required", (subcommand: &'static str, blobstore_id: String, repo: String); Rate, Sum),
+    component_get_found: dynamic_timeseries("{}.blobstore.{}.{}.get_found", (subcommand: &'static str, blobstore_id: String, repo: String); Rate, Sum),
+    component_get_missing: dynamic_timeseries("{}.blobstore.{}.{}.get_missing", (subcommand: &'static str, blobstore_id: String, repo: String); Rate, Sum),
+    component_get_error: dynamic_timeseries("{}.blobstore.{}.{}.get_error", (subcommand: &'static str, blobstore_id: String, repo: String); Rate, Sum),
+    component_get_ms: dynamic_histogram("{}.blobstore.{}.{}.get_ms", (subcommand: &'static str, blobstore_id: String, repo: String); 10, 0, 1_000, Average, Sum, Count; P 50; P 90; P 99),
 }
 
 pub const BLOBSTORE_ID: &str = "blobstore_id";
@@ -141,6 +151,153 @@ impl ScrubHandler for StatsScrubHandler {
     }
 }
 
+/// Records the outcome and latency of each read from a component of a
+/// multiplexed blobstore, so that errors and missing blobs are reported per
+/// store rather than hidden by the multiplex.  Reads from blobstores that
+/// aren't multiplexed are not recorded.
+///
+/// Sampling is passed on to the `inner` handler, if any.
+pub struct ComponentStatsHandler {
+    scuba: MononokeScubaSampleBuilder,
+    subcommand_stats_key: &'static str,
+    repo_id_to_name: HashMap<RepositoryId, String>,
+    inner: Option<Arc<dyn ComponentSamplingHandler>>,
+}
+
+impl ComponentStatsHandler {
+    pub fn new(
+        scuba: MononokeScubaSampleBuilder,
+        subcommand_stats_key: &'static str,
+        repo_id_to_name: HashMap<RepositoryId, String>,
+        inner: Option<Arc<dyn ComponentSamplingHandler>>,
+    ) -> Self {
+        Self {
+            scuba,
+            subcommand_stats_key,
+            repo_id_to_name,
+            inner,
+        }
+    }
+}
+
+impl fmt::Debug for ComponentStatsHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentStatsHandler")
+            .field("subcommand_stats_key", &self.subcommand_stats_key)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl ComponentSamplingHandler for ComponentStatsHandler {
+    fn sample_get(
+        &self,
+        ctx: &CoreContext,
+        key: &str,
+        value: Option<&BlobstoreGetData>,
+        inner_id: Option<BlobstoreId>,
+    ) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.sample_get(ctx, key, value, inner_id),
+            None => Ok(()),
+        }
+    }
+
+    fn sample_put(
+        &self,
+        ctx: &CoreContext,
+        key: &str,
+        value: &BlobstoreBytes,
+        inner_id: Option<BlobstoreId>,
+    ) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.sample_put(ctx, key, value, inner_id),
+            None => Ok(()),
+        }
+    }
+
+    fn sample_is_present(
+        &self,
+        ctx: &CoreContext,
+        key: &str,
+        value: &BlobstoreIsPresent,
+        inner_id: Option<BlobstoreId>,
+    ) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.sample_is_present(ctx, key, value, inner_id),
+            None => Ok(()),
+        }
+    }
+
+    fn sample_unlink(
+        &self,
+        ctx: &CoreContext,
+        key: &str,
+        inner_id: Option<BlobstoreId>,
+    ) -> Result<(), Error> {
+        match &self.inner {
+            Some(inner) => inner.sample_unlink(ctx, key, inner_id),
+            None => Ok(()),
+        }
+    }
+
+    fn record_get(
+        &self,
+        ctx: &CoreContext,
+        key: &str,
+        result: Result<Option<&BlobstoreGetData>, &Error>,
+        elapsed: Duration,
+        inner_id: Option<BlobstoreId>,
+    ) {
+        if let Some(inner) = &self.inner {
+            inner.record_get(ctx, key, result, elapsed, inner_id);
+        }
+        let blobstore_id = match inner_id {
+            Some(blobstore_id) => blobstore_id,
+            None => return,
+        };
+
+        let repo_name = get_repo_id_from_key(key)
+            .ok()
+            .flatten()
+            .and_then(|repo_id| self.repo_id_to_name.get(&repo_id));
+        if let Some(repo_name) = repo_name {
+            let stats_key = (
+                self.subcommand_stats_key,
+                blobstore_id.to_string(),
+                repo_name.clone(),
+            );
+            match result {
+                Ok(Some(_)) => STATS::component_get_found.add_value(1, stats_key.clone()),
+                Ok(None) => STATS::component_get_missing.add_value(1, stats_key.clone()),
+                Err(_) => STATS::component_get_error.add_value(1, stats_key.clone()),
+            }
+            STATS::component_get_ms.add_value(elapsed.as_millis() as i64, stats_key);
+        }
+
+        // Log the reads that this store couldn't serve, to find which keys
+        // are inconsistent between stores.
+        let error_msg = match result {
+            Ok(Some(_)) => return,
+            Ok(None) => None,
+            Err(e) => Some(format!("{:?}", e)),
+        };
+        let mut scuba = self.scuba.clone();
+        if let Some(repo_name) = repo_name {
+            scuba.add(REPO, repo_name.clone());
+        }
+        scuba
+            .add(BLOBSTORE_ID, blobstore_id)
+            .add(NODE_KEY, key)
+            .add(CHECK_TYPE, "component_get")
+            .add(CHECK_FAIL, 1)
+            .add_opt(ERROR_MSG, error_msg)
+            .add("elapsed_ms", elapsed.as_millis() as u64)
+            .add("session", ctx.session().metadata().session_id().to_string())
+            .log();
+    }
+}
+
 pub fn replace_blobconfig(
     blob_config: &mut BlobConfig,
     inner_blobstore_id: Option<u64>,
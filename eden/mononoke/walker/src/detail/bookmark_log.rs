@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::Error;
+use blobrepo::BlobRepo;
+use bookmarks::BookmarkKey;
+use bookmarks::BookmarkUpdateLogRef;
+use commit_graph::CommitGraphRef;
+use context::CoreContext;
+use futures::stream::TryStreamExt;
+use mononoke_types::ChangesetId;
+use mononoke_types::Timestamp;
+
+/// Most log entries read per bookmark for one window.
+const MAX_LOG_ENTRIES_PER_BOOKMARK: u32 = 10000;
+
+/// Commits introduced to the given bookmarks by log entries within the window
+/// ending now.
+pub struct LandedChangesets {
+    /// Bookmark positions set by the log entries, to walk from.
+    pub heads: Vec<ChangesetId>,
+    /// Every commit that became reachable from the bookmarks during the
+    /// window but was not reachable from their positions before it.
+    pub changesets: HashSet<ChangesetId>,
+}
+
+pub async fn landed_changesets(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    bookmarks: impl IntoIterator<Item = &BookmarkKey>,
+    window: Duration,
+) -> Result<LandedChangesets, Error> {
+    let now = Timestamp::now();
+    let window_start =
+        Timestamp::from_timestamp_nanos(now.timestamp_nanos() - window.as_nanos() as i64);
+    let before_window = Timestamp::from_timestamp_nanos(window_start.timestamp_nanos() - 1);
+
+    let log = repo.bookmark_update_log();
+    let mut heads = HashSet::new();
+    let mut common = HashSet::new();
+    for bookmark in bookmarks {
+        let new_positions: Vec<_> = log
+            .list_bookmark_log_entries_ts_in_range(
+                ctx.clone(),
+                bookmark.clone(),
+                MAX_LOG_ENTRIES_PER_BOOKMARK,
+                window_start,
+                now,
+            )
+            .try_collect()
+            .await?;
+        if new_positions.is_empty() {
+            continue;
+        }
+        heads.extend(
+            new_positions
+                .into_iter()
+                .filter_map(|(_, cs_id, _, _)| cs_id),
+        );
+
+        // The last position before the window, i.e. what was already there.
+        let old_positions: Vec<_> = log
+            .list_bookmark_log_entries_ts_in_range(
+                ctx.clone(),
+                bookmark.clone(),
+                1,
+                Timestamp::from_timestamp_nanos(0),
+                before_window,
+            )
+            .try_collect()
+            .await?;
+        common.extend(
+            old_positions
+                .into_iter()
+                .filter_map(|(_, cs_id, _, _)| cs_id),
+        );
+    }
+
+    let heads: Vec<_> = heads.into_iter().collect();
+    let changesets = repo
+        .commit_graph()
+        .ancestors_difference(ctx, heads.clone(), common.into_iter().collect())
+        .await?
+        .into_iter()
+        .collect();
+    Ok(LandedChangesets { heads, changesets })
+}
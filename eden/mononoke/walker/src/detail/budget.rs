@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use slog::info;
+use slog::Logger;
+
+#[derive(Default)]
+struct BudgetState {
+    max_nodes: Option<u64>,
+    max_bytes: Option<u64>,
+    nodes: AtomicU64,
+    bytes: AtomicU64,
+    exhausted: AtomicBool,
+}
+
+/// Limits on the total nodes walked and bytes loaded across all repos in a
+/// job.  Once either is exceeded no further edges are expanded, so the walk
+/// finishes the steps already scheduled and then ends with partial coverage.
+#[derive(Clone, Default)]
+pub struct WalkBudget {
+    state: Arc<BudgetState>,
+}
+
+impl WalkBudget {
+    pub fn new(max_nodes: Option<u64>, max_bytes: Option<u64>) -> Self {
+        Self {
+            state: Arc::new(BudgetState {
+                max_nodes,
+                max_bytes,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Record a walked node and the bytes loaded for it.
+    pub fn record_step(&self, bytes: u64) {
+        let state = &self.state;
+        let nodes = state.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = state.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if state.max_nodes.map_or(false, |max| nodes >= max)
+            || state.max_bytes.map_or(false, |max| bytes >= max)
+        {
+            state.exhausted.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.state.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Log how much was walked before the budget ran out.
+    pub fn log_summary(&self, logger: &Logger, repo_name: &str) {
+        let state = &self.state;
+        let limit = |max: Option<u64>| max.map_or_else(|| "none".to_string(), |v| v.to_string());
+        info!(
+            logger,
+            "Walk budget exhausted during walk of {}, coverage is partial. Walked {} nodes (max {}) and loaded {} bytes (max {})",
+            repo_name,
+            state.nodes.load(Ordering::Relaxed),
+            limit(state.max_nodes),
+            state.bytes.load(Ordering::Relaxed),
+            limit(state.max_bytes),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_exhausted_by_nodes() {
+        let budget = WalkBudget::new(Some(3), None);
+        budget.record_step(1000);
+        budget.record_step(1000);
+        assert!(!budget.is_exhausted());
+        budget.record_step(0);
+        assert!(budget.is_exhausted());
+    }
+
+    #[test]
+    fn test_budget_exhausted_by_bytes() {
+        let budget = WalkBudget::new(None, Some(100));
+        budget.clone().record_step(60);
+        assert!(!budget.is_exhausted());
+        budget.clone().record_step(60);
+        assert!(budget.is_exhausted());
+    }
+
+    #[test]
+    fn test_unlimited_budget() {
+        let budget = WalkBudget::default();
+        for _ in 0..100 {
+            budget.record_step(u32::MAX as u64);
+        }
+        assert!(!budget.is_exhausted());
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use mononoke_types::hash::Sha256;
+use slog::info;
+use slog::Logger;
+
+use crate::detail::graph::NodeData;
+use crate::detail::log;
+
+#[derive(Default)]
+struct ContentDedupState {
+    file_contents: u64,
+    sha256s: HashSet<Sha256>,
+    truncated: bool,
+}
+
+impl ContentDedupState {
+    fn add_sha256(&mut self, sha256: Sha256, max_distinct: usize) {
+        if self.sha256s.len() < max_distinct {
+            self.sha256s.insert(sha256);
+        } else if !self.sha256s.contains(&sha256) {
+            self.truncated = true;
+        }
+    }
+}
+
+/// Counts the FileContent nodes walked and the distinct content sha256s seen
+/// in FileContentMetadataV2 nodes, so the walk can report how much of the
+/// content it saw would dedup by sha256.
+///
+/// The sha256s are kept in an exact set of at most `max_distinct` entries.
+/// Once that is full further new sha256s are not counted, so the distinct
+/// count is a lower bound and the ratio an upper bound.
+#[derive(Clone)]
+pub struct ContentDedupStats {
+    logger: Logger,
+    repo_name: String,
+    max_distinct: Option<usize>,
+    state: Arc<Mutex<ContentDedupState>>,
+}
+
+impl ContentDedupStats {
+    /// Nothing is recorded if `max_distinct` is None.
+    pub fn new(logger: Logger, repo_name: String, max_distinct: Option<usize>) -> Self {
+        Self {
+            logger,
+            repo_name,
+            max_distinct,
+            state: Arc::new(Mutex::new(ContentDedupState::default())),
+        }
+    }
+
+    pub fn record(&self, node_data: &NodeData) {
+        let max_distinct = match self.max_distinct {
+            Some(max_distinct) => max_distinct,
+            None => return,
+        };
+        match node_data {
+            NodeData::FileContent(_) => {
+                self.state.lock().unwrap().file_contents += 1;
+            }
+            NodeData::FileContentMetadataV2(Some(metadata)) => {
+                self.state
+                    .lock()
+                    .unwrap()
+                    .add_sha256(metadata.sha256, max_distinct);
+            }
+            _ => {}
+        }
+    }
+
+    /// Log the FileContent nodes walked so far against the distinct sha256s
+    /// seen.  Only logs if recording was requested.
+    pub fn report(&self) {
+        if self.max_distinct.is_none() {
+            return;
+        }
+        let state = self.state.lock().unwrap();
+        let distinct = state.sha256s.len() as u64;
+        let ratio = if distinct == 0 {
+            0.0
+        } else {
+            state.file_contents as f64 / distinct as f64
+        };
+        info!(
+            self.logger,
+            #log::CONTENT_DEDUP,
+            "Repo:FileContents,DistinctSha256,DedupRatio,Truncated {}:{},{},{:.3},{}",
+            self.repo_name,
+            state.file_contents,
+            distinct,
+            ratio,
+            state.truncated,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distinct_sha256s_are_bounded() {
+        let mut state = ContentDedupState::default();
+        for byte in [1, 2, 2, 1] {
+            state.add_sha256(Sha256::from_byte_array([byte; 32]), 2);
+        }
+        assert_eq!(state.sha256s.len(), 2);
+        assert!(!state.truncated);
+        state.add_sha256(Sha256::from_byte_array([3; 32]), 2);
+        assert_eq!(state.sha256s.len(), 2);
+        assert!(state.truncated);
+    }
+}
@@ -92,7 +92,11 @@ where
     s.map_ok(move |(walk_key, payload, _progress_stats)| {
         let mtime = payload.mtime;
         match payload.data {
-            Some(NodeData::FileContent(FileContentData::ContentStream(file_bytes_stream))) => {
+            // Only sampled content needs loading, as other blobs aren't dumped
+            Some(NodeData::FileContent(FileContentData::ContentStream(
+                file_bytes_stream,
+                _size,
+            ))) if sampler.is_sampling(&walk_key) => {
                 cloned!(sampler);
                 file_bytes_stream
                     // Force file chunks to be loaded
@@ -190,12 +194,14 @@ fn dump_with_extension(node_type: NodeType) -> bool {
         NodeType::HgBonsaiMapping => false,
         NodeType::HgChangeset => false,
         NodeType::HgChangesetViaBonsai => false,
+        NodeType::HgConvertRevision => false,
         NodeType::HgManifest => false,
         NodeType::HgFileEnvelope => true,
         NodeType::HgFileNode => true,
         NodeType::HgManifestFileNode => false,
         // Content
         NodeType::FileContent => true,
+        NodeType::FileContentChunk => true,
         NodeType::FileContentMetadataV2 => true,
         NodeType::AliasContentMapping => true,
         // Derived Data
@@ -274,13 +280,22 @@ pub struct CorpusSamplingHandler<T> {
     output_dir: Option<String>,
 }
 
-impl<T> SampleTrigger<WalkKeyOptPath<WrappedPath>> for CorpusSamplingHandler<T>
-where
-    T: Default,
-{
+impl SampleTrigger<WalkKeyOptPath<WrappedPath>> for CorpusSamplingHandler<CorpusSample> {
     fn map_keys(&self, sample_key: SamplingKey, walk_key: WalkKeyOptPath<WrappedPath>) {
         self.inner.map_keys(sample_key, walk_key);
     }
+
+    // Any blobs already written for the node are discarded, as it won't
+    // reach the output stage to be moved into place.
+    fn unmap_keys(&self, walk_key: WalkKeyOptPath<WrappedPath>) {
+        if let Some(inflight_dir) = self
+            .inner
+            .complete_step(&walk_key)
+            .and_then(|sample| sample.inflight_dir)
+        {
+            let _ = std::fs::remove_dir_all(inflight_dir);
+        }
+    }
 }
 
 // This exists so we can track output_dir
@@ -295,6 +310,10 @@ impl<T> CorpusSamplingHandler<T> {
     pub fn complete_step(&self, walk_key: &WalkKeyOptPath<WrappedPath>) -> Option<T> {
         self.inner.complete_step(walk_key)
     }
+
+    pub fn is_sampling(&self, walk_key: &WalkKeyOptPath<WrappedPath>) -> bool {
+        self.inner.is_sampling(walk_key)
+    }
 }
 
 #[derive(Debug)]
@@ -413,9 +432,23 @@ async fn run_one(
     job_params: JobWalkParams,
     sub_params: RepoSubcommandParams,
     repo_params: RepoWalkParams,
-    command: CorpusCommand,
+    mut command: CorpusCommand,
     cancellation_requested: Arc<AtomicBool>,
 ) -> Result<(), Error> {
+    let repo_id = repo_params.repo.repo_identity().id();
+    let sample_rotation = sub_params.tail_params.sample_rotation.clone();
+    if let Some(rotation) = &sample_rotation {
+        command.sampling_options.sample_offset = rotation
+            .next_offset(
+                &repo_params.logger,
+                repo_id,
+                command.sampling_options.sample_rate,
+            )
+            .await?;
+    }
+    let sample_offset = command.sampling_options.sample_offset;
+    let budget = job_params.budget.clone();
+
     let sizing_progress_state =
         ProgressStateMutex::new(ProgressStateCountByType::<ScrubStats, ScrubStats>::new(
             fb,
@@ -477,7 +510,14 @@ async fn run_one(
         sub_params.tail_params,
         walk_state,
         make_sink,
-        cancellation_requested,
+        cancellation_requested.clone(),
     )
-    .await
+    .await?;
+
+    if let Some(rotation) = sample_rotation {
+        rotation
+            .record_if_complete(repo_id, sample_offset, &budget, &cancellation_requested)
+            .await?;
+    }
+    Ok(())
 }
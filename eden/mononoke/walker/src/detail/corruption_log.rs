@@ -0,0 +1,336 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Error;
+use dashmap::DashSet;
+use mononoke_types::RepositoryId;
+use mononoke_types::Timestamp;
+use slog::warn;
+use slog::Logger;
+use sql_construct::SqlConstruct;
+use sql_construct::SqlConstructFromMetadataDatabaseConfig;
+use sql_ext::mononoke_queries;
+use sql_ext::SqlConnections;
+use stats::prelude::*;
+
+use crate::detail::graph::Node;
+use crate::detail::walk::OutgoingEdge;
+
+define_stats! {
+    prefix = "mononoke.walker.corruption_log";
+    record_failures: timeseries(Sum),
+}
+
+const STATUS_OPEN: &str = "open";
+const STATUS_RESOLVED: &str = "resolved";
+
+/// Records the nodes walks find missing or corrupt in a SQL table, one row
+/// per node, so that repairs can be tracked against it.  A node found again
+/// has its last seen time and count bumped, and is reopened if it had been
+/// resolved.  A walk that later loads an open node intact marks it resolved.
+#[derive(Clone)]
+pub struct CorruptionLog {
+    sql_corruptions: Arc<SqlCorruptions>,
+}
+
+impl CorruptionLog {
+    pub fn new(sql_corruptions: SqlCorruptions) -> Self {
+        Self {
+            sql_corruptions: Arc::new(sql_corruptions),
+        }
+    }
+
+    /// Start logging a walk of one repo.  The repo's open findings are
+    /// loaded so that the walk can resolve them.
+    pub async fn start_walk(&self, repo_id: RepositoryId) -> Result<RepoCorruptionLog, Error> {
+        let open = self
+            .sql_corruptions
+            .load_open(repo_id)
+            .await?
+            .into_iter()
+            .collect();
+        Ok(RepoCorruptionLog {
+            repo_id,
+            sql_corruptions: self.sql_corruptions.clone(),
+            open,
+        })
+    }
+}
+
+impl fmt::Debug for CorruptionLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CorruptionLog").finish()
+    }
+}
+
+pub struct RepoCorruptionLog {
+    repo_id: RepositoryId,
+    sql_corruptions: Arc<SqlCorruptions>,
+    // (node_type, node_key) of the findings still open
+    open: DashSet<(String, String)>,
+}
+
+impl RepoCorruptionLog {
+    /// Record that stepping to `edge.target` failed the check `check_type`,
+    /// e.g. because it was missing.
+    pub async fn record_finding(
+        &self,
+        check_type: &'static str,
+        edge: &OutgoingEdge,
+        src_node: Option<&Node>,
+    ) -> Result<(), Error> {
+        let finding = Finding {
+            node_type: node_type(&edge.target),
+            node_key: edge.target.stats_key(),
+            check_type: check_type.to_string(),
+            edge_type: Into::<&'static str>::into(edge.label).to_string(),
+            src_node_type: src_node.map(node_type),
+            src_node_key: src_node.map(Node::stats_key),
+        };
+        self.sql_corruptions
+            .record(self.repo_id, &finding, Timestamp::now())
+            .await?;
+        self.open.insert((finding.node_type, finding.node_key));
+        Ok(())
+    }
+
+    /// Note that recording a finding for `node` failed.  Failing to record
+    /// a finding shouldn't stop the walk, so failures are logged and counted
+    /// instead.
+    pub fn record_failed(&self, logger: &Logger, node: &Node, error: Error) {
+        STATS::record_failures.add_value(1);
+        warn!(
+            logger,
+            "Failed to record corruption log entry for {:?}: {:?}", node, error
+        );
+    }
+
+    /// Record that `node` loaded without problems, resolving any open
+    /// finding for it.
+    pub async fn record_intact(&self, node: &Node) -> Result<(), Error> {
+        if self.open.is_empty() {
+            return Ok(());
+        }
+        let key = (node_type(node), node.stats_key());
+        if let Some((node_type, node_key)) = self.open.remove(&key) {
+            self.sql_corruptions
+                .resolve(self.repo_id, &node_type, &node_key, Timestamp::now())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn node_type(node: &Node) -> String {
+    Into::<&'static str>::into(node.get_type()).to_string()
+}
+
+struct Finding {
+    node_type: String,
+    node_key: String,
+    check_type: String,
+    edge_type: String,
+    src_node_type: Option<String>,
+    src_node_key: Option<String>,
+}
+
+pub struct SqlCorruptions {
+    connections: SqlConnections,
+}
+
+impl SqlConstruct for SqlCorruptions {
+    const LABEL: &'static str = "walker_corruptions";
+
+    const CREATION_QUERY: &'static str =
+        include_str!("../../schemas/sqlite-walker_corruptions.sql");
+
+    fn from_sql_connections(connections: SqlConnections) -> Self {
+        Self { connections }
+    }
+}
+
+impl SqlCorruptions {
+    async fn load_open(&self, repo_id: RepositoryId) -> Result<Vec<(String, String)>, Error> {
+        let rows = SelectCorruptionsByStatus::query(
+            &self.connections.read_master_connection,
+            &repo_id,
+            &STATUS_OPEN,
+        )
+        .await?;
+        Ok(rows)
+    }
+
+    async fn record(
+        &self,
+        repo_id: RepositoryId,
+        finding: &Finding,
+        now: Timestamp,
+    ) -> Result<(), Error> {
+        UpsertCorruption::query(
+            &self.connections.write_connection,
+            &repo_id,
+            &finding.node_type,
+            &finding.node_key,
+            &finding.check_type,
+            &finding.edge_type,
+            &finding.src_node_type,
+            &finding.src_node_key,
+            &now,
+            &STATUS_OPEN.to_string(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn resolve(
+        &self,
+        repo_id: RepositoryId,
+        node_type: &String,
+        node_key: &String,
+        now: Timestamp,
+    ) -> Result<(), Error> {
+        ResolveCorruption::query(
+            &self.connections.write_connection,
+            &repo_id,
+            node_type,
+            node_key,
+            &now,
+            &STATUS_RESOLVED.to_string(),
+            &STATUS_OPEN.to_string(),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl SqlConstructFromMetadataDatabaseConfig for SqlCorruptions {}
+
+mononoke_queries! {
+    read SelectTimesSeen(
+        repo_id: RepositoryId,
+        node_type: String,
+        node_key: String,
+    ) -> (u64) {
+        "SELECT times_seen
+        FROM walker_corruptions WHERE repo_id={repo_id} AND node_type={node_type} AND node_key={node_key}"
+    }
+
+    read SelectCorruptionsByStatus(
+        repo_id: RepositoryId,
+        status: &str,
+    ) -> (String, String) {
+        "SELECT node_type, node_key
+        FROM walker_corruptions WHERE repo_id={repo_id} AND status={status}"
+    }
+
+    // Insert the row on the first sighting, otherwise update it, in one
+    // statement so that concurrent walks don't race.
+    write UpsertCorruption(
+        repo_id: RepositoryId,
+        node_type: String,
+        node_key: String,
+        check_type: String,
+        edge_type: String,
+        src_node_type: Option<String>,
+        src_node_key: Option<String>,
+        seen_timestamp: Timestamp,
+        status: String,
+    ) {
+        none,
+        mysql(
+            "INSERT INTO walker_corruptions
+            (repo_id, node_type, node_key, check_type, edge_type, src_node_type, src_node_key, first_seen_timestamp, last_seen_timestamp, times_seen, status)
+            VALUES ({repo_id}, {node_type}, {node_key}, {check_type}, {edge_type}, {src_node_type}, {src_node_key}, {seen_timestamp}, {seen_timestamp}, 1, {status})
+            ON DUPLICATE KEY UPDATE check_type=VALUES(check_type), edge_type=VALUES(edge_type), src_node_type=VALUES(src_node_type), src_node_key=VALUES(src_node_key), last_seen_timestamp=VALUES(last_seen_timestamp), times_seen=times_seen+1, status=VALUES(status), resolved_timestamp=NULL"
+        )
+        sqlite(
+            "INSERT INTO walker_corruptions
+            (repo_id, node_type, node_key, check_type, edge_type, src_node_type, src_node_key, first_seen_timestamp, last_seen_timestamp, times_seen, status)
+            VALUES ({repo_id}, {node_type}, {node_key}, {check_type}, {edge_type}, {src_node_type}, {src_node_key}, {seen_timestamp}, {seen_timestamp}, 1, {status})
+            ON CONFLICT(repo_id, node_type, node_key) DO UPDATE SET check_type=excluded.check_type, edge_type=excluded.edge_type, src_node_type=excluded.src_node_type, src_node_key=excluded.src_node_key, last_seen_timestamp=excluded.last_seen_timestamp, times_seen=times_seen+1, status=excluded.status, resolved_timestamp=NULL"
+        )
+    }
+
+    write ResolveCorruption(
+        repo_id: RepositoryId,
+        node_type: String,
+        node_key: String,
+        resolved_timestamp: Timestamp,
+        resolved: String,
+        open: String,
+    ) {
+        none,
+        "UPDATE walker_corruptions
+        SET status={resolved}, resolved_timestamp={resolved_timestamp}
+        WHERE repo_id={repo_id} AND node_type={node_type} AND node_key={node_key} AND status={open}"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use mononoke_types::ChangesetId;
+
+    use super::*;
+    use crate::detail::graph::ChangesetKey;
+    use crate::detail::graph::EdgeType;
+
+    #[fbinit::test]
+    async fn test_findings_open_and_resolve(_fb: FacebookInit) -> Result<(), Error> {
+        let log = CorruptionLog::new(SqlCorruptions::with_sqlite_in_memory()?);
+        let repo_id = RepositoryId::new(123);
+        let node = Node::Changeset(ChangesetKey {
+            inner: ChangesetId::from_bytes([1; 32])?,
+            filenode_known_derived: false,
+        });
+        let edge = OutgoingEdge::new(EdgeType::ChangesetToBonsaiParent, node.clone());
+
+        // Repeated sightings are deduplicated
+        let walk = log.start_walk(repo_id).await?;
+        walk.record_finding("missing", &edge, None).await?;
+        walk.record_finding("missing", &edge, None).await?;
+        let open = log.sql_corruptions.load_open(repo_id).await?;
+        assert_eq!(open.len(), 1);
+        let (node_type, node_key) = open[0].clone();
+        let times_seen = SelectTimesSeen::query(
+            &log.sql_corruptions.connections.read_master_connection,
+            &repo_id,
+            &node_type,
+            &node_key,
+        )
+        .await?;
+        assert_eq!(times_seen, vec![(2,)]);
+
+        // Each repo has its own findings
+        let other = log.start_walk(RepositoryId::new(456)).await?;
+        other.record_intact(&node).await?;
+        assert!(other.open.is_empty());
+
+        // A later walk finding the node intact resolves it
+        let walk = log.start_walk(repo_id).await?;
+        assert_eq!(walk.open.len(), 1);
+        walk.record_intact(&node).await?;
+        assert!(log.sql_corruptions.load_open(repo_id).await?.is_empty());
+
+        // And finding it again reopens it
+        walk.record_finding("hash_validation_failure", &edge, Some(&node))
+            .await?;
+        assert_eq!(log.sql_corruptions.load_open(repo_id).await?.len(), 1);
+        let times_seen = SelectTimesSeen::query(
+            &log.sql_corruptions.connections.read_master_connection,
+            &repo_id,
+            &node_type,
+            &node_key,
+        )
+        .await?;
+        assert_eq!(times_seen, vec![(3,)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use slog::info;
+use slog::Logger;
+use stats::prelude::*;
+use strum::IntoEnumIterator;
+
+use crate::detail::graph::EdgeType;
+use crate::detail::log;
+
+define_stats! {
+    prefix = "mononoke.walker";
+    edge_load_latency_ms: dynamic_histogram("{}.edge_load.{}.{}.latency_ms", (subcommand: &'static str, repo: String, edge_type: &'static str); 10, 0, 1_000, Average, Sum, Count; P 50; P 90; P 99),
+    edge_load_bytes: dynamic_histogram("{}.edge_load.{}.{}.bytes", (subcommand: &'static str, repo: String, edge_type: &'static str); 65_536, 0, 4_194_304, Average, Sum, Count; P 50; P 90; P 99),
+    edge_load_retries: dynamic_timeseries("{}.edge_load.{}.{}.retries", (subcommand: &'static str, repo: String, edge_type: &'static str); Rate, Sum),
+    edge_load_missing_target: dynamic_timeseries("{}.edge_load.{}.{}.missing_target", (subcommand: &'static str, repo: String, edge_type: &'static str); Rate, Sum),
+    edge_load_errors: dynamic_timeseries("{}.edge_load.{}.{}.load_errors", (subcommand: &'static str, repo: String, edge_type: &'static str); Rate, Sum),
+}
+
+const BUCKETS: usize = 64;
+
+/// Histogram with power of two buckets, cheap enough to update on every step
+/// from many tasks at once.
+struct Log2Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl Default for Log2Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Log2Histogram {
+    fn add(&self, value: u64) {
+        // Bucket 0 holds 0, bucket n holds [2^(n-1), 2^n)
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.max.fetch_max(value, Ordering::Relaxed);
+    }
+
+    fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn sum(&self) -> u64 {
+        self.sum.load(Ordering::Relaxed)
+    }
+
+    fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    /// Upper bound of the bucket containing the given percentile
+    fn percentile(&self, percentile: u64) -> u64 {
+        let max = self.max();
+        let target = (self.count() * percentile + 99) / 100;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count.load(Ordering::Relaxed);
+            if seen >= target && seen > 0 {
+                let upper = if bucket == 0 { 0 } else { (1u64 << bucket) - 1 };
+                return upper.min(max);
+            }
+        }
+        max
+    }
+}
+
+#[derive(Default)]
+struct EdgeLoadSummary {
+    latency_us: Log2Histogram,
+    bytes: Log2Histogram,
+    missing_target: AtomicU64,
+    load_errors: AtomicU64,
+}
+
+/// Records how long each step took to load and how many blobstore bytes it
+/// read, grouped by the EdgeType that was followed to reach it.  Steps whose
+/// target key does not exist are counted apart from steps that failed to
+/// load, as only the former indicate corruption.
+#[derive(Clone)]
+pub struct EdgeLoadStats {
+    logger: Logger,
+    subcommand_stats_key: &'static str,
+    repo_stats_key: String,
+    // Only accumulated if reporting was requested.  Has an entry for every
+    // EdgeType so steps can update it without locking.
+    by_edge_type: Option<Arc<HashMap<EdgeType, EdgeLoadSummary>>>,
+}
+
+impl EdgeLoadStats {
+    pub fn new(
+        logger: Logger,
+        subcommand_stats_key: &'static str,
+        repo_stats_key: String,
+        report: bool,
+    ) -> Self {
+        let by_edge_type = report.then(|| {
+            Arc::new(
+                EdgeType::iter()
+                    .map(|edge_type| (edge_type, EdgeLoadSummary::default()))
+                    .collect(),
+            )
+        });
+        Self {
+            logger,
+            subcommand_stats_key,
+            repo_stats_key,
+            by_edge_type,
+        }
+    }
+
+    pub fn record(&self, edge_type: EdgeType, latency: Duration, bytes: u64) {
+        let key = (
+            self.subcommand_stats_key,
+            self.repo_stats_key.clone(),
+            edge_type.into(),
+        );
+        STATS::edge_load_latency_ms.add_value(latency.as_millis() as i64, key.clone());
+        STATS::edge_load_bytes.add_value(bytes as i64, key);
+
+        if let Some(summary) = self.summary(edge_type) {
+            summary.latency_us.add(latency.as_micros() as u64);
+            summary.bytes.add(bytes);
+        }
+    }
+
+    fn summary(&self, edge_type: EdgeType) -> Option<&EdgeLoadSummary> {
+        self.by_edge_type
+            .as_ref()
+            .and_then(|by_edge_type| by_edge_type.get(&edge_type))
+    }
+
+    /// Record that a step along this edge type is being retried after a
+    /// transient error.
+    pub fn record_retry(&self, edge_type: EdgeType) {
+        STATS::edge_load_retries.add_value(
+            1,
+            (
+                self.subcommand_stats_key,
+                self.repo_stats_key.clone(),
+                edge_type.into(),
+            ),
+        );
+    }
+
+    /// Record that a step along this edge type found its target key does not
+    /// exist.
+    pub fn record_missing_target(&self, edge_type: EdgeType) {
+        STATS::edge_load_missing_target.add_value(
+            1,
+            (
+                self.subcommand_stats_key,
+                self.repo_stats_key.clone(),
+                edge_type.into(),
+            ),
+        );
+        if let Some(summary) = self.summary(edge_type) {
+            summary.missing_target.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record that a step along this edge type failed to load, after any
+    /// retries.
+    pub fn record_load_error(&self, edge_type: EdgeType) {
+        STATS::edge_load_errors.add_value(
+            1,
+            (
+                self.subcommand_stats_key,
+                self.repo_stats_key.clone(),
+                edge_type.into(),
+            ),
+        );
+        if let Some(summary) = self.summary(edge_type) {
+            summary.load_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Log the cost of each edge type so far, most expensive first.  Only
+    /// logs if reporting was requested.
+    pub fn report(&self) {
+        let by_edge_type = match &self.by_edge_type {
+            Some(by_edge_type) => by_edge_type,
+            None => return,
+        };
+        let mut edge_types: Vec<_> = by_edge_type
+            .iter()
+            .filter(|(_, summary)| {
+                summary.latency_us.count() > 0
+                    || summary.missing_target.load(Ordering::Relaxed) > 0
+                    || summary.load_errors.load(Ordering::Relaxed) > 0
+            })
+            .collect();
+        edge_types.sort_by_key(|(edge_type, summary)| {
+            (
+                std::cmp::Reverse(summary.latency_us.sum()),
+                edge_type.to_string(),
+            )
+        });
+        for (edge_type, summary) in edge_types {
+            let latency = &summary.latency_us;
+            let bytes = &summary.bytes;
+            info!(
+                self.logger,
+                #log::EDGE_STATS,
+                "Edge:Loads,LatencyUs(Total,P50,P99,Max),Bytes(Total,P50,P99,Max),MissingTarget,LoadErrors {}:{},{},{},{},{},{},{},{},{},{},{}",
+                edge_type,
+                latency.count(),
+                latency.sum(),
+                latency.percentile(50),
+                latency.percentile(99),
+                latency.max(),
+                bytes.sum(),
+                bytes.percentile(50),
+                bytes.percentile(99),
+                bytes.max(),
+                summary.missing_target.load(Ordering::Relaxed),
+                summary.load_errors.load(Ordering::Relaxed),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use slog::o;
+
+    use super::*;
+
+    fn edge_load_stats(report: bool) -> EdgeLoadStats {
+        let logger = Logger::root(slog::Discard, o!());
+        EdgeLoadStats::new(logger, "test", "repo".to_string(), report)
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Log2Histogram::default();
+        assert_eq!(histogram.percentile(50), 0);
+        for value in [0, 1, 3, 100, 1000] {
+            histogram.add(value);
+        }
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.sum(), 1104);
+        assert_eq!(histogram.max(), 1000);
+        // 3 is in the [2, 4) bucket
+        assert_eq!(histogram.percentile(50), 3);
+        // The top bucket is capped at the max seen
+        assert_eq!(histogram.percentile(99), 1000);
+    }
+
+    #[test]
+    fn test_record_without_report() {
+        let stats = edge_load_stats(false);
+        stats.record(
+            EdgeType::HgManifestToHgFileNode,
+            Duration::from_micros(10),
+            100,
+        );
+        assert!(stats.by_edge_type.is_none());
+    }
+
+    #[test]
+    fn test_record_by_edge_type() {
+        let stats = edge_load_stats(true);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let stats = stats.clone();
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        stats.record(
+                            EdgeType::HgManifestToHgFileNode,
+                            Duration::from_micros(10),
+                            100,
+                        );
+                    }
+                });
+            }
+        });
+        stats.record(
+            EdgeType::FileContentToFileContentMetadataV2,
+            Duration::from_micros(5),
+            0,
+        );
+
+        let by_edge_type = stats.by_edge_type.as_ref().expect("reporting enabled");
+        let filenode = &by_edge_type[&EdgeType::HgManifestToHgFileNode];
+        assert_eq!(filenode.latency_us.count(), 400);
+        assert_eq!(filenode.latency_us.sum(), 4000);
+        assert_eq!(filenode.bytes.sum(), 40000);
+        let metadata = &by_edge_type[&EdgeType::FileContentToFileContentMetadataV2];
+        assert_eq!(metadata.latency_us.count(), 1);
+        assert_eq!(metadata.bytes.max(), 0);
+        let other = &by_edge_type[&EdgeType::HgManifestToChildHgManifest];
+        assert_eq!(other.latency_us.count(), 0);
+
+        stats.record_missing_target(EdgeType::HgManifestToHgFileNode);
+        stats.record_load_error(EdgeType::HgManifestToHgFileNode);
+        stats.record_load_error(EdgeType::HgManifestToHgFileNode);
+        assert_eq!(filenode.missing_target.load(Ordering::Relaxed), 1);
+        assert_eq!(filenode.load_errors.load(Ordering::Relaxed), 2);
+
+        // Reporting reads the accumulated stats
+        stats.report();
+    }
+}
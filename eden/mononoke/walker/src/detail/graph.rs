@@ -54,6 +54,7 @@ use mononoke_types::blame_v2::BlameV2;
 use mononoke_types::deleted_manifest_v2::DeletedManifestV2;
 use mononoke_types::fastlog_batch::FastlogBatch;
 use mononoke_types::fsnode::Fsnode;
+use mononoke_types::hash::GitSha1;
 use mononoke_types::skeleton_manifest::SkeletonManifest;
 use mononoke_types::unode::FileUnode;
 use mononoke_types::unode::ManifestUnode;
@@ -63,6 +64,7 @@ use mononoke_types::BlobstoreKey;
 use mononoke_types::BlobstoreValue;
 use mononoke_types::BonsaiChangeset;
 use mononoke_types::ChangesetId;
+use mononoke_types::ContentChunkId;
 use mononoke_types::ContentId;
 use mononoke_types::ContentMetadataV2;
 use mononoke_types::DeletedManifestV2Id;
@@ -75,6 +77,7 @@ use mononoke_types::MononokeId;
 use mononoke_types::NonRootMPath;
 use mononoke_types::RepoPath;
 use mononoke_types::SkeletonManifestId;
+use mononoke_types::Svnrev;
 use newfilenodes::PathHash;
 use phases::Phase;
 use repo_blobstore::RepoBlobstoreRef;
@@ -348,12 +351,14 @@ create_graph!(
             HgBonsaiMapping,
             HgChangeset,
             HgChangesetViaBonsai,
+            HgConvertRevision,
             HgManifest,
             HgFileEnvelope,
             HgFileNode,
             HgManifestFileNode,
             // Content
             FileContent,
+            FileContentChunk,
             FileContentMetadataV2,
             AliasContentMapping,
             // Derived
@@ -407,9 +412,10 @@ create_graph!(
     (
         HgChangeset,
         ChangesetKey<HgChangesetId>,
-        [HgParent(HgChangesetViaBonsai), HgManifest, HgManifestFileNode]
+        [HgParent(HgChangesetViaBonsai), HgManifest, HgManifestFileNode, HgConvertRevision]
     ),
     (HgChangesetViaBonsai, ChangesetKey<HgChangesetId>, [HgChangeset]),
+    (HgConvertRevision, ChangesetKey<HgChangesetId>, []),
     (
         HgManifest,
         PathKey<HgManifestId>,
@@ -437,7 +443,8 @@ create_graph!(
         ]
     ),
     // Content
-    (FileContent, ContentId, [FileContentMetadataV2]),
+    (FileContent, ContentId, [FileContentMetadataV2, Chunk(FileContentChunk)]),
+    (FileContentChunk, ContentChunkId, []),
     (
         FileContentMetadataV2,
         ContentId,
@@ -540,12 +547,14 @@ impl NodeType {
             NodeType::HgBonsaiMapping => Some(MappedHgChangesetId::NAME),
             NodeType::HgChangeset => Some(MappedHgChangesetId::NAME),
             NodeType::HgChangesetViaBonsai => Some(MappedHgChangesetId::NAME),
+            NodeType::HgConvertRevision => Some(MappedHgChangesetId::NAME),
             NodeType::HgManifest => Some(MappedHgChangesetId::NAME),
             NodeType::HgFileEnvelope => Some(MappedHgChangesetId::NAME),
             NodeType::HgFileNode => Some(FilenodesOnlyPublic::NAME),
             NodeType::HgManifestFileNode => Some(FilenodesOnlyPublic::NAME),
             // Content
             NodeType::FileContent => None,
+            NodeType::FileContentChunk => None,
             NodeType::FileContentMetadataV2 => None,
             NodeType::AliasContentMapping => None,
             // Derived data
@@ -587,12 +596,14 @@ impl NodeType {
             NodeType::HgBonsaiMapping => false,
             NodeType::HgChangeset => false,
             NodeType::HgChangesetViaBonsai => false,
+            NodeType::HgConvertRevision => false,
             NodeType::HgManifest => true,
             NodeType::HgFileEnvelope => true,
             NodeType::HgFileNode => true,
             NodeType::HgManifestFileNode => true,
             // Content
             NodeType::FileContent => true,
+            NodeType::FileContentChunk => false,
             NodeType::FileContentMetadataV2 => true,
             NodeType::AliasContentMapping => true,
             // Derived Data
@@ -812,14 +823,17 @@ impl fmt::Display for EdgeType {
 
 /// File content gets a special two-state content so we can chose when to read the data
 pub enum FileContentData {
-    ContentStream(BoxStream<'static, Result<FileBytes, Error>>),
+    /// The file's content, and its size in bytes.
+    ContentStream(BoxStream<'static, Result<FileBytes, Error>>, u64),
     Consumed(usize),
 }
 
 impl fmt::Debug for FileContentData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            FileContentData::ContentStream(_s) => write!(f, "FileContentData::ContentStream(_)"),
+            FileContentData::ContentStream(_s, size) => {
+                write!(f, "FileContentData::ContentStream(_, {})", size)
+            }
             FileContentData::Consumed(s) => write!(f, "FileContentData::Consumed({})", s),
         }
     }
@@ -834,6 +848,10 @@ pub enum NodeData {
     MissingAsData(Node),
     // Node has an invalid hash
     HashValidationFailureAsData(Node),
+    // Node is known to be bad, so wasn't loaded
+    SkippedKnownBadAsData(Node),
+    // Node is redacted, so its content can't be loaded
+    RedactedAsData(Node),
     NotRequired,
     OutsideChunk,
     // Bonsai
@@ -846,12 +864,14 @@ pub enum NodeData {
     HgBonsaiMapping(Option<ChangesetId>),
     HgChangeset(HgBlobChangeset),
     HgChangesetViaBonsai(HgChangesetId),
+    HgConvertRevision(Option<ConvertRevision>),
     HgManifest(HgBlobManifest),
     HgFileEnvelope(HgFileEnvelope),
     HgFileNode(Option<FilenodeInfo>),
     HgManifestFileNode(Option<FilenodeInfo>),
     // Content
     FileContent(FileContentData),
+    FileContentChunk(u64),
     FileContentMetadataV2(Option<ContentMetadataV2>),
     AliasContentMapping(ContentId),
     // Derived data
@@ -874,6 +894,30 @@ pub enum NodeData {
     UnodeMapping(Option<ManifestUnodeId>),
 }
 
+/// The origin an imported changeset's convert_revision extra points at,
+/// alongside what the repo's mapping for that origin has for the changeset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ConvertRevision {
+    Git {
+        extra: GitSha1,
+        mapped: Option<GitSha1>,
+    },
+    Svn {
+        extra: Svnrev,
+        mapped: Option<Svnrev>,
+    },
+}
+
+impl ConvertRevision {
+    /// Whether the mapping agrees with the extra.
+    pub fn is_mapped(&self) -> bool {
+        match self {
+            ConvertRevision::Git { extra, mapped } => mapped.as_ref() == Some(extra),
+            ConvertRevision::Svn { extra, mapped } => mapped.as_ref() == Some(extra),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SqlShardInfo {
     pub filenodes: SqlTierInfo,
@@ -905,6 +949,7 @@ impl Node {
             Node::HgBonsaiMapping(_) => Some(SqlShard::Metadata),
             Node::HgChangeset(_) => None,
             Node::HgChangesetViaBonsai(_) => Some(SqlShard::Metadata),
+            Node::HgConvertRevision(_) => Some(SqlShard::Metadata),
             Node::HgManifest(PathKey { id: _, path: _ }) => None,
             Node::HgFileEnvelope(_) => None,
             Node::HgFileNode(PathKey { id: _, path }) => {
@@ -925,6 +970,7 @@ impl Node {
             }
             // Content
             Node::FileContent(_) => None,
+            Node::FileContentChunk(_) => None,
             Node::FileContentMetadataV2(_) => None,
             Node::AliasContentMapping(_) => None,
             // Derived data
@@ -961,12 +1007,14 @@ impl Node {
             Node::HgBonsaiMapping(k) => k.blobstore_key(),
             Node::HgChangeset(k) => k.blobstore_key(),
             Node::HgChangesetViaBonsai(k) => k.blobstore_key(),
+            Node::HgConvertRevision(k) => k.blobstore_key(),
             Node::HgManifest(PathKey { id, path: _ }) => id.blobstore_key(),
             Node::HgFileEnvelope(k) => k.blobstore_key(),
             Node::HgFileNode(PathKey { id, path: _ }) => id.blobstore_key(),
             Node::HgManifestFileNode(PathKey { id, path: _ }) => id.blobstore_key(),
             // Content
             Node::FileContent(k) => k.blobstore_key(),
+            Node::FileContentChunk(k) => k.blobstore_key(),
             Node::FileContentMetadataV2(k) => k.blobstore_key(),
             Node::AliasContentMapping(k) => k.0.blobstore_key(),
             // Derived data
@@ -1003,12 +1051,14 @@ impl Node {
             Node::HgBonsaiMapping(_) => None,
             Node::HgChangeset(_) => None,
             Node::HgChangesetViaBonsai(_) => None,
+            Node::HgConvertRevision(_) => None,
             Node::HgManifest(PathKey { id: _, path }) => Some(path),
             Node::HgFileEnvelope(_) => None,
             Node::HgFileNode(PathKey { id: _, path }) => Some(path),
             Node::HgManifestFileNode(PathKey { id: _, path }) => Some(path),
             // Content
             Node::FileContent(_) => None,
+            Node::FileContentChunk(_) => None,
             Node::FileContentMetadataV2(_) => None,
             Node::AliasContentMapping(_) => None,
             // Derived data
@@ -1046,12 +1096,14 @@ impl Node {
             Node::HgBonsaiMapping(k) => Some(k.sampling_fingerprint()),
             Node::HgChangeset(k) => Some(k.sampling_fingerprint()),
             Node::HgChangesetViaBonsai(k) => Some(k.sampling_fingerprint()),
+            Node::HgConvertRevision(k) => Some(k.sampling_fingerprint()),
             Node::HgManifest(PathKey { id, path: _ }) => Some(id.sampling_fingerprint()),
             Node::HgFileEnvelope(k) => Some(k.sampling_fingerprint()),
             Node::HgFileNode(PathKey { id, path: _ }) => Some(id.sampling_fingerprint()),
             Node::HgManifestFileNode(PathKey { id, path: _ }) => Some(id.sampling_fingerprint()),
             // Content
             Node::FileContent(k) => Some(k.sampling_fingerprint()),
+            Node::FileContentChunk(k) => Some(k.sampling_fingerprint()),
             Node::FileContentMetadataV2(k) => Some(k.sampling_fingerprint()),
             Node::AliasContentMapping(k) => Some(k.0.sampling_fingerprint()),
             // Derived data
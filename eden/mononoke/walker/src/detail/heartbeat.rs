@@ -0,0 +1,181 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Error;
+use fbinit::FacebookInit;
+use serde::Serialize;
+use slog::error;
+use slog::warn;
+use slog::Logger;
+use stats::prelude::*;
+
+define_stats! {
+    prefix = "mononoke.walker";
+    heartbeat_seq: singleton_counter("heartbeat.seq"),
+    heartbeat_walked: singleton_counter("heartbeat.walked"),
+    heartbeat_secs_since_progress: singleton_counter("heartbeat.secs_since_progress"),
+}
+
+/// Exit code used when the walk aborts itself after stalling, so that
+/// schedulers can tell a stall apart from other failures.
+const STALLED_EXIT_CODE: i32 = 75;
+
+#[derive(Clone, Debug)]
+pub struct HeartbeatOptions {
+    /// File to write the latest heartbeat record to, if any.
+    pub file: Option<PathBuf>,
+    pub interval: Duration,
+    /// Abort the process if no steps are walked for this long while a walk
+    /// is running.
+    pub max_stall: Option<Duration>,
+}
+
+/// Heartbeat record written on each interval.  The sequence number increases
+/// on every record, so a record that stops changing means the process is
+/// gone, while `seconds_since_progress` growing means the walk is stuck.
+#[derive(Debug, Serialize)]
+pub struct HeartbeatRecord {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub pid: u32,
+    pub walked: u64,
+    pub running_walks: usize,
+    pub seconds_since_progress: u64,
+    pub stalled: bool,
+}
+
+#[derive(Default)]
+struct HeartbeatState {
+    walked: AtomicU64,
+    running_walks: AtomicUsize,
+}
+
+/// Counts steps walked across all repos in a job, and periodically reports
+/// them as heartbeat records.  The reporting task stops once every clone of
+/// the heartbeat has been dropped.
+#[derive(Clone, Default)]
+pub struct Heartbeat {
+    state: Arc<HeartbeatState>,
+}
+
+/// Marks a walk as running until dropped. Time spent between tailing runs
+/// is not counted as stalled.
+pub struct RunningWalk {
+    state: Arc<HeartbeatState>,
+}
+
+impl Drop for RunningWalk {
+    fn drop(&mut self) {
+        self.state.running_walks.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Heartbeat {
+    pub fn record_step(&self) {
+        self.state.walked.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn start_walk(&self) -> RunningWalk {
+        self.state.running_walks.fetch_add(1, Ordering::Relaxed);
+        RunningWalk {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Start reporting heartbeats in the background.
+    pub fn spawn_reporter(&self, fb: FacebookInit, logger: Logger, options: HeartbeatOptions) {
+        let state = Arc::downgrade(&self.state);
+        tokio::spawn(report_heartbeats(fb, logger, options, state));
+    }
+}
+
+async fn report_heartbeats(
+    fb: FacebookInit,
+    logger: Logger,
+    options: HeartbeatOptions,
+    state: Weak<HeartbeatState>,
+) {
+    let mut seq = 0;
+    let mut last_walked = 0;
+    let mut last_progress = Instant::now();
+    loop {
+        tokio::time::sleep(options.interval).await;
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let walked = state.walked.load(Ordering::Relaxed);
+        let running_walks = state.running_walks.load(Ordering::Relaxed);
+        if walked != last_walked || running_walks == 0 {
+            last_walked = walked;
+            last_progress = Instant::now();
+        }
+        let since_progress = last_progress.elapsed();
+        let stalled = options
+            .max_stall
+            .map_or(false, |max_stall| since_progress > max_stall);
+
+        seq += 1;
+        let record = HeartbeatRecord {
+            seq,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            pid: std::process::id(),
+            walked,
+            running_walks,
+            seconds_since_progress: since_progress.as_secs(),
+            stalled,
+        };
+        STATS::heartbeat_seq.set_value(fb, seq as i64);
+        STATS::heartbeat_walked.set_value(fb, walked as i64);
+        STATS::heartbeat_secs_since_progress.set_value(fb, since_progress.as_secs() as i64);
+        if let Some(path) = options.file.as_ref() {
+            if let Err(e) = write_record(path, &record).await {
+                warn!(
+                    logger,
+                    "Failed to write heartbeat to {}: {:?}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+
+        if stalled {
+            error!(
+                logger,
+                "No progress for {} seconds after walking {} steps, aborting",
+                since_progress.as_secs(),
+                walked
+            );
+            std::process::exit(STALLED_EXIT_CODE);
+        }
+    }
+}
+
+/// Replace the heartbeat file atomically, so readers never see a partial
+/// record.
+async fn write_record(path: &Path, record: &HeartbeatRecord) -> Result<(), Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    tokio::fs::write(&tmp_path, serde_json::to_vec(record)?).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
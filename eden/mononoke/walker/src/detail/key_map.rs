@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use anyhow::Error;
+use metaconfig_types::BlobstoreId;
+use serde::Serialize;
+
+use crate::detail::graph::NodeType;
+
+/// Record written for each blobstore key loaded for a sampled node.
+#[derive(Serialize)]
+struct SampleKeyRecord<'a> {
+    repo: &'a str,
+    path: Option<&'a str>,
+    node_type: &'a str,
+    blobstore_id: Option<u64>,
+    blobstore_key: &'a str,
+}
+
+/// Maps the repo path and type of sampled nodes to the blobstore keys
+/// loaded for them, so that hot paths can be matched to hot key ranges in
+/// the storage backend.  Records are appended to a file as JSON lines, so
+/// repos walked by separate shards can share it.
+#[derive(Clone)]
+pub struct SampleKeyMap {
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl SampleKeyMap {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open sample key map {}", path.display()))?;
+        Ok(Self {
+            writer: Arc::new(Mutex::new(BufWriter::new(file))),
+        })
+    }
+
+    pub fn record(
+        &self,
+        repo: &str,
+        path: Option<&str>,
+        node_type: NodeType,
+        blobstore_id: Option<BlobstoreId>,
+        blobstore_key: &str,
+    ) -> Result<(), Error> {
+        let record = SampleKeyRecord {
+            repo,
+            path,
+            node_type: node_type.as_ref(),
+            blobstore_id: blobstore_id.map(u64::from),
+            blobstore_key,
+        };
+        let mut writer = self.writer.lock().expect("lock poisoned");
+        serde_json::to_writer(&mut *writer, &record)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), Error> {
+        self.writer
+            .lock()
+            .expect("lock poisoned")
+            .flush()
+            .context("Failed to flush sample key map")
+    }
+}
@@ -0,0 +1,375 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Error;
+use mononoke_types::RepositoryId;
+use mononoke_types::Timestamp;
+use slog::warn;
+use slog::Logger;
+use sql_construct::SqlConstruct;
+use sql_construct::SqlConstructFromMetadataDatabaseConfig;
+use sql_ext::mononoke_queries;
+use sql_ext::SqlConnections;
+use tokio::task::JoinHandle;
+
+use crate::detail::log;
+
+/// Result of trying to lease a chunk.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LeaseOutcome {
+    /// The chunk was free, or was completed too long ago to still count.
+    Acquired,
+    /// The chunk's previous owner stopped renewing its lease.
+    TakenOver { previous_owner: String },
+    /// Another walker holds the lease.
+    Held { owner: String },
+    /// Another walker completed the chunk recently.
+    Completed,
+}
+
+/// Leases on chunks of a walk, shared between walkers through SQL so that
+/// several hosts can divide a walk of one repo between them.  Each chunk is
+/// identified by its bounds, so walkers sharing a lease name must use the
+/// same chunking parameters.
+#[derive(Clone)]
+pub struct ChunkLeases {
+    pub lease_name: String,
+    owner: String,
+    lease_duration: Duration,
+    rewalk_interval: Duration,
+    sql_leases: Arc<SqlChunkLeases>,
+}
+
+fn offset(timestamp: Timestamp, nanos: i64) -> Timestamp {
+    Timestamp::from_timestamp_nanos(timestamp.timestamp_nanos().saturating_add(nanos))
+}
+
+impl ChunkLeases {
+    pub fn new(
+        lease_name: String,
+        owner: String,
+        lease_duration: Duration,
+        rewalk_interval: Duration,
+        sql_leases: SqlChunkLeases,
+    ) -> Self {
+        Self {
+            lease_name,
+            owner,
+            lease_duration,
+            rewalk_interval,
+            sql_leases: Arc::new(sql_leases),
+        }
+    }
+
+    /// Try to lease the chunk with the given bounds.  Chunks are available if
+    /// nobody has leased them, if their owner's lease has expired, or if they
+    /// were completed more than the rewalk interval ago.
+    pub async fn acquire(
+        &self,
+        repo_id: RepositoryId,
+        lower_bound: u64,
+        upper_bound: u64,
+    ) -> Result<LeaseOutcome, Error> {
+        let now = Timestamp::now();
+        let expires = offset(now, self.lease_duration.as_nanos() as i64);
+        let inserted = InsertLease::query(
+            &self.sql_leases.connections.write_connection,
+            &[(
+                &repo_id,
+                &self.lease_name,
+                &lower_bound,
+                &upper_bound,
+                &self.owner,
+                &now,
+                &expires,
+            )],
+        )
+        .await?;
+        if inserted.affected_rows() == 1 {
+            return Ok(LeaseOutcome::Acquired);
+        }
+
+        let existing = SelectLease::query(
+            &self.sql_leases.connections.read_master_connection,
+            &repo_id,
+            &self.lease_name.as_str(),
+            &lower_bound,
+            &upper_bound,
+        )
+        .await?;
+        let (previous_owner, previous_complete) = match existing.into_iter().next() {
+            Some((owner, _expires, complete)) => (owner, complete),
+            None => bail!(
+                "Lease {} on chunk ({}, {}) disappeared while acquiring it",
+                self.lease_name,
+                lower_bound,
+                upper_bound
+            ),
+        };
+
+        let stale_before = offset(now, -(self.rewalk_interval.as_nanos() as i64));
+        let taken = TakeoverLease::query(
+            &self.sql_leases.connections.write_connection,
+            &repo_id,
+            &self.lease_name,
+            &lower_bound,
+            &upper_bound,
+            &self.owner,
+            &now,
+            &expires,
+            &stale_before,
+        )
+        .await?;
+        Ok(match (taken.affected_rows() == 1, previous_complete) {
+            (true, Some(_)) => LeaseOutcome::Acquired,
+            (true, None) => LeaseOutcome::TakenOver { previous_owner },
+            (false, Some(_)) => LeaseOutcome::Completed,
+            (false, None) => LeaseOutcome::Held {
+                owner: previous_owner,
+            },
+        })
+    }
+
+    /// Extend our lease on a chunk.  Returns false if the lease was lost to
+    /// another walker.
+    pub async fn renew(
+        &self,
+        repo_id: RepositoryId,
+        lower_bound: u64,
+        upper_bound: u64,
+    ) -> Result<bool, Error> {
+        let expires = offset(Timestamp::now(), self.lease_duration.as_nanos() as i64);
+        let res = RenewLease::query(
+            &self.sql_leases.connections.write_connection,
+            &repo_id,
+            &self.lease_name,
+            &lower_bound,
+            &upper_bound,
+            &self.owner,
+            &expires,
+        )
+        .await?;
+        Ok(res.affected_rows() == 1)
+    }
+
+    /// Mark a chunk we hold as completed.
+    pub async fn complete(
+        &self,
+        repo_id: RepositoryId,
+        lower_bound: u64,
+        upper_bound: u64,
+    ) -> Result<(), Error> {
+        CompleteLease::query(
+            &self.sql_leases.connections.write_connection,
+            &repo_id,
+            &self.lease_name,
+            &lower_bound,
+            &upper_bound,
+            &self.owner,
+            &Timestamp::now(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Renew the lease on a chunk in the background until the returned guard
+    /// is dropped.
+    pub fn keep_alive(
+        &self,
+        logger: Logger,
+        repo_id: RepositoryId,
+        lower_bound: u64,
+        upper_bound: u64,
+    ) -> LeaseKeepAlive {
+        let leases = self.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(leases.lease_duration / 3).await;
+                match leases.renew(repo_id, lower_bound, upper_bound).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!(logger, #log::CHUNKING, "Lost lease {} on chunk ({}, {}), it may be walked again by another walker", leases.lease_name, lower_bound, upper_bound);
+                        return;
+                    }
+                    Err(e) => {
+                        warn!(logger, #log::CHUNKING, "Failed to renew lease {} on chunk ({}, {}): {:?}", leases.lease_name, lower_bound, upper_bound, e);
+                    }
+                }
+            }
+        });
+        LeaseKeepAlive { handle }
+    }
+}
+
+/// Renews a chunk's lease in the background until dropped, so renewal stops
+/// however the walk of the chunk ends.
+pub struct LeaseKeepAlive {
+    handle: JoinHandle<()>,
+}
+
+impl Drop for LeaseKeepAlive {
+    fn drop(&mut self) {
+        self.handle.abort()
+    }
+}
+
+impl fmt::Debug for ChunkLeases {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkLeases")
+            .field("lease_name", &self.lease_name)
+            .field("owner", &self.owner)
+            .field("lease_duration", &self.lease_duration)
+            .field("rewalk_interval", &self.rewalk_interval)
+            .finish()
+    }
+}
+
+pub struct SqlChunkLeases {
+    connections: SqlConnections,
+}
+
+impl SqlConstruct for SqlChunkLeases {
+    const LABEL: &'static str = "walker_chunk_leases";
+
+    const CREATION_QUERY: &'static str =
+        include_str!("../../schemas/sqlite-walker_chunk_leases.sql");
+
+    fn from_sql_connections(connections: SqlConnections) -> Self {
+        Self { connections }
+    }
+}
+
+impl SqlConstructFromMetadataDatabaseConfig for SqlChunkLeases {}
+
+mononoke_queries! {
+    read SelectLease(
+        repo_id: RepositoryId,
+        lease_name: &str,
+        lower_bound: u64,
+        upper_bound: u64,
+    ) -> (String, Timestamp, Option<Timestamp>) {
+        "SELECT owner, expire_timestamp, complete_timestamp
+        FROM walker_chunk_leases
+        WHERE repo_id={repo_id} AND lease_name={lease_name} AND lower_bound={lower_bound} AND upper_bound={upper_bound}"
+    }
+
+    write InsertLease(
+        values: (
+            repo_id: RepositoryId,
+            lease_name: String,
+            lower_bound: u64,
+            upper_bound: u64,
+            owner: String,
+            acquire_timestamp: Timestamp,
+            expire_timestamp: Timestamp,
+        ),
+    ) {
+        insert_or_ignore,
+        "{insert_or_ignore} INTO walker_chunk_leases
+         (repo_id, lease_name, lower_bound, upper_bound, owner, acquire_timestamp, expire_timestamp)
+         VALUES {values}"
+    }
+
+    write TakeoverLease(
+        repo_id: RepositoryId,
+        lease_name: String,
+        lower_bound: u64,
+        upper_bound: u64,
+        owner: String,
+        now: Timestamp,
+        expire_timestamp: Timestamp,
+        stale_before: Timestamp,
+    ) {
+        none,
+        "UPDATE walker_chunk_leases
+        SET owner={owner}, acquire_timestamp={now}, expire_timestamp={expire_timestamp}, complete_timestamp=NULL
+        WHERE repo_id={repo_id} AND lease_name={lease_name} AND lower_bound={lower_bound} AND upper_bound={upper_bound}
+        AND ((complete_timestamp IS NULL AND expire_timestamp < {now}) OR complete_timestamp < {stale_before})"
+    }
+
+    write RenewLease(
+        repo_id: RepositoryId,
+        lease_name: String,
+        lower_bound: u64,
+        upper_bound: u64,
+        owner: String,
+        expire_timestamp: Timestamp,
+    ) {
+        none,
+        "UPDATE walker_chunk_leases
+        SET expire_timestamp={expire_timestamp}
+        WHERE repo_id={repo_id} AND lease_name={lease_name} AND lower_bound={lower_bound} AND upper_bound={upper_bound}
+        AND owner={owner} AND complete_timestamp IS NULL"
+    }
+
+    write CompleteLease(
+        repo_id: RepositoryId,
+        lease_name: String,
+        lower_bound: u64,
+        upper_bound: u64,
+        owner: String,
+        complete_timestamp: Timestamp,
+    ) {
+        none,
+        "UPDATE walker_chunk_leases
+        SET complete_timestamp={complete_timestamp}
+        WHERE repo_id={repo_id} AND lease_name={lease_name} AND lower_bound={lower_bound} AND upper_bound={upper_bound}
+        AND owner={owner}"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_lease_takeover(_fb: FacebookInit) -> Result<(), Error> {
+        let sql_leases = Arc::new(SqlChunkLeases::with_sqlite_in_memory()?);
+        let make_leases = |owner: &str, lease_duration| ChunkLeases {
+            lease_name: "test_lease".to_string(),
+            owner: owner.to_string(),
+            lease_duration,
+            rewalk_interval: Duration::from_secs(3600),
+            sql_leases: sql_leases.clone(),
+        };
+        // The first walker's leases expire immediately, as if it had died.
+        let first = make_leases("first", Duration::ZERO);
+        let second = make_leases("second", Duration::from_secs(3600));
+        let repo_id = RepositoryId::new(123);
+
+        assert_eq!(first.acquire(repo_id, 0, 8).await?, LeaseOutcome::Acquired);
+        assert_eq!(
+            second.acquire(repo_id, 0, 8).await?,
+            LeaseOutcome::TakenOver {
+                previous_owner: "first".to_string()
+            }
+        );
+        assert!(!first.renew(repo_id, 0, 8).await?);
+        assert!(second.renew(repo_id, 0, 8).await?);
+        assert_eq!(
+            first.acquire(repo_id, 0, 8).await?,
+            LeaseOutcome::Held {
+                owner: "second".to_string()
+            }
+        );
+
+        second.complete(repo_id, 0, 8).await?;
+        assert_eq!(first.acquire(repo_id, 0, 8).await?, LeaseOutcome::Completed);
+
+        // Other chunks are unaffected.
+        assert_eq!(first.acquire(repo_id, 8, 16).await?, LeaseOutcome::Acquired);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,595 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::format_err;
+use anyhow::Error;
+use blobrepo::BlobRepo;
+use blobstore::Loadable;
+use blobstore::LoadableError;
+use bonsai_hg_mapping::BonsaiHgMappingRef;
+use commit_graph::CommitGraphRef;
+use context::CoreContext;
+use filenodes::FilenodeInfo;
+use filenodes::FilenodesRef;
+use filenodes::PreparedFilenode;
+use filenodes_derivation::FilenodesOnlyPublic;
+use manifest::ManifestOps;
+use mercurial_types::HgChangesetId;
+use mercurial_types::HgFileNodeId;
+use mononoke_types::ChangesetId;
+use mononoke_types::NonRootMPath;
+use mononoke_types::RepoPath;
+use repo_blobstore::RepoBlobstoreRef;
+use repo_derived_data::RepoDerivedDataRef;
+use slog::info;
+use slog::Logger;
+
+use crate::detail::repair_lease::RepairLeases;
+
+#[derive(Clone, Copy, Debug)]
+pub struct LinknodeRepairOptions {
+    pub dry_run: bool,
+    pub batch_size: usize,
+    pub max_search_depth: u64,
+    pub max_checks: u64,
+}
+
+/// A filenode whose linknode may be wrong, as the walk found it in the
+/// manifest of a different changeset.
+#[derive(Debug)]
+pub struct LinknodeCandidate {
+    pub path: NonRootMPath,
+    pub info: FilenodeInfo,
+    pub via: HgChangesetId,
+}
+
+#[derive(Default)]
+struct RepairStats {
+    checked: AtomicU64,
+    repaired: AtomicU64,
+    unrepairable: AtomicU64,
+    skipped: AtomicU64,
+}
+
+/// Rewrites filenode linknodes that point at a changeset that doesn't
+/// contain the filenode to the changeset that introduced it.  The
+/// introducing changeset is the oldest ancestor of the changeset the walk
+/// found the filenode in that has it while none of its parents do.  At most
+/// `max_checks` linknodes are checked, as each check costs a manifest
+/// lookup.  Repairs are written in batches, or only logged for a dry run.
+/// Each repair is written under the derived data lease for filenodes of the
+/// introducing changeset, and skipped if another walker or the derived
+/// data tailer holds it.
+#[derive(Clone)]
+pub struct LinknodeRepair {
+    options: LinknodeRepairOptions,
+    pending: Arc<Mutex<Vec<(String, PreparedFilenode)>>>,
+    stats: Arc<RepairStats>,
+    leases: RepairLeases,
+}
+
+impl LinknodeRepair {
+    pub fn new(options: LinknodeRepairOptions) -> Self {
+        Self {
+            options,
+            pending: Arc::new(Mutex::new(Vec::new())),
+            stats: Arc::new(RepairStats::default()),
+            leases: RepairLeases::default(),
+        }
+    }
+
+    /// Check the candidate's linknode, and queue a repair if it is wrong.
+    pub async fn check(
+        &self,
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        candidate: LinknodeCandidate,
+    ) -> Result<(), Error> {
+        let under_limit = self
+            .stats
+            .checked
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |checked| {
+                (checked < self.options.max_checks).then_some(checked + 1)
+            })
+            .is_ok();
+        if !under_limit {
+            self.stats.skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let LinknodeCandidate { path, info, via } = candidate;
+        if has_filenode(ctx, repo, info.linknode, &path, info.filenode).await? {
+            return Ok(());
+        }
+
+        let introducing = introducing_changeset(
+            ctx,
+            repo,
+            via,
+            &path,
+            info.filenode,
+            self.options.max_search_depth,
+        )
+        .await?;
+        let linknode = match introducing {
+            Some(linknode) => linknode,
+            None => {
+                info!(
+                    ctx.logger(),
+                    "Can't repair linknode {} of {} filenode {}, no introducing changeset within {} of {}",
+                    info.linknode,
+                    path,
+                    info.filenode,
+                    self.options.max_search_depth,
+                    via,
+                );
+                self.stats.unrepairable.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+        };
+
+        info!(
+            ctx.logger(),
+            "{} linknode of {} filenode {} from {} to {}",
+            if self.options.dry_run {
+                "Would repair"
+            } else {
+                "Repairing"
+            },
+            path,
+            info.filenode,
+            info.linknode,
+            linknode,
+        );
+        if self.options.dry_run {
+            self.stats.repaired.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let lease_key = filenodes_lease_key(ctx, repo, linknode).await?;
+        let batch = {
+            let mut pending = self.pending.lock().expect("lock poisoned");
+            pending.push((
+                lease_key,
+                PreparedFilenode {
+                    path: RepoPath::FilePath(path),
+                    info: FilenodeInfo { linknode, ..info },
+                },
+            ));
+            if pending.len() >= self.options.batch_size {
+                mem::take(&mut *pending)
+            } else {
+                Vec::new()
+            }
+        };
+        self.write_batch(ctx, repo, batch).await
+    }
+
+    /// Write any repairs still queued.
+    pub async fn flush(&self, ctx: &CoreContext, repo: &BlobRepo) -> Result<(), Error> {
+        let batch = mem::take(&mut *self.pending.lock().expect("lock poisoned"));
+        self.write_batch(ctx, repo, batch).await
+    }
+
+    // Write the repairs whose leases can be taken, skipping those leased
+    // by another writer. Only repairs that were written count as repaired.
+    async fn write_batch(
+        &self,
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        batch: Vec<(String, PreparedFilenode)>,
+    ) -> Result<(), Error> {
+        let lease = repo.repo_derived_data().lease();
+        let mut guards = Vec::new();
+        let mut filenodes = Vec::new();
+        for (lease_key, filenode) in batch {
+            if let Some(guard) = self.leases.try_acquire(ctx, lease, lease_key).await? {
+                guards.push(guard);
+                filenodes.push(filenode);
+            }
+        }
+        let count = filenodes.len() as u64;
+        let written = write_filenodes(ctx, repo, filenodes).await;
+        for guard in guards {
+            guard.release().await;
+        }
+        if written.is_ok() {
+            self.stats.repaired.fetch_add(count, Ordering::Relaxed);
+        }
+        written
+    }
+
+    pub fn log_summary(&self, logger: &Logger, repo_name: &str) {
+        info!(
+            logger,
+            "Linknode repair of {}{}: checked {}, repaired {}, unrepairable {}, skipped over limit {}, skipped as leased {}",
+            repo_name,
+            if self.options.dry_run {
+                " (dry run)"
+            } else {
+                ""
+            },
+            self.stats.checked.load(Ordering::Relaxed),
+            self.stats.repaired.load(Ordering::Relaxed),
+            self.stats.unrepairable.load(Ordering::Relaxed),
+            self.stats.skipped.load(Ordering::Relaxed),
+            self.leases.conflict_count(),
+        );
+        self.leases.log_conflicts(logger, "Linknode repair");
+    }
+}
+
+async fn write_filenodes(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    filenodes: Vec<PreparedFilenode>,
+) -> Result<(), Error> {
+    if filenodes.is_empty() {
+        return Ok(());
+    }
+    repo.filenodes()
+        .add_or_replace_filenodes(ctx, filenodes)
+        .await?
+        .do_not_handle_disabled_filenodes()
+}
+
+// The lease the derived data manager takes to derive filenodes for the
+// changeset, which writes the same filenodes as the repair.
+async fn filenodes_lease_key(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    hg_cs_id: HgChangesetId,
+) -> Result<String, Error> {
+    let cs_id = bonsai_changeset(ctx, repo, hg_cs_id).await?;
+    Ok(repo
+        .repo_derived_data()
+        .manager()
+        .lease_key::<FilenodesOnlyPublic>(cs_id))
+}
+
+async fn bonsai_changeset(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    hg_cs_id: HgChangesetId,
+) -> Result<ChangesetId, Error> {
+    repo.bonsai_hg_mapping()
+        .get_bonsai_from_hg(ctx, hg_cs_id)
+        .await?
+        .ok_or_else(|| format_err!("No bonsai changeset for {}", hg_cs_id))
+}
+
+// Whether the changeset's manifest has the filenode at the path.  A linknode
+// to a changeset that doesn't exist is treated as not having it.
+async fn has_filenode(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    cs_id: HgChangesetId,
+    path: &NonRootMPath,
+    filenode: HgFileNodeId,
+) -> Result<bool, Error> {
+    let changeset = match cs_id.load(ctx, repo.repo_blobstore()).await {
+        Ok(changeset) => changeset,
+        Err(LoadableError::Missing(_)) => return Ok(false),
+        Err(LoadableError::Error(e)) => return Err(e),
+    };
+    let entry = changeset
+        .manifestid()
+        .find_entry(
+            ctx.clone(),
+            repo.repo_blobstore().clone(),
+            path.clone().into(),
+        )
+        .await?;
+    Ok(entry
+        .and_then(|entry| entry.into_leaf())
+        .map_or(false, |(_file_type, id)| id == filenode))
+}
+
+// Search the ancestors that have the filenode at the path for those none
+// of whose parents have it, and return the oldest of them.  Gives up if
+// more than max_search_depth changesets would need to be searched.
+async fn introducing_changeset(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    from: HgChangesetId,
+    path: &NonRootMPath,
+    filenode: HgFileNodeId,
+    max_search_depth: u64,
+) -> Result<Option<HgChangesetId>, Error> {
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+    let mut introducing = Vec::new();
+    let mut searched = 0;
+    while let Some(current) = queue.pop_front() {
+        if searched == max_search_depth {
+            return Ok(None);
+        }
+        searched += 1;
+        let changeset = current.load(ctx, repo.repo_blobstore()).await?;
+        let mut introduced_here = true;
+        for parent in changeset.parents().into_iter() {
+            let parent = HgChangesetId::new(parent);
+            if has_filenode(ctx, repo, parent, path, filenode).await? {
+                introduced_here = false;
+                if seen.insert(parent) {
+                    queue.push_back(parent);
+                }
+            }
+        }
+        if introduced_here {
+            introducing.push(current);
+        }
+    }
+
+    let mut oldest = None;
+    for hg_cs_id in introducing {
+        let cs_id = bonsai_changeset(ctx, repo, hg_cs_id).await?;
+        let generation = repo
+            .commit_graph()
+            .changeset_generation(ctx, cs_id)
+            .await?
+            .value();
+        if oldest.map_or(true, |(oldest, _)| generation < oldest) {
+            oldest = Some((generation, hg_cs_id));
+        }
+    }
+    Ok(oldest.map(|(_, hg_cs_id)| hg_cs_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use filenodes::FilenodeResult;
+    use mercurial_derivation::DeriveHgChangeset;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+
+    struct Fixture {
+        repo: BlobRepo,
+        introducing: HgChangesetId,
+        head: HgChangesetId,
+        unrelated: HgChangesetId,
+        path: NonRootMPath,
+        info: FilenodeInfo,
+    }
+
+    // A file introduced by the first commit of a stack of three, with its
+    // filenodes derived, and an unrelated root commit.
+    async fn fixture(ctx: &CoreContext) -> Result<Fixture, Error> {
+        let repo: BlobRepo = test_repo_factory::build_empty(ctx.fb).await?;
+        let first = CreateCommitContext::new_root(ctx, &repo)
+            .add_file("dir/file", "content")
+            .commit()
+            .await?;
+        let second = CreateCommitContext::new(ctx, &repo, vec![first])
+            .add_file("other", "other")
+            .commit()
+            .await?;
+        let third = CreateCommitContext::new(ctx, &repo, vec![second])
+            .add_file("another", "another")
+            .commit()
+            .await?;
+        let unrelated = CreateCommitContext::new_root(ctx, &repo)
+            .add_file("unrelated", "unrelated")
+            .commit()
+            .await?;
+        repo.repo_derived_data()
+            .derive::<FilenodesOnlyPublic>(ctx, third)
+            .await?;
+
+        let introducing = repo.derive_hg_changeset(ctx, first).await?;
+        let head = repo.derive_hg_changeset(ctx, third).await?;
+        let unrelated = repo.derive_hg_changeset(ctx, unrelated).await?;
+        let path = NonRootMPath::new("dir/file")?;
+        let filenode = introducing
+            .load(ctx, repo.repo_blobstore())
+            .await?
+            .manifestid()
+            .find_entry(
+                ctx.clone(),
+                repo.repo_blobstore().clone(),
+                path.clone().into(),
+            )
+            .await?
+            .and_then(|entry| entry.into_leaf())
+            .map(|(_file_type, id)| id)
+            .ok_or_else(|| format_err!("file missing"))?;
+        let info = read_filenode(ctx, &repo, &path, filenode).await?;
+        assert_eq!(info.linknode, introducing);
+        Ok(Fixture {
+            repo,
+            introducing,
+            head,
+            unrelated,
+            path,
+            info,
+        })
+    }
+
+    async fn read_filenode(
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        path: &NonRootMPath,
+        filenode: HgFileNodeId,
+    ) -> Result<FilenodeInfo, Error> {
+        match repo
+            .filenodes()
+            .get_filenode(ctx, &RepoPath::FilePath(path.clone()), filenode)
+            .await?
+        {
+            FilenodeResult::Present(Some(info)) => Ok(info),
+            _ => Err(format_err!("filenode missing")),
+        }
+    }
+
+    // Point the filenode's linknode at the unrelated commit.
+    async fn break_linknode(ctx: &CoreContext, fixture: &Fixture) -> Result<FilenodeInfo, Error> {
+        let broken = FilenodeInfo {
+            linknode: fixture.unrelated,
+            ..fixture.info.clone()
+        };
+        write_filenodes(
+            ctx,
+            &fixture.repo,
+            vec![PreparedFilenode {
+                path: RepoPath::FilePath(fixture.path.clone()),
+                info: broken.clone(),
+            }],
+        )
+        .await?;
+        Ok(broken)
+    }
+
+    fn options() -> LinknodeRepairOptions {
+        LinknodeRepairOptions {
+            dry_run: false,
+            batch_size: 10,
+            max_search_depth: 10,
+            max_checks: 10,
+        }
+    }
+
+    #[fbinit::test]
+    async fn test_repair_linknode(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let fixture = fixture(&ctx).await?;
+        let broken = break_linknode(&ctx, &fixture).await?;
+        let filenode = fixture.info.filenode;
+        assert_eq!(
+            read_filenode(&ctx, &fixture.repo, &fixture.path, filenode)
+                .await?
+                .linknode,
+            fixture.unrelated
+        );
+
+        let repair = LinknodeRepair::new(options());
+        repair
+            .check(
+                &ctx,
+                &fixture.repo,
+                LinknodeCandidate {
+                    path: fixture.path.clone(),
+                    info: broken,
+                    via: fixture.head,
+                },
+            )
+            .await?;
+        repair.flush(&ctx, &fixture.repo).await?;
+
+        let repaired = read_filenode(&ctx, &fixture.repo, &fixture.path, filenode).await?;
+        assert_eq!(repaired, fixture.info);
+        assert_eq!(repair.stats.repaired.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_repair_skips_correct_linknode(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let fixture = fixture(&ctx).await?;
+
+        let repair = LinknodeRepair::new(options());
+        repair
+            .check(
+                &ctx,
+                &fixture.repo,
+                LinknodeCandidate {
+                    path: fixture.path.clone(),
+                    info: fixture.info.clone(),
+                    via: fixture.head,
+                },
+            )
+            .await?;
+        repair.flush(&ctx, &fixture.repo).await?;
+
+        assert_eq!(repair.stats.checked.load(Ordering::Relaxed), 1);
+        assert_eq!(repair.stats.repaired.load(Ordering::Relaxed), 0);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_repair_limits(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let fixture = fixture(&ctx).await?;
+        let broken = break_linknode(&ctx, &fixture).await?;
+        let candidate = || LinknodeCandidate {
+            path: fixture.path.clone(),
+            info: broken.clone(),
+            via: fixture.head,
+        };
+
+        // The introducing commit is two steps back from the head.
+        let repair = LinknodeRepair::new(LinknodeRepairOptions {
+            max_search_depth: 2,
+            ..options()
+        });
+        repair.check(&ctx, &fixture.repo, candidate()).await?;
+        assert_eq!(repair.stats.unrepairable.load(Ordering::Relaxed), 1);
+
+        // Checks over the limit are skipped.
+        let repair = LinknodeRepair::new(LinknodeRepairOptions {
+            max_checks: 1,
+            ..options()
+        });
+        repair.check(&ctx, &fixture.repo, candidate()).await?;
+        repair.check(&ctx, &fixture.repo, candidate()).await?;
+        assert_eq!(repair.stats.checked.load(Ordering::Relaxed), 1);
+        assert_eq!(repair.stats.skipped.load(Ordering::Relaxed), 1);
+
+        // A dry run doesn't write the repair.
+        let repair = LinknodeRepair::new(LinknodeRepairOptions {
+            dry_run: true,
+            ..options()
+        });
+        repair.check(&ctx, &fixture.repo, candidate()).await?;
+        repair.flush(&ctx, &fixture.repo).await?;
+        assert_eq!(repair.stats.repaired.load(Ordering::Relaxed), 1);
+        let info = read_filenode(&ctx, &fixture.repo, &fixture.path, fixture.info.filenode).await?;
+        assert_eq!(info.linknode, fixture.unrelated);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_repair_skips_leased(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let fixture = fixture(&ctx).await?;
+        let broken = break_linknode(&ctx, &fixture).await?;
+
+        // Another writer holds the lease on the introducing changeset.
+        let key = filenodes_lease_key(&ctx, &fixture.repo, fixture.info.linknode).await?;
+        let lease = fixture.repo.repo_derived_data().lease();
+        assert!(lease.try_add_put_lease(&key).await?);
+
+        let repair = LinknodeRepair::new(options());
+        repair
+            .check(
+                &ctx,
+                &fixture.repo,
+                LinknodeCandidate {
+                    path: fixture.path.clone(),
+                    info: broken,
+                    via: fixture.head,
+                },
+            )
+            .await?;
+        repair.flush(&ctx, &fixture.repo).await?;
+
+        // The repair wasn't written, so isn't counted.
+        assert_eq!(repair.stats.repaired.load(Ordering::Relaxed), 0);
+        assert_eq!(repair.leases.conflict_count(), 1);
+        let info = read_filenode(&ctx, &fixture.repo, &fixture.path, fixture.info.filenode).await?;
+        assert_eq!(info.linknode, fixture.unrelated);
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@
 
 /// Tags for slog usage
 pub const CHUNKING: &str = "chunking";
+pub const CONTENT_DEDUP: &str = "content_dedup";
+pub const EDGE_STATS: &str = "edge_stats";
 pub const GRAPH: &str = "graph";
 pub const LOADED: &str = "loaded";
 pub const SIZING: &str = "sizing";
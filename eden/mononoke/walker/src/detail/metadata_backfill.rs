@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use mononoke_types::ContentId;
+use mononoke_types::RepositoryId;
+use slog::info;
+use slog::Logger;
+
+use crate::detail::repair_lease::RepairLeases;
+
+#[derive(Default)]
+struct BackfillState {
+    max_backfills: Option<u64>,
+    started: AtomicU64,
+    backfilled: AtomicU64,
+    failed: AtomicU64,
+    over_cap: AtomicU64,
+}
+
+/// Backfills file content metadata found missing during a walk, by deriving
+/// it from the content and storing it, up to a cap shared by all repos in a
+/// job.  Missing metadata over the cap is reported as missing, as it is
+/// when backfill is disabled.  Backfills are written under a lease on the
+/// content, and left missing if another walker holds it.
+#[derive(Clone, Default)]
+pub struct MetadataBackfill {
+    state: Option<Arc<BackfillState>>,
+    leases: RepairLeases,
+}
+
+impl MetadataBackfill {
+    pub fn new(max_backfills: Option<u64>) -> Self {
+        Self {
+            state: Some(Arc::new(BackfillState {
+                max_backfills,
+                ..Default::default()
+            })),
+            leases: RepairLeases::default(),
+        }
+    }
+
+    pub fn leases(&self) -> &RepairLeases {
+        &self.leases
+    }
+
+    /// The key of the lease held while backfilling the metadata of `id`.
+    /// The filestore stores content metadata without taking a lease, so
+    /// this key only guards concurrent walkers against each other.
+    pub fn lease_key(repo_id: RepositoryId, id: ContentId) -> String {
+        format!("repo{}.walker.content_metadata_backfill.{}", repo_id, id)
+    }
+
+    /// Claim one backfill, returning false if backfill is disabled or the
+    /// cap has been reached.
+    pub fn try_start(&self) -> bool {
+        let state = match &self.state {
+            Some(state) => state,
+            None => return false,
+        };
+        let started =
+            state
+                .started
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |started| match state
+                    .max_backfills
+                {
+                    Some(max) if started >= max => None,
+                    _ => Some(started + 1),
+                });
+        if started.is_err() {
+            state.over_cap.fetch_add(1, Ordering::Relaxed);
+        }
+        started.is_ok()
+    }
+
+    /// Record the outcome of a backfill claimed with `try_start`.
+    pub fn record_result(&self, success: bool) {
+        if let Some(state) = &self.state {
+            if success {
+                state.backfilled.fetch_add(1, Ordering::Relaxed);
+            } else {
+                state.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Log how much has been backfilled so far in the job.
+    pub fn log_summary(&self, logger: &Logger, repo_name: &str) {
+        let state = match &self.state {
+            Some(state) => state,
+            None => return,
+        };
+        let limit = state
+            .max_backfills
+            .map_or_else(|| "none".to_string(), |v| v.to_string());
+        info!(
+            logger,
+            "Content metadata backfill after walk of {}: backfilled {}, failed {}, left missing over cap {} (max {}), left missing as leased {}",
+            repo_name,
+            state.backfilled.load(Ordering::Relaxed),
+            state.failed.load(Ordering::Relaxed),
+            state.over_cap.load(Ordering::Relaxed),
+            limit,
+            self.leases.conflict_count(),
+        );
+        self.leases
+            .log_conflicts(logger, "Content metadata backfill");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backfill_disabled() {
+        let backfill = MetadataBackfill::default();
+        assert!(!backfill.try_start());
+    }
+
+    #[test]
+    fn test_backfill_capped() {
+        let backfill = MetadataBackfill::new(Some(2));
+        assert!(backfill.try_start());
+        assert!(backfill.clone().try_start());
+        assert!(!backfill.try_start());
+        let state = backfill.state.as_ref().unwrap();
+        assert_eq!(state.over_cap.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_backfill_uncapped() {
+        let backfill = MetadataBackfill::new(None);
+        for _ in 0..100 {
+            assert!(backfill.try_start());
+            backfill.record_result(true);
+        }
+        let state = backfill.state.as_ref().unwrap();
+        assert_eq!(state.backfilled.load(Ordering::Relaxed), 100);
+    }
+}
@@ -5,18 +5,35 @@
  * GNU General Public License version 2.
  */
 
+pub mod blob_age;
 pub mod blobstore;
+pub mod bookmark_log;
+pub mod budget;
 pub mod checkpoint;
+pub mod content_dedup;
+pub mod corruption_log;
+pub mod edge_stats;
 #[macro_use]
 pub mod graph;
 pub mod corpus;
+pub mod heartbeat;
+pub mod key_map;
+pub mod lease;
+pub mod linknode_repair;
 pub mod log;
+pub mod metadata_backfill;
+pub mod node_lookup;
 pub mod pack;
 pub mod parse_node;
 pub mod progress;
+pub mod record;
+pub mod repair_lease;
+pub mod retry;
+pub mod sample_rotation;
 pub mod sampling;
 pub mod scrub;
 pub mod sizing;
+pub mod skip_keys;
 pub mod state;
 pub mod tail;
 pub mod validate;
@@ -0,0 +1,188 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Error;
+use async_trait::async_trait;
+use bonsai_hg_mapping::BonsaiHgMapping;
+use context::CoreContext;
+use fbinit::FacebookInit;
+use futures::stream::TryStreamExt;
+use mercurial_types::HgChangesetId;
+use mononoke_types::ChangesetId;
+use phases::Phases;
+use slog::warn;
+use slog::Logger;
+
+use crate::args::OutputFormat;
+use crate::commands::JobParams;
+use crate::detail::graph::Node;
+use crate::detail::graph::NodeData;
+use crate::detail::state::WalkState;
+use crate::detail::walk::walk_exact;
+use crate::detail::walk::EmptyRoute;
+use crate::detail::walk::OutgoingEdge;
+use crate::detail::walk::RepoWalkTypeParams;
+use crate::detail::walk::VisitOne;
+use crate::detail::walk::WalkVisitor;
+
+type LookupOutput = (Node, Option<NodeData>, Vec<OutgoingEdge>);
+
+/// Visits only the walk roots, keeping their outgoing edges for display
+/// instead of following them.
+struct NodeLookupVisitor {
+    inner: WalkState,
+}
+
+#[async_trait]
+impl VisitOne for NodeLookupVisitor {
+    fn in_chunk(&self, bcs_id: &ChangesetId) -> bool {
+        self.inner.in_chunk(bcs_id)
+    }
+
+    fn needs_visit(&self, outgoing: &OutgoingEdge) -> bool {
+        self.inner.needs_visit(outgoing)
+    }
+
+    async fn is_public(
+        &self,
+        ctx: &CoreContext,
+        phases_store: &dyn Phases,
+        bcs_id: &ChangesetId,
+    ) -> Result<bool, Error> {
+        self.inner.is_public(ctx, phases_store, bcs_id).await
+    }
+
+    fn get_hg_from_bonsai(&self, bcs_id: &ChangesetId) -> Option<HgChangesetId> {
+        self.inner.get_hg_from_bonsai(bcs_id)
+    }
+
+    fn record_hg_from_bonsai(&self, bcs_id: &ChangesetId, hg_cs_id: HgChangesetId) {
+        self.inner.record_hg_from_bonsai(bcs_id, hg_cs_id)
+    }
+
+    async fn get_bonsai_from_hg(
+        &self,
+        ctx: &CoreContext,
+        bonsai_hg_mapping: &dyn BonsaiHgMapping,
+        hg_cs_id: &HgChangesetId,
+    ) -> Result<ChangesetId, Error> {
+        self.inner
+            .get_bonsai_from_hg(ctx, bonsai_hg_mapping, hg_cs_id)
+            .await
+    }
+
+    async fn defer_from_hg(
+        &self,
+        ctx: &CoreContext,
+        bonsai_hg_mapping: &dyn BonsaiHgMapping,
+        hg_cs_id: &HgChangesetId,
+    ) -> Result<Option<ChangesetId>, Error> {
+        self.inner
+            .defer_from_hg(ctx, bonsai_hg_mapping, hg_cs_id)
+            .await
+    }
+}
+
+impl WalkVisitor<LookupOutput, EmptyRoute> for NodeLookupVisitor {
+    fn start_step(
+        &self,
+        ctx: CoreContext,
+        route: Option<&EmptyRoute>,
+        step: &OutgoingEdge,
+    ) -> Option<CoreContext> {
+        self.inner.start_step(ctx, route, step)
+    }
+
+    fn visit(
+        &self,
+        ctx: &CoreContext,
+        resolved: OutgoingEdge,
+        node_data: Option<NodeData>,
+        route: Option<EmptyRoute>,
+        outgoing: Vec<OutgoingEdge>,
+    ) -> (LookupOutput, EmptyRoute, Vec<OutgoingEdge>) {
+        let ((node, node_data, _stats), route, outgoing) =
+            self.inner.visit(ctx, resolved, node_data, route, outgoing);
+        // Report the edges, but don't step to them
+        ((node, node_data, outgoing), route, vec![])
+    }
+
+    fn defer_visit(
+        &self,
+        bcs_id: &ChangesetId,
+        walk_item: &OutgoingEdge,
+        route: Option<EmptyRoute>,
+    ) -> Result<(LookupOutput, EmptyRoute), Error> {
+        let ((node, node_data, _stats), route) =
+            self.inner.defer_visit(bcs_id, walk_item, route)?;
+        Ok(((node, node_data, vec![]), route))
+    }
+}
+
+fn print_node(logger: &Logger, output_format: &OutputFormat, output: &LookupOutput) {
+    let (node, node_data, outgoing) = output;
+    if node_data.is_none() {
+        warn!(logger, "No data loaded for {:?}", node);
+    }
+    match output_format {
+        OutputFormat::Debug => println!("Node {:?}: NodeData: {:?}", node, node_data),
+        // Keep Node as non-Pretty so its on same line
+        OutputFormat::PrettyDebug => println!("Node {:?}: NodeData: {:#?}", node, node_data),
+    }
+    for edge in outgoing {
+        match &edge.path {
+            Some(path) => println!("Edge {:?}: {:?} via path {}", edge.label, edge.target, path),
+            None => println!("Edge {:?}: {:?}", edge.label, edge.target),
+        }
+    }
+}
+
+// Subcommand entry point for looking up single nodes in the graph
+pub async fn node_lookup(
+    fb: FacebookInit,
+    job_params: JobParams,
+    output_format: OutputFormat,
+) -> Result<(), Error> {
+    let JobParams {
+        walk_params,
+        per_repo,
+    } = job_params;
+
+    for (_sub_params, repo_params) in per_repo {
+        let ctx = CoreContext::new_with_logger(fb, repo_params.logger.clone());
+        let logger = repo_params.logger.clone();
+        let visitor = Arc::new(NodeLookupVisitor {
+            inner: WalkState::new(
+                repo_params.include_node_types.clone(),
+                repo_params.include_edge_types.clone(),
+                HashSet::new(),
+                walk_params.enable_derive,
+                None,
+            ),
+        });
+        let type_params = RepoWalkTypeParams {
+            required_node_data_types: repo_params
+                .walk_roots
+                .iter()
+                .map(|e| e.target.get_type())
+                .collect(),
+            keep_edge_paths: true,
+            ..Default::default()
+        };
+
+        walk_exact(ctx, visitor, walk_params.clone(), repo_params, type_params)
+            .try_for_each(|output| {
+                print_node(&logger, &output_format, &output);
+                async { Ok(()) }
+            })
+            .await?;
+    }
+    Ok(())
+}
@@ -7,10 +7,15 @@
 
 use std::collections::HashSet;
 
+use anyhow::Error;
 use blobstore::SizeMetadata;
 use metaconfig_types::BlobstoreId;
 use mononoke_types::Timestamp;
+use scribe_ext::Scribe;
 use scuba_ext::MononokeScubaSampleBuilder;
+use serde::Serialize;
+use slog::warn;
+use slog::Logger;
 
 use crate::detail::graph::NodeType;
 use crate::detail::validate::REPO;
@@ -48,26 +53,57 @@ pub trait PackInfoLogger {
     fn log(&self, info: PackInfo<'_>);
 }
 
+impl PackInfoLogger for Box<dyn PackInfoLogger + Send> {
+    fn log(&self, info: PackInfo<'_>) {
+        (**self).log(info)
+    }
+}
+
+/// Where pack info is sent
+#[derive(Clone)]
+pub enum PackInfoLogDest {
+    Scuba(MononokeScubaSampleBuilder),
+    /// Stream one JSON record per key to a scribe category as it is walked
+    Scribe {
+        scribe: Scribe,
+        category: String,
+    },
+}
+
 /// What to log for packing and where to send it
 #[derive(Clone)]
 pub struct PackInfoLogOptions {
     pub log_node_types: HashSet<NodeType>,
-    pub log_dest: MononokeScubaSampleBuilder, // TODO(ahornby), add an enum once alternate destinations possible
+    pub log_dest: PackInfoLogDest,
 }
 
 impl PackInfoLogOptions {
     pub fn make_logger(
         &self,
+        logger: Logger,
         repo_name: String,
         run_start: Timestamp,
         chunk_num: u64,
         checkpoint_name: Option<String>,
-    ) -> ScubaPackInfoLogger {
-        let mut scuba = self.log_dest.clone();
-        scuba
-            .add(REPO, repo_name)
-            .add_opt(CHECKPOINT_NAME, checkpoint_name);
-        ScubaPackInfoLogger::new(scuba, run_start, chunk_num)
+    ) -> Box<dyn PackInfoLogger + Send> {
+        match &self.log_dest {
+            PackInfoLogDest::Scuba(scuba) => {
+                let mut scuba = scuba.clone();
+                scuba
+                    .add(REPO, repo_name)
+                    .add_opt(CHECKPOINT_NAME, checkpoint_name);
+                Box::new(ScubaPackInfoLogger::new(scuba, run_start, chunk_num))
+            }
+            PackInfoLogDest::Scribe { scribe, category } => Box::new(ScribePackInfoLogger {
+                scribe: scribe.clone(),
+                category: category.clone(),
+                logger,
+                repo_name,
+                checkpoint_name,
+                run_start,
+                chunk_num,
+            }),
+        }
     }
 }
 
@@ -118,3 +154,69 @@ impl PackInfoLogger for ScubaPackInfoLogger {
         scuba.log();
     }
 }
+
+/// Record sent to scribe for each blobstore key, with the same fields as the
+/// scuba logging.
+#[derive(Serialize)]
+struct PackInfoRecord<'a> {
+    repo: &'a str,
+    checkpoint_name: Option<&'a str>,
+    run_start: i64,
+    chunk_num: u64,
+    blobstore_id: Option<u64>,
+    blobstore_key: &'a str,
+    node_type: &'a str,
+    node_fingerprint: Option<u64>,
+    similarity_key: Option<u64>,
+    mtime: Option<u64>,
+    uncompressed_size: u64,
+    unique_compressed_size: Option<u64>,
+    pack_key: Option<String>,
+    relevant_uncompressed_size: Option<u64>,
+    relevant_compressed_size: Option<u64>,
+    ctime: Option<i64>,
+}
+
+pub struct ScribePackInfoLogger {
+    scribe: Scribe,
+    category: String,
+    logger: Logger,
+    repo_name: String,
+    checkpoint_name: Option<String>,
+    run_start: Timestamp,
+    chunk_num: u64,
+}
+
+impl PackInfoLogger for ScribePackInfoLogger {
+    fn log(&self, info: PackInfo<'_>) {
+        let pack_meta = info.sizes.as_ref().and_then(|s| s.pack_meta.as_ref());
+        let record = PackInfoRecord {
+            repo: &self.repo_name,
+            checkpoint_name: self.checkpoint_name.as_deref(),
+            run_start: self.run_start.timestamp_seconds(),
+            chunk_num: self.chunk_num,
+            blobstore_id: info.blobstore_id.map(u64::from),
+            blobstore_key: info.blobstore_key,
+            node_type: info.node_type.as_ref(),
+            node_fingerprint: info.node_fingerprint,
+            similarity_key: info.similarity_key,
+            mtime: info.mtime,
+            uncompressed_size: info.uncompressed_size,
+            unique_compressed_size: info.sizes.as_ref().map(|s| s.unique_compressed_size),
+            pack_key: pack_meta.map(|m| m.pack_key.clone()),
+            relevant_uncompressed_size: pack_meta.map(|m| m.relevant_uncompressed_size),
+            relevant_compressed_size: pack_meta.map(|m| m.relevant_compressed_size),
+            ctime: info.ctime,
+        };
+        // Scribe is best effort, a dropped record should not fail the walk
+        let res = serde_json::to_string(&record)
+            .map_err(Error::from)
+            .and_then(|json| self.scribe.offer(&self.category, &json));
+        if let Err(e) = res {
+            warn!(
+                self.logger,
+                "Failed to log pack info to scribe category {}: {:?}", self.category, e
+            );
+        }
+    }
+}
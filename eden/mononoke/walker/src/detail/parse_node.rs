@@ -250,6 +250,10 @@ mod tests {
                 &parse_node(&format!("HgChangesetViaBonsai{}{}", NODE_SEP, SAMPLE_SHA1))?
                     .get_type()
             ),
+            NodeType::HgConvertRevision => assert_eq!(
+                node_type,
+                &parse_node(&format!("HgConvertRevision{}{}", NODE_SEP, SAMPLE_SHA1))?.get_type()
+            ),
             NodeType::HgManifest => assert_eq!(
                 node_type,
                 &parse_node(&format!(
@@ -283,6 +287,10 @@ mod tests {
                 node_type,
                 &parse_node(&format!("FileContent{}{}", NODE_SEP, SAMPLE_BLAKE2))?.get_type()
             ),
+            NodeType::FileContentChunk => assert_eq!(
+                node_type,
+                &parse_node(&format!("FileContentChunk{}{}", NODE_SEP, SAMPLE_BLAKE2))?.get_type()
+            ),
             NodeType::FileContentMetadataV2 => assert_eq!(
                 node_type,
                 &parse_node(&format!(
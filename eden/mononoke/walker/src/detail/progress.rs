@@ -8,6 +8,8 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ops::Add;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Duration;
@@ -40,6 +42,10 @@ define_stats! {
     walk_progress_errors: dynamic_timeseries("{}.progress.{}.errors", (subcommand: &'static str, repo: String); Rate, Sum),
     walk_progress_missing: dynamic_timeseries("{}.progress.{}.missing", (subcommand: &'static str, repo: String); Rate, Sum),
     walk_progress_hash_validation_failure: dynamic_timeseries("{}.progress.{}.hash_validation_failure", (subcommand: &'static str, repo: String); Rate, Sum),
+    walk_progress_skipped_known_bad: dynamic_timeseries("{}.progress.{}.skipped_known_bad", (subcommand: &'static str, repo: String); Rate, Sum),
+    walk_progress_redacted: dynamic_timeseries("{}.progress.{}.redacted", (subcommand: &'static str, repo: String); Rate, Sum),
+    walk_progress_queue_depth: dynamic_singleton_counter("{}.progress.{}.queue_depth", (subcommand: &'static str, repo: String)),
+    walk_progress_dedup_hits: dynamic_timeseries("{}.progress.{}.dedup_hits", (subcommand: &'static str, repo: String); Rate, Sum),
 }
 
 pub trait ProgressRecorderUnprotected<SS> {
@@ -61,6 +67,29 @@ pub trait ProgressReporterUnprotected {
 pub struct ProgressOptions {
     pub sample_rate: u64,
     pub interval: Duration,
+    pub report_edge_stats: bool,
+    pub report_content_dedup: bool,
+    pub content_dedup_max_distinct: usize,
+    pub report_queue_stats: bool,
+}
+
+/// Number of steps queued by the walk that have not yet finished, including
+/// those in flight.
+#[derive(Clone, Debug, Default)]
+pub struct PendingSteps(Arc<AtomicI64>);
+
+impl PendingSteps {
+    pub fn queued(&self, n: usize) {
+        self.0.fetch_add(n as i64, Ordering::Relaxed);
+    }
+
+    pub fn finished(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed).max(0) as u64
+    }
 }
 
 pub struct ProgressStateByTypeParams {
@@ -69,6 +98,7 @@ pub struct ProgressStateByTypeParams {
     pub subcommand_stats_key: &'static str,
     pub repo_stats_key: String,
     pub types_sorted_by_name: Vec<NodeType>,
+    pub pending_steps: PendingSteps,
     options: ProgressOptions,
 }
 
@@ -104,10 +134,13 @@ where
 pub struct ProgressSummary {
     walked: u64,
     checked: u64,
+    deduped: u64,
     queued: u64,
     errors: u64,
     missing: u64,
     hash_validation_failure: u64,
+    skipped_known_bad: u64,
+    redacted: u64,
 }
 
 // Takes a summary type as a parameter. e.g. ProgressSummary
@@ -161,6 +194,7 @@ where
                 subcommand_stats_key,
                 repo_stats_key,
                 types_sorted_by_name: types_by_name,
+                pending_steps: PendingSteps::default(),
                 options,
             },
             // Updated by record_step
@@ -178,6 +212,12 @@ where
         }
     }
 
+    /// Report the depth of the walk's queue from these pending steps.
+    pub fn with_pending_steps(mut self, pending_steps: PendingSteps) -> Self {
+        self.params.pending_steps = pending_steps;
+        self
+    }
+
     // Throttle by sample, then time
     pub fn should_log_throttled(&mut self) -> Option<Duration> {
         if self.work_stats.total_progress % self.params.options.sample_rate == 0 {
@@ -202,11 +242,14 @@ impl ProgressStateCountByType<StepStats, ProgressSummary> {
                 let s = ProgressSummary {
                     walked: *ps,
                     checked: ss.visited_of_type as u64,
+                    deduped: ss.deduped_of_type as u64,
                     // num_expanded_new is per type children which when summed == a top level queued stat
                     queued: ss.num_expanded_new as u64,
                     errors: ss.error_count as u64,
                     missing: ss.missing_count as u64,
                     hash_validation_failure: ss.hash_validation_failure_count as u64,
+                    skipped_known_bad: ss.skipped_known_bad_count as u64,
+                    redacted: ss.redacted_count as u64,
                 };
                 (*k, s)
             })
@@ -314,9 +357,94 @@ impl ProgressStateCountByType<StepStats, ProgressSummary> {
             ),
         );
 
+        STATS::walk_progress_skipped_known_bad.add_value(
+            delta_summary.skipped_known_bad as i64,
+            (
+                self.params.subcommand_stats_key,
+                self.params.repo_stats_key.clone(),
+            ),
+        );
+
+        STATS::walk_progress_redacted.add_value(
+            delta_summary.redacted as i64,
+            (
+                self.params.subcommand_stats_key,
+                self.params.repo_stats_key.clone(),
+            ),
+        );
+
+        if self.params.options.report_queue_stats {
+            self.report_queue_stats(&summary_by_type, &new_summary, &delta_summary);
+        }
+
         self.reporting_stats.last_summary_by_type = summary_by_type;
         self.reporting_stats.last_summary = new_summary;
     }
+
+    /// Log the depth of the walk's queue and how often checks against the
+    /// visited set found the node already visited, overall and by type.
+    fn report_queue_stats(
+        &self,
+        summary_by_type: &HashMap<NodeType, ProgressSummary>,
+        new_summary: &ProgressSummary,
+        delta_summary: &ProgressSummary,
+    ) {
+        fn percent(part: u64, whole: u64) -> u64 {
+            if whole > 0 {
+                part * 100 / whole
+            } else {
+                0
+            }
+        }
+
+        let queue_depth = self.params.pending_steps.get();
+        let detail = &self
+            .params
+            .types_sorted_by_name
+            .iter()
+            .map(|t| {
+                let s = summary_by_type.get(t).cloned().unwrap_or_default();
+                format!(
+                    "{}:{},{},{}%",
+                    t,
+                    s.checked,
+                    s.deduped,
+                    percent(s.deduped, s.checked)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        info!(
+            self.params.logger,
+            #log::GRAPH,
+            "Queued,Checks,Hits,Hit%; Delta {},{},{},{}%; Run {},{},{}%; Type:Checks,Hits,Hit% {}",
+            queue_depth,
+            delta_summary.checked,
+            delta_summary.deduped,
+            percent(delta_summary.deduped, delta_summary.checked),
+            new_summary.checked,
+            new_summary.deduped,
+            percent(new_summary.deduped, new_summary.checked),
+            detail,
+        );
+
+        STATS::walk_progress_queue_depth.set_value(
+            self.params.fb,
+            queue_depth as i64,
+            (
+                self.params.subcommand_stats_key,
+                self.params.repo_stats_key.clone(),
+            ),
+        );
+        STATS::walk_progress_dedup_hits.add_value(
+            delta_summary.deduped as i64,
+            (
+                self.params.subcommand_stats_key,
+                self.params.repo_stats_key.clone(),
+            ),
+        );
+    }
 }
 
 impl<SS, T> ProgressRecorderUnprotected<SS> for ProgressStateCountByType<SS, T>
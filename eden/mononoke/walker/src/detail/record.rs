@@ -0,0 +1,252 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Record and replay of the blobs loaded by a walk.
+//!
+//! In record mode each blob the walk loads is also written to a local file
+//! blobstore.  In replay mode that directory is used as the repo's blobstore,
+//! so a walk can be re-run against the captured data offline, without
+//! reading from the production blobstores.  Metadata from SQL, e.g.
+//! bookmarks and filenodes, is not recorded, so a replay must use a storage
+//! config with a local metadata database holding that metadata.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Error;
+use anyhow::Result;
+use async_trait::async_trait;
+use blobstore::Blobstore;
+use blobstore::BlobstoreGetData;
+use blobstore::BlobstoreIsPresent;
+use blobstore::PutBehaviour;
+use context::CoreContext;
+use fileblob::Fileblob;
+use metaconfig_types::BlobConfig;
+use mononoke_types::BlobstoreBytes;
+
+use crate::detail::graph::NodeType;
+
+// Blobs are kept in the same layout as a files blobstore config uses, so
+// that the directory can be replayed by pointing a config at it.
+const BLOBS_DIR: &str = "blobs";
+
+// Blobstore key prefixes of the node types whose data is held in a blob.
+// Longer prefixes come first where one prefix extends another.
+const KEY_PREFIXES: &[(&str, NodeType)] = &[
+    ("changeset.", NodeType::Changeset),
+    ("content.", NodeType::FileContent),
+    ("chunk.", NodeType::FileContentChunk),
+    ("content_metadata2.", NodeType::FileContentMetadataV2),
+    ("alias.", NodeType::AliasContentMapping),
+    ("hgchangeset.", NodeType::HgChangeset),
+    ("hgmanifest.", NodeType::HgManifest),
+    ("hgfilenode.", NodeType::HgFileEnvelope),
+    ("skeletonmanifest.", NodeType::SkeletonManifest),
+    ("fsnode.", NodeType::Fsnode),
+    ("fileunode.", NodeType::UnodeFile),
+    ("manifestunode.", NodeType::UnodeManifest),
+    ("fastlogbatch.", NodeType::FastlogBatch),
+    ("deletedmanifest2.", NodeType::DeletedManifestV2),
+    ("blame_v2.", NodeType::Blame),
+    ("bssm.", NodeType::BasenameSuffixSkeletonManifest),
+    ("changeset_info.", NodeType::ChangesetInfo),
+];
+
+/// The node type whose data is held in the blob with this key, if known.
+/// Keys may carry a repo prefix, e.g. `repo0000.`, before the type prefix.
+pub fn blob_key_node_type(key: &str) -> Option<NodeType> {
+    Some(0)
+        .into_iter()
+        .chain(key.match_indices('.').map(|(index, _)| index + 1))
+        .find_map(|index| {
+            KEY_PREFIXES
+                .iter()
+                .find(|(prefix, _)| key[index..].starts_with(prefix))
+                .map(|(_, node_type)| *node_type)
+        })
+}
+
+/// The blobstore config that replays a recording made to `dir`.
+pub fn replay_blobconfig(dir: &Path) -> BlobConfig {
+    BlobConfig::Files {
+        path: dir.to_path_buf(),
+    }
+}
+
+/// Which blobs a recording keeps.
+#[derive(Clone, Debug, Default)]
+pub struct RecordFilter {
+    /// Only keep the blobs of these node types.  Blobs whose node type isn't
+    /// known from their key, e.g. derived data mappings, are always kept, as
+    /// a replay needs them to reach the nodes of any type.
+    pub node_types: Option<HashSet<NodeType>>,
+}
+
+impl RecordFilter {
+    pub fn keeps(&self, key: &str) -> bool {
+        match (&self.node_types, blob_key_node_type(key)) {
+            (Some(node_types), Some(node_type)) => node_types.contains(&node_type),
+            _ => true,
+        }
+    }
+}
+
+/// Where and what to record.
+#[derive(Clone, Debug)]
+pub struct RecordOptions {
+    pub dir: PathBuf,
+    pub filter: RecordFilter,
+}
+
+impl RecordOptions {
+    pub fn open_store(&self) -> Result<Arc<dyn Blobstore>, Error> {
+        let store = Fileblob::create(self.dir.join(BLOBS_DIR), PutBehaviour::Overwrite)?;
+        Ok(Arc::new(store))
+    }
+}
+
+/// A layer over the repo's blobstore that records the blobs loaded through
+/// it to another store.
+#[derive(Debug)]
+pub struct RecordingBlobstore {
+    inner: Arc<dyn Blobstore>,
+    record: Arc<dyn Blobstore>,
+    filter: RecordFilter,
+}
+
+impl RecordingBlobstore {
+    pub fn new(
+        inner: Arc<dyn Blobstore>,
+        record: Arc<dyn Blobstore>,
+        filter: RecordFilter,
+    ) -> Self {
+        Self {
+            inner,
+            record,
+            filter,
+        }
+    }
+}
+
+impl fmt::Display for RecordingBlobstore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RecordingBlobstore<{}>", &self.inner)
+    }
+}
+
+#[async_trait]
+impl Blobstore for RecordingBlobstore {
+    async fn get<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<Option<BlobstoreGetData>> {
+        let opt_blob = self.inner.get(ctx, key).await?;
+        if let Some(blob) = &opt_blob {
+            if self.filter.keeps(key) {
+                self.record
+                    .put(ctx, key.to_string(), blob.as_bytes().clone())
+                    .await?;
+            }
+        }
+        Ok(opt_blob)
+    }
+
+    async fn put<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: String,
+        value: BlobstoreBytes,
+    ) -> Result<()> {
+        self.inner.put(ctx, key, value).await
+    }
+
+    async fn is_present<'a>(
+        &'a self,
+        ctx: &'a CoreContext,
+        key: &'a str,
+    ) -> Result<BlobstoreIsPresent> {
+        self.inner.is_present(ctx, key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use maplit::hashset;
+    use memblob::Memblob;
+
+    use super::*;
+
+    #[test]
+    fn test_blob_key_node_type() {
+        assert_eq!(
+            blob_key_node_type("repo0000.changeset.blake2.abc"),
+            Some(NodeType::Changeset)
+        );
+        assert_eq!(
+            blob_key_node_type("content.blake2.abc"),
+            Some(NodeType::FileContent)
+        );
+        assert_eq!(
+            blob_key_node_type("repo0001.content_metadata2.blake2.abc"),
+            Some(NodeType::FileContentMetadataV2)
+        );
+        assert_eq!(
+            blob_key_node_type("repo0000.changeset_info.blake2.abc"),
+            Some(NodeType::ChangesetInfo)
+        );
+        assert_eq!(blob_key_node_type("repo0000.derived_root_fsnode.abc"), None);
+    }
+
+    #[fbinit::test]
+    async fn test_record(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let inner = Arc::new(Memblob::new(PutBehaviour::Overwrite));
+        let record = Arc::new(Memblob::new(PutBehaviour::Overwrite));
+        let blobstore = RecordingBlobstore::new(
+            inner.clone(),
+            record.clone(),
+            RecordFilter {
+                node_types: Some(hashset! { NodeType::Changeset }),
+            },
+        );
+
+        let keys = [
+            "repo0000.changeset.blake2.abc",
+            "repo0000.content.blake2.abc",
+            "repo0000.derived_root_fsnode.abc",
+        ];
+        for key in keys {
+            inner
+                .put(&ctx, key.to_string(), BlobstoreBytes::from_bytes(key))
+                .await?;
+        }
+        // Only loaded blobs are recorded.
+        assert!(record.get(&ctx, keys[0]).await?.is_none());
+        for key in keys {
+            assert!(blobstore.get(&ctx, key).await?.is_some());
+        }
+        assert!(blobstore
+            .get(&ctx, "repo0000.changeset.missing")
+            .await?
+            .is_none());
+
+        assert!(record.get(&ctx, keys[0]).await?.is_some());
+        assert!(record.get(&ctx, keys[1]).await?.is_none());
+        assert!(record.get(&ctx, keys[2]).await?.is_some());
+        assert!(record
+            .get(&ctx, "repo0000.changeset.missing")
+            .await?
+            .is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Error;
+use cacheblob::LeaseOps;
+use context::CoreContext;
+use futures::channel::oneshot;
+use futures::future::FutureExt;
+use slog::info;
+use slog::Logger;
+
+// Number of most conflicted keys to log in the summary.
+const LOG_CONFLICTED_KEYS: usize = 10;
+
+/// A lease held for a repair write.  The lease is renewed until it is
+/// released, or until the guard is dropped, after which it expires.
+pub struct RepairLeaseGuard {
+    lease: Arc<dyn LeaseOps>,
+    key: String,
+    done: oneshot::Sender<()>,
+}
+
+impl RepairLeaseGuard {
+    pub async fn release(self) {
+        let _ = self.done.send(());
+        self.lease.release_lease(&self.key).await;
+    }
+}
+
+/// Guards the writes made by the walker's repair modes with the repo's
+/// derived data lease, so that concurrent walkers, or the derived data
+/// tailer deriving the same data, don't race writing it.  A write whose
+/// lease is held by another writer is skipped rather than waited for, and
+/// counted as a conflict on its key.
+#[derive(Clone, Default)]
+pub struct RepairLeases {
+    conflicts: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl RepairLeases {
+    /// Take the lease on `key`, or record a conflict and return None if
+    /// another writer holds it.
+    pub async fn try_acquire(
+        &self,
+        ctx: &CoreContext,
+        lease: &Arc<dyn LeaseOps>,
+        key: String,
+    ) -> Result<Option<RepairLeaseGuard>, Error> {
+        if !lease.try_add_put_lease(&key).await? {
+            *self
+                .conflicts
+                .lock()
+                .expect("lock poisoned")
+                .entry(key)
+                .or_default() += 1;
+            return Ok(None);
+        }
+        let (done, receiver) = oneshot::channel();
+        lease.renew_lease_until(ctx.clone(), &key, receiver.map(|_| ()).boxed());
+        Ok(Some(RepairLeaseGuard {
+            lease: lease.clone(),
+            key,
+            done,
+        }))
+    }
+
+    /// Run the write holding the lease on `key`.  Returns None without
+    /// running it if another writer holds the lease.
+    pub async fn try_write<T>(
+        &self,
+        ctx: &CoreContext,
+        lease: &Arc<dyn LeaseOps>,
+        key: String,
+        write: impl Future<Output = Result<T, Error>>,
+    ) -> Result<Option<T>, Error> {
+        let guard = match self.try_acquire(ctx, lease, key).await? {
+            Some(guard) => guard,
+            None => return Ok(None),
+        };
+        let written = write.await;
+        guard.release().await;
+        written.map(Some)
+    }
+
+    /// Conflicts on each key, most conflicted first.
+    pub fn conflicts(&self) -> Vec<(String, u64)> {
+        let mut conflicts: Vec<_> = self
+            .conflicts
+            .lock()
+            .expect("lock poisoned")
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .collect();
+        conflicts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        conflicts
+    }
+
+    /// Total number of writes skipped due to conflicts.
+    pub fn conflict_count(&self) -> u64 {
+        self.conflicts.lock().expect("lock poisoned").values().sum()
+    }
+
+    /// Log the most conflicted keys, if there were any conflicts.
+    pub fn log_conflicts(&self, logger: &Logger, repair: &str) {
+        for (key, count) in self.conflicts().into_iter().take(LOG_CONFLICTED_KEYS) {
+            info!(
+                logger,
+                "{} skipped {} writes to {} leased by another writer", repair, count, key
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cacheblob::InProcessLease;
+    use fbinit::FacebookInit;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_repair_lease_conflict(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let lease: Arc<dyn LeaseOps> = Arc::new(InProcessLease::new());
+        let leases = RepairLeases::default();
+
+        let guard = leases
+            .try_acquire(&ctx, &lease, "key1".to_string())
+            .await?
+            .expect("lease is free");
+        // Writes to a leased key are skipped and counted.
+        for _ in 0..2 {
+            let written = leases
+                .try_write(&ctx, &lease, "key1".to_string(), async { Ok(()) })
+                .await?;
+            assert_eq!(written, None);
+        }
+        // Other keys are unaffected.
+        let written = leases
+            .try_write(&ctx, &lease, "key2".to_string(), async { Ok(2) })
+            .await?;
+        assert_eq!(written, Some(2));
+
+        guard.release().await;
+        let written = leases
+            .try_write(&ctx, &lease, "key1".to_string(), async { Ok(1) })
+            .await?;
+        assert_eq!(written, Some(1));
+
+        assert_eq!(leases.conflicts(), vec![("key1".to_string(), 2)]);
+        assert_eq!(leases.conflict_count(), 2);
+        Ok(())
+    }
+}
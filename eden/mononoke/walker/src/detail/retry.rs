@@ -0,0 +1,88 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::io;
+use std::time::Duration;
+
+use anyhow::Error;
+
+/// How steps that fail with transient errors are retried.
+#[derive(Clone, Copy, Debug)]
+pub struct StepRetryOptions {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl StepRetryOptions {
+    /// Delay before the given retry (starting from 1), doubling each time up
+    /// to the maximum delay.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Whether a load error is likely to succeed if retried, e.g. a timeout or
+/// dropped connection, as opposed to missing or corrupt data.
+pub fn is_transient(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return true;
+        }
+        match cause.downcast_ref::<io::Error>() {
+            Some(e) => matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::WouldBlock
+            ),
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::format_err;
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_delay_is_capped() {
+        let options = StepRetryOptions {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        assert_eq!(options.delay(1), Duration::from_millis(100));
+        assert_eq!(options.delay(2), Duration::from_millis(200));
+        assert_eq!(options.delay(4), Duration::from_millis(800));
+        assert_eq!(options.delay(5), Duration::from_secs(1));
+        assert_eq!(options.delay(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let timeout = Error::from(io::Error::new(io::ErrorKind::TimedOut, "timed out"))
+            .context("While fetching blob");
+        assert!(is_transient(&timeout));
+
+        let reset: Result<(), _> = Err(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert!(is_transient(&reset.context("get failed").unwrap_err()));
+
+        let invalid = Error::from(io::Error::from(io::ErrorKind::InvalidData));
+        assert!(!is_transient(&invalid));
+        assert!(!is_transient(&format_err!("Blob is corrupt")));
+    }
+}
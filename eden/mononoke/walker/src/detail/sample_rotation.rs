@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Error;
+use mononoke_types::RepositoryId;
+use mononoke_types::Timestamp;
+use slog::info;
+use slog::Logger;
+use sql_construct::SqlConstruct;
+use sql_construct::SqlConstructFromMetadataDatabaseConfig;
+use sql_ext::mononoke_queries;
+use sql_ext::SqlConnections;
+
+use crate::detail::budget::WalkBudget;
+
+/// Cycles each repo through its sample offsets over successive runs, so that
+/// a periodic job covers the whole repo in sample-rate pieces.  The offset
+/// walked is recorded once the walk completes, and the next run walks the
+/// offset after it.
+#[derive(Clone)]
+pub struct SampleOffsetRotation {
+    pub rotation_name: String,
+    sql_sample_offsets: Arc<SqlSampleOffsets>,
+}
+
+impl SampleOffsetRotation {
+    pub fn new(rotation_name: String, sql_sample_offsets: SqlSampleOffsets) -> Self {
+        Self {
+            rotation_name,
+            sql_sample_offsets: Arc::new(sql_sample_offsets),
+        }
+    }
+
+    /// The offset to walk next: the one after the last recorded offset,
+    /// wrapping around at the sample rate.  Starts from 0.
+    pub async fn next_offset(
+        &self,
+        logger: &Logger,
+        repo_id: RepositoryId,
+        sample_rate: u64,
+    ) -> Result<u64, Error> {
+        let last = self
+            .sql_sample_offsets
+            .load(repo_id, &self.rotation_name)
+            .await?;
+        let next = last.map_or(0, |last| (last + 1) % sample_rate.max(1));
+        info!(
+            logger,
+            "Sample offset rotation {} walking offset {} of {} (last walked {:?})",
+            self.rotation_name,
+            next,
+            sample_rate,
+            last,
+        );
+        Ok(next)
+    }
+
+    /// Record that the walk of `sample_offset` has completed.
+    pub async fn record(&self, repo_id: RepositoryId, sample_offset: u64) -> Result<(), Error> {
+        self.sql_sample_offsets
+            .record(repo_id, &self.rotation_name, sample_offset)
+            .await
+    }
+
+    /// Record the walk of `sample_offset` unless it stopped early, because
+    /// it was cancelled or ran out of budget, so the next run retries it.
+    pub async fn record_if_complete(
+        &self,
+        repo_id: RepositoryId,
+        sample_offset: u64,
+        budget: &WalkBudget,
+        cancellation_requested: &AtomicBool,
+    ) -> Result<(), Error> {
+        if budget.is_exhausted() || cancellation_requested.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.record(repo_id, sample_offset).await
+    }
+}
+
+impl fmt::Debug for SampleOffsetRotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SampleOffsetRotation")
+            .field("rotation_name", &self.rotation_name)
+            .finish()
+    }
+}
+
+pub struct SqlSampleOffsets {
+    connections: SqlConnections,
+}
+
+impl SqlConstruct for SqlSampleOffsets {
+    const LABEL: &'static str = "walker_sample_offsets";
+
+    const CREATION_QUERY: &'static str =
+        include_str!("../../schemas/sqlite-walker_sample_offsets.sql");
+
+    fn from_sql_connections(connections: SqlConnections) -> Self {
+        Self { connections }
+    }
+}
+
+impl SqlSampleOffsets {
+    pub async fn load(
+        &self,
+        repo_id: RepositoryId,
+        rotation_name: &str,
+    ) -> Result<Option<u64>, Error> {
+        let rows = SelectSampleOffset::query(
+            &self.connections.read_master_connection,
+            &repo_id,
+            &rotation_name,
+        )
+        .await?;
+        Ok(rows.into_iter().next().map(|row| row.0))
+    }
+
+    pub async fn record(
+        &self,
+        repo_id: RepositoryId,
+        // Query macro wants &String rather than &str
+        rotation_name: &String,
+        sample_offset: u64,
+    ) -> Result<(), Error> {
+        ReplaceSampleOffset::query(
+            &self.connections.write_connection,
+            &[(&repo_id, rotation_name, &sample_offset, &Timestamp::now())],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl SqlConstructFromMetadataDatabaseConfig for SqlSampleOffsets {}
+
+mononoke_queries! {
+    read SelectSampleOffset(
+        repo_id: RepositoryId,
+        rotation_name: &str,
+    ) -> (u64,) {
+        "SELECT sample_offset
+        FROM walker_sample_offsets WHERE repo_id={repo_id} AND rotation_name={rotation_name}"
+    }
+
+    write ReplaceSampleOffset(
+        values: (
+            repo_id: RepositoryId,
+            rotation_name: String,
+            sample_offset: u64,
+            update_timestamp: Timestamp,
+        ),
+    ) {
+        none,
+        "REPLACE INTO walker_sample_offsets
+         (repo_id, rotation_name, sample_offset, update_timestamp)
+         VALUES {values}"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use slog::o;
+
+    use super::*;
+
+    #[fbinit::test]
+    async fn test_rotation_cycles_offsets(_fb: FacebookInit) -> Result<(), Error> {
+        let logger = Logger::root(slog::Discard, o!());
+        let rotation = SampleOffsetRotation::new(
+            "test_rotation".to_string(),
+            SqlSampleOffsets::with_sqlite_in_memory()?,
+        );
+        let repo_id = RepositoryId::new(123);
+        let other_repo_id = RepositoryId::new(456);
+
+        let mut walked = Vec::new();
+        for _ in 0..4 {
+            let offset = rotation.next_offset(&logger, repo_id, 3).await?;
+            rotation.record(repo_id, offset).await?;
+            walked.push(offset);
+        }
+        assert_eq!(walked, vec![0, 1, 2, 0]);
+
+        // Each repo rotates independently
+        assert_eq!(rotation.next_offset(&logger, other_repo_id, 3).await?, 0);
+
+        // Unrecorded walks are retried
+        assert_eq!(rotation.next_offset(&logger, repo_id, 3).await?, 1);
+        assert_eq!(rotation.next_offset(&logger, repo_id, 3).await?, 1);
+        Ok(())
+    }
+}
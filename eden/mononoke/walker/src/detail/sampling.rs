@@ -9,6 +9,7 @@ use std::collections::HashSet;
 use std::fmt;
 use std::hash;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use anyhow::Error;
 use async_trait::async_trait;
@@ -22,6 +23,9 @@ use mercurial_types::HgChangesetId;
 use mononoke_types::datetime::DateTime;
 use mononoke_types::ChangesetId;
 use phases::Phases;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use rand::SeedableRng;
 use regex::Regex;
 use slog::Logger;
 
@@ -43,14 +47,32 @@ use crate::detail::walk::WalkVisitor;
 
 pub trait SampleTrigger<K> {
     fn map_keys(&self, key: SamplingKey, walk_key: K);
+
+    // Drop a node from the sample after it was mapped, e.g. once its size
+    // is known to be too small.
+    fn unmap_keys(&self, walk_key: K);
+}
+
+/// How nodes are chosen for the sample.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SampleMode {
+    /// Sample nodes whose fingerprint plus the offset is a multiple of the
+    /// sample rate, so repeated walks pick the same nodes.
+    #[default]
+    Fingerprint,
+    /// Sample each node independently with probability 1/sample_rate.  The
+    /// same seed gives the same decisions for the same order of visits.
+    Random { seed: u64 },
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct SamplingOptions {
     pub sample_rate: u64,
     pub sample_offset: u64,
+    pub sample_mode: SampleMode,
     pub node_types: HashSet<NodeType>,
     pub exclude_types: HashSet<NodeType>,
+    pub min_size_bytes: Option<u64>,
 }
 
 impl SamplingOptions {
@@ -72,6 +94,23 @@ pub struct SamplingWalkVisitor<T> {
     options: SamplingOptions,
     sample_path_regex: Option<Regex>,
     sampler: Arc<T>,
+    rng: Option<Mutex<SmallRng>>,
+    walking_diff_base: bool,
+}
+
+/// Whether samples of a node type can be limited by size.  Only file contents
+/// and manifests are, as other nodes are small and of roughly uniform size.
+pub fn is_size_filtered(node_type: NodeType) -> bool {
+    match node_type {
+        NodeType::FileContent
+        | NodeType::HgManifest
+        | NodeType::Fsnode
+        | NodeType::SkeletonManifest
+        | NodeType::BasenameSuffixSkeletonManifest
+        | NodeType::UnodeManifest
+        | NodeType::DeletedManifestV2 => true,
+        _ => false,
+    }
 }
 
 impl<T> SamplingWalkVisitor<T> {
@@ -84,6 +123,10 @@ impl<T> SamplingWalkVisitor<T> {
         enable_derive: bool,
         chunk_direction: Option<Direction>,
     ) -> Self {
+        let rng = match options.sample_mode {
+            SampleMode::Fingerprint => None,
+            SampleMode::Random { seed } => Some(Mutex::new(SmallRng::seed_from_u64(seed))),
+        };
         Self {
             inner: WalkState::new(
                 include_node_types,
@@ -95,6 +138,36 @@ impl<T> SamplingWalkVisitor<T> {
             options,
             sample_path_regex,
             sampler,
+            rng,
+            walking_diff_base: false,
+        }
+    }
+
+    fn is_below_min_size(&self, step: &OutgoingEdge, size: u64) -> bool {
+        let min_size_bytes = match self.options.min_size_bytes {
+            Some(min_size_bytes) => min_size_bytes,
+            None => return false,
+        };
+        let node_type = step.target.get_type();
+        is_size_filtered(node_type)
+            && self.options.node_types.contains(&node_type)
+            && size < min_size_bytes
+    }
+
+    fn should_sample(&self, sampling_fingerprint: impl FnOnce() -> Option<u64>) -> bool {
+        if self.walking_diff_base {
+            return false;
+        }
+        match self.options.sample_rate {
+            0 => false,
+            1 => true,
+            sample_rate => match &self.rng {
+                Some(rng) => rng.lock().expect("lock poisoned").gen_range(0..sample_rate) == 0,
+                None => sampling_fingerprint()
+                    .map_or(self.options.sample_offset % sample_rate == 0, |fp| {
+                        (fp + self.options.sample_offset) % sample_rate == 0
+                    }),
+            },
         }
     }
 }
@@ -233,6 +306,10 @@ impl<T> TailingWalkVisitor for SamplingWalkVisitor<T> {
     fn num_deferred(&self) -> usize {
         self.inner.num_deferred()
     }
+
+    fn set_walking_diff_base(&mut self, walking_base: bool) {
+        self.walking_diff_base = walking_base;
+    }
 }
 
 impl<T, P>
@@ -257,20 +334,12 @@ where
                     Some(repo_path) => re.is_match(&repo_path.to_string()),
                 },
             ) {
-                let should_sample = match self.options.sample_rate {
-                    0 => false,
-                    1 => true,
-                    sample_rate => {
-                        let sampling_fingerprint = repo_path.map_or_else(
-                            || step.target.sampling_fingerprint(),
-                            |r| Some(r.sampling_fingerprint()),
-                        );
-                        sampling_fingerprint
-                            .map_or(self.options.sample_offset % sample_rate == 0, |fp| {
-                                (fp + self.options.sample_offset) % sample_rate == 0
-                            })
-                    }
-                };
+                let should_sample = self.should_sample(|| {
+                    repo_path.map_or_else(
+                        || step.target.sampling_fingerprint(),
+                        |r| Some(r.sampling_fingerprint()),
+                    )
+                });
 
                 if should_sample {
                     let sampling_key = SamplingKey::new();
@@ -289,6 +358,16 @@ where
             .start_step(ctx, route.map(|_| &EmptyRoute {}), step)
     }
 
+    fn record_size(&self, route: Option<&PathTrackingRoute<P>>, step: &OutgoingEdge, size: u64) {
+        if self.is_below_min_size(step, size) {
+            let repo_path = route.and_then(|r| P::evolve_path(r.path.as_ref(), step));
+            self.sampler.unmap_keys(WalkKeyOptPath {
+                node: step.target.clone(),
+                path: repo_path.cloned(),
+            });
+        }
+    }
+
     fn visit(
         &self,
         ctx: &CoreContext,
@@ -383,16 +462,7 @@ where
         step: &OutgoingEdge,
     ) -> Option<CoreContext> {
         if self.options.node_types.contains(&step.target.get_type()) {
-            let should_sample = match self.options.sample_rate {
-                0 => false,
-                1 => true,
-                sample_rate => step
-                    .target
-                    .sampling_fingerprint()
-                    .map_or(self.options.sample_offset % sample_rate == 0, |fp| {
-                        (fp + self.options.sample_offset) % sample_rate == 0
-                    }),
-            };
+            let should_sample = self.should_sample(|| step.target.sampling_fingerprint());
 
             if should_sample {
                 let sampling_key = SamplingKey::new();
@@ -409,6 +479,15 @@ where
         self.inner.start_step(ctx, route, step)
     }
 
+    fn record_size(&self, _route: Option<&EmptyRoute>, step: &OutgoingEdge, size: u64) {
+        if self.is_below_min_size(step, size) {
+            self.sampler.unmap_keys(WalkKeyOptPath {
+                node: step.target.clone(),
+                path: None,
+            });
+        }
+    }
+
     fn visit(
         &self,
         ctx: &CoreContext,
@@ -495,6 +574,10 @@ where
         self.inflight.insert(sample_key, T::default());
         self.inflight_reverse.insert(walk_key, sample_key);
     }
+
+    fn unmap_keys(&self, walk_key: Node) {
+        self.complete_step(&walk_key);
+    }
 }
 
 impl<T, P> SampleTrigger<WalkKeyOptPath<P>> for WalkSampleMapping<Node, T>
@@ -506,6 +589,10 @@ where
         self.inflight.insert(sample_key, T::default());
         self.inflight_reverse.insert(walk_key.node, sample_key);
     }
+
+    fn unmap_keys(&self, walk_key: WalkKeyOptPath<P>) {
+        self.complete_step(&walk_key.node);
+    }
 }
 
 impl<T, P> SampleTrigger<WalkKeyOptPath<P>> for WalkSampleMapping<WalkKeyOptPath<P>, T>
@@ -517,6 +604,10 @@ where
         self.inflight.insert(sample_key, T::default());
         self.inflight_reverse.insert(walk_key, sample_key);
     }
+
+    fn unmap_keys(&self, walk_key: WalkKeyOptPath<P>) {
+        self.complete_step(&walk_key);
+    }
 }
 
 impl<S, T> WalkSampleMapping<S, T>
@@ -15,6 +15,8 @@ use std::time::Duration;
 use anyhow::format_err;
 use anyhow::Error;
 use blobstore::BlobstoreGetData;
+use blobstore::Loadable;
+use blobstore::LoadableError;
 use blobstore::SizeMetadata;
 use cloned::cloned;
 use context::CoreContext;
@@ -23,6 +25,11 @@ use derive_more::Div;
 use derive_more::Mul;
 use derive_more::Sub;
 use fbinit::FacebookInit;
+use filestore::Alias;
+use filestore::FetchKey;
+use filestore::Hasher;
+use filestore::Sha1IncrementalHasher;
+use filestore::Sha256IncrementalHasher;
 use futures::future;
 use futures::future::try_join_all;
 use futures::future::FutureExt;
@@ -31,9 +38,16 @@ use futures::stream::TryStreamExt;
 use futures::TryFutureExt;
 use metaconfig_types::BlobstoreId;
 use mononoke_types::datetime::DateTime;
+use mononoke_types::hash::Sha1;
+use mononoke_types::hash::Sha256;
+use mononoke_types::ContentId;
+use repo_blobstore::RepoBlobstore;
+use repo_blobstore::RepoBlobstoreRef;
 use repo_identity::RepoIdentityRef;
 use samplingblob::ComponentSamplingHandler;
+use slog::error;
 use slog::info;
+use slog::Logger;
 use stats::prelude::*;
 
 use crate::args::OutputFormat;
@@ -41,12 +55,18 @@ use crate::commands::JobParams;
 use crate::commands::JobWalkParams;
 use crate::commands::RepoSubcommandParams;
 use crate::commands::SCRUB;
+use crate::commands::SCRUB_SPARSE;
+use crate::detail::blob_age::BlobAgeFilter;
+use crate::detail::blob_age::BlobAgeHistogram;
+use crate::detail::graph::EdgeType;
 use crate::detail::graph::FileContentData;
 use crate::detail::graph::Node;
 use crate::detail::graph::NodeData;
 use crate::detail::graph::NodeType;
+use crate::detail::graph::WrappedPath;
 use crate::detail::graph::WrappedPathHash;
 use crate::detail::graph::WrappedPathLike;
+use crate::detail::key_map::SampleKeyMap;
 use crate::detail::log;
 use crate::detail::pack::PackInfo;
 use crate::detail::pack::PackInfoLogOptions;
@@ -65,17 +85,79 @@ use crate::detail::sampling::WalkKeyOptPath;
 use crate::detail::sampling::WalkPayloadMtime;
 use crate::detail::sampling::WalkSampleMapping;
 use crate::detail::sizing::SizingSample;
+use crate::detail::state::StepStats;
 use crate::detail::tail::walk_exact_tail;
 use crate::detail::validate::TOTAL;
 use crate::detail::walk::EmptyRoute;
 use crate::detail::walk::RepoWalkParams;
 use crate::detail::walk::RepoWalkTypeParams;
+use crate::detail::walk::StepRoute;
+use crate::detail::walk::WalkVisitor;
 
 define_stats! {
     prefix = "mononoke.walker";
     walk_progress_keys: dynamic_timeseries("{}.progress.{}.blobstore_keys", (subcommand: &'static str, repo: String); Rate, Sum),
     walk_progress_bytes: dynamic_timeseries("{}.progress.{}.blobstore_bytes", (subcommand: &'static str, repo: String); Rate, Sum),
     walk_last_completed_by_type: dynamic_singleton_counter("{}.last_completed.{}.{}.{}", (subcommand: &'static str, repo: String, node_type: &'static str, desc: &'static str)),
+    walk_sparse_verified: dynamic_timeseries("{}.sparse_verified.{}.{}", (subcommand: &'static str, repo: String, node_type: &'static str); Rate, Sum),
+    walk_content_hash_verified: dynamic_timeseries("{}.content_hash_verified.{}", (subcommand: &'static str, repo: String); Rate, Sum),
+    walk_content_hash_mismatch: dynamic_timeseries("{}.content_hash_mismatch.{}", (subcommand: &'static str, repo: String); Rate, Sum),
+}
+
+// Node types a sparse scrub will step to. None of these load file content;
+// a HgFileEnvelope only holds the filenode's metadata and content id.
+const SPARSE_SCRUB_NODE_TYPES: &[NodeType] = &[
+    NodeType::Bookmark,
+    NodeType::PublishedBookmarks,
+    NodeType::Changeset,
+    NodeType::BonsaiHgMapping,
+    NodeType::HgBonsaiMapping,
+    NodeType::HgChangeset,
+    NodeType::HgChangesetViaBonsai,
+    NodeType::HgManifest,
+    NodeType::HgFileEnvelope,
+    NodeType::HgFileNode,
+    NodeType::HgManifestFileNode,
+];
+
+// Node types that a sparse scrub checks parse, counted in their own stats
+const SPARSE_VERIFIED_NODE_TYPES: &[NodeType] = &[
+    NodeType::HgManifest,
+    NodeType::HgFileEnvelope,
+    NodeType::HgFileNode,
+    NodeType::HgManifestFileNode,
+];
+
+/// How deep a scrub goes into the graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScrubTier {
+    /// Load everything reachable in the walk, including file content.
+    #[default]
+    Deep,
+    /// Only check that hg manifests, file envelopes and filenodes load and
+    /// parse, without touching file content. Cheap enough to run far more often than Deep.
+    Sparse,
+}
+
+impl ScrubTier {
+    pub fn stats_key(&self) -> &'static str {
+        match self {
+            ScrubTier::Deep => SCRUB,
+            ScrubTier::Sparse => SCRUB_SPARSE,
+        }
+    }
+
+    /// Restrict the walk to the node and edge types this tier scrubs.
+    fn restrict_walk(
+        &self,
+        include_node_types: &mut HashSet<NodeType>,
+        include_edge_types: &mut HashSet<EdgeType>,
+    ) {
+        if *self == ScrubTier::Sparse {
+            include_node_types.retain(|t| SPARSE_SCRUB_NODE_TYPES.contains(t));
+            include_edge_types.retain(|e| include_node_types.contains(&e.outgoing_type()));
+        }
+    }
 }
 
 #[derive(Add, Div, Mul, Sub, Clone, Copy, Default, Debug)]
@@ -122,51 +204,147 @@ impl fmt::Display for ScrubStats {
     }
 }
 
+// Check hashes recomputed from file content against the stored metadata and
+// aliases, returning a description of each mismatch.
+async fn check_content_hashes(
+    ctx: &CoreContext,
+    blobstore: &RepoBlobstore,
+    content_id: ContentId,
+    sha1: Sha1,
+    sha256: Sha256,
+) -> Result<Vec<String>, Error> {
+    let mut mismatches = Vec::new();
+
+    // Metadata may not have been computed yet, which is not corruption
+    if let Some(Some(metadata)) =
+        filestore::get_metadata_readonly(blobstore, ctx, &FetchKey::Canonical(content_id)).await?
+    {
+        if metadata.sha1 != sha1 {
+            mismatches.push(format!(
+                "metadata sha1 is {}, content is {}",
+                metadata.sha1, sha1
+            ));
+        }
+        if metadata.sha256 != sha256 {
+            mismatches.push(format!(
+                "metadata sha256 is {}, content is {}",
+                metadata.sha256, sha256
+            ));
+        }
+    }
+
+    for alias in [Alias::Sha1(sha1), Alias::Sha256(sha256)] {
+        match alias.load(ctx, blobstore).await {
+            Ok(alias_content_id) if alias_content_id == content_id => {}
+            Ok(alias_content_id) => mismatches.push(format!(
+                "alias {} points to {}",
+                alias.blobstore_key(),
+                alias_content_id
+            )),
+            Err(LoadableError::Missing(key)) => {
+                mismatches.push(format!("alias {} is missing", key))
+            }
+            Err(LoadableError::Error(e)) => return Err(e),
+        }
+    }
+
+    Ok(mismatches)
+}
+
 // Force load of leaf data like file contents that graph traversal did not need
-fn loading_stream<InStream, SS, L>(
+fn loading_stream<InStream, SS, L, P>(
+    ctx: CoreContext,
+    blobstore: RepoBlobstore,
+    logger: Logger,
+    tier: ScrubTier,
+    repo_name: String,
     limit_data_fetch: bool,
+    verify_content_hashes: bool,
     scheduled_max: usize,
     s: InStream,
     sampler: Arc<WalkSampleMapping<Node, ScrubSample>>,
     output_node_types: HashSet<NodeType>,
     output_format: OutputFormat,
     pack_info_logger: Option<L>,
+    sample_key_map: Option<SampleKeyMap>,
+    blob_age_filter: BlobAgeFilter,
+    blob_age_histogram: BlobAgeHistogram,
 ) -> impl Stream<Item = Result<(Node, Option<NodeData>, Option<ScrubStats>), Error>>
 where
-    InStream: Stream<
-            Item = Result<
-                (
-                    WalkKeyOptPath<WrappedPathHash>,
-                    WalkPayloadMtime,
-                    Option<SS>,
-                ),
-                Error,
-            >,
-        >
+    InStream: Stream<Item = Result<(WalkKeyOptPath<P>, WalkPayloadMtime, Option<SS>), Error>>
         + 'static
         + Send,
     L: PackInfoLogger + 'static + Send,
+    P: WrappedPathLike + fmt::Display + 'static + Send,
 {
+    let key_map_repo_name = repo_name.clone();
     s.map_ok(move |(walk_key, payload, _progress_stats)| {
         let mtime = payload.mtime;
         match payload.data {
-            Some(NodeData::FileContent(FileContentData::ContentStream(file_bytes_stream)))
-                if !limit_data_fetch =>
-            {
-                cloned!(sampler);
-                file_bytes_stream
-                    .try_fold(0, |acc, file_bytes| future::ok(acc + file_bytes.size()))
-                    .map_ok(move |num_bytes| {
-                        let sample = sampler.complete_step(&walk_key.node);
-                        (
-                            walk_key,
-                            mtime,
-                            Some(NodeData::FileContent(FileContentData::Consumed(num_bytes))),
-                            Some(sample),
+            Some(NodeData::FileContent(FileContentData::ContentStream(
+                file_bytes_stream,
+                _size,
+            ))) if !limit_data_fetch => {
+                cloned!(ctx, blobstore, logger, repo_name, sampler);
+                // Only sampled content is hashed, as hashing everything is expensive
+                let hashers = match walk_key.node {
+                    Node::FileContent(content_id)
+                        if verify_content_hashes && sampler.is_sampling(&walk_key.node) =>
+                    {
+                        Some((
+                            content_id,
+                            Sha1IncrementalHasher::new(),
+                            Sha256IncrementalHasher::new(),
+                        ))
+                    }
+                    _ => None,
+                };
+                async move {
+                    let (num_bytes, hashers) = file_bytes_stream
+                        .try_fold((0, hashers), |(acc, mut hashers), file_bytes| {
+                            if let Some((_, sha1, sha256)) = hashers.as_mut() {
+                                sha1.update(file_bytes.as_bytes());
+                                sha256.update(file_bytes.as_bytes());
+                            }
+                            future::ok((acc + file_bytes.size(), hashers))
+                        })
+                        .await
+                        .map_err(|e| {
+                            e.context(format_err!("While scrubbing file content stream"))
+                        })?;
+
+                    if let Some((content_id, sha1, sha256)) = hashers {
+                        let stats_key = (tier.stats_key(), repo_name);
+                        let mismatches = check_content_hashes(
+                            &ctx,
+                            &blobstore,
+                            content_id,
+                            sha1.finish(),
+                            sha256.finish(),
                         )
-                    })
-                    .map_err(|e| e.context(format_err!("While scrubbing file content stream")))
-                    .left_future()
+                        .await?;
+                        STATS::walk_content_hash_verified.add_value(1, stats_key.clone());
+                        if !mismatches.is_empty() {
+                            STATS::walk_content_hash_mismatch.add_value(1, stats_key);
+                            error!(
+                                logger,
+                                #log::VALIDATE,
+                                "Corrupt content {}: {}",
+                                content_id,
+                                mismatches.join(", ")
+                            );
+                        }
+                    }
+
+                    let sample = sampler.complete_step(&walk_key.node);
+                    Ok::<_, Error>((
+                        walk_key,
+                        mtime,
+                        Some(NodeData::FileContent(FileContentData::Consumed(num_bytes))),
+                        Some(sample),
+                    ))
+                }
+                .left_future()
             }
             data_opt => {
                 if output_node_types.contains(&walk_key.node.get_type()) {
@@ -180,6 +358,13 @@ where
                         }
                     }
                 }
+                if tier == ScrubTier::Sparse && data_opt.is_some() {
+                    let node_type = walk_key.node.get_type();
+                    if SPARSE_VERIFIED_NODE_TYPES.contains(&node_type) {
+                        STATS::walk_sparse_verified
+                            .add_value(1, (SCRUB_SPARSE, repo_name.clone(), node_type.into()));
+                    }
+                }
                 let sample = data_opt
                     .as_ref()
                     .map(|_d| sampler.complete_step(&walk_key.node));
@@ -188,9 +373,22 @@ where
         }
     })
     .try_buffer_unordered(scheduled_max)
-    .map_ok(move |(walk_key, mtime, data_opt, sample)| {
-        let size = if let Some(sample) = sample {
+    .and_then(move |(walk_key, mtime, data_opt, sample)| {
+        let size = if let Some(mut sample) = sample {
+            if let Some(sample) = sample.as_mut() {
+                sample.filter_by_age(
+                    walk_key.node.get_type(),
+                    DateTime::now().timestamp_secs(),
+                    &blob_age_filter,
+                    &blob_age_histogram,
+                );
+            }
             let size = ScrubStats::from(sample.as_ref());
+            if let (Some(key_map), Some(sample)) = (sample_key_map.as_ref(), sample.as_ref()) {
+                if let Err(e) = record_sample_keys(key_map, &key_map_repo_name, &walk_key, sample) {
+                    return future::err(e);
+                }
+            }
             if let Some(logger) = pack_info_logger.as_ref() {
                 record_for_packer(logger, &walk_key, mtime, sample);
             }
@@ -198,17 +396,43 @@ where
         } else {
             None
         };
-        (walk_key.node, data_opt, size)
+        future::ok((walk_key.node, data_opt, size))
     })
 }
 
-fn record_for_packer<L>(
+fn record_sample_keys<P>(
+    key_map: &SampleKeyMap,
+    repo_name: &str,
+    walk_key: &WalkKeyOptPath<P>,
+    sample: &ScrubSample,
+) -> Result<(), Error>
+where
+    P: WrappedPathLike + fmt::Display,
+{
+    let path = walk_key.path.as_ref().map(|p| p.to_string());
+    let node_type = walk_key.node.get_type();
+    for (blobstore_key, store_to_key_sizes) in &sample.data {
+        for blobstore_id in store_to_key_sizes.keys() {
+            key_map.record(
+                repo_name,
+                path.as_deref(),
+                node_type,
+                *blobstore_id,
+                blobstore_key,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn record_for_packer<L, P>(
     logger: &L,
-    walk_key: &WalkKeyOptPath<WrappedPathHash>,
+    walk_key: &WalkKeyOptPath<P>,
     mtime: Option<DateTime>,
     sample: Option<ScrubSample>,
 ) where
     L: PackInfoLogger,
+    P: WrappedPathLike,
 {
     if let Some(sample) = sample {
         for (blobstore_key, store_to_key_sizes) in sample.data {
@@ -218,7 +442,7 @@ fn record_for_packer<L>(
                     blobstore_key: blobstore_key.as_str(),
                     node_type: walk_key.node.get_type(),
                     node_fingerprint: walk_key.node.sampling_fingerprint(),
-                    similarity_key: walk_key.path.map(|p| p.sampling_fingerprint()),
+                    similarity_key: walk_key.path.as_ref().map(|p| p.sampling_fingerprint()),
                     mtime: mtime.map(|mtime| mtime.timestamp_secs() as u64),
                     uncompressed_size: key_sample.unique_uncompressed_size,
                     sizes: key_sample.sizes,
@@ -253,6 +477,33 @@ impl Default for ScrubSample {
     }
 }
 
+impl ScrubSample {
+    // Drop the keys created outside the filter's age range, and record the
+    // ages of those kept.  Keys without a creation time are always kept.
+    fn filter_by_age(
+        &mut self,
+        node_type: NodeType,
+        now_secs: i64,
+        filter: &BlobAgeFilter,
+        histogram: &BlobAgeHistogram,
+    ) {
+        self.data.retain(|_key, store_to_key_sample| {
+            store_to_key_sample.retain(|_blobstore_id, key_sample| match key_sample.ctime {
+                Some(ctime) => {
+                    let age_secs = now_secs - ctime;
+                    let keep = filter.matches(age_secs);
+                    if keep {
+                        histogram.record(node_type, age_secs);
+                    }
+                    keep
+                }
+                None => true,
+            });
+            !store_to_key_sample.is_empty()
+        });
+    }
+}
+
 impl ComponentSamplingHandler for WalkSampleMapping<Node, ScrubSample> {
     fn sample_get(
         &self,
@@ -403,17 +654,26 @@ impl ProgressReporterUnprotected for ProgressStateCountByType<ScrubStats, ScrubS
 
 #[derive(Clone)]
 pub struct ScrubCommand {
+    pub tier: ScrubTier,
     pub limit_data_fetch: bool,
+    pub verify_content_hashes: bool,
     pub output_format: OutputFormat,
     pub output_node_types: HashSet<NodeType>,
     pub progress_options: ProgressOptions,
     pub sampling_options: SamplingOptions,
     pub pack_info_log_options: Option<PackInfoLogOptions>,
+    pub sample_key_map: Option<SampleKeyMap>,
+    pub blob_age_filter: BlobAgeFilter,
+    pub blob_age_histogram: BlobAgeHistogram,
     pub sampler: Arc<WalkSampleMapping<Node, ScrubSample>>,
 }
 
 impl ScrubCommand {
-    fn apply_repo(&mut self, repo_params: &RepoWalkParams) {
+    fn apply_repo(&mut self, repo_params: &mut RepoWalkParams) {
+        self.tier.restrict_walk(
+            &mut repo_params.include_node_types,
+            &mut repo_params.include_edge_types,
+        );
         self.sampling_options
             .retain_or_default(&repo_params.include_node_types);
     }
@@ -432,10 +692,10 @@ pub async fn scrub_objects(
     } = job_params;
 
     let mut all_walks = Vec::new();
-    for (sub_params, repo_params) in per_repo {
+    for (sub_params, mut repo_params) in per_repo {
         cloned!(mut command, walk_params);
 
-        command.apply_repo(&repo_params);
+        command.apply_repo(&mut repo_params);
 
         let walk = run_one(
             fb,
@@ -455,50 +715,45 @@ async fn run_one(
     job_params: JobWalkParams,
     sub_params: RepoSubcommandParams,
     repo_params: RepoWalkParams,
-    command: ScrubCommand,
+    mut command: ScrubCommand,
     cancellation_requested: Arc<AtomicBool>,
 ) -> Result<(), Error> {
+    let repo_id = repo_params.repo.repo_identity().id();
+    let sample_rotation = sub_params.tail_params.sample_rotation.clone();
+    if let Some(rotation) = &sample_rotation {
+        command.sampling_options.sample_offset = rotation
+            .next_offset(
+                &repo_params.logger,
+                repo_id,
+                command.sampling_options.sample_rate,
+            )
+            .await?;
+    }
+    let sample_offset = command.sampling_options.sample_offset;
+    let budget = job_params.budget.clone();
+    // Each repo gets its own histogram.
+    command.blob_age_histogram = BlobAgeHistogram::default();
+
     let sizing_progress_state =
         ProgressStateMutex::new(ProgressStateCountByType::<ScrubStats, ScrubStats>::new(
             fb,
             repo_params.logger.clone(),
-            SCRUB,
+            command.tier.stats_key(),
             repo_params.repo.repo_identity().name().to_string(),
             command.sampling_options.node_types.clone(),
             command.progress_options,
         ));
 
-    let make_sink = {
-        cloned!(command, job_params.quiet, sub_params.progress_state,);
-        move |ctx: &CoreContext, repo_params: &RepoWalkParams| {
-            let repo_name = repo_params.repo.repo_identity().name().to_string();
-            cloned!(ctx, repo_params.scheduled_max);
-            async move |walk_output, run_start, chunk_num, checkpoint_name| {
-                let walk_progress = progress_stream(quiet, &progress_state, walk_output);
-                let loading = loading_stream(
-                    command.limit_data_fetch,
-                    scheduled_max,
-                    walk_progress,
-                    command.sampler,
-                    command.output_node_types,
-                    command.output_format,
-                    command
-                        .pack_info_log_options
-                        .map(|o| o.make_logger(repo_name, run_start, chunk_num, checkpoint_name)),
-                );
-                let report_sizing = progress_stream(quiet, &sizing_progress_state, loading);
-
-                report_state(ctx, report_sizing).await?;
-                sizing_progress_state.report_progress();
-                progress_state.report_progress();
-                Ok(())
-            }
-        }
-    };
-
     let mut stream_node_types = command.output_node_types.clone();
-    if !command.limit_data_fetch {
-        stream_node_types.insert(NodeType::FileContent);
+    match command.tier {
+        ScrubTier::Deep if !command.limit_data_fetch => {
+            stream_node_types.insert(NodeType::FileContent);
+        }
+        ScrubTier::Deep => {}
+        ScrubTier::Sparse => {
+            // Need the parsed data so we can count what was verified
+            stream_node_types.extend(SPARSE_VERIFIED_NODE_TYPES.iter().copied());
+        }
     }
     if command.pack_info_log_options.is_some() {
         // Need these to be able to see and log the commit time stamps
@@ -510,9 +765,9 @@ async fn run_one(
     let walk_state = SamplingWalkVisitor::new(
         repo_params.include_node_types.clone(),
         repo_params.include_edge_types.clone(),
-        command.sampling_options,
+        command.sampling_options.clone(),
         None,
-        command.sampler,
+        command.sampler.clone(),
         job_params.enable_derive,
         sub_params
             .tail_params
@@ -521,35 +776,208 @@ async fn run_one(
             .map(|v| v.direction),
     );
 
+    // Paths are needed to log pack info, and to map them to keys.
+    let keep_edge_paths =
+        command.pack_info_log_options.is_some() || command.sample_key_map.is_some();
     let type_params = RepoWalkTypeParams {
         required_node_data_types,
         always_emit_edge_types: HashSet::new(),
-        keep_edge_paths: command.pack_info_log_options.is_some(),
+        keep_edge_paths,
     };
 
-    if command.pack_info_log_options.is_some() {
-        walk_exact_tail::<_, _, _, _, _, PathTrackingRoute<WrappedPathHash>>(
+    let sample_key_map = command.sample_key_map.clone();
+    if sample_key_map.is_some() {
+        walk_and_load::<WrappedPath, PathTrackingRoute<WrappedPath>>(
             fb,
             job_params,
+            sub_params,
+            repo_params,
+            type_params,
+            walk_state,
+            command,
+            sizing_progress_state,
+            cancellation_requested.clone(),
+        )
+        .await?;
+    } else if command.pack_info_log_options.is_some() {
+        walk_and_load::<WrappedPathHash, PathTrackingRoute<WrappedPathHash>>(
+            fb,
+            job_params,
+            sub_params,
             repo_params,
             type_params,
-            sub_params.tail_params,
             walk_state,
-            make_sink,
-            cancellation_requested,
+            command,
+            sizing_progress_state,
+            cancellation_requested.clone(),
         )
-        .await
+        .await?;
     } else {
-        walk_exact_tail::<_, _, _, _, _, EmptyRoute>(
+        walk_and_load::<WrappedPathHash, EmptyRoute>(
             fb,
             job_params,
+            sub_params,
             repo_params,
             type_params,
-            sub_params.tail_params,
             walk_state,
-            make_sink,
-            cancellation_requested,
+            command,
+            sizing_progress_state,
+            cancellation_requested.clone(),
         )
-        .await
+        .await?;
+    }
+    if let Some(sample_key_map) = sample_key_map {
+        sample_key_map.flush()?;
+    }
+
+    if let Some(rotation) = sample_rotation {
+        rotation
+            .record_if_complete(repo_id, sample_offset, &budget, &cancellation_requested)
+            .await?;
+    }
+    Ok(())
+}
+
+// Walk with the given route, loading the data walked to and recording it.
+async fn walk_and_load<P, Route>(
+    fb: FacebookInit,
+    job_params: JobWalkParams,
+    sub_params: RepoSubcommandParams,
+    repo_params: RepoWalkParams,
+    type_params: RepoWalkTypeParams,
+    walk_state: SamplingWalkVisitor<WalkSampleMapping<Node, ScrubSample>>,
+    command: ScrubCommand,
+    sizing_progress_state: ProgressStateMutex<ProgressStateCountByType<ScrubStats, ScrubStats>>,
+    cancellation_requested: Arc<AtomicBool>,
+) -> Result<(), Error>
+where
+    P: WrappedPathLike + fmt::Display + 'static + Send,
+    Route: 'static + Send + Clone + StepRoute,
+    SamplingWalkVisitor<WalkSampleMapping<Node, ScrubSample>>:
+        WalkVisitor<(WalkKeyOptPath<P>, WalkPayloadMtime, Option<StepStats>), Route>,
+{
+    let make_sink = {
+        cloned!(command, job_params.quiet, sub_params.progress_state,);
+        move |ctx: &CoreContext, repo_params: &RepoWalkParams| {
+            let stats_key = command.tier.stats_key();
+            let repo_name = repo_params.repo.repo_identity().name().to_string();
+            cloned!(
+                ctx,
+                repo_params.repo,
+                repo_params.logger,
+                repo_params.scheduled_max
+            );
+            async move |walk_output, run_start, chunk_num, checkpoint_name| {
+                let walk_progress = progress_stream(quiet, &progress_state, walk_output);
+                let pack_info_logger = command.pack_info_log_options.map(|o| {
+                    o.make_logger(
+                        logger.clone(),
+                        repo_name.clone(),
+                        run_start,
+                        chunk_num,
+                        checkpoint_name,
+                    )
+                });
+                let loading = loading_stream(
+                    ctx.clone(),
+                    repo.repo_blobstore().clone(),
+                    logger.clone(),
+                    command.tier,
+                    repo_name.clone(),
+                    command.limit_data_fetch,
+                    command.verify_content_hashes,
+                    scheduled_max,
+                    walk_progress,
+                    command.sampler,
+                    command.output_node_types,
+                    command.output_format,
+                    pack_info_logger,
+                    command.sample_key_map,
+                    command.blob_age_filter,
+                    command.blob_age_histogram.clone(),
+                );
+                let report_sizing = progress_stream(quiet, &sizing_progress_state, loading);
+
+                report_state(ctx.clone(), report_sizing).await?;
+                sizing_progress_state.report_progress();
+                progress_state.report_progress();
+                command
+                    .blob_age_histogram
+                    .report(ctx.fb, &logger, stats_key, &repo_name);
+                Ok(())
+            }
+        }
+    };
+
+    walk_exact_tail::<_, _, _, _, _, Route>(
+        fb,
+        job_params,
+        repo_params,
+        type_params,
+        sub_params.tail_params,
+        walk_state,
+        make_sink,
+        cancellation_requested,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn test_scrub_tier_restrict_walk() {
+        let mut node_types: HashSet<NodeType> = NodeType::iter().collect();
+        let mut edge_types: HashSet<EdgeType> = EdgeType::iter().collect();
+        ScrubTier::Deep.restrict_walk(&mut node_types, &mut edge_types);
+        assert_eq!(node_types.len(), NodeType::iter().count());
+        assert_eq!(edge_types.len(), EdgeType::iter().count());
+
+        ScrubTier::Sparse.restrict_walk(&mut node_types, &mut edge_types);
+        assert_eq!(
+            node_types,
+            SPARSE_SCRUB_NODE_TYPES
+                .iter()
+                .copied()
+                .collect::<HashSet<_>>()
+        );
+        assert!(!node_types.contains(&NodeType::FileContent));
+        assert!(edge_types
+            .iter()
+            .all(|e| node_types.contains(&e.outgoing_type())));
+        assert!(edge_types.contains(&EdgeType::HgManifestToHgFileNode));
+        assert!(edge_types.contains(&EdgeType::HgManifestToHgFileEnvelope));
+        assert!(!edge_types.contains(&EdgeType::HgFileEnvelopeToFileContent));
+    }
+
+    #[test]
+    fn test_scrub_tier_restrict_walk_keeps_exclusions() {
+        // Types already excluded from the walk stay excluded
+        let mut node_types: HashSet<NodeType> = [
+            NodeType::Bookmark,
+            NodeType::Changeset,
+            NodeType::FileContent,
+        ]
+        .into_iter()
+        .collect();
+        let mut edge_types: HashSet<EdgeType> = EdgeType::iter().collect();
+        ScrubTier::Sparse.restrict_walk(&mut node_types, &mut edge_types);
+        assert_eq!(
+            node_types,
+            [NodeType::Bookmark, NodeType::Changeset]
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+        assert!(edge_types.contains(&EdgeType::BookmarkToChangeset));
+        assert!(!edge_types.contains(&EdgeType::ChangesetToBonsaiHgMapping));
+    }
+
+    #[test]
+    fn test_scrub_tier_stats_key() {
+        assert_eq!(ScrubTier::Deep.stats_key(), SCRUB);
+        assert_eq!(ScrubTier::Sparse.stats_key(), SCRUB_SPARSE);
     }
 }
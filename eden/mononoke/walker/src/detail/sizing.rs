@@ -13,6 +13,7 @@ use std::io::Cursor;
 use std::io::Write;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use anyhow::Error;
@@ -39,6 +40,9 @@ use mononoke_types::BlobstoreBytes;
 use repo_identity::RepoIdentityRef;
 use samplingblob::SamplingHandler;
 use slog::info;
+use slog::warn;
+use slog::Logger;
+use zstd::dict::EncoderDictionary;
 
 use crate::commands::JobParams;
 use crate::commands::JobWalkParams;
@@ -70,15 +74,17 @@ use crate::detail::walk::RepoWalkTypeParams;
 struct SizingStats {
     raw: u64,
     compressed: u64,
+    // Only blobs compressed with a trained dictionary, so excluding those
+    // used to train it.
+    dict_raw: u64,
+    dict_compressed: u64,
 }
 
-impl SizingStats {
-    fn compression_benefit_pct(&self) -> u64 {
-        if self.raw == 0 {
-            0
-        } else {
-            100 * (self.raw - self.compressed) / self.raw
-        }
+fn benefit_pct(raw: u64, compressed: u64) -> u64 {
+    if raw == 0 {
+        0
+    } else {
+        100 * (raw - compressed) / raw
     }
 }
 
@@ -86,10 +92,13 @@ impl fmt::Display for SizingStats {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         write!(
             fmt,
-            "{},{},{}%",
+            "{},{},{}%,{},{},{}%",
             self.raw,
             self.compressed,
-            self.compression_benefit_pct()
+            benefit_pct(self.raw, self.compressed),
+            self.dict_raw,
+            self.dict_compressed,
+            benefit_pct(self.dict_raw, self.dict_compressed),
         )
     }
 }
@@ -102,7 +111,128 @@ fn try_compress(raw_data: &Bytes, compressor_type: CompressorType) -> Result<Siz
     let compressed_buf = compressor.try_finish().map_err(|(_encoder, e)| e)?;
     // Assume we wouldn't compress if its bigger
     let compressed = min(raw, compressed_buf.total_thru());
-    Ok(SizingStats { raw, compressed })
+    Ok(SizingStats {
+        raw,
+        compressed,
+        ..Default::default()
+    })
+}
+
+/// Which node types to train zstd dictionaries for, and how.
+#[derive(Clone, Debug)]
+pub struct DictionaryOptions {
+    pub node_types: HashSet<NodeType>,
+    pub training_samples: usize,
+    pub max_size: usize,
+}
+
+enum Dictionary {
+    Training(Vec<Bytes>),
+    // None if training failed, e.g. because the samples were too small
+    Trained(Option<Arc<EncoderDictionary<'static>>>),
+}
+
+/// Per node type zstd dictionaries, each trained on the first samples of its
+/// type and then used to compress the later ones.
+struct DictionaryExperiments {
+    logger: Logger,
+    compression_level: i32,
+    training_samples: usize,
+    max_size: usize,
+    dictionaries: HashMap<NodeType, Mutex<Dictionary>>,
+}
+
+impl DictionaryExperiments {
+    fn new(logger: Logger, compression_level: i32, options: &DictionaryOptions) -> Self {
+        Self {
+            logger,
+            compression_level,
+            training_samples: options.training_samples,
+            max_size: options.max_size,
+            dictionaries: options
+                .node_types
+                .iter()
+                .map(|t| (*t, Mutex::new(Dictionary::Training(Vec::new()))))
+                .collect(),
+        }
+    }
+
+    fn dictionary(
+        &self,
+        node_type: NodeType,
+        raw_data: &Bytes,
+    ) -> Option<Arc<EncoderDictionary<'static>>> {
+        let mut dictionary = self
+            .dictionaries
+            .get(&node_type)?
+            .lock()
+            .expect("lock poisoned");
+        match &mut *dictionary {
+            Dictionary::Trained(dict) => dict.clone(),
+            Dictionary::Training(samples) => {
+                samples.push(raw_data.clone());
+                if samples.len() >= self.training_samples {
+                    let trained = match zstd::dict::from_samples(samples.as_slice(), self.max_size)
+                    {
+                        Ok(dict) => {
+                            info!(
+                                self.logger,
+                                "Trained {} byte dictionary for {} from {} samples",
+                                dict.len(),
+                                node_type,
+                                samples.len(),
+                            );
+                            Some(Arc::new(EncoderDictionary::copy(
+                                &dict,
+                                self.compression_level,
+                            )))
+                        }
+                        Err(e) => {
+                            warn!(
+                                self.logger,
+                                "Could not train dictionary for {}: {:?}", node_type, e
+                            );
+                            None
+                        }
+                    };
+                    *dictionary = Dictionary::Trained(trained);
+                }
+                // Don't measure the dictionary against its own training data
+                None
+            }
+        }
+    }
+
+    fn try_compress(&self, node_type: NodeType, raw_data: &Bytes) -> Result<SizingStats, Error> {
+        let dict = match self.dictionary(node_type, raw_data) {
+            Some(dict) => dict,
+            None => return Ok(SizingStats::default()),
+        };
+        let dict_raw = raw_data.len() as u64;
+        let mut compressor = zstd::bulk::Compressor::with_prepared_dictionary(&dict)?;
+        let compressed = compressor.compress(raw_data)?;
+        Ok(SizingStats {
+            dict_raw,
+            dict_compressed: min(dict_raw, compressed.len() as u64),
+            ..Default::default()
+        })
+    }
+}
+
+fn sample_sizes(
+    node_type: NodeType,
+    sizing_sample: SizingSample,
+    compressor_type: CompressorType,
+    dictionaries: &DictionaryExperiments,
+) -> Result<SizingStats, Error> {
+    sizing_sample
+        .data
+        .values()
+        .try_fold(SizingStats::default(), |acc, v| {
+            let sizes = try_compress(v.as_bytes(), compressor_type)?;
+            let dict_sizes = dictionaries.try_compress(node_type, v.as_bytes())?;
+            Ok(acc + sizes + dict_sizes)
+        })
 }
 
 // Force load of leaf data and check compression ratio
@@ -110,6 +240,7 @@ fn size_sampling_stream<InStream, InStats>(
     scheduled_max: usize,
     s: InStream,
     compressor_type: CompressorType,
+    dictionaries: Arc<DictionaryExperiments>,
     sampler: Arc<WalkSampleMapping<Node, SizingSample>>,
 ) -> impl Stream<Item = Result<(Node, Option<NodeData>, Option<SizingStats>), Error>>
 where
@@ -137,23 +268,23 @@ where
                         future::ok(_num_loaded_bytes).left_future()
                     }
                     // Consume the stream to make sure we loaded all blobs
-                    FileContentData::ContentStream(file_bytes_stream) => file_bytes_stream
+                    FileContentData::ContentStream(file_bytes_stream, _size) => file_bytes_stream
                         .try_fold(0, |acc, file_bytes| future::ok(acc + file_bytes.size()))
                         .right_future(),
                 }
                 .and_then({
-                    cloned!(sampler);
+                    cloned!(sampler, dictionaries);
                     move |fs_stream_size| {
                         // Report the blobstore sizes in sizing stats, more accurate than stream sizes, as headers included
+                        let node_type = walk_key.node.get_type();
                         let sizes = sampler
                             .complete_step(&walk_key.node)
                             .map(|sizing_sample| {
-                                sizing_sample.data.values().try_fold(
-                                    SizingStats::default(),
-                                    |acc, v| {
-                                        try_compress(v.as_bytes(), compressor_type)
-                                            .map(|sizes| acc + sizes)
-                                    },
+                                sample_sizes(
+                                    node_type,
+                                    sizing_sample,
+                                    compressor_type,
+                                    &dictionaries,
                                 )
                             })
                             .transpose();
@@ -173,16 +304,13 @@ where
                 .left_future()
             }
             (_, data_opt) => {
-                // Report the blobstore sizes in sizing stats, more accurate than stream sizes, as headers included
+                // Report the blobstore sizes in sizing stats, more accurate than stream sizes, as headers
+                // included.  For HgManifest and HgFileEnvelope this is the serialized blob.
+                let node_type = walk_key.node.get_type();
                 let sizes = sampler
                     .complete_step(&walk_key.node)
                     .map(|sizing_sample| {
-                        sizing_sample
-                            .data
-                            .values()
-                            .try_fold(SizingStats::default(), |acc, v| {
-                                try_compress(v.as_bytes(), compressor_type).map(|sizes| acc + sizes)
-                            })
+                        sample_sizes(node_type, sizing_sample, compressor_type, &dictionaries)
                     })
                     .transpose();
 
@@ -239,7 +367,7 @@ impl ProgressStateCountByType<SizingStats, SizingStats> {
 
         info!(
             self.params.logger,
-            "Raw/s,Compressed/s,Raw,Compressed,%Saving; Delta {:06}/s,{:06}/s,{},{}s; Run {:06}/s,{:06}/s,{},{}s; Type:Raw,Compressed,%Saving {}",
+            "Raw/s,Compressed/s,Raw,Compressed,%Saving,DictRaw,DictCompressed,%DictSaving; Delta {:06}/s,{:06}/s,{},{}s; Run {:06}/s,{:06}/s,{},{}s; Type:Raw,Compressed,%Saving,DictRaw,DictCompressed,%DictSaving {}",
             delta_summary_per_s.raw,
             delta_summary_per_s.compressed,
             delta_summary,
@@ -300,6 +428,7 @@ impl SamplingHandler for WalkSampleMapping<Node, SizingSample> {
 #[derive(Clone)]
 pub struct SizingCommand {
     pub compression_level: i32,
+    pub dictionary_options: DictionaryOptions,
     pub progress_options: ProgressOptions,
     pub sampling_options: SamplingOptions,
     pub sampler: Arc<WalkSampleMapping<Node, SizingSample>>,
@@ -348,9 +477,23 @@ async fn run_one(
     job_params: JobWalkParams,
     sub_params: RepoSubcommandParams,
     repo_params: RepoWalkParams,
-    command: SizingCommand,
+    mut command: SizingCommand,
     cancellation_requested: Arc<AtomicBool>,
 ) -> Result<(), Error> {
+    let repo_id = repo_params.repo.repo_identity().id();
+    let sample_rotation = sub_params.tail_params.sample_rotation.clone();
+    if let Some(rotation) = &sample_rotation {
+        command.sampling_options.sample_offset = rotation
+            .next_offset(
+                &repo_params.logger,
+                repo_id,
+                command.sampling_options.sample_rate,
+            )
+            .await?;
+    }
+    let sample_offset = command.sampling_options.sample_offset;
+    let budget = job_params.budget.clone();
+
     let sizing_progress_state =
         ProgressStateMutex::new(ProgressStateCountByType::<SizingStats, SizingStats>::new(
             fb,
@@ -361,12 +504,25 @@ async fn run_one(
             command.progress_options,
         ));
 
+    // Dictionaries are per repo, as blobs from different repos may not be
+    // alike.
+    let dictionaries = Arc::new(DictionaryExperiments::new(
+        repo_params.logger.clone(),
+        command.compression_level,
+        &command.dictionary_options,
+    ));
+
     let make_sink = {
-        cloned!(command, job_params.quiet, sub_params.progress_state,);
+        cloned!(
+            command,
+            job_params.quiet,
+            sub_params.progress_state,
+            dictionaries
+        );
         move |ctx: &CoreContext, repo_params: &RepoWalkParams| {
             cloned!(ctx, repo_params.scheduled_max);
             async move |walk_output, _run_start, _chunk_num, _checkpoint_name| {
-                cloned!(ctx, sizing_progress_state);
+                cloned!(ctx, sizing_progress_state, dictionaries);
                 // Sizing doesn't use mtime, so remove it from payload
                 let walk_progress = progress_stream(quiet, &progress_state, walk_output).map_ok(
                     |(key, payload, stats): (_, WalkPayloadMtime, _)| (key, payload.data, stats),
@@ -378,6 +534,7 @@ async fn run_one(
                     CompressorType::Zstd {
                         level: command.compression_level,
                     },
+                    dictionaries,
                     command.sampler,
                 );
                 let report_sizing = progress_stream(quiet, &sizing_progress_state, compressor);
@@ -418,7 +575,14 @@ async fn run_one(
         sub_params.tail_params,
         walk_state,
         make_sink,
-        cancellation_requested,
+        cancellation_requested.clone(),
     )
-    .await
+    .await?;
+
+    if let Some(rotation) = sample_rotation {
+        rotation
+            .record_if_complete(repo_id, sample_offset, &budget, &cancellation_requested)
+            .await?;
+    }
+    Ok(())
 }
@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use regex::RegexSet;
+
+use crate::detail::graph::Node;
+
+const REGEX_PREFIX: &str = "regex:";
+
+/// Keys of nodes that are known to be bad (e.g. corrupt legacy blobs), which
+/// the walker should not attempt to load.
+///
+/// Keys are matched against the node's stats key, which for most node types is
+/// its blobstore key.
+#[derive(Debug, Default)]
+pub struct KnownBadKeys {
+    keys: HashSet<String>,
+    patterns: Option<RegexSet>,
+}
+
+impl KnownBadKeys {
+    /// Load keys from a file with one key per line.  Lines starting with
+    /// `regex:` are treated as regexes to match keys against.  Empty lines and
+    /// lines starting with `#` are ignored.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read skip keys file {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid skip keys file {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self, Error> {
+        let mut keys = HashSet::new();
+        let mut patterns = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.strip_prefix(REGEX_PREFIX) {
+                Some(pattern) => patterns.push(pattern.to_string()),
+                None => {
+                    keys.insert(line.to_string());
+                }
+            }
+        }
+        let patterns = if patterns.is_empty() {
+            None
+        } else {
+            Some(RegexSet::new(&patterns).context("Invalid regex")?)
+        };
+        Ok(Self { keys, patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.patterns.is_none()
+    }
+
+    pub fn contains(&self, node: &Node) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let key = node.stats_key();
+        self.keys.contains(&key)
+            || self
+                .patterns
+                .as_ref()
+                .map_or(false, |patterns| patterns.is_match(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bookmarks::BookmarkKey;
+    use mononoke_types::ContentId;
+
+    use super::*;
+
+    fn bookmark(name: &str) -> Node {
+        Node::Bookmark(BookmarkKey::new(name).expect("valid bookmark"))
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), Error> {
+        let content_id = ContentId::from_bytes([1; 32])?;
+        let skip_keys = KnownBadKeys::parse(
+            "# comment\n\
+             \n\
+             \x20 bad_bookmark \n\
+             regex:^content\\.blake2\\.0101\n\
+             regex:^prefix_\n",
+        )?;
+        assert!(!skip_keys.is_empty());
+        // Keys are trimmed
+        assert!(skip_keys.contains(&bookmark("bad_bookmark")));
+        assert!(!skip_keys.contains(&bookmark("good_bookmark")));
+        // Comments are not keys
+        assert!(!skip_keys.contains(&bookmark("# comment")));
+        // Regexes match within the key
+        assert!(skip_keys.contains(&Node::FileContent(content_id)));
+        assert!(skip_keys.contains(&bookmark("prefix_bookmark")));
+        assert!(!skip_keys.contains(&bookmark("not_prefix_bookmark")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_empty() -> Result<(), Error> {
+        let skip_keys = KnownBadKeys::parse("# only comments\n\n")?;
+        assert!(skip_keys.is_empty());
+        assert!(!skip_keys.contains(&bookmark("any")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        assert!(KnownBadKeys::parse("regex:(unclosed\n").is_err());
+    }
+}
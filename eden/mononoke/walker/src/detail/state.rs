@@ -34,6 +34,7 @@ use mercurial_types::HgFileNodeId;
 use mercurial_types::HgManifestId;
 use mononoke_types::BasenameSuffixSkeletonManifestId;
 use mononoke_types::ChangesetId;
+use mononoke_types::ContentChunkId;
 use mononoke_types::ContentId;
 use mononoke_types::DeletedManifestV2Id;
 use mononoke_types::FastlogBatchId;
@@ -71,8 +72,11 @@ pub struct StepStats {
     pub error_count: usize,
     pub missing_count: usize,
     pub hash_validation_failure_count: usize,
+    pub skipped_known_bad_count: usize,
+    pub redacted_count: usize,
     pub num_expanded_new: usize,
     pub visited_of_type: usize,
+    pub deduped_of_type: usize,
 }
 
 impl Add<StepStats> for StepStats {
@@ -83,8 +87,11 @@ impl Add<StepStats> for StepStats {
             missing_count: self.missing_count + other.missing_count,
             hash_validation_failure_count: self.hash_validation_failure_count
                 + other.hash_validation_failure_count,
+            skipped_known_bad_count: self.skipped_known_bad_count + other.skipped_known_bad_count,
+            redacted_count: self.redacted_count + other.redacted_count,
             num_expanded_new: self.num_expanded_new + other.num_expanded_new,
             visited_of_type: cmp::max(self.visited_of_type, other.visited_of_type),
+            deduped_of_type: cmp::max(self.deduped_of_type, other.deduped_of_type),
         }
     }
 }
@@ -214,9 +221,11 @@ pub struct WalkState {
     public_not_visited: StateMap<InternedId<ChangesetId>>,
     visited_bcs_phase: StateMap<InternedId<ChangesetId>>,
     visited_file: StateMap<ContentId>,
+    visited_file_chunk: StateMap<ContentChunkId>,
     visited_hg_cs: StateMap<InternedId<HgChangesetId>>,
     visited_hg_cs_mapping: StateMap<InternedId<HgChangesetId>>,
     visited_hg_cs_via_bonsai: StateMap<InternedId<HgChangesetId>>,
+    visited_hg_convert_revision: StateMap<InternedId<HgChangesetId>>,
     visited_hg_file_envelope: StateMap<InternedId<HgFileNodeId>>,
     visited_hg_filenode: StateMap<(InternedId<WrappedPathHash>, InternedId<HgFileNodeId>)>,
     visited_hg_manifest_filenode: StateMap<(InternedId<WrappedPathHash>, InternedId<HgFileNodeId>)>,
@@ -241,6 +250,8 @@ pub struct WalkState {
     visited_unode_mapping: StateMap<InternedId<ChangesetId>>,
     // Count
     visit_count: [AtomicUsize; NodeType::COUNT],
+    // Checks that found the node already visited
+    dedup_count: [AtomicUsize; NodeType::COUNT],
 }
 
 impl WalkState {
@@ -277,9 +288,11 @@ impl WalkState {
             public_not_visited: StateMap::with_hasher(fac.clone()),
             visited_bcs_phase: StateMap::with_hasher(fac.clone()),
             visited_file: StateMap::with_hasher(fac.clone()),
+            visited_file_chunk: StateMap::with_hasher(fac.clone()),
             visited_hg_cs: StateMap::with_hasher(fac.clone()),
             visited_hg_cs_mapping: StateMap::with_hasher(fac.clone()),
             visited_hg_cs_via_bonsai: StateMap::with_hasher(fac.clone()),
+            visited_hg_convert_revision: StateMap::with_hasher(fac.clone()),
             visited_hg_file_envelope: StateMap::with_hasher(fac.clone()),
             visited_hg_filenode: StateMap::with_hasher(fac.clone()),
             visited_hg_manifest_filenode: StateMap::with_hasher(fac.clone()),
@@ -304,6 +317,7 @@ impl WalkState {
             visited_unode_mapping: StateMap::with_hasher(fac),
             // Count
             visit_count: array_init(|_i| AtomicUsize::new(0)),
+            dedup_count: array_init(|_i| AtomicUsize::new(0)),
         }
     }
 
@@ -414,6 +428,10 @@ impl WalkState {
         self.visit_count[*t as usize].load(Ordering::Acquire)
     }
 
+    fn get_dedup_count(&self, t: &NodeType) -> usize {
+        self.dedup_count[*t as usize].load(Ordering::Acquire)
+    }
+
     fn chunk_contains(&self, id: InternedId<ChangesetId>) -> bool {
         if self.chunk_bcs.is_empty() {
             true
@@ -443,6 +461,7 @@ impl WalkState {
                 self.clear_mapping(NodeType::HgChangeset);
                 self.clear_mapping(NodeType::HgBonsaiMapping);
                 self.clear_mapping(NodeType::HgChangesetViaBonsai);
+                self.clear_mapping(NodeType::HgConvertRevision);
             }
             InternedType::HgFileNodeId => {
                 self.hg_filenode_ids.clear();
@@ -480,12 +499,14 @@ impl WalkState {
             NodeType::HgBonsaiMapping => self.visited_hg_cs_mapping.clear(),
             NodeType::HgChangeset => self.visited_hg_cs.clear(),
             NodeType::HgChangesetViaBonsai => self.visited_hg_cs_via_bonsai.clear(),
+            NodeType::HgConvertRevision => self.visited_hg_convert_revision.clear(),
             NodeType::HgManifest => self.visited_hg_manifest.clear(),
             NodeType::HgFileNode => self.visited_hg_filenode.clear(),
             NodeType::HgManifestFileNode => self.visited_hg_manifest_filenode.clear(),
             NodeType::HgFileEnvelope => self.visited_hg_file_envelope.clear(),
             // Content
             NodeType::FileContent => self.visited_file.clear(),
+            NodeType::FileContentChunk => self.visited_file_chunk.clear(),
             NodeType::FileContentMetadataV2 => {} // reached via expand_checked_nodes
             NodeType::AliasContentMapping => {}   // reached via expand_checked_nodes
             // Derived
@@ -573,6 +594,11 @@ impl WalkState {
                 &self.visited_hg_cs_via_bonsai,
                 &self.hg_cs_ids.interned(&k.inner),
             ),
+            (Node::HgConvertRevision(_), true) => true,
+            (Node::HgConvertRevision(k), false) => self.record(
+                &self.visited_hg_convert_revision,
+                &self.hg_cs_ids.interned(&k.inner),
+            ),
             (Node::HgManifest(_), true) => true,
             (Node::HgManifest(k), false) => self.record_with_path(
                 &self.visited_hg_manifest,
@@ -596,6 +622,10 @@ impl WalkState {
             // Content
             (Node::FileContent(_), true) => true,
             (Node::FileContent(content_id), false) => self.record(&self.visited_file, content_id),
+            (Node::FileContentChunk(_), true) => true,
+            (Node::FileContentChunk(chunk_id), false) => {
+                self.record(&self.visited_file_chunk, chunk_id)
+            }
             (Node::FileContentMetadataV2(_), _) => true, // reached via expand_checked_nodes
             (Node::AliasContentMapping(_), _) => true,   // reached via expand_checked_nodes
             // Derived
@@ -830,7 +860,12 @@ impl VisitOne for WalkState {
 
     /// If the set did not have this value present, true is returned.
     fn needs_visit(&self, outgoing: &OutgoingEdge) -> bool {
-        self.needs_visit_impl(outgoing, false)
+        let needs_visit = self.needs_visit_impl(outgoing, false);
+        if !needs_visit {
+            let k = outgoing.target.get_type();
+            self.dedup_count[k as usize].fetch_add(1, Ordering::Release);
+        }
+        needs_visit
     }
 }
 
@@ -998,8 +1033,11 @@ impl WalkVisitor<(Node, Option<NodeData>, Option<StepStats>), EmptyRoute> for Wa
             error_count: 0,
             missing_count: 0,
             hash_validation_failure_count: 0,
+            skipped_known_bad_count: 0,
+            redacted_count: 0,
             num_expanded_new,
             visited_of_type: self.get_visit_count(&node.get_type()),
+            deduped_of_type: self.get_dedup_count(&node.get_type()),
         };
         let node_data = match node_data {
             Some(NodeData::ErrorAsData(_key)) => {
@@ -1014,6 +1052,14 @@ impl WalkVisitor<(Node, Option<NodeData>, Option<StepStats>), EmptyRoute> for Wa
                 stats.hash_validation_failure_count += 1;
                 None
             }
+            Some(NodeData::SkippedKnownBadAsData(_key)) => {
+                stats.skipped_known_bad_count += 1;
+                None
+            }
+            Some(NodeData::RedactedAsData(_key)) => {
+                stats.redacted_count += 1;
+                None
+            }
             Some(d) => Some(d),
             None => None,
         };
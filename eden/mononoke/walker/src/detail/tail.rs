@@ -47,10 +47,15 @@ use tokio::time::Instant;
 use crate::commands::JobWalkParams;
 use crate::detail::checkpoint::Checkpoint;
 use crate::detail::checkpoint::CheckpointsByName;
+use crate::detail::content_dedup::ContentDedupStats;
+use crate::detail::corruption_log::CorruptionLog;
 use crate::detail::graph::ChangesetKey;
 use crate::detail::graph::Node;
 use crate::detail::graph::NodeType;
+use crate::detail::lease::ChunkLeases;
+use crate::detail::lease::LeaseOutcome;
 use crate::detail::log;
+use crate::detail::sample_rotation::SampleOffsetRotation;
 use crate::detail::state::InternedType;
 use crate::detail::walk::walk_exact;
 use crate::detail::walk::OutgoingEdge;
@@ -114,6 +119,7 @@ pub struct ChunkingParams {
     pub direction: Direction,
     pub clear_state: Option<ClearStateParams>,
     pub checkpoints: Option<CheckpointsByName>,
+    pub leases: Option<ChunkLeases>,
     pub allow_remaining_deferred: bool,
     pub repo_lower_bound_override: Option<u64>,
     pub repo_upper_bound_override: Option<u64>,
@@ -124,6 +130,8 @@ pub struct TailParams {
     pub tail_secs: Option<u64>,
     pub chunking: Option<ChunkingParams>,
     pub state_max_age: Duration,
+    pub sample_rotation: Option<SampleOffsetRotation>,
+    pub corruption_log: Option<CorruptionLog>,
 }
 
 // Represent that only one end of the bound is optional, depending on direction
@@ -200,6 +208,53 @@ impl BestBounds {
     }
 }
 
+/// Walk from the diff base roots, discarding the output, so that everything
+/// reachable from them is marked visited and the following walk covers only
+/// the graph difference.
+async fn walk_diff_base<V, VOut, Route>(
+    ctx: &CoreContext,
+    job_params: JobWalkParams,
+    repo_params: &RepoWalkParams,
+    type_params: RepoWalkTypeParams,
+    mut visitor: V,
+) -> Result<V, Error>
+where
+    V: 'static + TailingWalkVisitor + WalkVisitor<VOut, Route> + Send + Sync,
+    VOut: 'static + Send,
+    Route: 'static + Send + Clone + StepRoute,
+{
+    let mut base_params = repo_params.clone();
+    base_params.walk_roots = repo_params.diff_base_roots.clone();
+    base_params.diff_base_roots = vec![];
+    // Only the difference is reported on, so don't count the base's content.
+    base_params.content_dedup = ContentDedupStats::new(
+        repo_params.logger.clone(),
+        repo_params.repo.repo_identity().name().to_string(),
+        None,
+    );
+    base_params.corruption_log = None;
+
+    visitor.set_walking_diff_base(true);
+    let arc_v = Arc::new(visitor);
+    let walked = walk_exact(
+        ctx.clone(),
+        arc_v.clone(),
+        job_params,
+        base_params,
+        type_params,
+    )
+    .try_fold(0u64, |walked, _| future::ok(walked + 1))
+    .await?;
+    let mut visitor = Arc::try_unwrap(arc_v).map_err(|_| anyhow!("could not unwrap visitor"))?;
+    visitor.set_walking_diff_base(false);
+
+    info!(
+        repo_params.logger,
+        "Walked {} nodes reachable from the diff base, walking the difference", walked
+    );
+    Ok(visitor)
+}
+
 pub async fn walk_exact_tail<RunFac, SinkFac, SinkOut, V, VOut, Route>(
     fb: FacebookInit,
     job_params: JobWalkParams,
@@ -222,6 +277,7 @@ where
     Route: 'static + Send + Clone + StepRoute,
 {
     let repo_id = repo_params.repo.repo_identity().id();
+    let budget = job_params.budget.clone();
 
     let mut state_start = Timestamp::now();
 
@@ -234,6 +290,7 @@ where
     while !cancellation_requested.load(Ordering::Relaxed) {
         cloned!(job_params, tail_params, type_params, make_run);
         let tail_secs = tail_params.tail_secs;
+        let running_walk = job_params.heartbeat.start_walk();
         // Each loop get new ctx and thus session id so we can distinguish runs
         let ctx = CoreContext::new_with_logger(fb, repo_params.logger.clone());
         let session_text = ctx.session().metadata().session_id().to_string();
@@ -398,6 +455,12 @@ where
         if cancellation_requested.load(Ordering::Relaxed) {
             return Ok(());
         }
+
+        if !repo_params.diff_base_roots.is_empty() {
+            cloned!(job_params, type_params);
+            visitor = walk_diff_base(&ctx, job_params, &repo_params, type_params, visitor).await?;
+        }
+
         while let Some(chunk_members) = chunk_stream.try_next().await? {
             if is_chunking && chunk_members.is_empty() {
                 continue;
@@ -457,6 +520,30 @@ where
             last_chunk_low.replace(chunk_low);
             last_chunk_upper.replace(chunk_upper);
 
+            let lease_keep_alive = if let Some(leases) = tail_params
+                .chunking
+                .as_ref()
+                .and_then(|chunking| chunking.leases.as_ref())
+            {
+                match leases.acquire(repo_id, chunk_low, chunk_upper).await? {
+                    LeaseOutcome::Acquired => {}
+                    LeaseOutcome::TakenOver { previous_owner } => {
+                        info!(logger, #log::CHUNKING, "Chunk {} taking over lease from {}", chunk_num, previous_owner)
+                    }
+                    LeaseOutcome::Held { owner } => {
+                        info!(logger, #log::CHUNKING, "Skipping chunk {}, leased by {}", chunk_num, owner);
+                        continue;
+                    }
+                    LeaseOutcome::Completed => {
+                        info!(logger, #log::CHUNKING, "Skipping chunk {}, recently completed by another walker", chunk_num);
+                        continue;
+                    }
+                }
+                Some(leases.keep_alive(logger.clone(), repo_id, chunk_low, chunk_upper))
+            } else {
+                None
+            };
+
             cloned!(mut repo_params);
             let hg_mapping_prepop = if with_hg && is_chunking {
                 // bulk prepopulate the hg/bonsai mappings
@@ -488,9 +575,31 @@ where
                 .as_ref()
                 .and_then(|chunking| chunking.checkpoints.as_ref())
                 .map(|v| v.name().to_string());
-            make_sink(walk_output, run_start, chunk_num, cp_name).await?;
+            let walk_result = make_sink(walk_output, run_start, chunk_num, cp_name).await;
+            // If the walk failed, stop renewing the lease so that another
+            // walker can take the chunk over once it expires. Early returns
+            // above stop it too, as dropping the keep alive aborts it.
+            let leased = lease_keep_alive.is_some();
+            drop(lease_keep_alive);
+            walk_result?;
             visitor = Arc::try_unwrap(arc_v).map_err(|_| anyhow!("could not unwrap visitor"))?;
 
+            // A chunk cut short by the budget was only partly walked, so
+            // don't complete its lease or checkpoint it.
+            if budget.is_exhausted() {
+                break;
+            }
+
+            if leased {
+                if let Some(leases) = tail_params
+                    .chunking
+                    .as_ref()
+                    .and_then(|chunking| chunking.leases.as_ref())
+                {
+                    leases.complete(repo_id, chunk_low, chunk_upper).await?;
+                }
+            }
+
             if let Some(chunking) = tail_params.chunking.as_ref() {
                 info!(logger, #log::LOADED, "Deferred: {}", visitor.num_deferred());
                 if let Some(clear_state) = chunking.clear_state.as_ref() {
@@ -535,11 +644,23 @@ where
             }
         }
 
+        if budget.is_exhausted() {
+            repo_params.edge_stats.report();
+            repo_params.content_dedup.report();
+            job_params
+                .metadata_backfill
+                .log_summary(&repo_params.logger, repo_params.repo.repo_identity().name());
+            budget.log_summary(&repo_params.logger, repo_params.repo.repo_identity().name());
+            return Ok(());
+        }
+
         if let Some(chunking) = tail_params.chunking.as_ref() {
             visitor.end_chunks(
                 &repo_params.logger,
                     contiguous_bounds
                         && !chunking.allow_remaining_deferred
+                        // Chunks leased by other walkers were skipped, so their edges may remain deferred.
+                        && chunking.leases.is_none()
                         // If lower bound overridden then not contiguous to repo start. Overriding upper bound should not result in deferred.
                         && ((chunking.direction == Direction::NewestFirst && chunking.repo_lower_bound_override.is_none())
                             || (chunking.direction == Direction::OldestFirst && chunking.repo_upper_bound_override.is_none())),
@@ -556,6 +677,13 @@ where
             );
         };
 
+        repo_params.edge_stats.report();
+        repo_params.content_dedup.report();
+        job_params
+            .metadata_backfill
+            .log_summary(&repo_params.logger, repo_params.repo.repo_identity().name());
+        drop(running_walk);
+
         match tail_secs {
             Some(interval) => {
                 let start = Instant::now();
@@ -30,6 +30,7 @@ use context::CoreContext;
 use derive_more::AddAssign;
 use fbinit::FacebookInit;
 use futures::future::try_join_all;
+use futures::stream::StreamExt;
 use futures::stream::TryStreamExt;
 use itertools::Itertools;
 use maplit::hashset;
@@ -53,6 +54,9 @@ use crate::detail::graph::Node;
 use crate::detail::graph::NodeData;
 use crate::detail::graph::NodeType;
 use crate::detail::graph::WrappedPath;
+use crate::detail::linknode_repair::LinknodeCandidate;
+use crate::detail::linknode_repair::LinknodeRepair;
+use crate::detail::linknode_repair::LinknodeRepairOptions;
 use crate::detail::log;
 use crate::detail::progress::progress_stream;
 use crate::detail::progress::report_state;
@@ -91,6 +95,7 @@ pub const CHECK_SIZE: &str = "check_size";
 pub const WALK_TYPE: &str = "walk_type";
 pub const REPO: &str = "repo";
 pub const ERROR_MSG: &str = "error_msg";
+pub const STEP_RETRIES: &str = "step_retries";
 const SRC_NODE_KEY: &str = "src_node_key";
 const SRC_NODE_TYPE: &str = "src_node_type";
 const SRC_NODE_PATH: &str = "src_node_path";
@@ -108,6 +113,7 @@ define_stats! {
 pub const DEFAULT_CHECK_TYPES: &[CheckType] = &[
     CheckType::ChangesetPhaseIsPublic,
     CheckType::HgLinkNodePopulated,
+    CheckType::HgConvertRevisionMapped,
 ];
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -149,6 +155,7 @@ enum CheckType {
     ChangesetPhaseIsPublic,
     HgLinkNodePopulated,
     FileContentIsLfs,
+    HgConvertRevisionMapped,
 }
 }
 
@@ -158,6 +165,7 @@ impl CheckType {
             CheckType::ChangesetPhaseIsPublic => "bonsai_phase_is_public",
             CheckType::HgLinkNodePopulated => "hg_link_node_populated",
             CheckType::FileContentIsLfs => "file_content_is_lfs",
+            CheckType::HgConvertRevisionMapped => "hg_convert_revision_mapped",
         }
     }
     pub fn node_type(&self) -> NodeType {
@@ -165,6 +173,7 @@ impl CheckType {
             CheckType::ChangesetPhaseIsPublic => NodeType::PhaseMapping,
             CheckType::HgLinkNodePopulated => NodeType::HgFileNode,
             CheckType::FileContentIsLfs => NodeType::FileContentMetadataV2,
+            CheckType::HgConvertRevisionMapped => NodeType::HgConvertRevision,
         }
     }
 }
@@ -192,6 +201,7 @@ struct ValidatingVisitor {
     inner: WalkState,
     checks_by_node_type: HashMap<NodeType, HashSet<CheckType>>,
     lfs_threshold: Option<u64>,
+    repair_linknodes: bool,
 }
 
 impl ValidatingVisitor {
@@ -204,6 +214,7 @@ impl ValidatingVisitor {
         enable_derive: bool,
         lfs_threshold: Option<u64>,
         chunk_direction: Option<Direction>,
+        repair_linknodes: bool,
     ) -> Self {
         Self {
             repo_stats_key,
@@ -221,6 +232,7 @@ impl ValidatingVisitor {
                 .map(|(key, group)| (key, HashSet::from_iter(group)))
                 .collect(),
             lfs_threshold,
+            repair_linknodes,
         }
     }
 }
@@ -389,6 +401,71 @@ fn check_file_content_is_lfs(
     }
 }
 
+// The git or svn mapping must agree with the changeset's convert_revision extra
+fn check_convert_revision_mapped(
+    node_data: Option<&NodeData>,
+    route: Option<&ValidateRoute>,
+) -> CheckStatus {
+    match node_data {
+        Some(NodeData::HgConvertRevision(Some(convert_revision)))
+            if !convert_revision.is_mapped() =>
+        {
+            let via = route.and_then(|r| {
+                r.via
+                    .iter()
+                    .rev()
+                    .find(|n| matches!(n, Node::HgChangeset(_)))
+                    .cloned()
+            });
+            CheckStatus::Fail(ValidateInfo::new(
+                route.map(|r| r.src_node.clone()),
+                via,
+                None,
+                None,
+            ))
+        }
+        // Not imported, or the mapping agrees
+        Some(NodeData::HgConvertRevision(_)) => CheckStatus::Pass(None),
+        // Unexpected node type
+        _ => CheckStatus::Fail(ValidateInfo::new(
+            route.map(|r| r.src_node.clone()),
+            None,
+            None,
+            None,
+        )),
+    }
+}
+
+// A filenode found in a manifest is a candidate for linknode repair if the
+// manifest was reached from a changeset other than its linknode.
+fn linknode_candidate(
+    resolved: &OutgoingEdge,
+    node_data: Option<&NodeData>,
+    route: Option<&ValidateRoute>,
+) -> Option<LinknodeCandidate> {
+    let route = route?;
+    if !matches!(route.src_node, Node::HgManifest(_)) {
+        return None;
+    }
+    match (&resolved.target, node_data) {
+        (Node::HgFileNode(key), Some(NodeData::HgFileNode(Some(info)))) => {
+            let via = route.via.iter().rev().find_map(|n| match n {
+                Node::HgChangeset(cs) => Some(cs.inner),
+                _ => None,
+            })?;
+            if via == info.linknode {
+                return None;
+            }
+            Some(LinknodeCandidate {
+                path: key.path.as_ref()?.clone(),
+                info: info.clone(),
+                via,
+            })
+        }
+        _ => None,
+    }
+}
+
 #[derive(AddAssign, Clone, Copy, Default, Debug)]
 struct CheckStats {
     pass: u64,
@@ -399,6 +476,7 @@ struct CheckStats {
 struct CheckData {
     checked: Vec<CheckOutput>,
     stats: CheckStats,
+    linknode_candidate: Option<LinknodeCandidate>,
 }
 
 #[derive(Clone, Debug)]
@@ -532,6 +610,9 @@ impl WalkVisitor<(Node, Option<CheckData>, Option<StepStats>), ValidateRoute>
                                 CheckStatus::Pass(None)
                             }
                         }
+                        CheckType::HgConvertRevisionMapped => {
+                            check_convert_revision_mapped(node_data.as_ref(), route.as_ref())
+                        }
                     };
                     match &status {
                         CheckStatus::Pass(_) => pass += 1,
@@ -549,6 +630,12 @@ impl WalkVisitor<(Node, Option<CheckData>, Option<StepStats>), ValidateRoute>
             (self.repo_stats_key.clone(), EDGES, TOTAL),
         );
 
+        let linknode_candidate = if self.repair_linknodes {
+            linknode_candidate(&resolved, node_data.as_ref(), route.as_ref())
+        } else {
+            None
+        };
+
         // Call inner after checks. otherwise it will prune outgoing edges we wanted to check.
         let ((node, _opt_data, opt_stats), _, outgoing) = self.inner.visit(
             ctx,
@@ -560,7 +647,7 @@ impl WalkVisitor<(Node, Option<CheckData>, Option<StepStats>), ValidateRoute>
 
         let vout = (
             node.clone(),
-            if checked.is_empty() {
+            if checked.is_empty() && linknode_candidate.is_none() {
                 None
             } else {
                 Some(CheckData {
@@ -570,6 +657,7 @@ impl WalkVisitor<(Node, Option<CheckData>, Option<StepStats>), ValidateRoute>
                         fail,
                         edges: num_edges,
                     },
+                    linknode_candidate,
                 })
             },
             opt_stats,
@@ -848,6 +936,7 @@ impl ProgressReporterUnprotected for ValidateProgressState {
 pub struct ValidateCommand {
     pub include_check_types: HashSet<CheckType>,
     pub progress_options: ProgressOptions,
+    pub linknode_repair: Option<LinknodeRepairOptions>,
 }
 
 impl ValidateCommand {
@@ -912,23 +1001,59 @@ async fn run_one(
         command.progress_options,
     ));
 
-    cloned!(job_params.quiet, sub_params.progress_state);
+    let linknode_repair = command.linknode_repair.map(LinknodeRepair::new);
+
+    cloned!(job_params.quiet, sub_params.progress_state, linknode_repair);
     let make_sink = move |ctx: &CoreContext, repo_params: &RepoWalkParams| {
-        cloned!(ctx);
+        cloned!(
+            ctx,
+            linknode_repair,
+            repo_params.repo,
+            repo_params.scheduled_max
+        );
         validate_progress_state.set_sample_builder(repo_params.scuba_builder.clone());
         async move |walk_output, _run_start, _chunk_num, _checkpoint_name| {
-            cloned!(ctx, progress_state, validate_progress_state);
+            cloned!(
+                ctx,
+                progress_state,
+                validate_progress_state,
+                linknode_repair,
+                repo
+            );
             let walk_progress =
                 progress_stream(quiet, &progress_state, walk_output).map_ok(|(n, d, s)| {
                     // swap stats and data round
                     (n, s, d)
                 });
 
+            let walk_progress = if let Some(linknode_repair) = linknode_repair.clone() {
+                cloned!(ctx, repo);
+                walk_progress
+                    .map_ok(move |(n, s, mut d): (_, _, Option<CheckData>)| {
+                        cloned!(ctx, repo, linknode_repair);
+                        let candidate = d.as_mut().and_then(|d| d.linknode_candidate.take());
+                        async move {
+                            if let Some(candidate) = candidate {
+                                linknode_repair.check(&ctx, &repo, candidate).await?;
+                            }
+                            Ok::<_, Error>((n, s, d))
+                        }
+                    })
+                    .try_buffer_unordered(scheduled_max)
+                    .left_stream()
+            } else {
+                walk_progress.right_stream()
+            };
+
             let validate_progress = progress_stream(quiet, &validate_progress_state, walk_progress);
 
-            report_state(ctx, validate_progress).await?;
+            report_state(ctx.clone(), validate_progress).await?;
             progress_state.report_progress();
             validate_progress_state.report_progress();
+            if let Some(linknode_repair) = linknode_repair {
+                linknode_repair.flush(&ctx, &repo).await?;
+                linknode_repair.log_summary(ctx.logger(), repo.repo_identity().name());
+            }
             Ok(())
         }
     };
@@ -945,6 +1070,15 @@ async fn run_one(
         required_node_data_types.insert(NodeType::FileContentMetadataV2);
         keep_edge_paths = true;
     }
+    if command
+        .include_check_types
+        .contains(&CheckType::HgConvertRevisionMapped)
+    {
+        required_node_data_types.insert(NodeType::HgConvertRevision);
+    }
+    if linknode_repair.is_some() {
+        required_node_data_types.insert(NodeType::HgFileNode);
+    }
 
     let stateful_visitor = ValidatingVisitor::new(
         repo_params.repo.repo_identity().name().to_string(),
@@ -959,6 +1093,7 @@ async fn run_one(
             .chunking
             .as_ref()
             .map(|v| v.direction),
+        linknode_repair.is_some(),
     );
 
     let type_params = RepoWalkTypeParams {
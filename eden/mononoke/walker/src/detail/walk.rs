@@ -9,6 +9,8 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::format_err;
 use anyhow::Context;
@@ -19,11 +21,16 @@ use basename_suffix_skeleton_manifest::RootBasenameSuffixSkeletonManifest;
 use blame::RootBlameV2;
 use blobrepo::BlobRepo;
 use blobrepo_hg::BlobRepoHg;
+use blobstore::Blobstore;
 use blobstore::Loadable;
 use blobstore::LoadableError;
+use bonsai_git_mapping::extract_git_sha1_from_bonsai_extra;
+use bonsai_git_mapping::BonsaiGitMappingRef;
+use bonsai_git_mapping::CONVERT_REVISION_EXTRA;
 use bonsai_hg_mapping::BonsaiHgMapping;
 use bonsai_hg_mapping::BonsaiHgMappingArc;
 use bonsai_hg_mapping::BonsaiHgMappingEntry;
+use bonsai_svnrev_mapping::BonsaiSvnrevMappingRef;
 use bookmarks::BookmarkCategory;
 use bookmarks::BookmarkKey;
 use bookmarks::BookmarkKind;
@@ -34,7 +41,11 @@ use bookmarks::Freshness;
 use bounded_traversal::limited_by_key_shardable;
 use changeset_info::ChangesetInfo;
 use cloned::cloned;
+use commit_graph::CommitGraph;
+use commit_graph::CommitGraphArc;
+use commit_graph::CommitGraphRef;
 use context::CoreContext;
+use context::PerfCounterType;
 use deleted_manifest::RootDeletedManifestIdCommon;
 use deleted_manifest::RootDeletedManifestV2Id;
 use derived_data::BonsaiDerived;
@@ -67,21 +78,27 @@ use mononoke_types::unode::UnodeEntry;
 use mononoke_types::BasenameSuffixSkeletonManifestId;
 use mononoke_types::BlameV2Id;
 use mononoke_types::ChangesetId;
+use mononoke_types::ContentChunkId;
 use mononoke_types::ContentId;
 use mononoke_types::DeletedManifestV2Id;
 use mononoke_types::FastlogBatchId;
+use mononoke_types::FileContents;
 use mononoke_types::FileUnodeId;
 use mononoke_types::FsnodeId;
 use mononoke_types::ManifestUnodeId;
 use mononoke_types::NonRootMPath;
 use mononoke_types::SkeletonManifestId;
+use mononoke_types::Svnrev;
 use phases::Phase;
 use phases::Phases;
 use phases::PhasesRef;
+use redactedblobstore::ErrorKind as RedactedErrorKind;
 use repo_blobstore::RepoBlobstoreRef;
+use repo_derived_data::RepoDerivedDataRef;
 use repo_identity::RepoIdentityRef;
 use scuba_ext::MononokeScubaSampleBuilder;
 use skeleton_manifest::RootSkeletonManifestId;
+use slog::debug;
 use slog::info;
 use slog::warn;
 use slog::Logger;
@@ -90,8 +107,15 @@ use unodes::RootUnodeManifestId;
 use yield_stream::YieldStreamExt;
 
 use crate::commands::JobWalkParams;
+use crate::detail::bookmark_log::landed_changesets;
+use crate::detail::budget::WalkBudget;
+use crate::detail::content_dedup::ContentDedupStats;
+use crate::detail::corruption_log::CorruptionLog;
+use crate::detail::corruption_log::RepoCorruptionLog;
+use crate::detail::edge_stats::EdgeLoadStats;
 use crate::detail::graph::AliasKey;
 use crate::detail::graph::ChangesetKey;
+use crate::detail::graph::ConvertRevision;
 use crate::detail::graph::EdgeType;
 use crate::detail::graph::FastlogKey;
 use crate::detail::graph::FileContentData;
@@ -106,12 +130,18 @@ use crate::detail::graph::UnodeKey;
 use crate::detail::graph::UnodeManifestEntry;
 use crate::detail::graph::WrappedPath;
 use crate::detail::log;
+use crate::detail::metadata_backfill::MetadataBackfill;
+use crate::detail::progress::PendingSteps;
+use crate::detail::retry::is_transient;
+use crate::detail::retry::StepRetryOptions;
+use crate::detail::skip_keys::KnownBadKeys;
 use crate::detail::state::InternedType;
 use crate::detail::validate::add_node_to_scuba;
 use crate::detail::validate::CHECK_FAIL;
 use crate::detail::validate::CHECK_TYPE;
 use crate::detail::validate::EDGE_TYPE;
 use crate::detail::validate::ERROR_MSG;
+use crate::detail::validate::STEP_RETRIES;
 
 /// How frequently to yield the CPU when processing large manifests.
 const MANIFEST_YIELD_EVERY_ENTRY_COUNT: usize = 2_000;
@@ -167,6 +197,8 @@ impl OutgoingEdge {
 pub enum ErrorKind {
     #[error("Could not step to {1:?} via {2} in repo {0}")]
     NotTraversable(String, OutgoingEdge, String),
+    #[error("Edge to {1:?} via {2} in repo {0} points at a missing key")]
+    MissingEdgeTarget(String, OutgoingEdge, String),
 }
 
 // Simpler visitor trait used inside each step to decide
@@ -237,6 +269,11 @@ pub trait WalkVisitor<VOut, Route>: VisitOne {
         walk_item: &OutgoingEdge,
         route: Option<Route>,
     ) -> Result<(VOut, Route), Error>;
+
+    // Called after a step completes, before visit, with the size of the
+    // node: the file size for file contents, otherwise the bytes loaded by
+    // the step.
+    fn record_size(&self, _route: Option<&Route>, _step: &OutgoingEdge, _size: u64) {}
 }
 
 // Visitor methods that are only needed during tailing
@@ -258,6 +295,11 @@ pub trait TailingWalkVisitor {
     fn end_chunks(&mut self, logger: &Logger, contiguous_bounds: bool) -> Result<(), Error>;
 
     fn num_deferred(&self) -> usize;
+
+    // Called before and after walking the base of a differential walk.  The
+    // base is only walked to mark what it reaches as visited, so it should
+    // not be sampled.
+    fn set_walking_diff_base(&mut self, _walking_base: bool) {}
 }
 
 // Data found for this node, plus next steps
@@ -272,15 +314,48 @@ enum StepError {
     Missing(String),
     #[error("Hash validation failure: {0}")]
     HashValidationFailure(Error),
+    // Holds the redacted blobstore key, never the content
+    #[error("{0} is redacted")]
+    Redacted(String),
     #[error(transparent)]
-    Other(#[from] Error),
+    Other(Error),
+}
+
+impl From<Error> for StepError {
+    fn from(error: Error) -> Self {
+        if let Some(key) = redacted_key(&error) {
+            StepError::Redacted(key)
+        } else if let Some(key) = missing_key(&error) {
+            StepError::Missing(key)
+        } else {
+            StepError::Other(error)
+        }
+    }
+}
+
+// A load that found no such key, even if it was wrapped with context on the
+// way up, is a missing edge target rather than a load failure.
+fn missing_key(error: &Error) -> Option<String> {
+    error
+        .chain()
+        .find_map(|cause| match cause.downcast_ref::<LoadableError>()? {
+            LoadableError::Missing(key) => Some(key.clone()),
+            LoadableError::Error(_) => None,
+        })
+}
+
+fn redacted_key(error: &Error) -> Option<String> {
+    error.chain().find_map(|cause| {
+        let RedactedErrorKind::Censored(key, _task) = cause.downcast_ref::<RedactedErrorKind>()?;
+        Some(key.clone())
+    })
 }
 
 impl From<LoadableError> for StepError {
     fn from(error: LoadableError) -> Self {
         match error {
             LoadableError::Missing(s) => StepError::Missing(s),
-            LoadableError::Error(err) => StepError::Other(err),
+            LoadableError::Error(err) => StepError::from(err),
         }
     }
 }
@@ -382,6 +457,9 @@ async fn blame_step<V: VisitOne>(
     // Ignore rejected blames, we are just interested in the changesets.
     if let Ok(csids) = blame.changeset_ids() {
         for (csid, _) in csids {
+            if checker.is_frontier(ctx, &csid).await? {
+                continue;
+            }
             checker.add_edge(&mut edges, EdgeType::BlameToChangeset, || {
                 Node::Changeset(ChangesetKey {
                     inner: csid,
@@ -406,6 +484,9 @@ async fn fastlog_batch_step<V: VisitOne>(
     let log = id.load(ctx, repo.repo_blobstore()).await?;
     let mut edges = vec![];
     for (cs_id, _offsets) in log.latest() {
+        if checker.is_frontier(ctx, cs_id).await? {
+            continue;
+        }
         checker.add_edge(&mut edges, EdgeType::FastlogBatchToChangeset, || {
             Node::Changeset(ChangesetKey {
                 inner: *cs_id,
@@ -444,6 +525,9 @@ async fn fastlog_dir_step<V: VisitOne>(
     match &log {
         Some(log) => {
             for (cs_id, _offsets) in log.latest() {
+                if checker.is_frontier(ctx, cs_id).await? {
+                    continue;
+                }
                 checker.add_edge(&mut edges, EdgeType::FastlogDirToChangeset, || {
                     Node::Changeset(ChangesetKey {
                         inner: *cs_id,
@@ -491,6 +575,9 @@ async fn fastlog_file_step<V: VisitOne>(
     match &log {
         Some(log) => {
             for (cs_id, _offsets) in log.latest() {
+                if checker.is_frontier(ctx, cs_id).await? {
+                    continue;
+                }
                 checker.add_edge(&mut edges, EdgeType::FastlogFileToChangeset, || {
                     Node::Changeset(ChangesetKey {
                         inner: *cs_id,
@@ -563,6 +650,9 @@ async fn changeset_info_step<V: VisitOne>(
     if let Some(info) = info {
         let mut edges = vec![];
         for parent_id in info.parents() {
+            if checker.is_frontier(ctx, &parent_id).await? {
+                continue;
+            }
             checker.add_edge(
                 &mut edges,
                 EdgeType::ChangesetInfoToChangesetInfoParent,
@@ -606,6 +696,9 @@ async fn bonsai_changeset_step<V: VisitOne>(
 
     // Parents expand 1:[0|1|2] and then the same as all below
     for parent_id in bcs.parents() {
+        if checker.is_frontier(ctx, &parent_id).await? {
+            continue;
+        }
         checker.add_edge(&mut edges, EdgeType::ChangesetToBonsaiParent, || {
             Node::Changeset(ChangesetKey {
                 inner: parent_id,
@@ -675,18 +768,47 @@ async fn file_content_step<V: VisitOne>(
     checker: &Checker<V>,
     id: ContentId,
 ) -> Result<StepOutput, StepError> {
-    let maybe_s = filestore::fetch(repo.repo_blobstore().clone(), ctx, &id.into()).await?;
-    let s = match maybe_s {
-        Some(s) => s.map_ok(FileBytes),
+    let maybe_s =
+        filestore::fetch_with_size(repo.repo_blobstore().clone(), ctx.clone(), &id.into()).await?;
+    let (s, size) = match maybe_s {
+        Some((s, size)) => (s.map_ok(FileBytes), size),
         None => {
             return Err(StepError::Missing(format!("missing content for {}", id)));
         }
     };
 
+    // Large files are stored as separate chunks, which are only loaded if
+    // the content stream is read, so step to them to check they exist.
+    let mut edges = vec![];
+    if checker.with_content_chunks {
+        if let FileContents::Chunked(chunked) = id.load(&ctx, repo.repo_blobstore()).await? {
+            for chunk in chunked.iter_chunks() {
+                checker.add_edge(&mut edges, EdgeType::FileContentToChunk, || {
+                    Node::FileContentChunk(chunk.chunk_id())
+                });
+            }
+        }
+    }
+
     // We don't force file loading here, content may not be needed
     Ok(StepOutput::Done(
         checker.step_data(NodeType::FileContent, || {
-            NodeData::FileContent(FileContentData::ContentStream(Box::pin(s)))
+            NodeData::FileContent(FileContentData::ContentStream(Box::pin(s), size))
+        }),
+        edges,
+    ))
+}
+
+async fn file_content_chunk_step<V: VisitOne>(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    checker: &Checker<V>,
+    id: ContentChunkId,
+) -> Result<StepOutput, StepError> {
+    let chunk = id.load(ctx, repo.repo_blobstore()).await?;
+    Ok(StepOutput::Done(
+        checker.step_data(NodeType::FileContentChunk, || {
+            NodeData::FileContentChunk(chunk.size())
         }),
         vec![],
     ))
@@ -704,7 +826,32 @@ async fn file_content_metadata_v2_step<V: VisitOne>(
             .await?
             .map(Some)
     } else {
-        filestore::get_metadata_readonly(repo.repo_blobstore(), ctx, &id.into()).await?
+        match filestore::get_metadata_readonly(repo.repo_blobstore(), ctx, &id.into()).await? {
+            // Missing, so derive it from the content and store it if allowed
+            Some(None) if checker.metadata_backfill.try_start() => {
+                let backfilled = checker
+                    .metadata_backfill
+                    .leases()
+                    .try_write(
+                        ctx,
+                        repo.repo_derived_data().lease(),
+                        MetadataBackfill::lease_key(repo.repo_identity().id(), id),
+                        filestore::get_metadata(repo.repo_blobstore(), ctx, &id.into()),
+                    )
+                    .await;
+                match backfilled {
+                    // Another writer is storing it, so it is still missing for now
+                    Ok(None) => Some(None),
+                    backfilled => {
+                        checker
+                            .metadata_backfill
+                            .record_result(matches!(backfilled, Ok(Some(Some(_)))));
+                        backfilled?.flatten().map(Some)
+                    }
+                }
+            }
+            metadata_opt => metadata_opt,
+        }
     };
 
     match metadata_opt {
@@ -937,19 +1084,74 @@ async fn hg_changeset_step<V: VisitOne>(
 
     // Mostly 1:1, can be 1:2, with further expansion
     for p in hgchangeset.parents().into_iter() {
+        let hg_parent = HgChangesetId::new(p);
+        if checker.has_frontier() {
+            let parent_id = checker.get_bonsai_from_hg(ctx, &hg_parent).await?;
+            if checker.is_frontier(ctx, &parent_id).await? {
+                continue;
+            }
+        }
         checker.add_edge(&mut edges, EdgeType::HgChangesetToHgParent, || {
             Node::HgChangesetViaBonsai(ChangesetKey {
-                inner: HgChangesetId::new(p),
+                inner: hg_parent,
                 filenode_known_derived: key.filenode_known_derived,
             })
         });
     }
+
+    // Only imported changesets record where they came from
+    if hgchangeset
+        .extra()
+        .contains_key(CONVERT_REVISION_EXTRA.as_bytes())
+    {
+        checker.add_edge(&mut edges, EdgeType::HgChangesetToHgConvertRevision, || {
+            Node::HgConvertRevision(key.clone())
+        });
+    }
     Ok(StepOutput::Done(
         checker.step_data(NodeType::HgChangeset, || NodeData::HgChangeset(hgchangeset)),
         edges,
     ))
 }
 
+async fn hg_convert_revision_step<V: VisitOne>(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    checker: &Checker<V>,
+    key: ChangesetKey<HgChangesetId>,
+) -> Result<StepOutput, StepError> {
+    let bcs_id = checker.get_bonsai_from_hg(ctx, &key.inner).await?;
+    let bcs = bcs_id.load(ctx, repo.repo_blobstore()).await?;
+    let is_svn = bcs
+        .hg_extra()
+        .any(|(k, v)| k == CONVERT_REVISION_EXTRA && v.starts_with(b"svn:"));
+
+    let convert_revision = if let Some(extra) = extract_git_sha1_from_bonsai_extra(bcs.hg_extra())?
+    {
+        let mapped = repo
+            .bonsai_git_mapping()
+            .get_git_sha1_from_bonsai(ctx, bcs_id)
+            .await?;
+        Some(ConvertRevision::Git { extra, mapped })
+    } else if is_svn {
+        let extra = Svnrev::from_bcs(&bcs)?;
+        let mapped = repo
+            .bonsai_svnrev_mapping()
+            .get_svnrev_from_bonsai(ctx, bcs_id)
+            .await?;
+        Some(ConvertRevision::Svn { extra, mapped })
+    } else {
+        None
+    };
+
+    Ok(StepOutput::Done(
+        checker.step_data(NodeType::HgConvertRevision, || {
+            NodeData::HgConvertRevision(convert_revision)
+        }),
+        vec![],
+    ))
+}
+
 async fn hg_file_envelope_step<V: VisitOne>(
     ctx: &CoreContext,
     repo: &BlobRepo,
@@ -1004,23 +1206,34 @@ where
             return Ok(StepOutput::Deferred(bcs_id));
         }
 
-        // Validate hg link node
-        checker.add_edge(&mut edges, linknode_edge, || {
-            Node::HgChangesetViaBonsai(ChangesetKey {
-                inner: file_node_info.linknode,
-                filenode_known_derived: true,
-            })
-        });
+        let linknode_is_frontier = if checker.has_frontier() {
+            let linknode_id = checker
+                .get_bonsai_from_hg(&ctx, &file_node_info.linknode)
+                .await?;
+            checker.is_frontier(&ctx, &linknode_id).await?
+        } else {
+            false
+        };
 
-        // Following linknode bonsai increases parallelism of walk.
-        // Linknodes will point to many commits we can then walk
-        // in parallel
-        checker.add_edge(&mut edges, linknode_mapping_edge, || {
-            Node::HgBonsaiMapping(ChangesetKey {
-                inner: file_node_info.linknode,
-                filenode_known_derived: true,
-            })
-        });
+        if !linknode_is_frontier {
+            // Validate hg link node
+            checker.add_edge(&mut edges, linknode_edge, || {
+                Node::HgChangesetViaBonsai(ChangesetKey {
+                    inner: file_node_info.linknode,
+                    filenode_known_derived: true,
+                })
+            });
+
+            // Following linknode bonsai increases parallelism of walk.
+            // Linknodes will point to many commits we can then walk
+            // in parallel
+            checker.add_edge(&mut edges, linknode_mapping_edge, || {
+                Node::HgBonsaiMapping(ChangesetKey {
+                    inner: file_node_info.linknode,
+                    filenode_known_derived: true,
+                })
+            });
+        }
 
         // Parents
         for parent in &[file_node_info.p1, file_node_info.p2] {
@@ -1424,12 +1637,14 @@ async fn unode_file_step<V: VisitOne>(
         );
     }
 
-    checker.add_edge(&mut edges, EdgeType::UnodeFileToLinkedChangeset, || {
-        Node::Changeset(ChangesetKey {
-            inner: linked_cs_id,
-            filenode_known_derived: false, /* unode does not imply hg is fully derived */
-        })
-    });
+    if !checker.is_frontier(ctx, &linked_cs_id).await? {
+        checker.add_edge(&mut edges, EdgeType::UnodeFileToLinkedChangeset, || {
+            Node::Changeset(ChangesetKey {
+                inner: linked_cs_id,
+                filenode_known_derived: false, /* unode does not imply hg is fully derived */
+            })
+        });
+    }
 
     for p in unode_file.parents() {
         checker.add_edge_with_path(
@@ -1468,12 +1683,14 @@ async fn unode_manifest_step<V: VisitOne>(
 
     let mut edges = vec![];
 
-    checker.add_edge(&mut edges, EdgeType::UnodeManifestToLinkedChangeset, || {
-        Node::Changeset(ChangesetKey {
-            inner: linked_cs_id,
-            filenode_known_derived: false, /* unode does not imply hg is fully derived */
-        })
-    });
+    if !checker.is_frontier(ctx, &linked_cs_id).await? {
+        checker.add_edge(&mut edges, EdgeType::UnodeManifestToLinkedChangeset, || {
+            Node::Changeset(ChangesetKey {
+                inner: linked_cs_id,
+                filenode_known_derived: false, /* unode does not imply hg is fully derived */
+            })
+        });
+    }
 
     // Check if we stepped from unode for non-public commit to unode for public, so can enable blame if required
     let mut flags = UnodeFlags::default();
@@ -1570,16 +1787,18 @@ async fn deleted_manifest_v2_step<V: VisitOne>(
         if !checker.in_chunk(&linked_cs_id) {
             return Ok(StepOutput::Deferred(linked_cs_id));
         }
-        checker.add_edge(
-            &mut edges,
-            EdgeType::DeletedManifestV2ToLinkedChangeset,
-            || {
-                Node::Changeset(ChangesetKey {
-                    inner: linked_cs_id,
-                    filenode_known_derived: false, /* dfm does not imply hg is fully derived */
-                })
-            },
-        );
+        if !checker.is_frontier(ctx, &linked_cs_id).await? {
+            checker.add_edge(
+                &mut edges,
+                EdgeType::DeletedManifestV2ToLinkedChangeset,
+                || {
+                    Node::Changeset(ChangesetKey {
+                        inner: linked_cs_id,
+                        filenode_known_derived: false, /* dfm does not imply hg is fully derived */
+                    })
+                },
+            );
+        }
     }
 
     let mut subentries = deleted_manifest_v2
@@ -1838,9 +2057,22 @@ struct Checker<V: VisitOne> {
     visitor: V,
     phases_store: Arc<dyn Phases>,
     bonsai_hg_mapping: Arc<dyn BonsaiHgMapping>,
+    commit_graph: Arc<CommitGraph>,
     with_blame: bool,
     with_fastlog: bool,
     with_filenodes: bool,
+    with_content_chunks: bool,
+    stop_at_public: bool,
+    landed_changesets: Option<HashSet<ChangesetId>>,
+    min_ancestor_generation: Option<u64>,
+    skip_keys: Arc<KnownBadKeys>,
+    check_redacted_present: bool,
+    edge_stats: EdgeLoadStats,
+    content_dedup: ContentDedupStats,
+    retry_options: StepRetryOptions,
+    budget: WalkBudget,
+    metadata_backfill: MetadataBackfill,
+    corruptions: Option<RepoCorruptionLog>,
 }
 
 impl<V: VisitOne> Checker<V> {
@@ -1850,6 +2082,34 @@ impl<V: VisitOne> Checker<V> {
             .await
     }
 
+    fn has_frontier(&self) -> bool {
+        self.stop_at_public
+            || self.landed_changesets.is_some()
+            || self.min_ancestor_generation.is_some()
+    }
+
+    // When stopping at public, edges to public changesets are not expanded
+    // so that the walk only covers draft history. This applies to every edge
+    // that leads from one changeset to another, i.e. parents as well as
+    // linknodes, blame and fastlog. Likewise when walking from the bookmark
+    // update log, changesets that were not landed during the window are not
+    // expanded, and with a maximum ancestor depth changesets too far below
+    // the roots are not expanded.
+    async fn is_frontier(&self, ctx: &CoreContext, bcs_id: &ChangesetId) -> Result<bool, Error> {
+        if let Some(landed) = &self.landed_changesets {
+            if !landed.contains(bcs_id) {
+                return Ok(true);
+            }
+        }
+        if let Some(min_generation) = self.min_ancestor_generation {
+            let generation = self.commit_graph.changeset_generation(ctx, *bcs_id).await?;
+            if generation.value() < min_generation {
+                return Ok(true);
+            }
+        }
+        Ok(self.stop_at_public && self.is_public(ctx, bcs_id).await?)
+    }
+
     fn in_chunk(&self, bcs_id: &ChangesetId) -> bool {
         self.visitor.in_chunk(bcs_id)
     }
@@ -1970,9 +2230,21 @@ pub struct RepoWalkParams {
     pub scheduled_max: usize,
     pub sql_shard_info: SqlShardInfo,
     pub walk_roots: Vec<OutgoingEdge>,
+    /// Also walk commits landed by bookmark update log entries in this
+    /// window, resolved at the start of each walk.
+    pub bookmark_log_window: Option<Duration>,
+    /// Mark everything reachable from these roots as visited before the
+    /// walk, so that it covers only what the walk roots add to them.
+    pub diff_base_roots: Vec<OutgoingEdge>,
     pub include_node_types: HashSet<NodeType>,
     pub include_edge_types: HashSet<EdgeType>,
     pub hash_validation_node_types: HashSet<NodeType>,
+    pub edge_stats: EdgeLoadStats,
+    pub content_dedup: ContentDedupStats,
+    /// Record missing and corrupt nodes found by the walk, if set.
+    pub corruption_log: Option<CorruptionLog>,
+    /// Counts the steps queued by the walk and not yet finished.
+    pub pending_steps: PendingSteps,
 }
 
 // Parameters that vary per repo but are set differently by scrub, validate etc.
@@ -1983,6 +2255,46 @@ pub struct RepoWalkTypeParams {
     pub keep_edge_paths: bool,
 }
 
+/// The lowest generation of the changesets the walk roots are or resolve
+/// to, if any.
+async fn lowest_root_generation(
+    ctx: &CoreContext,
+    repo: &BlobRepo,
+    roots: impl Iterator<Item = &Node>,
+    published_bookmarks: &HashMap<BookmarkKey, ChangesetId>,
+) -> Result<Option<u64>, Error> {
+    let mut root_changesets = vec![];
+    for root in roots {
+        match root {
+            Node::Bookmark(bookmark) => match published_bookmarks.get(bookmark) {
+                Some(bcs_id) => root_changesets.push(*bcs_id),
+                None => root_changesets.extend(repo.bookmarks().get(ctx.clone(), bookmark).await?),
+            },
+            Node::PublishedBookmarks(_) => {
+                root_changesets.extend(published_bookmarks.values().copied())
+            }
+            Node::Changeset(key) => root_changesets.push(key.inner),
+            Node::HgChangeset(key) | Node::HgChangesetViaBonsai(key) => root_changesets.extend(
+                repo.bonsai_hg_mapping_arc()
+                    .get_bonsai_from_hg(ctx, key.inner)
+                    .await?,
+            ),
+            _ => {}
+        }
+    }
+
+    let mut lowest = None;
+    for bcs_id in root_changesets {
+        let generation = repo
+            .commit_graph()
+            .changeset_generation(ctx, bcs_id)
+            .await?
+            .value();
+        lowest = Some(lowest.map_or(generation, |lowest: u64| lowest.min(generation)));
+    }
+    Ok(lowest)
+}
+
 /// Walk the graph from one or more starting points,  providing stream of data for later reduction
 pub fn walk_exact<V, VOut, Route>(
     ctx: CoreContext,
@@ -2013,12 +2325,14 @@ where
         .try_collect::<HashMap<_, _>>();
 
     // Roots were not stepped to from elsewhere, so their Option<Route> is None.
-    let walk_roots: Vec<(Option<Route>, OutgoingEdge)> = repo_params
+    let mut walk_roots: Vec<(Option<Route>, OutgoingEdge)> = repo_params
         .walk_roots
         .iter()
         .map(|e| (None, e.clone()))
         .collect();
 
+    let heartbeat = job_params.heartbeat.clone();
+
     async move {
         let published_bookmarks = Arc::new(published_bookmarks.await?);
         let heads = published_bookmarks
@@ -2026,6 +2340,56 @@ where
             .map(|(_, csid)| *csid)
             .collect::<Vec<_>>();
 
+        let landed_changesets = match repo_params.bookmark_log_window {
+            Some(window) => {
+                let landed =
+                    landed_changesets(&ctx, &repo_params.repo, published_bookmarks.keys(), window)
+                        .await?;
+                info!(
+                    ctx.logger(),
+                    "Walking {} changesets landed in the last {:?}",
+                    landed.changesets.len(),
+                    window
+                );
+                for head in landed.heads {
+                    if landed.changesets.contains(&head) {
+                        walk_roots.push((
+                            None,
+                            OutgoingEdge::new(
+                                EdgeType::RootToChangeset,
+                                Node::Changeset(ChangesetKey {
+                                    inner: head,
+                                    filenode_known_derived: false,
+                                }),
+                            ),
+                        ));
+                    }
+                }
+                Some(landed.changesets)
+            }
+            None => None,
+        };
+
+        let min_ancestor_generation = match job_params.max_ancestor_depth {
+            Some(depth) => {
+                let root_generation = lowest_root_generation(
+                    &ctx,
+                    &repo_params.repo,
+                    walk_roots.iter().map(|(_, e)| &e.target),
+                    &published_bookmarks,
+                )
+                .await?;
+                if root_generation.is_none() {
+                    warn!(
+                        ctx.logger(),
+                        "No changeset roots to limit ancestor depth from, walking all ancestors"
+                    );
+                }
+                root_generation.map(|generation| generation.saturating_sub(depth))
+            }
+            None => None,
+        };
+
         cloned!(
             repo_params.repo,
             repo_params.include_edge_types,
@@ -2034,6 +2398,13 @@ where
             repo_params.sql_shard_info,
         );
 
+        let corruptions = match &repo_params.corruption_log {
+            Some(corruption_log) => {
+                Some(corruption_log.start_walk(repo.repo_identity().id()).await?)
+            }
+            None => None,
+        };
+
         let mut required_node_data_types = type_params.required_node_data_types;
         required_node_data_types.extend(hash_validation_node_types.clone());
         let checker = Arc::new(Checker {
@@ -2045,6 +2416,7 @@ where
                 e.outgoing_type() == NodeType::HgFileNode
                     || e.outgoing_type() == NodeType::HgManifestFileNode
             }),
+            with_content_chunks: include_edge_types.contains(&EdgeType::FileContentToChunk),
             include_edge_types,
             hash_validation_node_types,
             always_emit_edge_types: type_params.always_emit_edge_types,
@@ -2053,19 +2425,34 @@ where
             required_node_data_types,
             phases_store: repo.phases().with_frozen_public_heads(heads),
             bonsai_hg_mapping: repo.bonsai_hg_mapping_arc().clone(),
+            commit_graph: repo.commit_graph_arc(),
+            stop_at_public: job_params.stop_at_public,
+            landed_changesets,
+            min_ancestor_generation,
+            skip_keys: job_params.skip_keys.clone(),
+            check_redacted_present: job_params.check_redacted_present,
+            edge_stats: repo_params.edge_stats.clone(),
+            content_dedup: repo_params.content_dedup.clone(),
+            retry_options: job_params.retry_options,
+            budget: job_params.budget.clone(),
+            metadata_backfill: job_params.metadata_backfill.clone(),
+            corruptions,
         });
 
+        repo_params.pending_steps.queued(walk_roots.len());
+
         Ok(limited_by_key_shardable(
             repo_params.scheduled_max,
             walk_roots,
             move |(via, walk_item): (Option<Route>, OutgoingEdge)| {
-                cloned!(repo_params.sql_shard_info);
+                cloned!(repo_params.sql_shard_info, repo_params.pending_steps);
                 let shard_key = walk_item.target.sql_shard(&sql_shard_info);
                 let ctx =
                     if let Some(ctx) = visitor.start_step(ctx.clone(), via.as_ref(), &walk_item) {
                         ctx
                     } else {
                         info!(ctx.logger(), #log::SUPPRESS, "Suppressing edge {:?}", walk_item);
+                        pending_steps.finished();
                         return future::ready((walk_item.target, shard_key, Ok(None))).boxed();
                     };
 
@@ -2100,7 +2487,19 @@ where
                     let handle = tokio::task::spawn(next);
                     handle.await?
                 }
-                .map(move |v| (target, shard_key, v))
+                .map(move |v| {
+                    // Queue the children before finishing this step, so the
+                    // count never dips below the true queue depth.
+                    let v = v.map(|next| {
+                        next.map(|(out, children)| {
+                            let children: Vec<_> = children.into_iter().collect();
+                            pending_steps.queued(children.len());
+                            (out, children)
+                        })
+                    });
+                    pending_steps.finished();
+                    (target, shard_key, v)
+                })
                 .boxed()
             },
             move |(_route, edge)| {
@@ -2119,10 +2518,11 @@ where
         ))
     }
     .try_flatten_stream()
+    .inspect_ok(move |_| heartbeat.record_step())
 }
 
 async fn walk_one<V, VOut, Route>(
-    ctx: CoreContext,
+    mut ctx: CoreContext,
     via: Option<Route>,
     walk_item: OutgoingEdge,
     repo: BlobRepo,
@@ -2152,126 +2552,171 @@ where
         visitor.visit(&ctx, walk_item.clone(), None, None, vec![walk_item.clone()]);
     }
 
-    let step_result = match walk_item.target.clone() {
-        Node::Root(_) => Err(StepError::Other(format_err!(
-            "Not expecting Roots to be generated"
-        ))),
-        // Bonsai
-        Node::Bookmark(bookmark_name) => {
-            bookmark_step(
-                ctx.clone(),
-                &repo,
-                &checker,
-                bookmark_name,
-                published_bookmarks.clone(),
-            )
-            .await
-        }
-        Node::Changeset(key) => bonsai_changeset_step(&ctx, &repo, &checker, &key).await,
-        Node::BonsaiHgMapping(bcs_id) => {
-            bonsai_to_hg_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::PhaseMapping(bcs_id) => bonsai_phase_step(&ctx, &checker, &bcs_id).await,
-        Node::PublishedBookmarks(_) => {
-            published_bookmarks_step(published_bookmarks.clone(), &checker).await
-        }
-        // Hg
-        Node::HgBonsaiMapping(key) => hg_to_bonsai_mapping_step(&ctx, &checker, key).await,
-        Node::HgChangeset(hg_csid) => hg_changeset_step(&ctx, &repo, &checker, hg_csid).await,
-        Node::HgChangesetViaBonsai(hg_csid) => {
-            hg_changeset_via_bonsai_step(&ctx, &repo, &checker, hg_csid, enable_derive).await
-        }
-        Node::HgFileEnvelope(hg_file_node_id) => {
-            hg_file_envelope_step(
-                &ctx,
-                &repo,
-                &checker,
-                hg_file_node_id,
-                walk_item.path.as_ref(),
-            )
-            .await
-        }
-        Node::HgFileNode(PathKey { id, path }) => {
-            hg_file_node_step(ctx.clone(), &repo, &checker, path, id).await
-        }
-        Node::HgManifestFileNode(PathKey { id, path }) => {
-            hg_manifest_file_node_step(ctx.clone(), &repo, &checker, path, id).await
-        }
-        Node::HgManifest(PathKey { id, path }) => {
-            hg_manifest_step(&ctx, &repo, &checker, path, id).await
-        }
-        // Content
-        Node::FileContent(content_id) => {
-            file_content_step(ctx.clone(), &repo, &checker, content_id).await
-        }
-        Node::FileContentMetadataV2(content_id) => {
-            file_content_metadata_v2_step(&ctx, &repo, &checker, content_id, enable_derive).await
-        }
-        Node::AliasContentMapping(AliasKey(alias)) => {
-            alias_content_mapping_step(&ctx, &repo, &checker, alias).await
-        }
-        // Derived
-        Node::Blame(blame_id) => blame_step(&ctx, &repo, &checker, blame_id).await,
-        Node::ChangesetInfo(bcs_id) => {
-            changeset_info_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::ChangesetInfoMapping(bcs_id) => {
-            bonsai_changeset_info_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::DeletedManifestV2(id) => {
-            deleted_manifest_v2_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::DeletedManifestV2Mapping(bcs_id) => {
-            deleted_manifest_v2_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::FastlogBatch(id) => {
-            fastlog_batch_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::FastlogDir(id) => {
-            fastlog_dir_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::FastlogFile(id) => {
-            fastlog_file_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::Fsnode(id) => fsnode_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await,
-        Node::FsnodeMapping(bcs_id) => {
-            bonsai_to_fsnode_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::SkeletonManifest(id) => {
-            skeleton_manifest_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::SkeletonManifestMapping(bcs_id) => {
-            skeleton_manifest_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
-        }
-        Node::BasenameSuffixSkeletonManifest(id) => {
-            basename_suffix_skeleton_manifest_step(
-                &ctx,
-                &repo,
-                &checker,
-                &id,
-                walk_item.path.as_ref(),
-            )
-            .await
-        }
-        Node::BasenameSuffixSkeletonManifestMapping(bcs_id) => {
-            basename_suffix_skeleton_manifest_mapping_step(
-                &ctx,
-                &repo,
-                &checker,
-                bcs_id,
-                enable_derive,
-            )
-            .await
-        }
-        Node::UnodeFile(id) => {
-            unode_file_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::UnodeManifest(id) => {
-            unode_manifest_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
-        }
-        Node::UnodeMapping(bcs_id) => {
-            bonsai_to_unode_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+    // Known bad keys are not loaded, and are recorded separately from errors
+    let skip_known_bad = checker.skip_keys.contains(&walk_item.target);
+
+    // Measure the load cost of this step, attributed to the edge followed
+    let step_perf_counters = ctx.fork_perf_counters();
+    let step_start = Instant::now();
+
+    // Transient load errors are retried with backoff before being recorded.
+    let mut retries = 0;
+    let step_result = loop {
+        let step_result = match walk_item.target.clone() {
+            _ if skip_known_bad => {
+                debug!(logger, "Skipping known bad node {:?}", walk_item.target);
+                Ok(StepOutput::Done(
+                    NodeData::SkippedKnownBadAsData(walk_item.target.clone()),
+                    vec![],
+                ))
+            }
+            Node::Root(_) => Err(StepError::Other(format_err!(
+                "Not expecting Roots to be generated"
+            ))),
+            // Bonsai
+            Node::Bookmark(bookmark_name) => {
+                bookmark_step(
+                    ctx.clone(),
+                    &repo,
+                    &checker,
+                    bookmark_name,
+                    published_bookmarks.clone(),
+                )
+                .await
+            }
+            Node::Changeset(key) => bonsai_changeset_step(&ctx, &repo, &checker, &key).await,
+            Node::BonsaiHgMapping(bcs_id) => {
+                bonsai_to_hg_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+            Node::PhaseMapping(bcs_id) => bonsai_phase_step(&ctx, &checker, &bcs_id).await,
+            Node::PublishedBookmarks(_) => {
+                published_bookmarks_step(published_bookmarks.clone(), &checker).await
+            }
+            // Hg
+            Node::HgBonsaiMapping(key) => hg_to_bonsai_mapping_step(&ctx, &checker, key).await,
+            Node::HgChangeset(hg_csid) => hg_changeset_step(&ctx, &repo, &checker, hg_csid).await,
+            Node::HgChangesetViaBonsai(hg_csid) => {
+                hg_changeset_via_bonsai_step(&ctx, &repo, &checker, hg_csid, enable_derive).await
+            }
+            Node::HgConvertRevision(hg_csid) => {
+                hg_convert_revision_step(&ctx, &repo, &checker, hg_csid).await
+            }
+            Node::HgFileEnvelope(hg_file_node_id) => {
+                hg_file_envelope_step(
+                    &ctx,
+                    &repo,
+                    &checker,
+                    hg_file_node_id,
+                    walk_item.path.as_ref(),
+                )
+                .await
+            }
+            Node::HgFileNode(PathKey { id, path }) => {
+                hg_file_node_step(ctx.clone(), &repo, &checker, path, id).await
+            }
+            Node::HgManifestFileNode(PathKey { id, path }) => {
+                hg_manifest_file_node_step(ctx.clone(), &repo, &checker, path, id).await
+            }
+            Node::HgManifest(PathKey { id, path }) => {
+                hg_manifest_step(&ctx, &repo, &checker, path, id).await
+            }
+            // Content
+            Node::FileContent(content_id) => {
+                file_content_step(ctx.clone(), &repo, &checker, content_id).await
+            }
+            Node::FileContentChunk(chunk_id) => {
+                file_content_chunk_step(&ctx, &repo, &checker, chunk_id).await
+            }
+            Node::FileContentMetadataV2(content_id) => {
+                file_content_metadata_v2_step(&ctx, &repo, &checker, content_id, enable_derive)
+                    .await
+            }
+            Node::AliasContentMapping(AliasKey(alias)) => {
+                alias_content_mapping_step(&ctx, &repo, &checker, alias).await
+            }
+            // Derived
+            Node::Blame(blame_id) => blame_step(&ctx, &repo, &checker, blame_id).await,
+            Node::ChangesetInfo(bcs_id) => {
+                changeset_info_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+            Node::ChangesetInfoMapping(bcs_id) => {
+                bonsai_changeset_info_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive)
+                    .await
+            }
+            Node::DeletedManifestV2(id) => {
+                deleted_manifest_v2_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::DeletedManifestV2Mapping(bcs_id) => {
+                deleted_manifest_v2_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+            Node::FastlogBatch(id) => {
+                fastlog_batch_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::FastlogDir(id) => {
+                fastlog_dir_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::FastlogFile(id) => {
+                fastlog_file_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::Fsnode(id) => {
+                fsnode_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::FsnodeMapping(bcs_id) => {
+                bonsai_to_fsnode_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+            Node::SkeletonManifest(id) => {
+                skeleton_manifest_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::SkeletonManifestMapping(bcs_id) => {
+                skeleton_manifest_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+            Node::BasenameSuffixSkeletonManifest(id) => {
+                basename_suffix_skeleton_manifest_step(
+                    &ctx,
+                    &repo,
+                    &checker,
+                    &id,
+                    walk_item.path.as_ref(),
+                )
+                .await
+            }
+            Node::BasenameSuffixSkeletonManifestMapping(bcs_id) => {
+                basename_suffix_skeleton_manifest_mapping_step(
+                    &ctx,
+                    &repo,
+                    &checker,
+                    bcs_id,
+                    enable_derive,
+                )
+                .await
+            }
+            Node::UnodeFile(id) => {
+                unode_file_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::UnodeManifest(id) => {
+                unode_manifest_step(&ctx, &repo, &checker, &id, walk_item.path.as_ref()).await
+            }
+            Node::UnodeMapping(bcs_id) => {
+                bonsai_to_unode_mapping_step(&ctx, &repo, &checker, bcs_id, enable_derive).await
+            }
+        };
+
+        if let Err(StepError::Other(e)) = &step_result {
+            if retries < checker.retry_options.max_retries && is_transient(e) {
+                retries += 1;
+                checker.edge_stats.record_retry(walk_item.label);
+                debug!(
+                    logger,
+                    "Retrying step to {:?} after transient error, attempt {}: {:?}",
+                    walk_item.target,
+                    retries,
+                    e
+                );
+                tokio::time::sleep(checker.retry_options.delay(retries)).await;
+                continue;
+            }
         }
+        break step_result;
     };
 
     let edge_label = walk_item.label;
@@ -2280,7 +2725,7 @@ where
     // Run hash validation if needed
     let step_result = match step_result {
         Ok(StepOutput::Done(node_data, children)) => {
-            if checker.hash_validation_node_types.contains(&node_type) {
+            if !skip_known_bad && checker.hash_validation_node_types.contains(&node_type) {
                 let f = walk_item
                     .target
                     .validate_hash(ctx.clone(), repo.clone(), &node_data);
@@ -2300,11 +2745,92 @@ where
                 Ok(StepOutput::Done(node_data, children))
             }
         }
+        // The redacted blob should still be stored, only access to it is blocked
+        Err(StepError::Redacted(key)) if checker.check_redacted_present => {
+            if repo
+                .repo_blobstore()
+                .is_present(&ctx, &key)
+                .await?
+                .assume_not_found_if_unsure()
+            {
+                Err(StepError::Redacted(key))
+            } else {
+                Err(StepError::Missing(format!("redacted blob {}", key)))
+            }
+        }
         res => res,
     };
 
+    let step_bytes = step_perf_counters.get_counter(PerfCounterType::BlobGetsTotalSize) as u64;
+    if !skip_known_bad {
+        checker
+            .edge_stats
+            .record(edge_label, step_start.elapsed(), step_bytes);
+    }
+    checker.budget.record_step(step_bytes);
+    if let Ok(StepOutput::Done(node_data, _)) = &step_result {
+        checker.content_dedup.record(node_data);
+    }
+    if let Some(corruptions) = &checker.corruptions {
+        let source_node = via.as_ref().and_then(|v| v.source_node());
+        let recorded = match &step_result {
+            Ok(StepOutput::Done(..)) => corruptions.record_intact(&walk_item.target).await,
+            Err(StepError::Missing(_)) => {
+                corruptions
+                    .record_finding("missing", &walk_item, source_node)
+                    .await
+            }
+            Err(StepError::HashValidationFailure(_)) => {
+                corruptions
+                    .record_finding("hash_validation_failure", &walk_item, source_node)
+                    .await
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = recorded {
+            corruptions.record_failed(&logger, &walk_item.target, e);
+        }
+    }
+    // Keep missing edge targets, which are corruption, apart from failed
+    // loads, which are usually storage trouble.
+    let missing_target = match &step_result {
+        Err(StepError::Missing(_)) => {
+            checker.edge_stats.record_missing_target(edge_label);
+            true
+        }
+        Err(StepError::Other(_)) => {
+            checker.edge_stats.record_load_error(edge_label);
+            false
+        }
+        _ => false,
+    };
+
     let step_output = match step_result {
         Ok(s) => Ok(s),
+        // Redaction is expected, so record it separately and carry on. Only
+        // the key is logged, never the content.
+        Err(StepError::Redacted(key)) => {
+            debug!(
+                logger,
+                "Redacted node {:?} with key {}", walk_item.target, key
+            );
+            add_node_to_scuba(
+                via.as_ref().and_then(|v| v.source_node()),
+                via.as_ref().and_then(|v| v.via_node()),
+                &walk_item.target,
+                walk_item.path.as_ref(),
+                &mut scuba,
+            );
+            scuba
+                .add(EDGE_TYPE, Into::<&'static str>::into(edge_label))
+                .add(CHECK_TYPE, "redacted")
+                .add(CHECK_FAIL, 0)
+                .log();
+            Ok(StepOutput::Done(
+                NodeData::RedactedAsData(walk_item.target.clone()),
+                vec![],
+            ))
+        }
         Err(e) => {
             let msg = format!(
                 "Could not step to {:?}, due to {:?}, via {:?}",
@@ -2322,7 +2848,8 @@ where
             let check_type = match e {
                 StepError::Missing(_) => "missing",
                 StepError::HashValidationFailure(_) => "hash_validation_failure",
-                StepError::Other(_) => "step",
+                StepError::Redacted(_) => "redacted",
+                StepError::Other(_) => "load_error",
             };
 
             scuba
@@ -2330,6 +2857,7 @@ where
                 .add(CHECK_TYPE, check_type)
                 .add(CHECK_FAIL, 1)
                 .add(ERROR_MSG, msg.clone())
+                .add(STEP_RETRIES, retries)
                 .log();
             // Optionally attempt to continue
             if error_as_data_node_types.contains(&walk_item.target.get_type()) {
@@ -2346,6 +2874,10 @@ where
                             NodeData::HashValidationFailureAsData(walk_item.target.clone()),
                             vec![],
                         )),
+                        StepError::Redacted(_key) => Ok(StepOutput::Done(
+                            NodeData::RedactedAsData(walk_item.target.clone()),
+                            vec![],
+                        )),
                         StepError::Other(_e) => Ok(StepOutput::Done(
                             NodeData::ErrorAsData(walk_item.target.clone()),
                             vec![],
@@ -2360,11 +2892,13 @@ where
         }
     }
     .with_context(|| {
-        ErrorKind::NotTraversable(
-            repo.repo_identity().name().to_string(),
-            walk_item.clone(),
-            format!("{:?}", via),
-        )
+        let repo_name = repo.repo_identity().name().to_string();
+        let via = format!("{:?}", via);
+        if missing_target {
+            ErrorKind::MissingEdgeTarget(repo_name, walk_item.clone(), via)
+        } else {
+            ErrorKind::NotTraversable(repo_name, walk_item.clone(), via)
+        }
     })?;
 
     let (vout, via, next) = match step_output {
@@ -2386,11 +2920,114 @@ where
                 }
             }
 
+            let size = match &node_data {
+                NodeData::FileContent(FileContentData::ContentStream(_, size)) => *size,
+                _ => step_bytes,
+            };
+            visitor.record_size(via.as_ref(), &walk_item, size);
+
             // Allow WalkVisitor to record state and decline outgoing nodes if already visited
             visitor.visit(&ctx, walk_item, Some(node_data), via, children)
         }
     };
+    // Once over budget, let the walk drain rather than expanding further
+    let next = if checker.budget.is_exhausted() {
+        vec![]
+    } else {
+        next
+    };
     let via = Some(via);
     let next = next.into_iter().map(move |e| (via.clone(), e));
     Ok(Some((vout, next)))
 }
+
+#[cfg(test)]
+mod tests {
+    use fbinit::FacebookInit;
+    use phases::PhasesRef;
+    use tests_utils::CreateCommitContext;
+
+    use super::*;
+    use crate::builder::WalkBuilder;
+    use crate::testlib::generate_repo;
+    use crate::testlib::SyntheticRepoShape;
+
+    async fn walk_changesets(
+        ctx: &CoreContext,
+        repo: &BlobRepo,
+        root: Node,
+        node_types: impl IntoIterator<Item = NodeType>,
+        stop_at_public: bool,
+    ) -> Result<HashSet<ChangesetId>, Error> {
+        WalkBuilder::new(repo.clone(), ctx.logger().clone())
+            .with_root(root)
+            .with_node_types(node_types)
+            .with_enable_derive(true)
+            .with_stop_at_public(stop_at_public)
+            .walk(ctx.clone())
+            .try_filter_map(|(node, _)| async move {
+                Ok(match node {
+                    Node::Changeset(key) => Some(key.inner),
+                    _ => None,
+                })
+            })
+            .try_collect()
+            .await
+    }
+
+    #[fbinit::test]
+    async fn test_stop_at_public(fb: FacebookInit) -> Result<(), Error> {
+        let ctx = CoreContext::test_mock(fb);
+        let synthetic = generate_repo(fb, &SyntheticRepoShape::default()).await?;
+        let repo = synthetic.repo.clone();
+        repo.phases()
+            .add_reachable_as_public(&ctx, vec![synthetic.head])
+            .await?;
+        let draft1 = CreateCommitContext::new(&ctx, &repo, vec![synthetic.head])
+            .add_file("draft", "1")
+            .commit()
+            .await?;
+        let draft2 = CreateCommitContext::new(&ctx, &repo, vec![draft1])
+            .add_file("draft", "2")
+            .commit()
+            .await?;
+        let draft_root = || {
+            Node::Changeset(ChangesetKey {
+                inner: draft2,
+                filenode_known_derived: false,
+            })
+        };
+
+        // Without stopping, the walk covers the public history too
+        let changeset_types = || [NodeType::Bookmark, NodeType::Changeset];
+        let all = walk_changesets(&ctx, &repo, draft_root(), changeset_types(), false).await?;
+        assert_eq!(all.len(), synthetic.changesets + 2);
+
+        // Stopping at public only walks the draft commits
+        let draft = walk_changesets(&ctx, &repo, draft_root(), changeset_types(), true).await?;
+        assert_eq!(draft, HashSet::from([draft1, draft2]));
+
+        // Unodes for files last changed in public history link to public
+        // changesets, and those linknodes are not followed either
+        let unode_types = [
+            NodeType::Changeset,
+            NodeType::UnodeMapping,
+            NodeType::UnodeManifest,
+            NodeType::UnodeFile,
+        ];
+        let via_unodes = walk_changesets(&ctx, &repo, draft_root(), unode_types, true).await?;
+        assert_eq!(via_unodes, HashSet::from([draft1, draft2]));
+
+        // A public root is still walked, but not its public parents
+        let public = walk_changesets(
+            &ctx,
+            &repo,
+            Node::Bookmark(synthetic.bookmark.clone()),
+            changeset_types(),
+            true,
+        )
+        .await?;
+        assert_eq!(public, HashSet::from([synthetic.head]));
+        Ok(())
+    }
+}
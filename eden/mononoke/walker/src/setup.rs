@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::num::NonZeroU64;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::bail;
 use anyhow::format_err;
@@ -48,15 +49,27 @@ use crate::commands::JobParams;
 use crate::commands::JobWalkParams;
 use crate::commands::RepoSubcommandParams;
 use crate::detail::blobstore::replace_blobconfig;
+use crate::detail::blobstore::ComponentStatsHandler;
 use crate::detail::blobstore::StatsScrubHandler;
+use crate::detail::budget::WalkBudget;
+use crate::detail::content_dedup::ContentDedupStats;
+use crate::detail::edge_stats::EdgeLoadStats;
 use crate::detail::graph::EdgeType;
 use crate::detail::graph::NodeType;
 use crate::detail::graph::SqlShardInfo;
+use crate::detail::heartbeat::Heartbeat;
 use crate::detail::log;
+use crate::detail::metadata_backfill::MetadataBackfill;
 use crate::detail::progress::sort_by_string;
+use crate::detail::progress::PendingSteps;
 use crate::detail::progress::ProgressOptions;
 use crate::detail::progress::ProgressStateCountByType;
 use crate::detail::progress::ProgressStateMutex;
+use crate::detail::record::replay_blobconfig;
+use crate::detail::record::RecordFilter;
+use crate::detail::record::RecordingBlobstore;
+use crate::detail::retry::StepRetryOptions;
+use crate::detail::skip_keys::KnownBadKeys;
 use crate::detail::tail::TailParams;
 use crate::detail::validate::REPO;
 use crate::detail::validate::WALK_TYPE;
@@ -99,6 +112,12 @@ pub async fn setup_common<'a>(
         );
     }
 
+    if common_args.backfill_content_metadata && app.readonly_storage().0 {
+        return Err(format_err!(
+            "Content metadata backfill writes to the blobstore, it can't be used with readonly storage"
+        ));
+    }
+
     // There is no need to check if repos is empty: at least one repo arg
     // is required when running the command.
     let repos = app.multi_repo_configs(repo_args.ids_or_names()?)?;
@@ -113,6 +132,19 @@ pub async fn setup_common<'a>(
 
     let repos = override_repo_configs(walk_stats_key, app, common_args, repos)?;
 
+    let record = common_args
+        .record_replay
+        .parse_record_args()
+        .map(|record_options| {
+            info!(
+                logger,
+                "Recording loaded blobs to {}",
+                record_options.dir.display()
+            );
+            Ok::<_, Error>((record_options.open_store()?, record_options.filter))
+        })
+        .transpose()?;
+
     // configure repo factory
     let repo_id_to_name: HashMap<_, _> = repos
         .iter()
@@ -124,6 +156,7 @@ pub async fn setup_common<'a>(
         repo_id_to_name,
         blobstore_sampler,
         blobstore_component_sampler,
+        record,
         scuba_builder.clone(),
         logger,
         common_args.quiet,
@@ -135,12 +168,16 @@ pub async fn setup_common<'a>(
     let mysql_options = app.mysql_options();
 
     let walk_roots = common_args.walk_roots.parse_args()?;
+    let bookmark_log_window = common_args.walk_roots.parse_bookmark_log_window();
+    let diff_base_roots = common_args.walk_roots.parse_diff_base_args()?;
     let mut parsed_tail_params = parse_tail_params(
         app.fb,
         &common_args.tailing,
         mysql_options,
         &repos,
         &walk_roots,
+        bookmark_log_window,
+        &diff_base_roots,
     )?;
 
     let mut per_repo = Vec::new();
@@ -223,6 +260,8 @@ pub async fn setup_common<'a>(
             repo.clone(),
             &repo_conf,
             walk_roots.clone(),
+            bookmark_log_window,
+            diff_base_roots.clone(),
             tail_params.clone(),
             include_edge_types.clone(),
             included_nodes,
@@ -234,13 +273,39 @@ pub async fn setup_common<'a>(
         per_repo.push(one_repo);
     }
 
+    let skip_keys = match &common_args.skip_keys_file {
+        Some(path) => KnownBadKeys::load(path)?,
+        None => KnownBadKeys::default(),
+    };
+
+    let heartbeat = Heartbeat::default();
+    if let Some(heartbeat_options) = common_args.progress.parse_heartbeat_args() {
+        heartbeat.spawn_reporter(app.fb, logger.clone(), heartbeat_options);
+    }
+
     Ok(JobParams {
         walk_params: JobWalkParams {
             enable_derive: common_args.enable_derive,
+            stop_at_public: common_args.stop_at_public,
+            max_ancestor_depth: common_args.max_ancestor_depth,
+            skip_keys: Arc::new(skip_keys),
+            check_redacted_present: common_args.check_redacted_present,
             quiet: common_args.quiet,
             error_as_data_node_types: error_as_data_node_types_for_all_repos,
             error_as_data_edge_types,
             repo_count,
+            heartbeat,
+            budget: WalkBudget::new(common_args.max_nodes, common_args.max_bytes),
+            metadata_backfill: if common_args.backfill_content_metadata {
+                MetadataBackfill::new(common_args.backfill_content_metadata_max)
+            } else {
+                MetadataBackfill::default()
+            },
+            retry_options: StepRetryOptions {
+                max_retries: common_args.step_retries,
+                base_delay: Duration::from_millis(common_args.step_retry_base_delay_ms),
+                max_delay: Duration::from_millis(common_args.step_retry_max_delay_ms),
+            },
         },
         per_repo,
     })
@@ -295,6 +360,17 @@ fn override_repo_configs(
             walk_stats_key,
             app.blobstore_options().scrub_options.is_some(),
         )?;
+        if let Some(replay_dir) = &common_args.record_replay.replay_dir {
+            // Only blobs are recorded, so a replay reading metadata from a
+            // remote database would mix recorded and production data.
+            if !config.storage_config.metadata.is_local() {
+                bail!(
+                    "Replay of repo {} needs a local metadata database, use --storage-id to pick a storage config with one",
+                    name
+                );
+            }
+            config.storage_config.blobstore = replay_blobconfig(replay_dir);
+        }
 
         let sampling_multiplier =
             NonZeroU64::new(common_args.blobstore_sampling_multiplier).context("Cannot be zero")?;
@@ -320,6 +396,7 @@ fn setup_repo_factory<'a>(
     repo_id_to_name: HashMap<RepositoryId, String>,
     blobstore_sampler: Option<Arc<dyn SamplingHandler>>,
     blobstore_component_sampler: Option<Arc<dyn ComponentSamplingHandler>>,
+    record: Option<(Arc<dyn Blobstore>, RecordFilter)>,
     scuba_builder: MononokeScubaSampleBuilder,
     logger: &'a Logger,
     quiet: bool,
@@ -327,21 +404,37 @@ fn setup_repo_factory<'a>(
     // We want to customize the repo factory, so take a deep clone
     // of the factory on the App.
     let mut repo_factory = RepoFactory::clone(app.repo_factory());
-    if let Some(blobstore_sampler) = blobstore_sampler.clone() {
+    if blobstore_sampler.is_some() || record.is_some() {
         repo_factory.with_blobstore_override({
             cloned!(logger);
-            move |blobstore| -> Arc<dyn Blobstore> {
-                if !quiet {
-                    info!(logger, "Sampling from blobstore: {}", blobstore);
+            move |mut blobstore: Arc<dyn Blobstore>| -> Arc<dyn Blobstore> {
+                if let Some(blobstore_sampler) = &blobstore_sampler {
+                    if !quiet {
+                        info!(logger, "Sampling from blobstore: {}", blobstore);
+                    }
+                    blobstore =
+                        Arc::new(SamplingBlobstore::new(blobstore, blobstore_sampler.clone()));
+                }
+                if let Some((record_store, filter)) = &record {
+                    blobstore = Arc::new(RecordingBlobstore::new(
+                        blobstore,
+                        record_store.clone(),
+                        filter.clone(),
+                    ));
                 }
-                Arc::new(SamplingBlobstore::new(blobstore, blobstore_sampler.clone()))
+                blobstore
             }
         });
     }
 
-    if let Some(sampler) = blobstore_component_sampler {
-        repo_factory.with_blobstore_component_sampler(sampler);
-    }
+    // Always record per-component gets, so that multiplexed blobstores
+    // report which store served each read.
+    repo_factory.with_blobstore_component_sampler(Arc::new(ComponentStatsHandler::new(
+        scuba_builder.clone(),
+        walk_stats_key,
+        repo_id_to_name.clone(),
+        blobstore_component_sampler,
+    )));
 
     repo_factory.with_scrub_handler(Arc::new(StatsScrubHandler::new(
         false,
@@ -359,6 +452,8 @@ fn parse_tail_params(
     mysql_options: &MysqlOptions,
     repos: &[(String, RepoConfig)],
     walk_roots: &[OutgoingEdge],
+    bookmark_log_window: Option<Duration>,
+    diff_base_roots: &[OutgoingEdge],
 ) -> Result<HashMap<MetadataDatabaseConfig, TailParams>, Error> {
     let mut parsed_tail_params: HashMap<MetadataDatabaseConfig, TailParams> = HashMap::new();
     for (_repo, repo_conf) in repos {
@@ -372,11 +467,28 @@ fn parse_tail_params(
             }
         };
 
-        if tail_params.chunking.is_none() && walk_roots.is_empty() {
+        if tail_params.chunking.is_none() && walk_roots.is_empty() && bookmark_log_window.is_none()
+        {
             bail!(
-                "No walk roots provided, pass with  --bookmark, --walk-root or --chunk-by-public",
+                "No walk roots provided, pass with  --bookmark, --walk-root, --bookmark-log-hours or --chunk-by-public",
             );
         }
+        if tail_params.chunking.is_some() && bookmark_log_window.is_some() {
+            bail!("--bookmark-log-hours cannot be combined with --chunk-by-public");
+        }
+        if !diff_base_roots.is_empty() {
+            if walk_roots.is_empty() {
+                bail!("--diff-base-root requires --bookmark or --walk-root");
+            }
+            if tail_params.chunking.is_some()
+                || bookmark_log_window.is_some()
+                || tail_params.tail_secs.is_some()
+            {
+                bail!(
+                    "--diff-base-root cannot be combined with --chunk-by-public, --bookmark-log-hours or --tail-interval"
+                );
+            }
+        }
     }
 
     Ok(parsed_tail_params)
@@ -395,6 +507,8 @@ async fn setup_repo<'a>(
     repo_name: String,
     repo_config: &'a RepoConfig,
     walk_roots: Vec<OutgoingEdge>,
+    bookmark_log_window: Option<Duration>,
+    diff_base_roots: Vec<OutgoingEdge>,
     mut tail_params: TailParams,
     include_edge_types: HashSet<EdgeType>,
     mut include_node_types: HashSet<NodeType>,
@@ -416,8 +530,14 @@ async fn setup_repo<'a>(
         }
     });
 
-    let mut root_node_types: HashSet<_> =
-        walk_roots.iter().map(|e| e.label.outgoing_type()).collect();
+    let mut root_node_types: HashSet<_> = walk_roots
+        .iter()
+        .chain(diff_base_roots.iter())
+        .map(|e| e.label.outgoing_type())
+        .collect();
+    if bookmark_log_window.is_some() {
+        root_node_types.insert(NodeType::Changeset);
+    }
 
     if let Some(ref mut chunking) = tail_params.chunking {
         chunking.chunk_by.retain(|t| {
@@ -449,23 +569,47 @@ async fn setup_repo<'a>(
     scuba_builder.add(REPO, repo_name.clone());
 
     let mut progress_node_types = include_node_types.clone();
-    for e in &walk_roots {
+    for e in walk_roots.iter().chain(diff_base_roots.iter()) {
         progress_node_types.insert(e.target.get_type());
     }
+    if bookmark_log_window.is_some() {
+        progress_node_types.insert(NodeType::Changeset);
+    }
 
-    let progress_state = ProgressStateMutex::new(ProgressStateCountByType::new(
-        fb,
+    let pending_steps = PendingSteps::default();
+    let progress_state = ProgressStateMutex::new(
+        ProgressStateCountByType::new(
+            fb,
+            logger.clone(),
+            walk_stats_key,
+            repo_name.clone(),
+            progress_node_types,
+            progress_options,
+        )
+        .with_pending_steps(pending_steps.clone()),
+    );
+
+    let edge_stats = EdgeLoadStats::new(
         logger.clone(),
         walk_stats_key,
         repo_name.clone(),
-        progress_node_types,
-        progress_options,
-    ));
+        progress_options.report_edge_stats,
+    );
+
+    let content_dedup = ContentDedupStats::new(
+        logger.clone(),
+        repo_name.clone(),
+        progress_options
+            .report_content_dedup
+            .then_some(progress_options.content_dedup_max_distinct),
+    );
 
     let repo: BlobRepo = repo_factory
         .build(repo_name.clone(), repo_config.clone(), common_config)
         .await?;
 
+    let corruption_log = tail_params.corruption_log.clone();
+
     Ok((
         RepoSubcommandParams {
             progress_state,
@@ -478,10 +622,16 @@ async fn setup_repo<'a>(
             scheduled_max,
             sql_shard_info,
             walk_roots,
+            bookmark_log_window,
+            diff_base_roots,
             include_node_types,
             include_edge_types,
             hash_validation_node_types,
             scuba_builder,
+            edge_stats,
+            content_dedup,
+            corruption_log,
+            pending_steps,
         },
     ))
 }
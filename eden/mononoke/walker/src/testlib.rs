@@ -0,0 +1,312 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License version 2.
+ */
+
+//! Synthetic repos for testing walks.
+//!
+//! Repos are generated from a seeded rng, so a given shape always produces
+//! the same commits, and the coverage and stats of a walk over them can be
+//! checked exactly.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use anyhow::Error;
+use blobrepo::BlobRepo;
+use bookmarks::BookmarkKey;
+use context::CoreContext;
+use fbinit::FacebookInit;
+use futures::stream::TryStreamExt;
+use mononoke_types::ChangesetId;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use rand::SeedableRng;
+use tests_utils::bookmark;
+use tests_utils::CreateCommitContext;
+
+use crate::builder::WalkBuilder;
+use crate::detail::graph::FileContentData;
+use crate::detail::graph::Node;
+use crate::detail::graph::NodeData;
+use crate::detail::graph::NodeType;
+
+/// The shape of a synthetic repo.
+#[derive(Clone, Debug)]
+pub struct SyntheticRepoShape {
+    /// Number of commits on the main line.  Each merge adds a side commit.
+    pub commits: usize,
+    /// Number of entries in each directory.
+    pub tree_width: usize,
+    /// Number of directories above each file.
+    pub tree_depth: usize,
+    /// Number of files changed by each commit.
+    pub files_per_commit: RangeInclusive<usize>,
+    /// Size in bytes of each file, chosen uniformly.
+    pub file_size: RangeInclusive<usize>,
+    /// Probability that a main line commit is a merge.
+    pub merge_ratio: f64,
+    pub seed: u64,
+}
+
+impl Default for SyntheticRepoShape {
+    fn default() -> Self {
+        Self {
+            commits: 20,
+            tree_width: 4,
+            tree_depth: 2,
+            files_per_commit: 1..=4,
+            file_size: 16..=1024,
+            merge_ratio: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// A generated repo, along with what a full walk of it should find.
+pub struct SyntheticRepo {
+    pub repo: BlobRepo,
+    pub bookmark: BookmarkKey,
+    pub head: ChangesetId,
+    /// Number of changesets reachable from the bookmark.
+    pub changesets: usize,
+    /// Number of distinct file contents.
+    pub contents: usize,
+    /// Total size of the distinct file contents.
+    pub content_bytes: u64,
+}
+
+struct Generator<'a> {
+    ctx: &'a CoreContext,
+    repo: &'a BlobRepo,
+    shape: &'a SyntheticRepoShape,
+    rng: SmallRng,
+    changesets: usize,
+    contents: HashSet<Vec<u8>>,
+}
+
+impl<'a> Generator<'a> {
+    fn path(&mut self) -> String {
+        let mut path: Vec<String> = (0..self.shape.tree_depth)
+            .map(|_| format!("d{}", self.rng.gen_range(0..self.shape.tree_width)))
+            .collect();
+        path.push(format!("f{}", self.rng.gen_range(0..self.shape.tree_width)));
+        path.join("/")
+    }
+
+    fn content(&mut self, path: &str) -> Vec<u8> {
+        // The header makes each content distinct.
+        let mut content =
+            format!("{}:{}:{}\n", self.shape.seed, self.changesets, path).into_bytes();
+        let size = self.rng.gen_range(self.shape.file_size.clone());
+        while content.len() < size {
+            content.push(b'a' + self.rng.gen_range(0..26));
+        }
+        content
+    }
+
+    async fn commit(
+        &mut self,
+        parents: Vec<ChangesetId>,
+        files: BTreeMap<String, Vec<u8>>,
+    ) -> Result<ChangesetId, Error> {
+        let mut commit = CreateCommitContext::new(self.ctx, self.repo, parents)
+            .set_message(format!("commit {}", self.changesets));
+        for (path, content) in files {
+            self.contents.insert(content.clone());
+            commit = commit.add_file(path.as_str(), content);
+        }
+        self.changesets += 1;
+        commit.commit().await
+    }
+
+    async fn main_commit(&mut self, parents: Vec<ChangesetId>) -> Result<ChangesetId, Error> {
+        let count = self.rng.gen_range(self.shape.files_per_commit.clone());
+        let mut files = BTreeMap::new();
+        for _ in 0..count {
+            let path = self.path();
+            let content = self.content(&path);
+            files.insert(path, content);
+        }
+        self.commit(parents, files).await
+    }
+
+    async fn side_commit(&mut self, parent: ChangesetId) -> Result<ChangesetId, Error> {
+        // Side commits change their own paths, so merges never conflict.
+        let path = format!("side{}/f", self.changesets);
+        let content = self.content(&path);
+        self.commit(vec![parent], BTreeMap::from([(path, content)]))
+            .await
+    }
+}
+
+/// Generate a repo of the given shape, with the `master` bookmark pointing
+/// at the head of its main line.
+pub async fn generate_repo(
+    fb: FacebookInit,
+    shape: &SyntheticRepoShape,
+) -> Result<SyntheticRepo, Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let repo: BlobRepo = test_repo_factory::build_empty(fb).await?;
+    let mut generator = Generator {
+        ctx: &ctx,
+        repo: &repo,
+        shape,
+        rng: SmallRng::seed_from_u64(shape.seed),
+        changesets: 0,
+        contents: HashSet::new(),
+    };
+
+    let mut main_line = vec![generator.main_commit(vec![]).await?];
+    for _ in 1..shape.commits {
+        let head = *main_line.last().expect("main line is not empty");
+        let parents = if generator.rng.gen_bool(shape.merge_ratio) {
+            let base = main_line[generator.rng.gen_range(0..main_line.len())];
+            vec![head, generator.side_commit(base).await?]
+        } else {
+            vec![head]
+        };
+        main_line.push(generator.main_commit(parents).await?);
+    }
+
+    let head = *main_line.last().expect("main line is not empty");
+    let bookmark = bookmark(&ctx, &repo, "master").set_to(head).await?;
+    let changesets = generator.changesets;
+    let contents = generator.contents.len();
+    let content_bytes = generator
+        .contents
+        .iter()
+        .map(|content| content.len() as u64)
+        .sum();
+    Ok(SyntheticRepo {
+        repo,
+        bookmark,
+        head,
+        changesets,
+        contents,
+        content_bytes,
+    })
+}
+
+/// Counts from a walk of a synthetic repo.
+#[derive(Debug, Default)]
+pub struct WalkCounts {
+    pub visited: HashMap<NodeType, usize>,
+    pub content_bytes: u64,
+}
+
+impl WalkCounts {
+    pub fn visited(&self, node_type: NodeType) -> usize {
+        self.visited.get(&node_type).copied().unwrap_or(0)
+    }
+}
+
+/// Walk the repo in process from its bookmark, visiting only the given
+/// node types.
+pub async fn walk_repo(
+    fb: FacebookInit,
+    repo: &SyntheticRepo,
+    node_types: impl IntoIterator<Item = NodeType>,
+) -> Result<WalkCounts, Error> {
+    let ctx = CoreContext::test_mock(fb);
+    let logger = ctx.logger().clone();
+    WalkBuilder::new(repo.repo.clone(), logger)
+        .with_root(Node::Bookmark(repo.bookmark.clone()))
+        .with_node_types(node_types)
+        .with_required_node_data([NodeType::FileContent])
+        .walk(ctx)
+        .try_fold(
+            WalkCounts::default(),
+            |mut counts, (node, node_data)| async move {
+                *counts.visited.entry(node.get_type()).or_default() += 1;
+                match node_data {
+                    Some(NodeData::FileContent(FileContentData::ContentStream(_, size))) => {
+                        counts.content_bytes += size;
+                    }
+                    Some(NodeData::FileContent(FileContentData::Consumed(size))) => {
+                        counts.content_bytes += size as u64;
+                    }
+                    _ => {}
+                }
+                Ok(counts)
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT_TYPES: [NodeType; 3] = [
+        NodeType::Bookmark,
+        NodeType::Changeset,
+        NodeType::FileContent,
+    ];
+
+    async fn check_walk(fb: FacebookInit, shape: SyntheticRepoShape) -> Result<(), Error> {
+        let repo = generate_repo(fb, &shape).await?;
+        let counts = walk_repo(fb, &repo, CONTENT_TYPES).await?;
+        assert_eq!(counts.visited(NodeType::Bookmark), 1);
+        assert_eq!(counts.visited(NodeType::Changeset), repo.changesets);
+        assert_eq!(counts.visited(NodeType::FileContent), repo.contents);
+        assert_eq!(counts.content_bytes, repo.content_bytes);
+        Ok(())
+    }
+
+    #[fbinit::test]
+    async fn test_walk_linear(fb: FacebookInit) -> Result<(), Error> {
+        check_walk(fb, SyntheticRepoShape::default()).await
+    }
+
+    #[fbinit::test]
+    async fn test_walk_merges(fb: FacebookInit) -> Result<(), Error> {
+        check_walk(
+            fb,
+            SyntheticRepoShape {
+                commits: 30,
+                merge_ratio: 0.3,
+                seed: 1,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    #[fbinit::test]
+    async fn test_walk_wide_deep(fb: FacebookInit) -> Result<(), Error> {
+        check_walk(
+            fb,
+            SyntheticRepoShape {
+                commits: 10,
+                tree_width: 16,
+                tree_depth: 5,
+                files_per_commit: 10..=50,
+                file_size: 1..=8192,
+                seed: 2,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    #[fbinit::test]
+    async fn test_generate_deterministic(fb: FacebookInit) -> Result<(), Error> {
+        let shape = SyntheticRepoShape {
+            merge_ratio: 0.5,
+            ..Default::default()
+        };
+        let first = generate_repo(fb, &shape).await?;
+        let second = generate_repo(fb, &shape).await?;
+        assert_eq!(first.head, second.head);
+        assert_eq!(first.changesets, second.changesets);
+
+        let other = generate_repo(fb, &SyntheticRepoShape { seed: 1, ..shape }).await?;
+        assert_ne!(first.head, other.head);
+        Ok(())
+    }
+}
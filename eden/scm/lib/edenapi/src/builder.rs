@@ -113,6 +113,7 @@ pub struct HttpClientBuilder {
     max_files: Option<usize>,
     max_trees: Option<usize>,
     try_route_consistently: bool,
+    clone_idmap_compression: bool,
     max_history: Option<usize>,
     max_location_to_hash: Option<usize>,
     max_commit_mutations: Option<usize>,
@@ -169,6 +170,8 @@ impl HttpClientBuilder {
         let max_trees = get_config(config, "edenapi", "maxtrees")?;
         let try_route_consistently =
             get_config(config, "edenapi", "try-route-consistently")?.unwrap_or_default();
+        let clone_idmap_compression =
+            get_config(config, "edenapi", "clone-idmap-compression")?.unwrap_or_default();
         let max_history = get_config(config, "edenapi", "maxhistory")?;
         let max_location_to_hash = get_config(config, "edenapi", "maxlocationtohash")?;
         let max_commit_mutations = get_config(config, "edenapi", "maxcommitmutations")?;
@@ -214,6 +217,7 @@ impl HttpClientBuilder {
             max_files,
             max_trees,
             try_route_consistently,
+            clone_idmap_compression,
             max_history,
             max_location_to_hash,
             max_commit_mutations,
@@ -298,6 +302,13 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Ask for the idmap in clone data to be sent zstd compressed, in
+    /// chunks.  Only servers that support chunked clone data understand this.
+    pub fn clone_idmap_compression(mut self, enable: bool) -> Self {
+        self.clone_idmap_compression = enable;
+        self
+    }
+
     /// Maximum number of retries per request.
     pub fn max_retry_per_request(mut self, max: usize) -> Self {
         self.max_retry_per_request = max;
@@ -364,6 +375,7 @@ pub(crate) struct Config {
     pub(crate) max_files: Option<usize>,
     pub(crate) max_trees: Option<usize>,
     pub(crate) try_route_consistently: bool,
+    pub(crate) clone_idmap_compression: bool,
     pub(crate) max_history: Option<usize>,
     pub(crate) max_location_to_hash: Option<usize>,
     pub(crate) max_commit_mutations: Option<usize>,
@@ -390,6 +402,7 @@ impl TryFrom<HttpClientBuilder> for Config {
             max_files,
             max_trees,
             try_route_consistently,
+            clone_idmap_compression,
             max_history,
             max_location_to_hash,
             max_commit_mutations,
@@ -427,6 +440,7 @@ impl TryFrom<HttpClientBuilder> for Config {
             max_files,
             max_trees,
             try_route_consistently,
+            clone_idmap_compression,
             max_history,
             max_location_to_hash,
             max_commit_mutations,
@@ -120,6 +120,9 @@ use crate::retryable::RetryableStreamRequest;
 use crate::retryable::RetryableTrees;
 use crate::types::wire::pull::PullFastForwardRequest;
 use crate::types::wire::pull::PullLazyRequest;
+use crate::types::wire::WireCloneData;
+use crate::types::wire::WireCloneDataChunk;
+use crate::types::wire::WireIdMapEntry;
 
 const MAX_CONCURRENT_LOOKUPS_PER_REQUEST: usize = 10000;
 const MAX_CONCURRENT_UPLOAD_FILENODES_PER_REQUEST: usize = 10000;
@@ -577,7 +580,18 @@ impl Client {
     }
 
     async fn clone_data_attempt(&self) -> Result<CloneData<HgId>, EdenApiError> {
-        let url = self.build_url(paths::CLONE_DATA)?;
+        let mut url = self.build_url(paths::CLONE_DATA)?;
+        if self.config().clone_idmap_compression {
+            url.query_pairs_mut()
+                .append_pair("idmap_compression", "zstd");
+            let req = self.configure_request(self.inner.client.post(url))?;
+            let chunks = self
+                .fetch::<WireCloneDataChunk>(vec![req])?
+                .entries
+                .try_collect::<Vec<_>>()
+                .await?;
+            return decode_clone_data_chunks(chunks);
+        }
         let req = self.configure_request(self.inner.client.post(url))?;
         let mut fetch = self.fetch::<CloneData<HgId>>(vec![req])?;
         fetch.entries.next().await.ok_or_else(|| {
@@ -1506,10 +1520,38 @@ async fn with_retry<'t, T>(
     }
 }
 
+/// Assemble clone data sent in chunks, decompressing the idmap entries of
+/// chunks that carry them compressed.
+fn decode_clone_data_chunks(
+    chunks: Vec<WireCloneDataChunk>,
+) -> Result<CloneData<HgId>, EdenApiError> {
+    let mut clone_data = WireCloneData {
+        flat_segments: Vec::new(),
+        idmap: Vec::new(),
+    };
+    for chunk in chunks {
+        clone_data.flat_segments.extend(chunk.flat_segments);
+        clone_data.idmap.extend(chunk.idmap);
+        if let Some(compressed) = chunk.idmap_zstd {
+            let idmap = zstd::stream::decode_all(&compressed[..])
+                .map_err(|e| EdenApiError::Other(format_err!("bad clone data chunk: {}", e)))?;
+            let idmap: Vec<WireIdMapEntry> = serde_cbor::from_slice(&idmap)
+                .map_err(|e| EdenApiError::Other(format_err!("bad clone data chunk: {}", e)))?;
+            clone_data.idmap.extend(idmap);
+        }
+    }
+    clone_data
+        .to_api()
+        .map_err(|e| EdenApiError::from(anyhow::Error::from(e)))
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use edenapi_types::wire::WireDagId;
+    use edenapi_types::wire::WireFlatSegment;
 
+    use super::*;
     use crate::builder::HttpClientBuilder;
 
     #[test]
@@ -1530,4 +1572,53 @@ mod tests {
 
         Ok(())
     }
+
+    fn dag_id(id: u64) -> WireDagId {
+        // Dag ids are plain integers on the wire.
+        serde_cbor::from_slice(&serde_cbor::to_vec(&id).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_decode_clone_data_chunks() -> Result<()> {
+        let flat_segments = vec![WireFlatSegment {
+            low: dag_id(0),
+            high: dag_id(3),
+            parents: Vec::new(),
+        }];
+        let mut idmap: Vec<_> = (0..4u64)
+            .map(|id| WireIdMapEntry {
+                dag_id: dag_id(id),
+                hg_id: HgId::from_byte_array([id as u8; 20]).to_wire(),
+            })
+            .collect();
+        let expected = WireCloneData {
+            flat_segments: flat_segments.clone(),
+            idmap: idmap.clone(),
+        }
+        .to_api()?;
+
+        // One plain chunk with the segments, and one compressed chunk.
+        let compressed = idmap.split_off(2);
+        let compressed = zstd::bulk::compress(&serde_cbor::to_vec(&compressed)?, 0)?;
+        let chunks = vec![
+            WireCloneDataChunk {
+                flat_segments,
+                idmap,
+                idmap_zstd: None,
+            },
+            WireCloneDataChunk {
+                flat_segments: Vec::new(),
+                idmap: Vec::new(),
+                idmap_zstd: Some(compressed.into()),
+            },
+        ];
+        assert_eq!(decode_clone_data_chunks(chunks)?, expected);
+
+        let bad = WireCloneDataChunk {
+            idmap_zstd: Some(vec![1, 2, 3].into()),
+            ..Default::default()
+        };
+        assert!(decode_clone_data_chunks(vec![bad]).is_err());
+        Ok(())
+    }
 }
@@ -6,13 +6,16 @@
  */
 
 use std::collections::BTreeMap;
+use std::convert::Infallible;
 
+use bytes::Bytes;
 #[cfg(any(test, feature = "for-tests"))]
 use quickcheck::Arbitrary;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use types::HgId;
 
+use crate::wire::is_default;
 use crate::wire::ToApi;
 use crate::wire::ToWire;
 use crate::wire::WireDagId;
@@ -31,6 +34,20 @@ pub struct WireCloneData {
     pub idmap: Vec<WireIdMapEntry>,
 }
 
+/// One chunk of clone data, for clients that ask for it to be streamed in
+/// chunks.  The flat segments are all sent in the first chunk, and each chunk
+/// carries a slice of the idmap, either as plain entries or, if the client
+/// asked for compression, as the zstd compressed CBOR of the entries.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct WireCloneDataChunk {
+    #[serde(rename = "1", default, skip_serializing_if = "is_default")]
+    pub flat_segments: Vec<WireFlatSegment>,
+    #[serde(rename = "2", default, skip_serializing_if = "is_default")]
+    pub idmap: Vec<WireIdMapEntry>,
+    #[serde(rename = "3", default, skip_serializing_if = "is_default")]
+    pub idmap_zstd: Option<Bytes>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct WireFlatSegment {
     #[serde(rename = "1")]
@@ -94,6 +111,25 @@ impl ToApi for WireCloneData {
     }
 }
 
+// Clients assemble the chunks into `CloneData` themselves, so a chunk is
+// its own API type.
+impl ToWire for WireCloneDataChunk {
+    type Wire = WireCloneDataChunk;
+
+    fn to_wire(self) -> Self::Wire {
+        self
+    }
+}
+
+impl ToApi for WireCloneDataChunk {
+    type Api = WireCloneDataChunk;
+    type Error = Infallible;
+
+    fn to_api(self) -> Result<Self::Api, Self::Error> {
+        Ok(self)
+    }
+}
+
 impl ToWire for FlatSegment {
     type Wire = WireFlatSegment;
 
@@ -133,10 +169,42 @@ impl Arbitrary for WireCloneData {
     }
 }
 
+#[cfg(any(test, feature = "for-tests"))]
+impl Arbitrary for WireCloneDataChunk {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let WireCloneData {
+            flat_segments,
+            idmap,
+        } = WireCloneData::arbitrary(g);
+        // Chunks carry either plain or compressed entries.  The compressed
+        // bytes don't need to be valid zstd to check the wire format.
+        if idmap.len() % 2 == 0 {
+            WireCloneDataChunk {
+                flat_segments,
+                idmap,
+                idmap_zstd: None,
+            }
+        } else {
+            let compressed = idmap
+                .iter()
+                .flat_map(|entry| match entry.hg_id.clone().to_api() {
+                    Ok(hg_id) => hg_id.into_byte_array(),
+                    Err(e) => match e {},
+                })
+                .collect::<Vec<_>>();
+            WireCloneDataChunk {
+                flat_segments,
+                idmap: Vec::new(),
+                idmap_zstd: Some(Bytes::from(compressed)),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::wire::tests::auto_wire_tests;
 
-    auto_wire_tests!(WireCloneData, WireFlatSegment);
+    auto_wire_tests!(WireCloneData, WireCloneDataChunk, WireFlatSegment);
 }
@@ -93,6 +93,7 @@ pub use crate::wire::bookmark::WireBookmarkEntry;
 pub use crate::wire::bookmark::WireBookmarkRequest;
 pub use crate::wire::bookmark::WireSetBookmarkRequest;
 pub use crate::wire::clone::WireCloneData;
+pub use crate::wire::clone::WireCloneDataChunk;
 pub use crate::wire::clone::WireIdMapEntry;
 pub use crate::wire::commit::WireCommitGraphEntry;
 pub use crate::wire::commit::WireCommitGraphRequest;